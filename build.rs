@@ -0,0 +1,42 @@
+//! Compiles the C test program of the `ffi` feature (tests/ffi/test.c) for
+//! the unit tests in src/ffi.rs. It is skipped when cross compiling or if no
+//! C compiler is found.
+
+use std::env;
+use std::path::PathBuf;
+use std::process::Command;
+
+fn main() {
+    println!("cargo:rustc-check-cfg=cfg(nexstar_c_tests)");
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=include/nexstar.h");
+    println!("cargo:rerun-if-changed=tests/ffi/test.c");
+
+    if env::var_os("CARGO_FEATURE_FFI").is_none() || env::var("TARGET") != env::var("HOST") {
+        return;
+    }
+
+    let out_dir = PathBuf::from(env::var_os("OUT_DIR").expect("OUT_DIR is set by cargo"));
+    let object = out_dir.join("nexstar_ffi_test.o");
+    let library = out_dir.join("libnexstar_ffi_test.a");
+    let cc = env::var("CC").unwrap_or_else(|_| "cc".into());
+    let ar = env::var("AR").unwrap_or_else(|_| "ar".into());
+
+    let compiled = run(Command::new(&cc)
+        .args(["-std=c99", "-Wall", "-Wextra", "-Werror", "-Iinclude", "-c"])
+        .arg("tests/ffi/test.c")
+        .arg("-o")
+        .arg(&object))
+        && run(Command::new(&ar).arg("crs").arg(&library).arg(&object));
+    if !compiled {
+        println!("cargo:warning=C test program of the ffi feature not compiled");
+        return;
+    }
+
+    println!("cargo:rustc-link-search=native={}", out_dir.display());
+    println!("cargo:rustc-cfg=nexstar_c_tests");
+}
+
+fn run(command: &mut Command) -> bool {
+    command.status().is_ok_and(|status| status.success())
+}
@@ -0,0 +1,64 @@
+//! Uses only the arcsecond API, so it also builds without floating point
+//! support in the driver:
+//!
+//! ```text
+//! cargo build --example integer --no-default-features
+//! ```
+
+use nexstar::{Dms, Hemisphere, NexStar, RaDecArcsec};
+
+use serial::{Baud9600, Bits8, FlowNone, ParityNone, Stop1};
+use serial_embedded_hal::{PortSettings, Serial};
+
+fn main() {
+    let port_settings = PortSettings {
+        baud_rate: Baud9600,
+        char_size: Bits8,
+        parity: ParityNone,
+        stop_bits: Stop1,
+        flow_control: FlowNone,
+    };
+
+    let port = Serial::new("/dev/ttyUSB0", &port_settings).expect("Failed to open serial port");
+    let (tx, rx) = port.split();
+
+    let mut nexstar = NexStar::new(rx, tx);
+
+    if let Ok((latitude, longitude)) = nexstar.location_dms() {
+        println!(
+            "Location: {}\" / {}\"",
+            latitude.to_arcsec(),
+            longitude.to_arcsec()
+        );
+    }
+
+    let latitude = Dms {
+        degrees: 47,
+        minutes: 12,
+        seconds: 34,
+        hemisphere: Hemisphere::North,
+    };
+    let longitude = Dms {
+        degrees: 8,
+        minutes: 32,
+        seconds: 7,
+        hemisphere: Hemisphere::East,
+    };
+    if nexstar.set_location_dms(latitude, longitude).is_err() {
+        println!("Communication error");
+    }
+
+    match nexstar.precise_ra_dec_arcsec() {
+        Ok(position) => println!("RA/Dec: {}\" / {}\"", position.ra, position.dec),
+        Err(_) => println!("Communication error"),
+    }
+
+    // Vega, 18h36m56s +38°47'01"
+    let vega = RaDecArcsec {
+        ra: (18 * 3600 + 36 * 60 + 56) * 15,
+        dec: 38 * 3600 + 47 * 60 + 1,
+    };
+    if nexstar.precise_goto_ra_dec_arcsec(vega).is_err() {
+        println!("Communication error");
+    }
+}
@@ -0,0 +1,76 @@
+//! Slews the mount with the arrow keys.
+//!
+//! Reads the keys from stdin, so switch the terminal to unbuffered input
+//! first, e.g. `stty -icanon -echo && cargo run --example joystick`. Every
+//! press of an arrow key deflects the axis further, space stops and `q`
+//! quits.
+
+use std::io::Read;
+
+use nexstar::joystick::JoystickMapper;
+use nexstar::prelude::*;
+
+use serial::{Baud9600, Bits8, FlowNone, ParityNone, Stop1};
+use serial_embedded_hal::{PortSettings, Serial};
+
+/// Deflection added by one key press
+const STEP: f32 = 0.25;
+
+fn main() {
+    let port_settings = PortSettings {
+        baud_rate: Baud9600,
+        char_size: Bits8,
+        parity: ParityNone,
+        stop_bits: Stop1,
+        flow_control: FlowNone,
+    };
+    let port = Serial::new("/dev/ttyUSB0", &port_settings).expect("Failed to open serial port");
+    let (tx, rx) = port.split();
+    let mut nexstar = NexStar::new(rx, tx);
+
+    // 2°/s at full deflection
+    let mapper = JoystickMapper::new(7200).with_deadzone(0.0);
+    let (mut azm, mut alt) = (0.0f32, 0.0f32);
+    let mut escape = Vec::new();
+
+    for byte in std::io::stdin().lock().bytes() {
+        let byte = byte.expect("Failed to read stdin");
+        // arrow keys are sent as ESC [ A..D
+        if byte == 0x1b || !escape.is_empty() {
+            escape.push(byte);
+            if escape.len() < 3 {
+                continue;
+            }
+        }
+
+        match (escape.as_slice(), byte) {
+            ([0x1b, b'[', b'A'], _) => alt = (alt + STEP).min(1.0),
+            ([0x1b, b'[', b'B'], _) => alt = (alt - STEP).max(-1.0),
+            ([0x1b, b'[', b'C'], _) => azm = (azm + STEP).min(1.0),
+            ([0x1b, b'[', b'D'], _) => azm = (azm - STEP).max(-1.0),
+            (_, b' ') => {
+                azm = 0.0;
+                alt = 0.0;
+            }
+            (_, b'q') => break,
+            _ => {}
+        }
+        escape.clear();
+
+        match nexstar.slew_joystick(azm, alt, &mapper) {
+            Ok(true) => println!(
+                "AZM {:+.2} ({} arcsec/s), ALT {:+.2} ({} arcsec/s)",
+                azm,
+                mapper.map(azm),
+                alt,
+                mapper.map(alt)
+            ),
+            Ok(false) => {}
+            Err(e) => println!("Slew failed: {}", e),
+        }
+    }
+
+    if nexstar.stop_tracking_rates().is_err() {
+        println!("Failed to stop the motors");
+    }
+}
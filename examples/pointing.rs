@@ -0,0 +1,71 @@
+use std::thread;
+use std::time::Duration;
+
+use embedded_hal::blocking::delay::DelayMs;
+use nexstar::pointing::{Fit, PointingModel};
+use nexstar::{NexStar, RaDec};
+
+use serial::{Baud9600, Bits8, FlowNone, ParityNone, Stop1};
+use serial_embedded_hal::{PortSettings, Serial};
+
+struct Delay;
+
+impl DelayMs<u32> for Delay {
+    fn delay_ms(&mut self, ms: u32) {
+        thread::sleep(Duration::from_millis(ms as u64));
+    }
+}
+
+/// Stands in for a plate solver, pretends the mount points a bit off.
+fn solve(position: RaDec) -> RaDec {
+    RaDec {
+        ra: position.ra + 0.2,
+        dec: position.dec - 0.1,
+    }
+}
+
+fn main() {
+    let port_settings = PortSettings {
+        baud_rate: Baud9600,
+        char_size: Bits8,
+        parity: ParityNone,
+        stop_bits: Stop1,
+        flow_control: FlowNone,
+    };
+
+    let port = Serial::new("/dev/ttyUSB0", &port_settings).expect("Failed to open serial port");
+    let (tx, rx) = port.split();
+
+    let mut nexstar = NexStar::new(rx, tx);
+    let mut delay = Delay;
+    let mut model = PointingModel::<8>::new(Fit::Offset);
+
+    // sync on the solved field the mount currently points at
+    let position = nexstar.precise_ra_dec().expect("Failed to read position");
+    nexstar
+        .precise_sync_ra_dec(solve(position))
+        .expect("Failed to sync");
+
+    // GOTO the target and remember where the mount actually ended up
+    let target = RaDec {
+        ra: 83.82,
+        dec: -5.39,
+    };
+    nexstar
+        .precise_goto_ra_dec(target)
+        .expect("Failed to start GOTO");
+    nexstar
+        .wait_for_goto(&mut delay, 500, 300_000)
+        .expect("GOTO failed");
+    model.add(target, solve(target));
+
+    // re-slew with the correction applied
+    let corrected = model.apply(target);
+    println!(
+        "Target {} / {} -> commanding {} / {}",
+        target.ra, target.dec, corrected.ra, corrected.dec
+    );
+    nexstar
+        .precise_goto_ra_dec(corrected)
+        .expect("Failed to start GOTO");
+}
@@ -0,0 +1,45 @@
+use std::thread;
+use std::time::Duration;
+
+use embedded_hal::blocking::delay::DelayMs;
+use nexstar::NexStar;
+
+use serial::{Baud9600, Bits8, FlowNone, ParityNone, Stop1};
+use serial_embedded_hal::{PortSettings, Serial};
+
+struct Delay;
+
+impl DelayMs<u32> for Delay {
+    fn delay_ms(&mut self, ms: u32) {
+        thread::sleep(Duration::from_millis(ms as u64));
+    }
+}
+
+fn main() {
+    let port_settings = PortSettings {
+        baud_rate: Baud9600,
+        char_size: Bits8,
+        parity: ParityNone,
+        stop_bits: Stop1,
+        flow_control: FlowNone,
+    };
+
+    let port = Serial::new("/dev/ttyUSB0", &port_settings).expect("Failed to open serial port");
+    let (tx, rx) = port.split();
+
+    let mut nexstar = NexStar::new(rx, tx);
+    let mut delay = Delay;
+
+    let mut positions = nexstar.positions(&mut delay, 1000);
+    while let Some(position) = positions.next() {
+        match position {
+            Ok(position) => println!("RA/Dec: {} / {}", position.ra, position.dec),
+            Err(_) => println!("Communication error"),
+        }
+
+        // the driver is still usable between samples
+        if let Ok(true) = positions.nexstar().is_goto_in_progress() {
+            println!("GOTO in progress");
+        }
+    }
+}
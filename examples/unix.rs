@@ -1,10 +1,8 @@
-use nexstar::{Device, NexStar};
+use nexstar::prelude::*;
 
 use serial::{Baud9600, Bits8, FlowNone, ParityNone, Stop1};
 use serial_embedded_hal::{PortSettings, Serial};
 
-//use nexstar::prelude::*;
-
 fn main() {
     println!("Opening serial port...");
 
@@ -59,12 +57,12 @@ fn main() {
 
 fn print_version<T, U>(nexstar: &mut NexStar<T, U>, name: &str, device: Device)
 where
-    T: embedded_hal::serial::Read<u8>,
-    U: embedded_hal::serial::Write<u8>,
+    T: SerialRead<u8>,
+    U: SerialWrite<u8>,
 {
     match nexstar.device_version(device) {
         Ok(version) => println!("{} Version: {}.{}", name, version.major, version.minor),
-        Err(nexstar::Error::UnexpectedResponse) => println!("{} not present.", name),
+        Err(Error::UnexpectedResponse(_)) => println!("{} not present.", name),
         Err(_) => println!("Communication error"),
     }
 }
@@ -0,0 +1,108 @@
+//! Drives the async driver the way a browser does, the received chunks are
+//! pushed by an event loop instead of being pulled by blocking reads.
+//!
+//! In a page built with wasm-bindgen the port is backed by the streams of the
+//! WebSerial API and the session is spawned with
+//! `wasm_bindgen_futures::spawn_local`. Here a simulated Hand Controller (HC)
+//! answers each command in two chunks and a loop plays the event loop:
+//!
+//! ```text
+//! cargo run --example webserial --features async
+//! cargo build --example webserial --features async --target wasm32-unknown-unknown
+//! ```
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::future::{poll_fn, Future};
+use std::pin::pin;
+use std::rc::Rc;
+use std::task::{Context, Poll, Waker};
+
+use nexstar::asynch::{AsyncNexStar, AsyncTransport};
+
+/// Both directions of the serial port, shared with the event loop
+#[derive(Default)]
+struct Port {
+    /// Bytes written by the driver which the HC hasn't seen yet
+    sent: Vec<u8>,
+    /// Chunks received from the HC
+    received: VecDeque<Vec<u8>>,
+    /// Wakes the session when a chunk arrives
+    waker: Option<Waker>,
+}
+
+struct WebSerial(Rc<RefCell<Port>>);
+
+impl AsyncTransport for WebSerial {
+    // JsValue in the browser
+    type Error = ();
+
+    async fn write_all(&mut self, bytes: &[u8]) -> Result<(), ()> {
+        self.0.borrow_mut().sent.extend_from_slice(bytes);
+        Ok(())
+    }
+
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, ()> {
+        poll_fn(|cx| {
+            let mut port = self.0.borrow_mut();
+            match port.received.pop_front() {
+                Some(mut chunk) => {
+                    let len = chunk.len().min(buf.len());
+                    buf[..len].copy_from_slice(&chunk[..len]);
+                    // keep the rest of a chunk larger than the buffer
+                    if len < chunk.len() {
+                        port.received.push_front(chunk.split_off(len));
+                    }
+                    Poll::Ready(Ok(len))
+                }
+                None => {
+                    port.waker = Some(cx.waker().clone());
+                    Poll::Pending
+                }
+            }
+        })
+        .await
+    }
+}
+
+/// Gets the answer of the simulated HC.
+fn answer(command: &[u8]) -> &'static [u8] {
+    match command {
+        b"V" => &[4, 21, b'#'],
+        b"e" => b"80000000,C0000000#",
+        _ => b"#",
+    }
+}
+
+fn main() {
+    let port = Rc::new(RefCell::new(Port::default()));
+    let mut nexstar = AsyncNexStar::new(WebSerial(port.clone()));
+    let mut session = pin!(async move {
+        let version = nexstar.version().await?;
+        let position = nexstar.precise_ra_dec().await?;
+        Ok::<_, nexstar::Error<(), ()>>((version, position))
+    });
+
+    let mut cx = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(result) = session.as_mut().poll(&mut cx) {
+            match result {
+                Ok((version, position)) => println!(
+                    "HC {}.{}, RA {:.2} Dec {:.2}",
+                    version.major, version.minor, position.ra, position.dec
+                ),
+                Err(e) => println!("error: {}", e),
+            }
+            break;
+        }
+
+        let mut port = port.borrow_mut();
+        let command = std::mem::take(&mut port.sent);
+        let (first, second) = answer(&command).split_at(1);
+        port.received.push_back(first.to_vec());
+        port.received.push_back(second.to_vec());
+        if let Some(waker) = port.waker.take() {
+            waker.wake();
+        }
+    }
+}
@@ -0,0 +1,12 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nexstar::aux::{Packet, MAX_FRAME};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(packet) = Packet::decode(data) {
+        let mut frame = [0u8; MAX_FRAME];
+        let len = packet.encode(&mut frame);
+        assert_eq!(&frame[..len], data);
+    }
+});
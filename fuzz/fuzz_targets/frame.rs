@@ -0,0 +1,28 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nexstar::fuzzing::{frame, FrameError, MAX_RESPONSE};
+
+// the first byte selects the expected data length
+fuzz_target!(|data: &[u8]| {
+    let (len, input) = match data.split_first() {
+        Some((len, input)) => (*len as usize % (MAX_RESPONSE + 1), input),
+        None => return,
+    };
+
+    match frame(input, len) {
+        Ok((response, consumed)) => {
+            assert_eq!(consumed, len + 1);
+            assert!(consumed <= input.len());
+            assert_eq!(response, &input[..len]);
+            assert_eq!(input[len], b'#');
+        }
+        Err(FrameError::Rejected { consumed }) => {
+            assert_eq!(consumed, len + 2);
+            assert!(consumed <= input.len());
+            assert_ne!(input[len], b'#');
+        }
+        // the driver reads one more byte, it must be decided soon
+        Err(FrameError::Incomplete) => assert!(input.len() < len + 2),
+    }
+});
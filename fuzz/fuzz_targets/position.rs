@@ -0,0 +1,37 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nexstar::fuzzing::{encode_position, position};
+
+/// Distance of two fractions of a revolution in counts.
+fn counts(a: &[u8], b: &[u8], precise: bool) -> u32 {
+    let value = |hex: &[u8]| u32::from_str_radix(core::str::from_utf8(hex).unwrap(), 16).unwrap();
+    let (a, b, full) = if precise {
+        // the lowest byte is ignored
+        (value(a) >> 8, value(b) >> 8, 1 << 24)
+    } else {
+        (value(a), value(b), 1 << 16)
+    };
+
+    let diff = a.wrapping_sub(b) % full;
+    diff.min(full - diff)
+}
+
+fuzz_target!(|data: &[u8]| {
+    let (first, second) = match position(data) {
+        Some(position) => position,
+        None => return,
+    };
+    assert!((0.0..360.0).contains(&first));
+    assert!((-180.0..=180.0).contains(&second));
+
+    // sending the position back addresses the same counts, f32 can't hold
+    // every 24 bit position above 256° though
+    let precise = data.len() == 17;
+    let tolerance = if precise { 1 } else { 0 };
+    let (encoded, len) = encode_position(first, second, precise);
+    let digits = len / 2;
+    assert_eq!(len, data.len());
+    assert!(counts(&encoded[..digits], &data[..digits], precise) <= tolerance);
+    assert!(counts(&encoded[digits + 1..len], &data[digits + 1..], precise) <= tolerance);
+});
@@ -0,0 +1,64 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nexstar::protocol::{parse_response, Axes, ParseError, Parser, Progress, Request, MAX_RESPONSE};
+use nexstar::{Command, Device};
+
+const REQUESTS: [Request; 11] = [
+    Request::GetPosition {
+        axes: Axes::RaDec,
+        precise: false,
+    },
+    Request::GetPosition {
+        axes: Axes::AzmAlt,
+        precise: true,
+    },
+    Request::GetWidePosition { axes: Axes::RaDec },
+    Request::CancelGoto,
+    Request::GetTrackingMode,
+    Request::GetLocation,
+    Request::GetDateTime,
+    Request::GetVersion,
+    Request::GetModel,
+    Request::IsGotoInProgress,
+    Request::Echo(0x42),
+];
+
+// the first byte selects the command, pass-through commands expect up to 17
+// bytes
+fuzz_target!(|data: &[u8]| {
+    let (selector, input) = match data.split_first() {
+        Some((selector, input)) => (*selector as usize, input),
+        None => return,
+    };
+    let request = match REQUESTS.get(selector) {
+        Some(request) => *request,
+        None => Request::pass_through(
+            Device::AzmRaMotor,
+            Command::GetDeviceVersion,
+            [],
+            (selector % 18) as u8,
+        ),
+    };
+
+    // the incremental parser agrees with the one-shot parser on the bytes it
+    // consumed and decides within two bytes after the data
+    let mut buffer = [0u8; MAX_RESPONSE + 2];
+    let mut parser = Parser::new(&request);
+    let mut progress = Progress::NeedMore;
+    for byte in input {
+        progress = parser.push(&mut buffer, *byte);
+        if progress != Progress::NeedMore {
+            break;
+        }
+    }
+
+    let consumed = &input[..parser.received()];
+    match progress {
+        Progress::Done => assert_eq!(parser.response(&buffer), consumed),
+        Progress::NeedMore => {
+            assert_eq!(parse_response(&request, input), Err(ParseError::Incomplete));
+            assert!(input.len() < request.response_len() + 2);
+        }
+    }
+});
@@ -0,0 +1,16 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nexstar::fuzzing::{datetime, encode_datetime, encode_location, location};
+
+fuzz_target!(|data: &[u8]| {
+    if let Some(location) = location(data) {
+        assert!(location.latitude.abs() <= 90.0);
+        assert!(location.longitude.abs() <= 180.0);
+        assert_eq!(&encode_location(location)[..], data);
+    }
+
+    if let Some(datetime) = datetime(data) {
+        assert_eq!(&encode_datetime(datetime)[..], data);
+    }
+});
@@ -0,0 +1,226 @@
+#ifndef NEXSTAR_H
+#define NEXSTAR_H
+
+/* Generated by cbindgen from src/ffi.rs, don't edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Size of the storage in a [`NexstarHandle`] in 64 bit words
+ */
+#define NEXSTAR_HANDLE_WORDS 40
+
+/**
+ * Result of the C functions, negative values are errors
+ */
+enum NexstarStatus
+#if __STDC_VERSION__ >= 202311L
+  : int32_t
+#endif // __STDC_VERSION__ >= 202311L
+ {
+  NEXSTAR_STATUS_OK = 0,
+  NEXSTAR_STATUS_UNEXPECTED_RESPONSE = -1,
+  NEXSTAR_STATUS_UNSUPPORTED_FIRMWARE = -2,
+  NEXSTAR_STATUS_TIMEOUT = -3,
+  NEXSTAR_STATUS_ABORTED = -4,
+  NEXSTAR_STATUS_NOT_ON_TARGET = -5,
+  NEXSTAR_STATUS_TRUNCATED = -6,
+  NEXSTAR_STATUS_INVALID_FRAME = -7,
+  NEXSTAR_STATUS_ECHO_MISMATCH = -8,
+  NEXSTAR_STATUS_LINK_DIRTY = -9,
+  NEXSTAR_STATUS_BELOW_ALTITUDE_LIMIT = -10,
+  NEXSTAR_STATUS_INVALID_LOCATION = -11,
+  NEXSTAR_STATUS_FRAME_OVERFLOW = -12,
+  NEXSTAR_STATUS_NO_GPS_FIX = -13,
+  NEXSTAR_STATUS_INVALID_SLEW_RATE = -14,
+  NEXSTAR_STATUS_UNSUPPORTED_COMMAND = -15,
+  NEXSTAR_STATUS_HOME_NOT_FOUND = -16,
+  NEXSTAR_STATUS_WATCHDOG_FIRED = -17,
+  /**
+   * The read callback returned an error.
+   */
+  NEXSTAR_STATUS_READ = -18,
+  /**
+   * The write callback returned an error.
+   */
+  NEXSTAR_STATUS_WRITE = -19,
+  /**
+   * A pointer argument is null.
+   */
+  NEXSTAR_STATUS_NULL_POINTER = -20,
+  /**
+   * The handle wasn't passed to [`nexstar_init`].
+   */
+  NEXSTAR_STATUS_NOT_INITIALIZED = -21,
+  NEXSTAR_STATUS_UNSUPPORTED_BY_MODEL = -22,
+  NEXSTAR_STATUS_BUFFER_TOO_SMALL = -23,
+  NEXSTAR_STATUS_TOO_FEW_SAMPLES = -24,
+  NEXSTAR_STATUS_INVALID_POSITION = -25,
+  NEXSTAR_STATUS_BUSY = -26,
+  NEXSTAR_STATUS_ALIGNING = -27,
+};
+#if __STDC_VERSION__ >= 202311L
+typedef enum NexstarStatus NexstarStatus;
+#else
+typedef int32_t NexstarStatus;
+#endif // __STDC_VERSION__ >= 202311L
+
+/**
+ * Storage of a driver, owned by the caller
+ *
+ * The fields are private, the handle is only used through the functions.
+ */
+typedef struct {
+  uint32_t magic;
+  uint64_t storage[NEXSTAR_HANDLE_WORDS];
+} NexstarHandle;
+
+/**
+ * Reads a byte into `byte`, returns 1 if a byte was read, 0 if none is
+ * available yet and a negative value on errors.
+ */
+typedef int32_t (*NexstarReadFn)(void *context, uint8_t *byte);
+
+/**
+ * Writes `byte`, returns 0 if it was written, 1 if the UART is busy and a
+ * negative value on errors.
+ */
+typedef int32_t (*NexstarWriteFn)(void *context, uint8_t byte);
+
+/**
+ * Location in degrees, north and east positive
+ */
+typedef struct {
+  float latitude;
+  float longitude;
+} NexstarLocation;
+
+/**
+ * Date and time as stored by the HC, see [`DateTime`]
+ */
+typedef struct {
+  uint8_t hour;
+  uint8_t minutes;
+  uint8_t seconds;
+  /**
+   * Offset of the standard time from UTC in hours
+   */
+  int8_t zone;
+  bool daylight_saving;
+  /**
+   * Year of the century
+   */
+  uint8_t year;
+  uint8_t month;
+  uint8_t day;
+} NexstarDateTime;
+
+/**
+ * Equatorial coordinates in degrees
+ */
+typedef struct {
+  float ra;
+  float dec;
+} NexstarRaDec;
+
+/**
+ * Initializes the driver in `handle` over the byte callbacks, `context` is
+ * passed to both of them.
+ *
+ * # Safety
+ *
+ * `handle` must be null or point to writable memory of a handle, which must
+ * not be moved while the driver is used.
+ */
+NexstarStatus nexstar_init(NexstarHandle *handle,
+                           NexstarReadFn read,
+                           NexstarWriteFn write,
+                           void *context);
+
+/**
+ * Gets the location set in the HC.
+ *
+ * # Safety
+ *
+ * `handle` and `location` must be null or valid pointers.
+ */
+NexstarStatus nexstar_get_location(NexstarHandle *handle, NexstarLocation *location);
+
+/**
+ * Sets the location of the HC, fails with `NEXSTAR_STATUS_INVALID_LOCATION`
+ * if it is out of range.
+ *
+ * # Safety
+ *
+ * `handle` must be null or a valid pointer.
+ */
+NexstarStatus nexstar_set_location(NexstarHandle *handle, NexstarLocation location);
+
+/**
+ * Gets the date and time set in the HC.
+ *
+ * # Safety
+ *
+ * `handle` and `datetime` must be null or valid pointers.
+ */
+NexstarStatus nexstar_get_datetime(NexstarHandle *handle, NexstarDateTime *datetime);
+
+/**
+ * Sets the date and time of the HC.
+ *
+ * # Safety
+ *
+ * `handle` must be null or a valid pointer.
+ */
+NexstarStatus nexstar_set_datetime(NexstarHandle *handle, NexstarDateTime datetime);
+
+/**
+ * Gets the precise RA/Dec position.
+ *
+ * # Safety
+ *
+ * `handle` and `position` must be null or valid pointers.
+ */
+NexstarStatus nexstar_get_radec(NexstarHandle *handle, NexstarRaDec *position);
+
+/**
+ * Starts a precise GOTO to the RA/Dec target.
+ *
+ * # Safety
+ *
+ * `handle` must be null or a valid pointer.
+ */
+NexstarStatus nexstar_goto_radec(NexstarHandle *handle, NexstarRaDec target);
+
+/**
+ * Gets whether a GOTO is in progress.
+ *
+ * # Safety
+ *
+ * `handle` and `in_progress` must be null or valid pointers.
+ */
+NexstarStatus nexstar_is_goto_in_progress(NexstarHandle *handle, bool *in_progress);
+
+/**
+ * Cancels a GOTO in progress.
+ *
+ * # Safety
+ *
+ * `handle` must be null or a valid pointer.
+ */
+NexstarStatus nexstar_cancel_goto(NexstarHandle *handle);
+
+/**
+ * Discards pending bytes and marks the link clean after an error.
+ *
+ * # Safety
+ *
+ * `handle` must be null or a valid pointer.
+ */
+NexstarStatus nexstar_resync(NexstarHandle *handle);
+
+#endif  /* NEXSTAR_H */
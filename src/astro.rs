@@ -0,0 +1,661 @@
+//! Astronomical calculations
+//!
+//! The calculations follow Meeus, "Astronomical Algorithms", and are accurate
+//! to a few arcminutes, which is plenty for horizon checks. The precession
+//! between J2000 and the current epoch is accurate to about an arcsecond.
+
+use crate::{AzmAlt, DateTime, Location, RaDec, UtcDateTime};
+use libm::{atan2, cos, floor, sin, sqrt, tan};
+
+const J2000: f64 = 2_451_545.0;
+/// Altitude in degrees below which the refraction formulas are evaluated at
+/// this altitude
+const MIN_REFRACTION_ALT: f64 = -1.0;
+
+/// Gets the altitude of the target in degrees as seen from the location at the
+/// date and time of the Hand Controller (HC).
+pub fn altitude(target: RaDec, location: Location, datetime: DateTime) -> f32 {
+    target.to_azm_alt(location, datetime.to_utc()).alt
+}
+
+/// Gets the altitude of the target in degrees like [`altitude`], raised by
+/// the refraction at the temperature in °C and the pressure in hPa as the
+/// mount sees it.
+pub fn apparent_altitude(
+    target: RaDec,
+    location: Location,
+    datetime: DateTime,
+    temperature_c: f32,
+    pressure_hpa: f32,
+) -> f32 {
+    let altitude = altitude(target, location, datetime);
+    altitude + true_refraction_arcmin(altitude, temperature_c, pressure_hpa) / 60.0
+}
+
+/// Gets the refraction in arcminutes at the apparent (observed) altitude in
+/// degrees, the temperature in °C and the pressure in hPa.
+///
+/// Uses the formula of Bennett, scaled to the temperature and pressure, which
+/// agrees with the standard refraction tables within 0.1' above the horizon.
+/// Below -1° the refraction at -1° is returned instead of the meaningless
+/// values of the formula.
+pub fn refraction_arcmin(apparent_alt: f32, temperature_c: f32, pressure_hpa: f32) -> f32 {
+    let alt = (apparent_alt as f64).clamp(MIN_REFRACTION_ALT, 90.0);
+    let refraction = 1.0 / tan((alt + 7.31 / (alt + 4.4)).to_radians());
+    scale_refraction(refraction, temperature_c, pressure_hpa)
+}
+
+/// Gets the refraction in arcminutes at the true (geometric) altitude in
+/// degrees with the formula of Saemundsson, the counterpart of
+/// [`refraction_arcmin`].
+fn true_refraction_arcmin(true_alt: f32, temperature_c: f32, pressure_hpa: f32) -> f32 {
+    let alt = (true_alt as f64).clamp(MIN_REFRACTION_ALT, 90.0);
+    let refraction = 1.02 / tan((alt + 10.3 / (alt + 5.11)).to_radians());
+    scale_refraction(refraction, temperature_c, pressure_hpa)
+}
+
+/// Scales the refraction at 10 °C and 1010 hPa, the formulas go slightly
+/// negative towards the zenith.
+fn scale_refraction(refraction: f64, temperature_c: f32, pressure_hpa: f32) -> f32 {
+    let scale = pressure_hpa as f64 / 1010.0 * 283.0 / (273.0 + temperature_c as f64);
+    (refraction * scale).max(0.0) as f32
+}
+
+/// Raises the true altitude of the position to the apparent one at the
+/// temperature in °C and the pressure in hPa, e.g. before a GOTO to a
+/// calculated Azm/Alt position.
+pub fn apply_refraction(position: AzmAlt, temperature_c: f32, pressure_hpa: f32) -> AzmAlt {
+    let refraction = true_refraction_arcmin(position.alt, temperature_c, pressure_hpa);
+    AzmAlt {
+        azm: position.azm,
+        alt: position.alt + refraction / 60.0,
+    }
+}
+
+/// Lowers the apparent altitude of the position, e.g. read from the mount, to
+/// the true one, the inverse of [`apply_refraction`].
+pub fn remove_refraction(position: AzmAlt, temperature_c: f32, pressure_hpa: f32) -> AzmAlt {
+    let refraction = refraction_arcmin(position.alt, temperature_c, pressure_hpa);
+    AzmAlt {
+        azm: position.azm,
+        alt: position.alt - refraction / 60.0,
+    }
+}
+
+impl RaDec {
+    /// Precesses the J2000 coordinates of a catalog to the mean equator and
+    /// equinox at the date and time of the HC (JNow), which the HC expects.
+    ///
+    /// Implements the IAU 1976 precession (Lieske), which agrees with the
+    /// current IAU 2006 model within an arcsecond from 1900 to 2100 and drifts
+    /// away slowly outside of it. Nutation (up to 17"), aberration (up to 20")
+    /// and proper motion aren't applied, so apparent places differ by up to
+    /// about 40", well below the pointing accuracy of the mounts. Skipping
+    /// the precession costs about 20' in 2025.
+    pub fn j2000_to_jnow(&self, datetime: DateTime) -> RaDec {
+        precess(*self, julian_centuries(datetime), false)
+    }
+
+    /// Precesses JNow coordinates at the date and time of the HC back to
+    /// J2000, the inverse of [`RaDec::j2000_to_jnow`].
+    pub fn jnow_to_j2000(&self, datetime: DateTime) -> RaDec {
+        precess(*self, julian_centuries(datetime), true)
+    }
+
+    /// Gets the horizontal coordinates as seen from the location at the UTC
+    /// date and time.
+    ///
+    /// The azimuth counts from north (0°) towards east (90°), the altitude
+    /// is geometric, see [`apply_refraction`] for the apparent one. The
+    /// coordinates are taken as JNow, see [`RaDec::j2000_to_jnow`]. The
+    /// transform is exact for the sidereal time of [`lst_degrees`] and stays
+    /// accurate at the zenith and the poles, where the azimuth is arbitrary.
+    pub fn to_azm_alt(&self, location: Location, datetime: UtcDateTime) -> AzmAlt {
+        let lst = local_sidereal_time(julian_date_utc(datetime), location.longitude as f64);
+        let (azm, alt) = rotate(
+            lst - self.ra as f64,
+            self.dec as f64,
+            location.latitude as f64,
+        );
+        // the hour angle turns west, the azimuth east
+        AzmAlt {
+            azm: normalize(-azm) as f32,
+            alt: alt as f32,
+        }
+    }
+}
+
+impl AzmAlt {
+    /// Gets the equatorial coordinates (JNow) of the geometric horizontal
+    /// ones as seen from the location at the UTC date and time, the inverse
+    /// of [`RaDec::to_azm_alt`].
+    pub fn to_ra_dec(&self, location: Location, datetime: UtcDateTime) -> RaDec {
+        let lst = local_sidereal_time(julian_date_utc(datetime), location.longitude as f64);
+        let (hour_angle, dec) = rotate(-self.azm as f64, self.alt as f64, location.latitude as f64);
+        RaDec {
+            ra: normalize(lst - hour_angle) as f32,
+            dec: dec as f32,
+        }
+    }
+}
+
+/// Rotates the spherical coordinates about the east-west axis between the
+/// hour angle (west) and declination and the horizontal coordinates with the
+/// azimuth counted west from north. The rotation is its own inverse.
+fn rotate(longitude: f64, latitude: f64, observer_latitude: f64) -> (f64, f64) {
+    let (longitude, latitude) = (longitude.to_radians(), latitude.to_radians());
+    let observer_latitude = observer_latitude.to_radians();
+
+    // towards the pole or the zenith, the meridian and west
+    let up = sin(observer_latitude) * sin(latitude)
+        + cos(observer_latitude) * cos(latitude) * cos(longitude);
+    let meridian = cos(observer_latitude) * sin(latitude)
+        - sin(observer_latitude) * cos(latitude) * cos(longitude);
+    let west = cos(latitude) * sin(longitude);
+
+    (
+        atan2(west, meridian).to_degrees(),
+        atan2(up, sqrt(meridian * meridian + west * west)).to_degrees(),
+    )
+}
+
+/// Wraps the angle in degrees into 0..360.
+fn normalize(degrees: f64) -> f64 {
+    let degrees = degrees % 360.0;
+    if degrees < 0.0 {
+        degrees + 360.0
+    } else {
+        degrees
+    }
+}
+
+/// Gets the Julian centuries from J2000 to the date and time of the HC.
+fn julian_centuries(datetime: DateTime) -> f64 {
+    (julian_date(datetime) - J2000) / 36_525.0
+}
+
+/// Rotates the coordinates from the J2000 equator and equinox to the ones `t`
+/// Julian centuries later (Meeus 21.2 to 21.4), or back with `inverse`.
+fn precess(position: RaDec, t: f64, inverse: bool) -> RaDec {
+    let zeta = (2306.2181 + (0.30188 + 0.017998 * t) * t) * t;
+    let z = (2306.2181 + (1.09468 + 0.018203 * t) * t) * t;
+    let theta = (2004.3109 - (0.42665 + 0.041833 * t) * t) * t;
+    // the inverse rotation swaps and negates the angles
+    let (zeta, z, theta) = if inverse {
+        (-z, -zeta, -theta)
+    } else {
+        (zeta, z, theta)
+    };
+    let zeta = (zeta / 3600.0).to_radians();
+    let z = (z / 3600.0).to_radians();
+    let theta = (theta / 3600.0).to_radians();
+
+    let ra = (position.ra as f64).to_radians() + zeta;
+    let dec = (position.dec as f64).to_radians();
+    let a = cos(dec) * sin(ra);
+    let b = cos(theta) * cos(dec) * cos(ra) - sin(theta) * sin(dec);
+    let c = sin(theta) * cos(dec) * cos(ra) + cos(theta) * sin(dec);
+
+    // atan2 keeps the declination accurate close to the poles
+    let ra = (atan2(a, b) + z).to_degrees() % 360.0;
+    RaDec {
+        ra: if ra < 0.0 { ra + 360.0 } else { ra } as f32,
+        dec: atan2(c, sqrt(a * a + b * b)).to_degrees() as f32,
+    }
+}
+
+/// Gets the local sidereal time in degrees (0..360) at the UTC date and time
+/// and the east positive longitude in degrees.
+///
+/// The Greenwich mean sidereal time follows the IAU 1982 polynomial (Meeus
+/// 12.4), which stays within 0.01 s of time of the IAU 2006 model from 1900
+/// to 2100. UTC stands in for UT1, which adds their difference of up to
+/// 0.9 s, so the result is accurate to about a second of time (0.004°).
+pub fn lst_degrees(datetime: UtcDateTime, longitude: f32) -> f32 {
+    local_sidereal_time(julian_date_utc(datetime), longitude as f64) as f32
+}
+
+/// Gets the Julian date of the local date and time of the Hand Controller (HC).
+pub(crate) fn julian_date(datetime: DateTime) -> f64 {
+    // the HC keeps the local time, the zone and DST offsets are ahead of UTC
+    julian_date_utc(datetime.to_utc())
+}
+
+/// Gets the Julian date of the UTC date and time.
+fn julian_date_utc(datetime: UtcDateTime) -> f64 {
+    let mut year = datetime.year as i32;
+    let mut month = datetime.month as i32;
+    if month <= 2 {
+        year -= 1;
+        month += 12;
+    }
+
+    let century = year / 100;
+    let gregorian = 2 - century + century / 4;
+    let day = floor(365.25 * (year + 4716) as f64) + floor(30.6001 * (month + 1) as f64) - 1524.5
+        + (datetime.day as i32 + gregorian) as f64;
+
+    let hours =
+        datetime.hour as f64 + datetime.minutes as f64 / 60.0 + datetime.seconds as f64 / 3600.0;
+
+    day + hours / 24.0
+}
+
+/// Gets the local sidereal time in degrees (0..360) at the east positive
+/// longitude.
+pub(crate) fn local_sidereal_time(jd: f64, longitude: f64) -> f64 {
+    let t = (jd - J2000) / 36_525.0;
+    let gmst = 280.460_618_37 + 360.985_647_366_29 * (jd - J2000) + 0.000_387_933 * t * t
+        - t * t * t / 38_710_000.0;
+
+    let lst = (gmst + longitude) % 360.0;
+    if lst < 0.0 {
+        lst + 360.0
+    } else {
+        lst
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOON: DateTime = DateTime {
+        hour: 12,
+        minutes: 0,
+        seconds: 0,
+        month: 1,
+        day: 1,
+        year: 0,
+        zone: 0,
+        daylight_saving: false,
+    };
+
+    #[test]
+    fn julian_date_j2000() {
+        assert_eq!(julian_date(NOON), J2000);
+
+        let local = DateTime {
+            hour: 14,
+            zone: 1,
+            daylight_saving: true,
+            ..NOON
+        };
+        assert_eq!(julian_date(local), J2000);
+    }
+
+    #[test]
+    fn sidereal_time_j2000() {
+        assert!((local_sidereal_time(J2000, 0.0) - 280.460_618_37).abs() < 1e-6);
+        assert!((local_sidereal_time(J2000, -90.0) - 190.460_618_37).abs() < 1e-6);
+    }
+
+    /// Distance in arcseconds
+    fn distance(first: RaDec, second: (f64, f64)) -> f64 {
+        let ra = (first.ra as f64 - second.0 + 540.0) % 360.0 - 180.0;
+        let ra = ra * cos(second.1.to_radians());
+        sqrt(ra * ra + (first.dec as f64 - second.1) * (first.dec as f64 - second.1)) * 3600.0
+    }
+
+    fn hc_time(year: u8, month: u8, day: u8, hour: u8, minutes: u8, seconds: u8) -> DateTime {
+        DateTime {
+            hour,
+            minutes,
+            seconds,
+            month,
+            day,
+            year,
+            ..NOON
+        }
+    }
+
+    #[test]
+    fn precession_reference() {
+        // J2000 positions precessed with the IAU 2006 (P03) angles of
+        // Capitaine et al., independently of the IAU 1976 model used here
+        let stars = [
+            // Vega, Polaris, Sirius, Betelgeuse, Acrux
+            (279.234733, 38.783689),
+            (37.954542, 89.264111),
+            (101.287154, -16.716117),
+            (88.792938, 7.407064),
+            (186.649583, -63.099083),
+        ];
+        let epochs = [
+            (
+                hc_time(25, 1, 1, 0, 0, 0),
+                [
+                    (279.444658, 38.806277),
+                    (46.083581, 89.367748),
+                    (101.566471, -16.743691),
+                    (89.131353, 7.409583),
+                    (187.002611, -63.237273),
+                ],
+            ),
+            (
+                hc_time(50, 7, 1, 0, 0, 0),
+                [
+                    (279.658771, 38.829828),
+                    (57.274066, 89.456138),
+                    (101.851332, -16.772493),
+                    (89.476513, 7.411305),
+                    (187.364614, -63.378070),
+                ],
+            ),
+            (
+                hc_time(100, 1, 1, 0, 0, 0),
+                [
+                    (280.074621, 38.877040),
+                    (88.322992, 89.540567),
+                    (102.404502, -16.830378),
+                    (90.146852, 7.412207),
+                    (188.073388, -63.651077),
+                ],
+            ),
+        ];
+
+        for (datetime, expected) in epochs.iter() {
+            for (star, jnow) in stars.iter().zip(expected) {
+                let j2000 = RaDec {
+                    ra: star.0 as f32,
+                    dec: star.1 as f32,
+                };
+                let precessed = j2000.j2000_to_jnow(*datetime);
+                assert!(distance(precessed, *jnow) < 2.0, "{:?}", precessed);
+
+                let back = precessed.jnow_to_j2000(*datetime);
+                assert!(distance(back, *star) < 0.5, "{:?}", back);
+            }
+        }
+    }
+
+    #[test]
+    fn precession_meeus() {
+        // Example 21.b, theta Persei with the proper motion applied to the
+        // J2000 position, 2028 November 13.19
+        let j2000 = RaDec {
+            ra: (2.0 + 44.0 / 60.0 + 12.975 / 3600.0) * 15.0,
+            dec: 49.0 + 13.0 / 60.0 + 39.896 / 3600.0,
+        };
+        let jnow = j2000.j2000_to_jnow(hc_time(28, 11, 13, 4, 33, 36));
+        let expected = (
+            (2.0 + 46.0 / 60.0 + 11.331 / 3600.0) * 15.0,
+            49.0 + 20.0 / 60.0 + 54.54 / 3600.0,
+        );
+        assert!(distance(jnow, expected) < 0.5, "{:?}", jnow);
+    }
+
+    #[test]
+    fn precession_at_j2000() {
+        let position = RaDec {
+            ra: 359.99,
+            dec: -89.99,
+        };
+        let precessed = position.j2000_to_jnow(NOON);
+        assert!(distance(precessed, (359.99, -89.99)) < 0.01);
+    }
+
+    #[test]
+    fn refraction_table() {
+        // standard refraction at 10 °C and 1010 hPa in arcminutes, rounded to
+        // 0.1' in the tables
+        for (alt, expected) in [(0.0, 34.5), (5.0, 9.9), (20.0, 2.6), (45.0, 1.0)] {
+            let refraction = refraction_arcmin(alt, 10.0, 1010.0);
+            assert!(
+                (refraction - expected).abs() < 0.15,
+                "{}: {}",
+                alt,
+                refraction
+            );
+        }
+        assert!(refraction_arcmin(90.0, 10.0, 1010.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn refraction_conditions() {
+        let standard = refraction_arcmin(5.0, 10.0, 1010.0);
+        let cold = refraction_arcmin(5.0, -10.0, 1010.0);
+        assert!((cold / standard - 283.0 / 263.0).abs() < 1e-4);
+        // half the air at altitude, half the refraction
+        assert!((refraction_arcmin(5.0, 10.0, 505.0) * 2.0 - standard).abs() < 1e-4);
+    }
+
+    #[test]
+    fn refraction_below_horizon() {
+        let horizon = refraction_arcmin(-1.0, 10.0, 1010.0);
+        for alt in [-1.5, -4.4, -5.11, -45.0, -90.0] {
+            assert_eq!(refraction_arcmin(alt, 10.0, 1010.0), horizon);
+            assert_eq!(
+                true_refraction_arcmin(alt, 10.0, 1010.0),
+                true_refraction_arcmin(-1.0, 10.0, 1010.0)
+            );
+        }
+        assert!(horizon.is_finite() && horizon < 60.0);
+    }
+
+    #[test]
+    fn refraction_roundtrip() {
+        for alt in [0.0, 2.0, 10.0, 30.0, 60.0, 89.0] {
+            let position = AzmAlt { azm: 120.0, alt };
+            let apparent = apply_refraction(position, 10.0, 1010.0);
+            assert!(apparent.alt > alt || alt > 85.0);
+            let back = remove_refraction(apparent, 10.0, 1010.0);
+            assert_eq!(back.azm, 120.0);
+            // the two formulas agree within a few arcseconds
+            assert!(
+                (back.alt - alt).abs() * 3600.0 < 5.0,
+                "{}: {}",
+                alt,
+                back.alt
+            );
+        }
+    }
+
+    #[test]
+    fn lst_reference() {
+        let utc = |year, month, day, hour, minutes, seconds| UtcDateTime {
+            year,
+            month,
+            day,
+            hour,
+            minutes,
+            seconds,
+        };
+        // Meeus examples 12.a and 12.b, the others from the IAU 2006 Earth
+        // rotation angle and GMST polynomial
+        let table: [(UtcDateTime, f32, f64); 7] = [
+            (utc(1987, 4, 10, 0, 0, 0), 0.0, 197.693195),
+            (utc(1987, 4, 10, 19, 21, 0), 0.0, 128.737873),
+            (utc(2000, 1, 1, 12, 0, 0), 0.0, 280.460622),
+            // leap day at the USNO
+            (utc(2024, 2, 29, 12, 0, 0), -77.0365, 261.762134),
+            // around the turn of the year in Zurich
+            (utc(2023, 12, 31, 23, 59, 59), 8.5417, 108.690137),
+            (utc(2024, 1, 1, 0, 0, 1), 8.5417, 108.698494),
+            (utc(2100, 3, 1, 6, 30, 0), 151.2093, 47.867604),
+        ];
+
+        for (datetime, longitude, expected) in table {
+            let lst = lst_degrees(datetime, longitude);
+            // 0.0005° are 0.12 s of time
+            assert!(
+                (lst as f64 - expected).abs() < 0.0005,
+                "{:?}: {}",
+                datetime,
+                lst
+            );
+        }
+    }
+
+    const USNO: Location = Location {
+        latitude: 38.92139,
+        longitude: -77.06556,
+    };
+
+    fn utc(year: u16, month: u8, day: u8, hour: u8, minutes: u8) -> UtcDateTime {
+        UtcDateTime {
+            year,
+            month,
+            day,
+            hour,
+            minutes,
+            seconds: 0,
+        }
+    }
+
+    /// Gets the position at the hour angle and declination.
+    fn at_hour_angle(
+        hour_angle: f32,
+        dec: f32,
+        location: Location,
+        datetime: UtcDateTime,
+    ) -> RaDec {
+        let lst = lst_degrees(datetime, location.longitude);
+        RaDec {
+            ra: (lst - hour_angle + 360.0) % 360.0,
+            dec,
+        }
+    }
+
+    #[test]
+    fn azm_alt_meeus() {
+        // Example 13.b, Venus from the USNO, with the azimuth from north and
+        // off by the 0.24 s between the mean and apparent sidereal time
+        let venus = RaDec {
+            ra: (23.0 + 9.0 / 60.0 + 16.641 / 3600.0) * 15.0,
+            dec: -(6.0 + 43.0 / 60.0 + 11.61 / 3600.0),
+        };
+        let datetime = utc(1987, 4, 10, 19, 21);
+
+        let position = venus.to_azm_alt(USNO, datetime);
+        assert!((position.azm - 248.0337).abs() < 0.002, "{:?}", position);
+        assert!((position.alt - 15.1249).abs() < 0.002, "{:?}", position);
+
+        let back = position.to_ra_dec(USNO, datetime);
+        assert!(back.separation(venus) * 3600.0 < 0.5, "{:?}", back);
+    }
+
+    #[test]
+    fn azm_alt_cardinal_points() {
+        let datetime = utc(2025, 3, 20, 21, 30);
+        let equator = Location {
+            latitude: 0.0,
+            longitude: 8.5,
+        };
+        let sydney = Location {
+            latitude: -33.87,
+            longitude: 151.21,
+        };
+        // hour angle, declination, location, azimuth and altitude
+        let table = [
+            // rising in the east and setting in the west
+            (-90.0, 0.0, equator, 90.0, 0.0),
+            (90.0, 0.0, equator, 270.0, 0.0),
+            // culminating south of the zenith and north of it
+            (0.0, 18.92139, USNO, 180.0, 70.0),
+            (0.0, -13.87, sydney, 0.0, 70.0),
+            // the poles are above the meridian at the latitude
+            (137.0, 90.0, USNO, 0.0, 38.92139),
+            (-61.0, -90.0, sydney, 180.0, 33.87),
+        ];
+
+        for (hour_angle, dec, location, azm, alt) in table {
+            let target = at_hour_angle(hour_angle, dec, location, datetime);
+            let position = target.to_azm_alt(location, datetime);
+            let expected = AzmAlt { azm, alt };
+            assert!(
+                position.separation(expected) < 0.001,
+                "{} {}: {:?}",
+                hour_angle,
+                dec,
+                position
+            );
+        }
+    }
+
+    #[test]
+    fn azm_alt_zenith_and_pole() {
+        let datetime = utc(2024, 12, 31, 23, 59);
+        // exactly at and next to the zenith, where the azimuth is arbitrary
+        for offset in [0.0, 0.0001, -0.0001] {
+            let target = at_hour_angle(offset, USNO.latitude, USNO, datetime);
+            let position = target.to_azm_alt(USNO, datetime);
+            assert!(
+                position.alt > 89.999 && position.alt <= 90.0,
+                "{:?}",
+                position
+            );
+            assert!(position.azm.is_finite());
+            let back = position.to_ra_dec(USNO, datetime);
+            assert!(back.separation(target) * 3600.0 < 0.5, "{:?}", back);
+        }
+
+        // from the north pole every star circles at its declination
+        let pole = Location {
+            latitude: 90.0,
+            longitude: 0.0,
+        };
+        for hour_angle in [0.0, 90.0, 200.0] {
+            let target = at_hour_angle(hour_angle, 89.99, pole, datetime);
+            let position = target.to_azm_alt(pole, datetime);
+            assert!((position.alt - 89.99).abs() < 0.0001, "{:?}", position);
+            let back = position.to_ra_dec(pole, datetime);
+            assert!(back.separation(target) * 3600.0 < 0.5, "{:?}", back);
+        }
+    }
+
+    #[test]
+    fn azm_alt_roundtrip() {
+        let locations = [
+            USNO,
+            Location {
+                latitude: -33.87,
+                longitude: 151.21,
+            },
+            Location {
+                latitude: 78.22,
+                longitude: 15.65,
+            },
+        ];
+        for location in locations {
+            for hour in [0, 7, 13, 22] {
+                let datetime = utc(2024, 2, 29, hour, 17);
+                for (ra, dec) in [
+                    (0.0, 0.0),
+                    (101.29, -16.72),
+                    (279.23, 38.78),
+                    (37.95, 89.26),
+                ] {
+                    let target = RaDec { ra, dec };
+                    let back = target
+                        .to_azm_alt(location, datetime)
+                        .to_ra_dec(location, datetime);
+                    assert!(back.separation(target) * 3600.0 < 0.5, "{:?}", back);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn zenith() {
+        let location = Location {
+            latitude: 48.0,
+            longitude: 11.0,
+        };
+        let lst = local_sidereal_time(julian_date(NOON), 11.0) as f32;
+        let zenith = RaDec { ra: lst, dec: 48.0 };
+
+        assert!((altitude(zenith, location, NOON) - 90.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn celestial_pole() {
+        let location = Location {
+            latitude: 48.0,
+            longitude: 11.0,
+        };
+        let pole = RaDec { ra: 0.0, dec: 90.0 };
+
+        assert!((altitude(pole, location, NOON) - 48.0).abs() < 0.01);
+    }
+}
@@ -0,0 +1,312 @@
+//! Async driver for chunked, externally driven IO
+//!
+//! Browsers (WebSerial through wasm-bindgen) and async runtimes deliver the
+//! received bytes in chunks and can't block on single bytes as the serial
+//! traits of [`NexStar`](crate::NexStar) do. [`AsyncNexStar`] runs the
+//! [sans-IO core](crate::protocol) over an [`AsyncTransport`] instead:
+//!
+//! ```ignore
+//! impl AsyncTransport for WebSerialPort {
+//!     type Error = JsValue;
+//!
+//!     async fn write_all(&mut self, bytes: &[u8]) -> Result<(), JsValue> {
+//!         JsFuture::from(self.writer.write_with_chunk(&Uint8Array::from(bytes))).await?;
+//!         Ok(())
+//!     }
+//!
+//!     async fn read(&mut self, buf: &mut [u8]) -> Result<usize, JsValue> {
+//!         // copy the next chunk of the ReadableStream, keeping the rest
+//!     }
+//! }
+//!
+//! let mut nexstar = AsyncNexStar::new(port);
+//! let position = nexstar.precise_ra_dec().await?;
+//! ```
+//!
+//! The driver needs neither an executor nor a timer, so it runs on any of
+//! them. Timeouts are up to the host, e.g. racing a call against a timer. A
+//! call whose future is dropped before it completes leaves the link dirty and
+//! the next call resyncs it with an echo command first, see
+//! [`AsyncNexStar::resync`].
+
+use crate::command::*;
+use crate::error::{Context, Error};
+use crate::protocol::{
+    encode_command, parse_response, Axes, Counts, ParseError, Parser, Progress, Request, Response,
+    MAX_COMMAND, MAX_RESPONSE,
+};
+use crate::types::*;
+
+/// Bytes received while resyncing before giving up
+const DRAIN_LIMIT: usize = 64;
+/// Size of the buffer for the received chunks
+const CHUNK: usize = 32;
+
+/// Transport of the [`AsyncNexStar`]
+///
+/// The futures don't need to be `Send`, the ones of the browser APIs aren't.
+#[allow(async_fn_in_trait)]
+pub trait AsyncTransport {
+    type Error;
+
+    /// Writes all the bytes.
+    async fn write_all(&mut self, bytes: &[u8]) -> Result<(), Self::Error>;
+
+    /// Reads the next chunk into `buf` as soon as at least one byte arrived
+    /// and returns its length, 0 if the transport is closed.
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error>;
+}
+
+/// Driver over an [`AsyncTransport`], see the [module docs](self)
+pub struct AsyncNexStar<T: AsyncTransport> {
+    transport: T,
+    /// Received chunk, `start..end` isn't consumed yet
+    buffer: [u8; CHUNK],
+    start: usize,
+    end: usize,
+    context: Context,
+    link_state: LinkState,
+    probe: u8,
+}
+
+impl<T: AsyncTransport> AsyncNexStar<T> {
+    pub fn new(transport: T) -> AsyncNexStar<T> {
+        AsyncNexStar {
+            transport,
+            buffer: [0; CHUNK],
+            start: 0,
+            end: 0,
+            context: Context::Hc(0),
+            link_state: LinkState::Clean,
+            probe: 0,
+        }
+    }
+
+    /// Sends the command and reads the response it expects.
+    ///
+    /// This is the way to any command without a method of its own.
+    pub async fn transact(
+        &mut self,
+        request: Request,
+    ) -> Result<Response, Error<T::Error, T::Error>> {
+        if self.link_state == LinkState::Dirty {
+            self.resync().await?;
+        }
+
+        let mut command = [0u8; MAX_COMMAND];
+        let len = encode_command(&request, &mut command).ok_or(Error::FrameOverflow)?;
+        let command = command.get(..len).ok_or(Error::FrameOverflow)?;
+        self.context = Context::from_frame(command);
+        // only cleared by a complete response, so a dropped call is noticed
+        self.link_state = LinkState::Dirty;
+        self.transport
+            .write_all(command)
+            .await
+            .map_err(Error::Write)?;
+
+        let mut buffer = [0u8; MAX_RESPONSE + 2];
+        let mut parser = Parser::new(&request);
+        loop {
+            let byte = match self.read().await {
+                Ok(byte) => byte,
+                Err(Error::Truncated { .. }) => {
+                    return Err(Error::Truncated {
+                        expected: request.response_len() + 1,
+                        got: parser.received(),
+                    })
+                }
+                Err(e) => return Err(e),
+            };
+            if parser.push(&mut buffer, byte) == Progress::NeedMore {
+                continue;
+            }
+            return match parse_response(&request, parser.response(&buffer)) {
+                Ok(response) => {
+                    self.link_state = LinkState::Clean;
+                    Ok(response)
+                }
+                Err(ParseError::Incomplete)
+                | Err(ParseError::Rejected)
+                | Err(ParseError::Invalid) => Err(Error::UnexpectedResponse(self.context)),
+            };
+        }
+    }
+
+    /// Gets the HC firmware version.
+    pub async fn version(&mut self) -> Result<Version, Error<T::Error, T::Error>> {
+        match self.transact(Request::GetVersion).await? {
+            Response::Version(version) => Ok(version),
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    /// Gets the precise RA/Dec position.
+    #[cfg(feature = "float")]
+    pub async fn precise_ra_dec(&mut self) -> Result<RaDec, Error<T::Error, T::Error>> {
+        let request = Request::GetPosition {
+            axes: Axes::RaDec,
+            precise: true,
+        };
+        match self.transact(request).await? {
+            Response::Position(Counts { first, second }) => Ok(RaDec {
+                ra: fraction_to_degrees(first, resolution(true), false),
+                dec: fraction_to_degrees(second, resolution(true), true),
+            }),
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    /// Starts a precise GOTO to the RA/Dec position.
+    #[cfg(feature = "float")]
+    pub async fn precise_goto_ra_dec(
+        &mut self,
+        target: RaDec,
+    ) -> Result<(), Error<T::Error, T::Error>> {
+        let request = Request::Goto {
+            axes: Axes::RaDec,
+            position: Counts {
+                first: degrees_to_fraction(target.ra, resolution(true)),
+                second: degrees_to_fraction(target.dec, resolution(true)),
+            },
+            precise: true,
+        };
+        self.transact(request).await?;
+        Ok(())
+    }
+
+    /// Gets whether a GOTO is in progress.
+    pub async fn is_goto_in_progress(&mut self) -> Result<bool, Error<T::Error, T::Error>> {
+        match self.transact(Request::IsGotoInProgress).await? {
+            Response::State(in_progress) => Ok(in_progress),
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    pub async fn cancel_goto(&mut self) -> Result<(), Error<T::Error, T::Error>> {
+        self.transact(Request::CancelGoto).await?;
+        Ok(())
+    }
+
+    /// Gets the location set in the HC.
+    #[cfg(feature = "float")]
+    pub async fn location(&mut self) -> Result<Location, Error<T::Error, T::Error>> {
+        match self.transact(Request::GetLocation).await? {
+            Response::Location {
+                latitude,
+                longitude,
+            } => match Location::from_dms(latitude, longitude) {
+                Some(location) => Ok(location),
+                None => Err(self.unexpected()),
+            },
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    /// Sets the location of the HC.
+    #[cfg(feature = "float")]
+    pub async fn set_location(
+        &mut self,
+        location: Location,
+    ) -> Result<(), Error<T::Error, T::Error>> {
+        let (latitude, longitude) = location.to_dms();
+        self.transact(Request::SetLocation {
+            latitude,
+            longitude,
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Gets the date and time set in the HC.
+    pub async fn datetime(&mut self) -> Result<DateTime, Error<T::Error, T::Error>> {
+        match self.transact(Request::GetDateTime).await? {
+            Response::DateTime(datetime) => Ok(datetime),
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    pub async fn set_datetime(
+        &mut self,
+        datetime: DateTime,
+    ) -> Result<(), Error<T::Error, T::Error>> {
+        self.transact(Request::SetDateTime(datetime)).await?;
+        Ok(())
+    }
+
+    /// Gets whether the last call completed, see [`LinkState`].
+    pub fn link_state(&self) -> LinkState {
+        self.link_state
+    }
+
+    /// Drops the buffered bytes, sends an echo command and discards everything
+    /// received before the echo, then marks the link clean.
+    ///
+    /// Called by the next command after an error or a dropped call.
+    pub async fn resync(&mut self) -> Result<(), Error<T::Error, T::Error>> {
+        self.start = 0;
+        self.end = 0;
+
+        // a new marker every time, the echo of an earlier probe may still be
+        // in transit
+        self.probe = self.probe.wrapping_add(1);
+        let marker = b'A' + self.probe % 26;
+        let mut command = [0u8; MAX_COMMAND];
+        let len =
+            encode_command(&Request::Echo(marker), &mut command).ok_or(Error::FrameOverflow)?;
+        let command = command.get(..len).ok_or(Error::FrameOverflow)?;
+        self.context = Context::from_frame(command);
+        self.transport
+            .write_all(command)
+            .await
+            .map_err(Error::Write)?;
+
+        let mut previous = 0;
+        for _ in 0..DRAIN_LIMIT {
+            let byte = self.read().await?;
+            if previous == marker && byte == b'#' {
+                self.link_state = LinkState::Clean;
+                return Ok(());
+            }
+            previous = byte;
+        }
+
+        Err(Error::UnexpectedResponse(self.context))
+    }
+
+    pub fn free(self) -> T {
+        self.transport
+    }
+
+    /// Takes the next received byte, reading a new chunk once the buffered
+    /// ones are used up.
+    async fn read(&mut self) -> Result<u8, Error<T::Error, T::Error>> {
+        if self.start >= self.end {
+            let len = self
+                .transport
+                .read(&mut self.buffer)
+                .await
+                .map_err(Error::Read)?;
+            if len == 0 {
+                return Err(Error::Truncated {
+                    expected: 1,
+                    got: 0,
+                });
+            }
+            self.start = 0;
+            self.end = len.min(CHUNK);
+        }
+
+        let byte = self
+            .buffer
+            .get(self.start)
+            .copied()
+            .ok_or(Error::FrameOverflow)?;
+        self.start += 1;
+        Ok(byte)
+    }
+
+    fn unexpected(&mut self) -> Error<T::Error, T::Error> {
+        self.link_state = LinkState::Dirty;
+        Error::UnexpectedResponse(self.context)
+    }
+}
@@ -0,0 +1,387 @@
+//! Direct AUX bus protocol
+//!
+//! Without a Hand Controller (HC) in the loop, e.g. on the USB/PC port of
+//! Evolution mounts, the devices speak the AUX bus framing:
+//!
+//! ```text
+//! 0x3b | length | source | destination | command | data... | checksum
+//! ```
+//!
+//! `length` counts source, destination, command and data bytes, the checksum
+//! is the two's complement of the sum of all bytes from `length` to the last
+//! data byte. The device ids are the same as the [`Device`] pass-through ids.
+//!
+//! No frames captured from a mount were available, the ones in the tests are
+//! synthetic and built from this description. The codec is only checked
+//! against the description, not against hardware.
+
+use embedded_hal::serial;
+use nb::block;
+
+use crate::command::{arcsec_to_fraction, fraction_to_arcsec, position_bytes};
+#[cfg(feature = "float")]
+use crate::command::{degrees_to_fraction, fraction_to_degrees};
+use crate::{Context, Device, Error, Version};
+
+/// Start of every AUX frame
+pub const PREAMBLE: u8 = 0x3b;
+/// Maximum number of data bytes in a [`Packet`]
+pub const MAX_DATA: usize = 16;
+/// Maximum length of an encoded frame
+pub const MAX_FRAME: usize = MAX_DATA + 6;
+/// Default source address of the driver (a PC application)
+pub const PC_ADDRESS: u8 = 0x20;
+
+/// Frames of other devices to skip while waiting for a response
+const MAX_SKIPPED: usize = 8;
+/// Length of a frame without data
+const MIN_FRAME: usize = 6;
+
+/// Motor controller commands
+#[derive(Copy, Clone, Debug)]
+pub enum McCommand {
+    GetPosition = 0x01,
+    GotoFast = 0x02,
+    SlewDone = 0x13,
+    GotoSlow = 0x17,
+    MovePositive = 0x24,
+    MoveNegative = 0x25,
+    GetVersion = 0xFE,
+}
+
+/// Invalid AUX frame
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FrameError {
+    /// The frame doesn't start with [`PREAMBLE`].
+    Preamble,
+    /// The length byte doesn't match the frame or exceeds [`MAX_DATA`].
+    Length,
+    /// The checksum doesn't match.
+    Checksum,
+}
+
+/// AUX bus packet
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Packet {
+    pub src: u8,
+    pub dst: u8,
+    pub cmd: u8,
+    data: [u8; MAX_DATA],
+    len: u8,
+}
+
+impl Packet {
+    /// Creates a packet, `None` if there are more than [`MAX_DATA`] bytes.
+    pub fn new(src: u8, dst: u8, cmd: u8, data: &[u8]) -> Option<Packet> {
+        if data.len() > MAX_DATA {
+            return None;
+        }
+
+        let mut packet = Packet {
+            src,
+            dst,
+            cmd,
+            data: [0; MAX_DATA],
+            len: data.len() as u8,
+        };
+        packet.data[..data.len()].copy_from_slice(data);
+        Some(packet)
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data[..self.len as usize]
+    }
+
+    /// Encodes the packet into `frame` and returns the length of the frame.
+    pub fn encode(&self, frame: &mut [u8; MAX_FRAME]) -> usize {
+        let len = self.len as usize + 3;
+        frame[0] = PREAMBLE;
+        frame[1] = len as u8;
+        frame[2] = self.src;
+        frame[3] = self.dst;
+        frame[4] = self.cmd;
+        frame[5..len + 2].copy_from_slice(self.data());
+        frame[len + 2] = checksum(&frame[1..len + 2]);
+
+        len + 3
+    }
+
+    /// Decodes a complete frame.
+    pub fn decode(frame: &[u8]) -> Result<Packet, FrameError> {
+        let (&preamble, rest) = frame.split_first().ok_or(FrameError::Preamble)?;
+        if preamble != PREAMBLE {
+            return Err(FrameError::Preamble);
+        }
+
+        let len = *rest.first().ok_or(FrameError::Length)? as usize;
+        if !(3..=MAX_DATA + 3).contains(&len) || rest.len() != len + 2 {
+            return Err(FrameError::Length);
+        }
+        if checksum(&rest[..len + 1]) != rest[len + 1] {
+            return Err(FrameError::Checksum);
+        }
+
+        Packet::new(rest[1], rest[2], rest[3], &rest[4..len + 1]).ok_or(FrameError::Length)
+    }
+}
+
+/// Calculates the checksum of the bytes from the length to the last data byte.
+pub fn checksum(bytes: &[u8]) -> u8 {
+    bytes
+        .iter()
+        .fold(0u8, |sum, byte| sum.wrapping_add(*byte))
+        .wrapping_neg()
+}
+
+/// Converts degrees into the 24 bit fraction of a revolution.
+#[cfg(feature = "float")]
+fn encode_position(degrees: f32) -> [u8; 3] {
+    position_bytes(degrees_to_fraction(degrees, 24))
+}
+
+/// Converts the 24 bit fraction of a revolution into the signed mechanical
+/// angle in degrees (-180..=180).
+#[cfg(feature = "float")]
+fn decode_position(bytes: [u8; 3]) -> f32 {
+    let [high, mid, low] = bytes;
+    fraction_to_degrees(u32::from_be_bytes([0, high, mid, low]), 24, true)
+}
+
+/// AUX bus driver
+pub struct AuxBus<T, U>
+where
+    T: serial::Read<u8>,
+    U: serial::Write<u8>,
+{
+    rx: T,
+    tx: U,
+    address: u8,
+    timeout: Option<(fn() -> u32, u32)>,
+}
+
+impl<T, U> AuxBus<T, U>
+where
+    T: serial::Read<u8>,
+    U: serial::Write<u8>,
+{
+    /// Creates the driver using [`PC_ADDRESS`] as source address.
+    pub fn new(rx: T, tx: U) -> AuxBus<T, U> {
+        AuxBus::with_address(rx, tx, PC_ADDRESS)
+    }
+
+    pub fn with_address(rx: T, tx: U, address: u8) -> AuxBus<T, U> {
+        AuxBus {
+            rx,
+            tx,
+            address,
+            timeout: None,
+        }
+    }
+
+    /// Sets the time to wait for each byte, like
+    /// [`NexStar::set_timeout`](crate::NexStar::set_timeout).
+    ///
+    /// [`AuxBus::recv`] fails with [`Error::Timeout`] if no frame starts in
+    /// time and with [`Error::Truncated`] if a frame stops early.
+    pub fn set_timeout(&mut self, clock: fn() -> u32, timeout_ms: u32) {
+        self.timeout = Some((clock, timeout_ms));
+    }
+
+    /// Waits for frames forever (the default).
+    pub fn clear_timeout(&mut self) {
+        self.timeout = None;
+    }
+
+    /// Sends a packet to the device.
+    pub fn send(
+        &mut self,
+        dst: Device,
+        cmd: u8,
+        data: &[u8],
+    ) -> Result<(), Error<T::Error, U::Error>> {
+        let packet = Packet::new(self.address, dst.bits(), cmd, data)
+            .ok_or(Error::InvalidFrame(FrameError::Length))?;
+
+        let mut frame = [0u8; MAX_FRAME];
+        let len = packet.encode(&mut frame);
+        for byte in &frame[..len] {
+            block!(self.tx.write(*byte)).map_err(Error::Write)?;
+        }
+        block!(self.tx.flush()).map_err(Error::Write)
+    }
+
+    /// Receives the next valid packet on the bus.
+    ///
+    /// Bytes before the preamble are skipped.
+    pub fn recv(&mut self) -> Result<Packet, Error<T::Error, U::Error>> {
+        while self.read()? != PREAMBLE {}
+
+        let len = self.read().map_err(|e| truncated(e, MIN_FRAME, 1))?;
+        let mut frame = [0u8; 2 + 255 + 1];
+        frame[0] = PREAMBLE;
+        frame[1] = len;
+        let end = len as usize + 3;
+        for (got, byte) in frame[..end].iter_mut().enumerate().skip(2) {
+            *byte = self.read().map_err(|e| truncated(e, end, got))?;
+        }
+
+        Packet::decode(&frame[..end]).map_err(Error::InvalidFrame)
+    }
+
+    /// Sends a command to the device and waits for its response.
+    ///
+    /// Packets of other devices, e.g. the echo of our own command on the bus,
+    /// are skipped.
+    pub fn command(
+        &mut self,
+        dst: Device,
+        cmd: u8,
+        data: &[u8],
+    ) -> Result<Packet, Error<T::Error, U::Error>> {
+        self.send(dst, cmd, data)?;
+
+        for _ in 0..MAX_SKIPPED {
+            let packet = self.recv()?;
+            if packet.src == dst.bits() && packet.dst == self.address && packet.cmd == cmd {
+                return Ok(packet);
+            }
+        }
+
+        Err(Error::UnexpectedResponse(Context::PassThrough {
+            device: dst,
+            command: cmd,
+        }))
+    }
+
+    /// Gets the mechanical angle of the motor in degrees (-180..=180), signed
+    /// like [`NexStar::motor_angle`](crate::NexStar::motor_angle).
+    #[cfg(feature = "float")]
+    pub fn motor_position(&mut self, device: Device) -> Result<f32, Error<T::Error, U::Error>> {
+        self.read_motor_position(device).map(decode_position)
+    }
+
+    /// Gets the mechanical angle of the motor in arcseconds
+    /// (-648_000..=648_000).
+    pub fn motor_position_arcsec(
+        &mut self,
+        device: Device,
+    ) -> Result<i32, Error<T::Error, U::Error>> {
+        let [high, mid, low] = self.read_motor_position(device)?;
+        Ok(fraction_to_arcsec(
+            u32::from_be_bytes([0, high, mid, low]),
+            24,
+            true,
+        ))
+    }
+
+    /// Starts a GOTO of the motor to the position in degrees.
+    #[cfg(feature = "float")]
+    pub fn goto(
+        &mut self,
+        device: Device,
+        degrees: f32,
+        fast: bool,
+    ) -> Result<(), Error<T::Error, U::Error>> {
+        self.goto_position(device, encode_position(degrees), fast)
+    }
+
+    /// Starts a GOTO of the motor to the position in arcseconds.
+    pub fn goto_arcsec(
+        &mut self,
+        device: Device,
+        arcsec: i32,
+        fast: bool,
+    ) -> Result<(), Error<T::Error, U::Error>> {
+        let position = position_bytes(arcsec_to_fraction(arcsec, 24));
+        self.goto_position(device, position, fast)
+    }
+
+    fn read_motor_position(
+        &mut self,
+        device: Device,
+    ) -> Result<[u8; 3], Error<T::Error, U::Error>> {
+        let response = self.command(device, McCommand::GetPosition as u8, &[])?;
+        match *response.data() {
+            [high, mid, low] => Ok([high, mid, low]),
+            _ => Err(Error::UnexpectedResponse(Context::PassThrough {
+                device,
+                command: McCommand::GetPosition as u8,
+            })),
+        }
+    }
+
+    fn goto_position(
+        &mut self,
+        device: Device,
+        position: [u8; 3],
+        fast: bool,
+    ) -> Result<(), Error<T::Error, U::Error>> {
+        let cmd = if fast {
+            McCommand::GotoFast
+        } else {
+            McCommand::GotoSlow
+        };
+        self.command(device, cmd as u8, &position).map(|_| ())
+    }
+
+    /// Checks whether the GOTO of the motor is done.
+    pub fn slew_done(&mut self, device: Device) -> Result<bool, Error<T::Error, U::Error>> {
+        let response = self.command(device, McCommand::SlewDone as u8, &[])?;
+        Ok(response.data().first() == Some(&0xFF))
+    }
+
+    /// Slews the motor at one of the fixed rates (-9..=9), 0 stops.
+    pub fn slew(&mut self, device: Device, rate: i8) -> Result<(), Error<T::Error, U::Error>> {
+        let cmd = if rate < 0 {
+            McCommand::MoveNegative
+        } else {
+            McCommand::MovePositive
+        };
+        let rate = rate.unsigned_abs().min(9);
+        self.command(device, cmd as u8, &[rate]).map(|_| ())
+    }
+
+    /// Gets the firmware version of the device.
+    pub fn version(&mut self, device: Device) -> Result<Version, Error<T::Error, U::Error>> {
+        let response = self.command(device, McCommand::GetVersion as u8, &[])?;
+        match *response.data() {
+            [major, minor, ..] => Ok(Version::new(major, minor)),
+            _ => Err(Error::UnexpectedResponse(Context::PassThrough {
+                device,
+                command: McCommand::GetVersion as u8,
+            })),
+        }
+    }
+
+    pub fn free(self) -> (T, U) {
+        (self.rx, self.tx)
+    }
+
+    fn read(&mut self) -> Result<u8, Error<T::Error, U::Error>> {
+        let (clock, timeout_ms) = match self.timeout {
+            None => return block!(self.rx.read()).map_err(Error::Read),
+            Some(timeout) => timeout,
+        };
+
+        let start = clock();
+        loop {
+            match self.rx.read() {
+                Ok(byte) => return Ok(byte),
+                Err(nb::Error::Other(e)) => return Err(Error::Read(e)),
+                Err(nb::Error::WouldBlock) => {
+                    if clock().wrapping_sub(start) > timeout_ms {
+                        return Err(Error::Timeout);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Turns a timeout within a frame into [`Error::Truncated`].
+fn truncated<R, W>(error: Error<R, W>, expected: usize, got: usize) -> Error<R, W> {
+    match error {
+        Error::Timeout => Error::Truncated { expected, got },
+        e => e,
+    }
+}
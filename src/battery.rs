@@ -0,0 +1,64 @@
+//! State of charge of the mount battery
+//!
+//! The Evolution mounts run from a built-in 12 V LiFePO4 pack, whose voltage
+//! is read with [`NexStar::battery_voltage`](crate::NexStar::battery_voltage).
+//! LiFePO4 cells hold an almost flat voltage over most of their charge, so the
+//! mapping of [`state_of_charge`] is only approximate: it interpolates the
+//! resting voltage curve of a four cell pack and reads high while charging and
+//! low under load, e.g. during a GOTO.
+
+/// Resting voltage of the pack in mV and its state of charge in percent,
+/// by descending voltage
+const CURVE: [(u32, u8); 11] = [
+    (13_600, 100),
+    (13_400, 99),
+    (13_300, 90),
+    (13_200, 70),
+    (13_100, 40),
+    (13_000, 30),
+    (12_900, 20),
+    (12_800, 17),
+    (12_500, 14),
+    (12_000, 9),
+    (10_000, 0),
+];
+
+/// Maps the battery voltage in mV to the approximate state of charge in
+/// percent (0..=100).
+pub fn state_of_charge(millivolts: u32) -> u8 {
+    let mut upper = CURVE[0];
+    for lower in CURVE {
+        if millivolts >= lower.0 {
+            if lower == upper {
+                return upper.1;
+            }
+            // linear between the neighbouring points, rounded
+            let span = upper.0 - lower.0;
+            let percent = (millivolts - lower.0) * (upper.1 - lower.1) as u32;
+            return lower.1 + ((percent + span / 2) / span) as u8;
+        }
+        upper = lower;
+    }
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn knee_points() {
+        for (millivolts, percent) in CURVE {
+            assert_eq!(state_of_charge(millivolts), percent);
+        }
+    }
+
+    #[test]
+    fn between_and_beyond() {
+        assert_eq!(state_of_charge(13_150), 55);
+        assert_eq!(state_of_charge(11_000), 5);
+        assert_eq!(state_of_charge(14_400), 100);
+        assert_eq!(state_of_charge(9_000), 0);
+        assert_eq!(state_of_charge(0), 0);
+    }
+}
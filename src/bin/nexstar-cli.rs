@@ -0,0 +1,53 @@
+use std::env;
+use std::io;
+use std::process;
+use std::sync::OnceLock;
+use std::time::Instant;
+
+use nexstar::cli::{self, Args};
+use nexstar::NexStar;
+
+use serial::{Baud9600, Bits8, FlowNone, ParityNone, Stop1};
+use serial_embedded_hal::{PortSettings, Serial};
+
+/// Time to wait for each response byte
+const TIMEOUT_MS: u32 = 3500;
+
+static START: OnceLock<Instant> = OnceLock::new();
+
+fn clock() -> u32 {
+    START.get_or_init(Instant::now).elapsed().as_millis() as u32
+}
+
+fn exit(error: cli::CliError) -> ! {
+    eprintln!("error: {}", error);
+    process::exit(error.exit_code());
+}
+
+fn main() {
+    let args = Args::parse(env::args().skip(1)).unwrap_or_else(|e| exit(e));
+    if args.is_help() {
+        print!("{}", cli::USAGE);
+        return;
+    }
+
+    let port_settings = PortSettings {
+        baud_rate: Baud9600,
+        char_size: Bits8,
+        parity: ParityNone,
+        stop_bits: Stop1,
+        flow_control: FlowNone,
+    };
+    let port = Serial::new(&args.port, &port_settings).unwrap_or_else(|e| {
+        eprintln!("error: can't open {}: {}", args.port, e);
+        process::exit(1);
+    });
+    let (tx, rx) = port.split();
+
+    let mut nexstar = NexStar::new(rx, tx);
+    nexstar.set_timeout(clock, TIMEOUT_MS);
+
+    if let Err(e) = cli::run(&args, &mut nexstar, &mut io::stdout().lock()) {
+        exit(e);
+    }
+}
@@ -0,0 +1,613 @@
+//! Command line front end of the `nexstar-cli` binary
+//!
+//! [`Args`] parses the command line and [`run`] executes it on a [`Mount`],
+//! so the binary only opens the serial port and the commands can be tested
+//! against the [simulator](crate::simulator) or a fake mount:
+//!
+//! ```ignore
+//! let args = Args::parse(env::args().skip(1))?;
+//! let mut nexstar = NexStar::new(rx, tx);
+//! cli::run(&args, &mut nexstar, &mut io::stdout())?;
+//! ```
+//!
+//! Every command connects first, which checks the link and picks the precise
+//! position commands if the firmware supports them.
+
+use core::fmt;
+use core::str::FromStr;
+use std::borrow::ToOwned;
+use std::format;
+use std::io;
+use std::string::{String, ToString};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::command::PRECISE_GOTO_VERSION;
+use crate::{
+    AzmAlt, DateTime, Error, Location, Model, Mount, RaDec, Target, TrackingMode, Version,
+};
+
+/// Serial port used without `--port`
+pub const DEFAULT_PORT: &str = "/dev/ttyUSB0";
+
+pub const USAGE: &str = "\
+usage: nexstar-cli [--port <path>] <command>
+
+commands:
+  info                                  model, firmware, alignment, location and time
+  get-position [--azm-alt]              current RA/Dec or Azm/Alt position
+  goto --ra <deg> --dec <deg>           start a GOTO to the RA/Dec position
+  goto --azm <deg> --alt <deg>          start a GOTO to the Azm/Alt position
+  set-time --now [--zone <h>] [--dst]   set the HC clock from the host clock
+  set-location --lat <deg> --lon <deg>  set the location, north and east positive
+  slew [--az-rate <\"/s>] [--alt-rate <\"/s>]
+                                        drive the motors, a rate of 0 stops them
+  track off|alt-az|eq-north|eq-south    set the tracking mode
+";
+
+/// Failed command line invocation
+#[derive(Debug)]
+pub enum CliError {
+    /// The arguments are invalid.
+    Usage(String),
+    /// The mount or the serial link failed.
+    Mount(String),
+    /// The output couldn't be written.
+    Output(io::Error),
+}
+
+impl CliError {
+    /// Gets the exit code of the process, 2 for usage errors and 1 otherwise.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CliError::Usage(_) => 2,
+            CliError::Mount(_) | CliError::Output(_) => 1,
+        }
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CliError::Usage(message) => write!(f, "{}\n\n{}", message, USAGE),
+            CliError::Mount(message) => write!(f, "{}", message),
+            CliError::Output(e) => write!(f, "can't write output: {}", e),
+        }
+    }
+}
+
+impl From<io::Error> for CliError {
+    fn from(e: io::Error) -> CliError {
+        CliError::Output(e)
+    }
+}
+
+impl<T: fmt::Debug, U: fmt::Debug> From<Error<T, U>> for CliError {
+    fn from(e: Error<T, U>) -> CliError {
+        let message = match e {
+            Error::Truncated { got: 0, .. } => {
+                "no response from the mount, check the cable, the port and that the HC is on"
+                    .to_owned()
+            }
+            Error::Truncated { .. } => format!("{}, the serial line looks unreliable", e),
+            Error::UnexpectedResponse(_) => format!("the mount rejected the command: {}", e),
+            Error::UnsupportedFirmware { .. } => {
+                format!("{}, the command needs a firmware update", e)
+            }
+            Error::Read(_) | Error::Write(_) => format!("serial port {}", e),
+            e => e.to_string(),
+        };
+        CliError::Mount(message)
+    }
+}
+
+#[derive(Copy, Clone)]
+enum Command {
+    Help,
+    Info,
+    GetPosition { azm_alt: bool },
+    Goto(Target),
+    SetTime { zone: i8, daylight_saving: bool },
+    SetLocation(Location),
+    Slew { azm: i32, alt: i32 },
+    Track(TrackingMode),
+}
+
+/// Parsed command line
+#[derive(Clone)]
+pub struct Args {
+    /// Path of the serial port
+    pub port: String,
+    command: Command,
+}
+
+impl Args {
+    /// Parses the arguments without the program name.
+    pub fn parse<I>(args: I) -> Result<Args, CliError>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let mut args = args.into_iter();
+        let mut port = DEFAULT_PORT.to_owned();
+
+        let command = loop {
+            match args.next() {
+                Some(arg) => match arg.as_ref() {
+                    "--port" => port = value(&mut args, "--port")?,
+                    "-h" | "--help" | "help" => break Command::Help,
+                    "info" => break Command::Info,
+                    "get-position" => break parse_get_position(&mut args)?,
+                    "goto" => break parse_goto(&mut args)?,
+                    "set-time" => break parse_set_time(&mut args)?,
+                    "set-location" => break parse_set_location(&mut args)?,
+                    "slew" => break parse_slew(&mut args)?,
+                    "track" => break parse_track(&mut args)?,
+                    other => return Err(usage(format!("unknown command '{}'", other))),
+                },
+                None => return Err(usage("missing command".to_owned())),
+            }
+        };
+
+        if let Some(arg) = args.next() {
+            return Err(usage(format!("unexpected argument '{}'", arg.as_ref())));
+        }
+
+        Ok(Args { port, command })
+    }
+
+    /// Checks whether only the usage was requested, which needs no mount.
+    pub fn is_help(&self) -> bool {
+        matches!(self.command, Command::Help)
+    }
+}
+
+fn usage(message: String) -> CliError {
+    CliError::Usage(message)
+}
+
+/// Gets the value following an option.
+fn value<I>(args: &mut I, option: &str) -> Result<String, CliError>
+where
+    I: Iterator,
+    I::Item: AsRef<str>,
+{
+    match args.next() {
+        Some(value) => Ok(value.as_ref().to_owned()),
+        None => Err(usage(format!("missing value of {}", option))),
+    }
+}
+
+fn number<N: FromStr>(value: &str, option: &str) -> Result<N, CliError> {
+    value
+        .parse()
+        .map_err(|_| usage(format!("invalid value '{}' of {}", value, option)))
+}
+
+/// Parses a value in degrees and checks it is within `-limit..=limit`.
+fn degrees(value: &str, option: &str, limit: f32) -> Result<f32, CliError> {
+    let degrees: f32 = number(value, option)?;
+    if !degrees.is_finite() || degrees.abs() > limit {
+        return Err(usage(format!(
+            "{} must be within -{}..{} degrees",
+            option, limit, limit
+        )));
+    }
+    Ok(degrees)
+}
+
+/// Collects the `--option value` pairs of the options in `options`.
+fn options<I, const N: usize>(
+    args: &mut I,
+    options: [&str; N],
+) -> Result<[Option<String>; N], CliError>
+where
+    I: Iterator,
+    I::Item: AsRef<str>,
+{
+    let mut values: [Option<String>; N] = core::array::from_fn(|_| None);
+    while let Some(arg) = args.next() {
+        let arg = arg.as_ref();
+        match options.iter().position(|option| *option == arg) {
+            Some(index) => values[index] = Some(value(args, arg)?),
+            None => return Err(usage(format!("unexpected argument '{}'", arg))),
+        }
+    }
+    Ok(values)
+}
+
+fn parse_get_position<I>(args: &mut I) -> Result<Command, CliError>
+where
+    I: Iterator,
+    I::Item: AsRef<str>,
+{
+    match args.next() {
+        None => Ok(Command::GetPosition { azm_alt: false }),
+        Some(arg) if arg.as_ref() == "--azm-alt" => Ok(Command::GetPosition { azm_alt: true }),
+        Some(arg) => Err(usage(format!("unexpected argument '{}'", arg.as_ref()))),
+    }
+}
+
+fn parse_goto<I>(args: &mut I) -> Result<Command, CliError>
+where
+    I: Iterator,
+    I::Item: AsRef<str>,
+{
+    match options(args, ["--ra", "--dec", "--azm", "--alt"])? {
+        [Some(ra), Some(dec), None, None] => Ok(Command::Goto(Target::RaDec(RaDec {
+            ra: degrees(&ra, "--ra", 360.0)?,
+            dec: degrees(&dec, "--dec", 90.0)?,
+        }))),
+        [None, None, Some(azm), Some(alt)] => Ok(Command::Goto(Target::AzmAlt(AzmAlt {
+            azm: degrees(&azm, "--azm", 360.0)?,
+            alt: degrees(&alt, "--alt", 90.0)?,
+        }))),
+        _ => Err(usage(
+            "goto needs either --ra and --dec or --azm and --alt".to_owned(),
+        )),
+    }
+}
+
+fn parse_set_time<I>(args: &mut I) -> Result<Command, CliError>
+where
+    I: Iterator,
+    I::Item: AsRef<str>,
+{
+    let (mut now, mut zone, mut daylight_saving) = (false, 0, false);
+    while let Some(arg) = args.next() {
+        match arg.as_ref() {
+            "--now" => now = true,
+            "--dst" => daylight_saving = true,
+            "--zone" => {
+                let value = value(args, "--zone")?;
+                zone = number(&value, "--zone")?;
+                if !(-12..=14).contains(&zone) {
+                    return Err(usage("--zone must be within -12..14 hours".to_owned()));
+                }
+            }
+            other => return Err(usage(format!("unexpected argument '{}'", other))),
+        }
+    }
+
+    if !now {
+        return Err(usage("set-time needs --now".to_owned()));
+    }
+    Ok(Command::SetTime {
+        zone,
+        daylight_saving,
+    })
+}
+
+fn parse_set_location<I>(args: &mut I) -> Result<Command, CliError>
+where
+    I: Iterator,
+    I::Item: AsRef<str>,
+{
+    match options(args, ["--lat", "--lon"])? {
+        [Some(latitude), Some(longitude)] => Ok(Command::SetLocation(Location {
+            latitude: degrees(&latitude, "--lat", 90.0)?,
+            longitude: degrees(&longitude, "--lon", 180.0)?,
+        })),
+        _ => Err(usage("set-location needs --lat and --lon".to_owned())),
+    }
+}
+
+fn parse_slew<I>(args: &mut I) -> Result<Command, CliError>
+where
+    I: Iterator,
+    I::Item: AsRef<str>,
+{
+    let [azm, alt] = options(args, ["--az-rate", "--alt-rate"])?;
+    let azm = match azm {
+        Some(rate) => number(&rate, "--az-rate")?,
+        None => 0,
+    };
+    let alt = match alt {
+        Some(rate) => number(&rate, "--alt-rate")?,
+        None => 0,
+    };
+    Ok(Command::Slew { azm, alt })
+}
+
+fn parse_track<I>(args: &mut I) -> Result<Command, CliError>
+where
+    I: Iterator,
+    I::Item: AsRef<str>,
+{
+    let mode = match args.next() {
+        Some(mode) => match mode.as_ref() {
+            "off" => TrackingMode::Off,
+            "alt-az" => TrackingMode::AltAz,
+            "eq-north" => TrackingMode::EQNorth,
+            "eq-south" => TrackingMode::EQSouth,
+            other => return Err(usage(format!("unknown tracking mode '{}'", other))),
+        },
+        None => return Err(usage("missing tracking mode".to_owned())),
+    };
+    Ok(Command::Track(mode))
+}
+
+/// Runs the command on the mount and writes the human readable result to
+/// `out`.
+pub fn run<M, W>(args: &Args, mount: &mut M, out: &mut W) -> Result<(), CliError>
+where
+    M: Mount + ?Sized,
+    CliError: From<M::Error>,
+    W: io::Write,
+{
+    if let Command::Help = args.command {
+        write!(out, "{}", USAGE)?;
+        return Ok(());
+    }
+
+    let info = mount.connect()?;
+    let precise = info.hc_version >= PRECISE_GOTO_VERSION;
+
+    match args.command {
+        Command::Help => {}
+        Command::Info => {
+            writeln!(out, "Model: {}", model_name(info.model))?;
+            writeln!(out, "HC firmware: {}", version(Some(info.hc_version)))?;
+            writeln!(
+                out,
+                "AZM/RA motor firmware: {}",
+                version(info.azm_ra_version)
+            )?;
+            writeln!(
+                out,
+                "ALT/DEC motor firmware: {}",
+                version(info.alt_dec_version)
+            )?;
+
+            let status = mount.status()?;
+            let location = mount.location()?;
+            let datetime = mount.datetime()?;
+            writeln!(
+                out,
+                "Alignment: {}",
+                if status.aligned {
+                    "complete"
+                } else {
+                    "incomplete"
+                }
+            )?;
+            writeln!(
+                out,
+                "GOTO: {}",
+                if status.goto_in_progress {
+                    "in progress"
+                } else {
+                    "idle"
+                }
+            )?;
+            writeln!(out, "Tracking: {}", status.tracking)?;
+            writeln!(out, "Location: {}", location)?;
+            writeln!(out, "Time: {}", datetime_text(datetime))?;
+        }
+        Command::GetPosition { azm_alt: false } => {
+            let position = if precise {
+                mount.precise_ra_dec()?
+            } else {
+                mount.ra_dec()?
+            };
+            writeln!(out, "RA  {}", ra_text(position.ra))?;
+            writeln!(out, "Dec {}", angle_text(position.dec))?;
+        }
+        Command::GetPosition { azm_alt: true } => {
+            let position = if precise {
+                mount.precise_azm_alt()?
+            } else {
+                mount.azm_alt()?
+            };
+            writeln!(out, "Azm {}", angle_text(position.azm))?;
+            writeln!(out, "Alt {}", angle_text(position.alt))?;
+        }
+        Command::Goto(target) => {
+            match (target, precise) {
+                (target, true) => mount.goto(target)?,
+                (Target::RaDec(target), false) => mount.goto_ra_dec(target)?,
+                (Target::AzmAlt(target), false) => mount.goto_azm_alt(target)?,
+            }
+            match target {
+                Target::RaDec(target) => writeln!(
+                    out,
+                    "GOTO started to RA {}, Dec {}",
+                    ra_text(target.ra),
+                    angle_text(target.dec)
+                )?,
+                Target::AzmAlt(target) => writeln!(
+                    out,
+                    "GOTO started to Azm {}, Alt {}",
+                    angle_text(target.azm),
+                    angle_text(target.alt)
+                )?,
+            }
+        }
+        Command::SetTime {
+            zone,
+            daylight_saving,
+        } => {
+            let datetime = host_datetime(zone, daylight_saving)?;
+            mount.set_datetime(datetime)?;
+            writeln!(out, "Time set to {}", datetime_text(datetime))?;
+        }
+        Command::SetLocation(location) => {
+            mount.set_location(location)?;
+            writeln!(out, "Location set to {}", location)?;
+        }
+        Command::Slew { azm: 0, alt: 0 } => {
+            mount.stop_tracking_rates()?;
+            writeln!(out, "Motors stopped")?;
+        }
+        Command::Slew { azm, alt } => {
+            mount.track_rates(azm, alt)?;
+            writeln!(out, "Slewing AZM/RA at {}\"/s, ALT/DEC at {}\"/s", azm, alt)?;
+        }
+        Command::Track(mode) => {
+            mount.set_tracking_mode(mode)?;
+            writeln!(out, "Tracking set to {}", mode)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn model_name(model: Model) -> String {
+    let name = match model {
+        Model::GPSSeries => "GPS Series",
+        Model::ISeries => "i-Series",
+        Model::ISeriesSE => "i-Series SE",
+        Model::CGE => "CGE",
+        Model::AdvancedGT => "Advanced GT",
+        Model::SLT => "SLT",
+        Model::CPC => "CPC",
+        Model::GT => "GT",
+        Model::Se4_5 => "4/5 SE",
+        Model::Se6_8 => "6/8 SE",
+        #[cfg(feature = "synscan")]
+        Model::Eq6 => "EQ6",
+        #[cfg(feature = "synscan")]
+        Model::Heq5 => "HEQ5",
+        #[cfg(feature = "synscan")]
+        Model::Eq5 => "EQ5",
+        #[cfg(feature = "synscan")]
+        Model::Eq3 => "EQ3",
+        #[cfg(feature = "synscan")]
+        Model::Eq8 => "EQ8",
+        #[cfg(feature = "synscan")]
+        Model::AzEq6 => "AZ-EQ6",
+        #[cfg(feature = "synscan")]
+        Model::AzEq5 => "AZ-EQ5",
+        #[cfg(feature = "synscan")]
+        Model::AzGoto => "AZ GOTO",
+        #[cfg(feature = "synscan")]
+        Model::DobGoto => "Dobsonian GOTO",
+        #[cfg(feature = "synscan")]
+        Model::AllView => "AllView",
+        Model::Unknown(bits) => return format!("unknown (0x{:02X})", bits),
+    };
+    name.to_owned()
+}
+
+fn version(version: Option<Version>) -> String {
+    match version {
+        Some(version) => format!("{}.{}", version.major, version.minor),
+        None => "not present".to_owned(),
+    }
+}
+
+/// Splits an absolute value into whole units, minutes and tenths of seconds.
+fn sexagesimal(value: f32) -> (u32, u32, u32) {
+    let tenths = (value.abs() as f64 * 36_000.0).round() as u32;
+    (tenths / 36_000, tenths / 600 % 60, tenths % 600)
+}
+
+/// Formats an RA in degrees as hours, minutes and seconds.
+fn ra_text(ra: f32) -> String {
+    let (hours, minutes, tenths) = sexagesimal(ra / 15.0);
+    format!(
+        "{:02}h{:02}m{:02}.{}s ({:.4}°)",
+        hours % 24,
+        minutes,
+        tenths / 10,
+        tenths % 10,
+        ra
+    )
+}
+
+/// Formats an angle in degrees as degrees, minutes and seconds.
+fn angle_text(angle: f32) -> String {
+    let (degrees, minutes, tenths) = sexagesimal(angle);
+    format!(
+        "{}{:02}°{:02}'{:02}.{}\" ({:.4}°)",
+        if angle < 0.0 { "-" } else { "" },
+        degrees,
+        minutes,
+        tenths / 10,
+        tenths % 10,
+        angle
+    )
+}
+
+fn datetime_text(datetime: DateTime) -> String {
+    format!(
+        "20{:02}-{:02}-{:02} {:02}:{:02}:{:02} UTC{:+}{}",
+        datetime.year,
+        datetime.month,
+        datetime.day,
+        datetime.hour,
+        datetime.minutes,
+        datetime.seconds,
+        datetime.zone,
+        if datetime.daylight_saving {
+            " (DST)"
+        } else {
+            ""
+        }
+    )
+}
+
+/// Gets the local time of the zone from the host clock.
+fn host_datetime(zone: i8, daylight_saving: bool) -> Result<DateTime, CliError> {
+    let unix = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|_| CliError::Mount("host clock is before 1970".to_owned()))?
+        .as_secs() as i64;
+    let local = unix + (zone as i64 + daylight_saving as i64) * 3600;
+
+    let (year, month, day) = civil_from_days(local.div_euclid(86_400));
+    let seconds = local.rem_euclid(86_400) as u32;
+    if !(2000..2256).contains(&year) {
+        return Err(CliError::Mount(format!(
+            "the HC can't store the year {}",
+            year
+        )));
+    }
+
+    Ok(DateTime {
+        hour: (seconds / 3600) as u8,
+        minutes: (seconds / 60 % 60) as u8,
+        seconds: (seconds % 60) as u8,
+        zone,
+        daylight_saving,
+        year: (year - 2000) as u8,
+        month,
+        day,
+    })
+}
+
+/// Converts days since 1970-01-01 into the proleptic Gregorian date.
+fn civil_from_days(days: i64) -> (i64, u8, u8) {
+    // shifted to start the year in March, so the leap day is the last one
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month + 2) / 5 + 1) as u8;
+    let month = if month < 10 { month + 3 } else { month - 9 } as u8;
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn civil_dates() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(10_957), (2000, 1, 1));
+        // leap day
+        assert_eq!(civil_from_days(19_782), (2024, 2, 29));
+        assert_eq!(civil_from_days(20_740), (2026, 10, 14));
+    }
+
+    #[test]
+    fn sexagesimal_carry() {
+        assert_eq!(sexagesimal(10.999_999), (11, 0, 0));
+        assert_eq!(sexagesimal(-5.39), (5, 23, 240));
+    }
+}
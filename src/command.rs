@@ -0,0 +1,690 @@
+use crate::types::{Device, Version};
+
+/// Sub Device Commands
+///
+/// The numbers are only unique per kind of device, e.g. 0x01 is the position
+/// of a motor and the latitude of a GPS unit.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Command {
+    /// Motor controller: variable slew or guide rate in the positive direction
+    SetPositiveVariableRate,
+    /// Motor controller: variable slew or guide rate in the negative direction
+    SetNegativeVariableRate,
+    GetDeviceVersion,
+    /// Motor controller: position as a 24 bit fraction of a revolution
+    GetPosition,
+    /// Motor controller: overwrites the position, see
+    /// [`NexStar::set_motor_position`](crate::NexStar::set_motor_position)
+    SetPosition,
+    /// Motor controller: identifies the board variant
+    GetMotorModel,
+    /// Motor controller: drives the axis to its level switch
+    LevelStart,
+    /// Motor controller: non-zero once the level switch was found
+    LevelDone,
+    /// Motor controller: non-zero once the index switch was found
+    AtIndex,
+    /// Motor controller: drives the axis to its index switch
+    SeekIndex,
+    /// Motor controller: maximum GOTO slew rate in quarter arcsec/s
+    SetMaxRate,
+    /// Motor controller: gets the maximum GOTO slew rate
+    GetMaxRate,
+    /// Motor controller: enables or disables the maximum GOTO slew rate
+    EnableMaxRate,
+    /// Motor controller: backlash correction when reversing into the
+    /// positive direction (0..=99)
+    SetPositiveBacklash,
+    /// Motor controller: backlash correction when reversing into the
+    /// negative direction (0..=99)
+    SetNegativeBacklash,
+    /// Motor controller: gets the positive backlash correction
+    GetPositiveBacklash,
+    /// Motor controller: gets the negative backlash correction
+    GetNegativeBacklash,
+    /// Motor controller and focuser: non-zero once a GOTO finished
+    SlewDone,
+    /// Focuser: starts the calibration of the travel limits
+    FocuserCalibrate,
+    /// Focuser: non-zero once the calibration finished, and its state
+    FocuserCalibrationDone,
+    /// Focuser: the travel limits, each a big endian `u32`
+    FocuserLimits,
+    /// Motor controller: autoguide rate in 1/256 of the sidereal rate
+    SetAutoguideRate,
+    /// Motor controller: gets the autoguide rate
+    GetAutoguideRate,
+    /// Motor controller: timed guide pulse, the signed rate in percent of the
+    /// sidereal rate and the duration in 10 ms
+    GuidePulse,
+    /// Motor controller: non-zero while a guide pulse runs
+    IsGuiding,
+    /// GPS unit: latitude as a 24 bit fraction of a revolution
+    GpsGetLatitude,
+    /// GPS unit: longitude as a 24 bit fraction of a revolution
+    GpsGetLongitude,
+    /// GPS unit and RTC: month and day (UTC)
+    GpsGetDate,
+    /// GPS unit and RTC: year, high byte first
+    GpsGetYear,
+    /// GPS unit and RTC: hour, minutes and seconds (UTC)
+    GpsGetTime,
+    /// GPS unit: non-zero once it has a fix
+    GpsIsLinked,
+    /// WiFi module: restarts the module, which may reset before the ack
+    WiFiReboot,
+    /// Battery: charging state, level and the voltage in µV, high byte first
+    BatteryVoltage,
+}
+
+impl Command {
+    pub(crate) const fn bits(&self) -> u8 {
+        match self {
+            Command::SetPositiveVariableRate => 0x06,
+            Command::SetNegativeVariableRate => 0x07,
+            Command::GetDeviceVersion => 0xFE,
+            Command::GetPosition => 0x01,
+            Command::SetPosition => 0x04,
+            Command::GetMotorModel => 0x05,
+            Command::LevelStart => 0x0B,
+            Command::LevelDone => 0x12,
+            Command::AtIndex => 0x18,
+            Command::SeekIndex => 0x19,
+            Command::SetMaxRate => 0x20,
+            Command::GetMaxRate => 0x21,
+            Command::EnableMaxRate => 0x22,
+            Command::SetPositiveBacklash => 0x10,
+            Command::SetNegativeBacklash => 0x11,
+            Command::GetPositiveBacklash => 0x40,
+            Command::GetNegativeBacklash => 0x41,
+            Command::SlewDone => 0x13,
+            Command::FocuserCalibrate => 0x2A,
+            Command::FocuserCalibrationDone => 0x2B,
+            Command::FocuserLimits => 0x2C,
+            Command::SetAutoguideRate => 0x46,
+            Command::GetAutoguideRate => 0x47,
+            Command::GuidePulse => 0x26,
+            Command::IsGuiding => 0x27,
+            Command::GpsGetLatitude => 0x01,
+            Command::GpsGetLongitude => 0x02,
+            Command::GpsGetDate => 0x03,
+            Command::GpsGetYear => 0x04,
+            Command::GpsGetTime => 0x33,
+            Command::GpsIsLinked => 0x37,
+            Command::WiFiReboot => 0x49,
+            Command::BatteryVoltage => 0x10,
+        }
+    }
+
+    /// Looks up the command of a sub device, whose numbers are only unique
+    /// per kind of device.
+    pub(crate) fn from_bits(device: Device, bits: u8) -> Option<Command> {
+        use Command::*;
+
+        let command = match (device, bits) {
+            (_, 0xFE) => GetDeviceVersion,
+            (Device::AzmRaMotor | Device::AltDecMotor, bits) => match bits {
+                0x01 => GetPosition,
+                0x04 => SetPosition,
+                0x05 => GetMotorModel,
+                0x06 => SetPositiveVariableRate,
+                0x07 => SetNegativeVariableRate,
+                0x0B => LevelStart,
+                0x10 => SetPositiveBacklash,
+                0x11 => SetNegativeBacklash,
+                0x12 => LevelDone,
+                0x13 => SlewDone,
+                0x18 => AtIndex,
+                0x19 => SeekIndex,
+                0x20 => SetMaxRate,
+                0x21 => GetMaxRate,
+                0x22 => EnableMaxRate,
+                0x26 => GuidePulse,
+                0x27 => IsGuiding,
+                0x40 => GetPositiveBacklash,
+                0x41 => GetNegativeBacklash,
+                0x46 => SetAutoguideRate,
+                0x47 => GetAutoguideRate,
+                _ => return None,
+            },
+            (Device::Focuser, bits) => match bits {
+                0x01 => GetPosition,
+                0x13 => SlewDone,
+                0x2A => FocuserCalibrate,
+                0x2B => FocuserCalibrationDone,
+                0x2C => FocuserLimits,
+                _ => return None,
+            },
+            (Device::GPSUnit, 0x01) => GpsGetLatitude,
+            (Device::GPSUnit, 0x02) => GpsGetLongitude,
+            (Device::GPSUnit, 0x37) => GpsIsLinked,
+            (Device::GPSUnit | Device::RTC, 0x03) => GpsGetDate,
+            (Device::GPSUnit | Device::RTC, 0x04) => GpsGetYear,
+            (Device::GPSUnit | Device::RTC, 0x33) => GpsGetTime,
+            (Device::WiFi, 0x49) => WiFiReboot,
+            (Device::Battery, 0x10) => BatteryVoltage,
+            _ => return None,
+        };
+        Some(command)
+    }
+
+    /// Gets the number of bytes the device answers the command with.
+    pub fn response_len(&self) -> u8 {
+        match self {
+            Command::SetPositiveVariableRate
+            | Command::SetNegativeVariableRate
+            | Command::LevelStart
+            | Command::SeekIndex
+            | Command::SetMaxRate
+            | Command::EnableMaxRate
+            | Command::SetPositiveBacklash
+            | Command::SetNegativeBacklash
+            | Command::SetAutoguideRate
+            | Command::GuidePulse
+            | Command::SetPosition
+            | Command::FocuserCalibrate
+            | Command::WiFiReboot => 0,
+            Command::GetMotorModel
+            | Command::LevelDone
+            | Command::AtIndex
+            | Command::GetPositiveBacklash
+            | Command::GetNegativeBacklash
+            | Command::GetAutoguideRate
+            | Command::SlewDone
+            | Command::IsGuiding
+            | Command::GpsIsLinked => 1,
+            Command::GetDeviceVersion
+            | Command::GetMaxRate
+            | Command::GpsGetDate
+            | Command::GpsGetYear
+            | Command::FocuserCalibrationDone => 2,
+            Command::GetPosition
+            | Command::GpsGetLatitude
+            | Command::GpsGetLongitude
+            | Command::GpsGetTime => 3,
+            Command::BatteryVoltage => 6,
+            Command::FocuserLimits => 8,
+        }
+    }
+}
+
+/// Builds the pass-through data of a variable rate slew in arcsec/s.
+#[cfg(feature = "float")]
+pub(crate) fn variable_rate(arcsec_per_sec: f32) -> (Command, [u8; 2]) {
+    let quarters = arcsec_per_sec * 4.0;
+    let quarters = if quarters < 0.0 {
+        quarters - 0.5
+    } else {
+        quarters + 0.5
+    };
+
+    variable_rate_quarters(quarters as i32)
+}
+
+/// Builds the pass-through data of a variable rate slew in quarter arcsec/s.
+///
+/// The motors expect the rate as a big endian `u16` with the direction
+/// selected by the command.
+pub(crate) fn variable_rate_quarters(quarters: i32) -> (Command, [u8; 2]) {
+    let cmd = if quarters < 0 {
+        Command::SetNegativeVariableRate
+    } else {
+        Command::SetPositiveVariableRate
+    };
+    // saturates at the maximum rate
+    let rate = quarters.unsigned_abs().min(u16::MAX as u32) as u16;
+
+    (cmd, rate.to_be_bytes())
+}
+
+/// Builds the pass-through data of a guide rate in arcsec/s for controllers
+/// taking it in 1/1024 arcsec/s.
+///
+/// The motors expect the rate as a big endian 24 bit value with the direction
+/// selected by the command, like a variable rate slew.
+#[cfg(feature = "float")]
+pub(crate) fn fine_guiderate(arcsec_per_sec: f32) -> (Command, [u8; 3]) {
+    let cmd = if arcsec_per_sec < 0.0 {
+        Command::SetNegativeVariableRate
+    } else {
+        Command::SetPositiveVariableRate
+    };
+    // saturates at the maximum rate
+    let rate = (arcsec_per_sec.abs() as f64 * 1024.0 + 0.5).min(0xFF_FFFF as f64) as u32;
+
+    (cmd, position_bytes(rate))
+}
+
+/// Highest maximum slew rate in arcsec/s, the quarter arcsec/s of faster rates
+/// don't fit 16 bits
+pub(crate) const MAX_SLEW_RATE: u32 = 16_383;
+
+/// Builds the pass-through data of a maximum slew rate in arcsec/s, `None`
+/// outside `1..=MAX_SLEW_RATE`.
+pub(crate) fn encode_max_rate(arcsec_per_sec: u32) -> Option<[u8; 2]> {
+    if !(1..=MAX_SLEW_RATE).contains(&arcsec_per_sec) {
+        return None;
+    }
+
+    // in the encoding of the variable rate commands
+    Some((arcsec_per_sec as u16 * 4).to_be_bytes())
+}
+
+/// Decodes a maximum slew rate into arcsec/s, rounded to the nearest one.
+pub(crate) fn decode_max_rate(rate: [u8; 2]) -> u32 {
+    (u16::from_be_bytes(rate) as u32 + 2) / 4
+}
+
+/// Highest autoguide rate in percent of the sidereal rate
+pub(crate) const MAX_AUTOGUIDE_RATE: u8 = 99;
+
+/// Encodes an autoguide rate in percent of the sidereal rate into 1/256 of
+/// it, `None` above [`MAX_AUTOGUIDE_RATE`].
+pub(crate) fn encode_autoguide_rate(percent: u8) -> Option<u8> {
+    if percent > MAX_AUTOGUIDE_RATE {
+        return None;
+    }
+    Some((percent as u16 * 256 / 100) as u8)
+}
+
+/// Highest rate of a timed guide pulse in percent of the sidereal rate
+pub(crate) const MAX_GUIDE_PULSE_RATE: u8 = 100;
+
+/// Decodes an autoguide rate into percent of the sidereal rate, rounded to the
+/// nearest one.
+pub(crate) fn decode_autoguide_rate(rate: u8) -> u8 {
+    ((rate as u16 * 100 + 128) / 256) as u8
+}
+
+// Minimum HC firmware versions of the commands which aren't available on every
+// hand controller.
+pub(crate) const GOTO_VERSION: Version = Version::new(1, 2);
+pub(crate) const PRECISE_GOTO_VERSION: Version = Version::new(1, 6);
+pub(crate) const PRECISE_AZM_ALT_VERSION: Version = Version::new(2, 2);
+pub(crate) const SYNC_VERSION: Version = Version::new(4, 10);
+pub(crate) const PRECISE_SYNC_VERSION: Version = Version::new(4, 10);
+pub(crate) const ECHO_VERSION: Version = Version::new(1, 2);
+pub(crate) const MODEL_VERSION: Version = Version::new(2, 2);
+/// Minimum motor controller firmware of timed guide pulses
+pub(crate) const GUIDE_PULSE_VERSION: Version = Version::new(7, 11);
+/// Earlier HCs answer the standard positions with 5 digits per axis
+pub(crate) const NARROW_POSITION_VERSION: Version = Version::new(1, 2);
+
+/// Bits per revolution of the 5 digit positions of legacy HCs
+pub(crate) const WIDE_POSITION_BITS: u32 = 20;
+
+/// Arcseconds of a full revolution
+pub(crate) const ARCSEC_PER_REVOLUTION: i32 = 1_296_000;
+
+/// Gets the bits per revolution of the standard (16) or precise (24) commands.
+pub(crate) fn resolution(precise: bool) -> u32 {
+    if precise {
+        24
+    } else {
+        16
+    }
+}
+
+/// Converts arcseconds, wrapped into one revolution, into the fraction of a
+/// revolution with `bits` bits, rounded to the nearest count.
+pub(crate) fn arcsec_to_fraction(arcsec: i32, bits: u32) -> u32 {
+    let revolution = ARCSEC_PER_REVOLUTION as i64;
+    let arcsec = arcsec.rem_euclid(ARCSEC_PER_REVOLUTION) as i64;
+    let fraction = (arcsec << bits) + revolution / 2;
+    (fraction / revolution) as u32 & ((1 << bits) - 1)
+}
+
+/// Gets the arcseconds a motor moved between two 24 bit positions, the
+/// shortest way around.
+#[cfg(feature = "float")]
+pub(crate) fn motor_delta(start: u32, end: u32) -> f64 {
+    let counts = (end.wrapping_sub(start) << 8) as i32 >> 8;
+    counts as f64 * ARCSEC_PER_REVOLUTION as f64 / (1 << 24) as f64
+}
+
+/// Splits the 24 bit fraction of a motor position into its bytes, high byte
+/// first, the encoding of the position commands of the motor controllers.
+pub(crate) fn position_bytes(fraction: u32) -> [u8; 3] {
+    let [_, high, mid, low] = fraction.to_be_bytes();
+    [high, mid, low]
+}
+
+/// Converts the fraction of a revolution with `bits` bits into arcseconds,
+/// rounded to the nearest arcsecond, in 0..1_296_000 or if `signed` in
+/// -648_000..=648_000.
+pub(crate) fn fraction_to_arcsec(fraction: u32, bits: u32, signed: bool) -> i32 {
+    let arcsec = (fraction as i64 * ARCSEC_PER_REVOLUTION as i64 + (1 << (bits - 1))) >> bits;
+    // the last counts round up to a full revolution
+    let arcsec = (arcsec % ARCSEC_PER_REVOLUTION as i64) as i32;
+
+    if signed && arcsec > ARCSEC_PER_REVOLUTION / 2 {
+        arcsec - ARCSEC_PER_REVOLUTION
+    } else {
+        arcsec
+    }
+}
+
+/// Converts degrees into the fraction of a revolution with `bits` bits.
+#[cfg(feature = "float")]
+pub(crate) fn degrees_to_fraction(value: f32, bits: u32) -> u32 {
+    // f64 keeps the wrapped negative angles exact to the last count
+    let value = value as f64 % 360.0;
+    let value = if value < 0.0 { value + 360.0 } else { value };
+
+    // rounded to the nearest count, truncating would move a decoded position
+    // down by one count when it is sent back
+    let counts = (1u64 << bits) as f64;
+    (value / 360.0 * counts + 0.5) as u32 & ((1 << bits) - 1)
+}
+
+/// Converts the fraction of a revolution with `bits` bits into degrees, in
+/// 0..360 or if `signed` in -180..=180.
+#[cfg(feature = "float")]
+pub(crate) fn fraction_to_degrees(fraction: u32, bits: u32, signed: bool) -> f32 {
+    let value = fraction as f64 / (1u64 << bits) as f64 * 360.0;
+
+    if signed && value > 180.0 {
+        (value - 360.0) as f32
+    } else {
+        value as f32
+    }
+}
+
+/// Encodes the counts of a revolution as hex digits, 4 digits or if `precise`
+/// 8 digits with the 24 bit counts in the upper bits.
+pub(crate) fn encode_counts(counts: u32, precise: bool, out: &mut [u8; 8]) -> &[u8] {
+    let (head, _) = out.split_at_mut(if precise { 8 } else { 4 });
+    if precise {
+        // the HCs only use the upper 24 bits
+        encode_hex((counts & 0x00FF_FFFF) << 8, head);
+    } else {
+        encode_hex(counts & 0xFFFF, head);
+    }
+    head
+}
+
+/// Decodes 4, 5 or 8 hex digits into the counts of a revolution and the bits
+/// per revolution; the lowest byte of the precise format is ignored.
+pub(crate) fn decode_counts(hex: &[u8]) -> Option<(u32, u32)> {
+    let fraction = decode_hex(hex)?;
+    match hex.len() {
+        4 => Some((fraction, 16)),
+        5 => Some((fraction, WIDE_POSITION_BITS)),
+        8 => Some((fraction >> 8, 24)),
+        _ => None,
+    }
+}
+
+/// Encodes an angle in degrees as hex digits, the driver itself sends
+/// [`Counts`](crate::protocol::Counts).
+#[cfg(any(feature = "fuzzing", feature = "simulator"))]
+pub(crate) fn encode_fraction(value: f32, precise: bool, out: &mut [u8; 8]) -> &[u8] {
+    encode_counts(
+        degrees_to_fraction(value, resolution(precise)),
+        precise,
+        out,
+    )
+}
+
+#[cfg(any(feature = "fuzzing", feature = "simulator"))]
+pub(crate) fn decode_fraction(hex: &[u8], signed: bool) -> Option<f32> {
+    let (fraction, bits) = decode_counts(hex)?;
+    Some(fraction_to_degrees(fraction, bits, signed))
+}
+
+pub(crate) fn encode_hex(value: u32, out: &mut [u8]) {
+    const DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+
+    for (idx, digit) in out.iter_mut().rev().enumerate() {
+        *digit = DIGITS[(value >> (idx * 4)) as usize & 0x0F];
+    }
+}
+
+pub(crate) fn decode_hex(hex: &[u8]) -> Option<u32> {
+    hex.iter().try_fold(0u32, |value, digit| {
+        let nibble = match digit {
+            b'0'..=b'9' => digit - b'0',
+            b'A'..=b'F' => digit - b'A' + 10,
+            b'a'..=b'f' => digit - b'a' + 10,
+            _ => return None,
+        };
+        Some(value << 4 | nibble as u32)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode_arcsec(arcsec: i32, precise: bool, out: &mut [u8; 8]) -> &[u8] {
+        encode_counts(
+            arcsec_to_fraction(arcsec, resolution(precise)),
+            precise,
+            out,
+        )
+    }
+
+    fn decode_arcsec(hex: &[u8], signed: bool) -> Option<i32> {
+        let (fraction, bits) = decode_counts(hex)?;
+        Some(fraction_to_arcsec(fraction, bits, signed))
+    }
+
+    #[cfg(feature = "float")]
+    fn encode_fraction(value: f32, precise: bool, out: &mut [u8; 8]) -> &[u8] {
+        encode_counts(
+            degrees_to_fraction(value, resolution(precise)),
+            precise,
+            out,
+        )
+    }
+
+    #[cfg(feature = "float")]
+    fn decode_fraction(hex: &[u8], signed: bool) -> Option<f32> {
+        let (fraction, bits) = decode_counts(hex)?;
+        Some(fraction_to_degrees(fraction, bits, signed))
+    }
+
+    #[test]
+    fn hex_roundtrip() {
+        let mut out = [0u8; 8];
+        encode_hex(0x12AB_CD00, &mut out);
+        assert_eq!(&out, b"12ABCD00");
+        assert_eq!(decode_hex(&out), Some(0x12AB_CD00));
+        assert_eq!(decode_hex(b"12abcd00"), Some(0x12AB_CD00));
+        assert_eq!(decode_hex(b"12G4"), None);
+    }
+
+    #[test]
+    fn arcsec_encoding() {
+        let mut out = [0u8; 8];
+        assert_eq!(encode_arcsec(324_000, false, &mut out), b"4000");
+        assert_eq!(encode_arcsec(-324_000, false, &mut out), b"C000");
+        assert_eq!(encode_arcsec(648_000, true, &mut out), b"80000000");
+        assert_eq!(encode_arcsec(1_296_000, true, &mut out), b"00000000");
+        // 1" is 12.9 counts of 24 bit and 0.05 counts of 16 bit
+        assert_eq!(encode_arcsec(1, true, &mut out), b"00000D00");
+        assert_eq!(encode_arcsec(1, false, &mut out), b"0000");
+        assert_eq!(encode_arcsec(-1, false, &mut out), b"0000");
+    }
+
+    #[test]
+    fn arcsec_decoding() {
+        assert_eq!(decode_arcsec(b"4000", false), Some(324_000));
+        assert_eq!(decode_arcsec(b"C000", true), Some(-324_000));
+        assert_eq!(decode_arcsec(b"80000000", true), Some(648_000));
+        assert_eq!(decode_arcsec(b"400000FF", true), Some(324_000));
+        // the last counts round up to a full revolution
+        assert_eq!(decode_arcsec(b"FFFFFF00", false), Some(0));
+        assert_eq!(decode_arcsec(b"FFFFFF00", true), Some(0));
+        assert_eq!(decode_arcsec(b"400", true), None);
+    }
+
+    #[test]
+    fn arcsec_reencoding() {
+        let mut out = [0u8; 8];
+        for arcsec in (0..ARCSEC_PER_REVOLUTION).step_by(7) {
+            let hex = encode_arcsec(arcsec, true, &mut out);
+            assert_eq!(decode_arcsec(hex, false), Some(arcsec));
+        }
+    }
+
+    #[cfg(feature = "float")]
+    #[test]
+    fn fraction_encoding() {
+        let mut out = [0u8; 8];
+        assert_eq!(encode_fraction(90.0, false, &mut out), b"4000");
+        assert_eq!(encode_fraction(-90.0, false, &mut out), b"C000");
+        assert_eq!(encode_fraction(180.0, true, &mut out), b"80000000");
+        assert_eq!(encode_fraction(360.0, true, &mut out), b"00000000");
+    }
+
+    #[cfg(feature = "float")]
+    #[test]
+    fn fraction_decoding() {
+        assert_eq!(decode_fraction(b"4000", false), Some(90.0));
+        assert_eq!(decode_fraction(b"C000", false), Some(270.0));
+        assert_eq!(decode_fraction(b"C000", true), Some(-90.0));
+        // the lowest byte of the precise format is ignored
+        assert_eq!(decode_fraction(b"400000FF", true), Some(90.0));
+        assert_eq!(decode_fraction(b"400", true), None);
+    }
+
+    #[cfg(feature = "float")]
+    #[test]
+    fn fraction_reencoding() {
+        // found by the position fuzz target: truncating lost one count
+        let mut out = [0u8; 8];
+        let value = decode_fraction(b"05B06900", false).unwrap();
+        assert_eq!(encode_fraction(value, true, &mut out), b"05B06900");
+        let value = decode_fraction(b"FFFF", false).unwrap();
+        assert_eq!(encode_fraction(value, false, &mut out), b"FFFF");
+
+        // f32 can't hold every 24 bit position above 256°
+        let value = decode_fraction(b"B60B6700", false).unwrap();
+        let fraction = decode_hex(encode_fraction(value, true, &mut out)).unwrap();
+        assert!((fraction >> 8).abs_diff(0xB6_0B67) <= 1);
+    }
+
+    #[test]
+    fn max_rate_roundtrip() {
+        for rate in 1..=MAX_SLEW_RATE {
+            assert_eq!(encode_max_rate(rate).map(decode_max_rate), Some(rate));
+        }
+        assert_eq!(encode_max_rate(0), None);
+        assert_eq!(encode_max_rate(MAX_SLEW_RATE + 1), None);
+        assert_eq!(decode_max_rate([0xff, 0xff]), MAX_SLEW_RATE + 1);
+    }
+
+    #[test]
+    fn command_lookup() {
+        let motor = Device::AltDecMotor;
+        assert_eq!(
+            Command::from_bits(motor, 0x47),
+            Some(Command::GetAutoguideRate)
+        );
+        assert_eq!(
+            Command::from_bits(Device::GPSUnit, 0x01),
+            Some(Command::GpsGetLatitude)
+        );
+        assert_eq!(
+            Command::from_bits(Device::RTC, 0x33),
+            Some(Command::GpsGetTime)
+        );
+        assert_eq!(
+            Command::from_bits(Device::RTC, 0xFE),
+            Some(Command::GetDeviceVersion)
+        );
+        // the GPS commands mean something else to a motor controller
+        assert_eq!(Command::from_bits(motor, 0x01), Some(Command::GetPosition));
+        assert_eq!(Command::from_bits(motor, 0x04), Some(Command::SetPosition));
+        assert_eq!(Command::from_bits(motor, 0x33), None);
+        assert_eq!(Command::from_bits(Device::RTC, 0x01), None);
+        assert_eq!(Command::from_bits(Device::MainBoard, 0x05), None);
+        assert_eq!(
+            Command::from_bits(Device::Focuser, 0x2B),
+            Some(Command::FocuserCalibrationDone)
+        );
+        assert_eq!(Command::from_bits(Device::Focuser, 0x06), None);
+        assert_eq!(
+            Command::from_bits(Device::WiFi, 0x49),
+            Some(Command::WiFiReboot)
+        );
+        assert_eq!(Command::from_bits(motor, 0x49), None);
+        assert_eq!(
+            Command::from_bits(Device::Battery, 0x10),
+            Some(Command::BatteryVoltage)
+        );
+
+        for (device, bits) in [(motor, 0x21), (Device::GPSUnit, 0x33), (motor, 0x01)] {
+            let command = Command::from_bits(device, bits).unwrap();
+            assert_eq!(command.bits(), bits);
+        }
+        assert_eq!(Command::GetMaxRate.response_len(), 2);
+        assert_eq!(Command::GpsGetTime.response_len(), 3);
+    }
+
+    #[test]
+    fn autoguide_rate_roundtrip() {
+        for percent in 0..=MAX_AUTOGUIDE_RATE {
+            let rate = encode_autoguide_rate(percent);
+            assert_eq!(rate.map(decode_autoguide_rate), Some(percent));
+        }
+        assert_eq!(encode_autoguide_rate(50), Some(128));
+        assert_eq!(encode_autoguide_rate(100), None);
+        assert_eq!(decode_autoguide_rate(0xff), 100);
+    }
+
+    #[cfg(feature = "float")]
+    #[test]
+    fn variable_rate_rounding() {
+        let (cmd, rate) = variable_rate(15.041);
+        assert_eq!(cmd.bits(), 0x06);
+        assert_eq!(u16::from_be_bytes(rate), 60);
+
+        let (cmd, rate) = variable_rate(-1.0);
+        assert_eq!(cmd.bits(), 0x07);
+        assert_eq!(u16::from_be_bytes(rate), 4);
+
+        let (_, rate) = variable_rate_quarters(1 << 20);
+        assert_eq!(u16::from_be_bytes(rate), u16::MAX);
+    }
+
+    #[cfg(feature = "float")]
+    #[test]
+    fn named_tracking_rate_encoding() {
+        use crate::TrackingRate;
+
+        // a sidereal day lasts 86164.0905 s, the lunar rate is the ASCOM one
+        let sidereal = (1_296_000.0 / 86_164.090_5_f64) as f32;
+        assert!((TrackingRate::SIDEREAL - sidereal).abs() < 1e-5);
+        assert_eq!(TrackingRate::LUNAR, 14.685);
+        assert_eq!(TrackingRate::SOLAR, 1_296_000.0 / 86_400.0);
+
+        for (rate, quarters) in [
+            (TrackingRate::Sidereal, [0x00, 0x3C]),
+            (TrackingRate::Lunar, [0x00, 0x3B]),
+            (TrackingRate::Solar, [0x00, 0x3C]),
+            (TrackingRate::Custom(-0.1), [0x00, 0x00]),
+            (TrackingRate::Custom(-0.2), [0x00, 0x01]),
+        ] {
+            assert_eq!(variable_rate(rate.arcsec_per_sec()).1, quarters);
+        }
+        assert_eq!(
+            variable_rate(TrackingRate::Custom(-0.2).arcsec_per_sec()).0,
+            Command::SetNegativeVariableRate
+        );
+    }
+
+    #[cfg(feature = "float")]
+    #[test]
+    fn fine_guiderate_rounding() {
+        let (cmd, rate) = fine_guiderate(15.041_067);
+        assert_eq!((cmd.bits(), rate), (0x06, [0x00, 0x3C, 0x2A]));
+
+        let (cmd, rate) = fine_guiderate(-1.0);
+        assert_eq!((cmd.bits(), rate), (0x07, [0x00, 0x04, 0x00]));
+
+        let (_, rate) = fine_guiderate(20_000.0);
+        assert_eq!(rate, [0xFF; 3]);
+    }
+}
@@ -0,0 +1,48 @@
+//! Settings the Hand Controller (HC) loses with its battery or a factory
+//! reset
+//!
+//! [`NexStar::snapshot`](crate::NexStar::snapshot) reads them into a
+//! [`MountConfig`] and [`NexStar::restore`](crate::NexStar::restore) writes
+//! them back. With the `serde` feature the config can be persisted by the
+//! application, e.g. as JSON.
+
+use crate::{Backlash, Error, Location, TrackingMode};
+
+/// Settings of the mount, `None` where the mount couldn't report them
+///
+/// The per motor settings are those of the AZM/RA and the ALT/DEC motor.
+#[derive(Copy, Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MountConfig {
+    pub location: Option<Location>,
+    /// Standard time zone and daylight saving of the HC time, see
+    /// [`DateTime`](crate::DateTime)
+    pub time_zone: Option<(i8, bool)>,
+    pub tracking_mode: Option<TrackingMode>,
+    /// Autoguide rates in percent of the sidereal rate
+    pub autoguide_rates: [Option<u8>; 2],
+    pub backlash: [Option<Backlash>; 2],
+}
+
+/// Outcome of [`NexStar::restore`](crate::NexStar::restore) per setting,
+/// settings missing in the [`MountConfig`] are `Ok`
+#[derive(Debug)]
+pub struct RestoreReport<T, U> {
+    pub location: Result<(), Error<T, U>>,
+    /// The date and time, written with the time zone of the config
+    pub datetime: Result<(), Error<T, U>>,
+    pub tracking_mode: Result<(), Error<T, U>>,
+    pub autoguide_rates: [Result<(), Error<T, U>>; 2],
+    pub backlash: [Result<(), Error<T, U>>; 2],
+}
+
+impl<T, U> RestoreReport<T, U> {
+    /// Gets whether every setting was written.
+    pub fn is_ok(&self) -> bool {
+        self.location.is_ok()
+            && self.datetime.is_ok()
+            && self.tracking_mode.is_ok()
+            && self.autoguide_rates.iter().all(Result::is_ok)
+            && self.backlash.iter().all(Result::is_ok)
+    }
+}
@@ -0,0 +1,242 @@
+//! Detection of the dialect spoken on a serial port
+//!
+//! The PC/USB port of some mounts speaks the ASCII protocol of the Hand
+//! Controller (HC) when one is plugged in and raw [AUX frames](crate::aux)
+//! otherwise. [`detect_protocol`] asks in both dialects, so one application
+//! can pick [`NexStar`](crate::NexStar) or [`AuxBus`](crate::aux::AuxBus) for
+//! either wiring:
+//!
+//! ```ignore
+//! match detect_protocol(&mut rx, &mut tx, &mut delay)? {
+//!     ProtocolKind::Hc => run_hc(NexStar::new(rx, tx)),
+//!     ProtocolKind::Aux => run_aux(AuxBus::new(rx, tx)),
+//!     ProtocolKind::Neither => println!("no mount"),
+//! }
+//! ```
+//!
+//! The direct AUX ports run at 19200 or 115200 baud depending on the
+//! hardware, the HC at 9600. [`detect_baud`] reopens the port at each
+//! candidate rate through a closure, as embedded-hal can't change the rate;
+//! HALs which switch the rate of an open port call [`detect_protocol`] after
+//! each switch instead.
+
+use embedded_hal::blocking::delay::DelayMs;
+use embedded_hal::serial;
+use nb::block;
+
+use crate::aux::{FrameError, Packet, MAX_DATA, MAX_FRAME, PC_ADDRESS, PREAMBLE};
+use crate::{Device, Error};
+
+/// Time to wait for the answer to each probe
+pub const PROBE_TIMEOUT_MS: u32 = 200;
+
+/// Interval of the reads while waiting
+const POLL_INTERVAL_MS: u32 = 1;
+/// Time without a byte after which the port counts as drained
+const DRAIN_QUIET_MS: u32 = 20;
+/// Bytes read at most while draining or waiting for an answer, so a port
+/// spewing garbage can't stall the detection
+const MAX_GARBAGE: usize = 256;
+/// Marker of the HC echo probe
+const ECHO_MARKER: u8 = b'?';
+
+/// Dialect which answered [`detect_protocol`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ProtocolKind {
+    /// ASCII protocol of the Hand Controller (HC)
+    Hc,
+    /// Raw AUX bus frames
+    Aux,
+    /// Nothing answered
+    Neither,
+}
+
+/// Port which answered [`detect_baud`]
+pub struct Detected<T, U> {
+    pub baud: u32,
+    pub protocol: ProtocolKind,
+    pub rx: T,
+    pub tx: U,
+}
+
+/// Opens the port at each of the `candidates` baud rates in turn and returns
+/// the first one answering [`detect_protocol`], `None` if none did.
+///
+/// `open` gets the rate and returns both halves of the port, its errors are
+/// returned right away. Read and write errors while probing, e.g. framing
+/// errors at a wrong rate, count as no answer.
+pub fn detect_baud<T, U, D, E, F>(
+    mut open: F,
+    candidates: &[u32],
+    delay: &mut D,
+) -> Result<Option<Detected<T, U>>, E>
+where
+    T: serial::Read<u8>,
+    U: serial::Write<u8>,
+    D: DelayMs<u32>,
+    F: FnMut(u32) -> Result<(T, U), E>,
+{
+    for &baud in candidates {
+        let (mut rx, mut tx) = open(baud)?;
+        match detect_protocol(&mut rx, &mut tx, delay) {
+            Ok(ProtocolKind::Neither) | Err(_) => {}
+            Ok(protocol) => {
+                return Ok(Some(Detected {
+                    baud,
+                    protocol,
+                    rx,
+                    tx,
+                }))
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Probes which dialect the port speaks.
+///
+/// Sends an HC echo command first and then an AUX version query to the
+/// AZM/RA motor, each after discarding whatever is pending and with
+/// [`PROBE_TIMEOUT_MS`] to answer. Only I/O errors fail, garbage counts as no
+/// answer.
+pub fn detect_protocol<T, U, D>(
+    rx: &mut T,
+    tx: &mut U,
+    delay: &mut D,
+) -> Result<ProtocolKind, Error<T::Error, U::Error>>
+where
+    T: serial::Read<u8>,
+    U: serial::Write<u8>,
+    D: DelayMs<u32>,
+{
+    drain(rx, delay)?;
+    write_all(tx, &[b'K', ECHO_MARKER])?;
+    if hc_answered(rx, delay)? {
+        return Ok(ProtocolKind::Hc);
+    }
+
+    drain(rx, delay)?;
+    let query = Packet::new(PC_ADDRESS, Device::AzmRaMotor.bits(), 0xFE, &[])
+        .ok_or(Error::InvalidFrame(FrameError::Length))?;
+    let mut frame = [0u8; MAX_FRAME];
+    let len = query.encode(&mut frame);
+    write_all(tx, &frame[..len])?;
+    if aux_answered(rx, delay)? {
+        return Ok(ProtocolKind::Aux);
+    }
+
+    Ok(ProtocolKind::Neither)
+}
+
+/// Waits for the echo, possibly after the local echo of the command.
+fn hc_answered<T, U, D>(rx: &mut T, delay: &mut D) -> Result<bool, Error<T::Error, U>>
+where
+    T: serial::Read<u8>,
+    D: DelayMs<u32>,
+{
+    let mut waited = 0;
+    let mut last = [0u8; 2];
+    for _ in 0..MAX_GARBAGE {
+        let byte = match read_within(rx, delay, &mut waited)? {
+            Some(byte) => byte,
+            None => break,
+        };
+        last = [last[1], byte];
+        if last == [ECHO_MARKER, b'#'] {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Waits for the version of the motor, skipping other frames like the echo
+/// of the query on the bus.
+fn aux_answered<T, U, D>(rx: &mut T, delay: &mut D) -> Result<bool, Error<T::Error, U>>
+where
+    T: serial::Read<u8>,
+    D: DelayMs<u32>,
+{
+    let mut waited = 0;
+    let mut frame = [0u8; MAX_FRAME];
+    let mut received = 0;
+    for _ in 0..MAX_GARBAGE {
+        let byte = match read_within(rx, delay, &mut waited)? {
+            Some(byte) => byte,
+            None => break,
+        };
+        if received == 0 && byte != PREAMBLE {
+            continue;
+        }
+        frame[received] = byte;
+        received += 1;
+
+        if received == 2 && !(3..=MAX_DATA as u8 + 3).contains(&byte) {
+            received = 0;
+        } else if received > 2 && received == frame[1] as usize + 3 {
+            received = 0;
+            if let Ok(packet) = Packet::decode(&frame[..frame[1] as usize + 3]) {
+                if packet.src == Device::AzmRaMotor.bits() && packet.dst == PC_ADDRESS {
+                    return Ok(true);
+                }
+            }
+        }
+    }
+    Ok(false)
+}
+
+/// Discards pending bytes until the port is quiet.
+fn drain<T, U, D>(rx: &mut T, delay: &mut D) -> Result<(), Error<T::Error, U>>
+where
+    T: serial::Read<u8>,
+    D: DelayMs<u32>,
+{
+    let mut quiet = 0;
+    let mut discarded = 0;
+    while quiet < DRAIN_QUIET_MS && discarded < MAX_GARBAGE {
+        match rx.read() {
+            Ok(_) => {
+                quiet = 0;
+                discarded += 1;
+            }
+            Err(nb::Error::WouldBlock) => {
+                delay.delay_ms(POLL_INTERVAL_MS);
+                quiet += POLL_INTERVAL_MS;
+            }
+            Err(nb::Error::Other(e)) => return Err(Error::Read(e)),
+        }
+    }
+    Ok(())
+}
+
+/// Reads the next byte, `None` once [`PROBE_TIMEOUT_MS`] passed in total.
+fn read_within<T, U, D>(
+    rx: &mut T,
+    delay: &mut D,
+    waited: &mut u32,
+) -> Result<Option<u8>, Error<T::Error, U>>
+where
+    T: serial::Read<u8>,
+    D: DelayMs<u32>,
+{
+    while *waited < PROBE_TIMEOUT_MS {
+        match rx.read() {
+            Ok(byte) => return Ok(Some(byte)),
+            Err(nb::Error::WouldBlock) => {
+                delay.delay_ms(POLL_INTERVAL_MS);
+                *waited += POLL_INTERVAL_MS;
+            }
+            Err(nb::Error::Other(e)) => return Err(Error::Read(e)),
+        }
+    }
+    Ok(None)
+}
+
+fn write_all<T, U>(tx: &mut U, bytes: &[u8]) -> Result<(), Error<T, U::Error>>
+where
+    U: serial::Write<u8>,
+{
+    for byte in bytes {
+        block!(tx.write(*byte)).map_err(Error::Write)?;
+    }
+    block!(tx.flush()).map_err(Error::Write)
+}
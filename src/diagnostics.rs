@@ -0,0 +1,223 @@
+//! Capture of the recent traffic for bug reports
+//!
+//! [`NexStar::set_diagnostics`](crate::NexStar::set_diagnostics) hands the
+//! driver a buffer, which it fills with a ring of the bytes sent and received
+//! and of the errors of failed commands, two bytes per record. Once the buffer
+//! is full the oldest records are overwritten, so a 1024 byte buffer holds
+//! about the last 256 bytes in each direction.
+//!
+//! [`NexStar::dump_diagnostics`](crate::NexStar::dump_diagnostics) renders the
+//! records in the transcript format of the `transcript` feature, oldest first,
+//! with the errors as comments:
+//!
+//! ```text
+//! > 50 01 11 40 00 00 00 01
+//! < 00 01 23
+//! # error: nack
+//! ```
+//!
+//! A dump can be replayed as a transcript, up to the first command whose
+//! response was cut off by the ring.
+
+const DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+
+/// Kind of a record
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Record {
+    Sent = b'>' as isize,
+    Received = b'<' as isize,
+    Error = b'!' as isize,
+}
+
+/// Error of a failed command, the value of an error record
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Event {
+    /// The mount rejected the command.
+    Nack = b'N' as isize,
+    /// The response was invalid for the command.
+    Unexpected = b'U' as isize,
+    /// The response stopped early.
+    Truncated = b'T' as isize,
+    Read = b'R' as isize,
+    Write = b'W' as isize,
+    EchoMismatch = b'E' as isize,
+}
+
+impl Event {
+    fn describe(bits: u8) -> &'static [u8] {
+        match bits {
+            b'N' => b"nack",
+            b'U' => b"unexpected response",
+            b'T' => b"truncated",
+            b'R' => b"read error",
+            b'W' => b"write error",
+            b'E' => b"echo mismatch",
+            _ => b"unknown",
+        }
+    }
+}
+
+/// Ring of records in a caller-provided buffer
+#[derive(Debug)]
+pub(crate) struct Diagnostics {
+    buffer: &'static mut [u8],
+    next: usize,
+    wrapped: bool,
+}
+
+impl Diagnostics {
+    pub(crate) fn new(buffer: &'static mut [u8]) -> Diagnostics {
+        Diagnostics {
+            buffer,
+            next: 0,
+            wrapped: false,
+        }
+    }
+
+    /// Gets the number of records the buffer holds.
+    fn capacity(&self) -> usize {
+        self.buffer.len() / 2
+    }
+
+    pub(crate) fn record(&mut self, record: Record, value: u8) {
+        if self.capacity() == 0 {
+            return;
+        }
+        self.buffer[self.next * 2] = record as u8;
+        self.buffer[self.next * 2 + 1] = value;
+        self.next += 1;
+        if self.next == self.capacity() {
+            self.next = 0;
+            self.wrapped = true;
+        }
+    }
+
+    pub(crate) fn record_all(&mut self, record: Record, bytes: &[u8]) {
+        for byte in bytes {
+            self.record(record, *byte);
+        }
+    }
+
+    /// Gets the records, oldest first.
+    fn records(&self) -> impl Iterator<Item = (u8, u8)> + '_ {
+        let (start, len) = match self.wrapped {
+            true => (self.next, self.capacity()),
+            false => (0, self.next),
+        };
+        (0..len).map(move |i| {
+            let index = (start + i) % self.capacity();
+            (self.buffer[index * 2], self.buffer[index * 2 + 1])
+        })
+    }
+
+    /// Renders the records into `out`, dropping the oldest ones which don't
+    /// fit, and returns the length of the dump.
+    pub(crate) fn dump(&self, out: &mut [u8]) -> usize {
+        let total = self.records().count();
+        let rendered = |skip: usize| render(self.records().skip(skip), &mut []);
+
+        // the fewest skipped records whose dump fits
+        let (mut low, mut high) = (0, total);
+        while low < high {
+            let mid = (low + high) / 2;
+            if rendered(mid) <= out.len() {
+                high = mid;
+            } else {
+                low = mid + 1;
+            }
+        }
+        render(self.records().skip(low), out)
+    }
+}
+
+/// Renders the records into `out` and returns the length of the complete
+/// dump, the bytes beyond `out` are only counted.
+fn render(records: impl Iterator<Item = (u8, u8)>, out: &mut [u8]) -> usize {
+    let mut len = 0;
+    let mut write = |bytes: &[u8]| {
+        for byte in bytes {
+            if let Some(slot) = out.get_mut(len) {
+                *slot = *byte;
+            }
+            len += 1;
+        }
+    };
+
+    let mut line = None;
+    for (kind, value) in records {
+        if kind == Record::Error as u8 {
+            if line.is_some() {
+                write(b"\n");
+            }
+            write(b"# error: ");
+            write(Event::describe(value));
+            write(b"\n");
+            line = None;
+            continue;
+        }
+
+        if line != Some(kind) {
+            if line.is_some() {
+                write(b"\n");
+            }
+            write(&[kind]);
+            line = Some(kind);
+        }
+        write(&[
+            b' ',
+            DIGITS[(value >> 4) as usize],
+            DIGITS[(value & 0x0F) as usize],
+        ]);
+    }
+    if line.is_some() {
+        write(b"\n");
+    }
+    len
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+    use std::string::String;
+    use std::vec;
+
+    use super::*;
+
+    fn ring(size: usize) -> Diagnostics {
+        Diagnostics::new(vec![0u8; size].leak())
+    }
+
+    fn dump(diagnostics: &Diagnostics, size: usize) -> String {
+        let mut out = vec![0u8; size];
+        let len = diagnostics.dump(&mut out);
+        String::from_utf8(out[..len].to_vec()).unwrap()
+    }
+
+    #[test]
+    fn dump_format() {
+        let mut diagnostics = ring(64);
+        diagnostics.record_all(Record::Sent, b"Kx");
+        diagnostics.record_all(Record::Received, b"x#");
+        diagnostics.record(Record::Sent, b'V');
+        diagnostics.record(Record::Error, Event::Truncated as u8);
+
+        assert_eq!(
+            dump(&diagnostics, 128),
+            "> 4B 78\n< 78 23\n> 56\n# error: truncated\n"
+        );
+        assert_eq!(dump(&ring(0), 128), "");
+    }
+
+    #[test]
+    fn ring_keeps_newest() {
+        // 3 records
+        let mut diagnostics = ring(7);
+        diagnostics.record_all(Record::Sent, b"abcd");
+        diagnostics.record(Record::Received, b'#');
+        assert_eq!(dump(&diagnostics, 128), "> 63 64\n< 23\n");
+
+        // a small output drops the oldest records
+        assert_eq!(dump(&diagnostics, 10), "> 64\n< 23\n");
+        assert_eq!(dump(&diagnostics, 4), "");
+    }
+}
@@ -0,0 +1,107 @@
+//! Encoder counts of the motor controllers
+//!
+//! The motor controllers report their position
+//! ([`NexStar::motor_position`](crate::NexStar::motor_position)) as a 24 bit
+//! fraction of a revolution of the axis, whatever the resolution of their
+//! encoders. [`Resolution`] converts positions and degrees into raw counts for
+//! diagnostics, given the counts per revolution of the encoder:
+//!
+//! ```ignore
+//! let resolution = Resolution::Known(counts_per_revolution);
+//! let counts = resolution.position_to_counts(nexstar.motor_position(Device::AzmRaMotor)?);
+//! ```
+//!
+//! The crate has no table of the counts of the mounts, as no per-model or
+//! per-board counts are published. The caller takes them from the
+//! documentation of the mount or measures them, otherwise
+//! [`Resolution::Fractional`] uses the 24 bit fraction itself.
+
+use crate::command::ARCSEC_PER_REVOLUTION;
+
+/// Counts per revolution of the position reported by the motor controllers
+pub const FRACTION_COUNTS: u32 = 1 << 24;
+
+/// Counts per revolution of an axis
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Resolution {
+    /// Counts of the encoder per revolution of the axis
+    Known(u32),
+    /// The counts aren't known, they are the 24 bit fraction of the
+    /// position.
+    Fractional,
+}
+
+impl Resolution {
+    pub fn counts_per_revolution(&self) -> u32 {
+        match *self {
+            // no encoder has fewer than one count per revolution
+            Resolution::Known(counts) => counts.max(1),
+            Resolution::Fractional => FRACTION_COUNTS,
+        }
+    }
+
+    /// Converts counts into degrees in 0..360, wrapped into one revolution.
+    pub fn counts_to_degrees(&self, counts: u32) -> f32 {
+        let revolution = self.counts_per_revolution();
+        ((counts % revolution) as f64 / revolution as f64 * 360.0) as f32
+    }
+
+    /// Converts degrees, wrapped into one revolution, into the nearest count.
+    pub fn degrees_to_counts(&self, degrees: f32) -> u32 {
+        let revolution = self.counts_per_revolution() as f64;
+        let degrees = degrees as f64 % 360.0;
+        let degrees = if degrees < 0.0 {
+            degrees + 360.0
+        } else {
+            degrees
+        };
+        (degrees / 360.0 * revolution + 0.5) as u64 as u32 % self.counts_per_revolution()
+    }
+
+    /// Converts a 24 bit position of a motor controller into the nearest count.
+    pub fn position_to_counts(&self, position: u32) -> u32 {
+        let revolution = self.counts_per_revolution() as u64;
+        let position = (position % FRACTION_COUNTS) as u64;
+        ((position * revolution + FRACTION_COUNTS as u64 / 2) / FRACTION_COUNTS as u64 % revolution)
+            as u32
+    }
+
+    /// Gets the arcseconds of the axis per count.
+    pub fn arcsec_per_count(&self) -> f32 {
+        (ARCSEC_PER_REVOLUTION as f64 / self.counts_per_revolution() as f64) as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fractional() {
+        for resolution in [Resolution::Fractional, Resolution::Known(FRACTION_COUNTS)] {
+            assert_eq!(resolution.counts_per_revolution(), FRACTION_COUNTS);
+            assert_eq!(resolution.counts_to_degrees(1 << 22), 90.0);
+            assert_eq!(resolution.counts_to_degrees(1 << 23), 180.0);
+            assert_eq!(resolution.degrees_to_counts(-90.0), 3 << 22);
+            assert_eq!(resolution.degrees_to_counts(360.0), 0);
+            assert_eq!(resolution.position_to_counts(0x12_3456), 0x12_3456);
+        }
+    }
+
+    #[test]
+    fn other_resolutions() {
+        let resolution = Resolution::Known(9_024_000);
+        assert_eq!(resolution.position_to_counts(1 << 23), 4_512_000);
+        assert_eq!(
+            resolution.position_to_counts(FRACTION_COUNTS - 1),
+            9_023_999
+        );
+        assert_eq!(resolution.degrees_to_counts(90.0), 2_256_000);
+        assert_eq!(resolution.counts_to_degrees(2_256_000), 90.0);
+        assert_eq!(resolution.counts_to_degrees(9_024_000), 0.0);
+        assert!((resolution.arcsec_per_count() - 0.143_617).abs() < 1e-5);
+
+        assert_eq!(Resolution::Known(0).counts_per_revolution(), 1);
+        assert_eq!(Resolution::Known(0).position_to_counts(1 << 23), 0);
+    }
+}
@@ -0,0 +1,164 @@
+use core::fmt;
+
+use crate::aux;
+use crate::types::{Device, HomeStatus, Model, Version};
+
+#[derive(Debug)]
+pub enum Error<T, U> {
+    /// The mount answered the command in flight with a nack or garbage.
+    UnexpectedResponse(Context),
+    /// The command requires a newer firmware than the one reported by the mount.
+    UnsupportedFirmware {
+        required: Version,
+        found: Version,
+    },
+    /// The mount didn't reach the expected state in time.
+    Timeout,
+    /// The abort hook requested to stop waiting.
+    Aborted,
+    /// The mount didn't arrive at the GOTO target.
+    NotOnTarget,
+    /// Only `got` of the `expected` response bytes arrived in time.
+    Truncated {
+        expected: usize,
+        got: usize,
+    },
+    /// An AUX bus frame was invalid.
+    InvalidFrame(aux::FrameError),
+    /// The local echo didn't match the transmitted bytes.
+    EchoMismatch,
+    /// A previous command failed and the link wasn't resynced.
+    LinkDirty,
+    /// The target altitude in degrees is below the requested limit.
+    #[cfg(feature = "float")]
+    BelowAltitudeLimit {
+        altitude: f32,
+        limit: f32,
+    },
+    /// A latitude or longitude is out of range.
+    InvalidLocation,
+    /// A command frame or its response exceeds the fixed capacity.
+    FrameOverflow,
+    /// The GPS unit has no fix yet.
+    NoGpsFix,
+    /// A slew rate is out of the supported range.
+    InvalidSlewRate,
+    /// The device rejected the command, e.g. because its firmware predates it.
+    UnsupportedCommand(Context),
+    /// An index switch wasn't found in time, the status tells which were.
+    HomeNotFound(HomeStatus),
+    /// A slew wasn't refreshed in time and the motors were stopped.
+    WatchdogFired,
+    /// The connected model lacks the feature the command needs, see
+    /// [`Model::capabilities`](crate::Model::capabilities).
+    UnsupportedByModel(Model),
+    /// A response of `needed` bytes doesn't fit the buffer of `got` bytes, it
+    /// was read completely and the link is still in sync.
+    BufferTooSmall {
+        needed: usize,
+        got: usize,
+    },
+    /// A measurement window holds fewer samples than the measurement needs.
+    TooFewSamples,
+    /// A motor position is outside of -180..=180 degrees.
+    InvalidPosition,
+    /// The device is still moving and can't start the command.
+    Busy,
+    /// A StarSense HC refused the command while its automatic alignment
+    /// is running.
+    Aligning(Context),
+    Read(T),
+    Write(U),
+}
+
+impl<T: fmt::Debug, U: fmt::Debug> fmt::Display for Error<T, U> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::UnexpectedResponse(context) => write!(f, "unexpected response to {}", context),
+            Error::UnsupportedFirmware { required, found } => write!(
+                f,
+                "firmware {}.{} found, {}.{} required",
+                found.major, found.minor, required.major, required.minor
+            ),
+            Error::Timeout => write!(f, "timeout"),
+            Error::Truncated { expected, got } => {
+                write!(f, "response truncated after {} of {} bytes", got, expected)
+            }
+            Error::InvalidFrame(e) => write!(f, "invalid AUX frame: {:?}", e),
+            Error::EchoMismatch => write!(f, "local echo mismatch"),
+            Error::LinkDirty => write!(f, "link dirty"),
+            Error::Aborted => write!(f, "aborted"),
+            Error::NotOnTarget => write!(f, "mount not on target"),
+            #[cfg(feature = "float")]
+            Error::BelowAltitudeLimit { altitude, limit } => {
+                write!(f, "altitude {} below limit {}", altitude, limit)
+            }
+            Error::InvalidLocation => write!(f, "invalid location"),
+            Error::FrameOverflow => write!(f, "frame overflow"),
+            Error::NoGpsFix => write!(f, "no GPS fix"),
+            Error::InvalidSlewRate => write!(f, "invalid slew rate"),
+            Error::UnsupportedCommand(context) => write!(f, "{} not supported", context),
+            Error::HomeNotFound(status) => write!(
+                f,
+                "home not found (AZM/RA {}, ALT/DEC {})",
+                found(status.azm_ra_found),
+                found(status.alt_dec_found)
+            ),
+            Error::WatchdogFired => write!(f, "slew watchdog fired"),
+            Error::UnsupportedByModel(model) => write!(f, "not supported by the {:?}", model),
+            Error::BufferTooSmall { needed, got } => {
+                write!(f, "response of {} bytes exceeds buffer of {}", needed, got)
+            }
+            Error::TooFewSamples => write!(f, "too few samples"),
+            Error::InvalidPosition => write!(f, "invalid position"),
+            Error::Busy => write!(f, "device busy"),
+            Error::Aligning(context) => write!(f, "{} refused, busy aligning", context),
+            Error::Read(e) => write!(f, "read error: {:?}", e),
+            Error::Write(e) => write!(f, "write error: {:?}", e),
+        }
+    }
+}
+
+fn found(found: bool) -> &'static str {
+    if found {
+        "found"
+    } else {
+        "not found"
+    }
+}
+
+/// The command in flight when an error occurred
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Context {
+    /// Hand Controller (HC) command
+    Hc(u8),
+    /// Pass-through command to a sub device
+    PassThrough { device: Device, command: u8 },
+}
+
+impl Context {
+    pub(crate) fn from_frame(frame: &[u8]) -> Context {
+        match *frame {
+            [b'P', _, device, command, _, _, _, _] => match Device::from_bits(device) {
+                Some(device) => Context::PassThrough { device, command },
+                None => Context::Hc(b'P'),
+            },
+            [command, ..] => Context::Hc(command),
+            [] => Context::Hc(0),
+        }
+    }
+}
+
+impl fmt::Display for Context {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Context::Hc(command) if command.is_ascii_graphic() => {
+                write!(f, "'{}'", *command as char)
+            }
+            Context::Hc(command) => write!(f, "0x{:02X}", command),
+            Context::PassThrough { device, command } => {
+                write!(f, "0x{:02X} to {:?}", command, device)
+            }
+        }
+    }
+}
@@ -0,0 +1,227 @@
+//! Fake mount for testing application logic
+//!
+//! [`FakeMount`] implements [`Mount`] on plain fields without any protocol,
+//! so tests set up the state they need and check what the code under test
+//! did to it:
+//!
+//! ```
+//! use nexstar::fake::FakeMount;
+//! use nexstar::{Error, Mount, RaDec};
+//!
+//! let mut mount = FakeMount::new();
+//! mount.precise_goto_ra_dec(RaDec { ra: 10.0, dec: 20.0 }).unwrap();
+//! assert_eq!(mount.ra_dec.ra, 10.0);
+//!
+//! mount.fail_next = Some(Error::Timeout);
+//! assert!(mount.tracking_mode().is_err());
+//! ```
+//!
+//! GOTOs arrive immediately. Use the [simulator](crate::simulator) to test
+//! against the actual protocol.
+
+use crate::{
+    AzmAlt, DateTime, Error, Location, Model, Mount, MountInfo, MountStatus, RaDec, Target,
+    TrackingMode, Version,
+};
+
+/// Mount keeping its state in public fields
+///
+/// Defaults to an aligned 6/8 SE with HC firmware 4.21 at 0/0, tracking off.
+pub struct FakeMount {
+    pub info: MountInfo,
+    pub ra_dec: RaDec,
+    pub azm_alt: AzmAlt,
+    pub tracking_mode: TrackingMode,
+    /// Rates of the AZM/RA and ALT/DEC motors in arcsec/s
+    pub rates: (i32, i32),
+    pub location: Location,
+    pub datetime: DateTime,
+    pub aligned: bool,
+    pub goto_in_progress: bool,
+    /// Target of the last GOTO
+    pub last_target: Option<Target>,
+    /// Returned by the next operation instead of its result
+    pub fail_next: Option<Error<(), ()>>,
+}
+
+impl FakeMount {
+    pub fn new() -> FakeMount {
+        FakeMount {
+            info: MountInfo {
+                model: Model::Se6_8,
+                hc_version: Version::new(4, 21),
+                azm_ra_version: Some(Version::new(7, 11)),
+                alt_dec_version: Some(Version::new(7, 11)),
+            },
+            ra_dec: RaDec { ra: 0.0, dec: 0.0 },
+            azm_alt: AzmAlt { azm: 0.0, alt: 0.0 },
+            tracking_mode: TrackingMode::Off,
+            rates: (0, 0),
+            location: Location {
+                latitude: 0.0,
+                longitude: 0.0,
+            },
+            datetime: DateTime {
+                hour: 0,
+                minutes: 0,
+                seconds: 0,
+                zone: 0,
+                daylight_saving: false,
+                year: 0,
+                month: 1,
+                day: 1,
+            },
+            aligned: true,
+            goto_in_progress: false,
+            last_target: None,
+            fail_next: None,
+        }
+    }
+
+    /// Fails with the injected error or runs `operation`.
+    fn run<R>(&mut self, operation: impl FnOnce(&mut Self) -> R) -> Result<R, Error<(), ()>> {
+        match self.fail_next.take() {
+            Some(e) => Err(e),
+            None => Ok(operation(self)),
+        }
+    }
+
+    fn goto_target(&mut self, target: Target) -> Result<(), Error<(), ()>> {
+        self.run(|mount| {
+            match target {
+                Target::RaDec(target) => mount.ra_dec = target,
+                Target::AzmAlt(target) => mount.azm_alt = target,
+            }
+            mount.last_target = Some(target);
+        })
+    }
+}
+
+impl Default for FakeMount {
+    fn default() -> FakeMount {
+        FakeMount::new()
+    }
+}
+
+impl Mount for FakeMount {
+    type Error = Error<(), ()>;
+
+    fn connect(&mut self) -> Result<MountInfo, Self::Error> {
+        self.run(|mount| mount.info)
+    }
+
+    fn ra_dec(&mut self) -> Result<RaDec, Self::Error> {
+        self.run(|mount| mount.ra_dec)
+    }
+
+    fn precise_ra_dec(&mut self) -> Result<RaDec, Self::Error> {
+        self.run(|mount| mount.ra_dec)
+    }
+
+    fn azm_alt(&mut self) -> Result<AzmAlt, Self::Error> {
+        self.run(|mount| mount.azm_alt)
+    }
+
+    fn precise_azm_alt(&mut self) -> Result<AzmAlt, Self::Error> {
+        self.run(|mount| mount.azm_alt)
+    }
+
+    fn goto_ra_dec(&mut self, target: RaDec) -> Result<(), Self::Error> {
+        self.goto_target(Target::RaDec(target))
+    }
+
+    fn precise_goto_ra_dec(&mut self, target: RaDec) -> Result<(), Self::Error> {
+        self.goto_target(Target::RaDec(target))
+    }
+
+    fn goto_azm_alt(&mut self, target: AzmAlt) -> Result<(), Self::Error> {
+        self.goto_target(Target::AzmAlt(target))
+    }
+
+    fn precise_goto_azm_alt(&mut self, target: AzmAlt) -> Result<(), Self::Error> {
+        self.goto_target(Target::AzmAlt(target))
+    }
+
+    fn goto(&mut self, target: Target) -> Result<(), Self::Error> {
+        self.goto_target(target)
+    }
+
+    fn cancel_goto(&mut self) -> Result<(), Self::Error> {
+        self.run(|mount| mount.goto_in_progress = false)
+    }
+
+    fn is_goto_in_progress(&mut self) -> Result<bool, Self::Error> {
+        self.run(|mount| mount.goto_in_progress)
+    }
+
+    fn last_target(&self) -> Option<Target> {
+        self.last_target
+    }
+
+    fn on_target(&mut self, tolerance_arcsec: f32) -> Result<bool, Self::Error> {
+        self.run(|mount| {
+            let distance = match mount.last_target {
+                Some(Target::RaDec(target)) => mount.ra_dec.separation(target),
+                Some(Target::AzmAlt(target)) => mount.azm_alt.separation(target),
+                None => return false,
+            };
+            distance * 3600.0 <= tolerance_arcsec
+        })
+    }
+
+    fn sync_ra_dec(&mut self, position: RaDec) -> Result<(), Self::Error> {
+        self.run(|mount| mount.ra_dec = position)
+    }
+
+    fn precise_sync_ra_dec(&mut self, position: RaDec) -> Result<(), Self::Error> {
+        self.run(|mount| mount.ra_dec = position)
+    }
+
+    fn tracking_mode(&mut self) -> Result<TrackingMode, Self::Error> {
+        self.run(|mount| mount.tracking_mode)
+    }
+
+    fn set_tracking_mode(&mut self, mode: TrackingMode) -> Result<(), Self::Error> {
+        self.run(|mount| mount.tracking_mode = mode)
+    }
+
+    fn track_rates(
+        &mut self,
+        azm_arcsec_per_sec: i32,
+        alt_arcsec_per_sec: i32,
+    ) -> Result<(), Self::Error> {
+        self.run(|mount| mount.rates = (azm_arcsec_per_sec, alt_arcsec_per_sec))
+    }
+
+    fn stop_tracking_rates(&mut self) -> Result<(), Self::Error> {
+        self.run(|mount| mount.rates = (0, 0))
+    }
+
+    fn is_alignment_complete(&mut self) -> Result<bool, Self::Error> {
+        self.run(|mount| mount.aligned)
+    }
+
+    fn status(&mut self) -> Result<MountStatus, Self::Error> {
+        self.run(|mount| MountStatus {
+            aligned: mount.aligned,
+            goto_in_progress: mount.goto_in_progress,
+            tracking: mount.tracking_mode,
+        })
+    }
+
+    fn location(&mut self) -> Result<Location, Self::Error> {
+        self.run(|mount| mount.location)
+    }
+
+    fn set_location(&mut self, location: Location) -> Result<(), Self::Error> {
+        self.run(|mount| mount.location = location)
+    }
+
+    fn datetime(&mut self) -> Result<DateTime, Self::Error> {
+        self.run(|mount| mount.datetime)
+    }
+
+    fn set_datetime(&mut self, datetime: DateTime) -> Result<(), Self::Error> {
+        self.run(|mount| mount.datetime = datetime)
+    }
+}
@@ -0,0 +1,189 @@
+//! Fault injection for robustness testing
+//!
+//! [`FaultInjector`] wraps the receiving half of a transport and corrupts the
+//! bytes like a long or noisy serial line would: it drops, duplicates and
+//! flips bits of bytes and stalls the line. The faults follow a pseudo random
+//! schedule derived from a seed, so a failing session can be reproduced.
+//!
+//! ```ignore
+//! let hc = SimulatedHc::new();
+//! let rx = FaultInjector::new(&hc, FaultSchedule::new(42).drop_one_in(200));
+//! let mut nexstar = NexStar::new(rx, &hc);
+//! nexstar.set_timeout(clock, 1000);
+//! ```
+//!
+//! Stalls are counted in reads returning [`nb::Error::WouldBlock`], set a
+//! timeout on the driver so it doesn't wait forever for a dropped byte.
+
+use embedded_hal::serial;
+
+/// Seeded schedule of the injected faults
+///
+/// The rates are given as "one in `n` received bytes", zero disables the
+/// fault. All faults are disabled by default.
+#[derive(Copy, Clone, Debug)]
+pub struct FaultSchedule {
+    seed: u32,
+    drop: u32,
+    duplicate: u32,
+    flip: u32,
+    stall: u32,
+    stall_reads: (u32, u32),
+}
+
+impl FaultSchedule {
+    pub fn new(seed: u32) -> FaultSchedule {
+        FaultSchedule {
+            // xorshift gets stuck at zero
+            seed: seed.max(1),
+            drop: 0,
+            duplicate: 0,
+            flip: 0,
+            stall: 0,
+            stall_reads: (0, 0),
+        }
+    }
+
+    /// Drops one in `n` bytes.
+    pub fn drop_one_in(mut self, n: u32) -> FaultSchedule {
+        self.drop = n;
+        self
+    }
+
+    /// Receives one in `n` bytes twice.
+    pub fn duplicate_one_in(mut self, n: u32) -> FaultSchedule {
+        self.duplicate = n;
+        self
+    }
+
+    /// Flips a random bit of one in `n` bytes.
+    pub fn flip_one_in(mut self, n: u32) -> FaultSchedule {
+        self.flip = n;
+        self
+    }
+
+    /// Stalls the line before one in `n` bytes for `min..=max` reads.
+    pub fn stall_one_in(mut self, n: u32, min: u32, max: u32) -> FaultSchedule {
+        self.stall = n;
+        self.stall_reads = (min, max.max(min));
+        self
+    }
+}
+
+/// Number of faults injected so far
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct FaultCounts {
+    pub dropped: usize,
+    pub duplicated: usize,
+    pub flipped: usize,
+    pub stalled: usize,
+}
+
+impl FaultCounts {
+    pub fn total(&self) -> usize {
+        self.dropped + self.duplicated + self.flipped + self.stalled
+    }
+}
+
+/// Receiving half of a transport injecting faults into the received bytes
+pub struct FaultInjector<T> {
+    inner: T,
+    schedule: FaultSchedule,
+    state: u32,
+    enabled: bool,
+    stall: u32,
+    queue: [u8; 2],
+    queued: usize,
+    counts: FaultCounts,
+}
+
+impl<T> FaultInjector<T>
+where
+    T: serial::Read<u8>,
+{
+    pub fn new(inner: T, schedule: FaultSchedule) -> FaultInjector<T> {
+        FaultInjector {
+            inner,
+            schedule,
+            state: schedule.seed,
+            enabled: true,
+            stall: 0,
+            queue: [0; 2],
+            queued: 0,
+            counts: FaultCounts::default(),
+        }
+    }
+
+    /// Enables or disables the injection, e.g. to check the driver recovers
+    /// once the line is clean again.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    pub fn counts(&self) -> FaultCounts {
+        self.counts
+    }
+
+    pub fn free(self) -> T {
+        self.inner
+    }
+
+    fn next_random(&mut self) -> u32 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 17;
+        self.state ^= self.state << 5;
+        self.state
+    }
+
+    fn roll(&mut self, one_in: u32) -> bool {
+        self.enabled && one_in != 0 && self.next_random().is_multiple_of(one_in)
+    }
+
+    fn push(&mut self, byte: u8) {
+        self.queue[self.queued] = byte;
+        self.queued += 1;
+    }
+}
+
+impl<T> serial::Read<u8> for FaultInjector<T>
+where
+    T: serial::Read<u8>,
+{
+    type Error = T::Error;
+
+    fn read(&mut self) -> nb::Result<u8, T::Error> {
+        if self.stall > 0 {
+            self.stall -= 1;
+            return Err(nb::Error::WouldBlock);
+        }
+        if self.queued > 0 {
+            let byte = self.queue[0];
+            self.queue[0] = self.queue[1];
+            self.queued -= 1;
+            return Ok(byte);
+        }
+
+        let mut byte = self.inner.read()?;
+        if self.roll(self.schedule.drop) {
+            self.counts.dropped += 1;
+            return Err(nb::Error::WouldBlock);
+        }
+        if self.roll(self.schedule.flip) {
+            self.counts.flipped += 1;
+            byte ^= 1 << (self.next_random() % 8);
+        }
+        if self.roll(self.schedule.duplicate) {
+            self.counts.duplicated += 1;
+            self.push(byte);
+        }
+        if self.roll(self.schedule.stall) {
+            self.counts.stalled += 1;
+            let (min, max) = self.schedule.stall_reads;
+            self.stall = min + self.next_random() % (max - min + 1);
+            self.push(byte);
+            return Err(nb::Error::WouldBlock);
+        }
+
+        Ok(byte)
+    }
+}
@@ -0,0 +1,449 @@
+//! C interface
+//!
+//! Exposes the driver to C firmware through the functions declared in
+//! `include/nexstar.h`, which is generated by cbindgen from this module:
+//!
+//! ```text
+//! cbindgen --config cbindgen.toml --output include/nexstar.h src/ffi.rs
+//! ```
+//!
+//! The caller owns the memory of the [`NexstarHandle`] and passes byte
+//! callbacks for its UART to [`nexstar_init`]:
+//!
+//! ```c
+//! static NexstarHandle mount;
+//!
+//! nexstar_init(&mount, uart_read, uart_write, NULL);
+//! NexstarLocation location;
+//! if (nexstar_get_location(&mount, &location) == NEXSTAR_STATUS_OK) { ... }
+//! ```
+//!
+//! Every function returns a [`NexstarStatus`] and writes its result through
+//! an out pointer. No function panics on bad input, null pointers and
+//! uninitialized handles are reported as errors. A read callback which never
+//! delivers a byte blocks the calls, so it should implement its own timeout
+//! by returning an error.
+
+use core::ffi::c_void;
+use core::mem::{align_of, size_of};
+
+use embedded_hal::serial;
+
+use crate::error::Error;
+use crate::hc::NexStar;
+use crate::types::{DateTime, Location, RaDec};
+
+/// Reads a byte into `byte`, returns 1 if a byte was read, 0 if none is
+/// available yet and a negative value on errors.
+pub type NexstarReadFn = Option<extern "C" fn(context: *mut c_void, byte: *mut u8) -> i32>;
+
+/// Writes `byte`, returns 0 if it was written, 1 if the UART is busy and a
+/// negative value on errors.
+pub type NexstarWriteFn = Option<extern "C" fn(context: *mut c_void, byte: u8) -> i32>;
+
+/// Result of the C functions, negative values are errors
+#[repr(i32)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum NexstarStatus {
+    Ok = 0,
+    UnexpectedResponse = -1,
+    UnsupportedFirmware = -2,
+    Timeout = -3,
+    Aborted = -4,
+    NotOnTarget = -5,
+    Truncated = -6,
+    InvalidFrame = -7,
+    EchoMismatch = -8,
+    LinkDirty = -9,
+    BelowAltitudeLimit = -10,
+    InvalidLocation = -11,
+    FrameOverflow = -12,
+    NoGpsFix = -13,
+    InvalidSlewRate = -14,
+    UnsupportedCommand = -15,
+    HomeNotFound = -16,
+    WatchdogFired = -17,
+    /// The read callback returned an error.
+    Read = -18,
+    /// The write callback returned an error.
+    Write = -19,
+    /// A pointer argument is null.
+    NullPointer = -20,
+    /// The handle wasn't passed to [`nexstar_init`].
+    NotInitialized = -21,
+    UnsupportedByModel = -22,
+    BufferTooSmall = -23,
+    TooFewSamples = -24,
+    InvalidPosition = -25,
+    Busy = -26,
+    Aligning = -27,
+}
+
+impl<T, U> From<Error<T, U>> for NexstarStatus {
+    fn from(error: Error<T, U>) -> Self {
+        match error {
+            Error::UnexpectedResponse(_) => NexstarStatus::UnexpectedResponse,
+            Error::UnsupportedFirmware { .. } => NexstarStatus::UnsupportedFirmware,
+            Error::Timeout => NexstarStatus::Timeout,
+            Error::Aborted => NexstarStatus::Aborted,
+            Error::NotOnTarget => NexstarStatus::NotOnTarget,
+            Error::Truncated { .. } => NexstarStatus::Truncated,
+            Error::InvalidFrame(_) => NexstarStatus::InvalidFrame,
+            Error::EchoMismatch => NexstarStatus::EchoMismatch,
+            Error::LinkDirty => NexstarStatus::LinkDirty,
+            Error::BelowAltitudeLimit { .. } => NexstarStatus::BelowAltitudeLimit,
+            Error::InvalidLocation => NexstarStatus::InvalidLocation,
+            Error::FrameOverflow => NexstarStatus::FrameOverflow,
+            Error::NoGpsFix => NexstarStatus::NoGpsFix,
+            Error::InvalidSlewRate => NexstarStatus::InvalidSlewRate,
+            Error::UnsupportedCommand(_) => NexstarStatus::UnsupportedCommand,
+            Error::HomeNotFound(_) => NexstarStatus::HomeNotFound,
+            Error::WatchdogFired => NexstarStatus::WatchdogFired,
+            Error::UnsupportedByModel(_) => NexstarStatus::UnsupportedByModel,
+            Error::BufferTooSmall { .. } => NexstarStatus::BufferTooSmall,
+            Error::TooFewSamples => NexstarStatus::TooFewSamples,
+            Error::InvalidPosition => NexstarStatus::InvalidPosition,
+            Error::Busy => NexstarStatus::Busy,
+            Error::Aligning(_) => NexstarStatus::Aligning,
+            Error::Read(_) => NexstarStatus::Read,
+            Error::Write(_) => NexstarStatus::Write,
+        }
+    }
+}
+
+/// Location in degrees, north and east positive
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct NexstarLocation {
+    pub latitude: f32,
+    pub longitude: f32,
+}
+
+/// Date and time as stored by the HC, see [`DateTime`]
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct NexstarDateTime {
+    pub hour: u8,
+    pub minutes: u8,
+    pub seconds: u8,
+    /// Offset of the standard time from UTC in hours
+    pub zone: i8,
+    pub daylight_saving: bool,
+    /// Year of the century
+    pub year: u8,
+    pub month: u8,
+    pub day: u8,
+}
+
+/// Equatorial coordinates in degrees
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct NexstarRaDec {
+    pub ra: f32,
+    pub dec: f32,
+}
+
+/// Serial port of the read callback
+pub struct CallbackRx {
+    read: extern "C" fn(*mut c_void, *mut u8) -> i32,
+    context: *mut c_void,
+}
+
+/// Serial port of the write callback
+pub struct CallbackTx {
+    write: extern "C" fn(*mut c_void, u8) -> i32,
+    context: *mut c_void,
+}
+
+impl serial::Read<u8> for CallbackRx {
+    type Error = i32;
+
+    fn read(&mut self) -> nb::Result<u8, i32> {
+        let mut byte = 0;
+        match (self.read)(self.context, &mut byte) {
+            1 => Ok(byte),
+            0 => Err(nb::Error::WouldBlock),
+            code => Err(nb::Error::Other(code)),
+        }
+    }
+}
+
+impl serial::Write<u8> for CallbackTx {
+    type Error = i32;
+
+    fn write(&mut self, byte: u8) -> nb::Result<(), i32> {
+        match (self.write)(self.context, byte) {
+            0 => Ok(()),
+            1 => Err(nb::Error::WouldBlock),
+            code => Err(nb::Error::Other(code)),
+        }
+    }
+
+    fn flush(&mut self) -> nb::Result<(), i32> {
+        Ok(())
+    }
+}
+
+type Driver = NexStar<CallbackRx, CallbackTx>;
+
+const HANDLE_MAGIC: u32 = 0x4E58_5354;
+
+/// Size of the storage in a [`NexstarHandle`] in 64 bit words
+pub const NEXSTAR_HANDLE_WORDS: usize = 40;
+
+const _: () = assert!(size_of::<Driver>() <= size_of::<[u64; NEXSTAR_HANDLE_WORDS]>());
+const _: () = assert!(align_of::<Driver>() <= align_of::<u64>());
+
+/// Storage of a driver, owned by the caller
+///
+/// The fields are private, the handle is only used through the functions.
+#[repr(C)]
+pub struct NexstarHandle {
+    magic: u32,
+    storage: [u64; NEXSTAR_HANDLE_WORDS],
+}
+
+/// Gets the driver of an initialized handle.
+unsafe fn driver<'a>(handle: *mut NexstarHandle) -> Result<&'a mut Driver, NexstarStatus> {
+    match handle.as_mut() {
+        None => Err(NexstarStatus::NullPointer),
+        Some(handle) if handle.magic != HANDLE_MAGIC => Err(NexstarStatus::NotInitialized),
+        Some(handle) => Ok(&mut *(handle.storage.as_mut_ptr() as *mut Driver)),
+    }
+}
+
+/// Runs `f` on the driver of `handle` and writes its result to `out`.
+unsafe fn query<R>(
+    handle: *mut NexstarHandle,
+    out: *mut R,
+    f: impl FnOnce(&mut Driver) -> Result<R, Error<i32, i32>>,
+) -> NexstarStatus {
+    if out.is_null() {
+        return NexstarStatus::NullPointer;
+    }
+    let driver = match driver(handle) {
+        Ok(driver) => driver,
+        Err(status) => return status,
+    };
+    match f(driver) {
+        Ok(value) => {
+            out.write(value);
+            NexstarStatus::Ok
+        }
+        Err(e) => e.into(),
+    }
+}
+
+/// Runs `f` on the driver of `handle`.
+unsafe fn command(
+    handle: *mut NexstarHandle,
+    f: impl FnOnce(&mut Driver) -> Result<(), Error<i32, i32>>,
+) -> NexstarStatus {
+    match driver(handle) {
+        Ok(driver) => f(driver).map_or_else(NexstarStatus::from, |_| NexstarStatus::Ok),
+        Err(status) => status,
+    }
+}
+
+/// Initializes the driver in `handle` over the byte callbacks, `context` is
+/// passed to both of them.
+///
+/// # Safety
+///
+/// `handle` must be null or point to writable memory of a handle, which must
+/// not be moved while the driver is used.
+#[no_mangle]
+pub unsafe extern "C" fn nexstar_init(
+    handle: *mut NexstarHandle,
+    read: NexstarReadFn,
+    write: NexstarWriteFn,
+    context: *mut c_void,
+) -> NexstarStatus {
+    let (handle, read, write) = match (handle.as_mut(), read, write) {
+        (Some(handle), Some(read), Some(write)) => (handle, read, write),
+        _ => return NexstarStatus::NullPointer,
+    };
+    let driver = NexStar::new(CallbackRx { read, context }, CallbackTx { write, context });
+    (handle.storage.as_mut_ptr() as *mut Driver).write(driver);
+    handle.magic = HANDLE_MAGIC;
+    NexstarStatus::Ok
+}
+
+/// Gets the location set in the HC.
+///
+/// # Safety
+///
+/// `handle` and `location` must be null or valid pointers.
+#[no_mangle]
+pub unsafe extern "C" fn nexstar_get_location(
+    handle: *mut NexstarHandle,
+    location: *mut NexstarLocation,
+) -> NexstarStatus {
+    query(handle, location, |driver| {
+        let Location {
+            latitude,
+            longitude,
+        } = driver.location()?;
+        Ok(NexstarLocation {
+            latitude,
+            longitude,
+        })
+    })
+}
+
+/// Sets the location of the HC, fails with `NEXSTAR_STATUS_INVALID_LOCATION`
+/// if it is out of range.
+///
+/// # Safety
+///
+/// `handle` must be null or a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn nexstar_set_location(
+    handle: *mut NexstarHandle,
+    location: NexstarLocation,
+) -> NexstarStatus {
+    // the ranges are false for NaN
+    if !(-90.0..=90.0).contains(&location.latitude)
+        || !(-180.0..=180.0).contains(&location.longitude)
+    {
+        return NexstarStatus::InvalidLocation;
+    }
+    command(handle, |driver| {
+        driver.set_location(Location {
+            latitude: location.latitude,
+            longitude: location.longitude,
+        })
+    })
+}
+
+/// Gets the date and time set in the HC.
+///
+/// # Safety
+///
+/// `handle` and `datetime` must be null or valid pointers.
+#[no_mangle]
+pub unsafe extern "C" fn nexstar_get_datetime(
+    handle: *mut NexstarHandle,
+    datetime: *mut NexstarDateTime,
+) -> NexstarStatus {
+    query(handle, datetime, |driver| {
+        let datetime = driver.datetime()?;
+        Ok(NexstarDateTime {
+            hour: datetime.hour,
+            minutes: datetime.minutes,
+            seconds: datetime.seconds,
+            zone: datetime.zone,
+            daylight_saving: datetime.daylight_saving,
+            year: datetime.year,
+            month: datetime.month,
+            day: datetime.day,
+        })
+    })
+}
+
+/// Sets the date and time of the HC.
+///
+/// # Safety
+///
+/// `handle` must be null or a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn nexstar_set_datetime(
+    handle: *mut NexstarHandle,
+    datetime: NexstarDateTime,
+) -> NexstarStatus {
+    command(handle, |driver| {
+        driver.set_datetime(DateTime {
+            hour: datetime.hour,
+            minutes: datetime.minutes,
+            seconds: datetime.seconds,
+            zone: datetime.zone,
+            daylight_saving: datetime.daylight_saving,
+            year: datetime.year,
+            month: datetime.month,
+            day: datetime.day,
+        })
+    })
+}
+
+/// Gets the precise RA/Dec position.
+///
+/// # Safety
+///
+/// `handle` and `position` must be null or valid pointers.
+#[no_mangle]
+pub unsafe extern "C" fn nexstar_get_radec(
+    handle: *mut NexstarHandle,
+    position: *mut NexstarRaDec,
+) -> NexstarStatus {
+    query(handle, position, |driver| {
+        let RaDec { ra, dec } = driver.precise_ra_dec()?;
+        Ok(NexstarRaDec { ra, dec })
+    })
+}
+
+/// Starts a precise GOTO to the RA/Dec target.
+///
+/// # Safety
+///
+/// `handle` must be null or a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn nexstar_goto_radec(
+    handle: *mut NexstarHandle,
+    target: NexstarRaDec,
+) -> NexstarStatus {
+    command(handle, |driver| {
+        driver.precise_goto_ra_dec(RaDec {
+            ra: target.ra,
+            dec: target.dec,
+        })
+    })
+}
+
+/// Gets whether a GOTO is in progress.
+///
+/// # Safety
+///
+/// `handle` and `in_progress` must be null or valid pointers.
+#[no_mangle]
+pub unsafe extern "C" fn nexstar_is_goto_in_progress(
+    handle: *mut NexstarHandle,
+    in_progress: *mut bool,
+) -> NexstarStatus {
+    query(handle, in_progress, |driver| driver.is_goto_in_progress())
+}
+
+/// Cancels a GOTO in progress.
+///
+/// # Safety
+///
+/// `handle` must be null or a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn nexstar_cancel_goto(handle: *mut NexstarHandle) -> NexstarStatus {
+    command(handle, |driver| driver.cancel_goto())
+}
+
+/// Discards pending bytes and marks the link clean after an error.
+///
+/// # Safety
+///
+/// `handle` must be null or a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn nexstar_resync(handle: *mut NexstarHandle) -> NexstarStatus {
+    command(handle, |driver| driver.resync())
+}
+
+#[cfg(all(test, nexstar_c_tests))]
+mod tests {
+    #[test]
+    fn c_program() {
+        // compiled from tests/ffi/test.c by build.rs, declared here so
+        // cbindgen doesn't pick it up
+        #[link(name = "nexstar_ffi_test", kind = "static")]
+        extern "C" {
+            fn nexstar_ffi_test() -> i32;
+        }
+
+        // the C test returns the line of the first failed check
+        assert_eq!(unsafe { nexstar_ffi_test() }, 0);
+    }
+}
@@ -0,0 +1,199 @@
+//! Builder of the HC command frames
+//!
+//! Every command is assembled into a fixed-capacity [`Frame`], which also
+//! checks that the response it expects fits the response buffer:
+//!
+//! ```ignore
+//! let frame = Frame::new(b'W').push_dms(latitude).push_dms(longitude);
+//! let frame = Frame::new(b'w').expect(8);
+//! ```
+//!
+//! The builder is `const`, the frames of the commands without arguments are
+//! built at compile time, see [`protocol`](crate::protocol).
+
+use crate::parse::MAX_RESPONSE;
+use crate::protocol::MAX_COMMAND;
+use crate::types::{DateTime, Device, Dms};
+
+/// Command frame
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Frame {
+    bytes: [u8; MAX_COMMAND],
+    len: usize,
+    overflow: bool,
+}
+
+impl Frame {
+    /// Starts a frame with the command byte, expecting only the ack.
+    pub(crate) const fn new(cmd: u8) -> Frame {
+        Frame {
+            bytes: [0; MAX_COMMAND],
+            len: 0,
+            overflow: false,
+        }
+        .push(cmd)
+    }
+
+    /// Builds a pass-through frame to a sub device with up to 3 data bytes
+    /// and a response of `response_len` bytes, more data bytes overflow.
+    pub(crate) const fn pass_through(
+        device: Device,
+        cmd: u8,
+        data: &[u8],
+        response_len: u8,
+    ) -> Frame {
+        let mut args = [0u8; 3];
+        let mut i = 0;
+        while i < args.len() && i < data.len() {
+            args[i] = data[i];
+            i += 1;
+        }
+
+        let mut frame = Frame::new(b'P')
+            .push(data.len() as u8 + 1)
+            .push(device.bits())
+            .push(cmd)
+            .push_bytes(&args)
+            .push(response_len);
+        frame.overflow |= data.len() > args.len();
+        frame
+    }
+
+    /// Appends a byte.
+    pub(crate) const fn push(mut self, byte: u8) -> Frame {
+        if self.len < self.bytes.len() {
+            self.bytes[self.len] = byte;
+            self.len += 1;
+        } else {
+            self.overflow = true;
+        }
+        self
+    }
+
+    /// Appends the bytes.
+    pub(crate) const fn push_bytes(mut self, bytes: &[u8]) -> Frame {
+        let mut i = 0;
+        while i < bytes.len() {
+            self = self.push(bytes[i]);
+            i += 1;
+        }
+        self
+    }
+
+    /// Appends the degrees, minutes, seconds and sign byte of a latitude or
+    /// longitude.
+    pub(crate) const fn push_dms(self, dms: Dms) -> Frame {
+        self.push_bytes(&dms.to_bytes())
+    }
+
+    /// Appends the date and time in the order of the set command.
+    pub(crate) const fn push_datetime(self, datetime: DateTime) -> Frame {
+        self.push_bytes(&datetime.to_bytes())
+    }
+
+    /// Checks the number of response bytes expected before the ack.
+    pub(crate) const fn expect(mut self, response_len: usize) -> Frame {
+        self.overflow |= response_len > MAX_RESPONSE;
+        self
+    }
+
+    /// Gets the bytes of the frame, `None` if they exceeded the capacity.
+    pub(crate) const fn bytes(&self) -> Option<&[u8]> {
+        if self.overflow {
+            return None;
+        }
+        Some(self.bytes.split_at(self.len).0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::Command;
+    use crate::types::{Hemisphere, TrackingMode};
+
+    #[test]
+    fn plain_commands() {
+        assert_eq!(Frame::new(b'M').bytes(), Some(&b"M"[..]));
+        assert_eq!(Frame::new(b'E').expect(9).bytes(), Some(&b"E"[..]));
+        assert_eq!(
+            Frame::new(b'T').push(TrackingMode::EQNorth.bits()).bytes(),
+            Some(&b"T\x02"[..])
+        );
+        assert_eq!(
+            Frame::new(b'K').push(0x42).expect(1).bytes(),
+            Some(&b"K\x42"[..])
+        );
+    }
+
+    #[test]
+    fn location_command() {
+        let latitude = Dms {
+            degrees: 47,
+            minutes: 12,
+            seconds: 34,
+            hemisphere: Hemisphere::North,
+        };
+        let longitude = Dms {
+            degrees: 8,
+            minutes: 32,
+            seconds: 7,
+            hemisphere: Hemisphere::West,
+        };
+        let frame = Frame::new(b'W').push_dms(latitude).push_dms(longitude);
+        assert_eq!(
+            frame.bytes(),
+            Some(&b"W\x2f\x0c\x22\x00\x08\x20\x07\x01"[..])
+        );
+    }
+
+    #[test]
+    fn datetime_command() {
+        let datetime = DateTime {
+            hour: 21,
+            minutes: 30,
+            seconds: 5,
+            zone: -5,
+            daylight_saving: true,
+            year: 24,
+            month: 10,
+            day: 31,
+        };
+        let frame = Frame::new(b'H').push_datetime(datetime);
+        assert_eq!(
+            frame.bytes(),
+            Some(&b"H\x15\x1e\x05\x0a\x1f\x18\xfb\x01"[..])
+        );
+    }
+
+    #[test]
+    fn pass_through_commands() {
+        let frame =
+            Frame::pass_through(Device::AzmRaMotor, Command::GetDeviceVersion.bits(), &[], 2);
+        assert_eq!(frame.bytes(), Some(&b"P\x01\x10\xfe\x00\x00\x00\x02"[..]));
+
+        let frame = Frame::pass_through(
+            Device::AltDecMotor,
+            Command::SetNegativeVariableRate.bits(),
+            &[0x01, 0x2c],
+            0,
+        );
+        assert_eq!(frame.bytes(), Some(&b"P\x03\x11\x07\x01\x2c\x00\x00"[..]));
+
+        let frame = Frame::pass_through(
+            Device::AltDecMotor,
+            Command::GetDeviceVersion.bits(),
+            &[0; 4],
+            2,
+        );
+        assert_eq!(frame.bytes(), None);
+    }
+
+    #[test]
+    fn capacity() {
+        let frame = Frame::new(b'x').push_bytes(&[0; MAX_COMMAND - 1]);
+        assert_eq!(frame.bytes().map(<[u8]>::len), Some(MAX_COMMAND));
+        assert_eq!(frame.push(0).bytes(), None);
+        assert_eq!(Frame::new(b'x').expect(MAX_RESPONSE + 1).bytes(), None);
+    }
+}
@@ -0,0 +1,31 @@
+//! Entry points of the fuzz targets in `fuzz/`, not part of the stable API
+
+pub use crate::parse::*;
+
+use crate::command::encode_fraction;
+use crate::types::{DateTime, Location};
+
+/// Encodes a position like the goto and sync commands, the inverse of
+/// [`position`].
+pub fn encode_position(first: f32, second: f32, precise: bool) -> ([u8; MAX_RESPONSE], usize) {
+    let mut first_hex = [0u8; 8];
+    let mut second_hex = [0u8; 8];
+    let first = encode_fraction(first, precise, &mut first_hex);
+    let second = encode_fraction(second, precise, &mut second_hex);
+
+    let mut data = [0u8; MAX_RESPONSE];
+    data[..first.len()].copy_from_slice(first);
+    data[first.len()] = b',';
+    data[first.len() + 1..first.len() * 2 + 1].copy_from_slice(second);
+    (data, first.len() * 2 + 1)
+}
+
+/// Encodes a location like the set command, the inverse of [`location`].
+pub fn encode_location(location: Location) -> [u8; 8] {
+    location.to_bytes()
+}
+
+/// Encodes a date and time like the set command, the inverse of [`datetime`].
+pub fn encode_datetime(datetime: DateTime) -> [u8; 8] {
+    datetime.to_bytes()
+}
@@ -0,0 +1,3651 @@
+use core::fmt;
+
+use embedded_hal::blocking::delay::DelayMs;
+use embedded_hal::blocking::serial::write::Default;
+use embedded_hal::serial;
+use nb::block;
+
+#[cfg(feature = "astro")]
+use crate::astro;
+use crate::battery;
+use crate::command::*;
+#[cfg(feature = "float")]
+use crate::config::{MountConfig, RestoreReport};
+use crate::diagnostics::{Diagnostics, Event, Record};
+use crate::error::{Context, Error};
+use crate::frame::Frame;
+#[cfg(feature = "float")]
+use crate::joystick::JoystickMapper;
+use crate::parse;
+#[cfg(feature = "float")]
+use crate::pe::{PeRecorder, PeStats};
+use crate::protocol::{
+    encode_command, parse_response, Axes, Counts, ParseError, Request, Response,
+};
+use crate::protocol::{MAX_COMMAND, MAX_RESPONSE};
+#[cfg(feature = "float")]
+use crate::queue::{QueueEntry, TargetQueue, Visit};
+use crate::types::*;
+
+const DRAIN_LIMIT: usize = 64;
+const HOME_POLL_INTERVAL_MS: u32 = 500;
+const FOCUSER_POLL_INTERVAL_MS: u32 = 500;
+/// Interval of the reads while [`NexStar::release`] drains the receive side
+const RELEASE_POLL_INTERVAL_MS: u32 = 10;
+/// Time without a byte after which [`NexStar::release`] stops draining
+const RELEASE_QUIET_MS: u32 = 50;
+/// Bytes echoed by [`NexStar::probe_link`]
+const LINK_PROBE_BYTES: [u8; 4] = [0x00, b'#', 0x5A, 0xA5];
+/// Time [`NexStar::probe_link`] waits for each echo
+const LINK_PROBE_TIMEOUT_MS: u32 = 200;
+const LINK_PROBE_POLL_INTERVAL_MS: u32 = 1;
+/// Time [`NexStar::gps_state`] waits for each byte of the answer
+const GPS_STATE_TIMEOUT_MS: u32 = 500;
+/// Time the StarSense detection waits for the ack of an answer whose build
+/// byte is `#`, above the latency timer of USB serial adapters (16 ms by
+/// default on FTDI)
+const STARSENSE_ACK_MS: u32 = 50;
+#[cfg(feature = "float")]
+const GOTO_POLL_INTERVAL_MS: u32 = 500;
+#[cfg(feature = "float")]
+const GOTO_TIMEOUT_MS: u32 = 300_000;
+#[cfg(feature = "float")]
+const PARK_TOLERANCE: f32 = 0.5;
+/// Longest wait of a slew rate calibration between checks of the abort hook
+#[cfg(feature = "float")]
+const CALIBRATION_ABORT_INTERVAL_MS: u32 = 100;
+/// Interval of the position polls of a backlash measurement
+#[cfg(feature = "float")]
+const BACKLASH_POLL_INTERVAL_MS: u32 = 50;
+/// Arcseconds an axis has to move for a backlash measurement to take it as
+/// moving, several counts of the 24 bit position
+#[cfg(feature = "float")]
+const BACKLASH_THRESHOLD_ARCSEC: f64 = 2.0;
+
+/// The motor controllers in the order of the per motor settings
+#[cfg(feature = "float")]
+const MOTORS: [Device; 2] = [Device::AzmRaMotor, Device::AltDecMotor];
+
+/// Accumulation of a least-squares line fit
+#[cfg(feature = "float")]
+#[derive(Default)]
+struct Slope {
+    n: f64,
+    t: f64,
+    tt: f64,
+    x: f64,
+    tx: f64,
+}
+
+#[cfg(feature = "float")]
+impl Slope {
+    fn add(&mut self, t: f64, x: f64) {
+        self.n += 1.0;
+        self.t += t;
+        self.tt += t * t;
+        self.x += x;
+        self.tx += t * x;
+    }
+
+    /// Gets the slope of the fitted line, `None` before two distinct times.
+    fn slope(&self) -> Option<f64> {
+        let spread = self.n * self.tt - self.t * self.t;
+        if self.n < 2.0 || spread <= 0.0 {
+            return None;
+        }
+        Some((self.n * self.tx - self.t * self.x) / spread)
+    }
+}
+
+/// Turns the errors of commands the mount doesn't support into `None`.
+#[cfg(feature = "float")]
+fn supported<V, R, W>(result: Result<V, Error<R, W>>) -> Result<Option<V>, Error<R, W>> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(
+            Error::UnsupportedCommand(_)
+            | Error::UnsupportedFirmware { .. }
+            | Error::UnsupportedByModel(_),
+        ) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Writes a line of [`NexStar::write_status`], `--` if the value failed.
+fn write_status_line<V: fmt::Display, E>(
+    out: &mut dyn fmt::Write,
+    label: &str,
+    value: Result<V, E>,
+) -> fmt::Result {
+    match value {
+        Ok(value) => writeln!(out, "{:<9}{}", label, value),
+        Err(_) => writeln!(out, "{:<9}--", label),
+    }
+}
+
+/// Gets the absolute difference of two angles in degrees (0..180).
+#[cfg(feature = "float")]
+fn angle_between(a: f32, b: f32) -> f32 {
+    let diff = (a - b) % 360.0;
+    let diff = if diff < 0.0 { -diff } else { diff };
+    if diff > 180.0 {
+        360.0 - diff
+    } else {
+        diff
+    }
+}
+
+/// Driver of the Hand Controller (HC) protocol
+///
+/// The driver isn't `Clone`, even over transports which are: two drivers on
+/// one port interleave their commands and take each other's responses. Share
+/// one driver instead, by `&mut` within a task or with `shared::SharedNexStar`
+/// (feature `critical-section`) between tasks.
+///
+/// ```compile_fail
+/// # use embedded_hal::serial;
+/// #[derive(Clone)]
+/// struct Port;
+/// # impl serial::Read<u8> for Port {
+/// #     type Error = ();
+/// #     fn read(&mut self) -> nb::Result<u8, ()> { Err(nb::Error::WouldBlock) }
+/// # }
+/// # impl serial::Write<u8> for Port {
+/// #     type Error = ();
+/// #     fn write(&mut self, _: u8) -> nb::Result<(), ()> { Ok(()) }
+/// #     fn flush(&mut self) -> nb::Result<(), ()> { Ok(()) }
+/// # }
+///
+/// let nexstar = nexstar::NexStar::new(Port, Port);
+/// let _ = nexstar.clone();
+/// ```
+pub struct NexStar<T, U>
+where
+    T: serial::Read<u8>,
+    U: serial::Write<u8>,
+{
+    rx: T,
+    tx: U,
+    info: Option<MountInfo>,
+    check_firmware: bool,
+    check_capabilities: bool,
+    /// Picked by `connect` unless overridden
+    compat: Compat,
+    compat_override: Option<Compat>,
+    model: Option<Model>,
+    versions: Versions,
+    force_refresh: bool,
+    abort: Option<fn() -> bool>,
+    context: Context,
+    link_state: LinkState,
+    auto_resync: bool,
+    echo_suppression: bool,
+    timeout: Option<(fn() -> u32, u32)>,
+    /// Whether [`NexStar::wifi_reboot`] fails without an ack
+    wifi_reboot_needs_ack: bool,
+    probe: u8,
+    #[cfg(feature = "float")]
+    last_target: Option<Target>,
+    #[cfg(feature = "float")]
+    goto_floor: Option<f32>,
+    /// Temperature in °C and pressure in hPa of the refraction
+    #[cfg(feature = "astro")]
+    refraction: Option<(f32, f32)>,
+    pacing: Option<Pacing>,
+    watchdog: Option<Watchdog>,
+    /// Rates of the last successful `track_rates`, `None` if unknown
+    last_rates: Option<(i32, i32)>,
+    /// Whether rates other than zero sent by `track_rates` may be active
+    moving: bool,
+    diagnostics: Option<Diagnostics>,
+    /// Byte of the last keepalive
+    keepalive: u8,
+    health: Option<LinkHealth>,
+    /// Clock of the StarSense detection, see
+    /// [`NexStar::set_starsense_detection`]
+    starsense_detection: Option<fn() -> u32>,
+    /// Byte after a version answer which wasn't a second ack, read before
+    /// the receiving half
+    held: Option<u8>,
+}
+
+/// Minimum gap between two transactions, see [`NexStar::with_pacing`]
+#[derive(Copy, Clone)]
+struct Pacing {
+    delay: fn(u32),
+    clock: fn() -> u32,
+    min_gap_ms: u32,
+    /// Tick of the last byte received
+    last: Option<u32>,
+}
+
+/// Stops a slew which isn't refreshed in time, see
+/// [`NexStar::set_slew_watchdog`]
+#[derive(Copy, Clone)]
+struct Watchdog {
+    clock: fn() -> u32,
+    timeout_ms: u32,
+    /// Tick of the last slew command while the motors are moving
+    fed: Option<u32>,
+}
+
+impl<T, U> NexStar<T, U>
+where
+    T: serial::Read<u8>,
+    U: serial::Write<u8>,
+{
+    pub fn new(rx: T, tx: U) -> NexStar<T, U> {
+        NexStar {
+            rx,
+            tx,
+            info: None,
+            check_firmware: true,
+            check_capabilities: true,
+            compat: Compat::CURRENT,
+            compat_override: None,
+            model: None,
+            versions: Versions::default(),
+            force_refresh: false,
+            abort: None,
+            context: Context::Hc(0),
+            link_state: LinkState::Clean,
+            auto_resync: true,
+            echo_suppression: false,
+            timeout: None,
+            wifi_reboot_needs_ack: false,
+            probe: 0,
+            #[cfg(feature = "float")]
+            last_target: None,
+            #[cfg(feature = "float")]
+            goto_floor: None,
+            #[cfg(feature = "astro")]
+            refraction: None,
+            pacing: None,
+            watchdog: None,
+            last_rates: None,
+            moving: false,
+            diagnostics: None,
+            keepalive: 0,
+            health: None,
+            starsense_detection: None,
+            held: None,
+        }
+    }
+
+    /// Sets a hook which is checked by the waiting helpers (e.g.
+    /// [`NexStar::wait_for_goto`]) between polls; they stop with
+    /// [`Error::Aborted`] as soon as it returns `true`.
+    pub fn set_abort_hook(&mut self, hook: Option<fn() -> bool>) {
+        self.abort = hook;
+    }
+
+    /// Checks the link and queries the mount model and firmware versions.
+    ///
+    /// The result is cached and used to refuse commands the firmware doesn't
+    /// support with [`Error::UnsupportedFirmware`] instead of sending them.
+    /// The HC version is queried first to pick the [`Compat`] of legacy HCs,
+    /// unless set with [`NexStar::set_compat`], so the echo and the model
+    /// query are skipped if the HC lacks them. The cached model and versions
+    /// are queried again.
+    ///
+    /// With the `synscan` feature the format of the version answer tells a
+    /// SkyWatcher SynScan HC apart, whose motors are not queried. A StarSense
+    /// HC is only told apart with [`NexStar::set_starsense_detection`].
+    pub fn connect(&mut self) -> Result<MountInfo, Error<T::Error, U::Error>> {
+        self.invalidate_cache();
+        let hc_version = self.detect_compat()?;
+        let compat = self.compat();
+        if compat.echo {
+            self.echo(0x42)?;
+        }
+
+        let info = MountInfo {
+            hc_version,
+            model: if compat.model_query {
+                self.model()?
+            } else {
+                Model::Unknown(0)
+            },
+            azm_ra_version: self.motor_version(Device::AzmRaMotor)?,
+            alt_dec_version: self.motor_version(Device::AltDecMotor)?,
+        };
+        self.info = Some(info);
+
+        Ok(info)
+    }
+
+    /// Gets the mount identity cached by [`NexStar::connect`].
+    pub fn mount_info(&self) -> Option<MountInfo> {
+        self.info
+    }
+
+    /// Sets the protocol differences of the HC instead of picking them from
+    /// its version in [`NexStar::connect`], `None` picks them again.
+    ///
+    /// Commands refused before a [`NexStar::connect`] report the firmware
+    /// found as 0.0.
+    pub fn set_compat(&mut self, compat: Option<Compat>) {
+        self.compat_override = compat;
+    }
+
+    /// Lets [`NexStar::connect`] tell a StarSense HC apart by its version
+    /// answer, `clock` giving the time in milliseconds. `None` (the default)
+    /// turns the detection off.
+    ///
+    /// Experimental: the StarSense encodings are reconstructed from reports
+    /// instead of captures of a StarSense HC. Without the detection the
+    /// version answer of a StarSense HC fails `connect` with
+    /// [`Error::UnexpectedResponse`], unless [`Compat::STARSENSE`] is set with
+    /// [`NexStar::set_compat`].
+    ///
+    /// A build number of 35 is a `#`, which leaves the answer looking like a
+    /// NexStar one up to a second ack. After each NexStar answer the
+    /// detection therefore waits 50 ms for that ack.
+    pub fn set_starsense_detection(&mut self, clock: Option<fn() -> u32>) {
+        self.starsense_detection = clock;
+    }
+
+    /// Gets the protocol differences of the HC in use, [`Compat::CURRENT`]
+    /// before [`NexStar::connect`] unless set.
+    pub fn compat(&self) -> Compat {
+        self.compat_override.unwrap_or(self.compat)
+    }
+
+    /// Gets the model read by [`NexStar::model`] before without a query,
+    /// `None` if it wasn't read since the cache was invalidated.
+    pub fn cached_model(&self) -> Option<Model> {
+        self.model
+    }
+
+    /// Gets the versions read by [`NexStar::version`] and
+    /// [`NexStar::device_version`] before without a query.
+    pub fn cached_versions(&self) -> Versions {
+        self.versions
+    }
+
+    /// Forgets the cached model and versions, e.g. after the HC was
+    /// replaced or its firmware updated. The next queries read them again.
+    pub fn invalidate_cache(&mut self) {
+        self.model = None;
+        self.versions = Versions::default();
+    }
+
+    /// Makes [`NexStar::model`], [`NexStar::version`] and
+    /// [`NexStar::device_version`] query the mount on every call instead of
+    /// answering from the cache (disabled by default). The cache is still
+    /// updated.
+    pub fn set_force_refresh(&mut self, enabled: bool) {
+        self.force_refresh = enabled;
+    }
+
+    /// Enables or disables the firmware version checks (enabled by default).
+    ///
+    /// Disable them if your firmware reports odd versions but supports the
+    /// commands anyway. Without a prior [`NexStar::connect`] no checks are done.
+    pub fn set_firmware_checks(&mut self, enabled: bool) {
+        self.check_firmware = enabled;
+    }
+
+    /// Enables or disables the checks of the model capabilities (enabled by
+    /// default), see [`Model::capabilities`].
+    ///
+    /// Disable them to send e.g. the RTC queries to a model which isn't known
+    /// to have the device. Unknown models are never refused.
+    pub fn set_capability_checks(&mut self, enabled: bool) {
+        self.check_capabilities = enabled;
+    }
+
+    // Position commands
+    /// Gets the RA/Dec position.
+    ///
+    /// The position has a resolution of 16 bits per revolution (about 20
+    /// arcseconds); the precise variants use 24 bits (about 0.08 arcseconds).
+    ///
+    /// The Dec and the Alt of all position commands are signed like
+    /// [`NexStar::motor_angle`], so a mount past the pole reports them beyond
+    /// ±90°.
+    #[cfg(feature = "float")]
+    pub fn ra_dec(&mut self) -> Result<RaDec, Error<T::Error, U::Error>> {
+        let (ra, dec) = self.read_position(Axes::RaDec, false, fraction_to_degrees)?;
+        Ok(RaDec { ra, dec })
+    }
+
+    /// Gets the precise RA/Dec position.
+    #[cfg(feature = "float")]
+    pub fn precise_ra_dec(&mut self) -> Result<RaDec, Error<T::Error, U::Error>> {
+        self.require_firmware(PRECISE_GOTO_VERSION)?;
+        let (ra, dec) = self.read_position(Axes::RaDec, true, fraction_to_degrees)?;
+        Ok(RaDec { ra, dec })
+    }
+
+    /// Gets the Azm/Alt position.
+    #[cfg(feature = "float")]
+    pub fn azm_alt(&mut self) -> Result<AzmAlt, Error<T::Error, U::Error>> {
+        let (azm, alt) = self.read_position(Axes::AzmAlt, false, fraction_to_degrees)?;
+        Ok(AzmAlt { azm, alt })
+    }
+
+    /// Gets the precise Azm/Alt position.
+    #[cfg(feature = "float")]
+    pub fn precise_azm_alt(&mut self) -> Result<AzmAlt, Error<T::Error, U::Error>> {
+        self.require_firmware(PRECISE_AZM_ALT_VERSION)?;
+        let (azm, alt) = self.read_position(Axes::AzmAlt, true, fraction_to_degrees)?;
+        Ok(AzmAlt { azm, alt })
+    }
+
+    /// Gets the RA/Dec position in arcseconds.
+    ///
+    /// The integer variants of the position commands round to whole
+    /// arcseconds and don't use any floating point arithmetic.
+    pub fn ra_dec_arcsec(&mut self) -> Result<RaDecArcsec, Error<T::Error, U::Error>> {
+        let (ra, dec) = self.read_position(Axes::RaDec, false, fraction_to_arcsec)?;
+        Ok(RaDecArcsec { ra, dec })
+    }
+
+    /// Gets the precise RA/Dec position in arcseconds.
+    pub fn precise_ra_dec_arcsec(&mut self) -> Result<RaDecArcsec, Error<T::Error, U::Error>> {
+        self.require_firmware(PRECISE_GOTO_VERSION)?;
+        let (ra, dec) = self.read_position(Axes::RaDec, true, fraction_to_arcsec)?;
+        Ok(RaDecArcsec { ra, dec })
+    }
+
+    /// Gets the Azm/Alt position in arcseconds.
+    pub fn azm_alt_arcsec(&mut self) -> Result<AzmAltArcsec, Error<T::Error, U::Error>> {
+        let (azm, alt) = self.read_position(Axes::AzmAlt, false, fraction_to_arcsec)?;
+        Ok(AzmAltArcsec { azm, alt })
+    }
+
+    /// Gets the precise Azm/Alt position in arcseconds.
+    pub fn precise_azm_alt_arcsec(&mut self) -> Result<AzmAltArcsec, Error<T::Error, U::Error>> {
+        self.require_firmware(PRECISE_AZM_ALT_VERSION)?;
+        let (azm, alt) = self.read_position(Axes::AzmAlt, true, fraction_to_arcsec)?;
+        Ok(AzmAltArcsec { azm, alt })
+    }
+
+    /// Polls the precise RA/Dec position every `interval_ms`.
+    ///
+    /// See [`Positions`] on how to issue other commands between samples.
+    #[cfg(feature = "float")]
+    pub fn positions<'a, D: DelayMs<u32>>(
+        &'a mut self,
+        delay: &'a mut D,
+        interval_ms: u32,
+    ) -> Positions<'a, T, U, D, RaDec> {
+        Positions::new(self, delay, interval_ms, NexStar::precise_ra_dec)
+    }
+
+    /// Polls the precise Azm/Alt position every `interval_ms`.
+    ///
+    /// See [`Positions`] on how to issue other commands between samples.
+    #[cfg(feature = "float")]
+    pub fn azm_alt_positions<'a, D: DelayMs<u32>>(
+        &'a mut self,
+        delay: &'a mut D,
+        interval_ms: u32,
+    ) -> Positions<'a, T, U, D, AzmAlt> {
+        Positions::new(self, delay, interval_ms, NexStar::precise_azm_alt)
+    }
+
+    /// Logs up to `samples` positions in both frames every `interval_ms`
+    /// into `sink`.
+    ///
+    /// Each sample reads the precise RA/Dec and then the precise Azm/Alt
+    /// position and hands them to the sink with the milliseconds since the
+    /// first sample. A failed read is handed over in their place, the link
+    /// is resynced by the next read (see [`NexStar::set_auto_resync`]). The
+    /// sink returns `false` to stop, e.g. on the first error. Returns the
+    /// number of samples handed over, or [`Error::Aborted`] if the abort hook
+    /// fires between samples.
+    ///
+    /// The milliseconds are read from the clock of the timeout (see
+    /// [`NexStar::set_timeout`]). Without a timeout they are nominal, the sum
+    /// of the intervals waited, and run early by the time the reads and the
+    /// sink take.
+    #[cfg(feature = "float")]
+    pub fn log_positions<D, F>(
+        &mut self,
+        delay: &mut D,
+        interval_ms: u32,
+        samples: u32,
+        mut sink: F,
+    ) -> Result<u32, Error<T::Error, U::Error>>
+    where
+        D: DelayMs<u32>,
+        F: FnMut(u32, Result<(RaDec, AzmAlt), Error<T::Error, U::Error>>) -> bool,
+    {
+        let start = self.timeout.map(|(clock, _)| (clock, clock()));
+        let mut elapsed = 0u32;
+        for sample in 0..samples {
+            if sample > 0 {
+                if self.abort.is_some_and(|abort| abort()) {
+                    return Err(Error::Aborted);
+                }
+                delay.delay_ms(interval_ms);
+                elapsed = match start {
+                    Some((clock, start)) => clock().wrapping_sub(start),
+                    None => elapsed.saturating_add(interval_ms),
+                };
+            }
+
+            // the Azm/Alt read isn't sent after a failed RA/Dec read, the
+            // sample is lost either way
+            let position = self
+                .precise_ra_dec()
+                .and_then(|ra_dec| Ok((ra_dec, self.precise_azm_alt()?)));
+            if !sink(elapsed, position) {
+                return Ok(sample + 1);
+            }
+        }
+        Ok(samples)
+    }
+
+    /// Measures how fast the position drifts while tracking, e.g. to check
+    /// the polar alignment.
+    ///
+    /// Samples the precise RA/Dec position every `interval_ms` for
+    /// `duration_ms` and fits a least-squares line to each axis. Failed reads
+    /// are skipped and counted, the link is resynced by the next read (see
+    /// [`NexStar::set_auto_resync`]). With fewer than two good samples the
+    /// measurement fails with the error of the last failed read, or with
+    /// [`Error::TooFewSamples`] if the window is shorter than the interval.
+    /// The abort hook is checked between samples.
+    #[cfg(feature = "float")]
+    pub fn measure_drift<D: DelayMs<u32>>(
+        &mut self,
+        duration_ms: u32,
+        interval_ms: u32,
+        delay: &mut D,
+    ) -> Result<Drift, Error<T::Error, U::Error>> {
+        let samples = match duration_ms.checked_div(interval_ms) {
+            Some(intervals) => intervals.saturating_add(1),
+            None => return Err(Error::TooFewSamples),
+        };
+
+        let mut origin = None;
+        let (mut ra, mut dec) = (Slope::default(), Slope::default());
+        let mut skipped = 0;
+        let mut failure = None;
+        for sample in 0..samples {
+            if sample > 0 {
+                if self.abort.is_some_and(|abort| abort()) {
+                    return Err(Error::Aborted);
+                }
+                delay.delay_ms(interval_ms);
+            }
+
+            let position = match self.precise_ra_dec() {
+                Ok(position) => position,
+                Err(e) => {
+                    skipped += 1;
+                    failure = Some(e);
+                    continue;
+                }
+            };
+            // the RA relative to the first sample, across 0h
+            let origin = *origin.get_or_insert(position);
+            let ra_offset = (position.ra - origin.ra + 540.0) % 360.0 - 180.0;
+
+            let minutes = (sample as f64 * interval_ms as f64) / 60_000.0;
+            ra.add(minutes, ra_offset as f64 * 3600.0);
+            dec.add(minutes, (position.dec - origin.dec) as f64 * 3600.0);
+        }
+
+        match (ra.slope(), dec.slope(), failure) {
+            (Some(ra), Some(dec), _) => Ok(Drift {
+                ra: ra as f32,
+                dec: dec as f32,
+                samples: samples - skipped,
+                skipped,
+            }),
+            (_, _, Some(e)) => Err(e),
+            _ => Err(Error::TooFewSamples),
+        }
+    }
+
+    /// Records the periodic error of the RA drive while the mount tracks, see
+    /// [`pe`](crate::pe).
+    ///
+    /// Samples the AZM/RA motor position every interval of the recorder over
+    /// its worm periods and stores the residuals after the tracking rate into
+    /// it, replacing an earlier recording. Fails with
+    /// [`Error::BufferTooSmall`] before sending anything if the residuals
+    /// don't fit the recorder. A failed read ends the recording with its
+    /// error, keeping the residuals recorded so far. The abort hook is checked
+    /// between samples.
+    #[cfg(feature = "float")]
+    pub fn record_pe<D: DelayMs<u32>>(
+        &mut self,
+        recorder: &mut PeRecorder,
+        delay: &mut D,
+    ) -> Result<PeStats, Error<T::Error, U::Error>> {
+        let samples = recorder.samples();
+        if samples > recorder.capacity() {
+            return Err(Error::BufferTooSmall {
+                needed: samples,
+                got: recorder.capacity(),
+            });
+        }
+        recorder.clear();
+
+        let start = self.motor_position(Device::AzmRaMotor)?;
+        let (mut last, mut moved) = (start, 0.0);
+        recorder.record(0.0);
+        for _ in 1..samples {
+            if self.abort.is_some_and(|abort| abort()) {
+                return Err(Error::Aborted);
+            }
+            delay.delay_ms(recorder.interval_ms());
+
+            // summed up per interval, so a recording may span revolutions
+            let position = self.motor_position(Device::AzmRaMotor)?;
+            moved += motor_delta(last, position);
+            last = position;
+            recorder.record(moved);
+        }
+        Ok(recorder.stats())
+    }
+
+    /// Polls the precise RA/Dec position in arcseconds every `interval_ms`.
+    pub fn positions_arcsec<'a, D: DelayMs<u32>>(
+        &'a mut self,
+        delay: &'a mut D,
+        interval_ms: u32,
+    ) -> Positions<'a, T, U, D, RaDecArcsec> {
+        Positions::new(self, delay, interval_ms, NexStar::precise_ra_dec_arcsec)
+    }
+
+    /// Polls the precise Azm/Alt position in arcseconds every `interval_ms`.
+    pub fn azm_alt_positions_arcsec<'a, D: DelayMs<u32>>(
+        &'a mut self,
+        delay: &'a mut D,
+        interval_ms: u32,
+    ) -> Positions<'a, T, U, D, AzmAltArcsec> {
+        Positions::new(self, delay, interval_ms, NexStar::precise_azm_alt_arcsec)
+    }
+
+    /// Sets the minimum altitude in degrees of all GOTOs, `None` removes it.
+    ///
+    /// GOTOs to Azm/Alt positions below the floor fail with
+    /// [`Error::BelowAltitudeLimit`] before anything is sent, and so does
+    /// [`NexStar::park`]. With the `astro` feature RA/Dec positions are
+    /// checked as well, which queries the location and time of the HC first.
+    #[cfg(feature = "float")]
+    pub fn set_goto_floor(&mut self, min_alt: Option<f32>) {
+        self.goto_floor = min_alt;
+    }
+
+    /// Sets the temperature in °C and the pressure in hPa to refract the
+    /// altitudes of RA/Dec positions with, `None` (the default) uses the
+    /// geometric altitudes.
+    ///
+    /// The altitudes checked by [`NexStar::goto_ra_dec_checked`] and the GOTO
+    /// floor are then the apparent ones, which are up to about half a degree
+    /// higher close to the horizon, see [`astro::refraction_arcmin`]. Azm/Alt
+    /// positions are taken as apparent, the way the mount points.
+    #[cfg(feature = "astro")]
+    pub fn set_refraction(&mut self, conditions: Option<(f32, f32)>) {
+        self.refraction = conditions;
+    }
+
+    // GOTO commands
+    /// Starts a GOTO to the RA/Dec position.
+    #[cfg(feature = "float")]
+    pub fn goto_ra_dec(&mut self, target: RaDec) -> Result<(), Error<T::Error, U::Error>> {
+        self.require_firmware(GOTO_VERSION)?;
+        self.write_degrees(Some(Axes::RaDec), target.ra, target.dec, false)?;
+        self.last_target = Some(Target::RaDec(target));
+        Ok(())
+    }
+
+    /// Starts a GOTO to the RA/Dec position if it is above the minimum altitude.
+    ///
+    /// The altitude is calculated from the location, date and time set in the
+    /// Hand Controller (HC), so make sure they are correct.
+    #[cfg(feature = "astro")]
+    pub fn goto_ra_dec_checked(
+        &mut self,
+        target: RaDec,
+        min_alt: f32,
+    ) -> Result<(), Error<T::Error, U::Error>> {
+        let altitude = self.altitude(target)?;
+        if altitude < min_alt {
+            return Err(Error::BelowAltitudeLimit {
+                altitude,
+                limit: min_alt,
+            });
+        }
+
+        self.goto_ra_dec(target)
+    }
+
+    /// Starts a precise GOTO to the RA/Dec position.
+    #[cfg(feature = "float")]
+    pub fn precise_goto_ra_dec(&mut self, target: RaDec) -> Result<(), Error<T::Error, U::Error>> {
+        self.require_firmware(PRECISE_GOTO_VERSION)?;
+        self.write_degrees(Some(Axes::RaDec), target.ra, target.dec, true)?;
+        self.last_target = Some(Target::RaDec(target));
+        Ok(())
+    }
+
+    /// Starts a GOTO to the Azm/Alt position.
+    #[cfg(feature = "float")]
+    pub fn goto_azm_alt(&mut self, target: AzmAlt) -> Result<(), Error<T::Error, U::Error>> {
+        self.require_firmware(GOTO_VERSION)?;
+        self.write_degrees(Some(Axes::AzmAlt), target.azm, target.alt, false)?;
+        self.last_target = Some(Target::AzmAlt(target));
+        Ok(())
+    }
+
+    /// Starts a precise GOTO to the Azm/Alt position.
+    #[cfg(feature = "float")]
+    pub fn precise_goto_azm_alt(
+        &mut self,
+        target: AzmAlt,
+    ) -> Result<(), Error<T::Error, U::Error>> {
+        self.require_firmware(PRECISE_AZM_ALT_VERSION)?;
+        self.write_degrees(Some(Axes::AzmAlt), target.azm, target.alt, true)?;
+        self.last_target = Some(Target::AzmAlt(target));
+        Ok(())
+    }
+
+    /// Starts a GOTO to the RA/Dec position in arcseconds.
+    pub fn goto_ra_dec_arcsec(
+        &mut self,
+        target: RaDecArcsec,
+    ) -> Result<(), Error<T::Error, U::Error>> {
+        self.require_firmware(GOTO_VERSION)?;
+        self.write_arcsec(Some(Axes::RaDec), target.ra, target.dec, false)?;
+        #[cfg(feature = "float")]
+        {
+            self.last_target = Some(Target::RaDec(target.into()));
+        }
+        Ok(())
+    }
+
+    /// Starts a precise GOTO to the RA/Dec position in arcseconds.
+    pub fn precise_goto_ra_dec_arcsec(
+        &mut self,
+        target: RaDecArcsec,
+    ) -> Result<(), Error<T::Error, U::Error>> {
+        self.require_firmware(PRECISE_GOTO_VERSION)?;
+        self.write_arcsec(Some(Axes::RaDec), target.ra, target.dec, true)?;
+        #[cfg(feature = "float")]
+        {
+            self.last_target = Some(Target::RaDec(target.into()));
+        }
+        Ok(())
+    }
+
+    /// Starts a GOTO to the Azm/Alt position in arcseconds.
+    pub fn goto_azm_alt_arcsec(
+        &mut self,
+        target: AzmAltArcsec,
+    ) -> Result<(), Error<T::Error, U::Error>> {
+        self.require_firmware(GOTO_VERSION)?;
+        self.write_arcsec(Some(Axes::AzmAlt), target.azm, target.alt, false)?;
+        #[cfg(feature = "float")]
+        {
+            self.last_target = Some(Target::AzmAlt(target.into()));
+        }
+        Ok(())
+    }
+
+    /// Starts a precise GOTO to the Azm/Alt position in arcseconds.
+    pub fn precise_goto_azm_alt_arcsec(
+        &mut self,
+        target: AzmAltArcsec,
+    ) -> Result<(), Error<T::Error, U::Error>> {
+        self.require_firmware(PRECISE_AZM_ALT_VERSION)?;
+        self.write_arcsec(Some(Axes::AzmAlt), target.azm, target.alt, true)?;
+        #[cfg(feature = "float")]
+        {
+            self.last_target = Some(Target::AzmAlt(target.into()));
+        }
+        Ok(())
+    }
+
+    /// Starts a precise GOTO to the target.
+    #[cfg(feature = "float")]
+    pub fn goto(&mut self, target: Target) -> Result<(), Error<T::Error, U::Error>> {
+        match target {
+            Target::RaDec(target) => self.precise_goto_ra_dec(target),
+            Target::AzmAlt(target) => self.precise_goto_azm_alt(target),
+        }
+    }
+
+    /// GOTOs the target always approaching it from the same direction.
+    ///
+    /// Moves to a point `overshoot_arcmin` before the target on both axes
+    /// first, waits for it and then GOTOs the target itself, so the gears take
+    /// up the backlash the same way every time.
+    #[cfg(feature = "float")]
+    pub fn goto_with_approach<D: DelayMs<u32>>(
+        &mut self,
+        target: Target,
+        overshoot_arcmin: f32,
+        approach: Approach,
+        delay: &mut D,
+    ) -> Result<(), Error<T::Error, U::Error>> {
+        let overshoot = overshoot_arcmin / 60.0;
+        let start = match approach {
+            Approach::Positive => target.offset(-overshoot),
+            Approach::Negative => target.offset(overshoot),
+        };
+
+        self.goto(start)?;
+        self.wait_for_goto(delay, GOTO_POLL_INTERVAL_MS, GOTO_TIMEOUT_MS)?;
+        self.goto(target)?;
+        self.wait_for_goto(delay, GOTO_POLL_INTERVAL_MS, GOTO_TIMEOUT_MS)
+    }
+
+    /// Visits the entries of the queue from its next one on.
+    ///
+    /// GOTOs each target, approaching it if the entry asks for it, waits up
+    /// to 5 minutes for the mount to arrive and calls `hook` with the index
+    /// and the entry. Returns once all entries were visited, with
+    /// [`Error::Aborted`] as soon as the hook returns [`Visit::Abort`], or
+    /// with the error of a failed GOTO. The queue records which entry failed
+    /// and another run continues where this one stopped, see the
+    /// [`queue`](crate::queue) module.
+    #[cfg(feature = "float")]
+    pub fn run_queue<D, F, const N: usize>(
+        &mut self,
+        queue: &mut TargetQueue<N>,
+        delay: &mut D,
+        mut hook: F,
+    ) -> Result<(), Error<T::Error, U::Error>>
+    where
+        D: DelayMs<u32>,
+        F: FnMut(usize, QueueEntry) -> Visit,
+    {
+        while let Some((index, entry)) = queue.next_entry() {
+            let arrived = match entry.approach {
+                Some((approach, overshoot_arcmin)) => {
+                    self.goto_with_approach(entry.target, overshoot_arcmin, approach, delay)
+                }
+                None => self.goto(entry.target).and_then(|_| {
+                    self.wait_for_goto(delay, GOTO_POLL_INTERVAL_MS, GOTO_TIMEOUT_MS)
+                }),
+            };
+            queue.record(arrived.is_ok());
+            arrived?;
+
+            if hook(index, entry) == Visit::Abort {
+                return Err(Error::Aborted);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Gets the target of the last GOTO started, `None` before the first one.
+    #[cfg(feature = "float")]
+    pub fn last_target(&self) -> Option<Target> {
+        self.last_target
+    }
+
+    /// Checks whether the mount points within `tolerance_arcsec` of the last
+    /// GOTO target.
+    ///
+    /// Reads the precise position in the frame of the target and compares the
+    /// angular [`separation`], so the RA/Azm wrap and the poles are handled.
+    /// Returns `false` if no GOTO was started yet.
+    #[cfg(feature = "float")]
+    pub fn on_target(&mut self, tolerance_arcsec: f32) -> Result<bool, Error<T::Error, U::Error>> {
+        let distance = match self.last_target {
+            Some(Target::RaDec(target)) => self.precise_ra_dec()?.separation(target),
+            Some(Target::AzmAlt(target)) => self.precise_azm_alt()?.separation(target),
+            None => return Ok(false),
+        };
+
+        Ok(distance * 3600.0 <= tolerance_arcsec)
+    }
+
+    /// Waits until the GOTO in progress is complete.
+    ///
+    /// Polls the GOTO state every `poll_interval_ms` and fails with
+    /// [`Error::Timeout`] if the GOTO isn't complete after `max_wait_ms`. The
+    /// GOTO is cancelled if the abort hook fires.
+    pub fn wait_for_goto<D: DelayMs<u32>>(
+        &mut self,
+        delay: &mut D,
+        poll_interval_ms: u32,
+        max_wait_ms: u32,
+    ) -> Result<(), Error<T::Error, U::Error>> {
+        let result = self.poll_until(delay, poll_interval_ms, max_wait_ms, |nexstar| {
+            nexstar.is_goto_in_progress().map(|active| !active)
+        });
+
+        if let Err(Error::Aborted) = result {
+            self.cancel_goto()?;
+        }
+        result
+    }
+
+    /// Cancels the GOTO in progress.
+    pub fn cancel_goto(&mut self) -> Result<(), Error<T::Error, U::Error>> {
+        self.transact(Request::CancelGoto)?;
+        Ok(())
+    }
+
+    // Sync commands
+    /// Syncs the mount to the RA/Dec position.
+    #[cfg(feature = "float")]
+    pub fn sync_ra_dec(&mut self, position: RaDec) -> Result<(), Error<T::Error, U::Error>> {
+        self.require_firmware(SYNC_VERSION)?;
+        self.write_degrees(None, position.ra, position.dec, false)
+    }
+
+    /// Syncs the mount to the precise RA/Dec position.
+    #[cfg(feature = "float")]
+    pub fn precise_sync_ra_dec(
+        &mut self,
+        position: RaDec,
+    ) -> Result<(), Error<T::Error, U::Error>> {
+        self.require_firmware(PRECISE_SYNC_VERSION)?;
+        self.write_degrees(None, position.ra, position.dec, true)
+    }
+
+    /// Syncs the mount to the RA/Dec position in arcseconds.
+    pub fn sync_ra_dec_arcsec(
+        &mut self,
+        position: RaDecArcsec,
+    ) -> Result<(), Error<T::Error, U::Error>> {
+        self.require_firmware(SYNC_VERSION)?;
+        self.write_arcsec(None, position.ra, position.dec, false)
+    }
+
+    /// Syncs the mount to the precise RA/Dec position in arcseconds.
+    pub fn precise_sync_ra_dec_arcsec(
+        &mut self,
+        position: RaDecArcsec,
+    ) -> Result<(), Error<T::Error, U::Error>> {
+        self.require_firmware(PRECISE_SYNC_VERSION)?;
+        self.write_arcsec(None, position.ra, position.dec, true)
+    }
+
+    // Tracking commands
+    /// Gets the tracking mode.
+    ///
+    /// SynScan HCs have a single equatorial mode and report the PEC mode,
+    /// both as [`TrackingMode::EQNorth`].
+    pub fn tracking_mode(&mut self) -> Result<TrackingMode, Error<T::Error, U::Error>> {
+        match self.transact(Request::GetTrackingMode)? {
+            Response::TrackingMode(TrackingMode::EQSouth) if self.is_synscan() => {
+                Ok(TrackingMode::EQNorth)
+            }
+            Response::TrackingMode(mode) => Ok(mode),
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    /// Sets the tracking mode.
+    ///
+    /// SynScan HCs pick the hemisphere from the location, both equatorial
+    /// modes set their single one.
+    pub fn set_tracking_mode(
+        &mut self,
+        mode: TrackingMode,
+    ) -> Result<(), Error<T::Error, U::Error>> {
+        let mode = match mode {
+            TrackingMode::EQSouth if self.is_synscan() => TrackingMode::EQNorth,
+            mode => mode,
+        };
+        self.transact(Request::SetTrackingMode(mode))?;
+        Ok(())
+    }
+
+    /// Drives the AZM/RA Motor at a custom tracking rate.
+    ///
+    /// The HC keeps issuing its own tracking rate while a tracking mode is
+    /// active, so set the tracking mode to [`TrackingMode::Off`] first. Setting a
+    /// tracking mode afterwards hands tracking back to the HC.
+    #[cfg(feature = "float")]
+    pub fn set_custom_tracking(
+        &mut self,
+        rate: TrackingRate,
+    ) -> Result<(), Error<T::Error, U::Error>> {
+        self.slew_variable(Device::AzmRaMotor, rate.arcsec_per_sec())
+    }
+
+    /// Stops the AZM/RA Motor started by [`NexStar::set_custom_tracking`].
+    ///
+    /// The HC doesn't resume tracking on its own; use
+    /// [`NexStar::set_tracking_mode`] for that.
+    #[cfg(feature = "float")]
+    pub fn stop_custom_tracking(&mut self) -> Result<(), Error<T::Error, U::Error>> {
+        self.slew_variable(Device::AzmRaMotor, 0.0)
+    }
+
+    /// Drives a motor at a guide rate in multiples of the sidereal rate, e.g.
+    /// the King rate or a rate compensating a measured drift.
+    ///
+    /// The motor controllers of the first generation take the rate with the
+    /// 16 bit encoding of the variable rate slews, in quarter arcsec/s. The
+    /// second generation and the controllers of the Evolution and CGX take a
+    /// 24 bit rate in 1/1024 arcsec/s. The board variant is queried first,
+    /// see [`MotorModel::has_fine_guiderate`]; controllers predating the
+    /// query get the first generation encoding. Rates beyond the encoding
+    /// saturate.
+    ///
+    /// Like [`NexStar::set_custom_tracking`], the HC overrides the rate while
+    /// a tracking mode is active. The controllers can't be queried for the
+    /// rate. Fails with [`Error::InvalidSlewRate`] before sending anything if
+    /// the rate isn't finite.
+    #[cfg(feature = "float")]
+    pub fn set_guiderate(
+        &mut self,
+        device: Device,
+        rate: GuideRate,
+    ) -> Result<(), Error<T::Error, U::Error>> {
+        if !rate.0.is_finite() {
+            return Err(Error::InvalidSlewRate);
+        }
+        let fine = match self.motor_model(device) {
+            Ok(model) => model.has_fine_guiderate(),
+            Err(Error::UnsupportedCommand(_)) => false,
+            Err(e) => return Err(e),
+        };
+
+        let request = if fine {
+            let (cmd, data) = fine_guiderate(rate.arcsec_per_sec());
+            Request::pass_through(device, cmd, data, 0)
+        } else {
+            let (cmd, data) = variable_rate(rate.arcsec_per_sec());
+            Request::pass_through(device, cmd, data, 0)
+        };
+        self.transact(request)?;
+        Ok(())
+    }
+
+    // Slew commands
+    /// Drives both motors at independent signed rates in arcsec/s.
+    ///
+    /// Meant to be called repeatedly (e.g. to follow a satellite), each call
+    /// writes one pass-through frame per axis and only reads the acks.
+    pub fn track_rates(
+        &mut self,
+        azm_arcsec_per_sec: i32,
+        alt_arcsec_per_sec: i32,
+    ) -> Result<(), Error<T::Error, U::Error>> {
+        // a failure in between leaves the motors in an unknown state
+        self.last_rates = None;
+        self.moving |= azm_arcsec_per_sec != 0 || alt_arcsec_per_sec != 0;
+
+        let (cmd, rate) = variable_rate_quarters(azm_arcsec_per_sec.saturating_mul(4));
+        self.transact(Request::pass_through(Device::AzmRaMotor, cmd, rate, 0))?;
+
+        let (cmd, rate) = variable_rate_quarters(alt_arcsec_per_sec.saturating_mul(4));
+        self.transact(Request::pass_through(Device::AltDecMotor, cmd, rate, 0))?;
+
+        if let Some(watchdog) = &mut self.watchdog {
+            let moving = azm_arcsec_per_sec != 0 || alt_arcsec_per_sec != 0;
+            watchdog.fed = if moving {
+                Some((watchdog.clock)())
+            } else {
+                None
+            };
+        }
+        self.last_rates = Some((azm_arcsec_per_sec, alt_arcsec_per_sec));
+        self.moving = azm_arcsec_per_sec != 0 || alt_arcsec_per_sec != 0;
+        Ok(())
+    }
+
+    /// Stops both motors started by [`NexStar::track_rates`].
+    pub fn stop_tracking_rates(&mut self) -> Result<(), Error<T::Error, U::Error>> {
+        self.track_rates(0, 0)
+    }
+
+    /// Starts driving both motors at signed rates in arcsec/s like
+    /// [`NexStar::track_rates`] and returns a guard which stops them when
+    /// dropped.
+    ///
+    /// An early return or a panic unwinding past the guard thus stops the
+    /// motors instead of leaving the mount slewing, see [`SlewGuard`]. A
+    /// failed start stops them as well.
+    pub fn begin_slew(
+        &mut self,
+        azm_arcsec_per_sec: i32,
+        alt_arcsec_per_sec: i32,
+    ) -> Result<SlewGuard<'_, T, U>, Error<T::Error, U::Error>> {
+        let mut guard = SlewGuard {
+            nexstar: self,
+            active: true,
+        };
+        guard.set_rates(azm_arcsec_per_sec, alt_arcsec_per_sec)?;
+        Ok(guard)
+    }
+
+    /// Slews at the rates `mapper` maps the joystick axes to, see
+    /// [`NexStar::track_rates`].
+    ///
+    /// Nothing is sent if the rates didn't change since the last call, which
+    /// keeps a fast polling loop from flooding the link at 9600 baud. With the
+    /// slew watchdog enabled unchanged rates are sent again after half of its
+    /// timeout, so holding the stick doesn't trip it. Returns whether the
+    /// rates were sent.
+    #[cfg(feature = "float")]
+    pub fn slew_joystick(
+        &mut self,
+        azm: f32,
+        alt: f32,
+        mapper: &JoystickMapper,
+    ) -> Result<bool, Error<T::Error, U::Error>> {
+        let rates = (mapper.map(azm), mapper.map(alt));
+        let refresh = match self.watchdog {
+            Some(Watchdog {
+                clock,
+                timeout_ms,
+                fed: Some(fed),
+            }) => clock().wrapping_sub(fed) >= timeout_ms / 2,
+            _ => false,
+        };
+        if self.last_rates == Some(rates) && !refresh {
+            return Ok(false);
+        }
+
+        self.track_rates(rates.0, rates.1)?;
+        Ok(true)
+    }
+
+    /// Gets the position of a motor in counts of a revolution, 24 bits with
+    /// no notion of the alignment.
+    pub fn motor_position(&mut self, device: Device) -> Result<u32, Error<T::Error, U::Error>> {
+        let request = Request::pass_through(device, Command::GetPosition, [], 3);
+
+        match self.transact_or_unsupported(request)? {
+            Response::Data(data) => match parse::gps_angle(data.as_bytes()) {
+                Some(position) => Ok(position),
+                None => Err(self.unexpected()),
+            },
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    /// Gets the mechanical angle of a motor in degrees, see
+    /// [`NexStar::motor_position`].
+    ///
+    /// The angle is signed, positions past half a revolution are negative:
+    /// the first half, up to `0x80_0000` which is +180°, counts up from 0°
+    /// and the second half counts up from just above -180°. Angles beyond
+    /// ±90° are legitimate, e.g. the Dec axis of a GEM with the counterweight
+    /// up, and are not folded back; whether such a position is on the other
+    /// side of the pier is up to the caller.
+    #[cfg(feature = "float")]
+    pub fn motor_angle(&mut self, device: Device) -> Result<f32, Error<T::Error, U::Error>> {
+        Ok(fraction_to_degrees(self.motor_position(device)?, 24, true))
+    }
+
+    /// Gets the mechanical angle of a motor in arcseconds
+    /// (-648_000..=648_000), see [`NexStar::motor_angle`].
+    pub fn motor_angle_arcsec(&mut self, device: Device) -> Result<i32, Error<T::Error, U::Error>> {
+        Ok(fraction_to_arcsec(self.motor_position(device)?, 24, true))
+    }
+
+    /// Overwrites the position of a motor with the mechanical angle in
+    /// degrees (-180..=180), signed like [`NexStar::motor_angle`], e.g. to
+    /// recover after a clutch slipped.
+    ///
+    /// **This bypasses the alignment of the HC.** The HC maps the motor
+    /// positions to the sky with the alignment it did at the old positions,
+    /// so after the motor jumped every RA/Dec and Azm/Alt position, GOTO and
+    /// sync is off by the difference until the mount is aligned again. Only
+    /// set the position the axis really is at, with the mount not slewing.
+    ///
+    /// Fails with [`Error::InvalidPosition`] before sending anything if the
+    /// angle is out of range.
+    #[cfg(feature = "float")]
+    pub fn set_motor_position(
+        &mut self,
+        device: Device,
+        degrees: f32,
+    ) -> Result<(), Error<T::Error, U::Error>> {
+        if !(-180.0..=180.0).contains(&degrees) {
+            return Err(Error::InvalidPosition);
+        }
+        self.write_motor_position(device, degrees_to_fraction(degrees, 24))
+    }
+
+    /// Overwrites the position of a motor with the mechanical angle in
+    /// arcseconds (-648_000..=648_000), see [`NexStar::set_motor_position`].
+    pub fn set_motor_position_arcsec(
+        &mut self,
+        device: Device,
+        arcsec: i32,
+    ) -> Result<(), Error<T::Error, U::Error>> {
+        if !(-ARCSEC_PER_REVOLUTION / 2..=ARCSEC_PER_REVOLUTION / 2).contains(&arcsec) {
+            return Err(Error::InvalidPosition);
+        }
+        self.write_motor_position(device, arcsec_to_fraction(arcsec, 24))
+    }
+
+    /// Measures the rate a motor actually moves at when slewing at a variable
+    /// rate in arcsec/s, e.g. to correct the rates of a worn gear.
+    ///
+    /// Reads the motor position, slews for `duration_ms`, stops and reads the
+    /// position again, the measured rate is signed like the commanded one. The
+    /// motors accelerate and the stop takes a command to arrive, so longer
+    /// durations give better measurements. A zero duration fails with
+    /// [`Error::InvalidSlewRate`] before sending anything.
+    ///
+    /// The abort hook ends the slew early with [`Error::Aborted`]. Once the
+    /// slew was commanded the motor is stopped whatever fails, and the first
+    /// error is returned.
+    #[cfg(feature = "float")]
+    pub fn calibrate_slew_rate<D: DelayMs<u32>>(
+        &mut self,
+        device: Device,
+        arcsec_per_sec: f32,
+        duration_ms: u32,
+        delay: &mut D,
+    ) -> Result<f32, Error<T::Error, U::Error>> {
+        if duration_ms == 0 {
+            return Err(Error::InvalidSlewRate);
+        }
+
+        let start = self.motor_position(device)?;
+        let slewed = match self.slew_variable(device, arcsec_per_sec) {
+            Ok(()) => self.wait_or_abort(delay, duration_ms),
+            Err(e) => Err(e),
+        };
+        let stopped = self.slew_variable(device, 0.0);
+        slewed?;
+        stopped?;
+        let end = self.motor_position(device)?;
+
+        let arcsec = motor_delta(start, end);
+        Ok((arcsec * 1000.0 / duration_ms as f64) as f32)
+    }
+
+    /// Measures the play of a motor per direction, e.g. to set the backlash
+    /// correction with [`NexStar::set_backlash`]:
+    ///
+    /// ```ignore
+    /// let measured = nexstar.measure_backlash(Device::AltDecMotor, 60.0, &mut delay)?;
+    /// nexstar.set_backlash(Device::AltDecMotor, measured.setting())?;
+    /// ```
+    ///
+    /// Slews at the variable rate in arcsec/s in the negative direction to
+    /// take up the play, then reverses into the positive and again into the
+    /// negative direction. After each reversal the motor position is polled
+    /// until the axis moves, the play is the distance the motor ran in the
+    /// meantime. The correction should be off while measuring.
+    ///
+    /// Each phase takes at most the time to run through the play of the
+    /// largest correction at the rate, a play beyond fails with
+    /// [`Error::Timeout`]. A zero rate fails with [`Error::InvalidSlewRate`]
+    /// before sending anything. The abort hook ends the measurement early with
+    /// [`Error::Aborted`]. Once a slew was commanded the motor is stopped
+    /// whatever fails, and the first error is returned.
+    #[cfg(feature = "float")]
+    pub fn measure_backlash<D: DelayMs<u32>>(
+        &mut self,
+        device: Device,
+        arcsec_per_sec: f32,
+        delay: &mut D,
+    ) -> Result<BacklashMeasurement, Error<T::Error, U::Error>> {
+        let rate = arcsec_per_sec.abs();
+        if rate == 0.0 || rate.is_nan() {
+            return Err(Error::InvalidSlewRate);
+        }
+        let max = Backlash::MAX as f32 * Backlash::ARCSEC_PER_STEP;
+        let timeout_ms = (max / rate * 1000.0) as u32;
+
+        let measured = match self.slew_variable(device, -rate) {
+            Ok(()) => self.wait_or_abort(delay, timeout_ms).and_then(|()| {
+                Ok(BacklashMeasurement {
+                    positive: self.dead_band(device, rate, timeout_ms, delay)?,
+                    negative: self.dead_band(device, -rate, timeout_ms, delay)?,
+                })
+            }),
+            Err(e) => Err(e),
+        };
+        let stopped = self.slew_variable(device, 0.0);
+        let measured = measured?;
+        stopped?;
+        Ok(measured)
+    }
+
+    /// Caps the GOTO slew rate of a motor, e.g. to reduce vibration with a
+    /// heavy payload.
+    ///
+    /// The rate is in arcsec/s (1..=16_383, about 4.5°/s) and is set per axis,
+    /// `device` is the AZM/RA or ALT/DEC motor. Fails with
+    /// [`Error::InvalidSlewRate`] before sending anything if the rate is out of
+    /// range.
+    ///
+    /// The cap isn't persistent, the motor controllers forget it on a power
+    /// cycle. Motor controllers without the command fail with
+    /// [`Error::UnsupportedCommand`].
+    pub fn set_max_slew_rate(
+        &mut self,
+        device: Device,
+        arcsec_per_sec: u32,
+    ) -> Result<(), Error<T::Error, U::Error>> {
+        let rate = encode_max_rate(arcsec_per_sec).ok_or(Error::InvalidSlewRate)?;
+
+        let set = Request::pass_through(device, Command::SetMaxRate, rate, 0);
+        self.transact_or_unsupported(set)?;
+        let enable = Request::pass_through(device, Command::EnableMaxRate, [1], 0);
+        self.transact_or_unsupported(enable)?;
+        Ok(())
+    }
+
+    /// Gets the maximum GOTO slew rate of a motor in arcsec/s, see
+    /// [`NexStar::set_max_slew_rate`].
+    pub fn max_slew_rate(&mut self, device: Device) -> Result<u32, Error<T::Error, U::Error>> {
+        let request = Request::pass_through(device, Command::GetMaxRate, [], 2);
+
+        match self.transact_or_unsupported(request)? {
+            Response::Data(data) => match *data.as_bytes() {
+                [high, low] => Ok(decode_max_rate([high, low])),
+                _ => Err(self.unexpected()),
+            },
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    /// Sets the backlash correction of a motor.
+    ///
+    /// Motor controllers without the command fail with
+    /// [`Error::UnsupportedCommand`].
+    pub fn set_backlash(
+        &mut self,
+        device: Device,
+        backlash: Backlash,
+    ) -> Result<(), Error<T::Error, U::Error>> {
+        let positive =
+            Request::pass_through(device, Command::SetPositiveBacklash, [backlash.positive], 0);
+        self.transact_or_unsupported(positive)?;
+        let negative =
+            Request::pass_through(device, Command::SetNegativeBacklash, [backlash.negative], 0);
+        self.transact_or_unsupported(negative)?;
+        Ok(())
+    }
+
+    /// Gets the backlash correction of a motor, see [`NexStar::set_backlash`].
+    pub fn backlash(&mut self, device: Device) -> Result<Backlash, Error<T::Error, U::Error>> {
+        Ok(Backlash {
+            positive: self.motor_setting(device, Command::GetPositiveBacklash)?,
+            negative: self.motor_setting(device, Command::GetNegativeBacklash)?,
+        })
+    }
+
+    /// Sets the autoguide rate of a motor in percent of the sidereal rate.
+    ///
+    /// Fails with [`Error::InvalidSlewRate`] before sending anything above 99%,
+    /// motor controllers without the command fail with
+    /// [`Error::UnsupportedCommand`].
+    pub fn set_autoguide_rate(
+        &mut self,
+        device: Device,
+        percent: u8,
+    ) -> Result<(), Error<T::Error, U::Error>> {
+        let rate = encode_autoguide_rate(percent).ok_or(Error::InvalidSlewRate)?;
+
+        let request = Request::pass_through(device, Command::SetAutoguideRate, [rate], 0);
+        self.transact_or_unsupported(request)?;
+        Ok(())
+    }
+
+    /// Gets the autoguide rate of a motor in percent of the sidereal rate,
+    /// rounded to the nearest one.
+    pub fn autoguide_rate(&mut self, device: Device) -> Result<u8, Error<T::Error, U::Error>> {
+        let rate = self.motor_setting(device, Command::GetAutoguideRate)?;
+        Ok(decode_autoguide_rate(rate))
+    }
+
+    /// Sends a timed guide pulse to a motor, `rate` in percent of the
+    /// sidereal rate with the sign giving the direction and `duration` in
+    /// 10 ms.
+    ///
+    /// Fails with [`Error::InvalidSlewRate`] beyond ±100% and, once
+    /// [`NexStar::connect`] read the motor firmware, with
+    /// [`Error::UnsupportedFirmware`] below MC 7.11 before sending anything,
+    /// see [`NexStar::set_firmware_checks`]. Poll [`NexStar::is_guiding`] for
+    /// the end of the pulse.
+    pub fn guide_pulse(
+        &mut self,
+        device: Device,
+        rate: i8,
+        duration: u8,
+    ) -> Result<(), Error<T::Error, U::Error>> {
+        if rate.unsigned_abs() > MAX_GUIDE_PULSE_RATE {
+            return Err(Error::InvalidSlewRate);
+        }
+        self.require_motor_firmware(device, GUIDE_PULSE_VERSION)?;
+
+        let request = Request::pass_through(device, Command::GuidePulse, [rate as u8, duration], 0);
+        self.transact_or_unsupported(request)?;
+        Ok(())
+    }
+
+    /// Gets whether a guide pulse of the motor is still running, see
+    /// [`NexStar::guide_pulse`].
+    pub fn is_guiding(&mut self, device: Device) -> Result<bool, Error<T::Error, U::Error>> {
+        self.device_flag(device, Command::IsGuiding)
+    }
+
+    // Homing commands
+    /// Starts driving the motor to its index switch.
+    ///
+    /// Only mounts with index switches (e.g. CPC, CGE, Evolution) support it,
+    /// others fail with [`Error::UnsupportedCommand`]. The seek runs in the
+    /// background: poll [`NexStar::index_found`] until the switch was found
+    /// and stop the motor with [`NexStar::stop_tracking_rates`] to abort it.
+    pub fn seek_index(&mut self, device: Device) -> Result<(), Error<T::Error, U::Error>> {
+        let request = Request::pass_through(device, Command::SeekIndex, [], 0);
+        self.transact_or_unsupported(request)?;
+        Ok(())
+    }
+
+    /// Starts driving the motor to its level switch, see
+    /// [`NexStar::seek_index`]. Poll [`NexStar::level_done`] for completion.
+    pub fn level_start(&mut self, device: Device) -> Result<(), Error<T::Error, U::Error>> {
+        let request = Request::pass_through(device, Command::LevelStart, [], 0);
+        self.transact_or_unsupported(request)?;
+        Ok(())
+    }
+
+    /// Gets whether the motor found its index switch.
+    pub fn index_found(&mut self, device: Device) -> Result<bool, Error<T::Error, U::Error>> {
+        self.device_flag(device, Command::AtIndex)
+    }
+
+    /// Gets whether the motor found its level switch.
+    pub fn level_done(&mut self, device: Device) -> Result<bool, Error<T::Error, U::Error>> {
+        self.device_flag(device, Command::LevelDone)
+    }
+
+    /// Drives both motors to their index switches and waits until both were
+    /// found.
+    ///
+    /// Polls the index state every 500 ms. If a switch isn't found within
+    /// `max_wait_ms` the motors are stopped and the call fails with
+    /// [`Error::HomeNotFound`], which tells which axes were found. The abort
+    /// hook stops the motors as well.
+    pub fn find_home<D: DelayMs<u32>>(
+        &mut self,
+        delay: &mut D,
+        max_wait_ms: u32,
+    ) -> Result<HomeStatus, Error<T::Error, U::Error>> {
+        self.seek_index(Device::AzmRaMotor)?;
+        self.seek_index(Device::AltDecMotor)?;
+
+        let mut status = HomeStatus {
+            azm_ra_found: false,
+            alt_dec_found: false,
+        };
+        let result = self.poll_until(delay, HOME_POLL_INTERVAL_MS, max_wait_ms, |nexstar| {
+            if !status.azm_ra_found {
+                status.azm_ra_found = nexstar.index_found(Device::AzmRaMotor)?;
+            }
+            if !status.alt_dec_found {
+                status.alt_dec_found = nexstar.index_found(Device::AltDecMotor)?;
+            }
+            Ok(status.azm_ra_found && status.alt_dec_found)
+        });
+
+        match result {
+            Ok(()) => Ok(status),
+            Err(Error::Timeout) => {
+                self.stop_tracking_rates()?;
+                Err(Error::HomeNotFound(status))
+            }
+            Err(Error::Aborted) => {
+                self.stop_tracking_rates()?;
+                Err(Error::Aborted)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    // Focuser commands
+    /// Gets whether the focus motor is moving, e.g. during a GOTO or its
+    /// calibration.
+    pub fn is_focuser_moving(&mut self) -> Result<bool, Error<T::Error, U::Error>> {
+        Ok(!self.device_flag(Device::Focuser, Command::SlewDone)?)
+    }
+
+    /// Gets the travel limits the focus motor found in its last
+    /// calibration, see [`NexStar::focuser_calibrate`].
+    pub fn focuser_limits(&mut self) -> Result<FocuserLimits, Error<T::Error, U::Error>> {
+        let request = Request::pass_through(Device::Focuser, Command::FocuserLimits, [], 8);
+
+        match self.transact_or_unsupported(request)? {
+            Response::Data(data) => match *data.as_bytes() {
+                [l0, l1, l2, l3, h0, h1, h2, h3] => Ok(FocuserLimits {
+                    low: u32::from_be_bytes([l0, l1, l2, l3]),
+                    high: u32::from_be_bytes([h0, h1, h2, h3]),
+                }),
+                _ => Err(self.unexpected()),
+            },
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    /// Calibrates the focus motor and gets the travel limits it found.
+    ///
+    /// The focus motor runs to both ends of its travel once, which it needs
+    /// to know before GOTOs to absolute positions. Fails with [`Error::Busy`]
+    /// before starting if the motor is moving. Polls the calibration every
+    /// 500 ms and fails with [`Error::Timeout`] if it didn't finish within
+    /// `max_wait_ms`, or with [`Error::Aborted`] if the abort hook asked to;
+    /// the focuser keeps calibrating in both cases.
+    pub fn focuser_calibrate<D: DelayMs<u32>>(
+        &mut self,
+        delay: &mut D,
+        max_wait_ms: u32,
+    ) -> Result<FocuserLimits, Error<T::Error, U::Error>> {
+        if self.is_focuser_moving()? {
+            return Err(Error::Busy);
+        }
+        let request = Request::pass_through(Device::Focuser, Command::FocuserCalibrate, [0], 0);
+        self.transact_or_unsupported(request)?;
+
+        self.poll_until(delay, FOCUSER_POLL_INTERVAL_MS, max_wait_ms, |nexstar| {
+            let request =
+                Request::pass_through(Device::Focuser, Command::FocuserCalibrationDone, [], 2);
+            match nexstar.transact_or_unsupported(request)? {
+                Response::Data(data) => match *data.as_bytes() {
+                    [done, _state] => Ok(done != 0),
+                    _ => Err(nexstar.unexpected()),
+                },
+                _ => Err(nexstar.unexpected()),
+            }
+        })?;
+        self.focuser_limits()
+    }
+
+    // Park commands
+    /// Parks the mount at the Azm/Alt position.
+    ///
+    /// Turns tracking off, moves to the position and verifies the mount arrived
+    /// within half a degree. The position is up to the caller to store, use
+    /// [`NexStar::unpark`] to resume tracking afterwards.
+    #[cfg(feature = "float")]
+    pub fn park<D: DelayMs<u32>>(
+        &mut self,
+        position: AzmAlt,
+        delay: &mut D,
+    ) -> Result<(), Error<T::Error, U::Error>> {
+        self.check_goto_floor(Some(Axes::AzmAlt), position.azm, position.alt)?;
+        self.set_tracking_mode(TrackingMode::Off)?;
+        self.goto_azm_alt(position)?;
+        self.wait_for_goto(delay, GOTO_POLL_INTERVAL_MS, GOTO_TIMEOUT_MS)?;
+
+        let parked = self.azm_alt()?;
+        if angle_between(parked.azm, position.azm) > PARK_TOLERANCE
+            || angle_between(parked.alt, position.alt) > PARK_TOLERANCE
+        {
+            return Err(Error::NotOnTarget);
+        }
+
+        Ok(())
+    }
+
+    /// Resumes tracking after [`NexStar::park`].
+    #[cfg(feature = "float")]
+    pub fn unpark(&mut self, tracking_mode: TrackingMode) -> Result<(), Error<T::Error, U::Error>> {
+        self.set_tracking_mode(tracking_mode)
+    }
+
+    // Time/Location Commands (Hand Control)
+    /// Gets the currently set location of the telescope.
+    #[cfg(feature = "float")]
+    pub fn location(&mut self) -> Result<Location, Error<T::Error, U::Error>> {
+        let (latitude, longitude) = self.location_dms()?;
+
+        match Location::from_dms(latitude, longitude) {
+            Some(location) => Ok(location),
+            None => Err(self.unexpected()),
+        }
+    }
+
+    /// Gets the currently set location as the degrees, minutes and seconds
+    /// of latitude and longitude stored by the HC.
+    ///
+    /// Unlike `NexStar::location` the values are exact, so they can be
+    /// displayed without rounding errors.
+    pub fn location_dms(&mut self) -> Result<(Dms, Dms), Error<T::Error, U::Error>> {
+        match self.transact(Request::GetLocation)? {
+            Response::Location {
+                latitude,
+                longitude,
+            } => Ok((latitude, longitude)),
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    /// Sets the location of the Hand Controller (HC).
+    #[cfg(feature = "float")]
+    pub fn set_location(&mut self, location: Location) -> Result<(), Error<T::Error, U::Error>> {
+        let (latitude, longitude) = location.to_dms();
+        self.transact(Request::SetLocation {
+            latitude,
+            longitude,
+        })?;
+        Ok(())
+    }
+
+    /// Sets the location from degrees, minutes and seconds, which are sent
+    /// without a conversion to decimal degrees.
+    ///
+    /// Fails with [`Error::InvalidLocation`] before sending anything if a
+    /// field is out of range or a hemisphere belongs to the other axis.
+    pub fn set_location_dms(
+        &mut self,
+        latitude: Dms,
+        longitude: Dms,
+    ) -> Result<(), Error<T::Error, U::Error>> {
+        if !latitude.is_latitude() || !longitude.is_longitude() {
+            return Err(Error::InvalidLocation);
+        }
+
+        self.transact(Request::SetLocation {
+            latitude,
+            longitude,
+        })?;
+        Ok(())
+    }
+
+    /// Gets the currently set date and time of the Hand Controller (HC).
+    pub fn datetime(&mut self) -> Result<DateTime, Error<T::Error, U::Error>> {
+        match self.transact(Request::GetDateTime)? {
+            Response::DateTime(datetime) => Ok(datetime),
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    /// Sets date and time of the Hand Controller (HC).
+    pub fn set_datetime(&mut self, datetime: DateTime) -> Result<(), Error<T::Error, U::Error>> {
+        self.transact(Request::SetDateTime(datetime))?;
+        Ok(())
+    }
+
+    /// Sets the date and time of the HC, compensating the time the command
+    /// takes to reach it, and returns the date and time it sent.
+    ///
+    /// `datetime` is taken as the time of the call. The round trip of an echo
+    /// command is measured with `clock` in milliseconds first, then the time is
+    /// advanced by it and by the transmission of the 9 byte command estimated
+    /// from it, rounded to the nearest second. The HC clock ends up within
+    /// about half a second of the true time, plus the jitter of the link
+    /// between the echo and the command.
+    pub fn set_datetime_compensated(
+        &mut self,
+        datetime: DateTime,
+        clock: fn() -> u32,
+    ) -> Result<DateTime, Error<T::Error, U::Error>> {
+        let (elapsed_ms, latency_ms) = self.datetime_latency(clock)?;
+        let seconds = ((elapsed_ms + latency_ms + 500) / 1000).min(u32::MAX as u64) as u32;
+        let datetime = datetime.add_seconds(seconds);
+
+        self.set_datetime(datetime)?;
+        Ok(datetime)
+    }
+
+    /// Sets the date and time of the HC so that it is applied on a whole
+    /// second boundary, and returns the date and time it sent.
+    ///
+    /// `wait_ms` is the time from the call to the boundary at which
+    /// `next_second` begins, as read from the reference clock of the host. The
+    /// latency is measured as for [`set_datetime_compensated`], then the rest
+    /// of the wait, less the latency, is spent with `delay` and the command
+    /// is sent right after. If the link is too slow to make the boundary, the
+    /// next boundary it makes is used with the time advanced to it.
+    ///
+    /// The HC clock ends up as close to the reference as the precision of
+    /// `delay` and the jitter of the link allow, rather than within half a
+    /// second.
+    ///
+    /// [`set_datetime_compensated`]: NexStar::set_datetime_compensated
+    pub fn set_datetime_at_boundary<D: DelayMs<u32>>(
+        &mut self,
+        next_second: DateTime,
+        delay: &mut D,
+        wait_ms: u32,
+        clock: fn() -> u32,
+    ) -> Result<DateTime, Error<T::Error, U::Error>> {
+        let (elapsed_ms, latency_ms) = self.datetime_latency(clock)?;
+
+        let needed_ms = elapsed_ms + latency_ms;
+        let (seconds, remaining_ms) = match needed_ms.checked_sub(wait_ms as u64) {
+            Some(late_ms) => {
+                let seconds = late_ms.div_ceil(1000);
+                (seconds, seconds * 1000 - late_ms)
+            }
+            None => (0, wait_ms as u64 - needed_ms),
+        };
+        let datetime = next_second.add_seconds(seconds.min(u32::MAX as u64) as u32);
+
+        if remaining_ms > 0 {
+            delay.delay_ms(remaining_ms as u32);
+        }
+        self.set_datetime(datetime)?;
+        Ok(datetime)
+    }
+
+    /// Measures the round trip of an echo command with `clock` and gets it
+    /// with the estimated time until the HC applies a date and time command.
+    fn datetime_latency(
+        &mut self,
+        clock: fn() -> u32,
+    ) -> Result<(u64, u64), Error<T::Error, U::Error>> {
+        let start = clock();
+        self.echo(0x42)?;
+        let round_trip = clock().wrapping_sub(start) as u64;
+
+        // the echo moves 4 bytes, the HC applies the time after 9
+        Ok((round_trip, round_trip * 9 / 4))
+    }
+
+    // GPS commands
+    /// Gets whether the GPS unit has a fix.
+    pub fn is_gps_linked(&mut self) -> Result<bool, Error<T::Error, U::Error>> {
+        let request = Request::pass_through(Device::GPSUnit, Command::GpsIsLinked, [], 1);
+
+        match self.transact(request)? {
+            Response::State(linked) => Ok(linked),
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    /// Tells an absent GPS unit apart from one without a fix yet.
+    ///
+    /// The HC nacks the link query of an absent unit, or doesn't answer at
+    /// all. The answer is awaited for 500 ms with `delay`, whether a timeout
+    /// is set or not (see [`NexStar::set_timeout`]). A unit which stays
+    /// silent counts as absent, the link is resynced before returning so a
+    /// late answer doesn't end up in the next command.
+    pub fn gps_state<D: DelayMs<u32>>(
+        &mut self,
+        delay: &mut D,
+    ) -> Result<GpsState, Error<T::Error, U::Error>> {
+        let request = Request::pass_through(Device::GPSUnit, Command::GpsIsLinked, [], 1);
+        self.check_slew_watchdog()?;
+        let mut command = [0u8; MAX_COMMAND];
+        let len = encode_command(&request, &mut command).ok_or(Error::FrameOverflow)?;
+        self.write_all(command.get(..len).ok_or(Error::FrameOverflow)?)?;
+
+        // the answer is decided after at most two more bytes, like in
+        // `exchange`
+        let mut buffer = [0u8; MAX_RESPONSE + 2];
+        let mut expected = request.response_len();
+        let mut received = 0;
+        loop {
+            let missing = buffer
+                .get_mut(received..expected)
+                .ok_or(Error::FrameOverflow)?;
+            let got = self.read_within(delay, GPS_STATE_TIMEOUT_MS, missing)?;
+            if received + got == 0 {
+                self.resync()?;
+                return Ok(GpsState::Absent);
+            }
+            if received + got < expected {
+                self.link_state = LinkState::Dirty;
+                self.log_error(Event::Truncated);
+                return Err(Error::Truncated {
+                    expected: expected - received,
+                    got,
+                });
+            }
+            received = expected;
+
+            match parse_response(&request, &buffer[..received]) {
+                Ok(Response::State(true)) => return Ok(GpsState::Linked),
+                Ok(Response::State(false)) => return Ok(GpsState::NoFix),
+                Err(ParseError::Rejected) => {
+                    self.log_error(Event::Nack);
+                    return Ok(GpsState::Absent);
+                }
+                Err(ParseError::Incomplete) => expected += 1,
+                _ => return Err(self.unexpected()),
+            }
+        }
+    }
+
+    /// Gets the position and UTC date and time of the GPS unit.
+    ///
+    /// Fails with [`Error::NoGpsFix`] if the GPS unit isn't linked yet, or if
+    /// it lost the fix while being read and answers with zeros; a position of
+    /// exactly 0°, 0° is taken for the latter.
+    pub fn gps_fix(&mut self) -> Result<GpsFix, Error<T::Error, U::Error>> {
+        if !self.is_gps_linked()? {
+            return Err(Error::NoGpsFix);
+        }
+
+        let latitude = self.gps_query(Command::GpsGetLatitude, 3, parse::gps_angle)?;
+        let longitude = self.gps_query(Command::GpsGetLongitude, 3, parse::gps_angle)?;
+        if latitude == 0 && longitude == 0 {
+            return Err(Error::NoGpsFix);
+        }
+        let (month, day) = match self.gps_query(Command::GpsGetDate, 2, parse::gps_date_unset)? {
+            Some(date) => date,
+            None => return Err(Error::NoGpsFix),
+        };
+        let year = self.gps_query(Command::GpsGetYear, 2, parse::gps_year)?;
+        let (hour, minutes, seconds) = self.gps_query(Command::GpsGetTime, 3, parse::gps_time)?;
+
+        Ok(GpsFix {
+            latitude: fraction_to_arcsec(latitude, 24, true),
+            longitude: fraction_to_arcsec(longitude, 24, true),
+            utc: UtcDateTime {
+                year,
+                month,
+                day,
+                hour,
+                minutes,
+                seconds,
+            },
+        })
+    }
+
+    /// Waits until the GPS unit has a fix and gets it.
+    ///
+    /// Polls the link state every `poll_interval_ms` and fails with
+    /// [`Error::Timeout`] if there is no fix after `max_wait_ms`; after power-on
+    /// this usually takes 30 to 120 seconds. Transport errors are returned
+    /// immediately.
+    pub fn wait_for_gps_fix<D: DelayMs<u32>>(
+        &mut self,
+        delay: &mut D,
+        poll_interval_ms: u32,
+        max_wait_ms: u32,
+    ) -> Result<GpsFix, Error<T::Error, U::Error>> {
+        self.poll_until(delay, poll_interval_ms, max_wait_ms, |nexstar| {
+            nexstar.is_gps_linked()
+        })?;
+        self.gps_fix()
+    }
+
+    /// Gets the date and time of the real time clock.
+    ///
+    /// Fails with [`Error::UnsupportedByModel`] before sending anything if the
+    /// model found by [`NexStar::connect`] has no RTC, which only the CGE has
+    /// among the known models. Unknown models are queried, see also
+    /// [`NexStar::set_capability_checks`].
+    pub fn rtc_time(&mut self) -> Result<UtcDateTime, Error<T::Error, U::Error>> {
+        self.require_capability(|capabilities| capabilities.has_rtc)?;
+
+        let (month, day) =
+            self.device_query(Device::RTC, Command::GpsGetDate, 2, parse::gps_date)?;
+        let year = self.device_query(Device::RTC, Command::GpsGetYear, 2, parse::gps_year)?;
+        let (hour, minutes, seconds) =
+            self.device_query(Device::RTC, Command::GpsGetTime, 3, parse::gps_time)?;
+
+        Ok(UtcDateTime {
+            year,
+            month,
+            day,
+            hour,
+            minutes,
+            seconds,
+        })
+    }
+
+    /// Sets the date and time of the HC from the GPS unit and returns the
+    /// local date and time it set.
+    ///
+    /// The UTC time of the GPS is converted into the local time in `zone`,
+    /// which is one hour later with `daylight_saving`. Fails with
+    /// [`Error::NoGpsFix`] without changing the HC clock if the GPS unit
+    /// isn't linked.
+    pub fn set_datetime_from_gps(
+        &mut self,
+        zone: i8,
+        daylight_saving: bool,
+    ) -> Result<DateTime, Error<T::Error, U::Error>> {
+        let fix = self.gps_fix()?;
+        let datetime = match DateTime::from_utc(fix.utc, zone, daylight_saving) {
+            Some(datetime) => datetime,
+            None => return Err(self.unexpected()),
+        };
+
+        self.set_datetime(datetime)?;
+        Ok(datetime)
+    }
+
+    /// Reads the settings which the HC loses with its battery or a factory
+    /// reset, see the [`MountConfig`].
+    ///
+    /// Settings the mount doesn't support (e.g. the backlash of older motor
+    /// controllers) are `None`, any other error fails the snapshot.
+    #[cfg(feature = "float")]
+    pub fn snapshot(&mut self) -> Result<MountConfig, Error<T::Error, U::Error>> {
+        let datetime = supported(self.datetime())?;
+        let mut config = MountConfig {
+            location: supported(self.location())?,
+            time_zone: datetime.map(|datetime| (datetime.zone, datetime.daylight_saving)),
+            tracking_mode: supported(self.tracking_mode())?,
+            ..MountConfig::default()
+        };
+        for (axis, &device) in MOTORS.iter().enumerate() {
+            config.autoguide_rates[axis] = supported(self.autoguide_rate(device))?;
+            config.backlash[axis] = supported(self.backlash(device))?;
+        }
+        Ok(config)
+    }
+
+    /// Writes the settings of a [`NexStar::snapshot`] back, e.g. after the HC
+    /// lost them.
+    ///
+    /// The date and time are set to `now`, converted into the time zone of
+    /// the config. Every setting is written even if others fail, the report
+    /// tells which ones did.
+    #[cfg(feature = "float")]
+    pub fn restore(
+        &mut self,
+        config: &MountConfig,
+        now: DateTime,
+    ) -> RestoreReport<T::Error, U::Error> {
+        let datetime = match config.time_zone {
+            // only fails within hours of the ends of the HC years
+            Some((zone, daylight_saving)) => {
+                DateTime::from_utc(now.to_utc(), zone, daylight_saving).unwrap_or(now)
+            }
+            None => now,
+        };
+
+        let mut report = RestoreReport {
+            location: config
+                .location
+                .map_or(Ok(()), |location| self.set_location(location)),
+            datetime: self.set_datetime(datetime),
+            tracking_mode: config
+                .tracking_mode
+                .map_or(Ok(()), |mode| self.set_tracking_mode(mode)),
+            autoguide_rates: [Ok(()), Ok(())],
+            backlash: [Ok(()), Ok(())],
+        };
+        for (axis, &device) in MOTORS.iter().enumerate() {
+            if let Some(percent) = config.autoguide_rates[axis] {
+                report.autoguide_rates[axis] = self.set_autoguide_rate(device, percent);
+            }
+            if let Some(backlash) = config.backlash[axis] {
+                report.backlash[axis] = self.set_backlash(device, backlash);
+            }
+        }
+        report
+    }
+
+    // Miscellaneous Commands
+    /// Gets the version of the Hand Controller (HC) firmware.
+    ///
+    /// The version is cached after the first successful query, see
+    /// [`NexStar::set_force_refresh`].
+    pub fn version(&mut self) -> Result<Version, Error<T::Error, U::Error>> {
+        if let (Some(version), false) = (self.versions.hc, self.force_refresh) {
+            return Ok(version);
+        }
+        let version = self.read_version(self.version_request())?;
+        self.versions.hc = Some(version);
+        Ok(version)
+    }
+
+    /// Gets the version of the specified sub device.
+    ///
+    /// Like [`NexStar::version`] the version is cached, a device which didn't
+    /// answer is queried again the next time.
+    pub fn device_version(&mut self, device: Device) -> Result<Version, Error<T::Error, U::Error>> {
+        if let (Some(version), false) = (self.versions.device(device), self.force_refresh) {
+            return Ok(version);
+        }
+        let version = self.read_version(Request::pass_through(
+            device,
+            Command::GetDeviceVersion,
+            [],
+            2,
+        ))?;
+        self.versions.set_device(device, version);
+        Ok(version)
+    }
+
+    /// Restarts the WiFi module, e.g. when the SkyPortal link hangs while
+    /// the serial side still works.
+    ///
+    /// The module may reset before it acks the command. With a timeout set
+    /// (see [`NexStar::set_timeout`]) the missing ack counts as success and
+    /// the link is resynced before the next command, unless
+    /// [`NexStar::set_wifi_reboot_needs_ack`] asks for the ack. Without a
+    /// timeout a missing ack blocks. Mounts without the module fail with
+    /// [`Error::UnsupportedCommand`].
+    pub fn wifi_reboot(&mut self) -> Result<(), Error<T::Error, U::Error>> {
+        let request = Request::pass_through(Device::WiFi, Command::WiFiReboot, [], 0);
+
+        match self.transact_or_unsupported(request) {
+            Ok(_) => Ok(()),
+            Err(Error::Truncated { got: 0, .. }) if !self.wifi_reboot_needs_ack => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Gets the voltage of the mount battery in mV.
+    ///
+    /// Mounts without a battery power controller fail with
+    /// [`Error::UnsupportedCommand`].
+    pub fn battery_voltage(&mut self) -> Result<u32, Error<T::Error, U::Error>> {
+        let request = Request::pass_through(Device::Battery, Command::BatteryVoltage, [], 6);
+
+        match self.transact_or_unsupported(request)? {
+            Response::Data(data) => match *data.as_bytes() {
+                [_charging, _level, v0, v1, v2, v3] => {
+                    Ok(u32::from_be_bytes([v0, v1, v2, v3]) / 1000)
+                }
+                _ => Err(self.unexpected()),
+            },
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    /// Gets the approximate state of charge of the mount battery in percent,
+    /// see [`battery::state_of_charge`](crate::battery::state_of_charge).
+    pub fn battery_percent(&mut self) -> Result<u8, Error<T::Error, U::Error>> {
+        self.battery_voltage().map(battery::state_of_charge)
+    }
+
+    /// Checks whether the approximate state of charge of the mount battery is
+    /// at or below `threshold_percent`.
+    pub fn is_battery_low(
+        &mut self,
+        threshold_percent: u8,
+    ) -> Result<bool, Error<T::Error, U::Error>> {
+        Ok(self.battery_percent()? <= threshold_percent)
+    }
+
+    /// Gets the model of the telescope mount.
+    ///
+    /// Like [`NexStar::version`] the model is cached.
+    pub fn model(&mut self) -> Result<Model, Error<T::Error, U::Error>> {
+        if let (Some(model), false) = (self.model, self.force_refresh) {
+            return Ok(model);
+        }
+        self.require_compat(self.compat().model_query, MODEL_VERSION)?;
+        match self.transact(self.model_request())? {
+            Response::Model(model) => {
+                self.model = Some(model);
+                Ok(model)
+            }
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    /// Gets the board variant of a motor controller, which tells e.g.
+    /// whether it supports timed guide pulses.
+    ///
+    /// Controllers predating the query fail with [`Error::UnsupportedCommand`].
+    pub fn motor_model(&mut self, device: Device) -> Result<MotorModel, Error<T::Error, U::Error>> {
+        let request = Request::pass_through(device, Command::GetMotorModel, [], 1);
+
+        match self.transact_or_unsupported(request)? {
+            Response::MotorModel(model) => Ok(model),
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    /// Gets the alignment state.
+    ///
+    /// Depending on the firmware the HC answers with binary 0 and 1 or the
+    /// ASCII digits, both are accepted.
+    ///
+    /// A StarSense HC answers `false` until its automatic alignment finished
+    /// and may nack the query meanwhile, which also reads as `false`.
+    pub fn is_alignment_complete(&mut self) -> Result<bool, Error<T::Error, U::Error>> {
+        match self.exchange(Request::IsAlignmentComplete)? {
+            Ok(Response::State(complete)) => Ok(complete),
+            Err(ParseError::Rejected) if self.is_starsense() => Ok(false),
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    /// Waits until the alignment is complete.
+    ///
+    /// Polls the alignment state every `poll_interval_ms` and fails with
+    /// [`Error::Timeout`] if the alignment isn't complete after `max_wait_ms`.
+    /// The automatic alignment of a StarSense HC takes a few minutes.
+    pub fn wait_for_alignment<D: DelayMs<u32>>(
+        &mut self,
+        delay: &mut D,
+        poll_interval_ms: u32,
+        max_wait_ms: u32,
+    ) -> Result<(), Error<T::Error, U::Error>> {
+        self.poll_until(delay, poll_interval_ms, max_wait_ms, |nexstar| {
+            nexstar.is_alignment_complete()
+        })
+    }
+
+    /// Gets GOTO state.
+    ///
+    /// Like [`NexStar::is_alignment_complete`] the answer is ASCII `'0'` and
+    /// `'1'` or binary 0 and 1 depending on the firmware.
+    pub fn is_goto_in_progress(&mut self) -> Result<bool, Error<T::Error, U::Error>> {
+        match self.transact(Request::IsGotoInProgress)? {
+            Response::State(active) => Ok(active),
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    /// Gets the alignment, GOTO and tracking state.
+    ///
+    /// This is the recommended call to poll the mount, e.g. for a dashboard.
+    /// If one of the queries fails the whole call fails with the error of that
+    /// query, whose [`Context`] tells which one it was.
+    pub fn status(&mut self) -> Result<MountStatus, Error<T::Error, U::Error>> {
+        Ok(MountStatus {
+            aligned: self.is_alignment_complete()?,
+            goto_in_progress: self.is_goto_in_progress()?,
+            tracking: self.tracking_mode()?,
+        })
+    }
+
+    /// Writes a summary of the position, tracking, alignment and GOTO state to
+    /// `out`, e.g. for a small display or a debug UART:
+    ///
+    /// ```text
+    /// RA/Dec   06h00m00s -45°00'00"
+    /// Tracking EQ North
+    /// Aligned  yes
+    /// GOTO     idle
+    /// ```
+    ///
+    /// A value which fails to read is written as `--` and the remaining ones
+    /// are still queried, so only the errors of `out` fail.
+    pub fn write_status(&mut self, out: &mut dyn fmt::Write) -> fmt::Result {
+        let position = self.ra_dec_arcsec();
+        write_status_line(out, "RA/Dec", position)?;
+        let tracking = self.tracking_mode();
+        write_status_line(out, "Tracking", tracking)?;
+        let aligned = self
+            .is_alignment_complete()
+            .map(|aligned| if aligned { "yes" } else { "no" });
+        write_status_line(out, "Aligned", aligned)?;
+        let goto = self
+            .is_goto_in_progress()
+            .map(|active| if active { "active" } else { "idle" });
+        write_status_line(out, "GOTO", goto)
+    }
+
+    /// Sends a command the driver doesn't wrap and reads its response into
+    /// `response`, returning the number of bytes read.
+    ///
+    /// With [`Terminator::Hash`] the response is read up to the `#`, which
+    /// isn't stored, with [`Terminator::Exact`] exactly that many bytes are
+    /// read. A response which doesn't fit fails with [`Error::FrameOverflow`]
+    /// and marks the link dirty. The command goes through the same resync,
+    /// pacing, echo suppression and timeout handling as the wrapped ones.
+    ///
+    /// This is unstable territory: the driver can't check the command or its
+    /// response, so an undocumented command with an unexpected response length
+    /// desyncs the link until the next resync.
+    pub fn raw_command(
+        &mut self,
+        request: &[u8],
+        response: &mut [u8],
+        terminator: Terminator,
+    ) -> Result<usize, Error<T::Error, U::Error>> {
+        let limit = match terminator {
+            Terminator::Hash => response.len(),
+            Terminator::Exact(len) => len,
+        };
+        let buffer = response.get_mut(..limit).ok_or(Error::FrameOverflow)?;
+
+        self.check_slew_watchdog()?;
+        self.write_all(request)?;
+
+        if let Terminator::Exact(_) = terminator {
+            self.read_multiple(buffer)?;
+            return Ok(limit);
+        }
+        for got in 0..=limit {
+            let byte = match self.read() {
+                Ok(byte) => byte,
+                Err(Error::Truncated { .. }) => {
+                    return Err(Error::Truncated {
+                        expected: got + 1,
+                        got,
+                    })
+                }
+                Err(e) => return Err(e),
+            };
+            if byte == b'#' {
+                return Ok(got);
+            }
+            match buffer.get_mut(got) {
+                Some(slot) => *slot = byte,
+                None => break,
+            }
+        }
+
+        self.link_state = LinkState::Dirty;
+        Err(Error::FrameOverflow)
+    }
+
+    /// Sends a pass-through command to a sub device and reads its response
+    /// into `response`, returning the number of bytes received.
+    ///
+    /// The `length` of the response is announced to the HC. Up to 3 data
+    /// bytes are sent, more fail with [`Error::FrameOverflow`] before
+    /// anything is sent. A response which doesn't fit `response` is still
+    /// read completely and fails with [`Error::BufferTooSmall`], keeping the
+    /// link in sync. A rejected command fails with
+    /// [`Error::UnsupportedCommand`].
+    pub fn pass_through(
+        &mut self,
+        device: Device,
+        command: u8,
+        data: &[u8],
+        length: ResponseLength,
+        response: &mut [u8],
+    ) -> Result<usize, Error<T::Error, U::Error>> {
+        let (len, until_hash) = match length {
+            ResponseLength::Exact(len) => (len, false),
+            ResponseLength::UpTo(len) => (len, true),
+            ResponseLength::Auto => match Command::from_bits(device, command) {
+                Some(known) => (known.response_len(), false),
+                None => (MAX_RESPONSE as u8, true),
+            },
+        };
+        let frame = Frame::pass_through(device, command, data, len);
+        let frame = frame.bytes().ok_or(Error::FrameOverflow)?;
+
+        self.check_slew_watchdog()?;
+        self.write_all(frame)?;
+
+        let len = len as usize;
+        let mut got = 0;
+        let terminator = loop {
+            let byte = match self.read() {
+                Ok(byte) => byte,
+                Err(Error::Truncated { .. }) => {
+                    return Err(Error::Truncated {
+                        expected: len + 1,
+                        got,
+                    })
+                }
+                Err(e) => return Err(e),
+            };
+            if got == len || (until_hash && byte == b'#') {
+                break byte;
+            }
+            // the rest is discarded to find the end of the response
+            if let Some(slot) = response.get_mut(got) {
+                *slot = byte;
+            }
+            got += 1;
+        };
+
+        if terminator != b'#' {
+            // an error code followed by the ack
+            return match self.read()? {
+                b'#' => Err(self.nack()),
+                _ => Err(self.unexpected()),
+            };
+        }
+        if got > response.len() {
+            return Err(Error::BufferTooSmall {
+                needed: got,
+                got: response.len(),
+            });
+        }
+        Ok(got)
+    }
+
+    /// Sends `byte` with the echo command and checks that the HC returns it,
+    /// another byte fails with [`Error::UnexpectedResponse`].
+    ///
+    /// A wire looping the sent bytes back passes a fixed byte too, see
+    /// [`NexStar::probe_link`] to tell it apart from the HC.
+    pub fn echo(&mut self, byte: u8) -> Result<(), Error<T::Error, U::Error>> {
+        self.require_compat(self.compat().echo, ECHO_VERSION)?;
+        match self.transact(Request::Echo(byte))? {
+            Response::Echo(echoed) if echoed == byte => Ok(()),
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    /// Tests the link with echoes of a few distinct bytes, including the
+    /// ack, e.g. for a connection test dialog.
+    ///
+    /// Each echo has 200 ms to arrive, polled with `delay`, and the probing
+    /// stops at the first one failing. Unlike a single [`NexStar::echo`] this
+    /// tells the HC apart from a wire looping the sent bytes back, which
+    /// returns the command instead of the byte. The link is left dirty unless
+    /// all echoes arrived. Only I/O errors fail, and HCs lacking the echo with
+    /// [`Error::UnsupportedFirmware`].
+    pub fn probe_link<D: DelayMs<u32>>(
+        &mut self,
+        delay: &mut D,
+    ) -> Result<LinkProbe, Error<T::Error, U::Error>> {
+        self.require_compat(self.compat().echo, ECHO_VERSION)?;
+
+        for byte in LINK_PROBE_BYTES {
+            let mut command = [0u8; MAX_COMMAND];
+            let len =
+                encode_command(&Request::Echo(byte), &mut command).ok_or(Error::FrameOverflow)?;
+            let outcome = match self.write_all(command.get(..len).ok_or(Error::FrameOverflow)?) {
+                Ok(()) => {
+                    let mut reply = [0u8; 2];
+                    match self.read_within(delay, LINK_PROBE_TIMEOUT_MS, &mut reply)? {
+                        0 => LinkProbe::Silent,
+                        2 if reply == [byte, b'#'] => LinkProbe::Ok,
+                        _ => LinkProbe::WrongEcho,
+                    }
+                }
+                // the local echo of the command
+                Err(Error::Truncated { .. }) => LinkProbe::Silent,
+                Err(Error::EchoMismatch) => LinkProbe::WrongEcho,
+                Err(e) => return Err(e),
+            };
+            if outcome != LinkProbe::Ok {
+                self.link_state = LinkState::Dirty;
+                return Ok(outcome);
+            }
+        }
+        Ok(LinkProbe::Ok)
+    }
+
+    /// Starts capturing the recent bytes sent and received and the errors of
+    /// failed commands into `buffer` for bug reports, `None` stops it.
+    ///
+    /// The buffer is a ring of two bytes per record, see
+    /// [`NexStar::dump_diagnostics`]. It must live as long as the driver, e.g.
+    /// a `static` or a leaked allocation. Without a buffer (the default)
+    /// nothing is recorded.
+    pub fn set_diagnostics(&mut self, buffer: Option<&'static mut [u8]>) {
+        self.diagnostics = buffer.map(Diagnostics::new);
+    }
+
+    /// Renders the captured diagnostics into `out` and returns their length.
+    ///
+    /// The dump is text in the transcript format, one line per direction with
+    /// `>` for the bytes sent and `<` for the bytes received as hex digits,
+    /// and the errors as `# error: <kind>` comments:
+    ///
+    /// ```text
+    /// > 50 01 11 40 00 00 00 01
+    /// < 00 01 23
+    /// # error: nack
+    /// ```
+    ///
+    /// The kinds are `nack`, `unexpected response`, `truncated`, `read error`,
+    /// `write error` and `echo mismatch`. The oldest records which don't fit
+    /// `out` are left out, nothing is written without diagnostics.
+    pub fn dump_diagnostics(&self, out: &mut [u8]) -> usize {
+        match &self.diagnostics {
+            Some(diagnostics) => diagnostics.dump(out),
+            None => 0,
+        }
+    }
+
+    /// Enables or disables the local echo suppression (disabled by default).
+    ///
+    /// Some half-duplex adapters echo every transmitted byte. With the
+    /// suppression enabled the echo is read and verified after each write and
+    /// [`Error::EchoMismatch`] is returned if it differs, which also catches
+    /// wiring problems.
+    pub fn set_echo_suppression(&mut self, enabled: bool) {
+        self.echo_suppression = enabled;
+    }
+
+    /// Sets the time to wait for each byte of a response.
+    ///
+    /// `clock` returns a millisecond tick which may wrap. A response which
+    /// stops early fails with [`Error::Truncated`] and the link becomes dirty,
+    /// so the rest is discarded before the next command.
+    pub fn set_timeout(&mut self, clock: fn() -> u32, timeout_ms: u32) {
+        self.timeout = Some((clock, timeout_ms));
+    }
+
+    /// Waits for responses forever (the default).
+    pub fn clear_timeout(&mut self) {
+        self.timeout = None;
+    }
+
+    /// Sets whether [`NexStar::wifi_reboot`] fails with [`Error::Truncated`]
+    /// if the module resets without an ack (disabled by default).
+    pub fn set_wifi_reboot_needs_ack(&mut self, needs_ack: bool) {
+        self.wifi_reboot_needs_ack = needs_ack;
+    }
+
+    /// Waits at least `min_gap_ms` between the end of a response and the next
+    /// command, for HCs which reject commands arriving back to back.
+    ///
+    /// `clock` returns a millisecond tick which may wrap and `delay` sleeps for
+    /// the given milliseconds; it is only called when a command follows the
+    /// previous response too soon. A gap of 0 (the default) disables pacing.
+    pub fn with_pacing(mut self, delay: fn(u32), clock: fn() -> u32, min_gap_ms: u32) -> Self {
+        self.pacing = if min_gap_ms == 0 {
+            None
+        } else {
+            Some(Pacing {
+                delay,
+                clock,
+                min_gap_ms,
+                last: None,
+            })
+        };
+        self
+    }
+
+    /// Stops slews which aren't refreshed within `timeout_ms`, e.g. because
+    /// the task commanding them crashed.
+    ///
+    /// Every [`NexStar::track_rates`] with a non-zero rate must be followed by
+    /// another one (with the same or a new rate) or by
+    /// [`NexStar::stop_tracking_rates`] within `timeout_ms` of the `clock`
+    /// (a millisecond tick which may wrap). There is no background task: the
+    /// first command sent later than that, whatever it is, stops both motors
+    /// first and fails with [`Error::WatchdogFired`] instead of being sent.
+    /// Call [`NexStar::check_slew_watchdog`] periodically to bound the time
+    /// the motors keep running when nothing else talks to the mount.
+    pub fn set_slew_watchdog(&mut self, clock: fn() -> u32, timeout_ms: u32) {
+        self.watchdog = Some(Watchdog {
+            clock,
+            timeout_ms,
+            fed: None,
+        });
+    }
+
+    /// Disables the slew watchdog (the default).
+    pub fn clear_slew_watchdog(&mut self) {
+        self.watchdog = None;
+    }
+
+    /// Stops both motors if the slew watchdog expired, which fails with
+    /// [`Error::WatchdogFired`]. Does nothing without a slew or watchdog.
+    pub fn check_slew_watchdog(&mut self) -> Result<(), Error<T::Error, U::Error>> {
+        let expired = match self.watchdog {
+            Some(Watchdog {
+                clock,
+                timeout_ms,
+                fed: Some(fed),
+            }) => clock().wrapping_sub(fed) > timeout_ms,
+            _ => false,
+        };
+        if !expired {
+            return Ok(());
+        }
+
+        // disarmed first, so the stop commands don't check it again
+        if let Some(watchdog) = &mut self.watchdog {
+            watchdog.fed = None;
+        }
+        self.stop_tracking_rates()?;
+        Err(Error::WatchdogFired)
+    }
+
+    /// Gets the state of the link.
+    pub fn link_state(&self) -> LinkState {
+        self.link_state
+    }
+
+    /// Checks that the link is alive with an echo of a new byte every call.
+    ///
+    /// Meant to be called periodically while the mount idles, e.g. every
+    /// minute, so a connection which died silently (as TCP/WiFi transports
+    /// do) shows up before the next GOTO. It is an ordinary command: call it
+    /// from the task owning the driver between other commands, or through a
+    /// `SharedNexStar`, never from an interrupt which may preempt a command
+    /// in flight. A dirty link is
+    /// resynced first like before any command. The outcome is recorded in the
+    /// [`LinkHealth`], see [`NexStar::track_link_health`].
+    pub fn keepalive(&mut self) -> Result<(), Error<T::Error, U::Error>> {
+        self.require_compat(self.compat().echo, ECHO_VERSION)?;
+        self.keepalive = self.keepalive.wrapping_add(1);
+        let marker = self.keepalive;
+        let result = match self.transact(Request::Echo(marker)) {
+            Ok(Response::Echo(byte)) if byte == marker => Ok(()),
+            Ok(_) => Err(self.unexpected()),
+            Err(e) => Err(e),
+        };
+
+        if let Some(health) = &mut self.health {
+            health.record(result.is_ok());
+        }
+        result
+    }
+
+    /// Starts recording the outcome of [`NexStar::keepalive`] with the
+    /// millisecond tick `clock`, which restarts the record.
+    pub fn track_link_health(&mut self, clock: fn() -> u32) {
+        self.health = Some(LinkHealth::new(clock));
+    }
+
+    /// Gets the outcome of the recent keepalives, `None` unless tracked, see
+    /// [`NexStar::track_link_health`].
+    pub fn link_health(&self) -> Option<LinkHealth> {
+        self.health
+    }
+
+    /// Enables or disables the automatic resync of a dirty link (enabled by
+    /// default).
+    ///
+    /// Without it commands fail with [`Error::LinkDirty`] after an error until
+    /// [`NexStar::resync`] is called.
+    pub fn set_auto_resync(&mut self, enabled: bool) {
+        self.auto_resync = enabled;
+    }
+
+    /// Discards the bytes already received (at most 64) and marks the link
+    /// clean.
+    ///
+    /// With a timeout set (see [`NexStar::set_timeout`]) it then sends an echo
+    /// command and discards everything received before the echo, so the rest
+    /// of a stalled response can't be taken for the next one. Without a timeout
+    /// it stops as soon as no more bytes are pending and leftovers still in
+    /// transit may remain.
+    pub fn resync(&mut self) -> Result<(), Error<T::Error, U::Error>> {
+        for _ in 0..DRAIN_LIMIT {
+            match self.receive() {
+                Ok(byte) => self.log(Record::Received, &[byte]),
+                Err(nb::Error::WouldBlock) => break,
+                Err(nb::Error::Other(e)) => return Err(Error::Read(e)),
+            }
+        }
+
+        // HCs without the echo can't be probed
+        if self.timeout.is_some() && self.compat().echo {
+            self.probe()?;
+        }
+
+        self.link_state = LinkState::Clean;
+        Ok(())
+    }
+
+    /// Gets the transport halves back as they are, see [`NexStar::release`]
+    /// to stop the mount first.
+    pub fn free(self) -> (T, U) {
+        (self.rx, self.tx)
+    }
+
+    /// Stops the mount and gets the transport halves back, e.g. before the
+    /// application exits.
+    ///
+    /// Cancels a GOTO in progress, stops the motors if rates sent by
+    /// [`NexStar::track_rates`] may still be active and discards the bytes
+    /// received until none arrived for 50 ms. Every step is tried whatever
+    /// the outcome of the previous ones, the report tells which succeeded.
+    pub fn release<D: DelayMs<u32>>(
+        mut self,
+        delay: &mut D,
+    ) -> (T, U, ReleaseReport<T::Error, U::Error>) {
+        let cancel_goto = match self.is_goto_in_progress() {
+            Ok(false) => Ok(()),
+            // cancelled as well if the state is unknown
+            _ => self.cancel_goto(),
+        };
+        let stop_rates = if self.moving {
+            self.stop_tracking_rates()
+        } else {
+            Ok(())
+        };
+        let drain = self.drain(delay);
+
+        let report = ReleaseReport {
+            cancel_goto,
+            stop_rates,
+            drain,
+        };
+        (self.rx, self.tx, report)
+    }
+
+    /// Reads into `buffer` until it is full or no byte arrived for
+    /// `timeout_ms` in total and returns the number of bytes read.
+    fn read_within<D: DelayMs<u32>>(
+        &mut self,
+        delay: &mut D,
+        timeout_ms: u32,
+        buffer: &mut [u8],
+    ) -> Result<usize, Error<T::Error, U::Error>> {
+        let mut waited = 0;
+        let mut got = 0;
+        while got < buffer.len() && waited < timeout_ms {
+            match self.receive() {
+                Ok(byte) => {
+                    self.log(Record::Received, &[byte]);
+                    buffer[got] = byte;
+                    got += 1;
+                }
+                Err(nb::Error::WouldBlock) => {
+                    delay.delay_ms(LINK_PROBE_POLL_INTERVAL_MS);
+                    waited += LINK_PROBE_POLL_INTERVAL_MS;
+                }
+                Err(nb::Error::Other(e)) => {
+                    self.log_error(Event::Read);
+                    self.link_state = LinkState::Dirty;
+                    return Err(Error::Read(e));
+                }
+            }
+        }
+        Ok(got)
+    }
+
+    /// Discards the bytes received until the receive side is quiet.
+    fn drain<D: DelayMs<u32>>(&mut self, delay: &mut D) -> Result<(), Error<T::Error, U::Error>> {
+        let mut quiet = 0;
+        let mut discarded = 0;
+        while quiet < RELEASE_QUIET_MS && discarded < DRAIN_LIMIT {
+            match self.receive() {
+                Ok(byte) => {
+                    self.log(Record::Received, &[byte]);
+                    quiet = 0;
+                    discarded += 1;
+                }
+                Err(nb::Error::WouldBlock) => {
+                    delay.delay_ms(RELEASE_POLL_INTERVAL_MS);
+                    quiet += RELEASE_POLL_INTERVAL_MS;
+                }
+                Err(nb::Error::Other(e)) => return Err(Error::Read(e)),
+            }
+        }
+        Ok(())
+    }
+
+    /// Polls `done` until it returns `true` or fails with [`Error::Timeout`]
+    /// after `max_wait_ms`. Errors of `done` are returned immediately.
+    fn poll_until<D, F>(
+        &mut self,
+        delay: &mut D,
+        poll_interval_ms: u32,
+        max_wait_ms: u32,
+        mut done: F,
+    ) -> Result<(), Error<T::Error, U::Error>>
+    where
+        D: DelayMs<u32>,
+        F: FnMut(&mut Self) -> Result<bool, Error<T::Error, U::Error>>,
+    {
+        let mut waited = 0u32;
+        loop {
+            if done(self)? {
+                return Ok(());
+            }
+            if self.abort.is_some_and(|abort| abort()) {
+                return Err(Error::Aborted);
+            }
+            if waited >= max_wait_ms {
+                return Err(Error::Timeout);
+            }
+
+            delay.delay_ms(poll_interval_ms);
+            waited = waited.saturating_add(poll_interval_ms);
+        }
+    }
+
+    fn write_motor_position(
+        &mut self,
+        device: Device,
+        fraction: u32,
+    ) -> Result<(), Error<T::Error, U::Error>> {
+        let request =
+            Request::pass_through(device, Command::SetPosition, position_bytes(fraction), 0);
+        self.transact_or_unsupported(request)?;
+        Ok(())
+    }
+
+    /// Stops the motor, reverses it into the direction of the rate and gets
+    /// the arcseconds it ran until the axis moved, leaving it slewing.
+    #[cfg(feature = "float")]
+    fn dead_band<D: DelayMs<u32>>(
+        &mut self,
+        device: Device,
+        arcsec_per_sec: f32,
+        timeout_ms: u32,
+        delay: &mut D,
+    ) -> Result<f32, Error<T::Error, U::Error>> {
+        self.slew_variable(device, 0.0)?;
+        let start = self.motor_position(device)?;
+        self.slew_variable(device, arcsec_per_sec)?;
+
+        let rate = arcsec_per_sec.abs() as f64;
+        let mut waited = 0;
+        while waited < timeout_ms {
+            if self.abort.is_some_and(|abort| abort()) {
+                return Err(Error::Aborted);
+            }
+            delay.delay_ms(BACKLASH_POLL_INTERVAL_MS);
+            waited += BACKLASH_POLL_INTERVAL_MS;
+
+            let position = self.motor_position(device)?;
+            let moved = motor_delta(start, position) * arcsec_per_sec.signum() as f64;
+            if moved >= BACKLASH_THRESHOLD_ARCSEC {
+                // back to when the axis started to move
+                let ran = rate * waited as f64 / 1000.0 - moved;
+                return Ok(ran.max(0.0) as f32);
+            }
+        }
+        Err(Error::Timeout)
+    }
+
+    /// Waits for `ms`, checking the abort hook in between.
+    #[cfg(feature = "float")]
+    fn wait_or_abort<D: DelayMs<u32>>(
+        &mut self,
+        delay: &mut D,
+        ms: u32,
+    ) -> Result<(), Error<T::Error, U::Error>> {
+        let mut waited = 0;
+        while waited < ms {
+            if self.abort.is_some_and(|abort| abort()) {
+                return Err(Error::Aborted);
+            }
+            let step = (ms - waited).min(CALIBRATION_ABORT_INTERVAL_MS);
+            delay.delay_ms(step);
+            waited += step;
+        }
+        Ok(())
+    }
+
+    fn read_multiple(&mut self, buffer: &mut [u8]) -> Result<(), Error<T::Error, U::Error>> {
+        let expected = buffer.len();
+        for (got, byte) in buffer.iter_mut().enumerate() {
+            *byte = match self.read() {
+                Ok(byte) => byte,
+                Err(Error::Truncated { .. }) => return Err(Error::Truncated { expected, got }),
+                Err(e) => return Err(e),
+            };
+        }
+        Ok(())
+    }
+
+    fn read(&mut self) -> Result<u8, Error<T::Error, U::Error>> {
+        let result = match self.timeout {
+            None => block!(self.receive()).map_err(Error::Read),
+            Some((clock, timeout_ms)) => {
+                let start = clock();
+                loop {
+                    match self.receive() {
+                        Ok(byte) => break Ok(byte),
+                        Err(nb::Error::Other(e)) => break Err(Error::Read(e)),
+                        Err(nb::Error::WouldBlock) => {
+                            if clock().wrapping_sub(start) > timeout_ms {
+                                break Err(Error::Truncated {
+                                    expected: 1,
+                                    got: 0,
+                                });
+                            }
+                        }
+                    }
+                }
+            }
+        };
+
+        match result {
+            Ok(byte) => self.log(Record::Received, &[byte]),
+            Err(Error::Truncated { .. }) => self.log_error(Event::Truncated),
+            Err(_) => self.log_error(Event::Read),
+        }
+        if result.is_err() {
+            self.link_state = LinkState::Dirty;
+        }
+        if let Some(pacing) = &mut self.pacing {
+            pacing.last = Some((pacing.clock)());
+        }
+        result
+    }
+
+    /// Reads a byte, the held one first.
+    fn receive(&mut self) -> nb::Result<u8, T::Error> {
+        match self.held.take() {
+            Some(byte) => Ok(byte),
+            None => self.rx.read(),
+        }
+    }
+
+    /// Waits until the minimum gap since the last response has passed.
+    fn pace(&mut self) {
+        if let Some(Pacing {
+            delay,
+            clock,
+            min_gap_ms,
+            last: Some(last),
+        }) = self.pacing
+        {
+            let elapsed = clock().wrapping_sub(last);
+            if elapsed < min_gap_ms {
+                delay(min_gap_ms - elapsed);
+            }
+        }
+    }
+
+    fn write_all(&mut self, buffer: &[u8]) -> Result<(), Error<T::Error, U::Error>> {
+        if self.link_state == LinkState::Dirty {
+            if !self.auto_resync {
+                return Err(Error::LinkDirty);
+            }
+            self.resync()?;
+        }
+
+        self.context = Context::from_frame(buffer);
+        self.pace();
+        self.send(buffer).map_err(|e| {
+            self.link_state = LinkState::Dirty;
+            self.log_error(Event::Write);
+            Error::Write(e)
+        })?;
+        self.log(Record::Sent, buffer);
+
+        if self.echo_suppression {
+            self.read_echo(buffer)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the bytes and flushes the transmitting half.
+    fn send(&mut self, buffer: &[u8]) -> Result<(), U::Error> {
+        for byte in buffer {
+            block!(self.tx.write(*byte))?;
+        }
+        block!(self.tx.flush())
+    }
+
+    /// Sends an echo command and reads until it came back.
+    fn probe(&mut self) -> Result<(), Error<T::Error, U::Error>> {
+        // a new marker every time, the echo of an earlier probe may still be
+        // in transit
+        self.probe = self.probe.wrapping_add(1);
+        let marker = b'A' + self.probe % 26;
+        let mut buffer = [0u8; MAX_COMMAND];
+        let len =
+            encode_command(&Request::Echo(marker), &mut buffer).ok_or(Error::FrameOverflow)?;
+        let command = buffer.get(..len).ok_or(Error::FrameOverflow)?;
+
+        self.context = Context::from_frame(command);
+        self.pace();
+        self.send(command).map_err(Error::Write)?;
+        if self.echo_suppression {
+            self.read_echo(command)?;
+        }
+
+        let mut previous = 0;
+        for _ in 0..DRAIN_LIMIT {
+            let byte = self.read()?;
+            if previous == marker && byte == b'#' {
+                return Ok(());
+            }
+            previous = byte;
+        }
+
+        Err(self.unexpected())
+    }
+
+    fn read_echo(&mut self, sent: &[u8]) -> Result<(), Error<T::Error, U::Error>> {
+        // read the whole echo even if it differs to stay in sync
+        let mut matches = true;
+        for byte in sent {
+            matches &= self.read()? == *byte;
+        }
+
+        if matches {
+            Ok(())
+        } else {
+            self.link_state = LinkState::Dirty;
+            self.log_error(Event::EchoMismatch);
+            Err(Error::EchoMismatch)
+        }
+    }
+
+    fn optional_device_version(
+        &mut self,
+        device: Device,
+    ) -> Result<Option<Version>, Error<T::Error, U::Error>> {
+        match self.device_version(device) {
+            Ok(version) => Ok(Some(version)),
+            Err(Error::UnexpectedResponse(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Refuses a command the NexStar HC firmware predates. The versions of
+    /// the other families count differently and aren't checked.
+    fn require_firmware(&self, required: Version) -> Result<(), Error<T::Error, U::Error>> {
+        match self.info {
+            Some(info)
+                if self.check_firmware
+                    && self.compat().family == Family::NexStar
+                    && info.hc_version < required =>
+            {
+                Err(Error::UnsupportedFirmware {
+                    required,
+                    found: info.hc_version,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Like [`NexStar::require_firmware`] for the firmware of a motor
+    /// controller, which is only known once [`NexStar::connect`] read it.
+    fn require_motor_firmware(
+        &self,
+        device: Device,
+        required: Version,
+    ) -> Result<(), Error<T::Error, U::Error>> {
+        match self.versions.device(device) {
+            Some(found) if self.check_firmware && found < required => {
+                Err(Error::UnsupportedFirmware { required, found })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Queries the HC version and picks the [`Compat`] from it, unless one
+    /// was set.
+    fn detect_compat(&mut self) -> Result<Version, Error<T::Error, U::Error>> {
+        if self.compat_override.is_none() {
+            return self.detect_family();
+        }
+        let version = self.version()?;
+        self.compat = Compat::for_version(version);
+        Ok(version)
+    }
+
+    /// Tells the families apart by the version answer: two binary bytes
+    /// before the ack from a NexStar HC, three from a StarSense HC (with the
+    /// detection on) and six hex digits from a SynScan HC.
+    ///
+    /// A StarSense build of 35 is a `#` itself, so its answer only differs
+    /// from a NexStar one by a second ack right after it.
+    fn detect_family(&mut self) -> Result<Version, Error<T::Error, U::Error>> {
+        let mut command = [0u8; MAX_COMMAND];
+        let len = encode_command(&Request::GetVersion, &mut command).ok_or(Error::FrameOverflow)?;
+        self.write_all(command.get(..len).ok_or(Error::FrameOverflow)?)?;
+
+        let mut buffer = [0u8; 7];
+        self.read_multiple(&mut buffer[..3])?;
+        let starsense = self.starsense_detection;
+        let (version, compat) = if buffer[2] == b'#' {
+            match starsense {
+                Some(clock) if self.second_ack(clock)? => {
+                    let version =
+                        parse::starsense_version(&buffer[..3]).ok_or_else(|| self.unexpected())?;
+                    (version, Compat::STARSENSE)
+                }
+                _ => {
+                    let version = parse::version(&buffer[..2]).ok_or_else(|| self.unexpected())?;
+                    (version, Compat::for_version(version))
+                }
+            }
+        } else {
+            self.read_multiple(&mut buffer[3..4])?;
+            if buffer[3] != b'#' {
+                self.read_synscan_version(&mut buffer)?
+            } else if starsense.is_some() {
+                let version =
+                    parse::starsense_version(&buffer[..3]).ok_or_else(|| self.unexpected())?;
+                (version, Compat::STARSENSE)
+            } else {
+                return Err(self.unexpected());
+            }
+        };
+        self.compat = compat;
+        self.versions.hc = Some(version);
+        Ok(version)
+    }
+
+    /// Waits up to [`STARSENSE_ACK_MS`] for the ack of a StarSense version
+    /// answer ending in `##`. A NexStar HC sends nothing more, any other byte
+    /// is held for the next read.
+    fn second_ack(&mut self, clock: fn() -> u32) -> Result<bool, Error<T::Error, U::Error>> {
+        let start = clock();
+        loop {
+            match self.receive() {
+                Ok(b'#') => {
+                    self.log(Record::Received, b"#");
+                    return Ok(true);
+                }
+                Ok(byte) => {
+                    // the start of the next response, kept for its read
+                    self.held = Some(byte);
+                    return Ok(false);
+                }
+                Err(nb::Error::WouldBlock) => {
+                    if clock().wrapping_sub(start) > STARSENSE_ACK_MS {
+                        return Ok(false);
+                    }
+                }
+                Err(nb::Error::Other(e)) => {
+                    self.log_error(Event::Read);
+                    self.link_state = LinkState::Dirty;
+                    return Err(Error::Read(e));
+                }
+            }
+        }
+    }
+
+    /// Reads the rest of a SynScan version answer whose first 4 bytes are in
+    /// `buffer`.
+    #[cfg(feature = "synscan")]
+    fn read_synscan_version(
+        &mut self,
+        buffer: &mut [u8; 7],
+    ) -> Result<(Version, Compat), Error<T::Error, U::Error>> {
+        self.read_multiple(&mut buffer[4..])?;
+        let version = match *buffer {
+            [ref data @ .., b'#'] => parse::synscan_version(data),
+            _ => None,
+        };
+        Ok((version.ok_or_else(|| self.unexpected())?, Compat::SYNSCAN))
+    }
+
+    #[cfg(not(feature = "synscan"))]
+    fn read_synscan_version(
+        &mut self,
+        _buffer: &mut [u8; 7],
+    ) -> Result<(Version, Compat), Error<T::Error, U::Error>> {
+        Err(self.unexpected())
+    }
+
+    /// Gets the version of a motor for [`NexStar::connect`], `None` if it
+    /// didn't answer or the HC family doesn't pass the query on.
+    fn motor_version(
+        &mut self,
+        device: Device,
+    ) -> Result<Option<Version>, Error<T::Error, U::Error>> {
+        if self.is_synscan() {
+            return Ok(None);
+        }
+        self.optional_device_version(device)
+    }
+
+    fn is_synscan(&self) -> bool {
+        #[cfg(feature = "synscan")]
+        if self.compat().family == Family::SynScan {
+            return true;
+        }
+        false
+    }
+
+    fn is_starsense(&self) -> bool {
+        self.compat().family == Family::StarSense
+    }
+
+    fn version_request(&self) -> Request {
+        if self.is_starsense() {
+            return Request::GetStarSenseVersion;
+        }
+        #[cfg(feature = "synscan")]
+        if self.is_synscan() {
+            return Request::GetSynScanVersion;
+        }
+        Request::GetVersion
+    }
+
+    fn model_request(&self) -> Request {
+        if self.is_starsense() {
+            return Request::GetStarSenseModel;
+        }
+        #[cfg(feature = "synscan")]
+        if self.is_synscan() {
+            return Request::GetSynScanModel;
+        }
+        Request::GetModel
+    }
+
+    /// Refuses a command the HC lacks according to its [`Compat`].
+    fn require_compat(
+        &self,
+        supported: bool,
+        required: Version,
+    ) -> Result<(), Error<T::Error, U::Error>> {
+        if supported {
+            return Ok(());
+        }
+        Err(Error::UnsupportedFirmware {
+            required,
+            found: self.info.map_or(Version::new(0, 0), |info| info.hc_version),
+        })
+    }
+
+    /// Refuses a command the connected model lacks the feature for, passes
+    /// before [`NexStar::connect`] and with the checks disabled.
+    fn require_capability(
+        &self,
+        has: fn(Capabilities) -> bool,
+    ) -> Result<(), Error<T::Error, U::Error>> {
+        match self.info {
+            Some(info) if self.check_capabilities && !has(info.model.capabilities()) => {
+                Err(Error::UnsupportedByModel(info.model))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Gets a one byte flag of a sub device.
+    fn device_flag(
+        &mut self,
+        device: Device,
+        command: Command,
+    ) -> Result<bool, Error<T::Error, U::Error>> {
+        let request = Request::pass_through(device, command, [], 1);
+
+        match self.transact_or_unsupported(request)? {
+            Response::State(set) => Ok(set),
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    /// Gets a one byte setting of a motor controller.
+    fn motor_setting(
+        &mut self,
+        device: Device,
+        command: Command,
+    ) -> Result<u8, Error<T::Error, U::Error>> {
+        let request = Request::pass_through(device, command, [], 1);
+
+        match self.transact_or_unsupported(request)? {
+            Response::Data(data) => match *data.as_bytes() {
+                [value] => Ok(value),
+                _ => Err(self.unexpected()),
+            },
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    /// Sends a GPS query expecting `len` bytes and parses them.
+    fn gps_query<P>(
+        &mut self,
+        command: Command,
+        len: u8,
+        parse: fn(&[u8]) -> Option<P>,
+    ) -> Result<P, Error<T::Error, U::Error>> {
+        self.device_query(Device::GPSUnit, command, len, parse)
+    }
+
+    /// Sends a query to a sub device expecting `len` bytes and parses them.
+    fn device_query<P>(
+        &mut self,
+        device: Device,
+        command: Command,
+        len: u8,
+        parse: fn(&[u8]) -> Option<P>,
+    ) -> Result<P, Error<T::Error, U::Error>> {
+        let request = Request::pass_through(device, command, [], len);
+
+        match self.transact(request)? {
+            Response::Data(data) => match parse(data.as_bytes()) {
+                Some(value) => Ok(value),
+                None => Err(self.unexpected()),
+            },
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    fn read_position<P>(
+        &mut self,
+        axes: Axes,
+        precise: bool,
+        convert: fn(u32, u32, bool) -> P,
+    ) -> Result<(P, P), Error<T::Error, U::Error>> {
+        let (request, bits) = if !precise && self.compat().wide_positions {
+            (Request::GetWidePosition { axes }, WIDE_POSITION_BITS)
+        } else {
+            (Request::GetPosition { axes, precise }, resolution(precise))
+        };
+        match self.transact(request)? {
+            Response::Position(Counts { first, second }) => {
+                Ok((convert(first, bits, false), convert(second, bits, true)))
+            }
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    /// Starts a GOTO to the position, or syncs to it without `axes`.
+    fn write_position(
+        &mut self,
+        axes: Option<Axes>,
+        position: Counts,
+        precise: bool,
+    ) -> Result<(), Error<T::Error, U::Error>> {
+        let request = match axes {
+            Some(axes) => Request::Goto {
+                axes,
+                position,
+                precise,
+            },
+            None => Request::Sync { position, precise },
+        };
+        self.transact(request)?;
+        Ok(())
+    }
+
+    #[cfg(feature = "float")]
+    fn write_degrees(
+        &mut self,
+        axes: Option<Axes>,
+        first: f32,
+        second: f32,
+        precise: bool,
+    ) -> Result<(), Error<T::Error, U::Error>> {
+        self.check_goto_floor(axes, first, second)?;
+        let bits = resolution(precise);
+        let position = Counts {
+            first: degrees_to_fraction(first, bits),
+            second: degrees_to_fraction(second, bits),
+        };
+        self.write_position(axes, position, precise)
+    }
+
+    fn write_arcsec(
+        &mut self,
+        axes: Option<Axes>,
+        first: i32,
+        second: i32,
+        precise: bool,
+    ) -> Result<(), Error<T::Error, U::Error>> {
+        #[cfg(feature = "float")]
+        self.check_goto_floor(axes, first as f32 / 3600.0, second as f32 / 3600.0)?;
+        let bits = resolution(precise);
+        let position = Counts {
+            first: arcsec_to_fraction(first, bits),
+            second: arcsec_to_fraction(second, bits),
+        };
+        self.write_position(axes, position, precise)
+    }
+
+    /// Refuses a GOTO below the floor set with [`NexStar::set_goto_floor`],
+    /// syncs (`axes` is `None`) are never refused.
+    #[cfg(feature = "float")]
+    #[cfg_attr(not(feature = "astro"), allow(unused_variables))]
+    fn check_goto_floor(
+        &mut self,
+        axes: Option<Axes>,
+        first: f32,
+        second: f32,
+    ) -> Result<(), Error<T::Error, U::Error>> {
+        let limit = match self.goto_floor {
+            Some(limit) => limit,
+            None => return Ok(()),
+        };
+        let altitude = match axes {
+            Some(Axes::AzmAlt) => second,
+            #[cfg(feature = "astro")]
+            Some(Axes::RaDec) => self.altitude(RaDec {
+                ra: first,
+                dec: second,
+            })?,
+            _ => return Ok(()),
+        };
+
+        if altitude < limit {
+            return Err(Error::BelowAltitudeLimit { altitude, limit });
+        }
+        Ok(())
+    }
+
+    /// Gets the altitude of the target from the location and time of the HC,
+    /// refracted if set with [`NexStar::set_refraction`].
+    #[cfg(feature = "astro")]
+    fn altitude(&mut self, target: RaDec) -> Result<f32, Error<T::Error, U::Error>> {
+        let location = self.location()?;
+        let datetime = self.datetime()?;
+
+        Ok(match self.refraction {
+            Some((temperature_c, pressure_hpa)) => {
+                astro::apparent_altitude(target, location, datetime, temperature_c, pressure_hpa)
+            }
+            None => astro::altitude(target, location, datetime),
+        })
+    }
+
+    #[cfg(feature = "float")]
+    fn slew_variable(
+        &mut self,
+        device: Device,
+        arcsec_per_sec: f32,
+    ) -> Result<(), Error<T::Error, U::Error>> {
+        let (cmd, rate) = variable_rate(arcsec_per_sec);
+        self.transact(Request::pass_through(device, cmd, rate, 0))?;
+        Ok(())
+    }
+
+    /// Sends the command and reads the response it expects.
+    fn transact(&mut self, request: Request) -> Result<Response, Error<T::Error, U::Error>> {
+        match self.exchange(request)? {
+            Ok(response) => Ok(response),
+            Err(ParseError::Rejected) => Err(self.aligning_or(Self::unexpected)),
+            Err(_) => Err(self.unexpected()),
+        }
+    }
+
+    /// Sends a command newer firmware may not know, a nack fails with
+    /// [`Error::UnsupportedCommand`].
+    fn transact_or_unsupported(
+        &mut self,
+        request: Request,
+    ) -> Result<Response, Error<T::Error, U::Error>> {
+        match self.exchange(request)? {
+            Ok(response) => Ok(response),
+            // the nack was read completely, the link is still in sync
+            Err(ParseError::Rejected) => Err(self.aligning_or(Self::nack)),
+            Err(_) => Err(self.unexpected()),
+        }
+    }
+
+    /// Sends the command and parses the response, only I/O errors fail.
+    fn exchange(
+        &mut self,
+        request: Request,
+    ) -> Result<Result<Response, ParseError>, Error<T::Error, U::Error>> {
+        self.check_slew_watchdog()?;
+
+        let mut command = [0u8; MAX_COMMAND];
+        let len = encode_command(&request, &mut command).ok_or(Error::FrameOverflow)?;
+        self.write_all(command.get(..len).ok_or(Error::FrameOverflow)?)?;
+
+        // a timeout before all data arrived reports the missing data bytes
+        let mut buffer = [0u8; MAX_RESPONSE + 2];
+        let len = request.response_len();
+        self.read_multiple(buffer.get_mut(..len).ok_or(Error::FrameOverflow)?)?;
+
+        // the response is decided after at most two more bytes
+        let mut received = len;
+        loop {
+            match parse_response(&request, &buffer[..received]) {
+                Err(ParseError::Incomplete) => {
+                    *buffer.get_mut(received).ok_or(Error::FrameOverflow)? = self.read()?;
+                    received += 1;
+                }
+                result => return Ok(result),
+            }
+        }
+    }
+
+    fn unexpected(&mut self) -> Error<T::Error, U::Error> {
+        self.link_state = LinkState::Dirty;
+        self.log_error(Event::Unexpected);
+        Error::UnexpectedResponse(self.context)
+    }
+
+    /// Fails a command a StarSense HC rejected during its automatic alignment
+    /// with [`Error::Aligning`], otherwise with `error`.
+    ///
+    /// The alignment state is queried after the nack, a nack of that query
+    /// counts as aligning too.
+    fn aligning_or(
+        &mut self,
+        error: fn(&mut Self) -> Error<T::Error, U::Error>,
+    ) -> Error<T::Error, U::Error> {
+        if self.is_starsense() {
+            let context = self.context;
+            let aligning = self.exchange(Request::IsAlignmentComplete);
+            self.context = context;
+            match aligning {
+                Ok(Ok(Response::State(false))) | Ok(Err(ParseError::Rejected)) => {
+                    self.log_error(Event::Nack);
+                    return Error::Aligning(context);
+                }
+                Err(e) => return e,
+                _ => {}
+            }
+        }
+        error(self)
+    }
+
+    /// Fails a command the device rejected, the link stays in sync.
+    fn nack(&mut self) -> Error<T::Error, U::Error> {
+        self.log_error(Event::Nack);
+        Error::UnsupportedCommand(self.context)
+    }
+
+    /// Records the bytes in the diagnostics, if there are any.
+    fn log(&mut self, record: Record, bytes: &[u8]) {
+        if let Some(diagnostics) = &mut self.diagnostics {
+            diagnostics.record_all(record, bytes);
+        }
+    }
+
+    fn log_error(&mut self, event: Event) {
+        if let Some(diagnostics) = &mut self.diagnostics {
+            diagnostics.record(Record::Error, event as u8);
+        }
+    }
+
+    fn read_version(&mut self, request: Request) -> Result<Version, Error<T::Error, U::Error>> {
+        match self.transact(request)? {
+            Response::Version(version) => Ok(version),
+            _ => Err(self.unexpected()),
+        }
+    }
+}
+
+/// Transport half borrowed by [`NexStar::borrowed`]
+pub struct Borrowed<'a, S>(&'a mut S);
+
+impl<'a, S: serial::Read<u8>> serial::Read<u8> for Borrowed<'a, S> {
+    type Error = S::Error;
+
+    fn read(&mut self) -> nb::Result<u8, S::Error> {
+        self.0.read()
+    }
+}
+
+impl<'a, S: serial::Write<u8>> serial::Write<u8> for Borrowed<'a, S> {
+    type Error = S::Error;
+
+    fn write(&mut self, word: u8) -> nb::Result<(), S::Error> {
+        self.0.write(word)
+    }
+
+    fn flush(&mut self) -> nb::Result<(), S::Error> {
+        self.0.flush()
+    }
+}
+
+impl<'a, R, W> NexStar<Borrowed<'a, R>, Borrowed<'a, W>>
+where
+    R: serial::Read<u8>,
+    W: serial::Write<u8>,
+{
+    /// Creates a driver borrowing the transport halves, e.g. for a UART
+    /// shared with another peripheral.
+    ///
+    /// The driver is cheap to create for every interaction, but its state
+    /// (the [`NexStar::connect`] result, hooks, link state) lives only as long
+    /// as the borrow.
+    pub fn borrowed(rx: &'a mut R, tx: &'a mut W) -> Self {
+        NexStar::new(Borrowed(rx), Borrowed(tx))
+    }
+}
+
+/// Position poller created by the `positions` methods of [`NexStar`], e.g.
+/// [`NexStar::positions_arcsec`]
+///
+/// The first sample is read immediately, every following one after waiting the
+/// interval. The poller borrows the driver, use [`Positions::nexstar`] to issue
+/// other commands between samples:
+///
+/// ```ignore
+/// let mut positions = nexstar.positions(&mut delay, 1000);
+/// while let Some(position) = positions.next() {
+///     let position = position?;
+///     if positions.nexstar().is_goto_in_progress()? {
+///         // ...
+///     }
+/// }
+/// ```
+pub struct Positions<'a, T, U, D, P>
+where
+    T: serial::Read<u8>,
+    U: serial::Write<u8>,
+{
+    nexstar: &'a mut NexStar<T, U>,
+    delay: &'a mut D,
+    interval_ms: u32,
+    first: bool,
+    read: PositionReader<T, U, P>,
+}
+
+type PositionReader<T, U, P> =
+    fn(
+        &mut NexStar<T, U>,
+    ) -> Result<P, Error<<T as serial::Read<u8>>::Error, <U as serial::Write<u8>>::Error>>;
+
+impl<'a, T, U, D, P> Positions<'a, T, U, D, P>
+where
+    T: serial::Read<u8>,
+    U: serial::Write<u8>,
+    D: DelayMs<u32>,
+{
+    fn new(
+        nexstar: &'a mut NexStar<T, U>,
+        delay: &'a mut D,
+        interval_ms: u32,
+        read: PositionReader<T, U, P>,
+    ) -> Self {
+        Positions {
+            nexstar,
+            delay,
+            interval_ms,
+            first: true,
+            read,
+        }
+    }
+
+    /// Gets the driver to issue other commands between samples.
+    pub fn nexstar(&mut self) -> &mut NexStar<T, U> {
+        self.nexstar
+    }
+}
+
+impl<T, U, D, P> Iterator for Positions<'_, T, U, D, P>
+where
+    T: serial::Read<u8>,
+    U: serial::Write<u8>,
+    D: DelayMs<u32>,
+{
+    type Item = Result<P, Error<T::Error, U::Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.first {
+            self.delay.delay_ms(self.interval_ms);
+        }
+        self.first = false;
+
+        Some((self.read)(self.nexstar))
+    }
+}
+
+/// Outcome of [`NexStar::release`] per step, steps with nothing to do are `Ok`
+#[derive(Debug)]
+pub struct ReleaseReport<T, U> {
+    pub cancel_goto: Result<(), Error<T, U>>,
+    /// Stop of the rates sent by [`NexStar::track_rates`]
+    pub stop_rates: Result<(), Error<T, U>>,
+    /// Discarding the bytes left on the receive side
+    pub drain: Result<(), Error<T, U>>,
+}
+
+impl<T, U> ReleaseReport<T, U> {
+    /// Gets whether every step succeeded.
+    pub fn is_ok(&self) -> bool {
+        self.cancel_goto.is_ok() && self.stop_rates.is_ok() && self.drain.is_ok()
+    }
+}
+
+/// Slew started by [`NexStar::begin_slew`], which stops both motors when
+/// dropped
+///
+/// Dropping the guard can't return the errors of the stop commands: a failed
+/// stop marks the link dirty, see [`NexStar::link_state`].
+/// [`SlewGuard::finish`] stops the motors and returns the error.
+pub struct SlewGuard<'a, T, U>
+where
+    T: serial::Read<u8>,
+    U: serial::Write<u8>,
+{
+    nexstar: &'a mut NexStar<T, U>,
+    /// Whether the motors still need to be stopped
+    active: bool,
+}
+
+impl<T, U> SlewGuard<'_, T, U>
+where
+    T: serial::Read<u8>,
+    U: serial::Write<u8>,
+{
+    /// Changes the rates of both motors in arcsec/s, see
+    /// [`NexStar::track_rates`].
+    pub fn set_rates(
+        &mut self,
+        azm_arcsec_per_sec: i32,
+        alt_arcsec_per_sec: i32,
+    ) -> Result<(), Error<T::Error, U::Error>> {
+        self.nexstar
+            .track_rates(azm_arcsec_per_sec, alt_arcsec_per_sec)
+    }
+
+    /// Gets the driver to issue other commands while slewing.
+    pub fn nexstar(&mut self) -> &mut NexStar<T, U> {
+        self.nexstar
+    }
+
+    /// Stops both motors.
+    pub fn finish(mut self) -> Result<(), Error<T::Error, U::Error>> {
+        self.active = false;
+        self.nexstar.stop_tracking_rates()
+    }
+}
+
+impl<T, U> Drop for SlewGuard<'_, T, U>
+where
+    T: serial::Read<u8>,
+    U: serial::Write<u8>,
+{
+    fn drop(&mut self) {
+        if self.active && self.nexstar.stop_tracking_rates().is_err() {
+            self.nexstar.link_state = LinkState::Dirty;
+        }
+    }
+}
+
+/// Raw access to the receiving half without dismantling the driver, e.g. to
+/// talk to a device behind the HC in a protocol the driver doesn't know.
+///
+/// Every command completes within its method call, so raw access never
+/// interrupts one. The raw bytes may leave a response unread though, so each
+/// byte read marks the link dirty and the next command resyncs it first (or
+/// fails with [`Error::LinkDirty`] without auto resync).
+impl<T, U> serial::Read<u8> for NexStar<T, U>
+where
+    T: serial::Read<u8>,
+    U: serial::Write<u8>,
+{
+    type Error = T::Error;
+
+    fn read(&mut self) -> nb::Result<u8, Self::Error> {
+        let result = self.receive();
+        if !matches!(result, Err(nb::Error::WouldBlock)) {
+            self.link_state = LinkState::Dirty;
+        }
+        result
+    }
+}
+
+/// Raw access to the transmitting half, see the `serial::Read` impl. Each
+/// byte written marks the link dirty.
+impl<T, U> serial::Write<u8> for NexStar<T, U>
+where
+    T: serial::Read<u8>,
+    U: serial::Write<u8>,
+{
+    type Error = U::Error;
+
+    fn write(&mut self, word: u8) -> nb::Result<(), Self::Error> {
+        let result = self.tx.write(word);
+        if !matches!(result, Err(nb::Error::WouldBlock)) {
+            self.link_state = LinkState::Dirty;
+        }
+        result
+    }
+
+    fn flush(&mut self) -> nb::Result<(), Self::Error> {
+        self.tx.flush()
+    }
+}
+
+impl<T, U> Default<u8> for NexStar<T, U>
+where
+    T: serial::Read<u8>,
+    U: serial::Write<u8>,
+{
+}
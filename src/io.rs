@@ -0,0 +1,148 @@
+//! Adapters between [`embedded_io`] and the `embedded-hal` serial traits
+//!
+//! [`FromEmbeddedIo`] turns an `embedded-io` transport into the serial halves
+//! the driver expects:
+//!
+//! ```ignore
+//! let (rx, tx) = (FromEmbeddedIo::new(uart_rx), FromEmbeddedIo::new(uart_tx));
+//! let mut nexstar = NexStar::new(rx, tx);
+//! ```
+//!
+//! In the other direction [`NexStar`] implements the `embedded-io` traits for
+//! raw access to the port, with the same link state handling as its serial
+//! impls.
+//!
+//! The errors of an `embedded-io` transport are kept as they are, so their
+//! [`ErrorKind`] stays available in [`Error::Read`](crate::Error::Read) and
+//! [`Error::Write`](crate::Error::Write). The serial errors of the raw access
+//! have no kind and are reported as [`ErrorKind::Other`].
+
+use core::fmt;
+
+use embedded_hal::serial;
+use embedded_io::{ErrorKind, ErrorType, Read, ReadReady, Write};
+use nb::block;
+
+use crate::NexStar;
+
+/// Serial half backed by an `embedded-io` transport
+///
+/// Reading requires [`ReadReady`], so the driver can poll without blocking
+/// and its response timeouts keep working. A transport at its end reads as
+/// no data. Writes block until the byte was accepted.
+pub struct FromEmbeddedIo<S> {
+    inner: S,
+}
+
+impl<S> FromEmbeddedIo<S> {
+    pub fn new(inner: S) -> Self {
+        FromEmbeddedIo { inner }
+    }
+
+    pub fn free(self) -> S {
+        self.inner
+    }
+}
+
+impl<S: Read + ReadReady> serial::Read<u8> for FromEmbeddedIo<S> {
+    type Error = S::Error;
+
+    fn read(&mut self) -> nb::Result<u8, S::Error> {
+        if !self.inner.read_ready()? {
+            return Err(nb::Error::WouldBlock);
+        }
+
+        let mut byte = [0u8];
+        match self.inner.read(&mut byte)? {
+            0 => Err(nb::Error::WouldBlock),
+            _ => Ok(byte[0]),
+        }
+    }
+}
+
+impl<S: Write> serial::Write<u8> for FromEmbeddedIo<S> {
+    type Error = S::Error;
+
+    fn write(&mut self, word: u8) -> nb::Result<(), S::Error> {
+        match self.inner.write(&[word])? {
+            0 => Err(nb::Error::WouldBlock),
+            _ => Ok(()),
+        }
+    }
+
+    fn flush(&mut self) -> nb::Result<(), S::Error> {
+        Ok(self.inner.flush()?)
+    }
+}
+
+/// Error of the raw `embedded-io` access to [`NexStar`]
+#[derive(Debug)]
+pub enum RawError<R, W> {
+    Read(R),
+    Write(W),
+}
+
+impl<R: fmt::Debug, W: fmt::Debug> embedded_io::Error for RawError<R, W> {
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::Other
+    }
+}
+
+impl<T, U> ErrorType for NexStar<T, U>
+where
+    T: serial::Read<u8>,
+    U: serial::Write<u8>,
+    T::Error: fmt::Debug,
+    U::Error: fmt::Debug,
+{
+    type Error = RawError<T::Error, U::Error>;
+}
+
+/// Blocks until at least one byte arrived and returns the bytes available
+/// without blocking further. Marks the link dirty like the serial impl.
+impl<T, U> Read for NexStar<T, U>
+where
+    T: serial::Read<u8>,
+    U: serial::Write<u8>,
+    T::Error: fmt::Debug,
+    U::Error: fmt::Debug,
+{
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let (first, rest) = match buf.split_first_mut() {
+            Some(split) => split,
+            None => return Ok(0),
+        };
+        *first = block!(serial::Read::read(self)).map_err(RawError::Read)?;
+
+        let mut len = 1;
+        for byte in rest {
+            match serial::Read::read(self) {
+                Ok(value) => *byte = value,
+                Err(nb::Error::WouldBlock) => break,
+                Err(nb::Error::Other(e)) => return Err(RawError::Read(e)),
+            }
+            len += 1;
+        }
+        Ok(len)
+    }
+}
+
+/// Writes all bytes and marks the link dirty like the serial impl.
+impl<T, U> Write for NexStar<T, U>
+where
+    T: serial::Read<u8>,
+    U: serial::Write<u8>,
+    T::Error: fmt::Debug,
+    U::Error: fmt::Debug,
+{
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        for byte in buf {
+            block!(serial::Write::write(self, *byte)).map_err(RawError::Write)?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        block!(serial::Write::flush(self)).map_err(RawError::Write)
+    }
+}
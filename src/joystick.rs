@@ -0,0 +1,142 @@
+//! Mapping of joystick axes to slew rates
+//!
+//! A [`JoystickMapper`] turns an axis deflection in `-1.0..=1.0` into a rate
+//! in arcsec/s for [`NexStar::slew_joystick`](crate::NexStar::slew_joystick):
+//!
+//! ```ignore
+//! let mapper = JoystickMapper::new(3600).with_deadzone(0.1);
+//! loop {
+//!     let (x, y) = read_stick();
+//!     nexstar.slew_joystick(x, y, &mapper)?;
+//! }
+//! ```
+
+use libm::{powf, roundf};
+
+/// Maps a joystick axis to a slew rate
+///
+/// Deflections within the deadzone are zero, the rest is stretched to
+/// `0.0..=1.0`, raised to the exponent of the curve and scaled to the maximum
+/// rate. The rate is rounded to multiples of the step, so a trembling hand
+/// doesn't produce a new rate on every poll.
+#[derive(Copy, Clone, Debug)]
+pub struct JoystickMapper {
+    max_rate: i32,
+    deadzone: f32,
+    exponent: f32,
+    step: i32,
+}
+
+impl JoystickMapper {
+    /// Creates a mapper with a deadzone of 0.05, a quadratic curve and a step
+    /// of 1/64 of the maximum rate in arcsec/s.
+    pub fn new(max_arcsec_per_sec: u32) -> JoystickMapper {
+        let max_rate = max_arcsec_per_sec.min(i32::MAX as u32) as i32;
+        JoystickMapper {
+            max_rate,
+            deadzone: 0.05,
+            exponent: 2.0,
+            step: (max_rate / 64).max(1),
+        }
+    }
+
+    /// Sets the deflection below which the rate is zero, `0.0..1.0`.
+    pub fn with_deadzone(mut self, deadzone: f32) -> Self {
+        self.deadzone = deadzone.clamp(0.0, 0.99);
+        self
+    }
+
+    /// Sets the exponent of the curve, 1.0 is linear and larger values give
+    /// finer control around the center.
+    pub fn with_exponent(mut self, exponent: f32) -> Self {
+        self.exponent = exponent.max(0.1);
+        self
+    }
+
+    /// Sets the step the rates are rounded to in arcsec/s, 1 disables the
+    /// rounding.
+    pub fn with_step(mut self, arcsec_per_sec: u32) -> Self {
+        self.step = arcsec_per_sec.clamp(1, i32::MAX as u32) as i32;
+        self
+    }
+
+    /// Gets the rate in arcsec/s of an axis deflected by `value`, which is
+    /// clamped to `-1.0..=1.0`. NaN maps to zero.
+    pub fn map(&self, value: f32) -> i32 {
+        let magnitude = value.abs();
+        if value.is_nan() || magnitude <= self.deadzone {
+            return 0;
+        }
+
+        // full deflection is the maximum even if it isn't a multiple of the step
+        let rate = if magnitude >= 1.0 {
+            self.max_rate
+        } else {
+            let stretched = (magnitude - self.deadzone) / (1.0 - self.deadzone);
+            let rate = powf(stretched, self.exponent) * self.max_rate as f32;
+            let steps = roundf(rate / self.step as f32) as i64;
+            (steps * self.step as i64).min(self.max_rate as i64) as i32
+        };
+
+        if value < 0.0 {
+            -rate
+        } else {
+            rate
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deadzone() {
+        let mapper = JoystickMapper::new(6400).with_deadzone(0.1);
+        assert_eq!(mapper.map(0.0), 0);
+        assert_eq!(mapper.map(0.1), 0);
+        assert_eq!(mapper.map(-0.05), 0);
+        assert_eq!(mapper.map(f32::NAN), 0);
+        assert!(mapper.map(0.2) > 0);
+    }
+
+    #[test]
+    fn curve_shape() {
+        let mapper = JoystickMapper::new(6400).with_deadzone(0.0).with_step(1);
+        assert_eq!(mapper.map(1.0), 6400);
+        assert_eq!(mapper.map(0.5), 1600);
+        assert_eq!(mapper.map(-0.25), -400);
+        // clamped to full deflection
+        assert_eq!(mapper.map(2.0), 6400);
+        assert_eq!(mapper.map(-3.0), -6400);
+
+        let linear = mapper.with_exponent(1.0);
+        assert_eq!(linear.map(0.5), 3200);
+        assert_eq!(linear.map(-0.75), -4800);
+    }
+
+    #[test]
+    fn curve_is_monotonic() {
+        let mapper = JoystickMapper::new(3600);
+        let mut last = 0;
+        for i in 0..=100 {
+            let rate = mapper.map(i as f32 / 100.0);
+            assert!(rate >= last, "{} < {} at {}", rate, last, i);
+            assert_eq!(mapper.map(-(i as f32) / 100.0), -rate);
+            last = rate;
+        }
+        assert_eq!(last, 3600);
+    }
+
+    #[test]
+    fn quantized() {
+        let mapper = JoystickMapper::new(6400)
+            .with_deadzone(0.0)
+            .with_exponent(1.0)
+            .with_step(100);
+        assert_eq!(mapper.map(0.5), 3200);
+        assert_eq!(mapper.map(0.501), 3200);
+        assert_eq!(mapper.map(0.509), 3300);
+        assert_eq!(mapper.map(0.007), 0);
+    }
+}
@@ -0,0 +1,240 @@
+//! Mount abstraction
+//!
+//! [`Mount`] has the user facing operations of [`NexStar`] so application
+//! logic can be written against `&mut dyn Mount` and tested with a fake (see
+//! `FakeMount` behind the `fake` feature) instead of a serial port:
+//!
+//! ```
+//! use nexstar::{Mount, RaDec};
+//!
+//! fn goto_next<E>(mount: &mut dyn Mount<Error = E>, targets: &[RaDec]) -> Result<(), E> {
+//!     if !mount.is_goto_in_progress()? {
+//!         if let Some(target) = targets.first() {
+//!             mount.precise_goto_ra_dec(*target)?;
+//!         }
+//!     }
+//!     Ok(())
+//! }
+//! ```
+//!
+//! The inherent methods of [`NexStar`] stay the canonical implementation, the
+//! trait only forwards to them. Operations taking a delay are generic and
+//! therefore not part of the trait.
+
+use embedded_hal::serial;
+
+use crate::{
+    AzmAlt, DateTime, Error, Location, MountInfo, MountStatus, NexStar, RaDec, Target, TrackingMode,
+};
+
+/// User facing operations of a mount
+///
+/// See the inherent methods of [`NexStar`] for the details of each operation.
+pub trait Mount {
+    type Error;
+
+    /// Checks the link and queries the mount model and firmware versions.
+    fn connect(&mut self) -> Result<MountInfo, Self::Error>;
+
+    // Position commands
+    /// Gets the RA/Dec position.
+    fn ra_dec(&mut self) -> Result<RaDec, Self::Error>;
+
+    /// Gets the precise RA/Dec position.
+    fn precise_ra_dec(&mut self) -> Result<RaDec, Self::Error>;
+
+    /// Gets the Azm/Alt position.
+    fn azm_alt(&mut self) -> Result<AzmAlt, Self::Error>;
+
+    /// Gets the precise Azm/Alt position.
+    fn precise_azm_alt(&mut self) -> Result<AzmAlt, Self::Error>;
+
+    // GOTO commands
+    /// Starts a GOTO to the RA/Dec position.
+    fn goto_ra_dec(&mut self, target: RaDec) -> Result<(), Self::Error>;
+
+    /// Starts a precise GOTO to the RA/Dec position.
+    fn precise_goto_ra_dec(&mut self, target: RaDec) -> Result<(), Self::Error>;
+
+    /// Starts a GOTO to the Azm/Alt position.
+    fn goto_azm_alt(&mut self, target: AzmAlt) -> Result<(), Self::Error>;
+
+    /// Starts a precise GOTO to the Azm/Alt position.
+    fn precise_goto_azm_alt(&mut self, target: AzmAlt) -> Result<(), Self::Error>;
+
+    /// Starts a precise GOTO to the target.
+    fn goto(&mut self, target: Target) -> Result<(), Self::Error>;
+
+    /// Cancels the GOTO in progress.
+    fn cancel_goto(&mut self) -> Result<(), Self::Error>;
+
+    /// Gets GOTO state.
+    fn is_goto_in_progress(&mut self) -> Result<bool, Self::Error>;
+
+    /// Gets the target of the last GOTO started.
+    fn last_target(&self) -> Option<Target>;
+
+    /// Checks whether the mount points within `tolerance_arcsec` of the last
+    /// GOTO target.
+    fn on_target(&mut self, tolerance_arcsec: f32) -> Result<bool, Self::Error>;
+
+    // Sync commands
+    /// Syncs the mount to the RA/Dec position.
+    fn sync_ra_dec(&mut self, position: RaDec) -> Result<(), Self::Error>;
+
+    /// Syncs the mount to the precise RA/Dec position.
+    fn precise_sync_ra_dec(&mut self, position: RaDec) -> Result<(), Self::Error>;
+
+    // Tracking commands
+    /// Gets the tracking mode.
+    fn tracking_mode(&mut self) -> Result<TrackingMode, Self::Error>;
+
+    /// Sets the tracking mode.
+    fn set_tracking_mode(&mut self, mode: TrackingMode) -> Result<(), Self::Error>;
+
+    /// Drives both motors at independent signed rates in arcsec/s.
+    fn track_rates(
+        &mut self,
+        azm_arcsec_per_sec: i32,
+        alt_arcsec_per_sec: i32,
+    ) -> Result<(), Self::Error>;
+
+    /// Stops both motors started by [`Mount::track_rates`].
+    fn stop_tracking_rates(&mut self) -> Result<(), Self::Error>;
+
+    // Alignment
+    /// Gets the alignment state.
+    fn is_alignment_complete(&mut self) -> Result<bool, Self::Error>;
+
+    /// Gets the alignment, GOTO and tracking state.
+    fn status(&mut self) -> Result<MountStatus, Self::Error>;
+
+    // Time/Location commands
+    /// Gets the currently set location of the telescope.
+    fn location(&mut self) -> Result<Location, Self::Error>;
+
+    /// Sets the location of the Hand Controller (HC).
+    fn set_location(&mut self, location: Location) -> Result<(), Self::Error>;
+
+    /// Gets the currently set date and time of the Hand Controller (HC).
+    fn datetime(&mut self) -> Result<DateTime, Self::Error>;
+
+    /// Sets date and time of the Hand Controller (HC).
+    fn set_datetime(&mut self, datetime: DateTime) -> Result<(), Self::Error>;
+}
+
+impl<T, U> Mount for NexStar<T, U>
+where
+    T: serial::Read<u8>,
+    U: serial::Write<u8>,
+{
+    type Error = Error<T::Error, U::Error>;
+
+    fn connect(&mut self) -> Result<MountInfo, Self::Error> {
+        NexStar::connect(self)
+    }
+
+    fn ra_dec(&mut self) -> Result<RaDec, Self::Error> {
+        NexStar::ra_dec(self)
+    }
+
+    fn precise_ra_dec(&mut self) -> Result<RaDec, Self::Error> {
+        NexStar::precise_ra_dec(self)
+    }
+
+    fn azm_alt(&mut self) -> Result<AzmAlt, Self::Error> {
+        NexStar::azm_alt(self)
+    }
+
+    fn precise_azm_alt(&mut self) -> Result<AzmAlt, Self::Error> {
+        NexStar::precise_azm_alt(self)
+    }
+
+    fn goto_ra_dec(&mut self, target: RaDec) -> Result<(), Self::Error> {
+        NexStar::goto_ra_dec(self, target)
+    }
+
+    fn precise_goto_ra_dec(&mut self, target: RaDec) -> Result<(), Self::Error> {
+        NexStar::precise_goto_ra_dec(self, target)
+    }
+
+    fn goto_azm_alt(&mut self, target: AzmAlt) -> Result<(), Self::Error> {
+        NexStar::goto_azm_alt(self, target)
+    }
+
+    fn precise_goto_azm_alt(&mut self, target: AzmAlt) -> Result<(), Self::Error> {
+        NexStar::precise_goto_azm_alt(self, target)
+    }
+
+    fn goto(&mut self, target: Target) -> Result<(), Self::Error> {
+        NexStar::goto(self, target)
+    }
+
+    fn cancel_goto(&mut self) -> Result<(), Self::Error> {
+        NexStar::cancel_goto(self)
+    }
+
+    fn is_goto_in_progress(&mut self) -> Result<bool, Self::Error> {
+        NexStar::is_goto_in_progress(self)
+    }
+
+    fn last_target(&self) -> Option<Target> {
+        NexStar::last_target(self)
+    }
+
+    fn on_target(&mut self, tolerance_arcsec: f32) -> Result<bool, Self::Error> {
+        NexStar::on_target(self, tolerance_arcsec)
+    }
+
+    fn sync_ra_dec(&mut self, position: RaDec) -> Result<(), Self::Error> {
+        NexStar::sync_ra_dec(self, position)
+    }
+
+    fn precise_sync_ra_dec(&mut self, position: RaDec) -> Result<(), Self::Error> {
+        NexStar::precise_sync_ra_dec(self, position)
+    }
+
+    fn tracking_mode(&mut self) -> Result<TrackingMode, Self::Error> {
+        NexStar::tracking_mode(self)
+    }
+
+    fn set_tracking_mode(&mut self, mode: TrackingMode) -> Result<(), Self::Error> {
+        NexStar::set_tracking_mode(self, mode)
+    }
+
+    fn track_rates(
+        &mut self,
+        azm_arcsec_per_sec: i32,
+        alt_arcsec_per_sec: i32,
+    ) -> Result<(), Self::Error> {
+        NexStar::track_rates(self, azm_arcsec_per_sec, alt_arcsec_per_sec)
+    }
+
+    fn stop_tracking_rates(&mut self) -> Result<(), Self::Error> {
+        NexStar::stop_tracking_rates(self)
+    }
+
+    fn is_alignment_complete(&mut self) -> Result<bool, Self::Error> {
+        NexStar::is_alignment_complete(self)
+    }
+
+    fn status(&mut self) -> Result<MountStatus, Self::Error> {
+        NexStar::status(self)
+    }
+
+    fn location(&mut self) -> Result<Location, Self::Error> {
+        NexStar::location(self)
+    }
+
+    fn set_location(&mut self, location: Location) -> Result<(), Self::Error> {
+        NexStar::set_location(self, location)
+    }
+
+    fn datetime(&mut self) -> Result<DateTime, Self::Error> {
+        NexStar::datetime(self)
+    }
+
+    fn set_datetime(&mut self, datetime: DateTime) -> Result<(), Self::Error> {
+        NexStar::set_datetime(self, datetime)
+    }
+}
@@ -0,0 +1,254 @@
+//! NMEA 0183 sentences of a GPS receiver attached to the MCU
+//!
+//! Parses the GGA and RMC sentences into a [`Location`] for
+//! [`NexStar::set_location`](crate::NexStar::set_location), RMC also carries
+//! the UTC date and time for [`DateTime::from_utc`](crate::DateTime::from_utc):
+//!
+//! ```
+//! use nexstar::nmea::{self, Sentence};
+//!
+//! let sentence = b"$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47";
+//! match nmea::parse(sentence) {
+//!     Ok(Sentence::Gga { location }) => assert!((location.latitude - 48.1173).abs() < 1e-4),
+//!     _ => unreachable!(),
+//! }
+//! ```
+//!
+//! Any talker (`GP`, `GN`, `GL`, ...) is accepted. Sentences must carry a
+//! valid checksum, a trailing line break is ignored.
+
+use crate::types::{Location, UtcDateTime};
+
+/// Parsed sentence
+#[derive(Copy, Clone, Debug)]
+pub enum Sentence {
+    /// GGA, position of the fix
+    Gga { location: Location },
+    /// RMC, position and UTC date and time of the fix
+    Rmc {
+        location: Location,
+        utc: UtcDateTime,
+    },
+}
+
+impl Sentence {
+    pub fn location(&self) -> Location {
+        match *self {
+            Sentence::Gga { location } | Sentence::Rmc { location, .. } => location,
+        }
+    }
+}
+
+/// Sentence which can't be parsed
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum NmeaError {
+    /// The checksum is missing or doesn't match.
+    Checksum,
+    /// A field is missing or malformed.
+    Invalid,
+    /// The receiver has no fix.
+    NoFix,
+    /// Neither a GGA nor an RMC sentence.
+    Unsupported,
+}
+
+/// Parses a GGA or RMC sentence starting with the `$`.
+pub fn parse(sentence: &[u8]) -> Result<Sentence, NmeaError> {
+    let body = checked_body(trim_line_break(sentence))?;
+    let mut fields = body.split(|&byte| byte == b',');
+
+    let address = fields.next().ok_or(NmeaError::Invalid)?;
+    match address {
+        [_, _, b'G', b'G', b'A'] => gga(fields),
+        [_, _, b'R', b'M', b'C'] => rmc(fields),
+        _ => Err(NmeaError::Unsupported),
+    }
+}
+
+fn gga<'a, I: Iterator<Item = &'a [u8]>>(mut fields: I) -> Result<Sentence, NmeaError> {
+    let _time = fields.next();
+    let location = location(&mut fields)?;
+    // fix quality, 0 is invalid
+    match fields.next() {
+        Some(b"0") | Some(b"") => Err(NmeaError::NoFix),
+        Some(quality) if is_digits(quality) => Ok(Sentence::Gga {
+            location: location.ok_or(NmeaError::Invalid)?,
+        }),
+        _ => Err(NmeaError::Invalid),
+    }
+}
+
+fn rmc<'a, I: Iterator<Item = &'a [u8]>>(mut fields: I) -> Result<Sentence, NmeaError> {
+    let time = fields.next().ok_or(NmeaError::Invalid)?;
+    // status, A is active and V void
+    match fields.next() {
+        Some(b"A") => {}
+        Some(b"V") => return Err(NmeaError::NoFix),
+        _ => return Err(NmeaError::Invalid),
+    }
+    let location = location(&mut fields)?.ok_or(NmeaError::Invalid)?;
+    let _speed = fields.next();
+    let _course = fields.next();
+    let date = fields.next().ok_or(NmeaError::Invalid)?;
+
+    let (hour, minutes, seconds) = utc_time(time).ok_or(NmeaError::Invalid)?;
+    let (day, month, year) = utc_date(date).ok_or(NmeaError::Invalid)?;
+    let utc = UtcDateTime {
+        year,
+        month,
+        day,
+        hour,
+        minutes,
+        seconds,
+    };
+    Ok(Sentence::Rmc { location, utc })
+}
+
+/// Parses the latitude and longitude fields with their hemispheres, `None` if
+/// they are empty as sent without a fix.
+fn location<'a, I: Iterator<Item = &'a [u8]>>(
+    fields: &mut I,
+) -> Result<Option<Location>, NmeaError> {
+    let mut next = || fields.next().ok_or(NmeaError::Invalid);
+    let (latitude, north_south) = (next()?, next()?);
+    let (longitude, east_west) = (next()?, next()?);
+    if latitude.is_empty() && longitude.is_empty() {
+        return Ok(None);
+    }
+
+    let latitude = coordinate(latitude, 2, 90.0).ok_or(NmeaError::Invalid)?;
+    let longitude = coordinate(longitude, 3, 180.0).ok_or(NmeaError::Invalid)?;
+    let latitude = match north_south {
+        b"N" => latitude,
+        b"S" => -latitude,
+        _ => return Err(NmeaError::Invalid),
+    };
+    let longitude = match east_west {
+        b"E" => longitude,
+        b"W" => -longitude,
+        _ => return Err(NmeaError::Invalid),
+    };
+    Ok(Some(Location {
+        latitude: latitude as f32,
+        longitude: longitude as f32,
+    }))
+}
+
+/// Parses `dddmm.mmmm` with `degree_digits` digits of degrees into degrees.
+fn coordinate(field: &[u8], degree_digits: usize, max: f64) -> Option<f64> {
+    if field.len() < degree_digits + 2 {
+        return None;
+    }
+    let (degrees, minutes) = field.split_at(degree_digits);
+    let degrees = number(degrees)? as f64;
+    let minutes = decimal(minutes)?;
+    let value = degrees + minutes / 60.0;
+    if minutes >= 60.0 || value > max {
+        return None;
+    }
+    Some(value)
+}
+
+/// Parses `hhmmss` with optional decimal seconds, which are dropped.
+fn utc_time(field: &[u8]) -> Option<(u8, u8, u8)> {
+    if field.len() < 6 || decimal(&field[4..]).is_none() {
+        return None;
+    }
+    match (
+        number(&field[..2])?,
+        number(&field[2..4])?,
+        number(&field[4..6])?,
+    ) {
+        (hour @ 0..=23, minutes @ 0..=59, seconds @ 0..=60) => {
+            Some((hour as u8, minutes as u8, seconds.min(59) as u8))
+        }
+        _ => None,
+    }
+}
+
+/// Parses `ddmmyy` into the day, month and year, the years are 2000..=2099.
+fn utc_date(field: &[u8]) -> Option<(u8, u8, u16)> {
+    if field.len() != 6 {
+        return None;
+    }
+    match (
+        number(&field[..2])?,
+        number(&field[2..4])?,
+        number(&field[4..])?,
+    ) {
+        (day @ 1..=31, month @ 1..=12, year) => Some((day as u8, month as u8, 2000 + year as u16)),
+        _ => None,
+    }
+}
+
+/// Parses the digits of a field.
+fn number(digits: &[u8]) -> Option<u32> {
+    if digits.is_empty() || digits.len() > 9 || !is_digits(digits) {
+        return None;
+    }
+    Some(
+        digits
+            .iter()
+            .fold(0, |value, digit| value * 10 + (digit - b'0') as u32),
+    )
+}
+
+/// Parses digits with an optional decimal point.
+fn decimal(field: &[u8]) -> Option<f64> {
+    let (integer, fraction) = match field.iter().position(|&byte| byte == b'.') {
+        Some(point) => (&field[..point], &field[point + 1..]),
+        None => (field, &b""[..]),
+    };
+    let mut value = number(integer)? as f64;
+    if !is_digits(fraction) {
+        return None;
+    }
+    let mut scale = 0.1;
+    for digit in fraction {
+        value += (digit - b'0') as f64 * scale;
+        scale /= 10.0;
+    }
+    Some(value)
+}
+
+fn is_digits(field: &[u8]) -> bool {
+    field.iter().all(u8::is_ascii_digit)
+}
+
+fn trim_line_break(mut sentence: &[u8]) -> &[u8] {
+    while let [rest @ .., b'\r' | b'\n'] = sentence {
+        sentence = rest;
+    }
+    sentence
+}
+
+/// Verifies the checksum and gets the fields between the `$` and the `*`.
+fn checked_body(sentence: &[u8]) -> Result<&[u8], NmeaError> {
+    let sentence = match sentence {
+        [b'$', rest @ ..] => rest,
+        _ => return Err(NmeaError::Invalid),
+    };
+    let (body, checksum) = match *sentence {
+        [ref body @ .., b'*', high, low] => (body, [high, low]),
+        _ => return Err(NmeaError::Checksum),
+    };
+
+    let expected = hex(checksum[0])
+        .zip(hex(checksum[1]))
+        .map(|(high, low)| high << 4 | low)
+        .ok_or(NmeaError::Checksum)?;
+    let actual = body.iter().fold(0, |checksum, byte| checksum ^ byte);
+    if actual != expected {
+        return Err(NmeaError::Checksum);
+    }
+    Ok(body)
+}
+
+fn hex(digit: u8) -> Option<u8> {
+    match digit {
+        b'0'..=b'9' => Some(digit - b'0'),
+        b'A'..=b'F' => Some(digit - b'A' + 10),
+        b'a'..=b'f' => Some(digit - b'a' + 10),
+        _ => None,
+    }
+}
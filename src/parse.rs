@@ -0,0 +1,357 @@
+//! Parsers of the HC responses
+//!
+//! [`protocol`](crate::protocol) hands the response bytes to these
+//! functions, which keeps the decoding free of I/O so it can be fuzzed (see
+//! `fuzz/`).
+
+use crate::command::decode_counts;
+#[cfg(feature = "fuzzing")]
+use crate::command::decode_fraction;
+#[cfg(feature = "synscan")]
+use crate::command::decode_hex;
+#[cfg(any(feature = "fuzzing", feature = "simulator"))]
+use crate::types::Location;
+use crate::types::{DateTime, Dms, Hemisphere, Model, MotorModel, TrackingMode, Version};
+
+/// Longest response data, the precise position "XXXXXXXX,XXXXXXXX"
+pub const MAX_RESPONSE: usize = 17;
+
+/// Response which can't be split off the received bytes
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FrameError {
+    /// More bytes are needed.
+    Incomplete,
+    /// The HC answered with an error instead of the ack, which takes
+    /// `consumed` bytes.
+    Rejected { consumed: usize },
+}
+
+/// Splits a response of `len` data bytes followed by the ack off the received
+/// bytes and returns the data and the number of bytes consumed.
+///
+/// Never consumes more than `len + 2` bytes.
+pub fn frame(input: &[u8], len: usize) -> Result<(&[u8], usize), FrameError> {
+    match input.get(len) {
+        Some(b'#') => Ok((&input[..len], len + 1)),
+        // an error is followed by one additional byte
+        Some(_) if input.len() > len + 1 => Err(FrameError::Rejected { consumed: len + 2 }),
+        _ => Err(FrameError::Incomplete),
+    }
+}
+
+/// Parses a position response, "XXXX,XXXX", "XXXXX,XXXXX" of legacy HCs or
+/// "XXXXXXXX,XXXXXXXX".
+///
+/// The first axis is in `0..360`, the second one in `-180..=180` degrees.
+#[cfg(feature = "fuzzing")]
+pub fn position(data: &[u8]) -> Option<(f32, f32)> {
+    let (first, second) = split_position(data)?;
+    Some((
+        decode_fraction(first, false)?,
+        decode_fraction(second, true)?,
+    ))
+}
+
+/// Parses a position response like [`position`] into the counts of a
+/// revolution, 16 bits per revolution for the standard, 20 bits for the
+/// legacy and 24 bits for the precise format.
+pub fn position_counts(data: &[u8]) -> Option<(u32, u32)> {
+    let (first, second) = split_position(data)?;
+    let (first, _) = decode_counts(first)?;
+    let (second, _) = decode_counts(second)?;
+    Some((first, second))
+}
+
+/// Splits a position response into the hex digits of both axes.
+fn split_position(data: &[u8]) -> Option<(&[u8], &[u8])> {
+    let digits = match data.len() {
+        9 => 4,
+        11 => 5,
+        17 => 8,
+        _ => return None,
+    };
+    if data[digits] != b',' {
+        return None;
+    }
+
+    Some((&data[..digits], &data[digits + 1..]))
+}
+
+/// Parses a version response.
+pub fn version(data: &[u8]) -> Option<Version> {
+    match *data {
+        [major, minor] => Some(Version::new(major, minor)),
+        _ => None,
+    }
+}
+
+/// Parses a model response.
+pub fn model(data: &[u8]) -> Option<Model> {
+    match *data {
+        [model] => Some(Model::from_bits(model)),
+        _ => None,
+    }
+}
+
+/// Parses a motor controller model response.
+pub fn motor_model(data: &[u8]) -> Option<MotorModel> {
+    match *data {
+        [model] => Some(MotorModel::from_bits(model)),
+        _ => None,
+    }
+}
+
+/// Parses a tracking mode response.
+pub fn tracking_mode(data: &[u8]) -> Option<TrackingMode> {
+    match *data {
+        [mode] => TrackingMode::from_bits(mode),
+        _ => None,
+    }
+}
+
+/// Parses the version of a StarSense HC, the major and minor version and a
+/// build number which is dropped.
+///
+/// The format is reconstructed from reports, not checked against a capture.
+pub fn starsense_version(data: &[u8]) -> Option<Version> {
+    match *data {
+        [major, minor, _build] => Some(Version::new(major, minor)),
+        _ => None,
+    }
+}
+
+/// Parses a StarSense model response, the id of the mount with the high bit
+/// set.
+///
+/// The format is reconstructed from reports, not checked against a capture.
+pub fn starsense_model(data: &[u8]) -> Option<Model> {
+    match *data {
+        [model] => Some(Model::from_bits(model & 0x7F)),
+        _ => None,
+    }
+}
+
+/// Parses the version of a SynScan HC, 6 hex digits of the major, minor and
+/// sub version. The sub version is dropped.
+#[cfg(feature = "synscan")]
+pub fn synscan_version(data: &[u8]) -> Option<Version> {
+    if data.len() != 6 {
+        return None;
+    }
+    decode_hex(&data[4..])?;
+    Some(Version::new(
+        decode_hex(&data[..2])? as u8,
+        decode_hex(&data[2..4])? as u8,
+    ))
+}
+
+/// Parses a SynScan model response.
+#[cfg(feature = "synscan")]
+pub fn synscan_model(data: &[u8]) -> Option<Model> {
+    match *data {
+        [model] => Some(Model::from_synscan_bits(model)),
+        _ => None,
+    }
+}
+
+/// Parses a single byte state, `'0'` and `'1'` or binary 0 and 1 as the
+/// firmware versions differ in which one they send.
+pub fn state(data: &[u8]) -> Option<bool> {
+    match *data {
+        [b'0'] | [0] => Some(false),
+        [b'1'] | [1] => Some(true),
+        _ => None,
+    }
+}
+
+/// Parses a flag of a sub device like the GPS link state, any non-zero value
+/// is set.
+pub fn flag(data: &[u8]) -> Option<bool> {
+    match *data {
+        [flag] => Some(flag != 0),
+        _ => None,
+    }
+}
+
+/// Parses a GPS latitude or longitude, a 24 bit fraction of a revolution.
+pub fn gps_angle(data: &[u8]) -> Option<u32> {
+    match *data {
+        [high, mid, low] => Some(u32::from_be_bytes([0, high, mid, low])),
+        _ => None,
+    }
+}
+
+/// Parses the GPS date into the month and day.
+pub fn gps_date(data: &[u8]) -> Option<(u8, u8)> {
+    match *data {
+        [month @ 1..=12, day @ 1..=31] => Some((month, day)),
+        _ => None,
+    }
+}
+
+/// Parses the GPS date like [`gps_date`], `None` for the zeros of a GPS unit
+/// without a fix.
+pub fn gps_date_unset(data: &[u8]) -> Option<Option<(u8, u8)>> {
+    match *data {
+        [0, 0] => Some(None),
+        _ => gps_date(data).map(Some),
+    }
+}
+
+/// Parses the GPS year.
+pub fn gps_year(data: &[u8]) -> Option<u16> {
+    match *data {
+        [high, low] => Some(u16::from_be_bytes([high, low])),
+        _ => None,
+    }
+}
+
+/// Parses the GPS time into the hour, minutes and seconds.
+pub fn gps_time(data: &[u8]) -> Option<(u8, u8, u8)> {
+    match *data {
+        [hour @ 0..=23, minutes @ 0..=59, seconds @ 0..=59] => Some((hour, minutes, seconds)),
+        _ => None,
+    }
+}
+
+/// Parses a location response.
+#[cfg(any(feature = "fuzzing", feature = "simulator"))]
+pub fn location(data: &[u8]) -> Option<Location> {
+    let (latitude, longitude) = location_dms(data)?;
+    Location::from_dms(latitude, longitude)
+}
+
+/// Parses a location response into the latitude and longitude as sent.
+pub fn location_dms(data: &[u8]) -> Option<(Dms, Dms)> {
+    let (latitude, longitude) = match *data {
+        [lat_d, lat_m, lat_s, lat_sign, lon_d, lon_m, lon_s, lon_sign] => (
+            [lat_d, lat_m, lat_s, lat_sign],
+            [lon_d, lon_m, lon_s, lon_sign],
+        ),
+        _ => return None,
+    };
+    let latitude = Dms::from_bytes(latitude, Hemisphere::North, Hemisphere::South)?;
+    let longitude = Dms::from_bytes(longitude, Hemisphere::East, Hemisphere::West)?;
+    if !latitude.is_latitude() || !longitude.is_longitude() {
+        return None;
+    }
+    Some((latitude, longitude))
+}
+
+/// Parses a date and time response.
+pub fn datetime(data: &[u8]) -> Option<DateTime> {
+    match *data {
+        [hour, minutes, seconds, month, day, year, zone, dst] => {
+            DateTime::from_bytes(&[hour, minutes, seconds, month, day, year, zone, dst])
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_ack() {
+        assert_eq!(frame(b"ab#", 2), Ok((&b"ab"[..], 3)));
+        assert_eq!(frame(b"#", 0), Ok((&b""[..], 1)));
+        // the bytes of the next response stay
+        assert_eq!(frame(b"ab#cd", 2), Ok((&b"ab"[..], 3)));
+    }
+
+    #[test]
+    fn frame_rejected() {
+        assert_eq!(
+            frame(b"ab\x00#", 2),
+            Err(FrameError::Rejected { consumed: 4 })
+        );
+        assert_eq!(frame(b"ab\x00", 2), Err(FrameError::Incomplete));
+    }
+
+    #[test]
+    fn frame_incomplete() {
+        assert_eq!(frame(b"", 0), Err(FrameError::Incomplete));
+        assert_eq!(frame(b"a#", 2), Err(FrameError::Incomplete));
+    }
+
+    #[cfg(feature = "fuzzing")]
+    #[test]
+    fn position_format() {
+        assert_eq!(position(b"4000,C000"), Some((90.0, -90.0)));
+        assert_eq!(position(b"40000000,c0000000"), Some((90.0, -90.0)));
+        assert_eq!(position(b"4000;C000"), None);
+        assert_eq!(position(b"4000,C00"), None);
+        assert_eq!(position(b"4000,C0G0"), None);
+    }
+
+    #[test]
+    fn position_counts_format() {
+        assert_eq!(position_counts(b"4000,C000"), Some((0x4000, 0xC000)));
+        assert_eq!(
+            position_counts(b"400000FF,c0000100"),
+            Some((0x40_0000, 0xC0_0001))
+        );
+        assert_eq!(position_counts(b"40000,C0001"), Some((0x4_0000, 0xC_0001)));
+        assert_eq!(position_counts(b"4000,C0000000"), None);
+        assert_eq!(position_counts(b"4000,C00000"), None);
+    }
+
+    #[test]
+    fn starsense_formats() {
+        assert_eq!(starsense_version(&[1, 2, 7]), Some(Version::new(1, 2)));
+        assert_eq!(starsense_version(&[1, 2]), None);
+        assert_eq!(starsense_model(&[0x89]), Some(Model::CPC));
+        assert_eq!(starsense_model(&[0x0C]), Some(Model::Se6_8));
+    }
+
+    #[cfg(feature = "synscan")]
+    #[test]
+    fn synscan_formats() {
+        assert_eq!(synscan_version(b"042705"), Some(Version::new(4, 39)));
+        assert_eq!(synscan_version(b"03a0ff"), Some(Version::new(3, 160)));
+        assert_eq!(synscan_version(b"0427"), None);
+        assert_eq!(synscan_version(b"04270G"), None);
+        assert_eq!(synscan_model(&[0]), Some(Model::Eq6));
+        assert_eq!(synscan_model(&[0x8F]), Some(Model::AzGoto));
+        assert_eq!(synscan_model(&[0x42]), Some(Model::Unknown(0x42)));
+    }
+
+    #[test]
+    fn state_values() {
+        assert_eq!(state(b"0"), Some(false));
+        assert_eq!(state(b"1"), Some(true));
+        assert_eq!(state(&[0]), Some(false));
+        assert_eq!(state(&[1]), Some(true));
+        assert_eq!(state(b"2"), None);
+        assert_eq!(state(&[2]), None);
+        assert_eq!(state(b"01"), None);
+    }
+
+    #[test]
+    fn gps_formats() {
+        assert_eq!(flag(&[2]), Some(true));
+        assert_eq!(flag(&[0xff]), Some(true));
+        assert_eq!(flag(&[0]), Some(false));
+        assert_eq!(gps_angle(&[0xC0, 0x00, 0x01]), Some(0xC0_0001));
+        assert_eq!(gps_date(&[12, 31]), Some((12, 31)));
+        assert_eq!(gps_date(&[13, 1]), None);
+        assert_eq!(gps_date_unset(&[0, 0]), Some(None));
+        assert_eq!(gps_date_unset(&[12, 31]), Some(Some((12, 31))));
+        assert_eq!(gps_date_unset(&[0, 1]), None);
+        assert_eq!(gps_year(&[0x07, 0xEA]), Some(2026));
+        assert_eq!(gps_time(&[23, 59, 59]), Some((23, 59, 59)));
+        assert_eq!(gps_time(&[24, 0, 0]), None);
+    }
+
+    #[test]
+    fn fixed_lengths() {
+        assert!(version(&[4]).is_none());
+        assert!(model(&[]).is_none());
+        assert!(motor_model(&[1, 0]).is_none());
+        assert!(location_dms(&[0; 9]).is_none());
+        #[cfg(any(feature = "fuzzing", feature = "simulator"))]
+        assert!(location(&[0; 7]).is_none());
+        assert!(datetime(&[0; 9]).is_none());
+    }
+}
@@ -0,0 +1,198 @@
+//! Periodic error (PE) of the RA drive
+//!
+//! [`NexStar::record_pe`](crate::NexStar::record_pe) samples the position of
+//! the AZM/RA motor while the mount tracks and stores how far the motor ran
+//! ahead of (positive) or behind (negative) the tracking rate into the buffer
+//! of a [`PeRecorder`]:
+//!
+//! ```ignore
+//! // two periods of a 480 s worm, sampled every second
+//! let mut residuals = [0.0; 961];
+//! let mut recorder = PeRecorder::new(&mut residuals, 480_000, 1000, 2);
+//! let stats = nexstar.record_pe(&mut recorder, &mut delay)?;
+//! ```
+//!
+//! The residuals are in arcseconds of the RA axis, the first one is 0. The
+//! motor position doesn't depend on the alignment, so the residuals don't
+//! include the drift of a polar alignment error, but a tracking rate which
+//! doesn't match the one of the recorder adds a linear slope.
+
+use libm::sqrt;
+
+use crate::TrackingRate;
+
+/// Recording of the residuals over whole worm periods
+#[derive(Debug)]
+pub struct PeRecorder<'a> {
+    residuals: &'a mut [f32],
+    worm_period_ms: u32,
+    interval_ms: u32,
+    periods: u32,
+    rate: f32,
+    len: usize,
+}
+
+impl<'a> PeRecorder<'a> {
+    /// Creates a recorder sampling every `interval_ms` over `periods` worm
+    /// periods of `worm_period_ms`, which takes
+    /// [`PeRecorder::samples`] of the `residuals`. The mount is expected to
+    /// track at the sidereal rate.
+    pub fn new(
+        residuals: &'a mut [f32],
+        worm_period_ms: u32,
+        interval_ms: u32,
+        periods: u32,
+    ) -> PeRecorder<'a> {
+        PeRecorder {
+            residuals,
+            worm_period_ms,
+            interval_ms,
+            periods,
+            rate: TrackingRate::SIDEREAL,
+            len: 0,
+        }
+    }
+
+    /// Sets the rate the mount tracks at, e.g. a negative one on the southern
+    /// hemisphere.
+    pub fn with_rate(mut self, rate: TrackingRate) -> Self {
+        self.rate = rate.arcsec_per_sec();
+        self
+    }
+
+    /// Gets the number of samples of a recording, the first one at the start
+    /// and the last one at the end of the last period.
+    pub fn samples(&self) -> usize {
+        let duration = self.worm_period_ms as u64 * self.periods as u64;
+        match duration.checked_div(self.interval_ms as u64) {
+            Some(intervals) => (intervals as usize).saturating_add(1),
+            None => 0,
+        }
+    }
+
+    /// Gets the time between two samples.
+    pub fn interval_ms(&self) -> u32 {
+        self.interval_ms
+    }
+
+    /// Gets the residuals recorded so far in arcseconds.
+    pub fn residuals(&self) -> &[f32] {
+        &self.residuals[..self.len]
+    }
+
+    /// Gets the statistics of the residuals recorded so far.
+    pub fn stats(&self) -> PeStats {
+        stats(self.residuals())
+    }
+
+    /// Gets the size of the buffer.
+    pub(crate) fn capacity(&self) -> usize {
+        self.residuals.len()
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Records the next sample from the arcseconds the motor moved since the
+    /// first one.
+    pub(crate) fn record(&mut self, moved_arcsec: f64) {
+        let seconds = self.len as f64 * self.interval_ms as f64 / 1000.0;
+        if let Some(slot) = self.residuals.get_mut(self.len) {
+            *slot = (moved_arcsec - self.rate as f64 * seconds) as f32;
+            self.len += 1;
+        }
+    }
+}
+
+/// Statistics of the residuals in arcseconds
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct PeStats {
+    pub peak_to_peak: f32,
+    /// Root mean square of the residuals around their mean
+    pub rms: f32,
+}
+
+/// Gets the statistics of residuals in arcseconds, zero without any.
+pub fn stats(residuals: &[f32]) -> PeStats {
+    if residuals.is_empty() {
+        return PeStats::default();
+    }
+
+    let (mut min, mut max, mut sum) = (f32::MAX, f32::MIN, 0.0);
+    for residual in residuals {
+        min = min.min(*residual);
+        max = max.max(*residual);
+        sum += *residual as f64;
+    }
+    let mean = sum / residuals.len() as f64;
+    let squares: f64 = residuals
+        .iter()
+        .map(|residual| (*residual as f64 - mean) * (*residual as f64 - mean))
+        .sum();
+
+    PeStats {
+        peak_to_peak: max - min,
+        rms: sqrt(squares / residuals.len() as f64) as f32,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::f64::consts::PI;
+
+    /// Fills the residuals with whole periods of a sine of the amplitude.
+    fn sine(residuals: &mut [f32], amplitude: f64, offset: f64) {
+        let len = residuals.len();
+        for (i, residual) in residuals.iter_mut().enumerate() {
+            *residual = (offset + amplitude * libm::sin(2.0 * PI * i as f64 / len as f64)) as f32;
+        }
+    }
+
+    #[test]
+    fn sine_stats() {
+        let mut residuals = [0.0; 480];
+        sine(&mut residuals, 5.0, 0.0);
+        let stats = stats(&residuals);
+        assert!((stats.peak_to_peak - 10.0).abs() < 1e-3, "{:?}", stats);
+        assert!((stats.rms - 5.0 / 2f32.sqrt()).abs() < 1e-3, "{:?}", stats);
+
+        // the RMS is taken around the mean
+        sine(&mut residuals, 5.0, 3.0);
+        let offset = super::stats(&residuals);
+        assert!((offset.peak_to_peak - stats.peak_to_peak).abs() < 1e-3);
+        assert!((offset.rms - stats.rms).abs() < 1e-3);
+
+        assert_eq!(super::stats(&[]), PeStats::default());
+        assert_eq!(super::stats(&[1.5]), PeStats::default());
+    }
+
+    #[test]
+    fn residuals_subtract_the_rate() {
+        let mut residuals = [0.0; 4];
+        let mut recorder = PeRecorder::new(&mut residuals, 2000, 1000, 1);
+        assert_eq!(recorder.samples(), 3);
+
+        // tracking 1 arcsec ahead after a second, then on time
+        recorder.record(0.0);
+        recorder.record(TrackingRate::SIDEREAL as f64 + 1.0);
+        recorder.record(TrackingRate::SIDEREAL as f64 * 2.0);
+        assert_eq!(recorder.residuals().len(), 3);
+        assert!((recorder.residuals()[1] - 1.0).abs() < 1e-4);
+        assert!(recorder.residuals()[2].abs() < 1e-4);
+        assert!((recorder.stats().peak_to_peak - 1.0).abs() < 1e-4);
+
+        let mut residuals = [0.0; 2];
+        let mut recorder =
+            PeRecorder::new(&mut residuals, 1000, 1000, 1).with_rate(TrackingRate::Custom(-10.0));
+        recorder.record(0.0);
+        recorder.record(-10.0);
+        assert_eq!(recorder.residuals(), [0.0, 0.0]);
+        // a full buffer keeps its samples
+        recorder.record(5.0);
+        assert_eq!(recorder.residuals().len(), 2);
+
+        assert_eq!(PeRecorder::new(&mut [], 1000, 0, 1).samples(), 0);
+    }
+}
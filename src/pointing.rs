@@ -0,0 +1,344 @@
+//! Pointing correction from sync offsets
+//!
+//! A [`PointingModel`] collects pairs of commanded and actual (e.g. plate
+//! solved) positions and corrects GOTO targets by the measured error:
+//!
+//! ```ignore
+//! let mut model = PointingModel::<8>::new(Fit::Offset);
+//! model.add(commanded, solved);
+//! nexstar.goto_ra_dec(model.apply(target))?;
+//! ```
+
+use crate::RaDec;
+
+/// Error model fitted to the sync pairs
+#[derive(Copy, Clone, Debug)]
+pub enum Fit {
+    /// Constant error per axis
+    Offset,
+    /// Error per axis changing linearly with the coordinate of the axis
+    Linear,
+}
+
+/// Pointing model holding up to `N` pairs of commanded and actual positions
+///
+/// Once the model is full the oldest pair is replaced.
+#[derive(Copy, Clone, Debug)]
+pub struct PointingModel<const N: usize> {
+    pairs: [(RaDec, RaDec); N],
+    len: usize,
+    next: usize,
+    fit: Fit,
+}
+
+/// Error of one axis: `offset + slope * (coordinate - center)` in degrees
+#[derive(Copy, Clone, Debug, Default)]
+struct AxisError {
+    center: f32,
+    offset: f32,
+    slope: f32,
+}
+
+impl AxisError {
+    fn at(&self, coordinate: f32) -> f32 {
+        self.offset + self.slope * signed_diff(coordinate, self.center)
+    }
+}
+
+impl<const N: usize> PointingModel<N> {
+    pub fn new(fit: Fit) -> Self {
+        let origin = RaDec { ra: 0.0, dec: 0.0 };
+        PointingModel {
+            pairs: [(origin, origin); N],
+            len: 0,
+            next: 0,
+            fit,
+        }
+    }
+
+    /// Adds the position the mount was commanded to and the position it
+    /// actually pointed at.
+    pub fn add(&mut self, commanded: RaDec, actual: RaDec) {
+        if N == 0 {
+            return;
+        }
+
+        self.pairs[self.next] = (commanded, actual);
+        self.next = (self.next + 1) % N;
+        self.len = (self.len + 1).min(N);
+    }
+
+    /// Gets the number of pairs in the model.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Removes all pairs.
+    pub fn clear(&mut self) {
+        self.len = 0;
+        self.next = 0;
+    }
+
+    /// Gets the pointing error (actual - commanded) at the position in degrees.
+    pub fn error(&self, position: RaDec) -> RaDec {
+        let (ra, dec) = self.fit();
+        RaDec {
+            ra: ra.at(position.ra),
+            dec: dec.at(position.dec),
+        }
+    }
+
+    /// Gets the position to command so that the mount points at `target`.
+    pub fn apply(&self, target: RaDec) -> RaDec {
+        let error = self.error(target);
+        let ra = (target.ra - error.ra) % 360.0;
+        RaDec {
+            ra: if ra < 0.0 { ra + 360.0 } else { ra },
+            dec: (target.dec - error.dec).clamp(-90.0, 90.0),
+        }
+    }
+
+    fn fit(&self) -> (AxisError, AxisError) {
+        let pairs = &self.pairs[..self.len];
+        if pairs.is_empty() {
+            return (AxisError::default(), AxisError::default());
+        }
+
+        let ra = fit_axis(
+            pairs
+                .iter()
+                .map(|(commanded, actual)| (commanded.ra, actual.ra)),
+            self.fit,
+        );
+        let dec = fit_axis(
+            pairs
+                .iter()
+                .map(|(commanded, actual)| (commanded.dec, actual.dec)),
+            self.fit,
+        );
+        (ra, dec)
+    }
+}
+
+/// Least squares fit of the error of one axis. The coordinates are taken
+/// relative to the first one so the fit works across the RA wrap.
+fn fit_axis<I>(pairs: I, fit: Fit) -> AxisError
+where
+    I: Iterator<Item = (f32, f32)> + Clone,
+{
+    let reference = match pairs.clone().next() {
+        Some((commanded, _)) => commanded,
+        None => return AxisError::default(),
+    };
+
+    let (mut n, mut sum_x, mut sum_e) = (0.0f32, 0.0f32, 0.0f32);
+    for (commanded, actual) in pairs.clone() {
+        n += 1.0;
+        sum_x += signed_diff(commanded, reference);
+        sum_e += signed_diff(actual, commanded);
+    }
+    let mean_x = sum_x / n;
+    let mean_e = sum_e / n;
+
+    let slope = match fit {
+        Fit::Offset => 0.0,
+        Fit::Linear => {
+            let (mut sxx, mut sxe) = (0.0f32, 0.0f32);
+            for (commanded, actual) in pairs {
+                let x = signed_diff(commanded, reference) - mean_x;
+                sxx += x * x;
+                sxe += x * (signed_diff(actual, commanded) - mean_e);
+            }
+            if sxx > 0.0 {
+                sxe / sxx
+            } else {
+                0.0
+            }
+        }
+    };
+
+    AxisError {
+        center: reference + mean_x,
+        offset: mean_e,
+        slope,
+    }
+}
+
+/// Gets `a - b` in degrees wrapped to -180..180.
+fn signed_diff(a: f32, b: f32) -> f32 {
+    let diff = (a - b) % 360.0;
+    if diff > 180.0 {
+        diff - 360.0
+    } else if diff < -180.0 {
+        diff + 360.0
+    } else {
+        diff
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn radec(ra: f32, dec: f32) -> RaDec {
+        RaDec { ra, dec }
+    }
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-3, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn empty_model() {
+        let model = PointingModel::<4>::new(Fit::Linear);
+        let target = model.apply(radec(10.0, 20.0));
+        assert_eq!((target.ra, target.dec), (10.0, 20.0));
+    }
+
+    #[test]
+    fn offset_fit() {
+        let mut model = PointingModel::<4>::new(Fit::Offset);
+        model.add(radec(10.0, 20.0), radec(10.5, 19.0));
+        model.add(radec(50.0, 40.0), radec(50.5, 39.0));
+
+        let target = model.apply(radec(30.0, 30.0));
+        assert_close(target.ra, 29.5);
+        assert_close(target.dec, 31.0);
+    }
+
+    #[test]
+    fn linear_fit() {
+        let mut model = PointingModel::<4>::new(Fit::Linear);
+        model.add(radec(10.0, 0.0), radec(11.0, 0.0));
+        model.add(radec(30.0, 0.0), radec(33.0, 0.0));
+
+        // error grows by 0.1 degrees per degree of RA
+        assert_close(model.error(radec(20.0, 0.0)).ra, 2.0);
+        assert_close(model.error(radec(40.0, 0.0)).ra, 4.0);
+    }
+
+    #[test]
+    fn fit_across_wrap() {
+        let mut model = PointingModel::<4>::new(Fit::Offset);
+        model.add(radec(359.5, 0.0), radec(0.5, 0.0));
+        model.add(radec(0.5, 0.0), radec(1.5, 0.0));
+
+        assert_close(model.error(radec(0.0, 0.0)).ra, 1.0);
+        assert_close(model.apply(radec(0.5, 0.0)).ra, 359.5);
+    }
+
+    #[test]
+    fn ring_buffer() {
+        let mut model = PointingModel::<2>::new(Fit::Offset);
+        model.add(radec(0.0, 0.0), radec(0.0, 10.0));
+        model.add(radec(0.0, 0.0), radec(0.0, 1.0));
+        model.add(radec(0.0, 0.0), radec(0.0, 1.0));
+
+        assert_eq!(model.len(), 2);
+        assert_close(model.error(radec(0.0, 0.0)).dec, 1.0);
+
+        model.clear();
+        assert!(model.is_empty());
+    }
+
+    /// Wraps an RA into 0..360.
+    fn wrap(ra: f32) -> f32 {
+        let ra = ra % 360.0;
+        if ra < 0.0 {
+            ra + 360.0
+        } else {
+            ra
+        }
+    }
+
+    #[test]
+    fn offset_grid() {
+        // every combination of synthetic offsets with samples all over the
+        // sky, including both sides of the RA wrap
+        let offsets = [-2.0, -0.5, -0.01, 0.0, 0.01, 0.5, 2.0];
+        for &ra_offset in &offsets {
+            for &dec_offset in &offsets {
+                let mut model = PointingModel::<16>::new(Fit::Offset);
+                for ra in (0..360).step_by(30) {
+                    let dec = (ra as f32 / 6.0) - 30.0;
+                    let commanded = radec(ra as f32, dec);
+                    let actual = radec(wrap(ra as f32 + ra_offset), dec + dec_offset);
+                    model.add(commanded, actual);
+                }
+                assert_eq!(model.len(), 12);
+
+                for ra in (0..360).step_by(15) {
+                    let target = radec(ra as f32 + 0.25, 45.0);
+                    let error = model.error(target);
+                    assert_close(error.ra, ra_offset);
+                    assert_close(error.dec, dec_offset);
+
+                    // the corrected GOTO lands on the target
+                    let commanded = model.apply(target);
+                    assert_close(signed_diff(commanded.ra + ra_offset, target.ra), 0.0);
+                    assert_close(commanded.dec + dec_offset, target.dec);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn linear_grid() {
+        let slopes = [-0.02, -0.001, 0.0, 0.001, 0.02];
+        let offsets = [-1.0, 0.0, 0.3];
+        for &slope in &slopes {
+            for &offset in &offsets {
+                for center in [5.0, 180.0, 355.0] {
+                    let mut model = PointingModel::<8>::new(Fit::Linear);
+                    for x in [-40.0, -25.0, -5.0, 10.0, 30.0, 45.0] {
+                        let ra = wrap(center + x);
+                        let dec = x / 2.0;
+                        let error = offset + slope * x;
+                        model.add(radec(ra, dec), radec(wrap(ra + error), dec + error));
+                    }
+
+                    for x in [-30.0, 0.0, 20.0] {
+                        let expected = offset + slope * x;
+                        let error = model.error(radec(wrap(center + x), x / 2.0));
+                        // twice the slope over half the spread in Dec
+                        assert_close(error.ra, expected);
+                        assert_close(error.dec, expected);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn offset_fit_of_full_model() {
+        // only the newest pairs count once the model is full
+        for extra in 0..8 {
+            let mut model = PointingModel::<4>::new(Fit::Offset);
+            for i in 0..extra {
+                model.add(radec(i as f32, 0.0), radec(i as f32 + 5.0, 0.0));
+            }
+            for i in 0..4 {
+                model.add(
+                    radec(i as f32 * 10.0, 0.0),
+                    radec(i as f32 * 10.0 - 1.0, 0.5),
+                );
+            }
+            let error = model.error(radec(100.0, 0.0));
+            assert_close(error.ra, -1.0);
+            assert_close(error.dec, 0.5);
+        }
+    }
+
+    #[test]
+    fn apply_clamps_at_poles() {
+        let mut model = PointingModel::<1>::new(Fit::Offset);
+        model.add(radec(0.0, 80.0), radec(0.0, 79.0));
+
+        assert_eq!(model.apply(radec(0.0, 89.5)).dec, 90.0);
+        assert_close(model.apply(radec(0.0, -90.0)).dec, -89.0);
+    }
+}
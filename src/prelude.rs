@@ -0,0 +1,34 @@
+//! Everything needed to use the driver, including generic code over it.
+//!
+//! ```
+//! use nexstar::prelude::*;
+//!
+//! fn print_model<T, U>(nexstar: &mut NexStar<T, U>)
+//! where
+//!     T: SerialRead<u8>,
+//!     U: SerialWrite<u8>,
+//! {
+//!     match nexstar.model() {
+//!         Ok(Model::Unknown(id)) => println!("Unknown model {}", id),
+//!         Ok(model) => println!("Model: {:?}", model),
+//!         Err(Error::UnexpectedResponse(context)) => println!("No answer to {}", context),
+//!         Err(_) => println!("Communication error"),
+//!     }
+//! }
+//! ```
+
+#[cfg(feature = "float")]
+pub use crate::{
+    Approach, AzmAlt, BacklashMeasurement, CoordinateParseError, Drift, GuideRate, Location, Mount,
+    MountConfig, RaDec, RestoreReport, Target, TrackingRate,
+};
+pub use crate::{
+    AzmAltArcsec, Backlash, Borrowed, Capabilities, Compat, Context, DateTime, Device, Dms, Error,
+    Family, FocuserLimits, GpsFix, GpsState, Hemisphere, HomeStatus, LinkHealth, LinkProbe,
+    LinkState, Model, MotorModel, MountInfo, MountStatus, NexStar, Positions, RaDecArcsec,
+    ReleaseReport, ResponseLength, SlewGuard, Terminator, TrackingMode, UtcDateTime, Version,
+    Versions,
+};
+pub use embedded_hal::blocking::delay::DelayMs;
+pub use embedded_hal::serial::Read as SerialRead;
+pub use embedded_hal::serial::Write as SerialWrite;
@@ -0,0 +1,827 @@
+//! Sans-IO core of the Hand Controller (HC) protocol
+//!
+//! Pure functions encoding the commands and parsing the responses without any
+//! transport, e.g. to drive a DMA UART with whole buffers or to decode captured
+//! traffic. [`NexStar`](crate::NexStar) is built on top of them.
+//!
+//! ```
+//! use nexstar::protocol::{
+//!     encode_command, parse_response, Parser, Progress, Request, Response, MAX_COMMAND,
+//!     MAX_RESPONSE,
+//! };
+//! use nexstar::Version;
+//!
+//! let mut buf = [0u8; MAX_COMMAND];
+//! let len = encode_command(&Request::GetVersion, &mut buf).unwrap();
+//! assert_eq!(&buf[..len], b"V");
+//!
+//! let mut response = [0u8; MAX_RESPONSE + 2];
+//! let mut parser = Parser::new(&Request::GetVersion);
+//! assert_eq!(parser.push(&mut response, 4), Progress::NeedMore);
+//! assert_eq!(parser.push(&mut response, 21), Progress::NeedMore);
+//! assert_eq!(parser.push(&mut response, b'#'), Progress::Done);
+//! assert_eq!(
+//!     parse_response(&Request::GetVersion, parser.response(&response)),
+//!     Ok(Response::Version(Version::new(4, 21)))
+//! );
+//! ```
+//!
+//! Parsing allocates nothing and copies nothing but the response of
+//! pass-through commands into [`Data`], [`response_data`] borrows it instead.
+//! The [`Parser`] keeps only a few bytes of state, the received bytes stay in
+//! the caller's buffer.
+//!
+//! The frames of the commands without arguments are constants, e.g.
+//! [`GET_VERSION_FRAME`] and [`device_version_frame`], which
+//! [`encode_command`] copies from flash instead of assembling them.
+
+use crate::command::{encode_counts, Command};
+use crate::frame::Frame;
+pub use crate::parse::MAX_RESPONSE;
+use crate::parse::{self, FrameError};
+use crate::types::{DateTime, Device, Dms, Model, MotorModel, TrackingMode, Version};
+
+/// Longest command, the precise GOTO "rXXXXXXXX,XXXXXXXX"
+pub const MAX_COMMAND: usize = 18;
+
+/// Frame of [`Request::CancelGoto`]
+pub const CANCEL_GOTO_FRAME: [u8; 1] = fixed(Frame::new(b'M'));
+/// Frame of [`Request::GetTrackingMode`]
+pub const GET_TRACKING_MODE_FRAME: [u8; 1] = fixed(Frame::new(b't'));
+/// Frame of [`Request::GetLocation`]
+pub const GET_LOCATION_FRAME: [u8; 1] = fixed(Frame::new(b'w'));
+/// Frame of [`Request::GetDateTime`]
+pub const GET_DATETIME_FRAME: [u8; 1] = fixed(Frame::new(b'h'));
+/// Frame of [`Request::GetVersion`]
+pub const GET_VERSION_FRAME: [u8; 1] = fixed(Frame::new(b'V'));
+/// Frame of [`Request::GetModel`]
+pub const GET_MODEL_FRAME: [u8; 1] = fixed(Frame::new(b'm'));
+/// Frame of [`Request::IsAlignmentComplete`]
+pub const IS_ALIGNMENT_COMPLETE_FRAME: [u8; 1] = fixed(Frame::new(b'J'));
+/// Frame of [`Request::IsGotoInProgress`]
+pub const IS_GOTO_IN_PROGRESS_FRAME: [u8; 1] = fixed(Frame::new(b'L'));
+
+/// Gets the frame of the version query of the sub device, e.g. to bake it
+/// into flash with a `const`.
+pub const fn device_version_frame(device: Device) -> [u8; 8] {
+    fixed(Frame::pass_through(
+        device,
+        Command::GetDeviceVersion.bits(),
+        &[],
+        2,
+    ))
+}
+
+/// Device version frames, in the order of [`Device`]
+static DEVICE_VERSION_FRAMES: [[u8; 8]; 9] = [
+    device_version_frame(Device::MainBoard),
+    device_version_frame(Device::HandController),
+    device_version_frame(Device::AzmRaMotor),
+    device_version_frame(Device::AltDecMotor),
+    device_version_frame(Device::Focuser),
+    device_version_frame(Device::GPSUnit),
+    device_version_frame(Device::RTC),
+    device_version_frame(Device::WiFi),
+    device_version_frame(Device::Battery),
+];
+
+/// Copies the frame into an array of its length, at compile time.
+const fn fixed<const N: usize>(frame: Frame) -> [u8; N] {
+    let bytes = match frame.bytes() {
+        Some(bytes) => bytes,
+        None => panic!("frame overflow"),
+    };
+    assert!(bytes.len() == N, "frame length");
+
+    let mut fixed = [0u8; N];
+    let mut i = 0;
+    while i < N {
+        fixed[i] = bytes[i];
+        i += 1;
+    }
+    fixed
+}
+
+/// Coordinate frame of the position commands
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Axes {
+    RaDec,
+    AzmAlt,
+}
+
+/// Position of both axes in counts of a revolution
+///
+/// The standard commands use 16 bits per revolution, the precise ones 24 bits
+/// and the wide positions of legacy HCs 20 bits.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Counts {
+    /// RA or Azm
+    pub first: u32,
+    /// Dec or Alt
+    pub second: u32,
+}
+
+/// HC command
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Request {
+    /// Gets the position.
+    GetPosition {
+        axes: Axes,
+        precise: bool,
+    },
+    /// Gets the position in the 5 digit format of legacy HCs, see
+    /// [`Compat`](crate::Compat).
+    GetWidePosition {
+        axes: Axes,
+    },
+    /// Starts a GOTO to the position.
+    Goto {
+        axes: Axes,
+        position: Counts,
+        precise: bool,
+    },
+    /// Syncs the mount to the RA/Dec position.
+    Sync {
+        position: Counts,
+        precise: bool,
+    },
+    /// Cancels the GOTO in progress.
+    CancelGoto,
+    GetTrackingMode,
+    SetTrackingMode(TrackingMode),
+    GetLocation,
+    /// Sets the location, the fields are sent as they are.
+    SetLocation {
+        latitude: Dms,
+        longitude: Dms,
+    },
+    GetDateTime,
+    SetDateTime(DateTime),
+    /// Gets the HC firmware version.
+    GetVersion,
+    GetModel,
+    /// Gets the firmware version of a StarSense HC.
+    GetStarSenseVersion,
+    /// Gets the model from a StarSense HC.
+    GetStarSenseModel,
+    /// Gets the firmware version of a SynScan HC.
+    #[cfg(feature = "synscan")]
+    GetSynScanVersion,
+    /// Gets the model from a SynScan HC, which numbers them differently.
+    #[cfg(feature = "synscan")]
+    GetSynScanModel,
+    IsAlignmentComplete,
+    IsGotoInProgress,
+    /// Echoes the byte.
+    Echo(u8),
+    /// Command to a sub device, see [`Request::pass_through`]
+    PassThrough {
+        device: Device,
+        command: Command,
+        data: [u8; 3],
+        /// Number of the `data` bytes sent (0..=3)
+        data_len: u8,
+        /// Number of response bytes expected before the ack
+        response_len: u8,
+    },
+}
+
+impl Request {
+    /// Creates a pass-through command with up to 3 data bytes.
+    ///
+    /// More data bytes don't fit the frame and fail to compile.
+    pub fn pass_through<const N: usize>(
+        device: Device,
+        command: Command,
+        data: [u8; N],
+        response_len: u8,
+    ) -> Request {
+        const { assert!(N <= 3, "at most 3 pass-through data bytes") };
+
+        let mut args = [0u8; 3];
+        for (arg, byte) in args.iter_mut().zip(data) {
+            *arg = byte;
+        }
+        Request::PassThrough {
+            device,
+            command,
+            data: args,
+            data_len: N as u8,
+            response_len,
+        }
+    }
+
+    /// Gets the number of response bytes expected before the `#` ack.
+    pub fn response_len(&self) -> usize {
+        match *self {
+            Request::GetPosition { precise: true, .. } => 17,
+            Request::GetPosition { precise: false, .. } => 9,
+            Request::GetWidePosition { .. } => 11,
+            Request::GetLocation | Request::GetDateTime => 8,
+            Request::GetVersion => 2,
+            Request::GetStarSenseVersion => 3,
+            #[cfg(feature = "synscan")]
+            Request::GetSynScanVersion => 6,
+            #[cfg(feature = "synscan")]
+            Request::GetSynScanModel => 1,
+            Request::GetTrackingMode
+            | Request::GetModel
+            | Request::GetStarSenseModel
+            | Request::IsAlignmentComplete
+            | Request::IsGotoInProgress
+            | Request::Echo(_) => 1,
+            Request::PassThrough { response_len, .. } => response_len as usize,
+            Request::Goto { .. }
+            | Request::Sync { .. }
+            | Request::CancelGoto
+            | Request::SetTrackingMode(_)
+            | Request::SetLocation { .. }
+            | Request::SetDateTime(_) => 0,
+        }
+    }
+
+    /// Gets the frame of a request without arguments, built at compile time.
+    fn static_frame(&self) -> Option<&'static [u8]> {
+        let frame: &'static [u8] = match *self {
+            Request::CancelGoto => &CANCEL_GOTO_FRAME,
+            Request::GetTrackingMode => &GET_TRACKING_MODE_FRAME,
+            Request::GetLocation => &GET_LOCATION_FRAME,
+            Request::GetDateTime => &GET_DATETIME_FRAME,
+            Request::GetVersion => &GET_VERSION_FRAME,
+            Request::GetModel => &GET_MODEL_FRAME,
+            Request::IsAlignmentComplete => &IS_ALIGNMENT_COMPLETE_FRAME,
+            Request::IsGotoInProgress => &IS_GOTO_IN_PROGRESS_FRAME,
+            Request::PassThrough {
+                device,
+                command: Command::GetDeviceVersion,
+                data_len: 0,
+                response_len: 2,
+                ..
+            } => match device {
+                Device::MainBoard => &DEVICE_VERSION_FRAMES[0],
+                Device::HandController => &DEVICE_VERSION_FRAMES[1],
+                Device::AzmRaMotor => &DEVICE_VERSION_FRAMES[2],
+                Device::AltDecMotor => &DEVICE_VERSION_FRAMES[3],
+                Device::Focuser => &DEVICE_VERSION_FRAMES[4],
+                Device::GPSUnit => &DEVICE_VERSION_FRAMES[5],
+                Device::RTC => &DEVICE_VERSION_FRAMES[6],
+                Device::WiFi => &DEVICE_VERSION_FRAMES[7],
+                Device::Battery => &DEVICE_VERSION_FRAMES[8],
+            },
+            _ => return None,
+        };
+        Some(frame)
+    }
+
+    fn frame(&self) -> Frame {
+        match *self {
+            Request::GetPosition { axes, precise } => {
+                let cmd = match (axes, precise) {
+                    (Axes::RaDec, false) => b'E',
+                    (Axes::RaDec, true) => b'e',
+                    (Axes::AzmAlt, false) => b'Z',
+                    (Axes::AzmAlt, true) => b'z',
+                };
+                Frame::new(cmd)
+            }
+            Request::GetWidePosition { axes } => Frame::new(match axes {
+                Axes::RaDec => b'E',
+                Axes::AzmAlt => b'Z',
+            }),
+            Request::Goto {
+                axes,
+                position,
+                precise,
+            } => {
+                let cmd = match (axes, precise) {
+                    (Axes::RaDec, false) => b'R',
+                    (Axes::RaDec, true) => b'r',
+                    (Axes::AzmAlt, false) => b'B',
+                    (Axes::AzmAlt, true) => b'b',
+                };
+                position_frame(cmd, position, precise)
+            }
+            Request::Sync { position, precise } => {
+                position_frame(if precise { b's' } else { b'S' }, position, precise)
+            }
+            Request::CancelGoto => Frame::new(b'M'),
+            Request::GetTrackingMode => Frame::new(b't'),
+            Request::SetTrackingMode(mode) => Frame::new(b'T').push(mode.bits()),
+            Request::GetLocation => Frame::new(b'w'),
+            Request::SetLocation {
+                latitude,
+                longitude,
+            } => Frame::new(b'W').push_dms(latitude).push_dms(longitude),
+            Request::GetDateTime => Frame::new(b'h'),
+            Request::SetDateTime(datetime) => Frame::new(b'H').push_datetime(datetime),
+            Request::GetVersion => Frame::new(b'V'),
+            Request::GetModel => Frame::new(b'm'),
+            Request::GetStarSenseVersion => Frame::new(b'V'),
+            Request::GetStarSenseModel => Frame::new(b'm'),
+            #[cfg(feature = "synscan")]
+            Request::GetSynScanVersion => Frame::new(b'V'),
+            #[cfg(feature = "synscan")]
+            Request::GetSynScanModel => Frame::new(b'm'),
+            Request::IsAlignmentComplete => Frame::new(b'J'),
+            Request::IsGotoInProgress => Frame::new(b'L'),
+            Request::Echo(byte) => Frame::new(b'K').push(byte),
+            Request::PassThrough {
+                device,
+                command,
+                data,
+                data_len,
+                response_len,
+            } => Frame::pass_through(
+                device,
+                command.bits(),
+                data.get(..data_len as usize).unwrap_or(&[0; 4]),
+                response_len,
+            ),
+        }
+        .expect(self.response_len())
+    }
+}
+
+fn position_frame(cmd: u8, position: Counts, precise: bool) -> Frame {
+    let mut first = [0u8; 8];
+    let mut second = [0u8; 8];
+    Frame::new(cmd)
+        .push_bytes(encode_counts(position.first, precise, &mut first))
+        .push(b',')
+        .push_bytes(encode_counts(position.second, precise, &mut second))
+}
+
+/// Response data of a pass-through command
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Data {
+    bytes: [u8; MAX_RESPONSE],
+    len: u8,
+}
+
+impl Data {
+    fn new(data: &[u8]) -> Option<Data> {
+        let mut bytes = [0u8; MAX_RESPONSE];
+        bytes.get_mut(..data.len())?.copy_from_slice(data);
+        Some(Data {
+            bytes,
+            len: data.len() as u8,
+        })
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..self.len as usize]
+    }
+}
+
+/// Parsed response of a [`Request`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Response {
+    /// The ack of a command without response data
+    Ack,
+    Position(Counts),
+    TrackingMode(TrackingMode),
+    Location {
+        latitude: Dms,
+        longitude: Dms,
+    },
+    DateTime(DateTime),
+    /// Firmware version of the HC or a sub device
+    Version(Version),
+    Model(Model),
+    /// Board variant of a motor controller
+    MotorModel(MotorModel),
+    /// Alignment complete, GOTO in progress or a flag of a sub device
+    State(bool),
+    Echo(u8),
+    /// Data of a pass-through command other than the version query
+    Data(Data),
+}
+
+/// Response which can't be parsed
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    /// More bytes are needed.
+    Incomplete,
+    /// The HC answered with an error instead of the ack.
+    Rejected,
+    /// The response data is invalid for the command.
+    Invalid,
+}
+
+/// Encodes the command into `buf` and returns its length, `None` if a
+/// pass-through command has more than 3 data bytes or expects more than
+/// [`MAX_RESPONSE`] bytes.
+pub fn encode_command(request: &Request, buf: &mut [u8; MAX_COMMAND]) -> Option<usize> {
+    let frame;
+    let bytes = match request.static_frame() {
+        Some(bytes) => bytes,
+        None => {
+            frame = request.frame();
+            frame.bytes()?
+        }
+    };
+    buf.get_mut(..bytes.len())?.copy_from_slice(bytes);
+    Some(bytes.len())
+}
+
+/// Parses the complete response to the command, the data followed by the `#`
+/// ack. Bytes after the ack are ignored.
+pub fn parse_response(request: &Request, input: &[u8]) -> Result<Response, ParseError> {
+    match parse::frame(input, request.response_len()) {
+        Ok((data, _)) => parse_data(request, data).ok_or(ParseError::Invalid),
+        Err(FrameError::Incomplete) => Err(ParseError::Incomplete),
+        Err(FrameError::Rejected { .. }) => Err(ParseError::Rejected),
+    }
+}
+
+/// Gets the data of the complete response to the command without parsing or
+/// copying it, e.g. the raw bytes of a pass-through response.
+pub fn response_data<'a>(request: &Request, input: &'a [u8]) -> Result<&'a [u8], ParseError> {
+    match parse::frame(input, request.response_len()) {
+        Ok((data, _)) => Ok(data),
+        Err(FrameError::Incomplete) => Err(ParseError::Incomplete),
+        Err(FrameError::Rejected { .. }) => Err(ParseError::Rejected),
+    }
+}
+
+fn parse_data(request: &Request, data: &[u8]) -> Option<Response> {
+    match *request {
+        Request::GetPosition { .. } | Request::GetWidePosition { .. } => {
+            let (first, second) = parse::position_counts(data)?;
+            Some(Response::Position(Counts { first, second }))
+        }
+        Request::GetTrackingMode => parse::tracking_mode(data).map(Response::TrackingMode),
+        Request::GetLocation => {
+            let (latitude, longitude) = parse::location_dms(data)?;
+            Some(Response::Location {
+                latitude,
+                longitude,
+            })
+        }
+        Request::GetDateTime => parse::datetime(data).map(Response::DateTime),
+        Request::GetVersion
+        | Request::PassThrough {
+            command: Command::GetDeviceVersion,
+            response_len: 2,
+            ..
+        } => parse::version(data).map(Response::Version),
+        Request::PassThrough {
+            command:
+                Command::GpsIsLinked
+                | Command::AtIndex
+                | Command::LevelDone
+                | Command::SlewDone
+                | Command::IsGuiding,
+            response_len: 1,
+            ..
+        } => parse::flag(data).map(Response::State),
+        Request::GetModel => parse::model(data).map(Response::Model),
+        Request::GetStarSenseVersion => parse::starsense_version(data).map(Response::Version),
+        Request::GetStarSenseModel => parse::starsense_model(data).map(Response::Model),
+        #[cfg(feature = "synscan")]
+        Request::GetSynScanVersion => parse::synscan_version(data).map(Response::Version),
+        #[cfg(feature = "synscan")]
+        Request::GetSynScanModel => parse::synscan_model(data).map(Response::Model),
+        Request::PassThrough {
+            command: Command::GetMotorModel,
+            response_len: 1,
+            ..
+        } => parse::motor_model(data).map(Response::MotorModel),
+        Request::IsAlignmentComplete | Request::IsGotoInProgress => {
+            parse::state(data).map(Response::State)
+        }
+        Request::Echo(_) => match *data {
+            [byte] => Some(Response::Echo(byte)),
+            _ => None,
+        },
+        Request::PassThrough { .. } if !data.is_empty() => Data::new(data).map(Response::Data),
+        _ if data.is_empty() => Some(Response::Ack),
+        _ => None,
+    }
+}
+
+/// State of the [`Parser`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Progress {
+    /// The response isn't complete yet.
+    NeedMore,
+    /// The response is complete, [`Parser::response`] gets its bytes for
+    /// [`parse_response`] and further bytes belong to the next one.
+    Done,
+}
+
+/// Incremental framing of the response to one command
+///
+/// The received bytes go into a buffer of the caller, the parser only keeps
+/// the expected data length and the number of bytes received. Its state is a
+/// few bytes whatever the size of the response, and it decides at most two
+/// bytes after the expected response data.
+///
+/// A buffer of [`Request::response_len`] + 2 bytes, at most
+/// [`MAX_RESPONSE`] + 2, holds any response. [`parse_response`] then borrows
+/// the bytes, so nothing is copied until the values are decoded.
+#[derive(Copy, Clone, Debug)]
+pub struct Parser {
+    response_len: u16,
+    received: u16,
+    done: bool,
+}
+
+// the state doesn't grow with the response
+const _: () = assert!(core::mem::size_of::<Parser>() < 16);
+
+impl Parser {
+    pub fn new(request: &Request) -> Parser {
+        Parser {
+            response_len: request.response_len() as u16,
+            received: 0,
+            done: false,
+        }
+    }
+
+    /// Stores the next received byte in `buffer`, at [`Parser::received`].
+    ///
+    /// If the byte doesn't fit the response is done early and
+    /// [`parse_response`] fails with [`ParseError::Incomplete`].
+    pub fn push(&mut self, buffer: &mut [u8], byte: u8) -> Progress {
+        if self.done {
+            return Progress::Done;
+        }
+        match buffer.get_mut(self.received as usize) {
+            Some(slot) => *slot = byte,
+            None => {
+                self.done = true;
+                return Progress::Done;
+            }
+        }
+        self.received += 1;
+
+        match parse::frame(self.response(buffer), self.response_len as usize) {
+            Err(FrameError::Incomplete) => Progress::NeedMore,
+            _ => {
+                self.done = true;
+                Progress::Done
+            }
+        }
+    }
+
+    /// Gets the number of bytes consumed so far.
+    pub fn received(&self) -> usize {
+        self.received as usize
+    }
+
+    /// Gets the bytes of the response consumed so far from the buffer they
+    /// were pushed into.
+    pub fn response<'a>(&self, buffer: &'a [u8]) -> &'a [u8] {
+        buffer.get(..self.received()).unwrap_or(buffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Hemisphere;
+
+    fn encoded(request: Request) -> ([u8; MAX_COMMAND], usize) {
+        let mut buf = [0u8; MAX_COMMAND];
+        let len = encode_command(&request, &mut buf).unwrap();
+        (buf, len)
+    }
+
+    #[test]
+    fn encode_positions() {
+        let position = Counts {
+            first: 0x4000,
+            second: 0xC000,
+        };
+        let (buf, len) = encoded(Request::Goto {
+            axes: Axes::AzmAlt,
+            position,
+            precise: false,
+        });
+        assert_eq!(&buf[..len], b"B4000,C000");
+
+        let position = Counts {
+            first: 0x80_0000,
+            second: 0x12_3456,
+        };
+        let (buf, len) = encoded(Request::Sync {
+            position,
+            precise: true,
+        });
+        assert_eq!(&buf[..len], b"s80000000,12345600");
+
+        let (buf, len) = encoded(Request::GetPosition {
+            axes: Axes::AzmAlt,
+            precise: true,
+        });
+        assert_eq!(&buf[..len], b"z");
+    }
+
+    #[test]
+    fn encode_pass_through() {
+        let request = Request::pass_through(Device::GPSUnit, Command::GetDeviceVersion, [], 2);
+        let (buf, len) = encoded(request);
+        assert_eq!(&buf[..len], b"P\x01\xb0\xfe\x00\x00\x00\x02");
+
+        let request = Request::PassThrough {
+            device: Device::AzmRaMotor,
+            command: Command::SetPositiveVariableRate,
+            data: [0; 3],
+            data_len: 4,
+            response_len: 0,
+        };
+        assert_eq!(encode_command(&request, &mut [0; MAX_COMMAND]), None);
+    }
+
+    #[test]
+    fn parse_responses() {
+        let request = Request::GetPosition {
+            axes: Axes::RaDec,
+            precise: false,
+        };
+        assert_eq!(
+            parse_response(&request, b"4000,C000#"),
+            Ok(Response::Position(Counts {
+                first: 0x4000,
+                second: 0xC000
+            }))
+        );
+        assert_eq!(
+            parse_response(&request, b"4000,C000"),
+            Err(ParseError::Incomplete)
+        );
+        assert_eq!(
+            parse_response(&request, b"4000;C000#"),
+            Err(ParseError::Invalid)
+        );
+
+        let request = Request::GetWidePosition { axes: Axes::AzmAlt };
+        assert_eq!(encoded(request).0[0], b'Z');
+        assert_eq!(
+            parse_response(&request, b"40000,C0000#"),
+            Ok(Response::Position(Counts {
+                first: 0x4_0000,
+                second: 0xC_0000
+            }))
+        );
+        assert_eq!(
+            parse_response(&request, b"4000,C000#"),
+            Err(ParseError::Incomplete)
+        );
+
+        assert_eq!(
+            parse_response(&Request::CancelGoto, b"#"),
+            Ok(Response::Ack)
+        );
+        assert_eq!(
+            parse_response(&Request::IsGotoInProgress, b"1#"),
+            Ok(Response::State(true))
+        );
+        assert_eq!(
+            parse_response(&Request::GetLocation, &[47, 12, 34, 0, 8, 32, 7, 1, b'#']),
+            Ok(Response::Location {
+                latitude: Dms {
+                    degrees: 47,
+                    minutes: 12,
+                    seconds: 34,
+                    hemisphere: Hemisphere::North,
+                },
+                longitude: Dms {
+                    degrees: 8,
+                    minutes: 32,
+                    seconds: 7,
+                    hemisphere: Hemisphere::West,
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn parse_pass_through() {
+        let request = Request::pass_through(Device::AzmRaMotor, Command::GetDeviceVersion, [], 2);
+        assert_eq!(
+            parse_response(&request, &[7, 11, b'#']),
+            Ok(Response::Version(Version::new(7, 11)))
+        );
+        // the error code follows the expected number of bytes
+        assert_eq!(
+            parse_response(&request, &[0, 0, 0, b'#']),
+            Err(ParseError::Rejected)
+        );
+
+        let request = Request::pass_through(
+            Device::AzmRaMotor,
+            Command::SetPositiveVariableRate,
+            [0, 60],
+            0,
+        );
+        assert_eq!(parse_response(&request, b"#"), Ok(Response::Ack));
+    }
+
+    #[test]
+    fn static_frames() {
+        let requests = [
+            Request::CancelGoto,
+            Request::GetTrackingMode,
+            Request::GetLocation,
+            Request::GetDateTime,
+            Request::GetVersion,
+            Request::GetModel,
+            Request::IsAlignmentComplete,
+            Request::IsGotoInProgress,
+        ];
+        for request in requests {
+            assert_eq!(
+                request.static_frame(),
+                request.frame().bytes(),
+                "{:?}",
+                request
+            );
+        }
+
+        let devices = [
+            Device::MainBoard,
+            Device::HandController,
+            Device::AzmRaMotor,
+            Device::AltDecMotor,
+            Device::Focuser,
+            Device::GPSUnit,
+            Device::RTC,
+            Device::WiFi,
+            Device::Battery,
+        ];
+        for device in devices {
+            let request = Request::pass_through(device, Command::GetDeviceVersion, [], 2);
+            assert_eq!(request.static_frame(), request.frame().bytes());
+            assert_eq!(
+                request.static_frame(),
+                Some(&device_version_frame(device)[..])
+            );
+        }
+        assert_eq!(&GET_LOCATION_FRAME, b"w");
+
+        // only the plain version query has a static frame
+        let request = Request::pass_through(Device::AzmRaMotor, Command::GetDeviceVersion, [], 3);
+        assert_eq!(request.static_frame(), None);
+    }
+
+    #[test]
+    fn parser_decides_early() {
+        let mut buffer = [0u8; MAX_RESPONSE + 2];
+        let mut parser = Parser::new(&Request::GetTrackingMode);
+        assert_eq!(parser.push(&mut buffer, 2), Progress::NeedMore);
+        assert_eq!(parser.push(&mut buffer, b'#'), Progress::Done);
+        assert_eq!(
+            parse_response(&Request::GetTrackingMode, parser.response(&buffer)),
+            Ok(Response::TrackingMode(TrackingMode::EQNorth))
+        );
+
+        let mut parser = Parser::new(&Request::GetModel);
+        assert_eq!(parser.push(&mut buffer, 0), Progress::NeedMore);
+        assert_eq!(parser.push(&mut buffer, 0), Progress::NeedMore);
+        assert_eq!(parser.push(&mut buffer, b'#'), Progress::Done);
+        // pushing after the response changes nothing
+        assert_eq!(parser.push(&mut buffer, b'#'), Progress::Done);
+        assert_eq!(parser.received(), 3);
+        assert_eq!(
+            parse_response(&Request::GetModel, parser.response(&buffer)),
+            Err(ParseError::Rejected)
+        );
+    }
+
+    #[test]
+    fn parser_in_short_buffer() {
+        let mut buffer = [0u8; 4];
+        let mut parser = Parser::new(&Request::GetLocation);
+        for byte in b"\x1E\x1E" {
+            assert_eq!(parser.push(&mut buffer, *byte), Progress::NeedMore);
+        }
+        assert_eq!(parser.push(&mut buffer, 0), Progress::NeedMore);
+        assert_eq!(parser.push(&mut buffer, 0), Progress::NeedMore);
+        assert_eq!(parser.push(&mut buffer, 0), Progress::Done);
+        assert_eq!(parser.received(), 4);
+        assert_eq!(
+            parse_response(&Request::GetLocation, parser.response(&buffer)),
+            Err(ParseError::Incomplete)
+        );
+    }
+
+    #[test]
+    fn response_data_borrows() {
+        let request = Request::pass_through(Device::GPSUnit, Command::GpsGetLatitude, [], 3);
+        let input = [0x12, 0x34, 0x56, b'#'];
+        let data = response_data(&request, &input).unwrap();
+        assert_eq!(data, &[0x12, 0x34, 0x56]);
+        assert_eq!(data.as_ptr(), input.as_ptr());
+
+        assert_eq!(
+            response_data(&request, &input[..3]),
+            Err(ParseError::Incomplete)
+        );
+        assert_eq!(
+            response_data(&request, b"\x00\x00\x00\x10#"),
+            Err(ParseError::Rejected)
+        );
+    }
+}
@@ -0,0 +1,357 @@
+//! GOTO targets visited one after the other
+//!
+//! A [`TargetQueue`] holds the targets of a session, e.g. a survey, and
+//! [`NexStar::run_queue`](crate::NexStar::run_queue) GOTOs each of them,
+//! waits for the mount to arrive and hands over to a hook, e.g. to take an
+//! exposure:
+//!
+//! ```ignore
+//! let mut queue = TargetQueue::<40>::new();
+//! queue.push(QueueEntry::new(Target::RaDec(m101)))?;
+//! nexstar.run_queue(&mut queue, &mut delay, |index, entry| {
+//!     camera.expose(entry.target);
+//!     Visit::Continue
+//! })?;
+//! ```
+//!
+//! The queue keeps its progress. After a failed GOTO [`TargetQueue::failed`]
+//! tells which entry failed and running the queue again retries it, or
+//! [`TargetQueue::skip`] moves on to the next one.
+//!
+//! [`mosaic`] generates the pointings of a mosaic to fill the queue with.
+
+use libm::cos;
+
+use crate::{Approach, RaDec, Target};
+
+/// Target of a [`TargetQueue`]
+#[derive(Copy, Clone, Debug)]
+pub struct QueueEntry {
+    pub target: Target,
+    /// Direction and overshoot in arcminutes to approach the target from,
+    /// see [`NexStar::goto_with_approach`](crate::NexStar::goto_with_approach),
+    /// `None` GOTOs it directly.
+    pub approach: Option<(Approach, f32)>,
+}
+
+impl QueueEntry {
+    /// Creates an entry which GOTOs the target directly.
+    pub fn new(target: Target) -> QueueEntry {
+        QueueEntry {
+            target,
+            approach: None,
+        }
+    }
+}
+
+/// Whether to continue with the next target, returned by the hook of
+/// [`NexStar::run_queue`](crate::NexStar::run_queue)
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Visit {
+    Continue,
+    Abort,
+}
+
+/// Queue of up to `N` GOTO targets and the progress through them
+#[derive(Copy, Clone, Debug)]
+pub struct TargetQueue<const N: usize> {
+    entries: [Option<QueueEntry>; N],
+    len: usize,
+    next: usize,
+    failed: Option<usize>,
+}
+
+impl<const N: usize> TargetQueue<N> {
+    pub fn new() -> Self {
+        TargetQueue {
+            entries: [None; N],
+            len: 0,
+            next: 0,
+            failed: None,
+        }
+    }
+
+    /// Appends the entry, or gives it back if the queue is full.
+    pub fn push(&mut self, entry: QueueEntry) -> Result<(), QueueEntry> {
+        match self.entries.get_mut(self.len) {
+            Some(slot) => {
+                *slot = Some(entry);
+                self.len += 1;
+                Ok(())
+            }
+            None => Err(entry),
+        }
+    }
+
+    pub fn get(&self, index: usize) -> Option<QueueEntry> {
+        self.entries.get(index).copied().flatten()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Gets the index of the next entry to visit, [`TargetQueue::len`] once
+    /// all were visited.
+    pub fn next_index(&self) -> usize {
+        self.next
+    }
+
+    /// Gets whether all entries were visited.
+    pub fn is_done(&self) -> bool {
+        self.next >= self.len
+    }
+
+    /// Gets the index of the entry whose GOTO failed in the last run, `None`
+    /// if it didn't fail.
+    pub fn failed(&self) -> Option<usize> {
+        self.failed
+    }
+
+    /// Moves on past the next entry without visiting it, e.g. after it
+    /// failed.
+    pub fn skip(&mut self) {
+        self.next = (self.next + 1).min(self.len);
+        self.failed = None;
+    }
+
+    /// Starts over at the first entry.
+    pub fn rewind(&mut self) {
+        self.next = 0;
+        self.failed = None;
+    }
+
+    /// Removes all entries.
+    pub fn clear(&mut self) {
+        *self = TargetQueue::new();
+    }
+
+    /// Gets the next entry to visit and its index.
+    pub(crate) fn next_entry(&self) -> Option<(usize, QueueEntry)> {
+        self.get(self.next).map(|entry| (self.next, entry))
+    }
+
+    /// Records the result of visiting the next entry.
+    pub(crate) fn record(&mut self, visited: bool) {
+        if visited {
+            self.next += 1;
+            self.failed = None;
+        } else {
+            self.failed = Some(self.next);
+        }
+    }
+}
+
+impl<const N: usize> Default for TargetQueue<N> {
+    fn default() -> Self {
+        TargetQueue::new()
+    }
+}
+
+/// Pointings of a mosaic, see [`mosaic`]
+#[derive(Copy, Clone, Debug)]
+pub struct Mosaic {
+    center: RaDec,
+    tiles_x: u32,
+    tiles_y: u32,
+    /// Distance of the tile centers in degrees
+    step_x: f64,
+    step_y: f64,
+    next: u32,
+}
+
+/// Generates the pointings of a mosaic of `tiles_x` by `tiles_y` tiles
+/// around the center.
+///
+/// The tiles of the field of view in arcminutes overlap their neighbours by
+/// `overlap_percent` of its width or height. The rows run from south to north
+/// and alternate their direction, the first one with increasing RA, so every
+/// slew goes to a neighbour. The RA offsets grow with 1 / cos(dec) of the
+/// row, so the tiles stay the same distance apart on the sky.
+///
+/// `None` if a tile reaches a pole, where the RA offsets are meaningless, or
+/// if there are no tiles, the field of view is empty or the overlap isn't in
+/// 0..100.
+pub fn mosaic(
+    center: RaDec,
+    tiles_x: u32,
+    tiles_y: u32,
+    fov_width_arcmin: f32,
+    fov_height_arcmin: f32,
+    overlap_percent: f32,
+) -> Option<Mosaic> {
+    let valid = tiles_x > 0
+        && tiles_y > 0
+        && tiles_x.checked_mul(tiles_y).is_some()
+        && fov_width_arcmin > 0.0
+        && fov_height_arcmin > 0.0
+        && (0.0..100.0).contains(&overlap_percent);
+    if !valid {
+        return None;
+    }
+
+    let shrink = 1.0 - overlap_percent as f64 / 100.0;
+    let mosaic = Mosaic {
+        center,
+        tiles_x,
+        tiles_y,
+        step_x: fov_width_arcmin as f64 / 60.0 * shrink,
+        step_y: fov_height_arcmin as f64 / 60.0 * shrink,
+        next: 0,
+    };
+
+    // the outermost rows with half a tile beyond them
+    let reach = mosaic
+        .row_dec(0)
+        .abs()
+        .max(mosaic.row_dec(tiles_y - 1).abs())
+        + fov_height_arcmin as f64 / 120.0;
+    if reach >= 90.0 {
+        return None;
+    }
+    Some(mosaic)
+}
+
+impl Mosaic {
+    /// Gets the declination of the row in degrees.
+    fn row_dec(&self, row: u32) -> f64 {
+        self.center.dec as f64 + offset(row, self.tiles_y) * self.step_y
+    }
+}
+
+/// Gets the offset of the tile from the middle of the tiles in steps.
+fn offset(index: u32, tiles: u32) -> f64 {
+    index as f64 - (tiles - 1) as f64 / 2.0
+}
+
+impl Iterator for Mosaic {
+    type Item = RaDec;
+
+    fn next(&mut self) -> Option<RaDec> {
+        if self.next >= self.tiles_x * self.tiles_y {
+            return None;
+        }
+        let row = self.next / self.tiles_x;
+        let column = match row % 2 {
+            0 => self.next % self.tiles_x,
+            _ => self.tiles_x - 1 - self.next % self.tiles_x,
+        };
+        self.next += 1;
+
+        let dec = self.row_dec(row);
+        let ra = self.center.ra as f64
+            + offset(column, self.tiles_x) * self.step_x / cos(dec.to_radians());
+        let ra = ra % 360.0;
+        Some(RaDec {
+            ra: if ra < 0.0 { ra + 360.0 } else { ra } as f32,
+            dec: dec as f32,
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = (self.tiles_x * self.tiles_y - self.next) as usize;
+        (left, Some(left))
+    }
+}
+
+impl ExactSizeIterator for Mosaic {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_tiles(mosaic: Mosaic, expected: &[(f32, f32)]) {
+        assert_eq!(mosaic.len(), expected.len());
+        for (tile, (ra, dec)) in mosaic.zip(expected) {
+            assert!(
+                (tile.ra - ra).abs() < 1e-4 && (tile.dec - dec).abs() < 1e-4,
+                "{:?} instead of {} {}",
+                tile,
+                ra,
+                dec
+            );
+        }
+    }
+
+    #[test]
+    fn mosaic_row() {
+        let center = RaDec {
+            ra: 100.0,
+            dec: 0.0,
+        };
+        let tiles = mosaic(center, 3, 1, 60.0, 40.0, 0.0).unwrap();
+        assert_tiles(tiles, &[(99.0, 0.0), (100.0, 0.0), (101.0, 0.0)]);
+
+        // a single tile is the center
+        let tiles = mosaic(center, 1, 1, 60.0, 40.0, 10.0).unwrap();
+        assert_tiles(tiles, &[(100.0, 0.0)]);
+    }
+
+    #[test]
+    fn mosaic_serpentine() {
+        let center = RaDec {
+            ra: 100.0,
+            dec: 60.0,
+        };
+        // half a degree apart, the RA offsets are 0.25° / cos(dec)
+        let tiles = mosaic(center, 2, 3, 60.0, 60.0, 50.0).unwrap();
+        let south = 0.25 / 59.5f32.to_radians().cos();
+        let north = 0.25 / 60.5f32.to_radians().cos();
+        assert_tiles(
+            tiles,
+            &[
+                (100.0 - south, 59.5),
+                (100.0 + south, 59.5),
+                (100.0 + 0.5, 60.0),
+                (100.0 - 0.5, 60.0),
+                (100.0 - north, 60.5),
+                (100.0 + north, 60.5),
+            ],
+        );
+    }
+
+    #[test]
+    fn mosaic_across_ra_zero() {
+        let center = RaDec { ra: 0.2, dec: 0.0 };
+        let tiles = mosaic(center, 3, 2, 30.0, 30.0, 0.0).unwrap();
+        assert_tiles(
+            tiles,
+            &[
+                (359.7, -0.25),
+                (0.2, -0.25),
+                (0.7, -0.25),
+                (0.7, 0.25),
+                (0.2, 0.25),
+                (359.7, 0.25),
+            ],
+        );
+    }
+
+    #[test]
+    fn mosaic_near_pole() {
+        let center = RaDec {
+            ra: 0.0,
+            dec: -88.0,
+        };
+        // the southern row ends 1° from the pole
+        assert!(mosaic(center, 2, 2, 60.0, 60.0, 0.0).is_some());
+        // and then reaches it
+        assert!(mosaic(center, 2, 4, 60.0, 60.0, 0.0).is_none());
+        assert!(mosaic(RaDec { ra: 0.0, dec: 90.0 }, 1, 1, 1.0, 1.0, 0.0).is_none());
+    }
+
+    #[test]
+    fn mosaic_invalid() {
+        let center = RaDec { ra: 0.0, dec: 0.0 };
+        assert!(mosaic(center, 0, 1, 60.0, 60.0, 0.0).is_none());
+        assert!(mosaic(center, 1, 0, 60.0, 60.0, 0.0).is_none());
+        assert!(mosaic(center, 1, 1, 0.0, 60.0, 0.0).is_none());
+        assert!(mosaic(center, 1, 1, 60.0, 60.0, 100.0).is_none());
+        assert!(mosaic(center, 1, 1, 60.0, 60.0, -1.0).is_none());
+        assert!(mosaic(center, u32::MAX, 2, 1.0, 1.0, 0.0).is_none());
+    }
+}
@@ -0,0 +1,138 @@
+//! Receive buffer filled by the UART interrupt
+//!
+//! The FIFO of many UARTs holds only a few bytes, so a response arriving while
+//! another interrupt runs long loses bytes if the driver reads the UART
+//! directly. An [`RxBuffer`] is split into an [`RxProducer`] for the receive
+//! interrupt and an [`RxReader`] which serves as the receiving half of the
+//! driver:
+//!
+//! ```ignore
+//! static mut BUFFER: RxBuffer<64> = RxBuffer::new();
+//! static PRODUCER: Mutex<RefCell<Option<RxProducer<'static, 64>>>> =
+//!     Mutex::new(RefCell::new(None));
+//!
+//! fn main() {
+//!     // split once, before the interrupt is enabled
+//!     let buffer = unsafe { &mut *addr_of_mut!(BUFFER) };
+//!     let (producer, reader) = buffer.split();
+//!     critical_section::with(|cs| PRODUCER.borrow_ref_mut(cs).replace(producer));
+//!     let mut nexstar = NexStar::new(reader, uart_tx);
+//!     // ...
+//! }
+//!
+//! #[interrupt]
+//! fn USART1() {
+//!     critical_section::with(|cs| {
+//!         if let Some(producer) = PRODUCER.borrow_ref_mut(cs).as_mut() {
+//!             while let Ok(byte) = uart_rx.read() {
+//!                 producer.push(byte);
+//!             }
+//!         }
+//!     });
+//! }
+//! ```
+//!
+//! A byte arriving while the buffer is full is dropped and the reader fails
+//! with [`Overrun`] once, which marks the link dirty so the driver resyncs
+//! before the next command instead of parsing a corrupted response.
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use embedded_hal::serial;
+use heapless::spsc::{Consumer, Producer, Queue};
+
+/// Buffer of `N - 1` received bytes
+pub struct RxBuffer<const N: usize> {
+    queue: Queue<u8, N>,
+    /// Overruns so far, only written by the producer
+    overruns: AtomicUsize,
+}
+
+impl<const N: usize> RxBuffer<N> {
+    pub const fn new() -> Self {
+        RxBuffer {
+            queue: Queue::new(),
+            overruns: AtomicUsize::new(0),
+        }
+    }
+
+    /// Splits the buffer into the halves for the interrupt and the driver.
+    pub fn split(&mut self) -> (RxProducer<'_, N>, RxReader<'_, N>) {
+        let (producer, consumer) = self.queue.split();
+        (
+            RxProducer {
+                producer,
+                overruns: &self.overruns,
+            },
+            RxReader {
+                consumer,
+                overruns: &self.overruns,
+                reported: 0,
+            },
+        )
+    }
+}
+
+impl<const N: usize> Default for RxBuffer<N> {
+    fn default() -> Self {
+        RxBuffer::new()
+    }
+}
+
+/// Half of an [`RxBuffer`] for the receive interrupt
+pub struct RxProducer<'a, const N: usize> {
+    producer: Producer<'a, u8, N>,
+    overruns: &'a AtomicUsize,
+}
+
+impl<'a, const N: usize> RxProducer<'a, N> {
+    /// Appends a received byte, a full buffer drops it and flags an overrun.
+    pub fn push(&mut self, byte: u8) {
+        if self.producer.enqueue(byte).is_err() {
+            // the only writer, so a load and store suffice where targets lack
+            // atomic read-modify-write
+            let overruns = self.overruns.load(Ordering::Relaxed);
+            self.overruns
+                .store(overruns.wrapping_add(1), Ordering::Release);
+        }
+    }
+}
+
+/// Half of an [`RxBuffer`] serving as the receiving half of the driver
+pub struct RxReader<'a, const N: usize> {
+    consumer: Consumer<'a, u8, N>,
+    overruns: &'a AtomicUsize,
+    /// Overruns reported so far
+    reported: usize,
+}
+
+impl<'a, const N: usize> RxReader<'a, N> {
+    /// Gets the number of bytes buffered.
+    pub fn len(&self) -> usize {
+        self.consumer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Bytes were dropped because the buffer was full
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Overrun;
+
+impl<'a, const N: usize> serial::Read<u8> for RxReader<'a, N> {
+    type Error = Overrun;
+
+    fn read(&mut self) -> nb::Result<u8, Overrun> {
+        // an overrun while reading shows up as a new count next time, where
+        // clearing a flag could lose it
+        let overruns = self.overruns.load(Ordering::Acquire);
+        if overruns != self.reported {
+            self.reported = overruns;
+            return Err(nb::Error::Other(Overrun));
+        }
+
+        self.consumer.dequeue().ok_or(nb::Error::WouldBlock)
+    }
+}
@@ -0,0 +1,577 @@
+//! Parsers of coordinates in degrees, minutes and seconds
+//!
+//! Accept what config files, catalogs and plate solvers commonly write. The
+//! components of an angle are separated by their signs, colons or spaces.
+//! Seconds and minutes are optional and the last component given may have
+//! decimals.
+//!
+//! Locations like `47°12'34"N 8°32'07"E`, `47 12 34 N, 8 32 07 E` or
+//! `N47:12.57 E8:32.12` use the degree, minute and second signs (also the
+//! primes ′ and ″). Each coordinate needs a hemisphere letter, before or
+//! after it, which also tells latitude and longitude apart.
+//!
+//! Equatorial coordinates like `12h34m56.7s +45°06'07"`,
+//! `12:34:56.7 -05:06:07` or `188.7363 45.1019` give the right ascension
+//! first, in hours with the `h`, `m` and `s` signs or with several components,
+//! in degrees as a single number. The declination follows with an optional
+//! sign (also the minus sign −) which applies to the whole angle, so
+//! `-00:30:00` is half a degree south of the equator.
+
+use core::convert::TryFrom;
+use core::fmt;
+use core::str::FromStr;
+
+use crate::types::{Dms, Hemisphere, Location, RaDec};
+
+/// Digits after the decimal point taken into account
+const MAX_DECIMALS: u32 = 9;
+/// Thousandths of a second in a unit, a minute and a second
+const UNITS: [u64; 3] = [3_600_000, 60_000, 1_000];
+
+/// Signs ending the components of an angle, a colon also ends the first two
+type Signs = [&'static [&'static str]; 3];
+
+const DMS: Signs = [&["°"], &["'", "′"], &["\"", "″", "''"]];
+/// The degree sign marks a right ascension in degrees.
+const HMS: Signs = [&["h", "°"], &["m"], &["s"]];
+
+/// Error of parsing a [`Location`] or a [`RaDec`], the offsets are in bytes
+/// of the input
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CoordinateParseError {
+    /// The input ended before the second coordinate was complete.
+    UnexpectedEnd,
+    /// A number was expected at the offset.
+    ExpectedNumber(usize),
+    /// A hemisphere letter was expected at the offset.
+    ExpectedHemisphere(usize),
+    /// The character at the offset doesn't belong there.
+    UnexpectedCharacter(usize),
+    /// The coordinate starting at the offset has minutes or seconds of 60 or
+    /// more, or exceeds 90° of latitude or declination, 180° of longitude or
+    /// 24 hours of right ascension.
+    OutOfRange(usize),
+    /// Both coordinates of a location are latitudes or both are longitudes.
+    SameAxis,
+}
+
+impl fmt::Display for CoordinateParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CoordinateParseError::UnexpectedEnd => write!(f, "coordinates incomplete"),
+            CoordinateParseError::ExpectedNumber(at) => write!(f, "expected a number at {}", at),
+            CoordinateParseError::ExpectedHemisphere(at) => {
+                write!(f, "expected a hemisphere letter (N, S, E, W) at {}", at)
+            }
+            CoordinateParseError::UnexpectedCharacter(at) => {
+                write!(f, "unexpected character at {}", at)
+            }
+            CoordinateParseError::OutOfRange(at) => {
+                write!(f, "coordinate at {} out of range", at)
+            }
+            CoordinateParseError::SameAxis => {
+                write!(f, "expected a latitude (N, S) and a longitude (E, W)")
+            }
+        }
+    }
+}
+
+impl FromStr for Location {
+    type Err = CoordinateParseError;
+
+    /// Parses a latitude and a longitude, in either order, separated by
+    /// spaces, a comma or a semicolon. See the formats in the
+    /// [`Display`](fmt::Display) of [`Location`], which this parses back.
+    fn from_str(s: &str) -> Result<Location, CoordinateParseError> {
+        let mut cursor = Cursor { input: s, pos: 0 };
+        let first = cursor.geographic()?;
+        cursor.list_separator();
+        let second = cursor.geographic()?;
+        cursor.end()?;
+
+        let (latitude, longitude) = match (is_latitude(first.1), is_latitude(second.1)) {
+            (true, false) => (first, second),
+            (false, true) => (second, first),
+            _ => return Err(CoordinateParseError::SameAxis),
+        };
+        let latitude = dms(latitude, 90)?;
+        let longitude = dms(longitude, 180)?;
+        Location::from_dms(latitude, longitude).ok_or(CoordinateParseError::SameAxis)
+    }
+}
+
+impl FromStr for RaDec {
+    type Err = CoordinateParseError;
+
+    /// Parses a right ascension and a declination separated by spaces, a
+    /// comma or a semicolon, see the [module docs](self).
+    fn from_str(s: &str) -> Result<RaDec, CoordinateParseError> {
+        let mut cursor = Cursor { input: s, pos: 0 };
+
+        cursor.skip_spaces();
+        let start = cursor.pos;
+        let ra = cursor.angle(&HMS)?;
+        let in_hours = match ra.first_sign {
+            Some(sign) => HMS[0][sign] == "h",
+            None => ra.components > 1,
+        };
+        let (units, degrees_per_unit) = if in_hours { (24, 15.0) } else { (360, 1.0) };
+        if ra.thousandths >= units * UNITS[0] {
+            return Err(CoordinateParseError::OutOfRange(start));
+        }
+
+        cursor.list_separator();
+        cursor.skip_spaces();
+        let start = cursor.pos;
+        // the sign applies to the whole angle, also with 0 degrees
+        let negative = matches!(cursor.peek(), Some('-') | Some('−'));
+        if negative || cursor.peek() == Some('+') {
+            cursor.bump();
+        }
+        let dec = cursor.angle(&DMS)?;
+        if dec.thousandths > 90 * UNITS[0] {
+            return Err(CoordinateParseError::OutOfRange(start));
+        }
+        cursor.end()?;
+
+        let ra = ra.thousandths as f64 / UNITS[0] as f64 * degrees_per_unit;
+        let dec = dec.thousandths as f64 / UNITS[0] as f64;
+        Ok(RaDec {
+            ra: ra as f32,
+            dec: if negative { -dec } else { dec } as f32,
+        })
+    }
+}
+
+/// Coordinate in whole arcseconds with its hemisphere and start offset
+type Coordinate = (u32, Hemisphere, usize);
+
+fn is_latitude(hemisphere: Hemisphere) -> bool {
+    matches!(hemisphere, Hemisphere::North | Hemisphere::South)
+}
+
+fn dms(
+    (arcsec, hemisphere, start): Coordinate,
+    max_degrees: u32,
+) -> Result<Dms, CoordinateParseError> {
+    if arcsec > max_degrees * 3600 {
+        return Err(CoordinateParseError::OutOfRange(start));
+    }
+
+    Ok(Dms {
+        degrees: (arcsec / 3600) as u8,
+        minutes: (arcsec / 60 % 60) as u8,
+        seconds: (arcsec % 60) as u8,
+        hemisphere,
+    })
+}
+
+fn hemisphere(c: char) -> Option<Hemisphere> {
+    match c {
+        'N' | 'n' => Some(Hemisphere::North),
+        'S' | 's' => Some(Hemisphere::South),
+        'E' | 'e' => Some(Hemisphere::East),
+        'W' | 'w' => Some(Hemisphere::West),
+        _ => None,
+    }
+}
+
+/// Angle of up to three components
+struct Angle {
+    /// In thousandths of a second of the unit of the first component
+    thousandths: u64,
+    components: usize,
+    /// Index of the sign after the first component, if any
+    first_sign: Option<usize>,
+}
+
+enum Separator {
+    /// Index of the sign
+    Sign(usize),
+    Colon,
+    Space,
+}
+
+struct Cursor<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl Cursor<'_> {
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn rest(&self) -> &str {
+        self.input.get(self.pos..).unwrap_or("")
+    }
+
+    fn bump(&mut self) {
+        if let Some(c) = self.peek() {
+            self.pos += c.len_utf8();
+        }
+    }
+
+    fn skip_spaces(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.bump();
+        }
+    }
+
+    /// Skips the spaces, comma or semicolon between two coordinates.
+    fn list_separator(&mut self) {
+        self.skip_spaces();
+        if matches!(self.peek(), Some(',') | Some(';')) {
+            self.bump();
+        }
+    }
+
+    /// Checks that only spaces are left.
+    fn end(&mut self) -> Result<(), CoordinateParseError> {
+        self.skip_spaces();
+        match self.peek() {
+            Some(_) => Err(CoordinateParseError::UnexpectedCharacter(self.pos)),
+            None => Ok(()),
+        }
+    }
+
+    fn hemisphere(&mut self) -> Option<Hemisphere> {
+        let hemisphere = hemisphere(self.peek()?)?;
+        self.bump();
+        Some(hemisphere)
+    }
+
+    /// Parses a latitude or longitude with its hemisphere.
+    fn geographic(&mut self) -> Result<Coordinate, CoordinateParseError> {
+        self.skip_spaces();
+        let start = self.pos;
+        let prefix = self.hemisphere();
+        self.skip_spaces();
+
+        let angle = self.angle(&DMS)?;
+        let hemisphere = match prefix {
+            Some(hemisphere) => hemisphere,
+            None => self
+                .hemisphere()
+                .ok_or(CoordinateParseError::ExpectedHemisphere(self.pos))?,
+        };
+        let arcsec = angle.thousandths.saturating_add(500) / 1000;
+        let arcsec = u32::try_from(arcsec).map_err(|_| CoordinateParseError::OutOfRange(start))?;
+        Ok((arcsec, hemisphere, start))
+    }
+
+    /// Parses the first component and the optional further ones, the spaces
+    /// after the last one are skipped.
+    fn angle(&mut self, signs: &Signs) -> Result<Angle, CoordinateParseError> {
+        let start = self.pos;
+        let mut angle = Angle {
+            thousandths: 0,
+            components: 0,
+            first_sign: None,
+        };
+
+        for (component, (unit, signs)) in UNITS.iter().zip(signs).enumerate() {
+            let (whole, decimals) = self.number()?;
+            if component > 0 && whole >= 60 {
+                return Err(CoordinateParseError::OutOfRange(start));
+            }
+            let decimals = decimals.map(|(digits, scale)| digits * unit / scale);
+            angle.thousandths = whole
+                .saturating_mul(*unit)
+                .saturating_add(decimals.unwrap_or(0))
+                .saturating_add(angle.thousandths);
+            angle.components += 1;
+
+            let separator = self.separator(signs, component < 2);
+            if let (0, Some(Separator::Sign(sign))) = (component, &separator) {
+                angle.first_sign = Some(*sign);
+            }
+            self.skip_spaces();
+            let more = separator.is_some() && self.peek().is_some_and(|c| c.is_ascii_digit());
+            if decimals.is_some() || !more {
+                break;
+            }
+        }
+
+        Ok(angle)
+    }
+
+    /// Parses a number and gets its whole part and its decimals with their
+    /// scale.
+    fn number(&mut self) -> Result<(u64, Option<(u64, u64)>), CoordinateParseError> {
+        let start = self.pos;
+        let whole = self.digits(u32::MAX).0;
+        if self.peek() != Some('.') {
+            return match whole {
+                Some(whole) => Ok((whole, None)),
+                None if self.peek().is_none() => Err(CoordinateParseError::UnexpectedEnd),
+                None => Err(CoordinateParseError::ExpectedNumber(start)),
+            };
+        }
+
+        self.bump();
+        match self.digits(MAX_DECIMALS) {
+            (Some(digits), scale) => Ok((whole.unwrap_or(0), Some((digits, scale)))),
+            (None, _) => Err(CoordinateParseError::ExpectedNumber(start)),
+        }
+    }
+
+    /// Parses decimal digits, the ones after the first `max` are skipped.
+    /// Gets the value, `None` without digits, and 10 to the number of digits
+    /// taken.
+    fn digits(&mut self, max: u32) -> (Option<u64>, u64) {
+        let mut value: Option<u64> = None;
+        let mut scale: u64 = 1;
+        let mut count = 0;
+        while let Some(digit) = self.peek().and_then(|c| c.to_digit(10)) {
+            self.bump();
+            if count < max {
+                // saturates, the range check rejects such values anyway
+                value = Some(
+                    value
+                        .unwrap_or(0)
+                        .saturating_mul(10)
+                        .saturating_add(digit as u64),
+                );
+                scale = scale.saturating_mul(10);
+                count += 1;
+            }
+        }
+        (value, scale)
+    }
+
+    /// Skips the sign or colon after a component.
+    fn separator(&mut self, signs: &[&str], colon: bool) -> Option<Separator> {
+        if let Some(index) = signs.iter().position(|sign| self.rest().starts_with(sign)) {
+            self.pos += signs[index].len();
+            return Some(Separator::Sign(index));
+        }
+
+        match self.peek()? {
+            ':' if colon => {
+                self.bump();
+                Some(Separator::Colon)
+            }
+            c if c.is_whitespace() => Some(Separator::Space),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    extern crate std;
+    use std::format;
+    use std::string::ToString;
+
+    fn arcsec(location: Location) -> (i32, i32) {
+        let (latitude, longitude) = location.to_dms();
+        (latitude.to_arcsec(), longitude.to_arcsec())
+    }
+
+    #[test]
+    fn accepted_formats() {
+        let zurich = (47 * 3600 + 12 * 60 + 34, 8 * 3600 + 32 * 60 + 7);
+        let table: &[(&str, (i32, i32))] = &[
+            ("47°12'34\"N 8°32'07\"E", zurich),
+            ("47 12 34 N, 8 32 07 E", zurich),
+            ("47:12:34N 8:32:7E", zurich),
+            ("N47°12'34\" E8°32'07\"", zurich),
+            ("N 47 12 34, E 8 32 07", zurich),
+            ("47° 12′ 34″ N; 8° 32′ 07″ E", zurich),
+            ("47°12'34''N 8°32'07''E", zurich),
+            ("  47°12'34\"n,8°32'07\"e  ", zurich),
+            ("8°32'07\"E 47°12'34\"N", zurich),
+            ("47°12.5667'N 8°32.1167'E", zurich),
+            ("47:12.5667 N 8:32.1167 E", zurich),
+            ("47.2094°N 8.5353°E", zurich),
+            (
+                "33°52'S 151°12'E",
+                (-(33 * 3600 + 52 * 60), 151 * 3600 + 12 * 60),
+            ),
+            ("S33 W70", (-33 * 3600, -70 * 3600)),
+            ("90°N 180°W", (90 * 3600, -180 * 3600)),
+        ];
+
+        for (input, expected) in table {
+            let location: Location = input.parse().unwrap_or_else(|e| {
+                panic!("{:?}: {}", input, e);
+            });
+            assert_eq!(arcsec(location), *expected, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn rejected_formats() {
+        let table: &[(&str, CoordinateParseError)] = &[
+            ("", CoordinateParseError::UnexpectedEnd),
+            ("47°12'34\"N", CoordinateParseError::UnexpectedEnd),
+            (
+                "47°12'34\" 8°32'07\"E",
+                CoordinateParseError::ExpectedHemisphere(11),
+            ),
+            ("47°12'34\"N 8°32'07\"N", CoordinateParseError::SameAxis),
+            (
+                "47°60'00\"N 8°32'07\"E",
+                CoordinateParseError::OutOfRange(0),
+            ),
+            ("91°N 8°E", CoordinateParseError::OutOfRange(0)),
+            ("47°N 180°00'01\"E", CoordinateParseError::OutOfRange(6)),
+            ("Nx 8°E", CoordinateParseError::ExpectedNumber(1)),
+            ("47.N 8°E", CoordinateParseError::ExpectedNumber(0)),
+            ("47.5°30'N 8°E", CoordinateParseError::ExpectedHemisphere(6)),
+            ("47°N 8°E 9", CoordinateParseError::UnexpectedCharacter(11)),
+        ];
+
+        for (input, expected) in table {
+            assert_eq!(
+                input.parse::<Location>().err(),
+                Some(*expected),
+                "{:?}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn ra_dec_formats() {
+        let m101 = (188.73625, 45.101944);
+        let table: &[(&str, (f32, f32))] = &[
+            ("12h34m56.7s +45°06'07\"", m101),
+            ("12h 34m 56.7s, 45° 06′ 07″", m101),
+            ("12:34:56.7 -05:06:07", (188.73625, -5.101944)),
+            ("12 34 56.7 −5 6 7", (188.73625, -5.101944)),
+            ("12:34.945 +45:06.1167", m101),
+            ("12.5824167h +45.101944°", m101),
+            ("188.73625 45.101944", m101),
+            ("188.73625°; +45.101944", m101),
+            ("23:59:59.9 +90", (359.9996, 90.0)),
+            ("0h +0", (0.0, 0.0)),
+            // a sign on a zero degree field, the classic
+            ("06:00:00 -00:30:00", (90.0, -0.5)),
+            ("6h -0 30", (90.0, -0.5)),
+            ("6h −0°00'30\"", (90.0, -0.008333)),
+            ("6h -0.5", (90.0, -0.5)),
+        ];
+
+        for (input, (ra, dec)) in table {
+            let position: RaDec = input.parse().unwrap_or_else(|e| {
+                panic!("{:?}: {}", input, e);
+            });
+            assert!(
+                (position.ra - ra).abs() < 1e-4,
+                "{:?}: {:?}",
+                input,
+                position
+            );
+            assert!(
+                (position.dec - dec).abs() < 1e-5,
+                "{:?}: {:?}",
+                input,
+                position
+            );
+        }
+
+        let position: RaDec = "00:00:00 -00:00:00".parse().unwrap();
+        assert_eq!(position.dec, 0.0);
+    }
+
+    #[test]
+    fn ra_dec_rejected() {
+        let table: &[(&str, CoordinateParseError)] = &[
+            ("", CoordinateParseError::UnexpectedEnd),
+            ("12:00:00", CoordinateParseError::UnexpectedEnd),
+            ("24:00:00 +10", CoordinateParseError::OutOfRange(0)),
+            ("24h +10", CoordinateParseError::OutOfRange(0)),
+            ("23:59:60 +10", CoordinateParseError::OutOfRange(0)),
+            ("12:60:00 +10", CoordinateParseError::OutOfRange(0)),
+            ("360 +10", CoordinateParseError::OutOfRange(0)),
+            ("12:00:00 +90:00:01", CoordinateParseError::OutOfRange(9)),
+            ("12:00:00 -91", CoordinateParseError::OutOfRange(9)),
+            ("-12:00:00 +10", CoordinateParseError::ExpectedNumber(0)),
+            ("12:00:00 +-10", CoordinateParseError::ExpectedNumber(10)),
+            (
+                "12:00:00 +10 x",
+                CoordinateParseError::UnexpectedCharacter(13),
+            ),
+            (
+                "12h30m +45°N",
+                CoordinateParseError::UnexpectedCharacter(12),
+            ),
+        ];
+
+        for (input, expected) in table {
+            assert_eq!(input.parse::<RaDec>().err(), Some(*expected), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn display_roundtrip() {
+        for (latitude, longitude) in [(47.2094, 8.5353), (-33.8688, -151.2093), (0.0, -0.0001)] {
+            let location = Location {
+                latitude,
+                longitude,
+            };
+            let parsed: Location = location.to_string().parse().unwrap();
+            assert_eq!(arcsec(parsed), arcsec(location), "{}", location);
+        }
+
+        let location: Location = "47°12'34\"N 8°32'07\"E".parse().unwrap();
+        assert_eq!(location.to_string(), "47°12'34\" N  008°32'07\" E");
+
+        // and the decimal degrees, which parse to whole seconds
+        let location = Location {
+            latitude: -33.8688,
+            longitude: -151.2093,
+        };
+        let parsed: Location = format!("{:#}", location).parse().unwrap();
+        assert!((parsed.latitude - location.latitude).abs() < 1.0 / 3600.0);
+        assert!((parsed.longitude - location.longitude).abs() < 1.0 / 3600.0);
+    }
+
+    #[test]
+    fn location_display() {
+        let location = |latitude, longitude| Location {
+            latitude,
+            longitude,
+        };
+
+        let munich = location(48.125, -11.5);
+        assert_eq!(munich.to_string(), "48°07'30\" N  011°30'00\" W");
+        assert_eq!(format!("{:#}", munich), "48.1250° N  11.5000° W");
+
+        let sydney = location(-33.86, 151.2111);
+        assert_eq!(sydney.to_string(), "33°51'36\" S  151°12'40\" E");
+        assert_eq!(format!("{:#.2}", sydney), "33.86° S  151.21° E");
+
+        assert_eq!(
+            location(0.0, 0.0).to_string(),
+            "00°00'00\" N  000°00'00\" E"
+        );
+        // negative angles which round to zero show the positive hemisphere
+        assert_eq!(
+            location(-0.0, -0.0).to_string(),
+            "00°00'00\" N  000°00'00\" E"
+        );
+        assert_eq!(
+            format!("{:#}", location(-0.0, -0.0)),
+            "0.0000° N  0.0000° E"
+        );
+        let near_zero = location(-0.00001, -0.0001);
+        assert_eq!(near_zero.to_string(), "00°00'00\" N  000°00'00\" E");
+        assert_eq!(format!("{:#}", near_zero), "0.0000° N  0.0001° W");
+        assert_eq!(format!("{:#.3}", near_zero), "0.000° N  0.000° E");
+        assert_eq!(
+            location(-0.0002, 0.0).to_string(),
+            "00°00'01\" S  000°00'00\" E"
+        );
+
+        // rounded seconds carry into the minutes instead of showing 60
+        let carried = location(5.0 + 59.0 / 60.0 + 59.6 / 3600.0, 179.0 + 59.9 / 3600.0);
+        assert_eq!(carried.to_string(), "06°00'00\" N  179°01'00\" E");
+        let kept = location(5.0 + 59.0 / 60.0 + 59.4 / 3600.0, -(9.0 + 59.0 / 60.0));
+        assert_eq!(kept.to_string(), "05°59'59\" N  009°59'00\" W");
+    }
+}
@@ -0,0 +1,117 @@
+//! Driver shared between tasks
+//!
+//! [`SharedNexStar`] guards a [`NexStar`] with a busy flag, so several tasks
+//! (or interrupt handlers) can use one link without wrapping it themselves:
+//!
+//! ```ignore
+//! let mount = SharedNexStar::new(NexStar::new(rx, tx));
+//!
+//! // in any task holding a reference
+//! let position = mount.lock(|nexstar| nexstar.precise_ra_dec())?;
+//!
+//! // in an interrupt handler, which must not wait for the task it preempted
+//! if let Ok(result) = mount.try_lock(|nexstar| nexstar.keepalive()) {
+//!     // ...
+//! }
+//! ```
+//!
+//! Every call of [`SharedNexStar::lock`] holds the flag for the whole closure,
+//! so the commands of different tasks are never interleaved, not even within
+//! a helper sending several commands like [`NexStar::connect`]. A critical
+//! section only guards setting and clearing the flag: interrupts stay enabled
+//! while the closure waits for the responses, so an interrupt fed receiver
+//! like [`RxBuffer`](crate::rx_buffer::RxBuffer) keeps receiving them.
+//!
+//! [`SharedNexStar::lock`] spins while another context holds the flag, which
+//! never ends if that context is the one the caller preempted. Interrupt
+//! handlers therefore use [`SharedNexStar::try_lock`], which fails with
+//! [`nb::Error::WouldBlock`] instead.
+
+use core::cell::{Cell, UnsafeCell};
+use core::convert::Infallible;
+
+use critical_section::Mutex;
+use embedded_hal::serial;
+
+use crate::NexStar;
+
+/// [`NexStar`] usable from several tasks, see the [module docs](self)
+pub struct SharedNexStar<T, U>
+where
+    T: serial::Read<u8>,
+    U: serial::Write<u8>,
+{
+    busy: Mutex<Cell<bool>>,
+    nexstar: UnsafeCell<NexStar<T, U>>,
+}
+
+// SAFETY: the driver is only accessed by the context holding the busy flag
+unsafe impl<T, U> Sync for SharedNexStar<T, U>
+where
+    T: serial::Read<u8>,
+    U: serial::Write<u8>,
+    NexStar<T, U>: Send,
+{
+}
+
+impl<T, U> SharedNexStar<T, U>
+where
+    T: serial::Read<u8>,
+    U: serial::Write<u8>,
+{
+    pub const fn new(nexstar: NexStar<T, U>) -> Self {
+        SharedNexStar {
+            busy: Mutex::new(Cell::new(false)),
+            nexstar: UnsafeCell::new(nexstar),
+        }
+    }
+
+    /// Runs `f` with exclusive access to the driver, waiting for other
+    /// contexts to finish first.
+    ///
+    /// Never returns if called again from within `f` or from an interrupt
+    /// which preempted a holder, use [`SharedNexStar::try_lock`] there.
+    pub fn lock<R>(&self, f: impl FnOnce(&mut NexStar<T, U>) -> R) -> R {
+        while !self.acquire() {
+            core::hint::spin_loop();
+        }
+        self.run(f)
+    }
+
+    /// Runs `f` with exclusive access to the driver, or fails with
+    /// [`nb::Error::WouldBlock`] right away if another context holds it.
+    pub fn try_lock<R>(
+        &self,
+        f: impl FnOnce(&mut NexStar<T, U>) -> R,
+    ) -> nb::Result<R, Infallible> {
+        if !self.acquire() {
+            return Err(nb::Error::WouldBlock);
+        }
+        Ok(self.run(f))
+    }
+
+    pub fn into_inner(self) -> NexStar<T, U> {
+        self.nexstar.into_inner()
+    }
+
+    /// Sets the busy flag, `false` if it was set already.
+    fn acquire(&self) -> bool {
+        critical_section::with(|cs| !self.busy.borrow(cs).replace(true))
+    }
+
+    /// Runs `f` on the driver and clears the busy flag, even if `f` panics.
+    fn run<R>(&self, f: impl FnOnce(&mut NexStar<T, U>) -> R) -> R {
+        let _release = Release(&self.busy);
+        // SAFETY: the caller set the busy flag, which grants exclusive access
+        f(unsafe { &mut *self.nexstar.get() })
+    }
+}
+
+/// Clears the busy flag when dropped
+struct Release<'a>(&'a Mutex<Cell<bool>>);
+
+impl Drop for Release<'_> {
+    fn drop(&mut self) {
+        critical_section::with(|cs| self.0.borrow(cs).set(false));
+    }
+}
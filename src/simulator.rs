@@ -0,0 +1,916 @@
+//! Simulated Hand Controller (HC)
+//!
+//! [`SimulatedHc`] answers the commands of the driver from a fixed internal
+//! state, so applications can be tested without a mount on the desk. The
+//! simulator implements the serial traits on shared references, which makes it
+//! both halves of the transport:
+//!
+//! ```
+//! use nexstar::simulator::SimulatedHc;
+//! use nexstar::{NexStar, RaDec};
+//!
+//! let hc = SimulatedHc::new();
+//! let mut nexstar = NexStar::new(&hc, &hc);
+//!
+//! nexstar.connect().unwrap();
+//! nexstar.goto_ra_dec(RaDec { ra: 10.0, dec: 20.0 }).unwrap();
+//! while nexstar.is_goto_in_progress().unwrap() {}
+//! ```
+//!
+//! The sky isn't modelled: RA/Dec and Azm/Alt both are the positions of the
+//! two axes. During a GOTO every command received moves the axes by the slew
+//! step towards the target.
+//!
+//! Time only matters to the variable rate slews, the drift and the periodic
+//! error, which move the axes when the simulator is used as the delay of the
+//! driver or [`SimulatedHc::advance`] is called.
+
+use core::cell::RefCell;
+use core::convert::Infallible;
+use core::f64::consts::PI;
+
+use embedded_hal::blocking::delay::DelayMs;
+use embedded_hal::serial;
+use libm::sin;
+
+use crate::aux::{Packet, MAX_DATA, MAX_FRAME, PREAMBLE};
+use crate::command::{
+    arcsec_to_fraction, decode_fraction, degrees_to_fraction, encode_fraction, fraction_to_degrees,
+    position_bytes,
+};
+use crate::detect::ProtocolKind;
+use crate::parse;
+use crate::{
+    DateTime, Device, FocuserLimits, GpsFix, Location, Model, MotorModel, TrackingMode,
+    UtcDateTime, Version,
+};
+
+/// Longest command, an AUX frame (a precise GOTO or sync has 18 bytes)
+const MAX_COMMAND: usize = MAX_FRAME;
+/// Responses queued but not read yet
+const MAX_RESPONSE: usize = 32;
+/// Longest pass-through response answered
+const MAX_PASS_THROUGH: usize = 8;
+/// Maximum slew rate of the motors after power-on, 4°/s in quarter arcsec/s
+const DEFAULT_MAX_RATE: u16 = 57_600;
+/// Autoguide rate of the motors after a reset, half the sidereal rate
+const DEFAULT_AUTOGUIDE_RATE: u8 = 128;
+/// Answer to a byte received at the wrong baud rate
+const GARBAGE: u8 = 0xF8;
+/// Position and time of a GPS unit without a fix
+const NO_FIX: GpsFix = GpsFix {
+    latitude: 0,
+    longitude: 0,
+    utc: UtcDateTime {
+        year: 0,
+        month: 0,
+        day: 0,
+        hour: 0,
+        minutes: 0,
+        seconds: 0,
+    },
+};
+
+struct State {
+    protocol: ProtocolKind,
+    /// Baud rate of the mount and of the port
+    baud: (u32, u32),
+    version: Version,
+    model: Model,
+    azm_ra_version: Option<Version>,
+    alt_dec_version: Option<Version>,
+    motor_models: [MotorModel; 2],
+    gps_version: Option<Version>,
+    focuser_version: Option<Version>,
+    wifi_version: Option<Version>,
+    battery_version: Option<Version>,
+    /// Battery voltage in mV
+    battery_voltage: u32,
+    /// Limits a calibration finds and the polls it takes, `None` never
+    /// finishes
+    focuser_calibration: (FocuserLimits, Option<u32>),
+    /// Polls left until the running calibration finishes
+    focuser_calibrating: Option<Option<u32>>,
+    focuser_limits: FocuserLimits,
+    focuser_moving: bool,
+    gps_fix: Option<GpsFix>,
+    gps_polls: u32,
+    /// Maximum slew rates of the motors in quarter arcsec/s
+    max_rates: [u16; 2],
+    /// Autoguide rates of the motors in 1/256 of the sidereal rate
+    autoguide_rates: [u8; 2],
+    /// Positive and negative backlash correction of the motors
+    backlash: [[u8; 2]; 2],
+    /// Variable slew or guide rates of the motors in 1/1024 arcsec/s
+    rates: [i32; 2],
+    /// Factors of the rates the motors actually move at
+    rate_scales: [f32; 2],
+    /// Play between the motors and the axes in arcsec
+    play: [f32; 2],
+    /// Offsets of the motors from the middle of the play in arcsec
+    slack: [f32; 2],
+    /// Drift of the axes in arcsec/s on top of the slew rates
+    drift: [f32; 2],
+    /// Amplitude in arcsec and period in ms of the periodic error of the
+    /// first axis
+    periodic_error: (f32, u32),
+    /// Time passed in ms
+    elapsed: u64,
+    /// Queries after which the index switches are found, `None` without one
+    index_switches: [Option<u32>; 2],
+    /// Queries left until the switch of a seeking motor is found
+    seeks: [Option<u32>; 2],
+    location: Location,
+    datetime: DateTime,
+    axes: (f32, f32),
+    target: Option<(f32, f32)>,
+    slew_step: f32,
+    tracking_mode: TrackingMode,
+    aligned: bool,
+    /// Polls left until the alignment completes
+    alignment_polls: u32,
+    command: [u8; MAX_COMMAND],
+    received: usize,
+    response: [u8; MAX_RESPONSE],
+    start: usize,
+    end: usize,
+}
+
+/// Simulated Hand Controller (HC)
+///
+/// Defaults to an aligned 6/8 SE with HC firmware 4.21, both motors present,
+/// no GPS unit and the axes at 0/0.
+pub struct SimulatedHc {
+    state: RefCell<State>,
+}
+
+impl SimulatedHc {
+    pub fn new() -> SimulatedHc {
+        let version = Version::new(7, 11);
+        SimulatedHc {
+            state: RefCell::new(State {
+                protocol: ProtocolKind::Hc,
+                baud: (9600, 9600),
+                version: Version::new(4, 21),
+                model: Model::Se6_8,
+                azm_ra_version: Some(version),
+                alt_dec_version: Some(version),
+                motor_models: [MotorModel::Gen2; 2],
+                gps_version: None,
+                focuser_version: None,
+                wifi_version: None,
+                battery_version: None,
+                battery_voltage: 13_300,
+                focuser_calibration: (FocuserLimits { low: 0, high: 0 }, Some(0)),
+                focuser_calibrating: None,
+                focuser_limits: FocuserLimits { low: 0, high: 0 },
+                focuser_moving: false,
+                gps_fix: None,
+                gps_polls: 0,
+                max_rates: [DEFAULT_MAX_RATE; 2],
+                autoguide_rates: [DEFAULT_AUTOGUIDE_RATE; 2],
+                backlash: [[0; 2]; 2],
+                rates: [0; 2],
+                rate_scales: [1.0; 2],
+                play: [0.0; 2],
+                slack: [0.0; 2],
+                drift: [0.0; 2],
+                periodic_error: (0.0, 0),
+                elapsed: 0,
+                index_switches: [None; 2],
+                seeks: [None; 2],
+                location: Location {
+                    latitude: 0.0,
+                    longitude: 0.0,
+                },
+                datetime: DateTime {
+                    hour: 0,
+                    minutes: 0,
+                    seconds: 0,
+                    zone: 0,
+                    daylight_saving: false,
+                    year: 0,
+                    month: 1,
+                    day: 1,
+                },
+                axes: (0.0, 0.0),
+                target: None,
+                slew_step: 5.0,
+                tracking_mode: TrackingMode::Off,
+                aligned: true,
+                alignment_polls: 0,
+                command: [0; MAX_COMMAND],
+                received: 0,
+                response: [0; MAX_RESPONSE],
+                start: 0,
+                end: 0,
+            }),
+        }
+    }
+
+    /// Sets the dialect the simulated port speaks, [`ProtocolKind::Hc`] by
+    /// default.
+    ///
+    /// With [`ProtocolKind::Aux`] it answers the version queries of the
+    /// present devices in AUX frames and ignores everything else, with
+    /// [`ProtocolKind::Neither`] it never answers.
+    pub fn set_protocol(&self, protocol: ProtocolKind) {
+        let mut state = self.state.borrow_mut();
+        state.protocol = protocol;
+        state.received = 0;
+    }
+
+    /// Sets the baud rate the mount runs at, 9600 by default.
+    ///
+    /// Bytes written while the port runs at another rate, see
+    /// [`SimulatedHc::open`], arrive garbled and are answered with garbage.
+    pub fn set_baud(&self, baud: u32) {
+        self.state.borrow_mut().baud.0 = baud;
+    }
+
+    /// Reopens the port at the baud rate, which discards pending bytes.
+    pub fn open(&self, baud: u32) -> (&SimulatedHc, &SimulatedHc) {
+        let mut state = self.state.borrow_mut();
+        state.baud.1 = baud;
+        state.received = 0;
+        state.start = state.end;
+        (self, self)
+    }
+
+    /// Sets the HC firmware version.
+    pub fn set_version(&self, version: Version) {
+        self.state.borrow_mut().version = version;
+    }
+
+    pub fn set_model(&self, model: Model) {
+        self.state.borrow_mut().model = model;
+    }
+
+    /// Sets the firmware version of a motor, the focuser, the GPS unit, the
+    /// WiFi module or the battery, `None` removes the device. Other devices
+    /// are never present.
+    pub fn set_device(&self, device: Device, version: Option<Version>) {
+        let mut state = self.state.borrow_mut();
+        match device {
+            Device::AzmRaMotor => state.azm_ra_version = version,
+            Device::AltDecMotor => state.alt_dec_version = version,
+            Device::Focuser => state.focuser_version = version,
+            Device::GPSUnit => state.gps_version = version,
+            Device::WiFi => state.wifi_version = version,
+            Device::Battery => state.battery_version = version,
+            _ => {}
+        }
+    }
+
+    /// Sets the limits a calibration of the focuser finds once it was asked
+    /// `polls` times whether it finished, `None` never finishes.
+    ///
+    /// The focuser must be present, see [`SimulatedHc::set_device`].
+    pub fn set_focuser_calibration(&self, limits: FocuserLimits, polls: Option<u32>) {
+        self.state.borrow_mut().focuser_calibration = (limits, polls);
+    }
+
+    /// Lets the focuser report that it is moving, e.g. during a GOTO.
+    pub fn set_focuser_moving(&self, moving: bool) {
+        self.state.borrow_mut().focuser_moving = moving;
+    }
+
+    /// Sets the battery voltage in mV (13300 by default).
+    ///
+    /// The battery must be present, see [`SimulatedHc::set_device`].
+    pub fn set_battery_voltage(&self, millivolts: u32) {
+        self.state.borrow_mut().battery_voltage = millivolts;
+    }
+
+    /// Sets the board variant of a motor, [`MotorModel::Gen2`] by default.
+    pub fn set_motor_model(&self, device: Device, model: MotorModel) {
+        let mut state = self.state.borrow_mut();
+        match device {
+            Device::AzmRaMotor => state.motor_models[0] = model,
+            Device::AltDecMotor => state.motor_models[1] = model,
+            _ => {}
+        }
+    }
+
+    /// Sets the fix the GPS unit reports once it was asked `polls` more times
+    /// whether it is linked, `None` never gets a fix.
+    ///
+    /// The GPS unit must be present, see [`SimulatedHc::set_device`].
+    pub fn set_gps_fix(&self, fix: Option<GpsFix>, polls: u32) {
+        let mut state = self.state.borrow_mut();
+        state.gps_fix = fix;
+        state.gps_polls = polls;
+    }
+
+    /// Gives the motor an index and level switch, which a seek finds after
+    /// the motor was asked `polls` times whether it found it. Without a
+    /// switch (`None`, the default) the motor rejects the homing commands.
+    pub fn set_index_switch(&self, device: Device, polls: Option<u32>) {
+        let mut state = self.state.borrow_mut();
+        match device {
+            Device::AzmRaMotor => state.index_switches[0] = polls,
+            Device::AltDecMotor => state.index_switches[1] = polls,
+            _ => {}
+        }
+    }
+
+    /// Sets the degrees the axes move per command received during a GOTO.
+    pub fn set_slew_step(&self, degrees: f32) {
+        self.state.borrow_mut().slew_step = degrees;
+    }
+
+    /// Sets the factor of the commanded variable rates the motor actually
+    /// moves at, e.g. 0.9 for a motor 10 % slower than commanded.
+    pub fn set_rate_scale(&self, device: Device, scale: f32) {
+        let mut state = self.state.borrow_mut();
+        match device {
+            Device::AzmRaMotor => state.rate_scales[0] = scale,
+            Device::AltDecMotor => state.rate_scales[1] = scale,
+            _ => {}
+        }
+    }
+
+    /// Sets the play in arcsec between a motor and its axis, which the motor
+    /// has to take up when reversing before the axis moves.
+    pub fn set_play(&self, device: Device, arcsec: f32) {
+        let mut state = self.state.borrow_mut();
+        match device {
+            Device::AzmRaMotor => state.play[0] = arcsec,
+            Device::AltDecMotor => state.play[1] = arcsec,
+            _ => {}
+        }
+    }
+
+    /// Lets the axes drift in arcsec/s, e.g. like a mount tracking with a
+    /// polar alignment error.
+    pub fn set_drift(&self, first: f32, second: f32) {
+        self.state.borrow_mut().drift = [first, second];
+    }
+
+    /// Adds a sinusoidal periodic error of the amplitude in arcsec to the
+    /// first axis, e.g. like the worm gear of the RA axis, a zero period
+    /// removes it.
+    pub fn set_periodic_error(&self, amplitude: f32, period_ms: u32) {
+        self.state.borrow_mut().periodic_error = (amplitude, period_ms);
+    }
+
+    /// Lets `ms` pass, moving the axes at their variable slew rates, drift
+    /// and periodic error.
+    pub fn advance(&self, ms: u32) {
+        let mut state = self.state.borrow_mut();
+        let seconds = ms as f32 / 1000.0;
+        let mut moved = [0.0; 2];
+        for (axis, moved) in moved.iter_mut().enumerate() {
+            let motor = state.rates[axis] as f32 / 1024.0 * state.rate_scales[axis] * seconds;
+            // the axis follows the motor once the play is taken up
+            let half = state.play[axis] / 2.0;
+            let slack = state.slack[axis] + motor;
+            state.slack[axis] = slack.clamp(-half, half);
+            *moved = (slack - state.slack[axis] + state.drift[axis] * seconds) / 3600.0;
+        }
+        let [first, second] = moved;
+
+        let (amplitude, period_ms) = state.periodic_error;
+        let error = |elapsed: u64| match period_ms {
+            0 => 0.0,
+            _ => {
+                let phase = (elapsed % period_ms as u64) as f64 / period_ms as f64;
+                amplitude as f64 * sin(2.0 * PI * phase)
+            }
+        };
+        let first =
+            first + ((error(state.elapsed + ms as u64) - error(state.elapsed)) / 3600.0) as f32;
+        state.elapsed += ms as u64;
+
+        let first = (state.axes.0 + first) % 360.0;
+        state.axes.0 = if first < 0.0 { first + 360.0 } else { first };
+        state.axes.1 += second;
+    }
+
+    pub fn set_aligned(&self, aligned: bool) {
+        let mut state = self.state.borrow_mut();
+        state.aligned = aligned;
+        state.alignment_polls = 0;
+    }
+
+    /// Completes the alignment once it was asked `polls` more times whether
+    /// it is complete, like a user finishing it on the HC.
+    pub fn set_aligned_after(&self, polls: u32) {
+        let mut state = self.state.borrow_mut();
+        state.aligned = true;
+        state.alignment_polls = polls;
+    }
+
+    /// Gets the positions of the two axes in degrees.
+    pub fn axes(&self) -> (f32, f32) {
+        self.state.borrow().axes
+    }
+
+    /// Moves the axes to the positions in degrees, cancelling any GOTO.
+    pub fn set_axes(&self, first: f32, second: f32) {
+        let mut state = self.state.borrow_mut();
+        state.axes = (first, second);
+        state.target = None;
+    }
+
+    pub fn location(&self) -> Location {
+        self.state.borrow().location
+    }
+
+    pub fn datetime(&self) -> DateTime {
+        self.state.borrow().datetime
+    }
+
+    pub fn tracking_mode(&self) -> TrackingMode {
+        self.state.borrow().tracking_mode
+    }
+
+    pub fn is_goto_in_progress(&self) -> bool {
+        self.state.borrow().target.is_some()
+    }
+}
+
+impl Default for SimulatedHc {
+    fn default() -> SimulatedHc {
+        SimulatedHc::new()
+    }
+}
+
+/// Waiting lets the time pass, see [`SimulatedHc::advance`].
+impl DelayMs<u32> for &SimulatedHc {
+    fn delay_ms(&mut self, ms: u32) {
+        self.advance(ms);
+    }
+}
+
+impl serial::Read<u8> for &SimulatedHc {
+    type Error = Infallible;
+
+    fn read(&mut self) -> nb::Result<u8, Infallible> {
+        let mut state = self.state.borrow_mut();
+        if state.start == state.end {
+            return Err(nb::Error::WouldBlock);
+        }
+
+        let byte = state.response[state.start];
+        state.start += 1;
+        Ok(byte)
+    }
+}
+
+impl serial::Write<u8> for &SimulatedHc {
+    type Error = Infallible;
+
+    fn write(&mut self, word: u8) -> nb::Result<(), Infallible> {
+        self.state.borrow_mut().receive(word);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> nb::Result<(), Infallible> {
+        Ok(())
+    }
+}
+
+impl State {
+    fn receive(&mut self, byte: u8) {
+        if self.baud.0 != self.baud.1 {
+            self.respond(&[GARBAGE]);
+            return;
+        }
+
+        match self.protocol {
+            ProtocolKind::Hc => self.receive_hc(byte),
+            ProtocolKind::Aux => self.receive_aux(byte),
+            ProtocolKind::Neither => {}
+        }
+    }
+
+    fn receive_hc(&mut self, byte: u8) {
+        self.command[self.received] = byte;
+        self.received += 1;
+
+        match command_len(self.command[0]) {
+            // unknown commands are ignored like on the HC
+            None => self.received = 0,
+            Some(len) if self.received == len => {
+                self.received = 0;
+                self.slew();
+                self.execute(len);
+            }
+            Some(_) => {}
+        }
+    }
+
+    /// Collects an AUX frame, bytes before the preamble are ignored.
+    fn receive_aux(&mut self, byte: u8) {
+        if self.received == 0 && byte != PREAMBLE {
+            return;
+        }
+        self.command[self.received] = byte;
+        self.received += 1;
+
+        let len = self.command[1] as usize + 3;
+        if self.received == 2 && !(3..=MAX_DATA + 3).contains(&(byte as usize)) {
+            self.received = 0;
+        } else if self.received > 2 && self.received == len {
+            self.received = 0;
+            if let Ok(packet) = Packet::decode(&self.command[..len]) {
+                self.execute_aux(packet);
+            }
+        }
+    }
+
+    fn execute_aux(&mut self, request: Packet) {
+        if let (Some(version), 0xFE) = (self.device_version(request.dst), request.cmd) {
+            let data = [version.major, version.minor];
+            if let Some(packet) = Packet::new(request.dst, request.src, request.cmd, &data) {
+                let mut frame = [0u8; MAX_FRAME];
+                let len = packet.encode(&mut frame);
+                self.respond(&frame[..len]);
+            }
+        }
+    }
+
+    fn execute(&mut self, len: usize) {
+        let command = self.command;
+        let args = &command[1..len];
+
+        match command[0] {
+            b'K' => self.respond(&[args[0], b'#']),
+            b'E' | b'Z' => self.respond_axes(false),
+            b'e' | b'z' => self.respond_axes(true),
+            b'R' | b'r' | b'B' | b'b' => match decode_axes(args) {
+                Some(target) => {
+                    self.target = Some(target);
+                    self.respond(b"#");
+                }
+                None => self.error(0),
+            },
+            b'S' | b's' => match decode_axes(args) {
+                Some(axes) => {
+                    self.axes = axes;
+                    self.target = None;
+                    self.respond(b"#");
+                }
+                None => self.error(0),
+            },
+            b'M' => {
+                self.target = None;
+                self.respond(b"#");
+            }
+            b'L' => {
+                let active = if self.target.is_some() { b'1' } else { b'0' };
+                self.respond(&[active, b'#']);
+            }
+            b't' => self.respond(&[self.tracking_mode.bits(), b'#']),
+            b'T' => match TrackingMode::from_bits(args[0]) {
+                Some(mode) => {
+                    self.tracking_mode = mode;
+                    self.respond(b"#");
+                }
+                None => self.error(0),
+            },
+            b'w' => {
+                let mut response = [b'#'; 9];
+                response[..8].copy_from_slice(&self.location.to_bytes());
+                self.respond(&response);
+            }
+            b'W' => match parse::location(args) {
+                Some(location) => {
+                    self.location = location;
+                    self.respond(b"#");
+                }
+                None => self.error(0),
+            },
+            b'h' => {
+                let mut response = [b'#'; 9];
+                response[..8].copy_from_slice(&self.datetime.to_bytes());
+                self.respond(&response);
+            }
+            b'H' => {
+                let mut bytes = [0u8; 8];
+                bytes.copy_from_slice(args);
+                match DateTime::from_bytes(&bytes) {
+                    Some(datetime) => {
+                        self.datetime = datetime;
+                        self.respond(b"#");
+                    }
+                    None => self.error(0),
+                }
+            }
+            b'V' => self.respond(&[self.version.major, self.version.minor, b'#']),
+            b'm' => self.respond(&[self.model.bits(), b'#']),
+            b'J' => {
+                let aligned = self.aligned && self.alignment_polls == 0;
+                self.alignment_polls = self.alignment_polls.saturating_sub(1);
+                self.respond(&[aligned as u8, b'#']);
+            }
+            b'P' => self.pass_through(args),
+            _ => {}
+        }
+    }
+
+    /// Answers `[len, device, command, data.., response length]`.
+    fn pass_through(&mut self, args: &[u8]) {
+        let (device, command) = (args[1], args[2]);
+        let response_len = (args[6] as usize).min(MAX_PASS_THROUGH);
+
+        let version = self.device_version(device);
+        let motor = device == Device::AzmRaMotor as u8 || device == Device::AltDecMotor as u8;
+        let gps = device == Device::GPSUnit as u8;
+
+        match (version, command) {
+            (Some(version), 0xFE) => {
+                self.respond_data(&[version.major, version.minor], response_len)
+            }
+            (Some(_), 0x05) if motor => {
+                let axis = (device - Device::AzmRaMotor as u8) as usize;
+                let model = self.motor_models[axis].bits();
+                self.respond_data(&[model], response_len);
+            }
+            (Some(_), 0x01) if motor => {
+                let axis = (device - Device::AzmRaMotor as u8) as usize;
+                let degrees = if axis == 0 { self.axes.0 } else { self.axes.1 };
+                self.respond_data(
+                    &position_bytes(degrees_to_fraction(degrees, 24)),
+                    response_len,
+                );
+            }
+            (Some(_), 0x04) if motor => {
+                let fraction = u32::from_be_bytes([0, args[3], args[4], args[5]]);
+                // the second axis is signed like a declination
+                if device == Device::AzmRaMotor as u8 {
+                    self.axes.0 = fraction_to_degrees(fraction, 24, false);
+                } else {
+                    self.axes.1 = fraction_to_degrees(fraction, 24, true);
+                }
+                self.target = None;
+                self.respond(b"#");
+            }
+            // variable rate slews in quarter arcsec/s, or guide rates in
+            // 1/1024 arcsec/s
+            (Some(_), 0x06) | (Some(_), 0x07) if motor => {
+                let axis = (device - Device::AzmRaMotor as u8) as usize;
+                let rate = match args[0] {
+                    4 => u32::from_be_bytes([0, args[3], args[4], args[5]]) as i32,
+                    _ => (u16::from_be_bytes([args[3], args[4]]) as i32) << 8,
+                };
+                self.rates[axis] = if command == 0x06 { rate } else { -rate };
+                self.respond(b"#");
+            }
+            // enabling the maximum rate
+            (Some(_), 0x22) if motor => self.respond(b"#"),
+            (Some(_), 0x20) if motor => {
+                let axis = (device - Device::AzmRaMotor as u8) as usize;
+                self.max_rates[axis] = u16::from_be_bytes([args[3], args[4]]);
+                self.respond(b"#");
+            }
+            (Some(_), 0x21) if motor => {
+                let axis = (device - Device::AzmRaMotor as u8) as usize;
+                self.respond_data(&self.max_rates[axis].to_be_bytes(), response_len);
+            }
+            // positive and negative backlash
+            (Some(_), 0x10) | (Some(_), 0x11) if motor => {
+                let axis = (device - Device::AzmRaMotor as u8) as usize;
+                self.backlash[axis][(command - 0x10) as usize] = args[3];
+                self.respond(b"#");
+            }
+            (Some(_), 0x40) | (Some(_), 0x41) if motor => {
+                let axis = (device - Device::AzmRaMotor as u8) as usize;
+                let backlash = self.backlash[axis][(command - 0x40) as usize];
+                self.respond_data(&[backlash], response_len);
+            }
+            (Some(_), 0x46) if motor => {
+                let axis = (device - Device::AzmRaMotor as u8) as usize;
+                self.autoguide_rates[axis] = args[3];
+                self.respond(b"#");
+            }
+            (Some(_), 0x47) if motor => {
+                let axis = (device - Device::AzmRaMotor as u8) as usize;
+                self.respond_data(&[self.autoguide_rates[axis]], response_len);
+            }
+            // level start and seek index
+            (Some(_), 0x0B) | (Some(_), 0x19) if motor => {
+                let axis = (device - Device::AzmRaMotor as u8) as usize;
+                match self.index_switches[axis] {
+                    Some(polls) => {
+                        self.seeks[axis] = Some(polls);
+                        self.respond(b"#");
+                    }
+                    None => self.error(response_len),
+                }
+            }
+            // level done and at index
+            (Some(_), 0x12) | (Some(_), 0x18) if motor => {
+                let axis = (device - Device::AzmRaMotor as u8) as usize;
+                if self.index_switches[axis].is_none() {
+                    return self.error(response_len);
+                }
+                let found = self.seeks[axis] == Some(0);
+                self.seeks[axis] = self.seeks[axis].map(|polls| polls.saturating_sub(1));
+                self.respond_data(&[if found { 0xFF } else { 0x00 }], response_len);
+            }
+            (Some(_), command) if device == Device::Focuser as u8 => {
+                self.focuser(command, response_len)
+            }
+            (Some(_), command) if gps => self.gps(command, response_len),
+            // the simulated module acks before it restarts
+            (Some(_), 0x49) if device == Device::WiFi as u8 => self.respond(b"#"),
+            (Some(_), 0x10) if device == Device::Battery as u8 => {
+                let [v0, v1, v2, v3] = (self.battery_voltage * 1000).to_be_bytes();
+                self.respond_data(&[0, 0, v0, v1, v2, v3], response_len);
+            }
+            _ => self.error(response_len),
+        }
+    }
+
+    /// Gets the firmware version of the device, `None` if it isn't present.
+    fn device_version(&self, device: u8) -> Option<Version> {
+        match Device::from_bits(device) {
+            Some(Device::AzmRaMotor) => self.azm_ra_version,
+            Some(Device::AltDecMotor) => self.alt_dec_version,
+            Some(Device::Focuser) => self.focuser_version,
+            Some(Device::GPSUnit) => self.gps_version,
+            Some(Device::WiFi) => self.wifi_version,
+            Some(Device::Battery) => self.battery_version,
+            _ => None,
+        }
+    }
+
+    /// Answers the commands of a present focuser.
+    fn focuser(&mut self, command: u8, response_len: usize) {
+        match command {
+            // slew done
+            0x13 => {
+                let moving = self.focuser_moving || self.focuser_calibrating.is_some();
+                self.respond_data(&[if moving { 0x00 } else { 0xFF }], response_len);
+            }
+            // start the calibration
+            0x2A => {
+                self.focuser_calibrating = Some(self.focuser_calibration.1);
+                self.respond(b"#");
+            }
+            // calibration done and its state
+            0x2B => {
+                match self.focuser_calibrating {
+                    Some(Some(0)) => {
+                        self.focuser_calibrating = None;
+                        self.focuser_limits = self.focuser_calibration.0;
+                    }
+                    Some(polls) => {
+                        self.focuser_calibrating = Some(polls.map(|polls| polls - 1));
+                    }
+                    None => {}
+                }
+                let done = self.focuser_calibrating.is_none();
+                self.respond_data(&[if done { 0xFF } else { 0x00 }, 0], response_len);
+            }
+            0x2C => {
+                let FocuserLimits { low, high } = self.focuser_limits;
+                let [l0, l1, l2, l3] = low.to_be_bytes();
+                let [h0, h1, h2, h3] = high.to_be_bytes();
+                self.respond_data(&[l0, l1, l2, l3, h0, h1, h2, h3], response_len);
+            }
+            _ => self.error(response_len),
+        }
+    }
+
+    /// Answers the queries of a present GPS unit, with zeros without a fix.
+    fn gps(&mut self, command: u8, response_len: usize) {
+        // link state
+        if command == 0x37 {
+            let linked = self.gps_fix.is_some() && self.gps_polls == 0;
+            self.gps_polls = self.gps_polls.saturating_sub(1);
+            self.respond_data(&[linked as u8], response_len);
+            return;
+        }
+
+        let fix = match self.gps_fix {
+            Some(fix) if self.gps_polls == 0 => fix,
+            _ => NO_FIX,
+        };
+        let angle = |arcsec| {
+            let [_, high, mid, low] = arcsec_to_fraction(arcsec, 24).to_be_bytes();
+            [high, mid, low]
+        };
+
+        match command {
+            0x01 => self.respond_data(&angle(fix.latitude), response_len),
+            0x02 => self.respond_data(&angle(fix.longitude), response_len),
+            0x03 => self.respond_data(&[fix.utc.month, fix.utc.day], response_len),
+            0x04 => self.respond_data(&fix.utc.year.to_be_bytes(), response_len),
+            0x33 => {
+                let utc = fix.utc;
+                self.respond_data(&[utc.hour, utc.minutes, utc.seconds], response_len)
+            }
+            _ => self.error(response_len),
+        }
+    }
+
+    /// Answers with the data, zero padded or cut to `response_len`.
+    fn respond_data(&mut self, data: &[u8], response_len: usize) {
+        let mut response = [0u8; MAX_PASS_THROUGH + 1];
+        let len = data.len().min(response_len);
+        response[..len].copy_from_slice(&data[..len]);
+        response[response_len] = b'#';
+        self.respond(&response[..=response_len]);
+    }
+
+    fn respond_axes(&mut self, precise: bool) {
+        let mut first = [0u8; 8];
+        let mut second = [0u8; 8];
+        let first = encode_fraction(self.axes.0, precise, &mut first);
+        let second = encode_fraction(self.axes.1, precise, &mut second);
+
+        let mut response = [0u8; MAX_COMMAND];
+        let len = first.len();
+        response[..len].copy_from_slice(first);
+        response[len] = b',';
+        response[len + 1..len * 2 + 1].copy_from_slice(second);
+        response[len * 2 + 1] = b'#';
+        self.respond(&response[..len * 2 + 2]);
+    }
+
+    /// Answers like an HC reporting an error: the expected number of bytes
+    /// followed by an error code and the ack.
+    fn error(&mut self, response_len: usize) {
+        let mut response = [0u8; MAX_PASS_THROUGH + 2];
+        response[response_len + 1] = b'#';
+        self.respond(&response[..response_len + 2]);
+    }
+
+    fn respond(&mut self, bytes: &[u8]) {
+        if self.end + bytes.len() > MAX_RESPONSE {
+            self.response.copy_within(self.start..self.end, 0);
+            self.end -= self.start;
+            self.start = 0;
+        }
+        // responses nobody reads are dropped once the queue is full
+        if self.end + bytes.len() <= MAX_RESPONSE {
+            self.response[self.end..self.end + bytes.len()].copy_from_slice(bytes);
+            self.end += bytes.len();
+        }
+    }
+
+    /// Moves the axes one slew step towards the GOTO target.
+    fn slew(&mut self) {
+        let (target_first, target_second) = match self.target {
+            Some(target) => target,
+            None => return,
+        };
+
+        let step = self.slew_step;
+        let toward = |from: f32, to: f32, diff: f32| {
+            if diff.abs() <= step {
+                to
+            } else if diff < 0.0 {
+                from - step
+            } else {
+                from + step
+            }
+        };
+
+        let mut diff = (target_first - self.axes.0) % 360.0;
+        if diff > 180.0 {
+            diff -= 360.0;
+        } else if diff < -180.0 {
+            diff += 360.0;
+        }
+        let first = toward(self.axes.0, target_first, diff) % 360.0;
+        let first = if first < 0.0 { first + 360.0 } else { first };
+        let second = toward(self.axes.1, target_second, target_second - self.axes.1);
+
+        self.axes = (first, second);
+        if first == target_first && second == target_second {
+            self.target = None;
+        }
+    }
+}
+
+/// Gets the length of the command including the command byte.
+fn command_len(command: u8) -> Option<usize> {
+    match command {
+        b'E' | b'e' | b'Z' | b'z' | b'M' | b'L' | b't' | b'w' | b'h' | b'V' | b'm' | b'J' => {
+            Some(1)
+        }
+        b'K' | b'T' => Some(2),
+        b'P' => Some(8),
+        b'W' | b'H' => Some(9),
+        b'R' | b'B' | b'S' => Some(10),
+        b'r' | b'b' | b's' => Some(18),
+        _ => None,
+    }
+}
+
+/// Decodes the `XXXX,XXXX` or `XXXXXXXX,XXXXXXXX` positions of the axes.
+fn decode_axes(args: &[u8]) -> Option<(f32, f32)> {
+    let digits = args.len() / 2;
+    if args[digits] != b',' {
+        return None;
+    }
+
+    Some((
+        decode_fraction(&args[..digits], false)?,
+        decode_fraction(&args[digits + 1..], true)?,
+    ))
+}
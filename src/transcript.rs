@@ -0,0 +1,327 @@
+//! Record and replay of serial sessions
+//!
+//! [`RecordingTransport`] sits between the driver and the real transport and
+//! logs every byte into a caller-provided buffer. [`ReplayTransport`] plays the
+//! received bytes of a transcript back and checks the driver writes the same
+//! bytes as in the recording, which turns a session with a misbehaving mount
+//! into a regression test:
+//!
+//! ```ignore
+//! let mut buffer = [0u8; 4096];
+//! let recording = RecordingTransport::new(rx, tx, &mut buffer);
+//! let mut nexstar = NexStar::new(&recording, &recording);
+//! nexstar.datetime()?;
+//! // store recording.transcript()
+//!
+//! let replay = ReplayTransport::new(TRANSCRIPT).unwrap();
+//! let mut nexstar = NexStar::new(&replay, &replay);
+//! nexstar.datetime()?;
+//! assert!(replay.is_finished());
+//! ```
+//!
+//! Both implement the serial traits on shared references, so one instance
+//! serves as both halves of the transport.
+//!
+//! The transcript is text with one line per direction, `>` for the bytes sent
+//! to the mount and `<` for the bytes received, each byte as two hex digits.
+//! Everything after a `#` up to the end of the line is a comment:
+//!
+//! ```text
+//! # echo
+//! > 4B 42
+//! < 42 23
+//! ```
+
+use core::cell::{Ref, RefCell};
+
+use embedded_hal::serial;
+
+const DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Direction {
+    Sent,
+    Received,
+}
+
+impl Direction {
+    fn marker(self) -> u8 {
+        match self {
+            Direction::Sent => b'>',
+            Direction::Received => b'<',
+        }
+    }
+}
+
+struct Log<'a> {
+    buffer: &'a mut [u8],
+    len: usize,
+    direction: Option<Direction>,
+    full: bool,
+}
+
+impl Log<'_> {
+    fn record(&mut self, direction: Direction, byte: u8) {
+        let mut entry = [0u8; 6];
+        let mut len = 0;
+        if self.direction != Some(direction) {
+            if self.direction.is_some() {
+                entry[len] = b'\n';
+                len += 1;
+            }
+            entry[len] = direction.marker();
+            len += 1;
+        }
+        entry[len..len + 3].copy_from_slice(&[
+            b' ',
+            DIGITS[(byte >> 4) as usize],
+            DIGITS[(byte & 0x0F) as usize],
+        ]);
+        len += 3;
+
+        // keep the transcript valid, the later bytes are lost
+        if self.full || self.len + len > self.buffer.len() {
+            self.full = true;
+            return;
+        }
+        self.buffer[self.len..self.len + len].copy_from_slice(&entry[..len]);
+        self.len += len;
+        self.direction = Some(direction);
+    }
+}
+
+/// Transport logging the bytes of the wrapped halves into a transcript
+pub struct RecordingTransport<'a, T, U> {
+    rx: RefCell<T>,
+    tx: RefCell<U>,
+    log: RefCell<Log<'a>>,
+}
+
+impl<'a, T, U> RecordingTransport<'a, T, U>
+where
+    T: serial::Read<u8>,
+    U: serial::Write<u8>,
+{
+    pub fn new(rx: T, tx: U, buffer: &'a mut [u8]) -> RecordingTransport<'a, T, U> {
+        RecordingTransport {
+            rx: RefCell::new(rx),
+            tx: RefCell::new(tx),
+            log: RefCell::new(Log {
+                buffer,
+                len: 0,
+                direction: None,
+                full: false,
+            }),
+        }
+    }
+
+    /// Gets the transcript recorded so far.
+    pub fn transcript(&self) -> Ref<'_, [u8]> {
+        Ref::map(self.log.borrow(), |log| &log.buffer[..log.len])
+    }
+
+    /// Checks whether bytes were lost because the buffer is full.
+    pub fn is_full(&self) -> bool {
+        self.log.borrow().full
+    }
+
+    pub fn free(self) -> (T, U) {
+        (self.rx.into_inner(), self.tx.into_inner())
+    }
+}
+
+impl<T, U> serial::Read<u8> for &RecordingTransport<'_, T, U>
+where
+    T: serial::Read<u8>,
+{
+    type Error = T::Error;
+
+    fn read(&mut self) -> nb::Result<u8, T::Error> {
+        let byte = self.rx.borrow_mut().read()?;
+        self.log.borrow_mut().record(Direction::Received, byte);
+        Ok(byte)
+    }
+}
+
+impl<T, U> serial::Write<u8> for &RecordingTransport<'_, T, U>
+where
+    U: serial::Write<u8>,
+{
+    type Error = U::Error;
+
+    fn write(&mut self, word: u8) -> nb::Result<(), U::Error> {
+        self.tx.borrow_mut().write(word)?;
+        self.log.borrow_mut().record(Direction::Sent, word);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> nb::Result<(), U::Error> {
+        self.tx.borrow_mut().flush()
+    }
+}
+
+/// Transcript which isn't in the format described in the [module
+/// documentation](self)
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TranscriptError {
+    /// Offset of the offending character
+    pub offset: usize,
+}
+
+/// Deviation of the driver from the transcript during a replay
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ReplayError {
+    /// The byte written differs from the byte sent in the recording.
+    Mismatch {
+        /// Offset of the expected byte in the transcript
+        offset: usize,
+        expected: u8,
+        found: u8,
+    },
+    /// A byte was written while the recording expects a received byte or
+    /// ended.
+    UnexpectedWrite { offset: usize, found: u8 },
+    /// The driver keeps reading while the recording expects it to send or
+    /// ended.
+    UnexpectedRead { offset: usize },
+}
+
+/// Reads returning `WouldBlock` in a row before [`ReplayError::UnexpectedRead`]
+const MAX_IDLE_READS: usize = 10_000;
+
+#[derive(Copy, Clone)]
+struct Cursor {
+    offset: usize,
+    direction: Direction,
+}
+
+/// Transport playing back a transcript
+pub struct ReplayTransport<'a> {
+    transcript: &'a [u8],
+    cursor: RefCell<Cursor>,
+    idle: RefCell<usize>,
+}
+
+impl<'a> ReplayTransport<'a> {
+    /// Creates the replay after validating the transcript.
+    pub fn new(transcript: &'a [u8]) -> Result<ReplayTransport<'a>, TranscriptError> {
+        let mut cursor = Cursor {
+            offset: 0,
+            direction: Direction::Sent,
+        };
+        while next_byte(transcript, &mut cursor)?.is_some() {}
+
+        Ok(ReplayTransport {
+            transcript,
+            cursor: RefCell::new(Cursor {
+                offset: 0,
+                direction: Direction::Sent,
+            }),
+            idle: RefCell::new(0),
+        })
+    }
+
+    /// Checks whether all bytes of the transcript were sent and received.
+    pub fn is_finished(&self) -> bool {
+        self.peek().is_none()
+    }
+
+    /// Gets the next byte and its direction without consuming it.
+    fn peek(&self) -> Option<(Direction, u8, Cursor)> {
+        let mut cursor = *self.cursor.borrow();
+        // the transcript is validated in `new`
+        let byte = next_byte(self.transcript, &mut cursor).ok()??;
+        Some((cursor.direction, byte, cursor))
+    }
+}
+
+impl serial::Read<u8> for &ReplayTransport<'_> {
+    type Error = ReplayError;
+
+    fn read(&mut self) -> nb::Result<u8, ReplayError> {
+        let mut idle = self.idle.borrow_mut();
+        match self.peek() {
+            Some((Direction::Received, byte, cursor)) => {
+                *self.cursor.borrow_mut() = cursor;
+                *idle = 0;
+                Ok(byte)
+            }
+            // nothing is received until the driver sends the next command,
+            // but don't let a blocking read wait forever
+            _ if *idle >= MAX_IDLE_READS => {
+                *idle = 0;
+                Err(nb::Error::Other(ReplayError::UnexpectedRead {
+                    offset: self.cursor.borrow().offset,
+                }))
+            }
+            _ => {
+                *idle += 1;
+                Err(nb::Error::WouldBlock)
+            }
+        }
+    }
+}
+
+impl serial::Write<u8> for &ReplayTransport<'_> {
+    type Error = ReplayError;
+
+    fn write(&mut self, word: u8) -> nb::Result<(), ReplayError> {
+        match self.peek() {
+            Some((Direction::Sent, byte, cursor)) if byte == word => {
+                *self.cursor.borrow_mut() = cursor;
+                Ok(())
+            }
+            Some((Direction::Sent, byte, cursor)) => {
+                Err(nb::Error::Other(ReplayError::Mismatch {
+                    // the cursor is behind the two digits of the byte
+                    offset: cursor.offset - 2,
+                    expected: byte,
+                    found: word,
+                }))
+            }
+            _ => Err(nb::Error::Other(ReplayError::UnexpectedWrite {
+                offset: self.cursor.borrow().offset,
+                found: word,
+            })),
+        }
+    }
+
+    fn flush(&mut self) -> nb::Result<(), ReplayError> {
+        Ok(())
+    }
+}
+
+/// Parses the next byte of the transcript at the cursor, `None` at the end.
+fn next_byte(transcript: &[u8], cursor: &mut Cursor) -> Result<Option<u8>, TranscriptError> {
+    let nibble = |digit: u8| (digit as char).to_digit(16).map(|nibble| nibble as u8);
+
+    while let Some(&c) = transcript.get(cursor.offset) {
+        match c {
+            b'>' => cursor.direction = Direction::Sent,
+            b'<' => cursor.direction = Direction::Received,
+            b'#' => {
+                while transcript.get(cursor.offset).is_some_and(|&c| c != b'\n') {
+                    cursor.offset += 1;
+                }
+                continue;
+            }
+            c if c.is_ascii_whitespace() => {}
+            c => {
+                let high = nibble(c);
+                let low = transcript.get(cursor.offset + 1).and_then(|&c| nibble(c));
+                return match (high, low) {
+                    (Some(high), Some(low)) => {
+                        cursor.offset += 2;
+                        Ok(Some(high << 4 | low))
+                    }
+                    _ => Err(TranscriptError {
+                        offset: cursor.offset,
+                    }),
+                };
+            }
+        }
+        cursor.offset += 1;
+    }
+
+    Ok(None)
+}
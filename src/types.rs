@@ -0,0 +1,1979 @@
+use core::fmt;
+
+use crate::command::{ECHO_VERSION, MODEL_VERSION, NARROW_POSITION_VERSION};
+#[cfg(feature = "float")]
+use libm::{atan2, cos, sin, sqrt};
+
+/// Date Time
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DateTime {
+    /// Hour (24 hour clock)
+    pub hour: u8,
+    /// Minutes
+    pub minutes: u8,
+    /// Seconds
+    pub seconds: u8,
+    /// Offset of the standard time from UTC in hours, without the daylight
+    /// saving hour.
+    pub zone: i8,
+    /// Daylight saving time, one hour ahead of the standard time `zone`
+    pub daylight_saving: bool,
+    /// Year with century assumed as 20.
+    pub year: u8,
+    /// Month
+    pub month: u8,
+    /// Day
+    pub day: u8,
+}
+
+impl DateTime {
+    /// Converts the date and time into the order used by the HC commands.
+    pub(crate) const fn to_bytes(self) -> [u8; 8] {
+        [
+            self.hour,
+            self.minutes,
+            self.seconds,
+            self.month,
+            self.day,
+            self.year,
+            // negative zones are sent as 256 + zone
+            self.zone as u8,
+            self.daylight_saving as u8,
+        ]
+    }
+
+    /// Converts the bytes of the HC commands, `None` if a field is out of range.
+    pub(crate) fn from_bytes(bytes: &[u8; 8]) -> Option<DateTime> {
+        let valid = bytes[0] < 24
+            && bytes[1] < 60
+            && bytes[2] < 60
+            && (1..=12).contains(&bytes[3])
+            && (1..=31).contains(&bytes[4])
+            && bytes[7] <= 1;
+        if !valid {
+            return None;
+        }
+
+        Some(DateTime {
+            hour: bytes[0],
+            minutes: bytes[1],
+            seconds: bytes[2],
+            zone: bytes[6] as i8,
+            daylight_saving: bytes[7] == 1,
+            year: bytes[5],
+            month: bytes[3],
+            day: bytes[4],
+        })
+    }
+
+    /// Converts the UTC date and time into the local time in the standard
+    /// time `zone`, one hour later with `daylight_saving`. `None` if the
+    /// local year is outside 2000..=2255.
+    pub fn from_utc(utc: UtcDateTime, zone: i8, daylight_saving: bool) -> Option<DateTime> {
+        let offset = zone as i32 + daylight_saving as i32;
+        let (year, month, day, hour) = shift_hours(utc.year, utc.month, utc.day, utc.hour, offset);
+        let year = match year.checked_sub(2000)? {
+            year @ 0..=255 => year as u8,
+            _ => return None,
+        };
+
+        Some(DateTime {
+            hour,
+            minutes: utc.minutes,
+            seconds: utc.seconds,
+            zone,
+            daylight_saving,
+            year,
+            month,
+            day,
+        })
+    }
+
+    /// Gets the date and time `seconds` later, carrying into the minutes,
+    /// hours, days, months and years. The year of the century wraps after 255.
+    pub fn add_seconds(&self, seconds: u32) -> DateTime {
+        let total = self.seconds as u64 + self.minutes as u64 * 60 + seconds as u64;
+        let hours = (total / 3600) as i32;
+        let (year, month, day, hour) = shift_hours(
+            2000 + self.year as u16,
+            self.month,
+            self.day,
+            self.hour,
+            hours,
+        );
+
+        DateTime {
+            hour,
+            minutes: (total / 60 % 60) as u8,
+            seconds: (total % 60) as u8,
+            year: year.wrapping_sub(2000) as u8,
+            month,
+            day,
+            ..*self
+        }
+    }
+
+    /// Converts the local date and time into UTC, subtracting the zone and
+    /// the daylight saving hour.
+    pub fn to_utc(&self) -> UtcDateTime {
+        let offset = self.zone as i32 + self.daylight_saving as i32;
+        let (year, month, day, hour) = shift_hours(
+            2000 + self.year as u16,
+            self.month,
+            self.day,
+            self.hour,
+            -offset,
+        );
+
+        UtcDateTime {
+            year,
+            month,
+            day,
+            hour,
+            minutes: self.minutes,
+            seconds: self.seconds,
+        }
+    }
+}
+
+/// Date and time in UTC, e.g. from the GPS unit
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct UtcDateTime {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    /// Hour (24 hour clock)
+    pub hour: u8,
+    pub minutes: u8,
+    pub seconds: u8,
+}
+
+fn is_leap_year(year: u16) -> bool {
+    year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400))
+}
+
+fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Adds `hours` to the date and hour, rolling over days, months and years.
+fn shift_hours(year: u16, month: u8, day: u8, hour: u8, hours: i32) -> (u16, u8, u8, u8) {
+    let (mut year, mut month, mut day) = (year, month, day);
+    let hour = hour as i32 + hours;
+
+    for _ in 0..hour.div_euclid(24) {
+        if day < days_in_month(year, month) {
+            day += 1;
+        } else if month < 12 {
+            (month, day) = (month + 1, 1);
+        } else {
+            (year, month, day) = (year.wrapping_add(1), 1, 1);
+        }
+    }
+    for _ in hour.div_euclid(24)..0 {
+        if day > 1 {
+            day -= 1;
+        } else if month > 1 {
+            month -= 1;
+            day = days_in_month(year, month);
+        } else {
+            (year, month, day) = (year.wrapping_sub(1), 12, 31);
+        }
+    }
+
+    (year, month, day, hour.rem_euclid(24) as u8)
+}
+
+/// Sub Device
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Device {
+    /// Main / Interconnection Board
+    MainBoard = 0x01,
+    /// Hand Controller (HC)
+    HandController = 0x04,
+    /// AZM/RA Motor
+    AzmRaMotor = 0x10,
+    /// ALT/DEC Motor
+    AltDecMotor = 0x11,
+    /// Focus motor
+    Focuser = 0x12,
+    /// GPS Unit
+    GPSUnit = 0xb0,
+    /// RTC (CGE only)
+    RTC = 0xb2,
+    /// WiFi module (SkyPortal, e.g. built into the Evolution)
+    WiFi = 0xb5,
+    /// Battery power controller (Evolution only)
+    Battery = 0xb6,
+}
+
+/// Number of the [`Device`] variants
+const DEVICES: usize = 9;
+
+impl Device {
+    pub(crate) const fn bits(&self) -> u8 {
+        *self as u8
+    }
+
+    /// Gets the position of the device in tables of all devices.
+    const fn index(&self) -> usize {
+        match *self {
+            Device::MainBoard => 0,
+            Device::HandController => 1,
+            Device::AzmRaMotor => 2,
+            Device::AltDecMotor => 3,
+            Device::Focuser => 4,
+            Device::GPSUnit => 5,
+            Device::RTC => 6,
+            Device::WiFi => 7,
+            Device::Battery => 8,
+        }
+    }
+
+    pub(crate) fn from_bits(bits: u8) -> Option<Device> {
+        match bits {
+            0x01 => Some(Device::MainBoard),
+            0x04 => Some(Device::HandController),
+            0x10 => Some(Device::AzmRaMotor),
+            0x11 => Some(Device::AltDecMotor),
+            0x12 => Some(Device::Focuser),
+            0xb0 => Some(Device::GPSUnit),
+            0xb2 => Some(Device::RTC),
+            0xb5 => Some(Device::WiFi),
+            0xb6 => Some(Device::Battery),
+            _ => None,
+        }
+    }
+}
+
+/// Location of the mount
+///
+/// Latitude and longitude are in degrees, north and east positive. The HC
+/// stores them with a resolution of one arcsecond.
+#[cfg(feature = "float")]
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Location {
+    pub latitude: f32,
+    pub longitude: f32,
+}
+
+#[cfg(feature = "float")]
+impl Location {
+    /// Converts the latitude and longitude in degrees, minutes and seconds,
+    /// `None` if a field is out of range or a hemisphere belongs to the other
+    /// axis.
+    pub fn from_dms(latitude: Dms, longitude: Dms) -> Option<Location> {
+        if !latitude.is_latitude() || !longitude.is_longitude() {
+            return None;
+        }
+
+        Some(Location {
+            latitude: latitude.to_degrees(),
+            longitude: longitude.to_degrees(),
+        })
+    }
+
+    pub fn lat_dms(&self) -> [u8; 4] {
+        dec_dms(self.latitude)
+    }
+
+    pub fn lon_dms(&self) -> [u8; 4] {
+        dec_dms(self.longitude)
+    }
+
+    /// Converts the location into the DMS bytes of latitude and longitude.
+    #[cfg(any(feature = "fuzzing", feature = "simulator"))]
+    pub(crate) fn to_bytes(self) -> [u8; 8] {
+        let [lat_d, lat_m, lat_s, lat_sign] = self.lat_dms();
+        let [lon_d, lon_m, lon_s, lon_sign] = self.lon_dms();
+        [lat_d, lat_m, lat_s, lat_sign, lon_d, lon_m, lon_s, lon_sign]
+    }
+
+    /// Converts the location into the DMS values of the set command.
+    pub(crate) fn to_dms(self) -> (Dms, Dms) {
+        let [degrees, minutes, seconds, sign] = self.lat_dms();
+        let latitude = Dms {
+            degrees,
+            minutes,
+            seconds,
+            hemisphere: if sign == 0 {
+                Hemisphere::North
+            } else {
+                Hemisphere::South
+            },
+        };
+        let [degrees, minutes, seconds, sign] = self.lon_dms();
+        let longitude = Dms {
+            degrees,
+            minutes,
+            seconds,
+            hemisphere: if sign == 0 {
+                Hemisphere::East
+            } else {
+                Hemisphere::West
+            },
+        };
+        (latitude, longitude)
+    }
+}
+
+/// Formats the location like `47°12'34" N  008°32'07" E`, rounded to whole
+/// seconds which carry into the minutes and degrees.
+///
+/// The alternate form `{:#}` gives decimal degrees like `47.2094° N  8.5353° E`,
+/// with 4 decimals unless a precision is given. Angles which round to zero
+/// show as north or east, even if slightly negative. [`str::parse`] reads
+/// both back, along with other common formats.
+#[cfg(feature = "float")]
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if f.alternate() {
+            let decimals = f.precision().unwrap_or(4);
+            let letter = |value: f32, positive, negative| {
+                if value.is_sign_negative() && !is_zero(value, decimals) {
+                    negative
+                } else {
+                    positive
+                }
+            };
+            let (latitude, longitude) = (self.latitude, self.longitude);
+            return write!(
+                f,
+                "{:.*}° {}  {:.*}° {}",
+                decimals,
+                latitude.abs(),
+                letter(latitude, 'N', 'S'),
+                decimals,
+                longitude.abs(),
+                letter(longitude, 'E', 'W')
+            );
+        }
+
+        let (latitude, longitude) = self.to_dms();
+        write!(
+            f,
+            "{:02}°{:02}'{:02}\" {}  {:03}°{:02}'{:02}\" {}",
+            latitude.degrees,
+            latitude.minutes,
+            latitude.seconds,
+            latitude.display_hemisphere().letter(),
+            longitude.degrees,
+            longitude.minutes,
+            longitude.seconds,
+            longitude.display_hemisphere().letter()
+        )
+    }
+}
+
+/// Checks whether the value shows as zero with the number of decimals.
+#[cfg(feature = "float")]
+fn is_zero(value: f32, decimals: usize) -> bool {
+    struct Digits(bool);
+
+    impl fmt::Write for Digits {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            self.0 |= s.bytes().any(|b| (b'1'..=b'9').contains(&b));
+            Ok(())
+        }
+    }
+
+    let mut digits = Digits(false);
+    let _ = fmt::write(&mut digits, format_args!("{:.*}", decimals, value));
+    !digits.0
+}
+
+/// Hemisphere of a latitude or longitude
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Hemisphere {
+    North,
+    South,
+    East,
+    West,
+}
+
+impl Hemisphere {
+    const fn is_negative(self) -> bool {
+        matches!(self, Hemisphere::South | Hemisphere::West)
+    }
+
+    fn letter(self) -> char {
+        match self {
+            Hemisphere::North => 'N',
+            Hemisphere::South => 'S',
+            Hemisphere::East => 'E',
+            Hemisphere::West => 'W',
+        }
+    }
+
+    #[cfg(feature = "float")]
+    const fn positive(self) -> Hemisphere {
+        match self {
+            Hemisphere::North | Hemisphere::South => Hemisphere::North,
+            Hemisphere::East | Hemisphere::West => Hemisphere::East,
+        }
+    }
+}
+
+/// Latitude or longitude in whole degrees, minutes and seconds like the HC
+/// stores it
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Dms {
+    pub degrees: u8,
+    pub minutes: u8,
+    pub seconds: u8,
+    pub hemisphere: Hemisphere,
+}
+
+impl Dms {
+    /// Converts a latitude in arcseconds (north positive), `None` outside of
+    /// -324_000..=324_000.
+    pub fn from_latitude_arcsec(arcsec: i32) -> Option<Dms> {
+        Dms::from_arcsec(arcsec, Hemisphere::North, Hemisphere::South).filter(Dms::is_latitude)
+    }
+
+    /// Converts a longitude in arcseconds (east positive), `None` outside of
+    /// -648_000..=648_000.
+    pub fn from_longitude_arcsec(arcsec: i32) -> Option<Dms> {
+        Dms::from_arcsec(arcsec, Hemisphere::East, Hemisphere::West).filter(Dms::is_longitude)
+    }
+
+    /// Hemisphere shown for the angle, north or east for 0° 0' 0" whichever
+    /// one it keeps.
+    #[cfg(feature = "float")]
+    fn display_hemisphere(&self) -> Hemisphere {
+        if self.degrees == 0 && self.minutes == 0 && self.seconds == 0 {
+            self.hemisphere.positive()
+        } else {
+            self.hemisphere
+        }
+    }
+
+    /// Converts the angle into arcseconds, south and west negative.
+    pub fn to_arcsec(&self) -> i32 {
+        let arcsec = self.degrees as i32 * 3600 + self.minutes as i32 * 60 + self.seconds as i32;
+        if self.hemisphere.is_negative() {
+            -arcsec
+        } else {
+            arcsec
+        }
+    }
+
+    /// Rounds a latitude in degrees (north positive) to whole seconds, `None`
+    /// outside of -90..=90.
+    #[cfg(feature = "float")]
+    pub fn from_latitude(degrees: f32) -> Option<Dms> {
+        Dms::from_degrees(degrees, Hemisphere::North, Hemisphere::South).filter(Dms::is_latitude)
+    }
+
+    /// Rounds a longitude in degrees (east positive) to whole seconds, `None`
+    /// outside of -180..=180.
+    #[cfg(feature = "float")]
+    pub fn from_longitude(degrees: f32) -> Option<Dms> {
+        Dms::from_degrees(degrees, Hemisphere::East, Hemisphere::West).filter(Dms::is_longitude)
+    }
+
+    /// Converts the angle into degrees, south and west negative.
+    #[cfg(feature = "float")]
+    pub fn to_degrees(&self) -> f32 {
+        let degrees =
+            self.degrees as f32 + self.minutes as f32 / 60.0 + self.seconds as f32 / 3600.0;
+        if self.hemisphere.is_negative() {
+            -degrees
+        } else {
+            degrees
+        }
+    }
+
+    /// Checks whether the fields are in range for a latitude.
+    pub fn is_latitude(&self) -> bool {
+        matches!(self.hemisphere, Hemisphere::North | Hemisphere::South) && self.is_within(90)
+    }
+
+    /// Checks whether the fields are in range for a longitude.
+    pub fn is_longitude(&self) -> bool {
+        matches!(self.hemisphere, Hemisphere::East | Hemisphere::West) && self.is_within(180)
+    }
+
+    fn is_within(&self, max_degrees: u8) -> bool {
+        self.minutes < 60
+            && self.seconds < 60
+            && (self.degrees < max_degrees
+                || self.degrees == max_degrees && self.minutes == 0 && self.seconds == 0)
+    }
+
+    #[cfg(feature = "float")]
+    fn from_degrees(degrees: f32, positive: Hemisphere, negative: Hemisphere) -> Option<Dms> {
+        // the range is checked by the callers, this only keeps the casts sane
+        if !(-255.0..=255.0).contains(&degrees) {
+            return None;
+        }
+
+        let [degrees, minutes, seconds, sign] = dec_dms(degrees);
+        Some(Dms {
+            degrees,
+            minutes,
+            seconds,
+            hemisphere: if sign == 0 { positive } else { negative },
+        })
+    }
+
+    fn from_arcsec(arcsec: i32, positive: Hemisphere, negative: Hemisphere) -> Option<Dms> {
+        let hemisphere = if arcsec < 0 { negative } else { positive };
+        let arcsec = arcsec.unsigned_abs();
+        // the range is checked by the callers, this only keeps the casts sane
+        if arcsec > 255 * 3600 {
+            return None;
+        }
+
+        Some(Dms {
+            degrees: (arcsec / 3600) as u8,
+            minutes: (arcsec / 60 % 60) as u8,
+            seconds: (arcsec % 60) as u8,
+            hemisphere,
+        })
+    }
+
+    /// Converts the angle into the DMS bytes of the HC commands.
+    pub(crate) const fn to_bytes(self) -> [u8; 4] {
+        [
+            self.degrees,
+            self.minutes,
+            self.seconds,
+            self.hemisphere.is_negative() as u8,
+        ]
+    }
+
+    /// Converts the DMS bytes of the HC commands, `None` if the sign byte is
+    /// invalid. The range of the other fields isn't checked.
+    pub(crate) fn from_bytes(
+        dms: [u8; 4],
+        positive: Hemisphere,
+        negative: Hemisphere,
+    ) -> Option<Dms> {
+        let [degrees, minutes, seconds, sign] = dms;
+        let hemisphere = match sign {
+            0x00 => positive,
+            0x01 => negative,
+            _ => return None,
+        };
+
+        Some(Dms {
+            degrees,
+            minutes,
+            seconds,
+            hemisphere,
+        })
+    }
+}
+
+/// Formats the angle like `8°32'07"E`.
+impl fmt::Display for Dms {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}°{:02}'{:02}\"{}",
+            self.degrees,
+            self.minutes,
+            self.seconds,
+            self.hemisphere.letter()
+        )
+    }
+}
+
+#[cfg(feature = "float")]
+fn dec_dms(dec: f32) -> [u8; 4] {
+    // -0 from a decoded "0° 0' 0\" S" keeps its hemisphere
+    let sign = if dec.is_sign_negative() { 0x01 } else { 0x00 };
+    let dec = if dec < 0.0 { -dec } else { dec };
+
+    // round to whole seconds first so they carry into minutes and degrees
+    let seconds = (dec * 3600.0 + 0.5) as u32;
+
+    [
+        (seconds / 3600) as u8,
+        (seconds / 60 % 60) as u8,
+        (seconds % 60) as u8,
+        sign,
+    ]
+}
+
+/// Telescope mount model
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Model {
+    /// GPS Series
+    GPSSeries,
+    /// i-Series
+    ISeries,
+    /// i-Series SE
+    ISeriesSE,
+    /// CGE
+    CGE,
+    /// Advanced GT
+    AdvancedGT,
+    /// SLT
+    SLT,
+    /// CPC
+    CPC,
+    /// GT
+    GT,
+    /// 4/5 SE
+    Se4_5,
+    /// 6/8 SE
+    Se6_8,
+    /// SynScan EQ6 series, e.g. the EQ6-R
+    #[cfg(feature = "synscan")]
+    Eq6,
+    /// SynScan HEQ5 series
+    #[cfg(feature = "synscan")]
+    Heq5,
+    /// SynScan EQ5 series
+    #[cfg(feature = "synscan")]
+    Eq5,
+    /// SynScan EQ3 series
+    #[cfg(feature = "synscan")]
+    Eq3,
+    /// SynScan EQ8 series
+    #[cfg(feature = "synscan")]
+    Eq8,
+    /// SynScan AZ-EQ6 series
+    #[cfg(feature = "synscan")]
+    AzEq6,
+    /// SynScan AZ-EQ5 series
+    #[cfg(feature = "synscan")]
+    AzEq5,
+    /// SynScan AZ GOTO series
+    #[cfg(feature = "synscan")]
+    AzGoto,
+    /// SynScan Dobsonian GOTO series
+    #[cfg(feature = "synscan")]
+    DobGoto,
+    /// SynScan AllView
+    #[cfg(feature = "synscan")]
+    AllView,
+    /// Unknown Model
+    Unknown(u8),
+}
+
+impl Model {
+    #[cfg(feature = "simulator")]
+    pub(crate) fn bits(&self) -> u8 {
+        match *self {
+            Model::GPSSeries => 0x01,
+            Model::ISeries => 0x03,
+            Model::ISeriesSE => 0x04,
+            Model::CGE => 0x05,
+            Model::AdvancedGT => 0x06,
+            Model::SLT => 0x07,
+            Model::CPC => 0x09,
+            Model::GT => 0x0A,
+            Model::Se4_5 => 0x0B,
+            Model::Se6_8 => 0x0C,
+            // the ids of the SynScan models overlap, they are only told
+            // apart by the family of the HC
+            #[cfg(feature = "synscan")]
+            Model::Eq6 => 0,
+            #[cfg(feature = "synscan")]
+            Model::Heq5 => 1,
+            #[cfg(feature = "synscan")]
+            Model::Eq5 => 2,
+            #[cfg(feature = "synscan")]
+            Model::Eq3 => 3,
+            #[cfg(feature = "synscan")]
+            Model::Eq8 => 4,
+            #[cfg(feature = "synscan")]
+            Model::AzEq6 => 5,
+            #[cfg(feature = "synscan")]
+            Model::AzEq5 => 6,
+            #[cfg(feature = "synscan")]
+            Model::AzGoto => 128,
+            #[cfg(feature = "synscan")]
+            Model::DobGoto => 144,
+            #[cfg(feature = "synscan")]
+            Model::AllView => 160,
+            Model::Unknown(id) => id,
+        }
+    }
+
+    /// Maps the model ids of the SynScan HCs, which group several ids into
+    /// one series.
+    #[cfg(feature = "synscan")]
+    pub(crate) fn from_synscan_bits(bits: u8) -> Model {
+        match bits {
+            0 => Model::Eq6,
+            1 => Model::Heq5,
+            2 => Model::Eq5,
+            3 => Model::Eq3,
+            4 => Model::Eq8,
+            5 => Model::AzEq6,
+            6 => Model::AzEq5,
+            128..=143 => Model::AzGoto,
+            144..=159 => Model::DobGoto,
+            160 => Model::AllView,
+            id => Model::Unknown(id),
+        }
+    }
+
+    pub(crate) fn from_bits(bits: u8) -> Model {
+        match bits {
+            0x01 => Model::GPSSeries,
+            0x03 => Model::ISeries,
+            0x04 => Model::ISeriesSE,
+            0x05 => Model::CGE,
+            0x06 => Model::AdvancedGT,
+            0x07 => Model::SLT,
+            0x09 => Model::CPC,
+            0x0A => Model::GT,
+            0x0B => Model::Se4_5,
+            0x0C => Model::Se6_8,
+            id => Model::Unknown(id),
+        }
+    }
+
+    /// Gets the features of the model.
+    ///
+    /// Unknown models claim every feature, so the driver doesn't refuse
+    /// commands a newer mount may support. They aren't assumed to be GEMs.
+    pub const fn capabilities(&self) -> Capabilities {
+        match *self {
+            Model::CGE => Capabilities {
+                is_gem: true,
+                has_builtin_gps: false,
+                has_rtc: true,
+                supports_cordwrap: false,
+            },
+            Model::AdvancedGT => Capabilities {
+                is_gem: true,
+                has_builtin_gps: false,
+                has_rtc: false,
+                supports_cordwrap: false,
+            },
+            Model::GPSSeries | Model::CPC => Capabilities {
+                has_builtin_gps: true,
+                ..ALT_AZ
+            },
+            Model::ISeries
+            | Model::ISeriesSE
+            | Model::SLT
+            | Model::GT
+            | Model::Se4_5
+            | Model::Se6_8 => ALT_AZ,
+            #[cfg(feature = "synscan")]
+            Model::Eq6
+            | Model::Heq5
+            | Model::Eq5
+            | Model::Eq3
+            | Model::Eq8
+            | Model::AzEq6
+            | Model::AzEq5 => Capabilities {
+                is_gem: true,
+                has_builtin_gps: false,
+                has_rtc: false,
+                supports_cordwrap: false,
+            },
+            #[cfg(feature = "synscan")]
+            Model::AzGoto | Model::DobGoto | Model::AllView => ALT_AZ,
+            Model::Unknown(_) => Capabilities {
+                is_gem: false,
+                has_builtin_gps: true,
+                has_rtc: true,
+                supports_cordwrap: true,
+            },
+        }
+    }
+}
+
+/// Fork or single arm alt-az mount without GPS and RTC
+const ALT_AZ: Capabilities = Capabilities {
+    is_gem: false,
+    has_builtin_gps: false,
+    has_rtc: false,
+    supports_cordwrap: true,
+};
+
+/// Features of a mount model, see [`Model::capabilities`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Capabilities {
+    /// German equatorial mount, otherwise a fork or single arm alt-az mount
+    pub is_gem: bool,
+    /// GPS receiver built in, the others may have one as an accessory
+    pub has_builtin_gps: bool,
+    /// Battery backed real time clock, see
+    /// [`NexStar::rtc_time`](crate::NexStar::rtc_time)
+    pub has_rtc: bool,
+    /// The HC keeps the azimuth axis from winding up the cables
+    pub supports_cordwrap: bool,
+}
+
+/// Motor controller board variant as queried by
+/// [`NexStar::motor_model`](crate::NexStar::motor_model)
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MotorModel {
+    /// First generation controller of the NexStar GPS and CGE mounts
+    Gen1,
+    /// Second generation controller with an autoguider port
+    Gen2,
+    /// Controller of the Evolution and CGX mounts
+    Gen3,
+    /// Unknown board variant
+    Unknown(u8),
+}
+
+impl MotorModel {
+    #[cfg(feature = "simulator")]
+    pub(crate) fn bits(&self) -> u8 {
+        match *self {
+            MotorModel::Gen1 => 0x01,
+            MotorModel::Gen2 => 0x02,
+            MotorModel::Gen3 => 0x03,
+            MotorModel::Unknown(id) => id,
+        }
+    }
+
+    pub(crate) fn from_bits(bits: u8) -> MotorModel {
+        match bits {
+            0x01 => MotorModel::Gen1,
+            0x02 => MotorModel::Gen2,
+            0x03 => MotorModel::Gen3,
+            id => MotorModel::Unknown(id),
+        }
+    }
+
+    /// The controller accepts timed guide pulses, unknown variants are
+    /// assumed not to.
+    pub fn supports_guide_pulses(&self) -> bool {
+        matches!(*self, MotorModel::Gen2 | MotorModel::Gen3)
+    }
+
+    /// The controller takes guide rates in 1/1024 arcsec/s, unknown variants
+    /// are assumed to take quarter arcsec/s like the first generation, see
+    /// [`NexStar::set_guiderate`](crate::NexStar::set_guiderate).
+    pub fn has_fine_guiderate(&self) -> bool {
+        matches!(*self, MotorModel::Gen2 | MotorModel::Gen3)
+    }
+}
+
+/// Tracking Mode
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TrackingMode {
+    Off = 0x00,
+    AltAz = 0x01,
+    EQNorth = 0x02,
+    EQSouth = 0x03,
+}
+
+impl TrackingMode {
+    pub(crate) fn bits(&self) -> u8 {
+        *self as u8
+    }
+
+    pub(crate) fn from_bits(bits: u8) -> Option<TrackingMode> {
+        match bits {
+            0x00 => Some(TrackingMode::Off),
+            0x01 => Some(TrackingMode::AltAz),
+            0x02 => Some(TrackingMode::EQNorth),
+            0x03 => Some(TrackingMode::EQSouth),
+            _ => None,
+        }
+    }
+}
+
+/// Formats the mode like `EQ North`.
+impl fmt::Display for TrackingMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            TrackingMode::Off => "off",
+            TrackingMode::AltAz => "Alt/Az",
+            TrackingMode::EQNorth => "EQ North",
+            TrackingMode::EQSouth => "EQ South",
+        })
+    }
+}
+
+/// Guide rate of a motor in multiples of the sidereal rate, see
+/// [`NexStar::set_guiderate`](crate::NexStar::set_guiderate)
+///
+/// Zero stops the motor, negative rates drive it in the negative direction.
+#[cfg(feature = "float")]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct GuideRate(pub f32);
+
+#[cfg(feature = "float")]
+impl GuideRate {
+    pub const SIDEREAL: GuideRate = GuideRate(1.0);
+    pub const OFF: GuideRate = GuideRate(0.0);
+
+    pub fn arcsec_per_sec(&self) -> f32 {
+        self.0 * TrackingRate::SIDEREAL
+    }
+}
+
+/// Custom tracking rate of the AZM/RA Motor
+///
+/// The named rates are the ASCOM drive rates.
+#[cfg(feature = "float")]
+#[derive(Copy, Clone, Debug)]
+pub enum TrackingRate {
+    /// 15.041067 arcsec/s
+    Sidereal,
+    /// 14.685 arcsec/s
+    Lunar,
+    /// 15.0 arcsec/s
+    Solar,
+    /// Rate in arcsec/s, negative values track in reverse
+    Custom(f32),
+}
+
+#[cfg(feature = "float")]
+impl TrackingRate {
+    pub const SIDEREAL: f32 = 15.041_067;
+    pub const LUNAR: f32 = 14.685;
+    pub const SOLAR: f32 = 15.0;
+
+    /// Gets the rate in arcsec/s.
+    pub fn arcsec_per_sec(&self) -> f32 {
+        match self {
+            TrackingRate::Sidereal => TrackingRate::SIDEREAL,
+            TrackingRate::Lunar => TrackingRate::LUNAR,
+            TrackingRate::Solar => TrackingRate::SOLAR,
+            TrackingRate::Custom(rate) => *rate,
+        }
+    }
+}
+
+/// Firmware version
+///
+/// The minor version is a plain number written with two decimal digits, so
+/// 4.9 is version 4.09 and precedes 4.21 (and 4.21 precedes 5.0). Versions are
+/// ordered by major and then by minor version accordingly.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Version {
+    pub major: u8,
+    pub minor: u8,
+}
+
+impl Version {
+    pub const fn new(major: u8, minor: u8) -> Version {
+        Version { major, minor }
+    }
+
+    /// Checks whether this version is `major.minor` or newer.
+    pub fn at_least(&self, major: u8, minor: u8) -> bool {
+        *self >= Version::new(major, minor)
+    }
+}
+
+/// Equatorial coordinates
+#[cfg(feature = "float")]
+#[derive(Copy, Clone, Debug)]
+pub struct RaDec {
+    /// Right ascension in degrees (0..360, 15 degrees per hour)
+    pub ra: f32,
+    /// Declination in degrees (-90..=90), positions read from the mount are
+    /// in -180..=180, see [`NexStar::motor_angle`](crate::NexStar::motor_angle)
+    pub dec: f32,
+}
+
+#[cfg(feature = "float")]
+impl RaDec {
+    /// Gets the angular distance to `other` in degrees, see [`separation`].
+    pub fn separation(&self, other: RaDec) -> f32 {
+        separation((self.ra, self.dec), (other.ra, other.dec))
+    }
+
+    /// Gets the angular distance to `other` in arcseconds.
+    pub fn separation_arcsec(&self, other: RaDec) -> f32 {
+        separation_arcsec((self.ra, self.dec), (other.ra, other.dec))
+    }
+}
+
+/// Horizontal coordinates
+#[cfg(feature = "float")]
+#[derive(Copy, Clone, Debug)]
+pub struct AzmAlt {
+    /// Azimuth in degrees (0..360)
+    pub azm: f32,
+    /// Altitude in degrees (-90..=90), positions read from the mount are in
+    /// -180..=180, see [`NexStar::motor_angle`](crate::NexStar::motor_angle)
+    pub alt: f32,
+}
+
+#[cfg(feature = "float")]
+impl AzmAlt {
+    /// Gets the angular distance to `other` in degrees, see [`separation`].
+    pub fn separation(&self, other: AzmAlt) -> f32 {
+        separation((self.azm, self.alt), (other.azm, other.alt))
+    }
+
+    /// Gets the angular distance to `other` in arcseconds.
+    pub fn separation_arcsec(&self, other: AzmAlt) -> f32 {
+        separation_arcsec((self.azm, self.alt), (other.azm, other.alt))
+    }
+}
+
+/// Equatorial coordinates in arcseconds
+///
+/// The integer counterpart of `RaDec` for targets without an FPU, with a
+/// resolution of one arcsecond.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct RaDecArcsec {
+    /// Right ascension (0..1_296_000, 54_000 per hour)
+    pub ra: i32,
+    /// Declination (-324_000..324_000)
+    pub dec: i32,
+}
+
+/// Formats the position like `06h00m00s -45°00'00"`, the RA rounded down to
+/// whole seconds of time.
+impl fmt::Display for RaDecArcsec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let time = self.ra.rem_euclid(1_296_000) / 15;
+        let dec = self.dec.unsigned_abs();
+        write!(
+            f,
+            "{:02}h{:02}m{:02}s {}{:02}°{:02}'{:02}\"",
+            time / 3600,
+            time / 60 % 60,
+            time % 60,
+            if self.dec < 0 { '-' } else { '+' },
+            dec / 3600,
+            dec / 60 % 60,
+            dec % 60
+        )
+    }
+}
+
+/// Horizontal coordinates in arcseconds
+///
+/// The integer counterpart of `AzmAlt` for targets without an FPU, with a
+/// resolution of one arcsecond.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct AzmAltArcsec {
+    /// Azimuth (0..1_296_000)
+    pub azm: i32,
+    /// Altitude (-324_000..324_000)
+    pub alt: i32,
+}
+
+#[cfg(feature = "float")]
+impl From<RaDecArcsec> for RaDec {
+    fn from(position: RaDecArcsec) -> RaDec {
+        RaDec {
+            ra: position.ra as f32 / 3600.0,
+            dec: position.dec as f32 / 3600.0,
+        }
+    }
+}
+
+#[cfg(feature = "float")]
+impl From<AzmAltArcsec> for AzmAlt {
+    fn from(position: AzmAltArcsec) -> AzmAlt {
+        AzmAlt {
+            azm: position.azm as f32 / 3600.0,
+            alt: position.alt as f32 / 3600.0,
+        }
+    }
+}
+
+/// Gets the angular distance in degrees between two points given as
+/// (longitude, latitude) in degrees, e.g. RA/Dec or Azm/Alt.
+///
+/// Uses the Vincenty formula for a sphere, which stays accurate for small and
+/// antipodal distances alike and isn't fooled by the wrap at 0/360 or by the
+/// RA/Azm converging at the poles.
+#[cfg(feature = "float")]
+pub fn separation(first: (f32, f32), second: (f32, f32)) -> f32 {
+    separation_radians(first, second).to_degrees() as f32
+}
+
+/// Gets the angular distance in arcseconds, see [`separation`].
+#[cfg(feature = "float")]
+pub fn separation_arcsec(first: (f32, f32), second: (f32, f32)) -> f32 {
+    (separation_radians(first, second).to_degrees() * 3600.0) as f32
+}
+
+#[cfg(feature = "float")]
+fn separation_radians(first: (f32, f32), second: (f32, f32)) -> f64 {
+    let lat1 = (first.1 as f64).to_radians();
+    let lat2 = (second.1 as f64).to_radians();
+    let delta = (second.0 as f64 - first.0 as f64).to_radians();
+    let (sin1, cos1) = (sin(lat1), cos(lat1));
+    let (sin2, cos2) = (sin(lat2), cos(lat2));
+
+    let east = cos2 * sin(delta);
+    let north = cos1 * sin2 - sin1 * cos2 * cos(delta);
+    let along = sin1 * sin2 + cos1 * cos2 * cos(delta);
+    atan2(sqrt(east * east + north * north), along)
+}
+
+/// GOTO target in either coordinate frame
+#[cfg(feature = "float")]
+#[derive(Copy, Clone, Debug)]
+pub enum Target {
+    RaDec(RaDec),
+    AzmAlt(AzmAlt),
+}
+
+#[cfg(feature = "float")]
+impl Target {
+    /// Moves the target by `degrees` along both axes, wrapping RA/Azm and
+    /// clamping Dec/Alt at the poles.
+    pub(crate) fn offset(&self, degrees: f32) -> Target {
+        let wrap = |value: f32| {
+            let value = (value + degrees) % 360.0;
+            if value < 0.0 {
+                value + 360.0
+            } else {
+                value
+            }
+        };
+        let clamp = |value: f32| (value + degrees).clamp(-90.0, 90.0);
+
+        match *self {
+            Target::RaDec(RaDec { ra, dec }) => Target::RaDec(RaDec {
+                ra: wrap(ra),
+                dec: clamp(dec),
+            }),
+            Target::AzmAlt(AzmAlt { azm, alt }) => Target::AzmAlt(AzmAlt {
+                azm: wrap(azm),
+                alt: clamp(alt),
+            }),
+        }
+    }
+}
+
+/// Direction of the final GOTO of [`NexStar::goto_with_approach`](crate::NexStar::goto_with_approach) on both
+/// axes
+#[cfg(feature = "float")]
+#[derive(Copy, Clone, Debug)]
+pub enum Approach {
+    /// Approach with increasing RA/Azm and Dec/Alt
+    Positive,
+    /// Approach with decreasing RA/Azm and Dec/Alt
+    Negative,
+}
+
+/// Drift of the position measured by
+/// [`NexStar::measure_drift`](crate::NexStar::measure_drift)
+#[cfg(feature = "float")]
+#[derive(Copy, Clone, Debug)]
+pub struct Drift {
+    /// Drift of the RA axis in arcsec/min, not scaled by the cosine of the
+    /// declination
+    pub ra: f32,
+    /// Drift of the Dec axis in arcsec/min
+    pub dec: f32,
+    /// Samples the drift was fitted to
+    pub samples: u32,
+    /// Samples skipped because their read failed
+    pub skipped: u32,
+}
+
+/// End of the response of a [`NexStar::raw_command`](crate::NexStar::raw_command)
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Terminator {
+    /// The response ends with a `#`, as all HC responses do.
+    Hash,
+    /// The response is exactly that many bytes, including any `#`.
+    Exact(usize),
+}
+
+/// Length of the response of a
+/// [`NexStar::pass_through`](crate::NexStar::pass_through) command
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ResponseLength {
+    /// The length of a [`Command`](crate::Command) the driver knows for the
+    /// device, other commands are read like `UpTo` the longest HC response.
+    Auto,
+    /// Exactly that many bytes
+    Exact(u8),
+    /// At most that many bytes, the response ends at the first `#`. Only for
+    /// responses which can't contain a `#`, e.g. text.
+    UpTo(u8),
+}
+
+/// Health of the link to the Hand Controller (HC)
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LinkState {
+    /// The last command completed normally.
+    Clean,
+    /// A command failed and the receive buffer may hold a partial response.
+    Dirty,
+}
+
+/// Outcome of [`NexStar::probe_link`](crate::NexStar::probe_link)
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LinkProbe {
+    /// Every byte came back.
+    Ok,
+    /// Something answered with other bytes, e.g. a wire looping the command
+    /// back or a wrong baud rate.
+    WrongEcho,
+    /// Nothing answered in time.
+    Silent,
+}
+
+/// Outcome of the recent keepalives, see
+/// [`NexStar::track_link_health`](crate::NexStar::track_link_health)
+#[derive(Copy, Clone, Debug)]
+pub struct LinkHealth {
+    clock: fn() -> u32,
+    /// Tick of the last success
+    last_success: Option<u32>,
+    consecutive_failures: u32,
+}
+
+impl LinkHealth {
+    /// Creates the accumulator, `clock` returns a millisecond tick which may
+    /// wrap.
+    pub fn new(clock: fn() -> u32) -> LinkHealth {
+        LinkHealth {
+            clock,
+            last_success: None,
+            consecutive_failures: 0,
+        }
+    }
+
+    /// Records the outcome of a keepalive.
+    pub fn record(&mut self, success: bool) {
+        if success {
+            self.last_success = Some((self.clock)());
+            self.consecutive_failures = 0;
+        } else {
+            self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+        }
+    }
+
+    /// Gets the number of failures since the last success.
+    pub fn consecutive_failures(&self) -> u32 {
+        self.consecutive_failures
+    }
+
+    /// Gets the time since the last success in ms, `None` if there was none.
+    pub fn since_success_ms(&self) -> Option<u32> {
+        self.last_success
+            .map(|last| (self.clock)().wrapping_sub(last))
+    }
+}
+
+/// Identity of the connected mount as queried by [`NexStar::connect`](crate::NexStar::connect).
+#[derive(Copy, Clone, Debug)]
+pub struct MountInfo {
+    /// Telescope mount model, `Model::Unknown(0)` if the HC lacks the model
+    /// query, see [`Compat`]
+    pub model: Model,
+    /// Hand Controller (HC) firmware version
+    pub hc_version: Version,
+    /// AZM/RA Motor firmware version, `None` if the motor didn't answer
+    pub azm_ra_version: Option<Version>,
+    /// ALT/DEC Motor firmware version, `None` if the motor didn't answer
+    pub alt_dec_version: Option<Version>,
+}
+
+/// Firmware versions cached by the driver, see
+/// [`NexStar::cached_versions`](crate::NexStar::cached_versions)
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Versions {
+    /// Hand Controller (HC) firmware version
+    pub hc: Option<Version>,
+    devices: [Option<Version>; DEVICES],
+}
+
+impl Versions {
+    /// Gets the version of a sub device, `None` unless it was read.
+    pub fn device(&self, device: Device) -> Option<Version> {
+        self.devices[device.index()]
+    }
+
+    pub(crate) fn set_device(&mut self, device: Device, version: Version) {
+        self.devices[device.index()] = Some(version);
+    }
+}
+
+/// Protocol family of a hand controller, see [`Compat`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Family {
+    /// Celestron NexStar
+    NexStar,
+    /// Celestron StarSense HC, which answers the version with a build
+    /// number and flags the model id. It refuses most commands during its
+    /// automatic alignment.
+    StarSense,
+    /// SkyWatcher SynScan, which answers the version as hex digits, numbers
+    /// the models differently and knows the PEC tracking mode instead of
+    /// EQ South. The motors don't answer the AUX version query.
+    #[cfg(feature = "synscan")]
+    SynScan,
+}
+
+/// Protocol differences of legacy hand controllers like the original GT HC
+/// and of other [`Family`]s, see
+/// [`NexStar::set_compat`](crate::NexStar::set_compat)
+///
+/// Commands the HC lacks fail with
+/// [`Error::UnsupportedFirmware`](crate::Error::UnsupportedFirmware) instead
+/// of being sent.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Compat {
+    /// Protocol family of the HC
+    pub family: Family,
+    /// The HC answers the `K` echo. Without it a dirty link is resynced by
+    /// discarding the pending bytes only.
+    pub echo: bool,
+    /// The HC answers the `m` model query.
+    pub model_query: bool,
+    /// The HC answers the standard positions with 5 instead of 4 hex digits
+    /// per axis (20 bits per revolution). Such HCs predate the GOTO commands,
+    /// which are sent in the standard format regardless.
+    pub wide_positions: bool,
+}
+
+impl Compat {
+    /// Hand controllers supporting the whole protocol
+    pub const CURRENT: Compat = Compat {
+        family: Family::NexStar,
+        echo: true,
+        model_query: true,
+        wide_positions: false,
+    };
+
+    /// Celestron StarSense hand controllers, experimental as their encodings
+    /// are reconstructed from reports instead of captures
+    pub const STARSENSE: Compat = Compat {
+        family: Family::StarSense,
+        ..Compat::CURRENT
+    };
+
+    /// SkyWatcher SynScan hand controllers
+    #[cfg(feature = "synscan")]
+    pub const SYNSCAN: Compat = Compat {
+        family: Family::SynScan,
+        ..Compat::CURRENT
+    };
+
+    /// Gets the differences of the NexStar HC firmware `version`, as picked by
+    /// [`NexStar::connect`](crate::NexStar::connect).
+    pub fn for_version(version: Version) -> Compat {
+        Compat {
+            family: Family::NexStar,
+            echo: version >= ECHO_VERSION,
+            model_query: version >= MODEL_VERSION,
+            wide_positions: version < NARROW_POSITION_VERSION,
+        }
+    }
+}
+
+impl Default for Compat {
+    fn default() -> Compat {
+        Compat::CURRENT
+    }
+}
+
+/// State of the GPS unit, see [`NexStar::gps_state`](crate::NexStar::gps_state)
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GpsState {
+    /// No GPS unit answered.
+    Absent,
+    /// The GPS unit is present but has no fix yet, its position and time
+    /// read as zeros.
+    NoFix,
+    /// The GPS unit has a fix.
+    Linked,
+}
+
+/// Position and UTC date and time reported by the GPS unit, see
+/// [`NexStar::gps_fix`](crate::NexStar::gps_fix)
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct GpsFix {
+    /// Latitude in arcseconds, north positive
+    pub latitude: i32,
+    /// Longitude in arcseconds, east positive
+    pub longitude: i32,
+    pub utc: UtcDateTime,
+}
+
+/// Travel limits of the focus motor in counts, found by
+/// [`NexStar::focuser_calibrate`](crate::NexStar::focuser_calibrate)
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct FocuserLimits {
+    pub low: u32,
+    pub high: u32,
+}
+
+/// Index switches found by [`NexStar::find_home`](crate::NexStar::find_home)
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct HomeStatus {
+    pub azm_ra_found: bool,
+    pub alt_dec_found: bool,
+}
+
+/// Backlash correction of a motor, see
+/// [`NexStar::set_backlash`](crate::NexStar::set_backlash)
+///
+/// The HC menu offers 0..=99 per direction, 0 disables the correction.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Backlash {
+    /// Correction when reversing into the positive direction
+    pub positive: u8,
+    /// Correction when reversing into the negative direction
+    pub negative: u8,
+}
+
+impl Backlash {
+    /// Largest correction the HC menu offers
+    pub const MAX: u8 = 99;
+    /// Approximate arcseconds of the axis one step of the correction takes up
+    pub const ARCSEC_PER_STEP: f32 = 10.0;
+
+    /// Converts the play in arcseconds per direction into the nearest
+    /// corrections, at most [`Backlash::MAX`].
+    pub fn from_arcsec(positive: f32, negative: f32) -> Backlash {
+        let steps = |arcsec: f32| {
+            let steps = arcsec / Backlash::ARCSEC_PER_STEP + 0.5;
+            steps.clamp(0.0, Backlash::MAX as f32) as u8
+        };
+        Backlash {
+            positive: steps(positive),
+            negative: steps(negative),
+        }
+    }
+}
+
+/// Play of a motor measured by
+/// [`NexStar::measure_backlash`](crate::NexStar::measure_backlash) in
+/// arcseconds
+#[cfg(feature = "float")]
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct BacklashMeasurement {
+    /// Play taken up when reversing into the positive direction
+    pub positive: f32,
+    /// Play taken up when reversing into the negative direction
+    pub negative: f32,
+}
+
+#[cfg(feature = "float")]
+impl BacklashMeasurement {
+    /// Gets the correction taking up the play, see
+    /// [`NexStar::set_backlash`](crate::NexStar::set_backlash).
+    pub fn setting(&self) -> Backlash {
+        Backlash::from_arcsec(self.positive, self.negative)
+    }
+}
+
+/// Aggregate state of the mount as polled by [`NexStar::status`](crate::NexStar::status).
+#[derive(Copy, Clone, Debug)]
+pub struct MountStatus {
+    /// The alignment is complete.
+    pub aligned: bool,
+    /// A GOTO is in progress.
+    pub goto_in_progress: bool,
+    /// Tracking mode
+    pub tracking: TrackingMode,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(year: u16, month: u8, day: u8, hour: u8) -> UtcDateTime {
+        UtcDateTime {
+            year,
+            month,
+            day,
+            hour,
+            minutes: 30,
+            seconds: 15,
+        }
+    }
+
+    #[test]
+    fn utc_next_day() {
+        let datetime = DateTime::from_utc(utc(2026, 3, 14, 23), 10, false).unwrap();
+        assert_eq!((datetime.year, datetime.month, datetime.day), (26, 3, 15));
+        assert_eq!(
+            (datetime.hour, datetime.minutes, datetime.seconds),
+            (9, 30, 15)
+        );
+        assert_eq!((datetime.zone, datetime.daylight_saving), (10, false));
+        assert_eq!(datetime.to_utc(), utc(2026, 3, 14, 23));
+    }
+
+    #[test]
+    fn utc_previous_day() {
+        let datetime = DateTime::from_utc(utc(2026, 3, 1, 0), -5, false).unwrap();
+        assert_eq!((datetime.year, datetime.month, datetime.day), (26, 2, 28));
+        assert_eq!(datetime.hour, 19);
+        assert_eq!(datetime.to_utc(), utc(2026, 3, 1, 0));
+
+        let datetime = DateTime::from_utc(utc(2024, 3, 1, 0), -5, true).unwrap();
+        assert_eq!((datetime.month, datetime.day, datetime.hour), (2, 29, 20));
+        assert_eq!(datetime.to_utc(), utc(2024, 3, 1, 0));
+    }
+
+    #[test]
+    fn utc_daylight_saving() {
+        let standard = DateTime::from_utc(utc(2026, 7, 1, 12), 1, false).unwrap();
+        let summer = DateTime::from_utc(utc(2026, 7, 1, 12), 1, true).unwrap();
+        assert_eq!((standard.hour, summer.hour), (13, 14));
+        assert_eq!(standard.to_utc(), summer.to_utc());
+
+        let datetime = DateTime::from_utc(utc(2026, 7, 1, 22), 1, true).unwrap();
+        assert_eq!((datetime.month, datetime.day, datetime.hour), (7, 2, 0));
+    }
+
+    #[test]
+    fn utc_year_rollover() {
+        let datetime = DateTime::from_utc(utc(2025, 12, 31, 23), 1, false).unwrap();
+        assert_eq!((datetime.year, datetime.month, datetime.day), (26, 1, 1));
+        assert_eq!(datetime.hour, 0);
+        assert_eq!(datetime.to_utc(), utc(2025, 12, 31, 23));
+
+        let datetime = DateTime::from_utc(utc(2026, 1, 1, 2), -8, false).unwrap();
+        assert_eq!((datetime.year, datetime.month, datetime.day), (25, 12, 31));
+        assert_eq!(datetime.hour, 18);
+        assert_eq!(datetime.to_utc(), utc(2026, 1, 1, 2));
+
+        // the HC can't store years before 2000
+        assert_eq!(DateTime::from_utc(utc(2000, 1, 1, 2), -8, false), None);
+    }
+
+    fn local(year: u8, month: u8, day: u8, hour: u8, minutes: u8, seconds: u8) -> DateTime {
+        DateTime {
+            hour,
+            minutes,
+            seconds,
+            zone: -5,
+            daylight_saving: true,
+            year,
+            month,
+            day,
+        }
+    }
+
+    #[test]
+    fn add_seconds_within_minute() {
+        let datetime = local(26, 10, 14, 22, 30, 15);
+        assert_eq!(datetime.add_seconds(0), datetime);
+        assert_eq!(datetime.add_seconds(2), local(26, 10, 14, 22, 30, 17));
+    }
+
+    #[test]
+    fn add_seconds_carries() {
+        // minute and hour
+        assert_eq!(
+            local(26, 10, 14, 22, 30, 59).add_seconds(1),
+            local(26, 10, 14, 22, 31, 0)
+        );
+        assert_eq!(
+            local(26, 10, 14, 22, 59, 58).add_seconds(3),
+            local(26, 10, 14, 23, 0, 1)
+        );
+        // day, month and leap day
+        assert_eq!(
+            local(26, 10, 14, 23, 59, 59).add_seconds(1),
+            local(26, 10, 15, 0, 0, 0)
+        );
+        assert_eq!(
+            local(26, 4, 30, 23, 59, 59).add_seconds(2),
+            local(26, 5, 1, 0, 0, 1)
+        );
+        assert_eq!(
+            local(24, 2, 28, 23, 59, 59).add_seconds(1),
+            local(24, 2, 29, 0, 0, 0)
+        );
+        assert_eq!(
+            local(26, 2, 28, 23, 59, 59).add_seconds(1),
+            local(26, 3, 1, 0, 0, 0)
+        );
+        // year, keeping the zone
+        let datetime = local(25, 12, 31, 23, 59, 59).add_seconds(1);
+        assert_eq!(datetime, local(26, 1, 1, 0, 0, 0));
+        assert_eq!((datetime.zone, datetime.daylight_saving), (-5, true));
+        // several days at once
+        assert_eq!(
+            local(26, 10, 14, 22, 30, 15).add_seconds(2 * 86_400 + 3_600),
+            local(26, 10, 16, 23, 30, 15)
+        );
+    }
+
+    #[test]
+    fn backlash_from_arcsec() {
+        let backlash = Backlash::from_arcsec(124.0, 125.0);
+        assert_eq!((backlash.positive, backlash.negative), (12, 13));
+        // clamped to the range of the HC menu
+        let backlash = Backlash::from_arcsec(-5.0, 5000.0);
+        assert_eq!((backlash.positive, backlash.negative), (0, Backlash::MAX));
+        assert_eq!(Backlash::from_arcsec(f32::NAN, 0.0), Backlash::default());
+    }
+
+    #[test]
+    fn version_ordering() {
+        assert!(Version::new(4, 9) < Version::new(4, 21));
+        assert!(Version::new(4, 21) < Version::new(5, 0));
+        assert!(Version::new(1, 6) > Version::new(1, 2));
+        assert_eq!(Version::new(4, 10), Version::new(4, 10));
+    }
+
+    #[test]
+    fn compat_of_versions() {
+        assert_eq!(Compat::for_version(Version::new(4, 21)), Compat::CURRENT);
+        assert_eq!(Compat::for_version(Version::new(2, 2)), Compat::CURRENT);
+        let compat = Compat::for_version(Version::new(1, 2));
+        assert!(compat.echo && !compat.model_query && !compat.wide_positions);
+        let compat = Compat::for_version(Version::new(1, 0));
+        assert!(!compat.echo && !compat.model_query && compat.wide_positions);
+    }
+
+    #[cfg(feature = "synscan")]
+    #[test]
+    fn synscan_model_ids() {
+        assert_eq!(Model::from_synscan_bits(0), Model::Eq6);
+        assert_eq!(Model::from_synscan_bits(6), Model::AzEq5);
+        assert_eq!(Model::from_synscan_bits(128), Model::AzGoto);
+        assert_eq!(Model::from_synscan_bits(159), Model::DobGoto);
+        assert_eq!(Model::from_synscan_bits(160), Model::AllView);
+        assert_eq!(Model::from_synscan_bits(7), Model::Unknown(7));
+        assert!(Model::Heq5.capabilities().is_gem);
+        assert!(!Model::DobGoto.capabilities().is_gem);
+    }
+
+    #[test]
+    fn ra_dec_arcsec_format() {
+        extern crate std;
+        use std::string::ToString;
+
+        let position = RaDecArcsec {
+            ra: 324_015,
+            dec: -162_000,
+        };
+        assert_eq!(position.to_string(), "06h00m01s -45°00'00\"");
+        let position = RaDecArcsec {
+            ra: 1_295_999,
+            dec: 3_661,
+        };
+        assert_eq!(position.to_string(), "23h59m59s +01°01'01\"");
+        assert_eq!(TrackingMode::EQSouth.to_string(), "EQ South");
+    }
+
+    #[test]
+    fn motor_model_ids() {
+        assert_eq!(MotorModel::from_bits(0x01), MotorModel::Gen1);
+        assert_eq!(MotorModel::from_bits(0x02), MotorModel::Gen2);
+        assert_eq!(MotorModel::from_bits(0x03), MotorModel::Gen3);
+        assert_eq!(MotorModel::from_bits(0x42), MotorModel::Unknown(0x42));
+        assert!(!MotorModel::Gen1.supports_guide_pulses());
+        assert!(MotorModel::Gen3.supports_guide_pulses());
+        assert!(!MotorModel::Unknown(0x42).supports_guide_pulses());
+        assert!(MotorModel::Gen2.has_fine_guiderate());
+        assert!(!MotorModel::Gen1.has_fine_guiderate());
+        assert!(!MotorModel::Unknown(0x42).has_fine_guiderate());
+    }
+
+    #[test]
+    fn model_capabilities() {
+        let gem = Model::CGE.capabilities();
+        assert!(gem.is_gem && gem.has_rtc);
+        assert!(!gem.has_builtin_gps && !gem.supports_cordwrap);
+
+        let alt_az = Model::SLT.capabilities();
+        assert!(!alt_az.is_gem && alt_az.supports_cordwrap);
+        assert!(!alt_az.has_rtc && !alt_az.has_builtin_gps);
+        assert!(Model::CPC.capabilities().has_builtin_gps);
+
+        let unknown = Model::Unknown(0x42).capabilities();
+        assert!(unknown.has_rtc && unknown.has_builtin_gps && unknown.supports_cordwrap);
+        assert!(!unknown.is_gem);
+    }
+
+    #[test]
+    fn version_at_least() {
+        assert!(Version::new(4, 21).at_least(4, 10));
+        assert!(Version::new(4, 10).at_least(4, 10));
+        assert!(!Version::new(4, 9).at_least(4, 10));
+        assert!(Version::new(5, 0).at_least(4, 21));
+        assert!(!Version::new(4, 99).at_least(5, 0));
+        assert!(Version::new(5, 0).at_least(5, 0));
+    }
+
+    #[test]
+    fn version_sorting() {
+        use core::cmp::Ordering;
+
+        let mut versions = [
+            Version::new(5, 0),
+            Version::new(4, 21),
+            Version::new(1, 6),
+            Version::new(4, 9),
+            Version::new(4, 10),
+        ];
+        versions.sort();
+        assert_eq!(
+            versions,
+            [
+                Version::new(1, 6),
+                Version::new(4, 9),
+                Version::new(4, 10),
+                Version::new(4, 21),
+                Version::new(5, 0),
+            ]
+        );
+        // the major version wins over any minor one
+        assert_eq!(Version::new(4, 99).cmp(&Version::new(5, 0)), Ordering::Less);
+        assert_eq!(
+            Version::new(4, 21).cmp(&Version::new(4, 21)),
+            Ordering::Equal
+        );
+        assert_eq!(versions.iter().max(), Some(&Version::new(5, 0)));
+    }
+
+    #[cfg(feature = "float")]
+    #[test]
+    fn dms_seconds_carry() {
+        assert_eq!(dec_dms(10.999_99), [11, 0, 0, 0]);
+        assert_eq!(dec_dms(-0.5), [0, 30, 0, 1]);
+    }
+
+    #[cfg(feature = "float")]
+    #[test]
+    fn dms_southern_zero_roundtrip() {
+        // found by the time_location fuzz target: -0 was sent back as north
+        let (latitude, longitude) = crate::parse::location_dms(&[0, 0, 0, 1, 0, 0, 0, 1]).unwrap();
+        let location = Location::from_dms(latitude, longitude).unwrap();
+        assert_eq!(location.to_dms(), (latitude, longitude));
+    }
+
+    fn dms(degrees: u8, minutes: u8, seconds: u8, hemisphere: Hemisphere) -> Dms {
+        Dms {
+            degrees,
+            minutes,
+            seconds,
+            hemisphere,
+        }
+    }
+
+    #[test]
+    fn dms_limits() {
+        assert!(dms(90, 0, 0, Hemisphere::South).is_latitude());
+        assert!(!dms(90, 0, 1, Hemisphere::North).is_latitude());
+        assert!(!dms(89, 60, 0, Hemisphere::North).is_latitude());
+        assert!(!dms(89, 0, 60, Hemisphere::North).is_latitude());
+        assert!(!dms(10, 0, 0, Hemisphere::East).is_latitude());
+
+        assert!(dms(180, 0, 0, Hemisphere::West).is_longitude());
+        assert!(dms(179, 59, 59, Hemisphere::East).is_longitude());
+        assert!(!dms(180, 1, 0, Hemisphere::East).is_longitude());
+        assert!(!dms(10, 0, 0, Hemisphere::North).is_longitude());
+    }
+
+    #[test]
+    fn dms_arcsec() {
+        assert_eq!(dms(0, 7, 31, Hemisphere::South).to_arcsec(), -451);
+        assert_eq!(
+            Dms::from_latitude_arcsec(-451),
+            Some(dms(0, 7, 31, Hemisphere::South))
+        );
+        assert_eq!(
+            Dms::from_latitude_arcsec(324_000),
+            Some(dms(90, 0, 0, Hemisphere::North))
+        );
+        assert_eq!(Dms::from_latitude_arcsec(324_001), None);
+        assert_eq!(
+            Dms::from_longitude_arcsec(-648_000),
+            Some(dms(180, 0, 0, Hemisphere::West))
+        );
+        assert_eq!(Dms::from_longitude_arcsec(648_001), None);
+        assert_eq!(Dms::from_longitude_arcsec(i32::MIN), None);
+    }
+
+    #[cfg(feature = "float")]
+    #[test]
+    fn location_from_dms() {
+        assert!(Location::from_dms(
+            dms(90, 0, 0, Hemisphere::North),
+            dms(180, 0, 0, Hemisphere::West)
+        )
+        .is_some());
+        // swapped axes
+        assert!(Location::from_dms(
+            dms(8, 0, 0, Hemisphere::East),
+            dms(47, 0, 0, Hemisphere::North)
+        )
+        .is_none());
+    }
+
+    #[cfg(feature = "float")]
+    #[test]
+    fn dms_from_degrees() {
+        assert_eq!(
+            Dms::from_latitude(-90.0),
+            Some(dms(90, 0, 0, Hemisphere::South))
+        );
+        assert_eq!(Dms::from_latitude(90.000_2), None);
+        assert_eq!(
+            Dms::from_longitude(180.0),
+            Some(dms(180, 0, 0, Hemisphere::East))
+        );
+        assert_eq!(Dms::from_longitude(-180.001), None);
+        assert_eq!(Dms::from_longitude(f32::NAN), None);
+        // rounds and carries into the degrees
+        assert_eq!(
+            Dms::from_longitude(-10.999_9),
+            Some(dms(11, 0, 0, Hemisphere::West))
+        );
+        assert_eq!(
+            Dms::from_latitude(0.0),
+            Some(dms(0, 0, 0, Hemisphere::North))
+        );
+    }
+
+    #[cfg(feature = "float")]
+    #[test]
+    fn dms_to_degrees() {
+        assert_eq!(dms(0, 30, 0, Hemisphere::South).to_degrees(), -0.5);
+        assert_eq!(dms(0, 0, 0, Hemisphere::East).to_degrees(), 0.0);
+        assert_eq!(dms(180, 0, 0, Hemisphere::West).to_degrees(), -180.0);
+    }
+
+    #[cfg(feature = "float")]
+    #[test]
+    fn dms_roundtrip() {
+        // every latitude second and the longitude seconds of a few degrees
+        // survive the conversion to degrees
+        for seconds in 0..=90 * 3600 {
+            let value = dms(
+                (seconds / 3600) as u8,
+                (seconds / 60 % 60) as u8,
+                (seconds % 60) as u8,
+                Hemisphere::South,
+            );
+            assert_eq!(Dms::from_latitude(value.to_degrees()), Some(value));
+        }
+        for &degrees in &[0, 1, 89, 90, 91, 179] {
+            for seconds in 0..3600 {
+                let value = dms(
+                    degrees,
+                    (seconds / 60) as u8,
+                    (seconds % 60) as u8,
+                    Hemisphere::East,
+                );
+                assert_eq!(Dms::from_longitude(value.to_degrees()), Some(value));
+            }
+        }
+    }
+
+    #[cfg(feature = "float")]
+    fn assert_separation(first: (f32, f32), second: (f32, f32), expected: f32) {
+        let distance = separation(first, second);
+        assert!(
+            (distance - expected).abs() < 1e-5,
+            "{:?} {:?}: {}",
+            first,
+            second,
+            distance
+        );
+        assert!((separation(second, first) - distance).abs() < 1e-6);
+    }
+
+    #[cfg(feature = "float")]
+    #[test]
+    fn separation_wraps() {
+        assert_separation((359.99, 0.0), (0.01, 0.0), 0.02);
+        assert_separation((0.0, 10.0), (360.0, 10.0), 0.0);
+        assert_separation((-0.25, 0.0), (359.5, 0.0), 0.25);
+    }
+
+    #[cfg(feature = "float")]
+    #[test]
+    fn separation_near_pole() {
+        // opposite RA across the pole
+        assert_separation((0.0, 89.999), (180.0, 89.999), 0.002);
+        assert_separation((10.0, 90.0), (250.0, 90.0), 0.0);
+        assert_separation((0.0, -89.0), (90.0, -89.0), 1.414_18);
+    }
+
+    #[cfg(feature = "float")]
+    #[test]
+    fn separation_large() {
+        assert_separation((0.0, 0.0), (180.0, 0.0), 180.0);
+        assert_separation((0.0, 0.0), (0.0, 90.0), 90.0);
+        assert_separation((123.0, -45.0), (123.0, -45.0), 0.0);
+    }
+
+    #[cfg(feature = "float")]
+    fn assert_separation_arcsec(first: RaDec, second: RaDec, expected: f32) {
+        let distance = first.separation_arcsec(second);
+        assert!(
+            (distance - expected).abs() < 0.01 + expected * 1e-7,
+            "{:?} {:?}: {}",
+            first,
+            second,
+            distance
+        );
+    }
+
+    #[cfg(feature = "float")]
+    #[test]
+    fn separation_arcsec_reference() {
+        let ra_dec = |ra, dec| RaDec { ra, dec };
+        // identical points
+        assert_separation_arcsec(ra_dec(83.822, -5.391), ra_dec(83.822, -5.391), 0.0);
+        // straddling RA 0h
+        assert_separation_arcsec(ra_dec(359.75, 0.0), ra_dec(0.25, 0.0), 1800.0);
+        assert_separation_arcsec(ra_dec(359.5, 30.0), ra_dec(0.5, 30.0), 3117.682);
+        // antipodal and nearly antipodal points
+        assert_separation_arcsec(ra_dec(10.0, 20.0), ra_dec(190.0, -20.0), 648_000.0);
+        assert_separation_arcsec(ra_dec(0.0, 0.0), ra_dec(180.0, 0.001), 647_996.4);
+        // Betelgeuse and Rigel
+        assert_separation_arcsec(
+            ra_dec(88.792_94, 7.407_06),
+            ra_dec(78.634_47, -8.201_64),
+            66_981.448,
+        );
+
+        let azm_alt = AzmAlt {
+            azm: 0.0,
+            alt: 89.0,
+        };
+        let distance = azm_alt.separation_arcsec(AzmAlt {
+            azm: 180.0,
+            alt: 89.0,
+        });
+        assert!((distance - 7200.0).abs() < 0.01);
+    }
+
+    #[cfg(feature = "float")]
+    #[test]
+    fn target_offset_wraps_and_clamps() {
+        match Target::RaDec(RaDec {
+            ra: 359.9,
+            dec: 89.9,
+        })
+        .offset(0.2)
+        {
+            Target::RaDec(RaDec { ra, dec }) => {
+                assert!((ra - 0.1).abs() < 1e-3);
+                assert_eq!(dec, 90.0);
+            }
+            _ => panic!(),
+        }
+        match Target::AzmAlt(AzmAlt {
+            azm: 0.1,
+            alt: -89.9,
+        })
+        .offset(-0.2)
+        {
+            Target::AzmAlt(AzmAlt { azm, alt }) => {
+                assert!((azm - 359.9).abs() < 1e-3);
+                assert_eq!(alt, -90.0);
+            }
+            _ => panic!(),
+        }
+    }
+}
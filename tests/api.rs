@@ -0,0 +1,698 @@
+//! Compile-time check of the public API paths.
+#![cfg(feature = "float")]
+
+use embedded_hal::serial;
+use nexstar::aux::{AuxBus, FrameError, Packet};
+use nexstar::battery::state_of_charge;
+use nexstar::detect::{detect_baud, detect_protocol, Detected, ProtocolKind, PROBE_TIMEOUT_MS};
+use nexstar::encoder::{Resolution, FRACTION_COUNTS};
+use nexstar::pe::{stats, PeRecorder, PeStats};
+use nexstar::pointing::{Fit, PointingModel};
+use nexstar::queue::{mosaic, Mosaic, QueueEntry, TargetQueue, Visit};
+use nexstar::{
+    Approach, AzmAlt, Backlash, BacklashMeasurement, Borrowed, Capabilities, Command, Compat,
+    Context, CoordinateParseError, DateTime, Device, Dms, Drift, Error, Family, FocuserLimits,
+    GpsFix, GpsState, GuideRate, Hemisphere, HomeStatus, LinkHealth, LinkProbe, LinkState,
+    Location, Model, MotorModel, MountConfig, MountInfo, MountStatus, NexStar, Positions, RaDec,
+    ReleaseReport, ResponseLength, RestoreReport, SlewGuard, Target, Terminator, TrackingMode,
+    TrackingRate, UtcDateTime, Version, Versions,
+};
+
+#[allow(dead_code)]
+fn driver_api<T, U>()
+where
+    T: serial::Read<u8>,
+    U: serial::Write<u8>,
+{
+    let _: fn(T, U) -> NexStar<T, U> = NexStar::new;
+    let _ = NexStar::<T, U>::connect;
+    let _ = NexStar::<T, U>::mount_info;
+    let _ = NexStar::<T, U>::set_firmware_checks;
+    let _ = NexStar::<T, U>::set_capability_checks;
+    let _: fn(&mut NexStar<T, U>, Option<Compat>) = NexStar::<T, U>::set_compat;
+    let _ = NexStar::<T, U>::set_starsense_detection;
+    let _: fn(&NexStar<T, U>) -> Compat = NexStar::<T, U>::compat;
+    let _: fn(&NexStar<T, U>) -> Option<Model> = NexStar::<T, U>::cached_model;
+    let _: fn(&NexStar<T, U>) -> Versions = NexStar::<T, U>::cached_versions;
+    let _ = NexStar::<T, U>::invalidate_cache;
+    let _ = NexStar::<T, U>::set_force_refresh;
+    let _: fn(&NexStar<T, U>) -> Option<Target> = NexStar::<T, U>::last_target;
+    let _ = NexStar::<T, U>::on_target;
+    let _: fn((f32, f32), (f32, f32)) -> f32 = nexstar::separation;
+    let _: fn((f32, f32), (f32, f32)) -> f32 = nexstar::separation_arcsec;
+    let _: fn(&mut NexStar<T, U>) -> Result<MountStatus, _> = NexStar::<T, U>::status;
+    let _: fn(&mut NexStar<T, U>) -> Result<UtcDateTime, _> = NexStar::<T, U>::rtc_time;
+    let _: Capabilities = Model::SLT.capabilities();
+    let _ = NexStar::<T, U>::raw_command;
+    let _ = [Terminator::Hash, Terminator::Exact(1)];
+    let _ = NexStar::<T, U>::pass_through;
+    let _ = NexStar::<T, U>::set_diagnostics;
+    let _ = NexStar::<T, U>::dump_diagnostics;
+    let _ = [
+        ResponseLength::Auto,
+        ResponseLength::Exact(1),
+        ResponseLength::UpTo(1),
+    ];
+    let _: u8 = Command::GetDeviceVersion.response_len();
+    let _ = NexStar::<T, U>::set_abort_hook;
+    let _ = NexStar::<T, U>::ra_dec;
+    let _ = NexStar::<T, U>::precise_ra_dec;
+    let _ = NexStar::<T, U>::azm_alt;
+    let _ = NexStar::<T, U>::precise_azm_alt;
+    let _ = NexStar::<T, U>::goto_ra_dec;
+    let _ = NexStar::<T, U>::precise_goto_ra_dec;
+    #[cfg(feature = "astro")]
+    let _ = NexStar::<T, U>::goto_ra_dec_checked;
+    #[cfg(feature = "astro")]
+    let _ = NexStar::<T, U>::set_refraction;
+    let _ = NexStar::<T, U>::set_goto_floor;
+    let _ = NexStar::<T, U>::goto_azm_alt;
+    let _ = NexStar::<T, U>::precise_goto_azm_alt;
+    let _ = NexStar::<T, U>::goto;
+    let _ = NexStar::<T, U>::cancel_goto;
+    let _ = NexStar::<T, U>::sync_ra_dec;
+    let _ = NexStar::<T, U>::precise_sync_ra_dec;
+    let _ = NexStar::<T, U>::tracking_mode;
+    let _ = NexStar::<T, U>::set_tracking_mode;
+    let _ = NexStar::<T, U>::set_custom_tracking;
+    let _: fn(&mut NexStar<T, U>, Device, GuideRate) -> Result<(), _> =
+        NexStar::<T, U>::set_guiderate;
+    let _ = NexStar::<T, U>::stop_custom_tracking;
+    let _ = NexStar::<T, U>::track_rates;
+    let _ = NexStar::<T, U>::stop_tracking_rates;
+    let _ = NexStar::<T, U>::begin_slew;
+    let _ = SlewGuard::<T, U>::set_rates;
+    let _ = SlewGuard::<T, U>::nexstar;
+    let _ = SlewGuard::<T, U>::finish;
+    let _ = NexStar::<T, U>::set_max_slew_rate;
+    let _ = NexStar::<T, U>::max_slew_rate;
+    let _ = NexStar::<T, U>::motor_position;
+    let _: fn(&mut NexStar<T, U>, Device) -> Result<f32, _> = NexStar::<T, U>::motor_angle;
+    let _: fn(&mut NexStar<T, U>, Device) -> Result<i32, _> = NexStar::<T, U>::motor_angle_arcsec;
+    let _: fn(&mut NexStar<T, U>, Device, f32) -> Result<(), _> =
+        NexStar::<T, U>::set_motor_position;
+    let _: fn(&mut NexStar<T, U>, Device, i32) -> Result<(), _> =
+        NexStar::<T, U>::set_motor_position_arcsec;
+    let _: fn(&mut NexStar<T, U>) -> Result<bool, _> = NexStar::<T, U>::is_focuser_moving;
+    let _: fn(&mut NexStar<T, U>) -> Result<(), _> = NexStar::<T, U>::wifi_reboot;
+    let _: fn(&mut NexStar<T, U>) -> Result<u32, _> = NexStar::<T, U>::battery_voltage;
+    let _: fn(&mut NexStar<T, U>) -> Result<u8, _> = NexStar::<T, U>::battery_percent;
+    let _: fn(&mut NexStar<T, U>, u8) -> Result<bool, _> = NexStar::<T, U>::is_battery_low;
+    let _: fn(&mut NexStar<T, U>) -> Result<FocuserLimits, _> = NexStar::<T, U>::focuser_limits;
+    let _ = NexStar::<T, U>::set_backlash;
+    let _: fn(&mut NexStar<T, U>, Device) -> Result<Backlash, _> = NexStar::<T, U>::backlash;
+    let _ = NexStar::<T, U>::set_autoguide_rate;
+    let _ = NexStar::<T, U>::guide_pulse;
+    let _ = NexStar::<T, U>::is_guiding;
+    let _: fn(&mut NexStar<T, U>, Device) -> Result<u8, _> = NexStar::<T, U>::autoguide_rate;
+    let _: fn(&mut NexStar<T, U>) -> Result<MountConfig, _> = NexStar::<T, U>::snapshot;
+    let _: fn(&mut NexStar<T, U>, &MountConfig, DateTime) -> RestoreReport<_, _> =
+        NexStar::<T, U>::restore;
+    let _ = NexStar::<T, U>::seek_index;
+    let _ = NexStar::<T, U>::level_start;
+    let _ = NexStar::<T, U>::index_found;
+    let _ = NexStar::<T, U>::level_done;
+    let _ = NexStar::<T, U>::unpark;
+    let _ = NexStar::<T, U>::location;
+    let _ = NexStar::<T, U>::set_location;
+    let _ = NexStar::<T, U>::location_dms;
+    let _ = NexStar::<T, U>::set_location_dms;
+    let _ = NexStar::<T, U>::datetime;
+    let _ = NexStar::<T, U>::set_datetime;
+    let _ = NexStar::<T, U>::set_datetime_compensated;
+    let _ = NexStar::<T, U>::set_datetime_from_gps;
+    let _ = NexStar::<T, U>::is_gps_linked;
+    let _: fn(&mut NexStar<T, U>) -> Result<GpsFix, _> = NexStar::<T, U>::gps_fix;
+    let _: fn(&mut NexStar<T, U>, &mut dyn core::fmt::Write) -> core::fmt::Result =
+        NexStar::<T, U>::write_status;
+    let _ = NexStar::<T, U>::version;
+    let _ = NexStar::<T, U>::device_version;
+    let _ = NexStar::<T, U>::model;
+    let _: fn(&mut NexStar<T, U>, Device) -> Result<MotorModel, _> = NexStar::<T, U>::motor_model;
+    let _ = MotorModel::Unknown(0).supports_guide_pulses();
+    let _ = NexStar::<T, U>::is_alignment_complete;
+    let _ = NexStar::<T, U>::is_goto_in_progress;
+    let _ = NexStar::<T, U>::set_echo_suppression;
+    let _ = NexStar::<T, U>::set_timeout;
+    let _: fn(&mut NexStar<T, U>, bool) = NexStar::<T, U>::set_wifi_reboot_needs_ack;
+    let _ = NexStar::<T, U>::set_slew_watchdog;
+    let _ = NexStar::<T, U>::slew_joystick;
+    let _ = NexStar::<T, U>::clear_slew_watchdog;
+    let _ = NexStar::<T, U>::check_slew_watchdog;
+    let _ = NexStar::<T, U>::with_pacing;
+    let _ = NexStar::<T, U>::clear_timeout;
+    let _ = NexStar::<T, U>::link_state;
+    let _: fn(&mut NexStar<T, U>) -> Result<(), _> = NexStar::<T, U>::keepalive;
+    let _ = NexStar::<T, U>::track_link_health;
+    let _: fn(&NexStar<T, U>) -> Option<LinkHealth> = NexStar::<T, U>::link_health;
+    let _: fn(&mut NexStar<T, U>, u8) -> Result<(), _> = NexStar::<T, U>::echo;
+    let _ = NexStar::<T, U>::set_auto_resync;
+    let _ = NexStar::<T, U>::resync;
+    let _ = NexStar::<T, U>::free;
+
+    let _: fn(T, U) -> AuxBus<T, U> = AuxBus::new;
+    let _ = AuxBus::<T, U>::send;
+    let _ = AuxBus::<T, U>::recv;
+    let _ = AuxBus::<T, U>::command;
+    let _ = AuxBus::<T, U>::motor_position;
+    let _ = AuxBus::<T, U>::goto;
+    let _ = AuxBus::<T, U>::slew;
+    let _ = AuxBus::<T, U>::slew_done;
+    let _ = AuxBus::<T, U>::version;
+    let _ = AuxBus::<T, U>::set_timeout;
+    let _ = AuxBus::<T, U>::clear_timeout;
+}
+
+#[allow(dead_code)]
+fn borrowed_api<T: serial::Read<u8>, U: serial::Write<u8>>(rx: &mut T, tx: &mut U) {
+    let _: NexStar<Borrowed<T>, Borrowed<U>> = NexStar::borrowed(rx, tx);
+}
+
+#[allow(dead_code)]
+fn slew_guard_api<T: serial::Read<u8>, U: serial::Write<u8>>(nexstar: &mut NexStar<T, U>) {
+    if let Ok(guard) = nexstar.begin_slew(0, 0) {
+        let _: SlewGuard<T, U> = guard;
+    }
+}
+
+#[allow(dead_code)]
+fn detect_api<T, U, D>(rx: &mut T, tx: &mut U, delay: &mut D)
+where
+    T: serial::Read<u8>,
+    U: serial::Write<u8>,
+    D: embedded_hal::blocking::delay::DelayMs<u32>,
+{
+    let _: Result<ProtocolKind, Error<T::Error, U::Error>> = detect_protocol(rx, tx, delay);
+    let _ = [ProtocolKind::Hc, ProtocolKind::Aux, ProtocolKind::Neither];
+    let _: u32 = PROBE_TIMEOUT_MS;
+    let detected: Result<Option<Detected<T, U>>, ()> =
+        detect_baud(|_| Err(()), &[9600, 115_200], delay);
+    if let Ok(Some(Detected {
+        baud,
+        protocol,
+        rx,
+        tx,
+    })) = detected
+    {
+        let _: (u32, ProtocolKind, T, U) = (baud, protocol, rx, tx);
+    }
+}
+
+#[allow(dead_code)]
+fn delay_api<T, U, D>(
+    nexstar: &mut NexStar<T, U>,
+    delay: &mut D,
+    datetime: DateTime,
+    clock: fn() -> u32,
+) where
+    T: serial::Read<u8>,
+    U: serial::Write<u8>,
+    D: embedded_hal::blocking::delay::DelayMs<u32>,
+{
+    let target = Target::AzmAlt(AzmAlt { azm: 0.0, alt: 0.0 });
+    let _ = nexstar.wait_for_alignment(delay, 1, 1);
+    let _ = nexstar.wait_for_gps_fix(delay, 1, 1);
+    let _: Result<HomeStatus, _> = nexstar.find_home(delay, 1);
+    let _: Result<FocuserLimits, _> = nexstar.focuser_calibrate(delay, 1);
+    let _ = NexStar::<T, U>::release::<D>;
+    let _ = NexStar::<T, U>::probe_link::<D>;
+    let _ = nexstar.wait_for_goto(delay, 1, 1);
+    let _: Result<DateTime, _> = nexstar.set_datetime_at_boundary(datetime, delay, 1, clock);
+    let _ = nexstar.goto_with_approach(target, 1.0, Approach::Positive, delay);
+    let mut queue = TargetQueue::<1>::new();
+    let _: Result<(), _> = nexstar.run_queue(&mut queue, delay, |_, _| Visit::Continue);
+    let _ = nexstar.park(AzmAlt { azm: 0.0, alt: 0.0 }, delay);
+    let _: Result<u32, _> = nexstar.log_positions(delay, 1, 1, |_, _| true);
+    let _: Result<GpsState, _> = nexstar.gps_state(delay);
+    let _: Result<f32, _> = nexstar.calibrate_slew_rate(Device::AzmRaMotor, 1.0, 1, delay);
+    let _: Result<Drift, _> = nexstar.measure_drift(1, 1, delay);
+    let _: Result<BacklashMeasurement, _> =
+        nexstar.measure_backlash(Device::AzmRaMotor, 1.0, delay);
+    let mut recorder = PeRecorder::new(&mut [], 1, 1, 1);
+    let _: Result<PeStats, _> = nexstar.record_pe(&mut recorder, delay);
+    let _: Positions<T, U, D, RaDec> = nexstar.positions(delay, 1);
+    let _: Positions<T, U, D, AzmAlt> = nexstar.azm_alt_positions(delay, 1);
+}
+
+#[allow(dead_code)]
+fn release_report_api<T, U>(report: ReleaseReport<T, U>) {
+    let _: bool = report.is_ok();
+    let ReleaseReport {
+        cancel_goto: _,
+        stop_rates: _,
+        drain: _,
+    } = report;
+}
+
+#[allow(dead_code)]
+fn error_api<T, U>(error: Error<T, U>) {
+    match error {
+        Error::UnexpectedResponse(Context::Hc(_)) => {}
+        Error::UnexpectedResponse(Context::PassThrough { .. }) => {}
+        Error::UnsupportedFirmware { .. } => {}
+        Error::Timeout => {}
+        Error::Aborted => {}
+        Error::NotOnTarget => {}
+        Error::Truncated { .. } => {}
+        Error::InvalidFrame(_) => {}
+        Error::EchoMismatch => {}
+        Error::LinkDirty => {}
+        Error::InvalidLocation => {}
+        Error::FrameOverflow => {}
+        Error::NoGpsFix => {}
+        Error::InvalidSlewRate => {}
+        Error::UnsupportedCommand(_) => {}
+        Error::HomeNotFound(_) => {}
+        Error::WatchdogFired => {}
+        Error::UnsupportedByModel(_) => {}
+        Error::BufferTooSmall { needed, got } => {
+            let _: (usize, usize) = (needed, got);
+        }
+        Error::TooFewSamples => {}
+        Error::InvalidPosition => {}
+        Error::Busy => {}
+        Error::Aligning(_) => {}
+        Error::BelowAltitudeLimit { .. } => {}
+        Error::Read(_) => {}
+        Error::Write(_) => {}
+    }
+}
+
+#[test]
+fn data_types() {
+    let location = Location {
+        latitude: 47.0,
+        longitude: 8.0,
+    };
+    let _: [u8; 4] = location.lat_dms();
+    let _: [u8; 4] = location.lon_dms();
+    let _ = Drift {
+        ra: 1.0,
+        dec: -1.0,
+        samples: 2,
+        skipped: 0,
+    };
+    let measured = BacklashMeasurement {
+        positive: 10.0,
+        negative: 20.0,
+    };
+    let _: Backlash = measured.setting();
+    let _: Backlash = Backlash::from_arcsec(10.0, 20.0);
+
+    let resolution = Resolution::Known(FRACTION_COUNTS);
+    let _ = [resolution, Resolution::Fractional];
+    let _: (u32, f32) = (
+        resolution.counts_per_revolution(),
+        resolution.arcsec_per_count(),
+    );
+    let _: f32 = resolution.counts_to_degrees(resolution.degrees_to_counts(1.0));
+    let _: u32 = resolution.position_to_counts(0);
+    let _: (u8, f32) = (Backlash::MAX, Backlash::ARCSEC_PER_STEP);
+    let dms = Dms {
+        degrees: 47,
+        minutes: 12,
+        seconds: 34,
+        hemisphere: Hemisphere::North,
+    };
+    let _: Option<Location> = Location::from_dms(dms, dms);
+    let _: Option<Dms> = Dms::from_latitude(47.0);
+    let _: Option<Dms> = Dms::from_longitude(8.0);
+    let _: f32 = dms.to_degrees();
+    let _: bool = dms.is_latitude() && dms.is_longitude();
+    let parsed: Result<Location, CoordinateParseError> = "47°12'34\"N 8°32'07\"E".parse();
+    let parsed = parsed.unwrap();
+    assert_eq!(parsed.to_string(), "47°12'34\" N  008°32'07\" E");
+    assert_eq!(format!("{:#.1}", parsed), "47.2° N  8.5° E");
+    assert_eq!(dms.to_string(), "47°12'34\"N");
+    let _ = CoordinateParseError::SameAxis.to_string();
+    let _: Result<RaDec, CoordinateParseError> = "12:34:56.7 -05:06:07".parse();
+
+    let datetime = DateTime {
+        hour: 0,
+        minutes: 0,
+        seconds: 0,
+        zone: 0,
+        daylight_saving: false,
+        year: 0,
+        month: 1,
+        day: 1,
+    };
+    let utc: UtcDateTime = datetime.to_utc();
+    let _: Option<DateTime> = DateTime::from_utc(utc, 1, true);
+    let _: DateTime = datetime.add_seconds(2);
+    #[cfg(feature = "astro")]
+    {
+        let position = RaDec { ra: 0.0, dec: 0.0 };
+        let _: RaDec = position.j2000_to_jnow(datetime).jnow_to_j2000(datetime);
+        let _: f32 = nexstar::astro::refraction_arcmin(0.0, 10.0, 1010.0);
+        let _: f32 = nexstar::astro::lst_degrees(utc, 8.5);
+        let horizontal: AzmAlt = position.to_azm_alt(location, utc);
+        let _: RaDec = horizontal.to_ra_dec(location, utc);
+        let _: f32 = nexstar::astro::apparent_altitude(position, location, datetime, 10.0, 1010.0);
+        let apparent: AzmAlt =
+            nexstar::astro::apply_refraction(AzmAlt { azm: 0.0, alt: 0.0 }, 10.0, 1010.0);
+        let _: AzmAlt = nexstar::astro::remove_refraction(apparent, 10.0, 1010.0);
+    }
+
+    let config = MountConfig {
+        location: Some(location),
+        time_zone: Some((1, false)),
+        tracking_mode: Some(TrackingMode::AltAz),
+        autoguide_rates: [Some(50), None],
+        backlash: [Some(Backlash::default()), None],
+    };
+    let _ = MountConfig::default();
+    #[cfg(feature = "serde")]
+    {
+        fn persistable<C: serde::Serialize + serde::de::DeserializeOwned>(_: &C) {}
+        persistable(&config);
+    }
+    let _ = config;
+
+    let version = Version::new(4, 21);
+    assert!(version.at_least(4, 21));
+    let _ = MountInfo {
+        model: Model::Unknown(0),
+        hc_version: version,
+        azm_ra_version: None,
+        alt_dec_version: None,
+    };
+    let _ = Compat {
+        family: Family::NexStar,
+        echo: false,
+        model_query: false,
+        wide_positions: true,
+    };
+    let _: Compat = Compat::for_version(version);
+    let _: Compat = Compat::STARSENSE;
+    #[cfg(feature = "synscan")]
+    let _: Compat = Compat::SYNSCAN;
+    let versions = Versions::default();
+    let _: Option<Version> = versions.hc;
+    let _: Option<Version> = versions.device(Device::AzmRaMotor);
+    let _ = [Compat::CURRENT, Compat::default()];
+
+    let _ = [
+        Device::MainBoard,
+        Device::HandController,
+        Device::AzmRaMotor,
+        Device::AltDecMotor,
+        Device::Focuser,
+        Device::GPSUnit,
+        Device::RTC,
+        Device::WiFi,
+        Device::Battery,
+    ];
+    let _: u8 = state_of_charge(13_000);
+    let _ = [
+        TrackingMode::Off,
+        TrackingMode::AltAz,
+        TrackingMode::EQNorth,
+        TrackingMode::EQSouth,
+    ];
+    let _ = TrackingRate::Custom(TrackingRate::SIDEREAL).arcsec_per_sec();
+    let _: f32 = GuideRate(0.5).arcsec_per_sec();
+    let _ = [GuideRate::SIDEREAL, GuideRate::OFF];
+    let _ = FocuserLimits { low: 0, high: 1 };
+    let _ = [
+        Command::SetPositiveVariableRate,
+        Command::SetNegativeVariableRate,
+        Command::GetDeviceVersion,
+    ];
+    let _ = [LinkState::Clean, LinkState::Dirty];
+    let _ = [LinkProbe::Ok, LinkProbe::WrongEcho, LinkProbe::Silent];
+    let mut health = LinkHealth::new(|| 0);
+    health.record(true);
+    let _: u32 = health.consecutive_failures();
+    let _: Option<u32> = health.since_success_ms();
+    let _ = Target::RaDec(RaDec { ra: 0.0, dec: 0.0 });
+    let _ = Approach::Negative;
+
+    let mut model = PointingModel::<2>::new(Fit::Linear);
+    model.add(RaDec { ra: 0.0, dec: 0.0 }, RaDec { ra: 0.0, dec: 0.0 });
+
+    let mut queue = TargetQueue::<2>::default();
+    let entry = QueueEntry {
+        target: Target::RaDec(RaDec { ra: 0.0, dec: 0.0 }),
+        approach: Some((Approach::Positive, 30.0)),
+    };
+    let _: Result<(), QueueEntry> = queue.push(entry);
+    let _: Option<QueueEntry> = queue.get(0);
+    let _: (usize, bool, usize, bool) = (
+        queue.len(),
+        queue.is_empty(),
+        queue.next_index(),
+        queue.is_done(),
+    );
+    let _: Option<usize> = queue.failed();
+    queue.skip();
+    queue.rewind();
+    queue.clear();
+    let _ = [Visit::Continue, Visit::Abort];
+    let tiles: Option<Mosaic> = mosaic(RaDec { ra: 0.0, dec: 0.0 }, 3, 2, 60.0, 40.0, 10.0);
+    for tile in tiles.unwrap() {
+        let _ = queue.push(QueueEntry::new(Target::RaDec(tile)));
+    }
+
+    let mut residuals = [0.0; 3];
+    let recorder = PeRecorder::new(&mut residuals, 2, 1, 1).with_rate(TrackingRate::Sidereal);
+    let _: (usize, u32, &[f32]) = (
+        recorder.samples(),
+        recorder.interval_ms(),
+        recorder.residuals(),
+    );
+    let PeStats { peak_to_peak, rms }: PeStats = recorder.stats();
+    let _: PeStats = stats(&[peak_to_peak, rms]);
+
+    let _: Result<Packet, FrameError> = Packet::decode(&[]);
+}
+
+#[cfg(feature = "simulator")]
+#[allow(dead_code)]
+fn simulator_api() {
+    use nexstar::simulator::SimulatedHc;
+
+    let hc = SimulatedHc::new();
+    let _: NexStar<&SimulatedHc, &SimulatedHc> = NexStar::new(&hc, &hc);
+    let _ = SimulatedHc::set_version;
+    let _ = SimulatedHc::set_model;
+    let _ = SimulatedHc::set_device;
+    let _ = SimulatedHc::set_slew_step;
+    let _ = SimulatedHc::set_rate_scale;
+    let _ = SimulatedHc::advance;
+    let _ = SimulatedHc::set_drift;
+    let _ = SimulatedHc::set_periodic_error;
+    let _ = SimulatedHc::set_play;
+    let _ = SimulatedHc::set_focuser_calibration;
+    let _ = SimulatedHc::set_focuser_moving;
+    let _ = SimulatedHc::set_battery_voltage;
+    let _ = SimulatedHc::set_protocol;
+    let _ = SimulatedHc::set_baud;
+    let _: fn(&SimulatedHc, u32) -> (&SimulatedHc, &SimulatedHc) = SimulatedHc::open;
+    let _: &mut dyn embedded_hal::blocking::delay::DelayMs<u32> = &mut &hc;
+    let _ = SimulatedHc::set_aligned;
+    let _ = SimulatedHc::axes;
+    let _ = SimulatedHc::set_axes;
+    let _ = SimulatedHc::location;
+    let _ = SimulatedHc::datetime;
+    let _ = SimulatedHc::tracking_mode;
+    let _ = SimulatedHc::is_goto_in_progress;
+}
+
+#[cfg(feature = "faults")]
+#[allow(dead_code)]
+fn faults_api<T: serial::Read<u8>>(rx: T) {
+    use nexstar::faults::{FaultCounts, FaultInjector, FaultSchedule};
+
+    let schedule = FaultSchedule::new(1)
+        .drop_one_in(1)
+        .duplicate_one_in(1)
+        .flip_one_in(1)
+        .stall_one_in(1, 0, 1);
+    let mut rx = FaultInjector::new(rx, schedule);
+    rx.set_enabled(false);
+    let counts: FaultCounts = rx.counts();
+    let _ = (
+        counts.dropped,
+        counts.duplicated,
+        counts.flipped,
+        counts.stalled,
+        counts.total(),
+    );
+    let _: T = rx.free();
+}
+
+#[cfg(feature = "nmea")]
+#[allow(dead_code)]
+fn nmea_api() {
+    use nexstar::nmea::{self, NmeaError, Sentence};
+
+    match nmea::parse(b"") {
+        Ok(Sentence::Gga { location }) => {
+            let _: Location = location;
+        }
+        Ok(Sentence::Rmc { location: _, utc }) => {
+            let _: UtcDateTime = utc;
+        }
+        Err(NmeaError::Checksum)
+        | Err(NmeaError::Invalid)
+        | Err(NmeaError::NoFix)
+        | Err(NmeaError::Unsupported) => {}
+    }
+}
+
+#[cfg(feature = "embedded-io")]
+#[allow(dead_code)]
+fn io_api<S, T, U>(inner: S, nexstar: &mut NexStar<T, U>)
+where
+    S: embedded_io::Read + embedded_io::ReadReady + embedded_io::Write,
+    T: serial::Read<u8>,
+    U: serial::Write<u8>,
+    T::Error: core::fmt::Debug,
+    U::Error: core::fmt::Debug,
+{
+    use embedded_io::Error as _;
+    use nexstar::io::{FromEmbeddedIo, RawError};
+
+    let adapter = FromEmbeddedIo::new(inner);
+    let _: &dyn serial::Read<u8, Error = S::Error> = &adapter;
+    let _: &dyn serial::Write<u8, Error = S::Error> = &adapter;
+    let _: S = adapter.free();
+
+    let _: Result<usize, RawError<T::Error, U::Error>> = embedded_io::Read::read(nexstar, &mut []);
+    let _: Result<usize, RawError<T::Error, U::Error>> = embedded_io::Write::write(nexstar, &[]);
+    if let Err(e) = embedded_io::Write::flush(nexstar) {
+        let _: embedded_io::ErrorKind = e.kind();
+        match e {
+            RawError::Read(_) | RawError::Write(_) => {}
+        }
+    }
+}
+
+#[cfg(feature = "critical-section")]
+#[allow(dead_code)]
+fn shared_api<T: serial::Read<u8>, U: serial::Write<u8>>(nexstar: NexStar<T, U>) {
+    use nexstar::shared::SharedNexStar;
+
+    let shared = SharedNexStar::new(nexstar);
+    let _: Result<Version, _> = shared.lock(|nexstar| nexstar.version());
+    let _: nb::Result<Result<Version, _>, _> = shared.try_lock(|nexstar| nexstar.version());
+    let _: NexStar<T, U> = shared.into_inner();
+}
+
+#[cfg(feature = "heapless")]
+#[allow(dead_code)]
+fn rx_buffer_api<U: serial::Write<u8>>(tx: U) {
+    use nexstar::rx_buffer::{Overrun, RxBuffer, RxProducer, RxReader};
+
+    let mut buffer = RxBuffer::<8>::new();
+    let (mut producer, reader): (RxProducer<8>, RxReader<8>) = buffer.split();
+    producer.push(0);
+    let _: usize = reader.len();
+    let _: bool = reader.is_empty();
+    let _: NexStar<RxReader<8>, U> = NexStar::new(reader, tx);
+    let _ = Overrun;
+}
+
+#[cfg(feature = "transcript")]
+#[allow(dead_code)]
+fn transcript_api<T: serial::Read<u8>, U: serial::Write<u8>>(rx: T, tx: U) {
+    use nexstar::transcript::{RecordingTransport, ReplayError, ReplayTransport, TranscriptError};
+
+    let mut buffer = [0u8; 16];
+    let recording = RecordingTransport::new(rx, tx, &mut buffer);
+    let _: NexStar<&RecordingTransport<T, U>, &RecordingTransport<T, U>> =
+        NexStar::new(&recording, &recording);
+    let _: bool = recording.is_full();
+    let _ = recording.transcript().len();
+    let _: (T, U) = recording.free();
+
+    let replay: Result<ReplayTransport, TranscriptError> = ReplayTransport::new(b"");
+    if let Ok(replay) = replay {
+        let _: NexStar<&ReplayTransport, &ReplayTransport> = NexStar::new(&replay, &replay);
+        let _: bool = replay.is_finished();
+    }
+    match (ReplayError::UnexpectedRead { offset: 0 }) {
+        ReplayError::Mismatch {
+            offset: _,
+            expected: _,
+            found: _,
+        } => {}
+        ReplayError::UnexpectedWrite {
+            offset: _,
+            found: _,
+        } => {}
+        ReplayError::UnexpectedRead { offset: _ } => {}
+    }
+    let _ = TranscriptError { offset: 0 }.offset;
+}
+
+#[allow(dead_code)]
+fn mount_api<T: serial::Read<u8>, U: serial::Write<u8>>(nexstar: &mut NexStar<T, U>) {
+    use nexstar::Mount;
+
+    let mount: &mut dyn Mount<Error = Error<T::Error, U::Error>> = nexstar;
+    let _: Result<MountInfo, _> = mount.connect();
+    let _: Result<RaDec, _> = mount.precise_ra_dec();
+    let _: Result<(), _> = mount.goto(Target::RaDec(RaDec { ra: 0.0, dec: 0.0 }));
+    let _: Result<bool, _> = mount.is_goto_in_progress();
+    let _: Result<(), _> = mount.track_rates(0, 0);
+}
+
+#[cfg(feature = "fake")]
+#[allow(dead_code)]
+fn fake_api() {
+    use nexstar::fake::FakeMount;
+    use nexstar::Mount;
+
+    let mut fake = FakeMount::new();
+    fake.fail_next = Some(Error::Timeout);
+    let _: &mut dyn Mount<Error = Error<(), ()>> = &mut fake;
+}
+
+#[cfg(feature = "ffi")]
+#[allow(dead_code)]
+fn ffi_api() {
+    use nexstar::ffi::*;
+
+    let _: unsafe extern "C" fn(*mut NexstarHandle, NexstarReadFn, NexstarWriteFn, _) -> _ =
+        nexstar_init;
+    let _: unsafe extern "C" fn(_, *mut NexstarLocation) -> NexstarStatus = nexstar_get_location;
+    let _: unsafe extern "C" fn(_, NexstarLocation) -> NexstarStatus = nexstar_set_location;
+    let _: unsafe extern "C" fn(_, *mut NexstarDateTime) -> NexstarStatus = nexstar_get_datetime;
+    let _: unsafe extern "C" fn(_, NexstarDateTime) -> NexstarStatus = nexstar_set_datetime;
+    let _: unsafe extern "C" fn(_, *mut NexstarRaDec) -> NexstarStatus = nexstar_get_radec;
+    let _: unsafe extern "C" fn(_, NexstarRaDec) -> NexstarStatus = nexstar_goto_radec;
+    let _: unsafe extern "C" fn(_, *mut bool) -> NexstarStatus = nexstar_is_goto_in_progress;
+    let _: unsafe extern "C" fn(_) -> NexstarStatus = nexstar_cancel_goto;
+    let _: unsafe extern "C" fn(_) -> NexstarStatus = nexstar_resync;
+    let _: usize = NEXSTAR_HANDLE_WORDS;
+    let _: NexstarStatus = Error::<(), ()>::Timeout.into();
+}
+
+#[cfg(feature = "async")]
+#[allow(dead_code)]
+async fn async_api<T: nexstar::asynch::AsyncTransport>(transport: T) {
+    use nexstar::asynch::AsyncNexStar;
+    use nexstar::protocol::{Request, Response};
+
+    let mut nexstar = AsyncNexStar::new(transport);
+    let _: Result<Response, Error<T::Error, T::Error>> =
+        nexstar.transact(Request::GetVersion).await;
+    let _: Result<Version, _> = nexstar.version().await;
+    let _: Result<RaDec, _> = nexstar.precise_ra_dec().await;
+    let _: Result<(), _> = nexstar
+        .precise_goto_ra_dec(RaDec { ra: 0.0, dec: 0.0 })
+        .await;
+    let _: Result<bool, _> = nexstar.is_goto_in_progress().await;
+    let _: Result<(), _> = nexstar.cancel_goto().await;
+    if let Ok(location) = nexstar.location().await {
+        let _: Result<(), _> = nexstar.set_location(location).await;
+    }
+    if let Ok(datetime) = nexstar.datetime().await {
+        let _: Result<(), _> = nexstar.set_datetime(datetime).await;
+    }
+    let _: Result<(), _> = nexstar.resync().await;
+    let _: LinkState = nexstar.link_state();
+    let _: T = nexstar.free();
+}
@@ -0,0 +1,220 @@
+#![cfg(feature = "async")]
+
+mod common;
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::future::{poll_fn, Future};
+use std::pin::pin;
+use std::rc::Rc;
+use std::task::{Context, Poll, Waker};
+
+use nexstar::asynch::{AsyncNexStar, AsyncTransport};
+use nexstar::protocol::{Request, Response};
+use nexstar::{DateTime, Error, LinkState, Version};
+
+#[derive(Default)]
+struct State {
+    /// Chunks which arrive after the next write
+    staged: VecDeque<Vec<u8>>,
+    chunks: VecDeque<Vec<u8>>,
+    written: Vec<u8>,
+}
+
+/// Transport delivering the scripted chunks, each after a pending poll like
+/// a browser waiting for the next chunk of a stream
+struct Chunked {
+    state: Rc<RefCell<State>>,
+    ready: bool,
+}
+
+#[derive(Clone)]
+struct Script {
+    state: Rc<RefCell<State>>,
+}
+
+impl Script {
+    fn respond(&self, chunks: &[&[u8]]) {
+        let mut state = self.state.borrow_mut();
+        state
+            .staged
+            .extend(chunks.iter().map(|chunk| chunk.to_vec()));
+    }
+
+    fn written(&self) -> Vec<u8> {
+        std::mem::take(&mut self.state.borrow_mut().written)
+    }
+}
+
+impl AsyncTransport for Chunked {
+    type Error = ();
+
+    async fn write_all(&mut self, bytes: &[u8]) -> Result<(), ()> {
+        let mut state = self.state.borrow_mut();
+        state.written.extend_from_slice(bytes);
+        let staged = std::mem::take(&mut state.staged);
+        state.chunks.extend(staged);
+        Ok(())
+    }
+
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, ()> {
+        poll_fn(|_| {
+            if !self.ready {
+                self.ready = true;
+                return Poll::Pending;
+            }
+            let mut state = self.state.borrow_mut();
+            match state.chunks.pop_front() {
+                Some(mut chunk) => {
+                    self.ready = false;
+                    let len = chunk.len().min(buf.len());
+                    buf[..len].copy_from_slice(&chunk[..len]);
+                    if len < chunk.len() {
+                        state.chunks.push_front(chunk.split_off(len));
+                    }
+                    Poll::Ready(Ok(len))
+                }
+                None => Poll::Pending,
+            }
+        })
+        .await
+    }
+}
+
+fn nexstar(chunks: &[&[u8]]) -> (AsyncNexStar<Chunked>, Script) {
+    let state = Rc::new(RefCell::new(State::default()));
+    let script = Script {
+        state: state.clone(),
+    };
+    script.respond(chunks);
+    let transport = Chunked {
+        state,
+        ready: false,
+    };
+    (AsyncNexStar::new(transport), script)
+}
+
+/// Polls the future until it is ready, fails if it stalls.
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+    for _ in 0..1000 {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+    panic!("future stalled");
+}
+
+#[test]
+fn version_in_chunks() {
+    let (mut nexstar, script) = nexstar(&[&[4], &[21, b'#']]);
+
+    assert_eq!(block_on(nexstar.version()).unwrap(), Version::new(4, 21));
+    assert_eq!(script.written(), b"V");
+    assert_eq!(nexstar.link_state(), LinkState::Clean);
+}
+
+#[cfg(feature = "float")]
+#[test]
+fn position_split_anywhere() {
+    let response = b"80000000,C0000000#";
+    for split in 1..response.len() {
+        let (first, second) = response.split_at(split);
+        let (mut nexstar, _) = nexstar(&[first, second]);
+
+        let position = block_on(nexstar.precise_ra_dec()).unwrap();
+        common::assert_close(position.ra, 180.0, 0.0001);
+        common::assert_close(position.dec, -90.0, 0.0001);
+    }
+}
+
+#[cfg(feature = "float")]
+#[test]
+fn goto_and_location() {
+    let (mut nexstar, script) = nexstar(&[b"#"]);
+
+    block_on(nexstar.precise_goto_ra_dec(nexstar::RaDec {
+        ra: 180.0,
+        dec: -90.0,
+    }))
+    .unwrap();
+    assert_eq!(script.written(), b"r80000000,C0000000");
+
+    script.respond(&[&[47, 12, 34, 0], &[8, 32, 7, 0, b'#']]);
+    let location = block_on(nexstar.location()).unwrap();
+    common::assert_close(location.latitude, 47.2094, 0.0001);
+    common::assert_close(location.longitude, 8.5353, 0.0001);
+}
+
+#[test]
+fn datetime_roundtrip() {
+    let (mut nexstar, script) = nexstar(&[&[22, 30, 15, 10, 14, 26, 0xFE, 1, b'#']]);
+
+    let datetime: DateTime = block_on(nexstar.datetime()).unwrap();
+    assert_eq!(datetime.zone, -2);
+    script.respond(&[b"#"]);
+    block_on(nexstar.set_datetime(datetime)).unwrap();
+    assert_eq!(script.written(), b"hH\x16\x1e\x0f\x0a\x0e\x1a\xfe\x01");
+}
+
+#[test]
+fn raw_requests() {
+    let (mut nexstar, script) = nexstar(&[b"x#"]);
+
+    assert_eq!(
+        block_on(nexstar.transact(Request::Echo(b'x'))).unwrap(),
+        Response::Echo(b'x')
+    );
+    assert_eq!(script.written(), b"Kx");
+}
+
+#[test]
+fn nack_resyncs_next_call() {
+    let (mut nexstar, script) = nexstar(&[b"1\x00#"]);
+
+    assert!(matches!(
+        block_on(nexstar.is_goto_in_progress()),
+        Err(Error::UnexpectedResponse(_))
+    ));
+    assert_eq!(nexstar.link_state(), LinkState::Dirty);
+
+    script.respond(&[b"B#"]);
+    script.respond(&[b"0#"]);
+    assert!(!block_on(nexstar.is_goto_in_progress()).unwrap());
+    assert_eq!(script.written(), b"LKBL");
+}
+
+#[test]
+fn dropped_call_resyncs_next_call() {
+    let (mut nexstar, script) = nexstar(&[&[4]]);
+
+    {
+        // the rest of the response never arrives before the call is dropped
+        let mut call = pin!(nexstar.version());
+        let mut cx = Context::from_waker(Waker::noop());
+        for _ in 0..10 {
+            assert!(call.as_mut().poll(&mut cx).is_pending());
+        }
+    }
+    assert_eq!(nexstar.link_state(), LinkState::Dirty);
+
+    // the stale tail arrives ahead of the echo
+    script.respond(&[&[21, b'#'], b"B#"]);
+    script.respond(&[&[4, 21, b'#']]);
+    assert_eq!(block_on(nexstar.version()).unwrap(), Version::new(4, 21));
+    assert_eq!(script.written(), b"VKBV");
+}
+
+#[test]
+fn closed_transport_truncates() {
+    let (mut nexstar, _) = nexstar(&[&[4], &[]]);
+
+    assert!(matches!(
+        block_on(nexstar.version()),
+        Err(Error::Truncated {
+            expected: 3,
+            got: 1
+        })
+    ));
+}
@@ -0,0 +1,291 @@
+mod common;
+
+use common::{ticking_clock, transport, Script};
+use nexstar::aux::{checksum, AuxBus, FrameError, McCommand, Packet, MAX_DATA, MAX_FRAME};
+use nexstar::{Device, Error, Version};
+
+/// Encodes the packet from the device to the driver.
+fn frame(src: Device, cmd: McCommand, data: &[u8]) -> Vec<u8> {
+    let mut frame = [0u8; MAX_FRAME];
+    let len = Packet::new(src as u8, 0x20, cmd as u8, data)
+        .unwrap()
+        .encode(&mut frame);
+    frame[..len].to_vec()
+}
+
+fn aux() -> (AuxBus<common::Rx, common::Tx>, Script) {
+    let (rx, tx, script) = transport();
+    (AuxBus::new(rx, tx), script)
+}
+
+/// Deterministic pseudo random bytes (xorshift)
+struct Random(u32);
+
+impl Random {
+    fn next(&mut self) -> u8 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0 as u8
+    }
+}
+
+// No frames captured from a mount are available yet. These are synthetic,
+// worked out from the framing description, so they only check the codec
+// against that description.
+#[test]
+fn encode_synthetic_frame() {
+    let mut frame = [0u8; MAX_FRAME];
+    let packet = Packet::new(0x20, 0x10, 0x01, &[]).unwrap();
+
+    let len = packet.encode(&mut frame);
+    assert_eq!(&frame[..len], &[0x3b, 0x03, 0x20, 0x10, 0x01, 0xcc]);
+}
+
+#[test]
+fn decode_synthetic_frame() {
+    let packet = Packet::decode(&[0x3b, 0x06, 0x10, 0x20, 0x01, 0x40, 0x00, 0x00, 0x89]).unwrap();
+
+    assert_eq!((packet.src, packet.dst, packet.cmd), (0x10, 0x20, 0x01));
+    assert_eq!(packet.data(), &[0x40, 0x00, 0x00]);
+}
+
+#[test]
+fn checksum_is_twos_complement() {
+    assert_eq!(checksum(&[]), 0);
+    assert_eq!(checksum(&[0x03, 0x20, 0x10, 0x01]), 0xcc);
+    assert_eq!(checksum(&[0xff, 0x02]), 0xff);
+}
+
+#[test]
+fn decode_errors() {
+    assert_eq!(Packet::decode(&[]), Err(FrameError::Preamble));
+    assert_eq!(
+        Packet::decode(&[0x3c, 0x03, 0x20, 0x10, 0x01, 0xcc]),
+        Err(FrameError::Preamble)
+    );
+    assert_eq!(Packet::decode(&[0x3b]), Err(FrameError::Length));
+    assert_eq!(
+        Packet::decode(&[0x3b, 0x03, 0x20, 0x10, 0x01]),
+        Err(FrameError::Length)
+    );
+    assert_eq!(
+        Packet::decode(&[0x3b, 0x02, 0x20, 0x10, 0xce]),
+        Err(FrameError::Length)
+    );
+    assert_eq!(
+        Packet::decode(&[0x3b, 0x03, 0x20, 0x10, 0x01, 0xcd]),
+        Err(FrameError::Checksum)
+    );
+}
+
+#[test]
+fn too_much_data() {
+    assert!(Packet::new(0x20, 0x10, 0x01, &[0; MAX_DATA]).is_some());
+    assert!(Packet::new(0x20, 0x10, 0x01, &[0; MAX_DATA + 1]).is_none());
+}
+
+#[test]
+fn fuzz_decode() {
+    let mut random = Random(0x1234_5678);
+    for _ in 0..10_000 {
+        let len = random.next() as usize % (MAX_FRAME + 4);
+        let mut bytes: Vec<u8> = (0..len).map(|_| random.next()).collect();
+        if let Some(first) = bytes.first_mut() {
+            if random.next() & 1 == 0 {
+                *first = 0x3b;
+            }
+        }
+
+        if let Ok(packet) = Packet::decode(&bytes) {
+            let mut frame = [0u8; MAX_FRAME];
+            let len = packet.encode(&mut frame);
+            assert_eq!(&frame[..len], &bytes[..]);
+        }
+    }
+}
+
+#[test]
+fn fuzz_roundtrip() {
+    let mut random = Random(0x8765_4321);
+    for _ in 0..10_000 {
+        let data: Vec<u8> = (0..random.next() as usize % (MAX_DATA + 1))
+            .map(|_| random.next())
+            .collect();
+        let packet = Packet::new(random.next(), random.next(), random.next(), &data).unwrap();
+
+        let mut frame = [0u8; MAX_FRAME];
+        let len = packet.encode(&mut frame);
+        assert_eq!(Packet::decode(&frame[..len]), Ok(packet));
+    }
+}
+
+#[cfg(feature = "float")]
+#[test]
+fn motor_position() {
+    let (mut aux, script) = aux();
+    script.respond(&frame(
+        Device::AzmRaMotor,
+        McCommand::GetPosition,
+        &[0x40, 0, 0],
+    ));
+
+    assert_eq!(aux.motor_position(Device::AzmRaMotor).unwrap(), 90.0);
+    assert_eq!(script.written(), &[0x3b, 0x03, 0x20, 0x10, 0x01, 0xcc]);
+}
+
+#[cfg(feature = "float")]
+#[test]
+fn motor_position_signed() {
+    let (mut aux, script) = aux();
+    for position in [[0x40, 0x01, 0x00], [0x80, 0, 0], [0xBF, 0xFF, 0x00]] {
+        script.respond(&frame(
+            Device::AltDecMotor,
+            McCommand::GetPosition,
+            &position,
+        ));
+    }
+
+    let past_90 = aux.motor_position(Device::AltDecMotor).unwrap();
+    assert!(past_90 > 90.0 && past_90 < 90.01, "{}", past_90);
+    assert_eq!(aux.motor_position(Device::AltDecMotor).unwrap(), 180.0);
+    let below_minus_90 = aux.motor_position(Device::AltDecMotor).unwrap();
+    assert!(
+        below_minus_90 < -90.0 && below_minus_90 > -90.01,
+        "{}",
+        below_minus_90
+    );
+}
+
+#[test]
+fn skips_own_echo() {
+    let (mut aux, script) = aux();
+    script.set_local_echo(true);
+    script.respond(&frame(Device::AltDecMotor, McCommand::GetVersion, &[7, 11]));
+
+    assert_eq!(
+        aux.version(Device::AltDecMotor).unwrap(),
+        Version::new(7, 11)
+    );
+    assert_eq!(script.pending(), 0);
+}
+
+#[test]
+fn skips_noise_before_preamble() {
+    let (mut aux, script) = aux();
+    script.respond(&[0x00, 0xff]);
+    script.respond(&frame(Device::AzmRaMotor, McCommand::SlewDone, &[0xff]));
+
+    assert!(aux.slew_done(Device::AzmRaMotor).unwrap());
+}
+
+#[test]
+fn motor_position_arcsec() {
+    let (mut aux, script) = aux();
+    script.respond(&frame(
+        Device::AzmRaMotor,
+        McCommand::GetPosition,
+        &[0xC0, 0, 0],
+    ));
+
+    // 270° are -90°
+    assert_eq!(
+        aux.motor_position_arcsec(Device::AzmRaMotor).unwrap(),
+        -324_000
+    );
+}
+
+#[test]
+fn goto_arcsec_encodes_position() {
+    let (mut aux, script) = aux();
+    script.respond(&frame(Device::AzmRaMotor, McCommand::GotoFast, &[]));
+
+    aux.goto_arcsec(Device::AzmRaMotor, -324_000, true).unwrap();
+    let written = script.written();
+    assert_eq!(&written[1..8], &[0x06, 0x20, 0x10, 0x02, 0xC0, 0x00, 0x00]);
+}
+
+#[cfg(feature = "float")]
+#[test]
+fn goto_encodes_position() {
+    let (mut aux, script) = aux();
+    script.respond(&frame(Device::AzmRaMotor, McCommand::GotoSlow, &[]));
+
+    aux.goto(Device::AzmRaMotor, 180.0, false).unwrap();
+    let written = script.written();
+    assert_eq!(&written[1..8], &[0x06, 0x20, 0x10, 0x17, 0x80, 0x00, 0x00]);
+}
+
+#[test]
+fn slew_clamps_rate() {
+    let (mut aux, script) = aux();
+    script.respond(&frame(Device::AltDecMotor, McCommand::MoveNegative, &[]));
+
+    aux.slew(Device::AltDecMotor, -20).unwrap();
+    let written = script.written();
+    assert_eq!(&written[4..6], &[0x25, 9]);
+}
+
+#[cfg(feature = "float")]
+#[test]
+fn invalid_checksum() {
+    let (mut aux, script) = aux();
+    let mut response = frame(Device::AzmRaMotor, McCommand::GetPosition, &[0, 0, 0]);
+    *response.last_mut().unwrap() ^= 0xff;
+    script.respond(&response);
+
+    assert!(matches!(
+        aux.motor_position(Device::AzmRaMotor),
+        Err(Error::InvalidFrame(FrameError::Checksum))
+    ));
+}
+
+#[test]
+fn silent_bus_times_out() {
+    let (mut aux, script) = aux();
+    aux.set_timeout(ticking_clock, 10);
+
+    assert!(matches!(
+        aux.version(Device::AzmRaMotor),
+        Err(Error::Timeout)
+    ));
+    assert_eq!(script.written().len(), 6);
+}
+
+#[test]
+fn truncated_frame() {
+    let (mut aux, script) = aux();
+    aux.set_timeout(ticking_clock, 10);
+    let response = frame(Device::AzmRaMotor, McCommand::GetPosition, &[0x40, 0, 0]);
+    script.respond(&response[..5]);
+
+    assert!(matches!(
+        aux.motor_position_arcsec(Device::AzmRaMotor),
+        Err(Error::Truncated {
+            expected: 9,
+            got: 5
+        })
+    ));
+
+    // stops right after the preamble
+    script.respond(&[0x3b]);
+    assert!(matches!(
+        aux.motor_position_arcsec(Device::AzmRaMotor),
+        Err(Error::Truncated {
+            expected: 6,
+            got: 1
+        })
+    ));
+}
+
+#[test]
+fn timeout_applies_per_byte() {
+    let (mut aux, script) = aux();
+    aux.set_timeout(ticking_clock, 2);
+    script.respond(&frame(Device::AltDecMotor, McCommand::GetVersion, &[7, 11]));
+
+    assert_eq!(
+        aux.version(Device::AltDecMotor).unwrap(),
+        Version::new(7, 11)
+    );
+}
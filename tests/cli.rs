@@ -0,0 +1,262 @@
+#![cfg(all(feature = "cli", feature = "simulator"))]
+
+mod common;
+
+use common::{assert_close, ticking_clock, transport};
+use nexstar::cli::{self, Args, CliError};
+use nexstar::simulator::SimulatedHc;
+use nexstar::{Device, Model, NexStar, TrackingMode, Version};
+
+/// Runs the command line on the simulator and returns the output.
+fn run(hc: &SimulatedHc, args: &[&str]) -> Result<String, CliError> {
+    let args = Args::parse(args)?;
+    let mut nexstar = NexStar::new(hc, hc);
+    let mut out = Vec::new();
+    cli::run(&args, &mut nexstar, &mut out)?;
+    Ok(String::from_utf8(out).unwrap())
+}
+
+#[test]
+fn info() {
+    let hc = SimulatedHc::new();
+    hc.set_model(Model::CPC);
+    hc.set_device(Device::AltDecMotor, None);
+
+    let out = run(&hc, &["info"]).unwrap();
+    assert!(out.contains("Model: CPC\n"));
+    assert!(out.contains("HC firmware: 4.21\n"));
+    assert!(out.contains("AZM/RA motor firmware: 7.11\n"));
+    assert!(out.contains("ALT/DEC motor firmware: not present\n"));
+    assert!(out.contains("Alignment: complete\n"));
+    assert!(out.contains("GOTO: idle\n"));
+}
+
+#[test]
+fn get_position() {
+    let hc = SimulatedHc::new();
+    hc.set_axes(83.82, -5.39);
+
+    let out = run(&hc, &["get-position"]).unwrap();
+    assert_eq!(
+        out,
+        "RA  05h35m16.8s (83.8200°)\nDec -05°23'24.0\" (-5.3900°)\n"
+    );
+
+    let out = run(&hc, &["get-position", "--azm-alt"]).unwrap();
+    assert!(out.starts_with("Azm 83°49'12.0\""));
+}
+
+#[test]
+fn get_position_old_firmware() {
+    let hc = SimulatedHc::new();
+    hc.set_version(Version::new(1, 2));
+    hc.set_axes(90.0, 45.0);
+
+    let out = run(&hc, &["--port", "/dev/null", "get-position"]).unwrap();
+    assert!(out.contains("(90.0000°)"));
+    assert!(out.contains("(45.0000°)"));
+}
+
+#[test]
+fn goto() {
+    let hc = SimulatedHc::new();
+
+    let out = run(&hc, &["goto", "--ra", "83.82", "--dec", "-5.39"]).unwrap();
+    assert!(out.starts_with("GOTO started to RA 05h35m16.8s"));
+    assert!(hc.is_goto_in_progress());
+
+    let out = run(&hc, &["goto", "--azm", "180", "--alt", "30"]).unwrap();
+    assert!(out.starts_with("GOTO started to Azm 180°00'00.0\""));
+}
+
+#[test]
+fn set_time() {
+    let hc = SimulatedHc::new();
+
+    let out = run(&hc, &["set-time", "--now", "--zone", "-5", "--dst"]).unwrap();
+    assert!(out.starts_with("Time set to 20"));
+    assert!(out.ends_with(" UTC-5 (DST)\n"));
+    let datetime = hc.datetime();
+    assert_eq!(datetime.zone, -5);
+    assert!(datetime.daylight_saving);
+    assert!(datetime.year >= 26);
+}
+
+#[test]
+fn set_location() {
+    let hc = SimulatedHc::new();
+
+    let out = run(
+        &hc,
+        &["set-location", "--lat", "-33.86", "--lon", "151.2111"],
+    )
+    .unwrap();
+    assert_eq!(out, "Location set to 33°51'36\" S  151°12'40\" E\n");
+    assert_close(hc.location().latitude, -33.86, 1e-3);
+    assert_close(hc.location().longitude, 151.2111, 1e-3);
+}
+
+#[test]
+fn slew_and_track() {
+    let hc = SimulatedHc::new();
+
+    let out = run(&hc, &["slew", "--az-rate", "120", "--alt-rate", "-30"]).unwrap();
+    assert_eq!(out, "Slewing AZM/RA at 120\"/s, ALT/DEC at -30\"/s\n");
+    assert_eq!(run(&hc, &["slew"]).unwrap(), "Motors stopped\n");
+
+    assert_eq!(
+        run(&hc, &["track", "off"]).unwrap(),
+        "Tracking set to off\n"
+    );
+    assert!(matches!(hc.tracking_mode(), TrackingMode::Off));
+    run(&hc, &["track", "eq-south"]).unwrap();
+    assert!(matches!(hc.tracking_mode(), TrackingMode::EQSouth));
+}
+
+#[test]
+fn help() {
+    let args = Args::parse(["--help"]).unwrap();
+    assert!(args.is_help());
+    assert_eq!(run(&SimulatedHc::new(), &["help"]).unwrap(), cli::USAGE);
+}
+
+#[test]
+fn usage_errors() {
+    let usage = |args: &[&str]| match Args::parse(args) {
+        Err(e @ CliError::Usage(_)) => {
+            assert_eq!(e.exit_code(), 2);
+            e.to_string()
+        }
+        _ => panic!("{:?} parsed", args),
+    };
+
+    assert!(usage(&[]).starts_with("missing command"));
+    assert!(usage(&["park"]).starts_with("unknown command 'park'"));
+    assert!(usage(&["goto", "--ra", "10"]).starts_with("goto needs"));
+    assert!(usage(&["goto", "--ra", "x", "--dec", "1"]).starts_with("invalid value 'x' of --ra"));
+    assert!(usage(&["set-location", "--lat", "91", "--lon", "0"]).starts_with("--lat must be"));
+    assert!(usage(&["set-time"]).starts_with("set-time needs --now"));
+    assert!(usage(&["track", "sideways"]).starts_with("unknown tracking mode"));
+    assert!(usage(&["info", "now"]).starts_with("unexpected argument 'now'"));
+    assert!(usage(&["--port"]).starts_with("missing value of --port"));
+    assert_eq!(
+        Args::parse(["--port", "/dev/ttyS1", "info"]).unwrap().port,
+        "/dev/ttyS1"
+    );
+}
+
+#[test]
+fn mount_errors() {
+    let (rx, tx, _) = transport();
+    let mut nexstar = NexStar::new(rx, tx);
+    nexstar.set_timeout(ticking_clock, 10);
+
+    let args = Args::parse(["info"]).unwrap();
+    let err = cli::run(&args, &mut nexstar, &mut Vec::new()).unwrap_err();
+    assert_eq!(err.exit_code(), 1);
+    assert!(err.to_string().starts_with("no response from the mount"));
+
+    let (rx, tx, script) = transport();
+    script.respond(&[
+        4, 21, b'#', 0x42, b'#', 0x0C, b'#', 7, 11, b'#', 7, 11, b'#',
+    ]);
+    script.respond(&[0x00, b'#']);
+    let mut nexstar = NexStar::new(rx, tx);
+    let args = Args::parse(["track", "off"]).unwrap();
+    let err = cli::run(&args, &mut nexstar, &mut Vec::new()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "the mount rejected the command: unexpected response to 'T'"
+    );
+}
+
+#[cfg(feature = "fake")]
+mod fake {
+    use super::*;
+    use nexstar::fake::FakeMount;
+    use nexstar::{DateTime, Error, Location, Mount, RaDec, Target};
+
+    fn run(
+        mount: &mut dyn Mount<Error = Error<(), ()>>,
+        args: &[&str],
+    ) -> Result<String, CliError> {
+        let args = Args::parse(args)?;
+        let mut out = Vec::new();
+        cli::run(&args, mount, &mut out)?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn info() {
+        let mut mount = FakeMount::new();
+        mount.tracking_mode = TrackingMode::EQNorth;
+        mount.goto_in_progress = true;
+        mount.location = Location {
+            latitude: 48.125,
+            longitude: -11.5,
+        };
+        mount.datetime = DateTime {
+            hour: 21,
+            minutes: 30,
+            seconds: 5,
+            zone: 1,
+            daylight_saving: true,
+            year: 24,
+            month: 6,
+            day: 15,
+        };
+
+        assert_eq!(
+            run(&mut mount, &["info"]).unwrap(),
+            "Model: 6/8 SE\n\
+             HC firmware: 4.21\n\
+             AZM/RA motor firmware: 7.11\n\
+             ALT/DEC motor firmware: 7.11\n\
+             Alignment: complete\n\
+             GOTO: in progress\n\
+             Tracking: EQ North\n\
+             Location: 48°07'30\" N  011°30'00\" W\n\
+             Time: 2024-06-15 21:30:05 UTC+1 (DST)\n"
+        );
+    }
+
+    #[test]
+    fn commands() {
+        let mut mount = FakeMount::new();
+
+        run(&mut mount, &["goto", "--ra", "10", "--dec", "20"]).unwrap();
+        assert!(matches!(
+            mount.last_target,
+            Some(Target::RaDec(RaDec { ra, dec })) if ra == 10.0 && dec == 20.0
+        ));
+        run(&mut mount, &["slew", "--alt-rate", "-30"]).unwrap();
+        assert_eq!(mount.rates, (0, -30));
+        run(&mut mount, &["track", "alt-az"]).unwrap();
+        assert!(matches!(mount.tracking_mode, TrackingMode::AltAz));
+    }
+
+    #[test]
+    fn error_messages() {
+        let mut mount = FakeMount::new();
+        let mut fail = |error| {
+            mount.fail_next = Some(error);
+            run(&mut mount, &["info"]).unwrap_err().to_string()
+        };
+
+        assert_eq!(
+            fail(Error::Truncated {
+                expected: 2,
+                got: 1
+            }),
+            "response truncated after 1 of 2 bytes, the serial line looks unreliable"
+        );
+        assert_eq!(
+            fail(Error::UnsupportedFirmware {
+                required: Version::new(1, 6),
+                found: Version::new(1, 2),
+            }),
+            "firmware 1.2 found, 1.6 required, the command needs a firmware update"
+        );
+        assert_eq!(fail(Error::Read(())), "serial port read error: ()");
+    }
+}
@@ -0,0 +1,209 @@
+//! Scripted serial transport shared by the integration tests.
+#![allow(dead_code)]
+
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+use embedded_hal::blocking::delay::DelayMs;
+use embedded_hal::serial;
+use nexstar::NexStar;
+
+/// Reads returning `WouldBlock` in a row before the script is considered
+/// exhausted, keeps a missing response from hanging the test.
+const MAX_IDLE_READS: usize = 100_000;
+
+#[derive(Debug, PartialEq)]
+pub struct MockError;
+
+#[derive(Default)]
+struct State {
+    staged: VecDeque<u8>,
+    rx: VecDeque<u8>,
+    echo: VecDeque<u8>,
+    tx: Vec<u8>,
+    local_echo: bool,
+    fail_writes: bool,
+    fail_reads: bool,
+}
+
+/// Receiving half, returns the scripted bytes
+pub struct Rx {
+    state: Rc<RefCell<State>>,
+    idle: usize,
+}
+
+/// Transmitting half, records the written bytes
+pub struct Tx {
+    state: Rc<RefCell<State>>,
+}
+
+/// Handle to inspect and extend the script while the driver owns the halves
+#[derive(Clone)]
+pub struct Script {
+    state: Rc<RefCell<State>>,
+}
+
+impl Script {
+    /// Appends bytes to the response script.
+    ///
+    /// The bytes become readable once the next byte is written, like a
+    /// response which only arrives after the command was sent.
+    pub fn respond(&self, bytes: &[u8]) {
+        self.state.borrow_mut().staged.extend(bytes);
+    }
+
+    /// Appends bytes which are readable right away, like a late response.
+    pub fn arrive(&self, bytes: &[u8]) {
+        self.state.borrow_mut().rx.extend(bytes);
+    }
+
+    /// Takes the bytes written so far.
+    pub fn written(&self) -> Vec<u8> {
+        std::mem::take(&mut self.state.borrow_mut().tx)
+    }
+
+    /// Number of scripted bytes not read yet.
+    pub fn pending(&self) -> usize {
+        let state = self.state.borrow();
+        state.staged.len() + state.rx.len()
+    }
+
+    /// Echoes every written byte back before the scripted response.
+    pub fn set_local_echo(&self, enabled: bool) {
+        self.state.borrow_mut().local_echo = enabled;
+    }
+
+    /// Fails every write.
+    pub fn set_fail_writes(&self, enabled: bool) {
+        self.state.borrow_mut().fail_writes = enabled;
+    }
+
+    /// Fails every read.
+    pub fn set_fail_reads(&self, enabled: bool) {
+        self.state.borrow_mut().fail_reads = enabled;
+    }
+}
+
+impl serial::Read<u8> for Rx {
+    type Error = MockError;
+
+    fn read(&mut self) -> nb::Result<u8, MockError> {
+        let mut state = self.state.borrow_mut();
+        if state.fail_reads {
+            return Err(nb::Error::Other(MockError));
+        }
+        match state.echo.pop_front().or_else(|| state.rx.pop_front()) {
+            Some(byte) => {
+                self.idle = 0;
+                Ok(byte)
+            }
+            None => {
+                self.idle += 1;
+                assert!(
+                    self.idle < MAX_IDLE_READS,
+                    "read past the end of the script"
+                );
+                Err(nb::Error::WouldBlock)
+            }
+        }
+    }
+}
+
+impl serial::Write<u8> for Tx {
+    type Error = MockError;
+
+    fn write(&mut self, word: u8) -> nb::Result<(), MockError> {
+        let mut state = self.state.borrow_mut();
+        if state.fail_writes {
+            return Err(nb::Error::Other(MockError));
+        }
+        state.tx.push(word);
+        let staged = std::mem::take(&mut state.staged);
+        state.rx.extend(staged);
+        if state.local_echo {
+            state.echo.push_back(word);
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> nb::Result<(), MockError> {
+        Ok(())
+    }
+}
+
+/// Creates the transport halves and the script handle.
+pub fn transport() -> (Rx, Tx, Script) {
+    let state = Rc::new(RefCell::new(State::default()));
+    (
+        Rx {
+            state: state.clone(),
+            idle: 0,
+        },
+        Tx {
+            state: state.clone(),
+        },
+        Script { state },
+    )
+}
+
+/// Creates a driver answering with the scripted response.
+pub fn nexstar(response: &[u8]) -> (NexStar<Rx, Tx>, Script) {
+    let (rx, tx, script) = transport();
+    script.respond(response);
+    (NexStar::new(rx, tx), script)
+}
+
+/// Delay recording the requested delays instead of sleeping
+#[derive(Default)]
+pub struct FakeDelay {
+    pub delays: Vec<u32>,
+}
+
+impl FakeDelay {
+    pub fn total(&self) -> u32 {
+        self.delays.iter().sum()
+    }
+}
+
+impl DelayMs<u32> for FakeDelay {
+    fn delay_ms(&mut self, ms: u32) {
+        self.delays.push(ms);
+    }
+}
+
+thread_local! {
+    static TICKS: Cell<u32> = const { Cell::new(0) };
+}
+
+/// Millisecond clock advancing by one on every call
+pub fn ticking_clock() -> u32 {
+    TICKS.with(|ticks| {
+        let now = ticks.get();
+        ticks.set(now.wrapping_add(1));
+        now
+    })
+}
+
+thread_local! {
+    static NOW: Cell<u32> = const { Cell::new(0) };
+}
+
+/// Millisecond clock which only moves with [`set_now`]
+pub fn manual_clock() -> u32 {
+    NOW.with(Cell::get)
+}
+
+pub fn set_now(ms: u32) {
+    NOW.with(|now| now.set(ms));
+}
+
+pub fn assert_close(actual: f32, expected: f32, tolerance: f32) {
+    assert!(
+        (actual - expected).abs() <= tolerance,
+        "{} is not within {} of {}",
+        actual,
+        tolerance,
+        expected
+    );
+}
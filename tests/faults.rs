@@ -0,0 +1,218 @@
+#![cfg(all(feature = "faults", feature = "simulator"))]
+
+mod common;
+
+use common::ticking_clock;
+use nexstar::faults::{FaultCounts, FaultInjector, FaultSchedule};
+use nexstar::simulator::SimulatedHc;
+use nexstar::{DateTime, Error, Location, Model, NexStar, TrackingMode, Version};
+
+type Driver<'a> = NexStar<FaultInjector<&'a SimulatedHc>, &'a SimulatedHc>;
+
+const SEEDS: u32 = 20;
+const COMMANDS: usize = 100;
+const TIMEOUT: u32 = 1500;
+
+// none of the bytes of the state equals the ack, see `session`
+const LOCATION: Location = Location {
+    latitude: 48.125,
+    longitude: 11.5,
+};
+const DATETIME: DateTime = DateTime {
+    hour: 21,
+    minutes: 30,
+    seconds: 5,
+    zone: 1,
+    daylight_saving: false,
+    year: 24,
+    month: 6,
+    day: 15,
+};
+const AXES: (f32, f32) = (123.4, 56.7);
+
+/// Creates a simulator with a known state and a driver receiving through the
+/// fault injector.
+fn setup(hc: &SimulatedHc, schedule: FaultSchedule) -> Driver<'_> {
+    hc.set_axes(AXES.0, AXES.1);
+    let mut rx = FaultInjector::new(hc, schedule);
+    rx.set_enabled(false);
+
+    let mut nexstar = NexStar::new(rx, hc);
+    nexstar.set_location(LOCATION).unwrap();
+    nexstar.set_datetime(DATETIME).unwrap();
+    nexstar.set_tracking_mode(TrackingMode::EQNorth).unwrap();
+
+    let (mut rx, tx) = nexstar.free();
+    rx.set_enabled(true);
+    let mut nexstar = NexStar::new(rx, tx);
+    nexstar.set_timeout(ticking_clock, TIMEOUT);
+    nexstar
+}
+
+fn close(a: f32, b: f32, tolerance: f32) -> bool {
+    (a - b).abs() <= tolerance
+}
+
+/// Runs one command, `Ok(false)` if it returned wrong data.
+fn command(nexstar: &mut Driver, index: usize) -> Result<bool, Error<(), ()>> {
+    let classify = |e: Error<_, _>| match e {
+        Error::Truncated { expected, got } => Error::Truncated { expected, got },
+        Error::UnexpectedResponse(context) => Error::UnexpectedResponse(context),
+        e => panic!("unclassified error {:?}", e),
+    };
+
+    let correct = match index % 12 {
+        0 => {
+            let location = nexstar.location().map_err(classify)?;
+            close(location.latitude, LOCATION.latitude, 1.0 / 3600.0)
+                && close(location.longitude, LOCATION.longitude, 1.0 / 3600.0)
+        }
+        1 => {
+            let position = nexstar.ra_dec().map_err(classify)?;
+            close(position.ra, AXES.0, 0.01) && close(position.dec, AXES.1, 0.01)
+        }
+        2 => {
+            let position = nexstar.precise_ra_dec().map_err(classify)?;
+            close(position.ra, AXES.0, 1e-4) && close(position.dec, AXES.1, 1e-4)
+        }
+        3 => nexstar.version().map_err(classify)? == Version::new(4, 21),
+        4 => matches!(nexstar.model().map_err(classify)?, Model::Se6_8),
+        5 => matches!(
+            nexstar.tracking_mode().map_err(classify)?,
+            TrackingMode::EQNorth
+        ),
+        6 => {
+            let datetime = nexstar.datetime().map_err(classify)?;
+            (datetime.hour, datetime.minutes, datetime.seconds) == (21, 30, 5)
+                && (datetime.year, datetime.month, datetime.day) == (24, 6, 15)
+                && datetime.zone == 1
+                && !datetime.daylight_saving
+        }
+        7 => nexstar.is_alignment_complete().map_err(classify)?,
+        8 => !nexstar.is_goto_in_progress().map_err(classify)?,
+        9 => {
+            let position = nexstar.azm_alt().map_err(classify)?;
+            close(position.azm, AXES.0, 0.01) && close(position.alt, AXES.1, 0.01)
+        }
+        10 => nexstar.cancel_goto().map_err(classify).map(|_| true)?,
+        _ => nexstar
+            .set_tracking_mode(TrackingMode::EQNorth)
+            .map_err(classify)
+            .map(|_| true)?,
+    };
+
+    Ok(correct)
+}
+
+/// Runs a session of mixed commands and returns the number of commands which
+/// succeeded with wrong data, failed and the faults injected.
+///
+/// A dropped, duplicated or delayed byte shifts the response against the
+/// trailing ack, which catches it as long as no data byte equals the ack.
+fn session(schedule: FaultSchedule) -> (usize, usize, FaultCounts) {
+    let hc = SimulatedHc::new();
+    let mut nexstar = setup(&hc, schedule);
+
+    let (mut wrong, mut failed) = (0, 0);
+    for index in 0..COMMANDS {
+        match command(&mut nexstar, index) {
+            Ok(true) => {}
+            Ok(false) => wrong += 1,
+            Err(_) => failed += 1,
+        }
+    }
+
+    // the link recovers once the line is clean again
+    let (mut rx, tx) = nexstar.free();
+    let faults = rx.counts();
+    rx.set_enabled(false);
+    let mut nexstar = NexStar::new(rx, tx);
+    nexstar.set_timeout(ticking_clock, TIMEOUT);
+    nexstar.resync().unwrap();
+    for index in 0..12 {
+        assert!(matches!(command(&mut nexstar, index), Ok(true)));
+    }
+
+    (wrong, failed, faults)
+}
+
+#[test]
+fn clean_line() {
+    let (wrong, failed, faults) = session(FaultSchedule::new(1));
+    assert_eq!((wrong, failed, faults.total()), (0, 0, 0));
+}
+
+#[test]
+fn dropped_bytes() {
+    for seed in 1..=SEEDS {
+        let (wrong, failed, faults) = session(FaultSchedule::new(seed).drop_one_in(50));
+        assert_eq!(wrong, 0, "seed {}", seed);
+        assert!(failed <= faults.total(), "seed {}", seed);
+    }
+}
+
+#[test]
+fn duplicated_bytes() {
+    for seed in 1..=SEEDS {
+        let (wrong, _, faults) = session(FaultSchedule::new(seed).duplicate_one_in(50));
+        assert_eq!(wrong, 0, "seed {}", seed);
+        assert!(faults.total() > 0, "seed {}", seed);
+    }
+}
+
+#[test]
+fn stalls() {
+    for seed in 1..=SEEDS {
+        let schedule = FaultSchedule::new(seed).stall_one_in(50, 500, 2500);
+        let (wrong, failed, faults) = session(schedule);
+        assert_eq!(wrong, 0, "seed {}", seed);
+        assert!(failed <= faults.total(), "seed {}", seed);
+    }
+}
+
+#[test]
+fn mixed_framing_faults() {
+    for seed in 1..=SEEDS {
+        let schedule = FaultSchedule::new(seed)
+            .drop_one_in(100)
+            .stall_one_in(100, 500, 2500);
+        let (wrong, _, _) = session(schedule);
+        assert_eq!(wrong, 0, "seed {}", seed);
+
+        let schedule = FaultSchedule::new(seed)
+            .duplicate_one_in(100)
+            .stall_one_in(100, 500, 2500);
+        let (wrong, _, _) = session(schedule);
+        assert_eq!(wrong, 0, "seed {}", seed);
+    }
+}
+
+/// A byte dropped and another one duplicated in the same response keep its
+/// length, so the shifted data can pass the framing checks. Each wrong result
+/// takes such a pair.
+#[test]
+fn compensating_faults() {
+    for seed in 1..=SEEDS {
+        let schedule = FaultSchedule::new(seed)
+            .drop_one_in(100)
+            .duplicate_one_in(100);
+        let (wrong, _, faults) = session(schedule);
+        assert!(
+            wrong <= faults.dropped.min(faults.duplicated),
+            "seed {}",
+            seed
+        );
+    }
+}
+
+/// The protocol has no checksum: a flipped bit inside a data byte can't be
+/// detected, only the flips hitting the framing or pushing a field out of its
+/// range. The driver must still never hang, only fail with classified errors
+/// and recover.
+#[test]
+fn flipped_bits() {
+    for seed in 1..=SEEDS {
+        let (wrong, failed, faults) = session(FaultSchedule::new(seed).flip_one_in(50));
+        assert!(wrong + failed <= faults.total(), "seed {}", seed);
+    }
+}
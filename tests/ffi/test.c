@@ -0,0 +1,128 @@
+/* Exercises the C interface of src/ffi.rs against a scripted UART, linked
+ * into the unit tests of the crate by build.rs. */
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <string.h>
+
+#include "nexstar.h"
+
+#define CHECK(condition)         \
+    do {                         \
+        if (!(condition))        \
+            return __LINE__;     \
+    } while (0)
+
+struct uart {
+    const uint8_t *response;
+    size_t response_len;
+    size_t read;
+    uint8_t written[32];
+    size_t written_len;
+};
+
+static int32_t uart_read(void *context, uint8_t *byte)
+{
+    struct uart *uart = context;
+    if (uart->read == uart->response_len)
+        return 0;
+    *byte = uart->response[uart->read++];
+    return 1;
+}
+
+static int32_t uart_write(void *context, uint8_t byte)
+{
+    struct uart *uart = context;
+    if (uart->written_len == sizeof uart->written)
+        return -1;
+    uart->written[uart->written_len++] = byte;
+    return 0;
+}
+
+static int32_t failing_read(void *context, uint8_t *byte)
+{
+    (void)context;
+    (void)byte;
+    return -5;
+}
+
+static void respond(struct uart *uart, const char *response, size_t len)
+{
+    uart->response = (const uint8_t *)response;
+    uart->response_len = len;
+    uart->read = 0;
+    uart->written_len = 0;
+}
+
+static bool written(const struct uart *uart, const char *expected, size_t len)
+{
+    return uart->written_len == len && memcmp(uart->written, expected, len) == 0;
+}
+
+static bool near(float value, float expected)
+{
+    float diff = value - expected;
+    return diff < 0.0001f && diff > -0.0001f;
+}
+
+int nexstar_ffi_test(void)
+{
+    NexstarHandle mount;
+    struct uart uart = {0};
+    NexstarLocation location;
+    NexstarDateTime datetime;
+    NexstarRaDec position;
+    bool in_progress;
+
+    memset(&mount, 0, sizeof mount);
+    CHECK(nexstar_cancel_goto(&mount) == NEXSTAR_STATUS_NOT_INITIALIZED);
+    CHECK(nexstar_cancel_goto(NULL) == NEXSTAR_STATUS_NULL_POINTER);
+    CHECK(nexstar_init(&mount, NULL, uart_write, &uart) == NEXSTAR_STATUS_NULL_POINTER);
+    CHECK(nexstar_init(&mount, uart_read, uart_write, &uart) == NEXSTAR_STATUS_OK);
+
+    respond(&uart, "\x2f\x0c\x22\x00\x08\x20\x07\x00#", 9);
+    CHECK(nexstar_get_location(&mount, &location) == NEXSTAR_STATUS_OK);
+    CHECK(near(location.latitude, 47.2094f) && near(location.longitude, 8.5353f));
+    CHECK(written(&uart, "w", 1));
+    CHECK(nexstar_get_location(&mount, NULL) == NEXSTAR_STATUS_NULL_POINTER);
+
+    respond(&uart, "#", 1);
+    location.latitude = 47.20944f;
+    location.longitude = -8.53528f;
+    CHECK(nexstar_set_location(&mount, location) == NEXSTAR_STATUS_OK);
+    CHECK(written(&uart, "W\x2f\x0c\x22\x00\x08\x20\x07\x01", 9));
+    location.latitude = 91.0f;
+    CHECK(nexstar_set_location(&mount, location) == NEXSTAR_STATUS_INVALID_LOCATION);
+
+    respond(&uart, "\x16\x1e\x0f\x0a\x0e\x1a\xfe\x01#", 9);
+    CHECK(nexstar_get_datetime(&mount, &datetime) == NEXSTAR_STATUS_OK);
+    CHECK(datetime.hour == 22 && datetime.minutes == 30 && datetime.seconds == 15);
+    CHECK(datetime.year == 26 && datetime.month == 10 && datetime.day == 14);
+    CHECK(datetime.zone == -2 && datetime.daylight_saving);
+
+    respond(&uart, "#", 1);
+    CHECK(nexstar_set_datetime(&mount, datetime) == NEXSTAR_STATUS_OK);
+    CHECK(written(&uart, "H\x16\x1e\x0f\x0a\x0e\x1a\xfe\x01", 9));
+
+    respond(&uart, "80000000,C0000000#", 18);
+    CHECK(nexstar_get_radec(&mount, &position) == NEXSTAR_STATUS_OK);
+    CHECK(near(position.ra, 180.0f) && near(position.dec, -90.0f));
+
+    respond(&uart, "#", 1);
+    CHECK(nexstar_goto_radec(&mount, position) == NEXSTAR_STATUS_OK);
+    CHECK(written(&uart, "r80000000,C0000000", 18));
+
+    respond(&uart, "1#", 2);
+    CHECK(nexstar_is_goto_in_progress(&mount, &in_progress) == NEXSTAR_STATUS_OK);
+    CHECK(in_progress);
+
+    respond(&uart, "1\x00#", 3);
+    CHECK(nexstar_is_goto_in_progress(&mount, &in_progress) ==
+          NEXSTAR_STATUS_UNEXPECTED_RESPONSE);
+
+    CHECK(nexstar_init(&mount, failing_read, uart_write, &uart) == NEXSTAR_STATUS_OK);
+    CHECK(nexstar_cancel_goto(&mount) == NEXSTAR_STATUS_READ);
+
+    return 0;
+}
@@ -0,0 +1,130 @@
+//! The arcsecond API, which also builds without the `float` feature.
+
+mod common;
+
+use common::{nexstar, FakeDelay};
+use nexstar::{AzmAltArcsec, Context, Error, RaDecArcsec};
+
+#[test]
+fn ra_dec_arcsec() {
+    let (mut nexstar, script) = nexstar(b"4000,F000#");
+
+    let position = nexstar.ra_dec_arcsec().unwrap();
+    assert_eq!(
+        position,
+        RaDecArcsec {
+            ra: 324_000,
+            dec: -81_000
+        }
+    );
+    assert_eq!(script.written(), b"E");
+}
+
+#[test]
+fn precise_azm_alt_arcsec() {
+    // 0x12345600 is 92_159.96" and 0xFFFFFF00 is 0.08" below a full turn
+    let (mut nexstar, script) = nexstar(b"12345600,FFFFFF00#");
+
+    let position = nexstar.precise_azm_alt_arcsec().unwrap();
+    assert_eq!(
+        position,
+        AzmAltArcsec {
+            azm: 92_160,
+            alt: 0
+        }
+    );
+    assert_eq!(script.written(), b"z");
+}
+
+#[test]
+fn position_arcsec_invalid() {
+    let (mut nexstar, _) = nexstar(b"40X0,F000#");
+
+    assert!(matches!(
+        nexstar.azm_alt_arcsec(),
+        Err(Error::UnexpectedResponse(Context::Hc(b'Z')))
+    ));
+}
+
+#[test]
+fn goto_arcsec() {
+    let (mut nexstar, script) = nexstar(b"##");
+
+    nexstar
+        .goto_ra_dec_arcsec(RaDecArcsec {
+            ra: 648_000,
+            dec: -324_000,
+        })
+        .unwrap();
+    assert_eq!(script.written(), b"R8000,C000");
+
+    // wraps into one revolution
+    nexstar
+        .precise_goto_azm_alt_arcsec(AzmAltArcsec {
+            azm: -1,
+            alt: 1_296_001,
+        })
+        .unwrap();
+    assert_eq!(script.written(), b"bFFFFF300,00000D00");
+}
+
+#[test]
+fn sync_arcsec() {
+    let (mut nexstar, script) = nexstar(b"#");
+
+    nexstar
+        .precise_sync_ra_dec_arcsec(RaDecArcsec {
+            ra: 324_000,
+            dec: 162_000,
+        })
+        .unwrap();
+    assert_eq!(script.written(), b"s40000000,20000000");
+}
+
+#[test]
+fn positions_arcsec() {
+    let (mut nexstar, script) = nexstar(b"40000000,20000000#80000000,E0000000#");
+    let mut delay = FakeDelay::default();
+
+    let positions: Vec<_> = nexstar
+        .positions_arcsec(&mut delay, 250)
+        .take(2)
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(
+        positions,
+        [
+            RaDecArcsec {
+                ra: 324_000,
+                dec: 162_000
+            },
+            RaDecArcsec {
+                ra: 648_000,
+                dec: -162_000
+            }
+        ]
+    );
+    assert_eq!(delay.delays, [250]);
+    assert_eq!(script.written(), b"ee");
+}
+
+#[cfg(feature = "float")]
+#[test]
+fn same_frames_as_degrees() {
+    let (mut nexstar, script) = nexstar(b"##");
+
+    for &(ra, dec) in &[(0, 0), (1, -1), (45_296, -292_953), (1_295_999, 324_000)] {
+        nexstar
+            .precise_goto_ra_dec_arcsec(RaDecArcsec { ra, dec })
+            .unwrap();
+        let arcsec = script.written();
+        nexstar
+            .precise_goto_ra_dec(nexstar::RaDec {
+                ra: ra as f32 / 3600.0,
+                dec: dec as f32 / 3600.0,
+            })
+            .unwrap();
+        assert_eq!(script.written(), arcsec);
+        script.respond(b"##");
+    }
+}
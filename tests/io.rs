@@ -0,0 +1,80 @@
+#![cfg(feature = "embedded-io")]
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use std::rc::Rc;
+
+use embedded_io::{ErrorType, Read, ReadReady, Write};
+use nexstar::io::FromEmbeddedIo;
+use nexstar::{LinkState, NexStar, Version};
+
+/// In-memory `embedded-io` pipe, the bytes written become readable
+#[derive(Clone, Default)]
+struct Pipe {
+    buffer: Rc<RefCell<VecDeque<u8>>>,
+}
+
+impl ErrorType for Pipe {
+    type Error = Infallible;
+}
+
+impl Read for Pipe {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Infallible> {
+        let mut buffer = self.buffer.borrow_mut();
+        let len = buf.len().min(buffer.len());
+        for (byte, value) in buf.iter_mut().zip(buffer.drain(..len)) {
+            *byte = value;
+        }
+        Ok(len)
+    }
+}
+
+impl ReadReady for Pipe {
+    fn read_ready(&mut self) -> Result<bool, Infallible> {
+        Ok(!self.buffer.borrow().is_empty())
+    }
+}
+
+impl Write for Pipe {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Infallible> {
+        self.buffer.borrow_mut().extend(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Infallible> {
+        Ok(())
+    }
+}
+
+#[test]
+fn embedded_io_backend() {
+    let (from_hc, mut to_hc) = (Pipe::default(), Pipe::default());
+    let mut nexstar = NexStar::new(
+        FromEmbeddedIo::new(from_hc.clone()),
+        FromEmbeddedIo::new(to_hc.clone()),
+    );
+
+    from_hc.clone().write_all(&[4, 21, b'#']).unwrap();
+    assert_eq!(nexstar.version().unwrap(), Version::new(4, 21));
+
+    let mut command = [0u8; 2];
+    assert_eq!(to_hc.read(&mut command).unwrap(), 1);
+    assert_eq!(command[0], b'V');
+    assert!(!to_hc.read_ready().unwrap());
+}
+
+#[test]
+fn raw_embedded_io_loopback() {
+    let pipe = Pipe::default();
+    let mut nexstar = NexStar::new(FromEmbeddedIo::new(pipe.clone()), FromEmbeddedIo::new(pipe));
+
+    nexstar.write_all(b"loopback").unwrap();
+    nexstar.flush().unwrap();
+    assert_eq!(nexstar.link_state(), LinkState::Dirty);
+
+    let mut received = [0u8; 16];
+    assert_eq!(nexstar.read(&mut received).unwrap(), 8);
+    assert_eq!(&received[..8], b"loopback");
+    assert_eq!(nexstar.read(&mut []).unwrap(), 0);
+}
@@ -0,0 +1,1044 @@
+mod common;
+
+use std::cell::{Cell, RefCell};
+
+use common::{nexstar, ticking_clock, transport, FakeDelay, MockError, Rx, Script, Tx};
+#[cfg(feature = "float")]
+use nexstar::RaDec;
+use nexstar::{
+    AzmAltArcsec, Borrowed, Compat, Context, Device, Error, LinkProbe, LinkState, NexStar,
+    RaDecArcsec, ResponseLength, Terminator, Version,
+};
+
+#[cfg(feature = "float")]
+const TARGET: RaDec = RaDec { ra: 0.0, dec: 0.0 };
+
+/// Creates a driver connected to an HC with the given firmware version.
+fn connected(major: u8, minor: u8) -> (NexStar<Rx, Tx>, Script) {
+    connected_with_motors(major, minor, [7, 11])
+}
+
+/// Creates a driver connected to an HC and motor controllers with the given
+/// firmware versions.
+fn connected_with_motors(major: u8, minor: u8, mc: [u8; 2]) -> (NexStar<Rx, Tx>, Script) {
+    let (mut nexstar, script) = nexstar(&[major, minor, b'#']);
+    let compat = Compat::for_version(Version::new(major, minor));
+    if compat.echo {
+        script.respond(&[0x42, b'#']);
+    }
+    if compat.model_query {
+        script.respond(&[0x0C, b'#']);
+    }
+    script.respond(&[mc[0], mc[1], b'#', mc[0], mc[1], b'#']);
+    nexstar.connect().unwrap();
+    script.written();
+    (nexstar, script)
+}
+
+#[cfg(feature = "float")]
+#[test]
+fn firmware_below_threshold() {
+    let (mut nexstar, script) = connected(1, 2);
+
+    assert!(matches!(
+        nexstar.precise_goto_ra_dec(TARGET),
+        Err(Error::UnsupportedFirmware {
+            required: Version { major: 1, minor: 6 },
+            found: Version { major: 1, minor: 2 },
+        })
+    ));
+    assert!(script.written().is_empty());
+}
+
+#[test]
+fn firmware_below_threshold_arcsec() {
+    let (mut nexstar, script) = connected(1, 2);
+
+    assert!(matches!(
+        nexstar.precise_goto_ra_dec_arcsec(RaDecArcsec { ra: 0, dec: 0 }),
+        Err(Error::UnsupportedFirmware {
+            required: Version { major: 1, minor: 6 },
+            found: Version { major: 1, minor: 2 },
+        })
+    ));
+    assert!(script.written().is_empty());
+}
+
+#[cfg(feature = "float")]
+#[test]
+fn firmware_minor_below_threshold() {
+    let (mut nexstar, script) = connected(4, 9);
+
+    assert!(matches!(
+        nexstar.precise_sync_ra_dec(TARGET),
+        Err(Error::UnsupportedFirmware {
+            required: Version {
+                major: 4,
+                minor: 10
+            },
+            ..
+        })
+    ));
+    assert!(script.written().is_empty());
+}
+
+#[cfg(feature = "float")]
+#[test]
+fn firmware_at_threshold() {
+    let (mut nexstar, script) = connected(1, 6);
+    script.respond(b"#");
+
+    nexstar.precise_goto_ra_dec(TARGET).unwrap();
+    assert_eq!(script.written(), b"r00000000,00000000");
+}
+
+#[cfg(feature = "float")]
+#[test]
+fn firmware_unknown() {
+    let (mut nexstar, script) = nexstar(b"#");
+
+    nexstar.precise_sync_ra_dec(TARGET).unwrap();
+    assert_eq!(script.written(), b"s00000000,00000000");
+}
+
+#[test]
+fn firmware_precise_azm_alt_below_threshold() {
+    // precise Azm/Alt came later than precise RA/Dec
+    let (mut nexstar, script) = connected(2, 1);
+
+    assert!(matches!(
+        nexstar.precise_azm_alt_arcsec(),
+        Err(Error::UnsupportedFirmware {
+            required: Version { major: 2, minor: 2 },
+            found: Version { major: 2, minor: 1 },
+        })
+    ));
+    assert!(matches!(
+        nexstar.precise_goto_azm_alt_arcsec(AzmAltArcsec { azm: 0, alt: 0 }),
+        Err(Error::UnsupportedFirmware { .. })
+    ));
+    assert!(script.written().is_empty());
+
+    script.respond(b"00000000,00000000#");
+    nexstar.precise_ra_dec_arcsec().unwrap();
+    assert_eq!(script.written(), b"e");
+}
+
+#[test]
+fn firmware_precise_azm_alt_at_threshold() {
+    let (mut nexstar, script) = connected(2, 2);
+    script.respond(b"#");
+
+    nexstar
+        .precise_goto_azm_alt_arcsec(AzmAltArcsec { azm: 0, alt: 0 })
+        .unwrap();
+    assert_eq!(script.written(), b"b00000000,00000000");
+}
+
+#[test]
+fn firmware_precise_azm_alt_unknown() {
+    let (mut nexstar, script) = nexstar(b"00000000,00000000#");
+
+    nexstar.precise_azm_alt_arcsec().unwrap();
+    assert_eq!(script.written(), b"z");
+}
+
+#[test]
+fn guide_pulse_firmware_below_threshold() {
+    let (mut nexstar, script) = connected_with_motors(4, 21, [6, 1]);
+
+    assert!(matches!(
+        nexstar.guide_pulse(Device::AzmRaMotor, 50, 100),
+        Err(Error::UnsupportedFirmware {
+            required: Version {
+                major: 7,
+                minor: 11
+            },
+            found: Version { major: 6, minor: 1 },
+        })
+    ));
+    assert!(script.written().is_empty());
+}
+
+#[test]
+fn guide_pulse_firmware_at_threshold() {
+    let (mut nexstar, script) = connected(4, 21);
+    script.respond(b"#");
+
+    nexstar.guide_pulse(Device::AltDecMotor, -50, 100).unwrap();
+    assert_eq!(script.written(), [b'P', 3, 17, 0x26, 0xCE, 100, 0, 0]);
+
+    script.respond(&[1, b'#']);
+    assert!(nexstar.is_guiding(Device::AltDecMotor).unwrap());
+}
+
+#[test]
+fn guide_pulse_firmware_unknown() {
+    let (mut nexstar, script) = nexstar(b"#");
+
+    nexstar.guide_pulse(Device::AzmRaMotor, 100, 1).unwrap();
+    assert_eq!(script.written(), [b'P', 3, 16, 0x26, 100, 1, 0, 0]);
+    assert!(matches!(
+        nexstar.guide_pulse(Device::AzmRaMotor, -101, 1),
+        Err(Error::InvalidSlewRate)
+    ));
+}
+
+#[test]
+fn guide_pulse_firmware_checks_disabled() {
+    let (mut nexstar, script) = connected_with_motors(4, 21, [6, 1]);
+    nexstar.set_firmware_checks(false);
+    script.respond(b"#");
+
+    nexstar.guide_pulse(Device::AzmRaMotor, 50, 100).unwrap();
+    assert_eq!(script.written().len(), 8);
+}
+
+#[cfg(feature = "float")]
+#[test]
+fn firmware_checks_disabled() {
+    let (mut nexstar, script) = connected(1, 2);
+    nexstar.set_firmware_checks(false);
+    script.respond(b"#");
+
+    nexstar.precise_goto_ra_dec(TARGET).unwrap();
+    assert_eq!(script.written().len(), 18);
+}
+
+#[cfg(feature = "float")]
+#[test]
+fn firmware_error_display() {
+    let (mut nexstar, _) = connected(1, 2);
+
+    let err = nexstar.precise_goto_ra_dec(TARGET).unwrap_err();
+    assert_eq!(err.to_string(), "firmware 1.2 found, 1.6 required");
+}
+
+#[test]
+fn compat_of_legacy_hc() {
+    // 2.0 answers the echo but not the model query
+    let (mut nexstar, script) = connected(2, 0);
+
+    assert!(matches!(
+        nexstar.model(),
+        Err(Error::UnsupportedFirmware {
+            required: Version { major: 2, minor: 2 },
+            found: Version { major: 2, minor: 0 },
+        })
+    ));
+    assert!(script.written().is_empty());
+    assert!(!nexstar.compat().wide_positions);
+}
+
+#[test]
+fn compat_override() {
+    let (mut nexstar, script) = nexstar(b"40000,C0000#");
+    nexstar.set_compat(Some(Compat {
+        wide_positions: true,
+        ..Compat::CURRENT
+    }));
+
+    let position = nexstar.ra_dec_arcsec().unwrap();
+    assert_eq!((position.ra, position.dec), (324_000, -324_000));
+    assert_eq!(script.written(), b"E");
+
+    // a connect keeps the override
+    script.respond(&[
+        4, 21, b'#', 0x42, b'#', 0x0C, b'#', 7, 11, b'#', 7, 11, b'#',
+    ]);
+    nexstar.connect().unwrap();
+    assert!(nexstar.compat().wide_positions);
+
+    nexstar.set_compat(None);
+    assert_eq!(nexstar.compat(), Compat::CURRENT);
+}
+
+#[test]
+fn compat_without_echo() {
+    let (mut nexstar, script) = nexstar(b"\x00#");
+    nexstar.set_compat(Some(Compat {
+        echo: false,
+        ..Compat::CURRENT
+    }));
+    nexstar.set_timeout(ticking_clock, 10);
+
+    // found 0.0 before a connect
+    let err = nexstar.keepalive().unwrap_err();
+    assert_eq!(err.to_string(), "firmware 0.0 found, 1.2 required");
+
+    // the dirty link is resynced without the echo probe
+    assert!(nexstar.cancel_goto().is_err());
+    script.respond(b"#");
+    nexstar.cancel_goto().unwrap();
+    assert_eq!(script.written(), b"MM");
+    assert_eq!(nexstar.link_state(), LinkState::Clean);
+}
+
+#[test]
+fn resync_after_error() {
+    let (mut nexstar, script) = nexstar(b"\x00#junk");
+
+    assert!(nexstar.cancel_goto().is_err());
+    assert_eq!(nexstar.link_state(), LinkState::Dirty);
+
+    script.respond(b"#");
+    nexstar.cancel_goto().unwrap();
+    assert_eq!(nexstar.link_state(), LinkState::Clean);
+    assert_eq!(script.pending(), 0);
+
+    script.respond(b"#");
+    nexstar.cancel_goto().unwrap();
+    assert_eq!(script.written(), b"MMM");
+}
+
+#[test]
+fn resync_runs_once() {
+    let (mut nexstar, script) = nexstar(b"\x00#junk");
+    nexstar.set_timeout(ticking_clock, 10);
+    assert!(nexstar.cancel_goto().is_err());
+    script.written();
+
+    // one probe before the next command drops the junk
+    script.respond(b"B##");
+    nexstar.cancel_goto().unwrap();
+    assert_eq!(script.written(), b"KBM");
+    assert_eq!(script.pending(), 0);
+
+    // none once the link is clean, so stray bytes reach the next command
+    script.arrive(b"x");
+    script.respond(b"#");
+    assert!(matches!(
+        nexstar.cancel_goto(),
+        Err(Error::UnexpectedResponse(Context::Hc(b'M')))
+    ));
+    assert_eq!(script.written(), b"M");
+}
+
+#[test]
+fn link_dirty_without_auto_resync() {
+    let (mut nexstar, script) = nexstar(b"\x00#junk");
+    nexstar.set_auto_resync(false);
+
+    assert!(nexstar.cancel_goto().is_err());
+    script.written();
+    assert!(matches!(nexstar.cancel_goto(), Err(Error::LinkDirty)));
+    assert!(script.written().is_empty());
+
+    nexstar.resync().unwrap();
+    assert_eq!(nexstar.link_state(), LinkState::Clean);
+    script.respond(b"#");
+    nexstar.cancel_goto().unwrap();
+    assert_eq!(script.pending(), 0);
+}
+
+#[test]
+fn write_error_marks_link_dirty() {
+    let (mut nexstar, script) = nexstar(&[]);
+    script.set_fail_writes(true);
+
+    assert!(matches!(nexstar.cancel_goto(), Err(Error::Write(_))));
+    assert_eq!(nexstar.link_state(), LinkState::Dirty);
+}
+
+#[test]
+fn echo_suppression() {
+    let (mut nexstar, script) = nexstar(&[4, 21, b'#']);
+    script.set_local_echo(true);
+    nexstar.set_echo_suppression(true);
+
+    assert_eq!(nexstar.version().unwrap(), Version::new(4, 21));
+    assert_eq!(script.pending(), 0);
+}
+
+#[test]
+fn echo_suppression_without_echo() {
+    let (mut nexstar, _) = nexstar(&[4, 21, b'#']);
+    nexstar.set_echo_suppression(true);
+
+    assert!(matches!(nexstar.version(), Err(Error::EchoMismatch)));
+    assert_eq!(nexstar.link_state(), LinkState::Dirty);
+}
+
+/// Runs a mix of ASCII and pass-through commands.
+fn echo_sequence(nexstar: &mut NexStar<Rx, Tx>, script: &Script) {
+    script.respond(b"#");
+    nexstar
+        .precise_goto_ra_dec_arcsec(RaDecArcsec { ra: 0, dec: 0 })
+        .unwrap();
+    script.respond(&[7, 11, b'#']);
+    assert_eq!(
+        nexstar.device_version(Device::AzmRaMotor).unwrap(),
+        Version::new(7, 11)
+    );
+    script.respond(b"0#");
+    assert!(!nexstar.is_goto_in_progress().unwrap());
+    assert_eq!(script.pending(), 0);
+}
+
+#[test]
+fn echo_suppression_on_both_transports() {
+    let (mut echoing, echoed) = nexstar(&[]);
+    echoed.set_local_echo(true);
+    echoing.set_echo_suppression(true);
+    echo_sequence(&mut echoing, &echoed);
+
+    let (mut plain, script) = nexstar(&[]);
+    echo_sequence(&mut plain, &script);
+
+    // the same commands went out either way
+    assert_eq!(echoed.written(), script.written());
+    assert_eq!(echoing.link_state(), LinkState::Clean);
+}
+
+#[test]
+fn echo_suppression_corrupted_echo() {
+    // a stray byte in front of the echo
+    let (mut nexstar, script) = nexstar(&[]);
+    nexstar.set_echo_suppression(true);
+    script.arrive(b"X");
+
+    assert!(matches!(nexstar.version(), Err(Error::EchoMismatch)));
+    assert_eq!(nexstar.link_state(), LinkState::Dirty);
+}
+
+#[test]
+fn echo_without_suppression() {
+    let (mut nexstar, script) = nexstar(&[4, 21, b'#']);
+    script.set_local_echo(true);
+
+    assert!(nexstar.version().is_err());
+}
+
+#[cfg(feature = "float")]
+#[test]
+fn truncated_response() {
+    let (mut nexstar, script) = nexstar(&[47, 12, 34, 0, 8]);
+    nexstar.set_timeout(ticking_clock, 10);
+
+    assert!(matches!(
+        nexstar.location(),
+        Err(Error::Truncated {
+            expected: 8,
+            got: 5
+        })
+    ));
+    assert_eq!(nexstar.link_state(), LinkState::Dirty);
+
+    // the rest arrives late and must not be taken for the next response
+    script.arrive(&[32, 7, 0, b'#']);
+    script.respond(b"B#");
+    script.respond(&[4, 21, b'#']);
+    assert_eq!(nexstar.version().unwrap(), Version::new(4, 21));
+    assert_eq!(script.written(), b"wKBV");
+}
+
+#[cfg(feature = "float")]
+#[test]
+fn resync_probe_skips_stalled_tail() {
+    let (mut nexstar, script) = nexstar(&[47, 12]);
+    nexstar.set_timeout(ticking_clock, 10);
+    assert!(nexstar.location().is_err());
+
+    // the tail only arrives after the probe was sent, ahead of its echo
+    script.respond(&[34, 0, 8, 32, 7, 0, b'#', b'B', b'#']);
+    nexstar.resync().unwrap();
+    assert_eq!(script.pending(), 0);
+
+    script.respond(&[4, 21, b'#']);
+    assert_eq!(nexstar.version().unwrap(), Version::new(4, 21));
+}
+
+#[test]
+fn resync_probe_markers_change() {
+    let (mut nexstar, script) = nexstar(&[]);
+    nexstar.set_timeout(ticking_clock, 10);
+
+    script.respond(b"B#");
+    nexstar.resync().unwrap();
+    // the echo of the first probe arrives again, the second one isn't fooled
+    script.respond(b"B#C#");
+    nexstar.resync().unwrap();
+    assert_eq!(script.written(), b"KBKC");
+    assert_eq!(script.pending(), 0);
+}
+
+#[test]
+fn timeout_applies_per_byte() {
+    // reading the 18 bytes takes far longer than the 2 ms per byte
+    let (mut nexstar, _) = nexstar(b"00000000,40000000#");
+    nexstar.set_timeout(ticking_clock, 2);
+
+    let position = nexstar.precise_ra_dec_arcsec().unwrap();
+    assert_eq!(position.dec, 324_000);
+}
+
+#[test]
+fn truncated_before_first_byte() {
+    let (mut nexstar, script) = nexstar(&[]);
+    nexstar.set_timeout(ticking_clock, 10);
+
+    assert!(matches!(
+        nexstar.location_dms(),
+        Err(Error::Truncated {
+            expected: 8,
+            got: 0
+        })
+    ));
+
+    // the whole response arrives late and is dropped
+    script.arrive(&[47, 12, 34, 0, 8, 32, 7, 0, b'#']);
+    script.respond(b"B#");
+    script.respond(&[1, b'#']);
+    assert!(nexstar.is_alignment_complete().unwrap());
+    assert_eq!(script.pending(), 0);
+}
+
+#[test]
+fn missing_ack() {
+    let (mut nexstar, _) = nexstar(&[4, 21]);
+    nexstar.set_timeout(ticking_clock, 10);
+
+    assert!(matches!(
+        nexstar.version(),
+        Err(Error::Truncated {
+            expected: 1,
+            got: 0
+        })
+    ));
+}
+
+#[test]
+fn context_of_pass_through() {
+    let (rx, tx, script) = transport();
+    let mut nexstar = NexStar::new(rx, tx);
+    script.respond(&[0, 0, 0, b'#']);
+
+    let err = nexstar.device_version(nexstar::Device::RTC).unwrap_err();
+    assert!(matches!(
+        err,
+        Error::UnexpectedResponse(Context::PassThrough {
+            device: nexstar::Device::RTC,
+            command: 0xFE
+        })
+    ));
+    assert_eq!(err.to_string(), "unexpected response to 0xFE to RTC");
+}
+
+thread_local! {
+    static NOW: Cell<u32> = const { Cell::new(0) };
+    static DELAYS: RefCell<Vec<u32>> = const { RefCell::new(Vec::new()) };
+}
+
+fn manual_clock() -> u32 {
+    NOW.with(Cell::get)
+}
+
+/// Records the delay and lets the clock advance by it
+fn recording_delay(ms: u32) {
+    DELAYS.with(|delays| delays.borrow_mut().push(ms));
+    NOW.with(|now| now.set(now.get().wrapping_add(ms)));
+}
+
+fn advance(ms: u32) {
+    NOW.with(|now| now.set(now.get().wrapping_add(ms)));
+}
+
+fn delays() -> Vec<u32> {
+    DELAYS.with(|delays| delays.take())
+}
+
+#[test]
+fn keepalive_healthy() {
+    let (mut nexstar, script) = nexstar(&[1, b'#', 2, b'#']);
+    assert!(nexstar.link_health().is_none());
+    nexstar.track_link_health(manual_clock);
+
+    nexstar.keepalive().unwrap();
+    advance(60_000);
+    nexstar.keepalive().unwrap();
+    assert_eq!(script.written(), [b'K', 1, b'K', 2]);
+
+    advance(1500);
+    let health = nexstar.link_health().unwrap();
+    assert_eq!(health.consecutive_failures(), 0);
+    assert_eq!(health.since_success_ms(), Some(1500));
+}
+
+#[test]
+fn keepalive_intermittent() {
+    let (mut nexstar, script) = nexstar(&[1, b'#']);
+    nexstar.track_link_health(manual_clock);
+    nexstar.keepalive().unwrap();
+
+    // a stale echo fails the check
+    advance(60_000);
+    script.respond(&[1, b'#']);
+    assert!(matches!(
+        nexstar.keepalive(),
+        Err(Error::UnexpectedResponse(Context::Hc(b'K')))
+    ));
+    let health = nexstar.link_health().unwrap();
+    assert_eq!(health.consecutive_failures(), 1);
+    assert_eq!(health.since_success_ms(), Some(60_000));
+
+    // the next one succeeds and resets the count
+    script.respond(&[3, b'#']);
+    nexstar.keepalive().unwrap();
+    let health = nexstar.link_health().unwrap();
+    assert_eq!(health.consecutive_failures(), 0);
+    assert_eq!(health.since_success_ms(), Some(0));
+    assert_eq!(nexstar.link_state(), LinkState::Clean);
+}
+
+#[test]
+fn keepalive_dead_link() {
+    let (mut nexstar, _) = nexstar(&[]);
+    nexstar.set_timeout(ticking_clock, 10);
+    nexstar.track_link_health(manual_clock);
+
+    for failures in 1..=3 {
+        assert!(nexstar.keepalive().is_err());
+        let health = nexstar.link_health().unwrap();
+        assert_eq!(health.consecutive_failures(), failures);
+        assert_eq!(health.since_success_ms(), None);
+    }
+    assert_eq!(nexstar.link_state(), LinkState::Dirty);
+}
+
+#[test]
+fn echo_byte() {
+    let (mut nexstar, script) = nexstar(b"x###\x42#");
+
+    nexstar.echo(b'x').unwrap();
+    nexstar.echo(b'#').unwrap();
+    assert!(matches!(
+        nexstar.echo(0x41),
+        Err(Error::UnexpectedResponse(Context::Hc(b'K')))
+    ));
+    assert_eq!(script.written(), b"KxK#KA");
+}
+
+#[test]
+fn probe_link_ok() {
+    let (mut nexstar, script) = nexstar(b"\x00###Z#\xa5#");
+    let mut delay = FakeDelay::default();
+
+    assert_eq!(nexstar.probe_link(&mut delay).unwrap(), LinkProbe::Ok);
+    assert_eq!(script.written(), b"K\x00K#KZK\xa5");
+    assert_eq!(nexstar.link_state(), LinkState::Clean);
+}
+
+#[test]
+fn probe_link_loopback_wire() {
+    // a wire shorted TX to RX returns the command, which a fixed echo byte
+    // can't tell from the HC
+    let (mut nexstar, script) = nexstar(&[]);
+    script.set_local_echo(true);
+    let mut delay = FakeDelay::default();
+
+    assert_eq!(
+        nexstar.probe_link(&mut delay).unwrap(),
+        LinkProbe::WrongEcho
+    );
+    assert_eq!(script.written(), b"K\x00");
+    assert_eq!(nexstar.link_state(), LinkState::Dirty);
+
+    // with the local echo expected the HC is missing behind it
+    let (rx, tx, script) = transport();
+    script.set_local_echo(true);
+    let mut nexstar = NexStar::new(rx, tx);
+    nexstar.set_echo_suppression(true);
+
+    assert_eq!(nexstar.probe_link(&mut delay).unwrap(), LinkProbe::Silent);
+}
+
+#[test]
+fn probe_link_fixed_echo() {
+    // a device answering every echo with the same byte
+    let (mut nexstar, _) = nexstar(b"B#");
+    let mut delay = FakeDelay::default();
+
+    assert_eq!(
+        nexstar.probe_link(&mut delay).unwrap(),
+        LinkProbe::WrongEcho
+    );
+}
+
+#[test]
+fn probe_link_silent() {
+    let (mut nexstar, script) = nexstar(&[]);
+    let mut delay = FakeDelay::default();
+
+    assert_eq!(nexstar.probe_link(&mut delay).unwrap(), LinkProbe::Silent);
+    assert_eq!(delay.total(), 200);
+    assert_eq!(script.written(), b"K\x00");
+    assert_eq!(nexstar.link_state(), LinkState::Dirty);
+}
+
+#[test]
+fn probe_link_read_error() {
+    let (mut nexstar, script) = nexstar(&[]);
+    script.set_fail_reads(true);
+    let mut delay = FakeDelay::default();
+
+    assert!(matches!(
+        nexstar.probe_link(&mut delay),
+        Err(Error::Read(MockError))
+    ));
+    assert_eq!(nexstar.link_state(), LinkState::Dirty);
+}
+
+#[test]
+fn pacing() {
+    let (rx, tx, script) = transport();
+    let mut nexstar = NexStar::new(rx, tx).with_pacing(recording_delay, manual_clock, 50);
+    NOW.with(|now| now.set(u32::MAX - 30));
+
+    // nothing to wait for before the first command
+    script.respond(b"#");
+    nexstar.cancel_goto().unwrap();
+    assert_eq!(delays(), []);
+
+    // across the wrap of the clock
+    advance(40);
+    script.respond(b"#");
+    nexstar.cancel_goto().unwrap();
+    assert_eq!(delays(), [10]);
+
+    script.respond(b"#");
+    nexstar.cancel_goto().unwrap();
+    assert_eq!(delays(), [50]);
+
+    advance(50);
+    script.respond(b"#");
+    nexstar.cancel_goto().unwrap();
+    assert_eq!(delays(), []);
+    assert_eq!(script.written(), b"MMMM");
+}
+
+#[test]
+fn pacing_disabled() {
+    let (rx, tx, script) = transport();
+    let mut nexstar = NexStar::new(rx, tx).with_pacing(recording_delay, manual_clock, 0);
+
+    script.respond(b"##");
+    nexstar.cancel_goto().unwrap();
+    nexstar.cancel_goto().unwrap();
+    assert_eq!(delays(), []);
+}
+
+/// Gets the version with a driver living only for this call.
+fn version(rx: &mut Rx, tx: &mut Tx) -> Version {
+    let mut nexstar: NexStar<Borrowed<Rx>, Borrowed<Tx>> = NexStar::borrowed(rx, tx);
+    nexstar.version().unwrap()
+}
+
+#[test]
+fn borrowed_transport_reused() {
+    let (mut rx, mut tx, script) = transport();
+
+    script.respond(&[4, 21, b'#']);
+    assert_eq!(version(&mut rx, &mut tx), Version::new(4, 21));
+
+    // another peripheral behind the mux uses the UART in between
+    embedded_hal::serial::Write::write(&mut tx, 0xAA).unwrap();
+
+    script.respond(&[4, 21, b'#']);
+    assert_eq!(version(&mut rx, &mut tx), Version::new(4, 21));
+    assert_eq!(script.written(), b"V\xaaV");
+}
+
+#[test]
+fn raw_write_marks_link_dirty() {
+    let (mut nexstar, script) = nexstar(b"");
+
+    embedded_hal::serial::Write::write(&mut nexstar, b'K').unwrap();
+    embedded_hal::serial::Write::flush(&mut nexstar).unwrap();
+    assert_eq!(nexstar.link_state(), LinkState::Dirty);
+    assert_eq!(script.written(), b"K");
+
+    // the raw echo command left its response unread, the resync drains it
+    script.arrive(b"X#");
+    script.respond(&[4, 21, b'#']);
+    assert_eq!(nexstar.version().unwrap(), Version::new(4, 21));
+    assert_eq!(script.written(), b"V");
+    assert_eq!(nexstar.link_state(), LinkState::Clean);
+}
+
+#[test]
+fn raw_read_marks_link_dirty() {
+    let (mut nexstar, script) = nexstar(b"");
+
+    assert_eq!(
+        embedded_hal::serial::Read::read(&mut nexstar),
+        Err(nb::Error::WouldBlock)
+    );
+    assert_eq!(nexstar.link_state(), LinkState::Clean);
+
+    script.arrive(b"\x42");
+    assert_eq!(embedded_hal::serial::Read::read(&mut nexstar), Ok(0x42));
+    assert_eq!(nexstar.link_state(), LinkState::Dirty);
+}
+
+#[test]
+fn raw_command_echo() {
+    let (mut nexstar, script) = nexstar(b"x#y#");
+    let mut response = [0u8; 4];
+
+    assert_eq!(
+        nexstar
+            .raw_command(b"Kx", &mut response, Terminator::Hash)
+            .unwrap(),
+        1
+    );
+    assert_eq!(response[0], b'x');
+    assert_eq!(
+        nexstar
+            .raw_command(b"Ky", &mut response, Terminator::Exact(2))
+            .unwrap(),
+        2
+    );
+    assert_eq!(&response[..2], b"y#");
+    assert_eq!(script.written(), b"KxKy");
+    assert_eq!(nexstar.link_state(), LinkState::Clean);
+}
+
+#[test]
+fn raw_command_overflow() {
+    let (mut nexstar, script) = nexstar(b"abc#");
+    let mut response = [0u8; 2];
+
+    assert!(matches!(
+        nexstar.raw_command(b"?", &mut response, Terminator::Exact(3)),
+        Err(Error::FrameOverflow)
+    ));
+    assert!(script.written().is_empty());
+
+    assert!(matches!(
+        nexstar.raw_command(b"?", &mut response, Terminator::Hash),
+        Err(Error::FrameOverflow)
+    ));
+    assert_eq!(nexstar.link_state(), LinkState::Dirty);
+
+    // the rest of the response is dropped by the resync
+    script.respond(&[4, 21, b'#']);
+    assert_eq!(nexstar.version().unwrap(), Version::new(4, 21));
+}
+
+#[test]
+fn raw_command_truncated() {
+    let (mut nexstar, script) = nexstar(b"ab");
+    nexstar.set_timeout(ticking_clock, 10);
+    let mut response = [0u8; 8];
+
+    assert!(matches!(
+        nexstar.raw_command(b"?", &mut response, Terminator::Hash),
+        Err(Error::Truncated {
+            expected: 3,
+            got: 2
+        })
+    ));
+    assert_eq!(nexstar.link_state(), LinkState::Dirty);
+
+    script.respond(b"B#");
+    script.respond(&[4, 21, b'#']);
+    assert_eq!(nexstar.version().unwrap(), Version::new(4, 21));
+    assert_eq!(script.written(), b"?KBV");
+}
+
+#[test]
+fn pass_through_exact_fit() {
+    let (mut nexstar, script) = nexstar(&[7, 11, b'#', 0x80, b'#']);
+    let mut response = [0u8; 2];
+
+    let len = nexstar
+        .pass_through(
+            Device::AzmRaMotor,
+            0xFE,
+            &[],
+            ResponseLength::Exact(2),
+            &mut response,
+        )
+        .unwrap();
+    assert_eq!(&response[..len], &[7, 11]);
+
+    // the length of a known command is looked up
+    let len = nexstar
+        .pass_through(
+            Device::AltDecMotor,
+            0x47,
+            &[],
+            ResponseLength::Auto,
+            &mut response,
+        )
+        .unwrap();
+    assert_eq!(&response[..len], &[0x80]);
+
+    let mut expected = b"P\x01\x10\xfe\x00\x00\x00\x02".to_vec();
+    expected.extend(b"P\x01\x11\x47\x00\x00\x00\x01");
+    assert_eq!(script.written(), expected);
+    assert_eq!(nexstar.link_state(), LinkState::Clean);
+}
+
+#[test]
+fn pass_through_hash_in_data() {
+    let (mut nexstar, script) = nexstar(b"#\x01##\x23\x23\x23#");
+    let mut response = [0u8; 3];
+
+    let len = nexstar
+        .pass_through(
+            Device::AzmRaMotor,
+            0x01,
+            &[],
+            ResponseLength::Exact(3),
+            &mut response,
+        )
+        .unwrap();
+    assert_eq!(&response[..len], b"#\x01#");
+
+    let len = nexstar
+        .pass_through(
+            Device::AltDecMotor,
+            0x01,
+            &[],
+            ResponseLength::Auto,
+            &mut response,
+        )
+        .unwrap();
+    assert_eq!(&response[..len], b"###");
+    assert_eq!(nexstar.link_state(), LinkState::Clean);
+    assert_eq!(script.pending(), 0);
+}
+
+#[test]
+fn pass_through_buffer_too_small() {
+    let (mut nexstar, script) = nexstar(b"SS 1.0.4#\x01\x02\x03#");
+    let mut response = [0u8; 4];
+
+    // unknown commands are read up to the #
+    assert!(matches!(
+        nexstar.pass_through(
+            Device::MainBoard,
+            0x3C,
+            &[1],
+            ResponseLength::Auto,
+            &mut response,
+        ),
+        Err(Error::BufferTooSmall { needed: 8, got: 4 })
+    ));
+    assert_eq!(&response, b"SS 1");
+    assert_eq!(script.written(), b"P\x02\x01\x3c\x01\x00\x00\x11");
+
+    // the response was consumed, the next one starts in sync
+    let mut response = [0u8; 2];
+    assert!(matches!(
+        nexstar.pass_through(
+            Device::AzmRaMotor,
+            0x01,
+            &[],
+            ResponseLength::Exact(3),
+            &mut response,
+        ),
+        Err(Error::BufferTooSmall { needed: 3, got: 2 })
+    ));
+    assert_eq!(nexstar.link_state(), LinkState::Clean);
+    assert_eq!(script.pending(), 0);
+}
+
+#[test]
+fn pass_through_shorter_than_expected() {
+    let (mut nexstar, script) = nexstar(b"7.11#");
+    let mut response = [0u8; 8];
+
+    let len = nexstar
+        .pass_through(
+            Device::MainBoard,
+            0xFE,
+            &[],
+            ResponseLength::UpTo(8),
+            &mut response,
+        )
+        .unwrap();
+    assert_eq!(&response[..len], b"7.11");
+
+    // a fixed length waits for the missing bytes
+    nexstar.set_timeout(ticking_clock, 10);
+    script.respond(&[7, 11, b'#']);
+    assert!(matches!(
+        nexstar.pass_through(
+            Device::AzmRaMotor,
+            0xFE,
+            &[],
+            ResponseLength::Exact(4),
+            &mut response,
+        ),
+        Err(Error::Truncated {
+            expected: 5,
+            got: 3
+        })
+    ));
+    assert_eq!(nexstar.link_state(), LinkState::Dirty);
+}
+
+#[test]
+fn pass_through_rejected() {
+    let (mut nexstar, _) = nexstar(&[0, 0x01, b'#']);
+    let mut response = [0u8; 1];
+
+    assert!(matches!(
+        nexstar.pass_through(
+            Device::AltDecMotor,
+            0x40,
+            &[],
+            ResponseLength::Auto,
+            &mut response,
+        ),
+        Err(Error::UnsupportedCommand(Context::PassThrough {
+            device: Device::AltDecMotor,
+            command: 0x40
+        }))
+    ));
+    assert_eq!(nexstar.link_state(), LinkState::Clean);
+
+    assert!(matches!(
+        nexstar.pass_through(
+            Device::AltDecMotor,
+            0x40,
+            &[1, 2, 3, 4],
+            ResponseLength::Auto,
+            &mut response,
+        ),
+        Err(Error::FrameOverflow)
+    ));
+}
+
+#[test]
+fn diagnostics_capture_nack() {
+    let (mut nexstar, _) = nexstar(&[4, 21, b'#', 0, 0, 0x01, b'#']);
+    let mut out = [0u8; 256];
+    assert_eq!(nexstar.dump_diagnostics(&mut out), 0);
+
+    nexstar.set_diagnostics(Some(Box::leak(Box::new([0u8; 64]))));
+    nexstar.version().unwrap();
+    assert!(matches!(
+        nexstar.max_slew_rate(Device::AltDecMotor),
+        Err(Error::UnsupportedCommand(_))
+    ));
+
+    let len = nexstar.dump_diagnostics(&mut out);
+    assert_eq!(
+        std::str::from_utf8(&out[..len]).unwrap(),
+        "> 56\n\
+         < 04 15 23\n\
+         > 50 01 11 21 00 00 00 02\n\
+         < 00 00 01 23\n\
+         # error: nack\n"
+    );
+
+    // a small output keeps the newest records
+    let len = nexstar.dump_diagnostics(&mut out[..40]);
+    assert_eq!(
+        std::str::from_utf8(&out[..len]).unwrap(),
+        "> 00 00 02\n< 00 00 01 23\n# error: nack\n"
+    );
+}
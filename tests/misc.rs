@@ -0,0 +1,1148 @@
+mod common;
+
+use std::cell::RefCell;
+
+use common::{nexstar, ticking_clock, FakeDelay, Rx, Script, Tx};
+use nexstar::{
+    Backlash, Compat, Context, Device, Error, GpsState, LinkState, Model, MotorModel, NexStar,
+    RaDecArcsec, TrackingMode, Version, Versions,
+};
+
+#[test]
+fn version() {
+    let (mut nexstar, script) = nexstar(&[4, 21, b'#']);
+
+    assert_eq!(nexstar.version().unwrap(), Version::new(4, 21));
+    assert_eq!(script.written(), b"V");
+}
+
+#[test]
+fn version_nack() {
+    let (mut nexstar, _) = nexstar(&[4, 21, 0x00, b'#']);
+
+    assert!(matches!(
+        nexstar.version(),
+        Err(Error::UnexpectedResponse(Context::Hc(b'V')))
+    ));
+}
+
+#[test]
+fn device_version() {
+    let (mut nexstar, script) = nexstar(&[7, 11, b'#']);
+
+    assert_eq!(
+        nexstar.device_version(Device::AzmRaMotor).unwrap(),
+        Version::new(7, 11)
+    );
+    assert_eq!(script.written(), [b'P', 1, 0x10, 0xFE, 0, 0, 0, 2]);
+}
+
+#[test]
+fn device_version_absent() {
+    let (mut nexstar, _) = nexstar(&[0, 0, 0, b'#']);
+
+    assert!(matches!(
+        nexstar.device_version(Device::GPSUnit),
+        Err(Error::UnexpectedResponse(Context::PassThrough {
+            device: Device::GPSUnit,
+            command: 0xFE
+        }))
+    ));
+}
+
+#[test]
+fn cached_identity() {
+    let (mut nexstar, script) = nexstar(&[4, 21, b'#', 0x0C, b'#', 7, 11, b'#']);
+    assert_eq!(nexstar.cached_model(), None);
+    assert_eq!(nexstar.cached_versions(), Versions::default());
+
+    assert_eq!(nexstar.version().unwrap(), Version::new(4, 21));
+    assert!(matches!(nexstar.model().unwrap(), Model::Se6_8));
+    nexstar.device_version(Device::AltDecMotor).unwrap();
+    assert_eq!(script.written().len(), 10);
+
+    // answered from the cache without any I/O
+    assert_eq!(nexstar.version().unwrap(), Version::new(4, 21));
+    assert!(matches!(nexstar.model().unwrap(), Model::Se6_8));
+    assert_eq!(
+        nexstar.device_version(Device::AltDecMotor).unwrap(),
+        Version::new(7, 11)
+    );
+    assert!(script.written().is_empty());
+
+    assert!(matches!(nexstar.cached_model(), Some(Model::Se6_8)));
+    let versions = nexstar.cached_versions();
+    assert_eq!(versions.hc, Some(Version::new(4, 21)));
+    assert_eq!(
+        versions.device(Device::AltDecMotor),
+        Some(Version::new(7, 11))
+    );
+    assert_eq!(versions.device(Device::AzmRaMotor), None);
+}
+
+#[test]
+fn cache_invalidated() {
+    let (mut nexstar, script) = nexstar(&[4, 21, b'#', 0x0C, b'#']);
+    nexstar.version().unwrap();
+    nexstar.model().unwrap();
+
+    nexstar.invalidate_cache();
+    assert_eq!(nexstar.cached_model(), None);
+    assert_eq!(nexstar.cached_versions().hc, None);
+
+    // a firmware update
+    script.respond(&[4, 22, b'#']);
+    assert_eq!(nexstar.version().unwrap(), Version::new(4, 22));
+    assert_eq!(script.written(), b"VmV");
+}
+
+#[test]
+fn cache_force_refresh() {
+    let (mut nexstar, script) = nexstar(&[4, 21, b'#', 4, 22, b'#', 4, 22, b'#']);
+    nexstar.set_force_refresh(true);
+
+    nexstar.version().unwrap();
+    assert_eq!(nexstar.version().unwrap(), Version::new(4, 22));
+    assert_eq!(nexstar.cached_versions().hc, Some(Version::new(4, 22)));
+
+    nexstar.set_force_refresh(false);
+    nexstar.version().unwrap();
+    assert_eq!(script.written(), b"VV");
+    assert_eq!(script.pending(), 3);
+}
+
+#[test]
+fn cache_skips_absent_device() {
+    let (mut nexstar, script) = nexstar(&[0, 0, 0, b'#']);
+
+    assert!(nexstar.device_version(Device::GPSUnit).is_err());
+    assert_eq!(nexstar.cached_versions().device(Device::GPSUnit), None);
+
+    // plugged in later
+    script.respond(&[1, 6, b'#']);
+    assert_eq!(
+        nexstar.device_version(Device::GPSUnit).unwrap(),
+        Version::new(1, 6)
+    );
+    assert_eq!(script.written().len(), 16);
+}
+
+#[test]
+fn wifi_reboot() {
+    let (mut nexstar, script) = nexstar(&[2, 5, b'#']);
+
+    assert_eq!(
+        nexstar.device_version(Device::WiFi).unwrap(),
+        Version::new(2, 5)
+    );
+    assert_eq!(script.written(), [b'P', 1, 0xb5, 0xFE, 0, 0, 0, 2]);
+
+    script.respond(b"#");
+    nexstar.wifi_reboot().unwrap();
+    assert_eq!(script.written(), [b'P', 1, 0xb5, 0x49, 0, 0, 0, 0]);
+}
+
+#[test]
+fn wifi_reboot_absent() {
+    let (mut nexstar, _) = nexstar(&[0, b'#']);
+
+    assert!(matches!(
+        nexstar.wifi_reboot(),
+        Err(Error::UnsupportedCommand(Context::PassThrough {
+            device: Device::WiFi,
+            command: 0x49
+        }))
+    ));
+}
+
+#[test]
+fn wifi_reboot_without_ack() {
+    let (mut nexstar, script) = nexstar(&[]);
+    nexstar.set_timeout(ticking_clock, 10);
+
+    nexstar.wifi_reboot().unwrap();
+    assert_eq!(nexstar.link_state(), LinkState::Dirty);
+
+    // the link recovers once the module is back
+    script.respond(b"B#");
+    script.respond(&[4, 21, b'#']);
+    assert_eq!(nexstar.version().unwrap(), Version::new(4, 21));
+    assert_eq!(script.written()[8..], *b"KBV");
+
+    nexstar.set_wifi_reboot_needs_ack(true);
+    assert!(matches!(
+        nexstar.wifi_reboot(),
+        Err(Error::Truncated {
+            expected: 1,
+            got: 0
+        })
+    ));
+}
+
+#[test]
+fn battery_voltage() {
+    // 12.8 V in µV
+    let (mut nexstar, script) = nexstar(&[0, 0x40, 0x00, 0xC3, 0x50, 0x00, b'#']);
+
+    assert_eq!(nexstar.battery_voltage().unwrap(), 12_800);
+    assert_eq!(script.written(), [b'P', 1, 0xb6, 0x10, 0, 0, 0, 6]);
+}
+
+#[test]
+fn battery_absent() {
+    let (mut nexstar, _) = nexstar(&[0, 0, 0, 0, 0, 0, 0, b'#']);
+
+    assert!(matches!(
+        nexstar.battery_percent(),
+        Err(Error::UnsupportedCommand(Context::PassThrough {
+            device: Device::Battery,
+            command: 0x10
+        }))
+    ));
+}
+
+#[test]
+fn release_stops_rates() {
+    let (mut nexstar, script) = nexstar(b"##");
+    nexstar.track_rates(100, 0).unwrap();
+    script.written();
+
+    // no GOTO, but the rates and a late byte
+    script.respond(b"0###x");
+    let mut delay = FakeDelay::default();
+    let (_, _, report) = nexstar.release(&mut delay);
+
+    assert!(report.is_ok());
+    let written = script.written();
+    assert_eq!(written[0], b'L');
+    assert_eq!(
+        written[1..],
+        [
+            [b'P', 3, 0x10, 0x06, 0, 0, 0, 0],
+            [b'P', 3, 0x11, 0x06, 0, 0, 0, 0]
+        ]
+        .concat()
+    );
+    assert_eq!(script.pending(), 0);
+    assert_eq!(delay.total(), 50);
+}
+
+#[test]
+fn release_reports_failures() {
+    let (nexstar, script) = nexstar(b"1#\x00#");
+    let mut delay = FakeDelay::default();
+
+    let (_, _, report) = nexstar.release(&mut delay);
+    assert!(!report.is_ok());
+    assert!(matches!(
+        report.cancel_goto,
+        Err(Error::UnexpectedResponse(Context::Hc(b'M')))
+    ));
+    // nothing else to stop
+    assert!(report.stop_rates.is_ok() && report.drain.is_ok());
+    assert_eq!(script.written(), b"LM");
+}
+
+#[test]
+fn model() {
+    let (mut nexstar, script) = nexstar(&[0x0C, b'#']);
+
+    assert!(matches!(nexstar.model().unwrap(), Model::Se6_8));
+    assert_eq!(script.written(), b"m");
+}
+
+#[test]
+fn model_unknown() {
+    let (mut nexstar, _) = nexstar(&[0x42, b'#']);
+
+    assert!(matches!(nexstar.model().unwrap(), Model::Unknown(0x42)));
+}
+
+#[test]
+fn model_nack() {
+    let (mut nexstar, _) = nexstar(&[0x0C, 0x00, b'#']);
+
+    let err = nexstar.model().unwrap_err();
+    assert!(matches!(err, Error::UnexpectedResponse(Context::Hc(b'm'))));
+    assert_eq!(err.to_string(), "unexpected response to 'm'");
+}
+
+#[test]
+fn is_alignment_complete() {
+    let (mut nexstar, script) = nexstar(&[1, b'#', 0, b'#']);
+
+    assert!(nexstar.is_alignment_complete().unwrap());
+    assert!(!nexstar.is_alignment_complete().unwrap());
+    assert_eq!(script.written(), b"JJ");
+}
+
+#[test]
+fn is_alignment_complete_ascii() {
+    // some firmware answers with the digits instead
+    let (mut nexstar, _) = nexstar(b"1#0#");
+
+    assert!(nexstar.is_alignment_complete().unwrap());
+    assert!(!nexstar.is_alignment_complete().unwrap());
+}
+
+#[test]
+fn is_alignment_complete_invalid() {
+    let (mut nexstar, _) = nexstar(b"2#");
+
+    assert!(matches!(
+        nexstar.is_alignment_complete(),
+        Err(Error::UnexpectedResponse(Context::Hc(b'J')))
+    ));
+}
+
+#[test]
+fn is_alignment_complete_nack() {
+    let (mut nexstar, _) = nexstar(&[1, 0x00, b'#']);
+
+    assert!(nexstar.is_alignment_complete().is_err());
+}
+
+#[test]
+fn is_goto_in_progress() {
+    let (mut nexstar, script) = nexstar(b"1#0#");
+
+    assert!(nexstar.is_goto_in_progress().unwrap());
+    assert!(!nexstar.is_goto_in_progress().unwrap());
+    assert_eq!(script.written(), b"LL");
+}
+
+#[test]
+fn is_goto_in_progress_binary() {
+    // some firmware answers with binary 0 and 1 like the alignment state
+    let (mut nexstar, _) = nexstar(&[1, b'#', 0, b'#']);
+
+    assert!(nexstar.is_goto_in_progress().unwrap());
+    assert!(!nexstar.is_goto_in_progress().unwrap());
+}
+
+#[test]
+fn is_goto_in_progress_invalid() {
+    let (mut nexstar, _) = nexstar(&[2, b'#']);
+
+    assert!(matches!(
+        nexstar.is_goto_in_progress(),
+        Err(Error::UnexpectedResponse(Context::Hc(b'L')))
+    ));
+}
+
+#[test]
+fn is_goto_in_progress_nack() {
+    let (mut nexstar, _) = nexstar(&[b'1', 0x00, b'#']);
+
+    assert!(nexstar.is_goto_in_progress().is_err());
+}
+
+#[test]
+fn status() {
+    let (mut nexstar, script) = nexstar(b"\x01#1#\x02#");
+
+    let status = nexstar.status().unwrap();
+    assert!(status.aligned);
+    assert!(status.goto_in_progress);
+    assert!(matches!(status.tracking, TrackingMode::EQNorth));
+    assert_eq!(script.written(), b"JLt");
+}
+
+#[test]
+fn status_fails_with_context() {
+    let (mut nexstar, script) = nexstar(&[0x01, b'#', b'1', 0x00, b'#']);
+
+    assert!(matches!(
+        nexstar.status(),
+        Err(Error::UnexpectedResponse(Context::Hc(b'L')))
+    ));
+    assert_eq!(script.written(), b"JL");
+}
+
+#[test]
+fn connect() {
+    let (mut nexstar, script) = nexstar(&[]);
+    script.respond(&[4, 21, b'#']);
+    script.respond(&[0x42, b'#']);
+    script.respond(&[0x0C, b'#']);
+    script.respond(&[7, 11, b'#']);
+    // no ALT/DEC motor
+    script.respond(&[0, 0, 0, b'#']);
+
+    let info = nexstar.connect().unwrap();
+    assert_eq!(info.hc_version, Version::new(4, 21));
+    assert!(matches!(info.model, Model::Se6_8));
+    assert_eq!(info.azm_ra_version, Some(Version::new(7, 11)));
+    assert_eq!(info.alt_dec_version, None);
+    assert_eq!(nexstar.mount_info().unwrap().hc_version, info.hc_version);
+
+    let mut expected = vec![b'V', b'K', 0x42, b'm'];
+    expected.extend([b'P', 1, 0x10, 0xFE, 0, 0, 0, 2]);
+    expected.extend([b'P', 1, 0x11, 0xFE, 0, 0, 0, 2]);
+    assert_eq!(script.written(), expected);
+}
+
+#[test]
+fn connect_echo_mismatch() {
+    let (mut nexstar, _) = nexstar(&[4, 21, b'#', 0x43, b'#']);
+
+    assert!(matches!(
+        nexstar.connect(),
+        Err(Error::UnexpectedResponse(Context::Hc(b'K')))
+    ));
+    assert!(nexstar.mount_info().is_none());
+}
+
+/// Creates a driver connected to a StarSense HC 1.2.
+fn starsense() -> (NexStar<Rx, Tx>, Script) {
+    let (mut nexstar, script) = nexstar(&[1, 2, 7, b'#', 0x42, b'#', 0x8C, b'#']);
+    nexstar.set_starsense_detection(Some(ticking_clock));
+    script.respond(&[7, 11, b'#', 7, 11, b'#']);
+    nexstar.connect().unwrap();
+    script.written();
+    (nexstar, script)
+}
+
+#[test]
+fn connect_starsense() {
+    let (mut nexstar, script) = nexstar(&[1, 2, 7, b'#', 0x42, b'#', 0x8C, b'#']);
+    nexstar.set_starsense_detection(Some(ticking_clock));
+    script.respond(&[7, 11, b'#', 7, 11, b'#']);
+
+    let info = nexstar.connect().unwrap();
+    assert_eq!(info.hc_version, Version::new(1, 2));
+    assert!(matches!(info.model, Model::Se6_8));
+    assert_eq!(nexstar.compat(), Compat::STARSENSE);
+    assert_eq!(&script.written()[..4], b"VK\x42m");
+
+    // the version is read in the StarSense format afterwards
+    nexstar.set_force_refresh(true);
+    script.respond(&[1, 3, 0, b'#']);
+    assert_eq!(nexstar.version().unwrap(), Version::new(1, 3));
+}
+
+#[test]
+fn connect_starsense_without_detection() {
+    // the detection is experimental and off by default
+    let (mut nexstar, script) = nexstar(&[1, 2, 7, b'#']);
+    assert!(matches!(
+        nexstar.connect(),
+        Err(Error::UnexpectedResponse(Context::Hc(b'V')))
+    ));
+    assert_eq!(script.written(), b"V");
+
+    // the compat set by hand reads the StarSense answers
+    let (mut nexstar, script) = common::nexstar(&[1, 2, 7, b'#', 0x42, b'#', 0x8C, b'#']);
+    nexstar.set_compat(Some(Compat::STARSENSE));
+    script.respond(&[7, 11, b'#', 7, 11, b'#']);
+    let info = nexstar.connect().unwrap();
+    assert_eq!(info.hc_version, Version::new(1, 2));
+    assert!(matches!(info.model, Model::Se6_8));
+    assert_eq!(script.pending(), 0);
+}
+
+thread_local! {
+    /// Script, tick and responses of [`late_ack_clock`]
+    static LATE_ACK: RefCell<Option<(Script, u32, &'static [u8])>> = const { RefCell::new(None) };
+}
+
+/// Ticking clock making a `#` arrive at its tick, like a USB serial adapter
+/// holding back the last byte, and staging the responses after it
+fn late_ack_clock() -> u32 {
+    let now = ticking_clock();
+    LATE_ACK.with(|late| {
+        if let Some((script, at, responses)) = &*late.borrow() {
+            if now == *at {
+                script.arrive(b"#");
+                script.respond(responses);
+            }
+        }
+    });
+    now
+}
+
+#[test]
+fn connect_starsense_build_35() {
+    // the build byte is a '#', so only the second ack tells it from a NexStar
+    let (mut nexstar, script) = nexstar(&[1, 2, b'#', b'#', 0x42, b'#', 0x8C, b'#']);
+    nexstar.set_starsense_detection(Some(ticking_clock));
+    script.respond(&[7, 11, b'#', 7, 11, b'#']);
+
+    let info = nexstar.connect().unwrap();
+    assert_eq!(info.hc_version, Version::new(1, 2));
+    assert!(matches!(info.model, Model::Se6_8));
+    assert_eq!(nexstar.compat(), Compat::STARSENSE);
+    assert_eq!(&script.written()[..4], b"VK\x42m");
+    assert_eq!(script.pending(), 0);
+    assert_eq!(nexstar.link_state(), LinkState::Clean);
+
+    // the second ack held back for 20 ms by the adapter
+    let (mut nexstar, script) = common::nexstar(&[1, 2, b'#']);
+    nexstar.set_starsense_detection(Some(late_ack_clock));
+    let responses: &[u8] = &[0x42, b'#', 0x8C, b'#', 7, 11, b'#', 7, 11, b'#'];
+    LATE_ACK
+        .with(|late| *late.borrow_mut() = Some((script.clone(), ticking_clock() + 20, responses)));
+
+    let info = nexstar.connect().unwrap();
+    assert_eq!(info.hc_version, Version::new(1, 2));
+    assert_eq!(nexstar.compat(), Compat::STARSENSE);
+    assert_eq!(script.pending(), 0);
+    assert_eq!(nexstar.link_state(), LinkState::Clean);
+}
+
+#[test]
+fn connect_nexstar_with_starsense_detection() {
+    // a NexStar answer is taken as it is after waiting for the second ack,
+    // the echo is left unanswered to stop the connect right after it
+    let (mut nexstar, script) = nexstar(&[4, 21, b'#']);
+    nexstar.set_starsense_detection(Some(ticking_clock));
+    nexstar.set_timeout(ticking_clock, 10);
+    let start = ticking_clock();
+
+    assert!(matches!(nexstar.connect(), Err(Error::Truncated { .. })));
+    assert_eq!(script.written(), b"VK\x42");
+    assert_eq!(nexstar.compat(), Compat::for_version(Version::new(4, 21)));
+    assert!(ticking_clock() - start > 50);
+
+    // a byte read after it is kept for the next response
+    let (mut nexstar, script) = common::nexstar(&[4, 21, b'#', 0x42, b'#', 0x0C, b'#']);
+    nexstar.set_starsense_detection(Some(ticking_clock));
+    script.respond(&[7, 11, b'#', 7, 11, b'#']);
+    let info = nexstar.connect().unwrap();
+    assert_eq!(info.hc_version, Version::new(4, 21));
+    assert_eq!(nexstar.link_state(), LinkState::Clean);
+}
+
+#[test]
+fn starsense_busy_aligning() {
+    let (mut nexstar, script) = starsense();
+    script.respond(&[0x00, b'#', 0, b'#']);
+
+    let err = nexstar.cancel_goto().unwrap_err();
+    assert!(matches!(err, Error::Aligning(Context::Hc(b'M'))));
+    assert_eq!(script.written(), b"MJ");
+    assert_eq!(nexstar.link_state(), LinkState::Clean);
+
+    // a nacked alignment query counts as aligning too
+    script.respond(&[0x00, b'#', 0, 0x00, b'#']);
+    assert!(matches!(
+        nexstar.cancel_goto(),
+        Err(Error::Aligning(Context::Hc(b'M')))
+    ));
+}
+
+#[test]
+fn starsense_nack_when_aligned() {
+    let (mut nexstar, script) = starsense();
+    script.respond(&[0x00, b'#', 1, b'#']);
+
+    assert!(matches!(
+        nexstar.cancel_goto(),
+        Err(Error::UnexpectedResponse(Context::Hc(b'M')))
+    ));
+}
+
+#[test]
+fn starsense_skips_firmware_check() {
+    // SYNC needs NexStar firmware 4.10
+    let (mut nexstar, script) = starsense();
+    script.respond(b"#");
+
+    nexstar
+        .sync_ra_dec_arcsec(RaDecArcsec { ra: 0, dec: 0 })
+        .unwrap();
+}
+
+#[test]
+fn wait_for_alignment_starsense() {
+    let (mut nexstar, script) = starsense();
+    script.respond(&[0, 0x00, b'#', 0, b'#', 1, b'#']);
+    let mut delay = FakeDelay::default();
+
+    nexstar.wait_for_alignment(&mut delay, 100, 1000).unwrap();
+    assert_eq!(delay.delays, [100, 100]);
+    assert_eq!(script.written(), b"JJJ");
+}
+
+#[test]
+fn write_status_degrades() {
+    // the GOTO state is invalid
+    let (mut nexstar, _) = nexstar(b"4000,C000#\x02#\x01#2#");
+
+    let mut out = String::new();
+    nexstar.write_status(&mut out).unwrap();
+    assert_eq!(
+        out,
+        "RA/Dec   06h00m00s -90°00'00\"\nTracking EQ North\nAligned  yes\nGOTO     --\n"
+    );
+}
+
+#[test]
+fn write_status_silent_mount() {
+    let (mut nexstar, _) = nexstar(&[]);
+    nexstar.set_timeout(ticking_clock, 10);
+
+    let mut out = String::new();
+    nexstar.write_status(&mut out).unwrap();
+    assert_eq!(out, "RA/Dec   --\nTracking --\nAligned  --\nGOTO     --\n");
+}
+
+#[test]
+fn wait_for_alignment() {
+    let (mut nexstar, script) = nexstar(&[0, b'#', 0, b'#', 1, b'#']);
+    let mut delay = FakeDelay::default();
+
+    nexstar.wait_for_alignment(&mut delay, 100, 1000).unwrap();
+    assert_eq!(delay.delays, [100, 100]);
+    assert_eq!(script.written(), b"JJJ");
+}
+
+#[test]
+fn wait_for_alignment_timeout() {
+    let (mut nexstar, _) = nexstar(&[0, b'#', 0, b'#', 0, b'#', 0, b'#']);
+    let mut delay = FakeDelay::default();
+
+    assert!(matches!(
+        nexstar.wait_for_alignment(&mut delay, 100, 250),
+        Err(Error::Timeout)
+    ));
+    assert_eq!(delay.total(), 300);
+}
+
+#[test]
+fn set_max_slew_rate() {
+    let (mut nexstar, script) = nexstar(b"##");
+
+    nexstar
+        .set_max_slew_rate(Device::AltDecMotor, 10_800)
+        .unwrap();
+
+    // 3°/s in quarter arcsec/s, then enabled
+    let mut expected = b"P\x03\x11\x20\xa8\xc0\x00\x00".to_vec();
+    expected.extend(b"P\x02\x11\x22\x01\x00\x00\x00");
+    assert_eq!(script.written(), expected);
+}
+
+#[test]
+fn set_max_slew_rate_range() {
+    let (mut nexstar, script) = nexstar(b"##");
+
+    for rate in [0, 16_384] {
+        assert!(matches!(
+            nexstar.set_max_slew_rate(Device::AzmRaMotor, rate),
+            Err(Error::InvalidSlewRate)
+        ));
+    }
+    assert!(script.written().is_empty());
+
+    nexstar
+        .set_max_slew_rate(Device::AzmRaMotor, 16_383)
+        .unwrap();
+    assert_eq!(&script.written()[4..6], &[0xff, 0xfc]);
+}
+
+#[test]
+fn max_slew_rate() {
+    let (mut nexstar, script) = nexstar(&[0xa8, 0xc0, b'#']);
+
+    assert_eq!(nexstar.max_slew_rate(Device::AzmRaMotor).unwrap(), 10_800);
+    assert_eq!(script.written(), b"P\x01\x10\x21\x00\x00\x00\x02");
+}
+
+#[test]
+fn max_slew_rate_unsupported() {
+    // old firmware answers with an error code after the expected bytes
+    let (mut nexstar, _) = nexstar(&[0, 0, 0x01, b'#', 0x01, b'#']);
+
+    assert!(matches!(
+        nexstar.max_slew_rate(Device::AltDecMotor),
+        Err(Error::UnsupportedCommand(Context::PassThrough {
+            device: Device::AltDecMotor,
+            command: 0x21,
+        }))
+    ));
+    assert!(matches!(
+        nexstar.set_max_slew_rate(Device::AltDecMotor, 3600),
+        Err(Error::UnsupportedCommand(_))
+    ));
+    assert_eq!(nexstar.link_state(), LinkState::Clean);
+}
+
+#[test]
+fn backlash_and_autoguide_rate() {
+    let (mut nexstar, script) = nexstar(b"##\x1e#\x0c##\xc0#");
+
+    let backlash = Backlash {
+        positive: 30,
+        negative: 12,
+    };
+    nexstar.set_backlash(Device::AzmRaMotor, backlash).unwrap();
+    assert_eq!(nexstar.backlash(Device::AzmRaMotor).unwrap(), backlash);
+    let mut expected = b"P\x02\x10\x10\x1e\x00\x00\x00".to_vec();
+    expected.extend(b"P\x02\x10\x11\x0c\x00\x00\x00");
+    expected.extend(b"P\x01\x10\x40\x00\x00\x00\x01");
+    expected.extend(b"P\x01\x10\x41\x00\x00\x00\x01");
+    assert_eq!(script.written(), expected);
+
+    // 75% of the sidereal rate in 1/256 of it
+    nexstar.set_autoguide_rate(Device::AltDecMotor, 75).unwrap();
+    assert_eq!(nexstar.autoguide_rate(Device::AltDecMotor).unwrap(), 75);
+    let mut expected = b"P\x02\x11\x46\xc0\x00\x00\x00".to_vec();
+    expected.extend(b"P\x01\x11\x47\x00\x00\x00\x01");
+    assert_eq!(script.written(), expected);
+
+    assert!(matches!(
+        nexstar.set_autoguide_rate(Device::AltDecMotor, 100),
+        Err(Error::InvalidSlewRate)
+    ));
+    assert!(script.written().is_empty());
+}
+
+#[test]
+fn motor_position() {
+    let (mut nexstar, script) = nexstar(b"\x12\x34\x56#");
+
+    assert_eq!(
+        nexstar.motor_position(Device::AltDecMotor).unwrap(),
+        0x12_3456
+    );
+    assert_eq!(script.written(), b"P\x01\x11\x01\x00\x00\x00\x03");
+}
+
+#[test]
+fn motor_position_with_hash_bytes() {
+    let (mut nexstar, script) = nexstar(b"####\x23\x00\x23#\x01\x23\x00#");
+
+    assert_eq!(
+        nexstar.motor_position(Device::AzmRaMotor).unwrap(),
+        0x23_2323
+    );
+    assert_eq!(
+        nexstar.motor_position(Device::AzmRaMotor).unwrap(),
+        0x23_0023
+    );
+    assert_eq!(
+        nexstar.motor_position(Device::AzmRaMotor).unwrap(),
+        0x01_2300
+    );
+    assert_eq!(script.pending(), 0);
+}
+
+#[cfg(feature = "float")]
+#[test]
+fn motor_angle_beyond_90() {
+    // 256 counts are about 20 arcseconds
+    let (mut nexstar, _script) = nexstar(b"\x40\x01\x00#\x80\x00\x00#\xBF\xFF\x00#\xFF\xFF\xFF#");
+
+    let past_90 = nexstar.motor_angle(Device::AltDecMotor).unwrap();
+    assert!(past_90 > 90.0 && past_90 < 90.01, "{}", past_90);
+    assert_eq!(nexstar.motor_angle(Device::AltDecMotor).unwrap(), 180.0);
+    let below_minus_90 = nexstar.motor_angle(Device::AltDecMotor).unwrap();
+    assert!(
+        below_minus_90 < -90.0 && below_minus_90 > -90.01,
+        "{}",
+        below_minus_90
+    );
+    let below_zero = nexstar.motor_angle(Device::AltDecMotor).unwrap();
+    assert!(below_zero < 0.0 && below_zero > -0.001, "{}", below_zero);
+}
+
+#[test]
+fn set_motor_position() {
+    let (mut nexstar, script) = nexstar(b"##");
+
+    nexstar
+        .set_motor_position_arcsec(Device::AltDecMotor, -324_000)
+        .unwrap();
+    assert_eq!(script.written(), b"P\x04\x11\x04\xC0\x00\x00\x00");
+    nexstar
+        .set_motor_position_arcsec(Device::AzmRaMotor, 648_000)
+        .unwrap();
+    assert_eq!(script.written(), b"P\x04\x10\x04\x80\x00\x00\x00");
+
+    for arcsec in [648_001, -648_001, i32::MIN] {
+        assert!(matches!(
+            nexstar.set_motor_position_arcsec(Device::AzmRaMotor, arcsec),
+            Err(Error::InvalidPosition)
+        ));
+    }
+    #[cfg(feature = "float")]
+    for degrees in [180.01, -180.01, f32::NAN, f32::INFINITY] {
+        assert!(matches!(
+            nexstar.set_motor_position(Device::AzmRaMotor, degrees),
+            Err(Error::InvalidPosition)
+        ));
+    }
+    assert!(script.written().is_empty());
+}
+
+#[test]
+fn motor_angle_arcsec_beyond_90() {
+    let (mut nexstar, _script) = nexstar(b"\x40\x01\x00#\x80\x00\x00#\xBF\xFF\x00#");
+
+    assert_eq!(
+        nexstar.motor_angle_arcsec(Device::AltDecMotor).unwrap(),
+        324_020
+    );
+    assert_eq!(
+        nexstar.motor_angle_arcsec(Device::AltDecMotor).unwrap(),
+        648_000
+    );
+    assert_eq!(
+        nexstar.motor_angle_arcsec(Device::AltDecMotor).unwrap(),
+        -324_020
+    );
+}
+
+#[cfg(feature = "float")]
+#[test]
+fn calibrate_slew_rate_always_stops() {
+    // 100 arcsec/s are 400 quarter arcsec/s
+    let position = b"P\x01\x10\x01\x00\x00\x00\x03";
+    let slew = b"P\x03\x10\x06\x01\x90\x00\x00";
+    let stop = b"P\x03\x10\x06\x00\x00\x00\x00";
+
+    let (mut nexstar, script) = nexstar(b"\x00\x00\x00###");
+    let mut delay = FakeDelay::default();
+    nexstar.set_abort_hook(Some(|| true));
+    assert!(matches!(
+        nexstar.calibrate_slew_rate(Device::AzmRaMotor, 100.0, 5000, &mut delay),
+        Err(Error::Aborted)
+    ));
+    assert_eq!(script.written(), [&position[..], slew, stop].concat());
+    assert!(delay.delays.is_empty());
+
+    // the final position can only be read once the motor stopped
+    let (mut nexstar, script) = common::nexstar(b"\x00\x00\x00###\x00\x00\x00\x01#");
+    assert!(matches!(
+        nexstar.calibrate_slew_rate(Device::AzmRaMotor, 100.0, 250, &mut delay),
+        Err(Error::UnsupportedCommand(_))
+    ));
+    assert_eq!(
+        script.written(),
+        [&position[..], slew, stop, position].concat()
+    );
+    assert_eq!(delay.delays, [100, 100, 50]);
+
+    assert!(matches!(
+        nexstar.calibrate_slew_rate(Device::AzmRaMotor, 100.0, 0, &mut delay),
+        Err(Error::InvalidSlewRate)
+    ));
+    assert!(script.written().is_empty());
+}
+
+#[cfg(feature = "float")]
+#[test]
+fn measure_backlash_always_stops() {
+    // 60 arcsec/s are 240 quarter arcsec/s
+    let position = b"P\x01\x11\x01\x00\x00\x00\x03";
+    let negative = b"P\x03\x11\x07\x00\xF0\x00\x00";
+    let positive = b"P\x03\x11\x06\x00\xF0\x00\x00";
+    let stop = b"P\x03\x11\x06\x00\x00\x00\x00";
+
+    // the axis never moves
+    let mut responses = b"##\x00\x00\x00##".to_vec();
+    for _ in 0..330 {
+        responses.extend_from_slice(b"\x00\x00\x00#");
+    }
+    responses.push(b'#');
+    let (mut nexstar, script) = nexstar(&responses);
+    let mut delay = FakeDelay::default();
+    assert!(matches!(
+        nexstar.measure_backlash(Device::AltDecMotor, 60.0, &mut delay),
+        Err(Error::Timeout)
+    ));
+    let mut expected = [&negative[..], stop, position, positive].concat();
+    for _ in 0..330 {
+        expected.extend_from_slice(position);
+    }
+    expected.extend_from_slice(stop);
+    assert_eq!(script.written(), expected);
+    // taking up the play and waiting for the axis, 990 arcsec each
+    assert_eq!(delay.total(), 33_000);
+
+    let (mut nexstar, script) = common::nexstar(b"##");
+    nexstar.set_abort_hook(Some(|| true));
+    assert!(matches!(
+        nexstar.measure_backlash(Device::AltDecMotor, -60.0, &mut delay),
+        Err(Error::Aborted)
+    ));
+    assert_eq!(script.written(), [&negative[..], stop].concat());
+
+    assert!(matches!(
+        nexstar.measure_backlash(Device::AltDecMotor, 0.0, &mut delay),
+        Err(Error::InvalidSlewRate)
+    ));
+    assert!(script.written().is_empty());
+}
+
+#[test]
+fn homing_commands() {
+    let (mut nexstar, script) = nexstar(&[b'#', b'#', 0x00, b'#', 0xFF, b'#']);
+
+    nexstar.seek_index(Device::AzmRaMotor).unwrap();
+    nexstar.level_start(Device::AltDecMotor).unwrap();
+    assert!(!nexstar.index_found(Device::AzmRaMotor).unwrap());
+    assert!(nexstar.level_done(Device::AltDecMotor).unwrap());
+
+    let written = script.written();
+    let commands: Vec<_> = written
+        .chunks(8)
+        .map(|frame| (frame[2], frame[3], frame[7]))
+        .collect();
+    assert_eq!(
+        commands,
+        [
+            (0x10, 0x19, 0),
+            (0x11, 0x0B, 0),
+            (0x10, 0x18, 1),
+            (0x11, 0x12, 1)
+        ]
+    );
+}
+
+#[test]
+fn motor_model() {
+    let (mut nexstar, script) = nexstar(&[0x03, b'#', 0x42, b'#', 0, 0x01, b'#']);
+
+    assert_eq!(
+        nexstar.motor_model(Device::AzmRaMotor).unwrap(),
+        MotorModel::Gen3
+    );
+    assert_eq!(
+        nexstar.motor_model(Device::AltDecMotor).unwrap(),
+        MotorModel::Unknown(0x42)
+    );
+    assert!(matches!(
+        nexstar.motor_model(Device::AzmRaMotor),
+        Err(Error::UnsupportedCommand(_))
+    ));
+    assert_eq!(&script.written()[..8], b"P\x01\x10\x05\x00\x00\x00\x01");
+}
+
+#[cfg(feature = "float")]
+#[test]
+fn set_guiderate() {
+    use nexstar::GuideRate;
+
+    let model = b"P\x01\x10\x05\x00\x00\x00\x01";
+
+    // second generation, in 1/1024 arcsec/s
+    let (mut nexstar, script) = nexstar(b"\x02##\x02##\x02##\x02##");
+    let rates = [
+        (GuideRate::SIDEREAL, b"P\x04\x10\x06\x00\x3C\x2A\x00"),
+        (GuideRate(0.5), b"P\x04\x10\x06\x00\x1E\x15\x00"),
+        (GuideRate::OFF, b"P\x04\x10\x06\x00\x00\x00\x00"),
+        (GuideRate(-0.5), b"P\x04\x10\x07\x00\x1E\x15\x00"),
+    ];
+    for (rate, frame) in rates {
+        nexstar.set_guiderate(Device::AzmRaMotor, rate).unwrap();
+        assert_eq!(script.written(), [&model[..], frame].concat());
+    }
+
+    // first generation and controllers predating the query, in quarter
+    // arcsec/s
+    let (mut nexstar, script) = common::nexstar(b"\x01##\x01##\x00\x01##");
+    let rates = [
+        (GuideRate::SIDEREAL, &b"P\x03\x10\x06\x00\x3C\x00\x00"[..]),
+        (GuideRate(0.5), b"P\x03\x10\x06\x00\x1E\x00\x00"),
+        (GuideRate::OFF, b"P\x03\x10\x06\x00\x00\x00\x00"),
+    ];
+    for (rate, frame) in rates {
+        nexstar.set_guiderate(Device::AzmRaMotor, rate).unwrap();
+        assert_eq!(script.written(), [&model[..], frame].concat());
+    }
+
+    assert!(matches!(
+        nexstar.set_guiderate(Device::AzmRaMotor, GuideRate(f32::NAN)),
+        Err(Error::InvalidSlewRate)
+    ));
+    assert!(script.written().is_empty());
+}
+
+/// Creates a driver connected to a mount of the model `id`.
+fn connected(id: u8) -> (NexStar<Rx, Tx>, Script) {
+    let (mut nexstar, script) = nexstar(&[4, 21, b'#', 0x42, b'#', id, b'#']);
+    script.respond(&[7, 11, b'#', 7, 11, b'#']);
+    nexstar.connect().unwrap();
+    script.written();
+    (nexstar, script)
+}
+
+#[test]
+fn rtc_time() {
+    // CGE
+    let (mut nexstar, script) = connected(0x05);
+    script.respond(&[10, 14, b'#', 0x07, 0xEA, b'#', 22, 30, 15, b'#']);
+
+    let utc = nexstar.rtc_time().unwrap();
+    assert_eq!((utc.year, utc.month, utc.day), (2026, 10, 14));
+    assert_eq!((utc.hour, utc.minutes, utc.seconds), (22, 30, 15));
+    let written = script.written();
+    let commands: Vec<_> = written
+        .chunks(8)
+        .map(|frame| (frame[2], frame[3]))
+        .collect();
+    assert_eq!(commands, [(0xb2, 0x03), (0xb2, 0x04), (0xb2, 0x33)]);
+}
+
+#[test]
+fn rtc_time_refused_without_rtc() {
+    // SLT
+    let (mut nexstar, script) = connected(0x07);
+
+    let err = nexstar.rtc_time().unwrap_err();
+    assert!(matches!(err, Error::UnsupportedByModel(Model::SLT)));
+    assert_eq!(err.to_string(), "not supported by the SLT");
+    assert!(script.written().is_empty());
+}
+
+#[test]
+fn rtc_time_unknown_model() {
+    let (mut nexstar, script) = connected(0x42);
+    script.respond(&[10, 14, b'#', 0x07, 0xEA, b'#', 22, 30, 15, b'#']);
+
+    assert!(nexstar.rtc_time().is_ok());
+}
+
+#[test]
+fn rtc_time_capability_checks_disabled() {
+    // SLT
+    let (mut nexstar, script) = connected(0x07);
+    nexstar.set_capability_checks(false);
+    script.respond(&[10, 14, b'#', 0x07, 0xEA, b'#', 22, 30, 15, b'#']);
+
+    assert!(nexstar.rtc_time().is_ok());
+    assert_eq!(script.written().len(), 24);
+}
+
+#[test]
+fn gps_fix_lost_while_reading() {
+    // linked, then zeros for the position
+    let (mut nexstar, script) = nexstar(&[1, b'#', 0, 0, 0, b'#', 0, 0, 0, b'#']);
+
+    assert!(matches!(nexstar.gps_fix(), Err(Error::NoGpsFix)));
+    assert_eq!(script.written().len(), 24);
+
+    // a position, then zeros for the date
+    script.respond(&[1, b'#', 0x10, 0, 0, b'#', 0, 0, 0, b'#', 0, 0, b'#']);
+    assert!(matches!(nexstar.gps_fix(), Err(Error::NoGpsFix)));
+}
+
+#[test]
+fn gps_state_silent_unit() {
+    // no timeout set, the query waits on its own
+    let (mut nexstar, script) = nexstar(&[]);
+    let mut delay = FakeDelay::default();
+
+    assert_eq!(nexstar.gps_state(&mut delay).unwrap(), GpsState::Absent);
+    assert_eq!(delay.total(), 500);
+    assert_eq!(script.written(), [b'P', 1, 0xB0, 0x37, 0, 0, 0, 1]);
+    assert_eq!(nexstar.link_state(), LinkState::Clean);
+
+    // the next command isn't charged for a resync
+    script.respond(b"#");
+    nexstar.cancel_goto().unwrap();
+    assert_eq!(script.written(), b"M");
+}
+
+#[test]
+fn gps_state_answers() {
+    let (mut nexstar, script) = nexstar(&[1, b'#']);
+    let mut delay = FakeDelay::default();
+
+    assert_eq!(nexstar.gps_state(&mut delay).unwrap(), GpsState::Linked);
+    script.respond(&[0, b'#']);
+    assert_eq!(nexstar.gps_state(&mut delay).unwrap(), GpsState::NoFix);
+    // the HC nacks the query of an absent unit
+    script.respond(&[0, 0x00, b'#']);
+    assert_eq!(nexstar.gps_state(&mut delay).unwrap(), GpsState::Absent);
+    assert_eq!(delay.total(), 0);
+    assert_eq!(nexstar.link_state(), LinkState::Clean);
+
+    // an answer cut short
+    script.respond(&[1]);
+    assert!(matches!(
+        nexstar.gps_state(&mut delay),
+        Err(Error::Truncated {
+            expected: 1,
+            got: 0
+        })
+    ));
+    assert_eq!(nexstar.link_state(), LinkState::Dirty);
+}
+
+#[test]
+fn wait_for_gps_fix_error() {
+    // the second link query is answered with an error code
+    let (mut nexstar, script) = nexstar(&[0, b'#', 0, 0x00, b'#']);
+    let mut delay = FakeDelay::default();
+
+    assert!(matches!(
+        nexstar.wait_for_gps_fix(&mut delay, 1000, 60_000),
+        Err(Error::UnexpectedResponse(Context::PassThrough {
+            device: Device::GPSUnit,
+            command: 0x37,
+        }))
+    ));
+    assert_eq!(delay.delays, [1000]);
+    assert_eq!(script.written().len(), 16);
+}
+
+#[test]
+fn wait_for_alignment_error() {
+    let (mut nexstar, _) = nexstar(&[0, b'#', 0, 0x00, b'#']);
+    let mut delay = FakeDelay::default();
+
+    assert!(matches!(
+        nexstar.wait_for_alignment(&mut delay, 100, 1000),
+        Err(Error::UnexpectedResponse(_))
+    ));
+    assert_eq!(delay.delays, [100]);
+}
+
+#[test]
+fn wait_for_alignment_aborted() {
+    let (mut nexstar, _) = nexstar(&[0, b'#']);
+    let mut delay = FakeDelay::default();
+    nexstar.set_abort_hook(Some(|| true));
+
+    assert!(matches!(
+        nexstar.wait_for_alignment(&mut delay, 100, 1000),
+        Err(Error::Aborted)
+    ));
+}
+
+/// Generic code written against the prelude alone
+mod generic {
+    use nexstar::prelude::*;
+
+    pub fn describe<T, U, D>(
+        nexstar: &mut NexStar<T, U>,
+        delay: &mut D,
+    ) -> Result<String, Error<T::Error, U::Error>>
+    where
+        T: SerialRead<u8>,
+        U: SerialWrite<u8>,
+        D: DelayMs<u32>,
+    {
+        let model = nexstar.model()?;
+        let version = nexstar.version()?;
+        nexstar.wait_for_alignment(delay, 100, 1000)?;
+        let aligned = nexstar.is_alignment_complete()?;
+        Ok(format!(
+            "{:?} {}.{} aligned: {}",
+            model, version.major, version.minor, aligned
+        ))
+    }
+}
+
+#[test]
+fn prelude_is_sufficient() {
+    let (mut nexstar, script) = nexstar(&[0x0C, b'#', 4, 21, b'#', 0, b'#', 1, b'#', 1, b'#']);
+    let mut delay = FakeDelay::default();
+
+    let summary = generic::describe(&mut nexstar, &mut delay).unwrap();
+    assert_eq!(summary, "Se6_8 4.21 aligned: true");
+    assert_eq!(script.written(), b"mVJJJ");
+}
@@ -0,0 +1,105 @@
+#![cfg(feature = "nmea")]
+
+mod common;
+
+use common::assert_close;
+use nexstar::nmea::{parse, NmeaError, Sentence};
+use nexstar::{DateTime, UtcDateTime};
+
+const SENTENCES: &str = include_str!("fixtures/nmea.txt");
+
+/// Gets the `index`th sentence of the fixture.
+fn sentence(index: usize) -> &'static [u8] {
+    SENTENCES
+        .lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .nth(index)
+        .unwrap()
+        .as_bytes()
+}
+
+#[test]
+fn gga_north_east() {
+    let location = match parse(sentence(0)) {
+        Ok(Sentence::Gga { location }) => location,
+        other => panic!("unexpected result {:?}", other),
+    };
+    assert_close(location.latitude, 48.117_3, 1e-4);
+    assert_close(location.longitude, 11.516_667, 1e-4);
+}
+
+#[test]
+fn rmc_south_east() {
+    let (location, utc) = match parse(sentence(1)) {
+        Ok(Sentence::Rmc { location, utc }) => (location, utc),
+        other => panic!("unexpected result {:?}", other),
+    };
+    assert_close(location.latitude, -33.86, 1e-4);
+    assert_close(location.longitude, 151.211_12, 1e-4);
+    assert_eq!(
+        utc,
+        UtcDateTime {
+            year: 2026,
+            month: 3,
+            day: 14,
+            hour: 8,
+            minutes: 18,
+            seconds: 36,
+        }
+    );
+}
+
+#[test]
+fn gga_north_west() {
+    let location = parse(sentence(2)).unwrap().location();
+    assert_close(location.latitude, 40.712_8, 1e-4);
+    assert_close(location.longitude, -74.006, 1e-4);
+}
+
+#[test]
+fn rmc_south_west() {
+    let (location, utc) = match parse(sentence(3)) {
+        Ok(Sentence::Rmc { location, utc }) => (location, utc),
+        other => panic!("unexpected result {:?}", other),
+    };
+    assert_close(location.latitude, -33.445, 1e-4);
+    assert_close(location.longitude, -70.65, 1e-4);
+
+    // Santiago is UTC-4 with daylight saving time in December
+    let local = DateTime::from_utc(utc, -4, true).unwrap();
+    assert_eq!(
+        (local.year, local.month, local.day, local.hour),
+        (26, 12, 31, 20)
+    );
+    assert_eq!((local.minutes, local.seconds), (59, 59));
+}
+
+#[test]
+fn no_fix() {
+    assert_eq!(parse(sentence(4)).unwrap_err(), NmeaError::NoFix);
+    assert_eq!(parse(sentence(5)).unwrap_err(), NmeaError::NoFix);
+}
+
+#[test]
+fn bad_checksum() {
+    assert_eq!(parse(sentence(6)).unwrap_err(), NmeaError::Checksum);
+    // missing checksum
+    let sentence = sentence(0);
+    assert_eq!(
+        parse(&sentence[..sentence.len() - 3]).unwrap_err(),
+        NmeaError::Checksum
+    );
+}
+
+#[test]
+fn unsupported() {
+    assert_eq!(parse(sentence(7)).unwrap_err(), NmeaError::Unsupported);
+    assert_eq!(parse(b"GPGGA,*47").unwrap_err(), NmeaError::Invalid);
+}
+
+#[test]
+fn line_break() {
+    let mut line = sentence(0).to_vec();
+    line.extend(b"\r\n");
+    assert!(parse(&line).is_ok());
+}
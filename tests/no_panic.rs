@@ -0,0 +1,125 @@
+//! Worst-case inputs to the public methods, which must fail with an error
+//! instead of panicking.
+
+mod common;
+
+use common::nexstar;
+#[cfg(feature = "float")]
+use nexstar::{AzmAlt, Location, RaDec};
+use nexstar::{AzmAltArcsec, DateTime, Device, Dms, Hemisphere, RaDecArcsec};
+use proptest::prelude::*;
+
+/// Bytes following the arbitrary response so no read runs past the script
+const PADDING: [u8; 20] = [b'#'; 20];
+
+fn response() -> impl Strategy<Value = Vec<u8>> {
+    prop::collection::vec(any::<u8>(), 0..32).prop_map(|mut bytes| {
+        bytes.extend_from_slice(&PADDING);
+        bytes
+    })
+}
+
+fn hemisphere() -> impl Strategy<Value = Hemisphere> {
+    prop::sample::select(vec![
+        Hemisphere::North,
+        Hemisphere::South,
+        Hemisphere::East,
+        Hemisphere::West,
+    ])
+}
+
+fn dms() -> impl Strategy<Value = Dms> {
+    (any::<u8>(), any::<u8>(), any::<u8>(), hemisphere()).prop_map(
+        |(degrees, minutes, seconds, hemisphere)| Dms {
+            degrees,
+            minutes,
+            seconds,
+            hemisphere,
+        },
+    )
+}
+
+/// Any `f32` including NaN, the infinities and subnormals
+#[cfg(feature = "float")]
+fn any_f32() -> impl Strategy<Value = f32> {
+    prop_oneof![
+        prop::sample::select(vec![
+            f32::NAN,
+            f32::INFINITY,
+            f32::NEG_INFINITY,
+            f32::MAX,
+            f32::MIN,
+            -0.0,
+        ]),
+        any::<u32>().prop_map(f32::from_bits),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn responses_dont_panic(response in response()) {
+        let (mut driver, _) = nexstar(&response);
+        let _ = driver.ra_dec_arcsec();
+        let (mut driver, _) = nexstar(&response);
+        let _ = driver.precise_azm_alt_arcsec();
+        let (mut driver, _) = nexstar(&response);
+        let _ = driver.location_dms();
+        let (mut driver, _) = nexstar(&response);
+        let _ = driver.datetime();
+        let (mut driver, _) = nexstar(&response);
+        let _ = driver.device_version(Device::AzmRaMotor);
+        let (mut driver, _) = nexstar(&response);
+        let _ = driver.status();
+    }
+
+    #[cfg(feature = "float")]
+    #[test]
+    fn float_responses_dont_panic(response in response()) {
+        let (mut driver, _) = nexstar(&response);
+        let _ = driver.precise_ra_dec();
+        let (mut driver, _) = nexstar(&response);
+        let _ = driver.azm_alt();
+        let (mut driver, _) = nexstar(&response);
+        let _ = driver.location();
+    }
+
+    #[test]
+    fn arcsec_commands_dont_panic(first in any::<i32>(), second in any::<i32>()) {
+        let (mut driver, _) = nexstar(&PADDING);
+        let _ = driver.goto_ra_dec_arcsec(RaDecArcsec { ra: first, dec: second });
+        let _ = driver.precise_goto_azm_alt_arcsec(AzmAltArcsec { azm: first, alt: second });
+        let _ = driver.precise_sync_ra_dec_arcsec(RaDecArcsec { ra: first, dec: second });
+        let _ = driver.track_rates(first, second);
+    }
+
+    #[cfg(feature = "float")]
+    #[test]
+    fn float_commands_dont_panic(first in any_f32(), second in any_f32()) {
+        let (mut driver, _) = nexstar(&PADDING);
+        let _ = driver.goto_ra_dec(RaDec { ra: first, dec: second });
+        let _ = driver.precise_goto_azm_alt(AzmAlt { azm: first, alt: second });
+        let _ = driver.set_location(Location { latitude: first, longitude: second });
+    }
+
+    #[test]
+    fn location_dms_doesnt_panic(latitude in dms(), longitude in dms()) {
+        let (mut driver, _) = nexstar(&PADDING);
+        let _ = driver.set_location_dms(latitude, longitude);
+    }
+
+    #[test]
+    fn datetime_doesnt_panic(bytes in any::<[u8; 8]>()) {
+        let [hour, minutes, seconds, month, day, year, zone, dst] = bytes;
+        let (mut driver, _) = nexstar(&PADDING);
+        let _ = driver.set_datetime(DateTime {
+            hour,
+            minutes,
+            seconds,
+            zone: zone as i8,
+            daylight_saving: dst & 1 == 1,
+            year,
+            month,
+            day,
+        });
+    }
+}
@@ -0,0 +1,696 @@
+#![cfg(feature = "float")]
+
+mod common;
+
+use common::{assert_close, manual_clock, nexstar, set_now, FakeDelay, Script};
+use embedded_hal::blocking::delay::DelayMs;
+use nexstar::{
+    Approach, AzmAlt, AzmAltArcsec, Context, Error, LinkState, RaDec, Target, TrackingMode,
+};
+
+/// Decodes the two hex coordinates of a written position frame into degrees.
+fn decode_frame(frame: &[u8]) -> (char, f32, f32) {
+    let text = std::str::from_utf8(&frame[1..]).unwrap();
+    let (first, second) = text.split_once(',').unwrap();
+    let scale = 16f64.powi(first.len() as i32);
+    let degrees = |hex: &str| (u32::from_str_radix(hex, 16).unwrap() as f64 / scale * 360.0) as f32;
+
+    (frame[0] as char, degrees(first), degrees(second))
+}
+
+#[test]
+fn ra_dec() {
+    let (mut nexstar, script) = nexstar(b"4000,F000#");
+
+    let position = nexstar.ra_dec().unwrap();
+    assert_close(position.ra, 90.0, 0.001);
+    assert_close(position.dec, -22.5, 0.001);
+    assert_eq!(script.written(), b"E");
+}
+
+#[test]
+fn ra_dec_invalid() {
+    let (mut nexstar, _) = nexstar(b"40X0,F000#");
+
+    assert!(matches!(
+        nexstar.ra_dec(),
+        Err(Error::UnexpectedResponse(Context::Hc(b'E')))
+    ));
+}
+
+#[test]
+fn ra_dec_nack() {
+    let (mut nexstar, _) = nexstar(b"4000,F000\x00#");
+
+    assert!(nexstar.ra_dec().is_err());
+}
+
+#[test]
+fn precise_ra_dec() {
+    let (mut nexstar, script) = nexstar(b"80000000,C0000000#");
+
+    let position = nexstar.precise_ra_dec().unwrap();
+    assert_close(position.ra, 180.0, 0.0001);
+    assert_close(position.dec, -90.0, 0.0001);
+    assert_eq!(script.written(), b"e");
+}
+
+#[test]
+fn precise_ra_dec_nack() {
+    let (mut nexstar, _) = nexstar(b"80000000,C0000000\x00#");
+
+    assert!(nexstar.precise_ra_dec().is_err());
+}
+
+#[test]
+fn azm_alt() {
+    let (mut nexstar, script) = nexstar(b"2000,1000#");
+
+    let position = nexstar.azm_alt().unwrap();
+    assert_close(position.azm, 45.0, 0.001);
+    assert_close(position.alt, 22.5, 0.001);
+    assert_eq!(script.written(), b"Z");
+}
+
+#[test]
+fn azm_alt_nack() {
+    let (mut nexstar, _) = nexstar(b"2000,1000\x00#");
+
+    assert!(nexstar.azm_alt().is_err());
+}
+
+#[test]
+fn precise_azm_alt() {
+    let (mut nexstar, script) = nexstar(b"20000000,F0000000#");
+
+    let position = nexstar.precise_azm_alt().unwrap();
+    assert_close(position.azm, 45.0, 0.0001);
+    assert_close(position.alt, -22.5, 0.0001);
+    assert_eq!(script.written(), b"z");
+}
+
+#[test]
+fn precise_azm_alt_nack() {
+    let (mut nexstar, _) = nexstar(b"20000000,F0000000\x00#");
+
+    assert!(nexstar.precise_azm_alt().is_err());
+}
+
+#[test]
+fn goto_ra_dec() {
+    let (mut nexstar, script) = nexstar(b"#");
+
+    nexstar
+        .goto_ra_dec(RaDec {
+            ra: 90.0,
+            dec: -22.5,
+        })
+        .unwrap();
+    assert_eq!(script.written(), b"R4000,F000");
+}
+
+#[test]
+fn goto_ra_dec_nack() {
+    let (mut nexstar, _) = nexstar(b"\x00#");
+
+    assert!(matches!(
+        nexstar.goto_ra_dec(RaDec { ra: 0.0, dec: 0.0 }),
+        Err(Error::UnexpectedResponse(Context::Hc(b'R')))
+    ));
+}
+
+#[test]
+fn precise_goto_ra_dec() {
+    let (mut nexstar, script) = nexstar(b"#");
+
+    nexstar
+        .precise_goto_ra_dec(RaDec {
+            ra: 180.0,
+            dec: -90.0,
+        })
+        .unwrap();
+    assert_eq!(script.written(), b"r80000000,C0000000");
+}
+
+#[test]
+fn precise_goto_ra_dec_nack() {
+    let (mut nexstar, _) = nexstar(b"\x00#");
+
+    assert!(nexstar
+        .precise_goto_ra_dec(RaDec { ra: 0.0, dec: 0.0 })
+        .is_err());
+}
+
+#[test]
+fn goto_azm_alt() {
+    let (mut nexstar, script) = nexstar(b"#");
+
+    nexstar
+        .goto_azm_alt(AzmAlt {
+            azm: 360.0,
+            alt: 45.0,
+        })
+        .unwrap();
+    assert_eq!(script.written(), b"B0000,2000");
+}
+
+#[test]
+fn goto_azm_alt_nack() {
+    let (mut nexstar, _) = nexstar(b"\x00#");
+
+    assert!(nexstar.goto_azm_alt(AzmAlt { azm: 0.0, alt: 0.0 }).is_err());
+}
+
+#[test]
+fn precise_goto_azm_alt() {
+    let (mut nexstar, script) = nexstar(b"#");
+
+    nexstar
+        .precise_goto_azm_alt(AzmAlt {
+            azm: -90.0,
+            alt: 22.5,
+        })
+        .unwrap();
+    assert_eq!(script.written(), b"bC0000000,10000000");
+}
+
+#[test]
+fn precise_goto_azm_alt_nack() {
+    let (mut nexstar, _) = nexstar(b"\x00#");
+
+    assert!(nexstar
+        .precise_goto_azm_alt(AzmAlt { azm: 0.0, alt: 0.0 })
+        .is_err());
+}
+
+#[test]
+fn goto_target() {
+    let (mut nexstar, script) = nexstar(b"##");
+
+    nexstar
+        .goto(Target::RaDec(RaDec { ra: 90.0, dec: 0.0 }))
+        .unwrap();
+    nexstar
+        .goto(Target::AzmAlt(AzmAlt {
+            azm: 90.0,
+            alt: 0.0,
+        }))
+        .unwrap();
+    assert_eq!(script.written(), b"r40000000,00000000b40000000,00000000");
+}
+
+#[test]
+fn sync_ra_dec() {
+    let (mut nexstar, script) = nexstar(b"#");
+
+    nexstar
+        .sync_ra_dec(RaDec {
+            ra: 90.0,
+            dec: 45.0,
+        })
+        .unwrap();
+    assert_eq!(script.written(), b"S4000,2000");
+}
+
+#[test]
+fn sync_ra_dec_nack() {
+    let (mut nexstar, _) = nexstar(b"\x00#");
+
+    assert!(nexstar.sync_ra_dec(RaDec { ra: 0.0, dec: 0.0 }).is_err());
+}
+
+#[test]
+fn precise_sync_ra_dec() {
+    let (mut nexstar, script) = nexstar(b"#");
+
+    nexstar
+        .precise_sync_ra_dec(RaDec {
+            ra: 90.0,
+            dec: 45.0,
+        })
+        .unwrap();
+    assert_eq!(script.written(), b"s40000000,20000000");
+}
+
+#[test]
+fn precise_sync_ra_dec_nack() {
+    let (mut nexstar, _) = nexstar(b"\x00#");
+
+    assert!(nexstar
+        .precise_sync_ra_dec(RaDec { ra: 0.0, dec: 0.0 })
+        .is_err());
+}
+
+#[test]
+fn cancel_goto() {
+    let (mut nexstar, script) = nexstar(b"#");
+
+    nexstar.cancel_goto().unwrap();
+    assert_eq!(script.written(), b"M");
+}
+
+#[test]
+fn cancel_goto_nack() {
+    let (mut nexstar, _) = nexstar(b"\x00#");
+
+    assert!(nexstar.cancel_goto().is_err());
+}
+
+#[test]
+fn wait_for_goto() {
+    let (mut nexstar, script) = nexstar(b"1#1#0#");
+    let mut delay = FakeDelay::default();
+
+    nexstar.wait_for_goto(&mut delay, 500, 10_000).unwrap();
+    assert_eq!(delay.delays, [500, 500]);
+    assert_eq!(script.written(), b"LLL");
+}
+
+#[test]
+fn wait_for_goto_aborted_cancels() {
+    let (mut nexstar, script) = nexstar(b"1##");
+    let mut delay = FakeDelay::default();
+    nexstar.set_abort_hook(Some(|| true));
+
+    assert!(matches!(
+        nexstar.wait_for_goto(&mut delay, 500, 10_000),
+        Err(Error::Aborted)
+    ));
+    assert_eq!(script.written(), b"LM");
+}
+
+#[test]
+fn park() {
+    let (mut nexstar, script) = nexstar(b"##1#0#8000,0010#");
+    let mut delay = FakeDelay::default();
+
+    nexstar
+        .park(
+            AzmAlt {
+                azm: 180.0,
+                alt: 0.0,
+            },
+            &mut delay,
+        )
+        .unwrap();
+    assert_eq!(script.written(), b"T\x00B8000,0000LLZ");
+}
+
+#[test]
+fn park_not_on_target() {
+    let (mut nexstar, _) = nexstar(b"##0#4000,0000#");
+    let mut delay = FakeDelay::default();
+
+    assert!(matches!(
+        nexstar.park(
+            AzmAlt {
+                azm: 180.0,
+                alt: 0.0
+            },
+            &mut delay
+        ),
+        Err(Error::NotOnTarget)
+    ));
+}
+
+#[test]
+fn park_refused() {
+    let (mut nexstar, script) = nexstar(b"#\x00#");
+    let mut delay = FakeDelay::default();
+
+    assert!(matches!(
+        nexstar.park(
+            AzmAlt {
+                azm: 0.0,
+                alt: -30.0
+            },
+            &mut delay
+        ),
+        Err(Error::UnexpectedResponse(Context::Hc(b'B')))
+    ));
+    assert_eq!(script.written(), b"T\x00B0000,EAAB");
+}
+
+#[test]
+fn goto_floor() {
+    let (mut nexstar, script) = nexstar(b"#");
+    nexstar.set_goto_floor(Some(0.0));
+
+    let below = AzmAlt {
+        azm: 90.0,
+        alt: -5.0,
+    };
+    assert!(matches!(
+        nexstar.goto_azm_alt(below),
+        Err(Error::BelowAltitudeLimit { limit, .. }) if limit == 0.0
+    ));
+    assert!(matches!(
+        nexstar.goto_azm_alt_arcsec(AzmAltArcsec {
+            azm: 0,
+            alt: -18_000
+        }),
+        Err(Error::BelowAltitudeLimit { .. })
+    ));
+    assert!(script.written().is_empty());
+
+    nexstar
+        .goto_azm_alt(AzmAlt {
+            azm: 90.0,
+            alt: 5.0,
+        })
+        .unwrap();
+    assert_eq!(script.written(), b"B4000,038E");
+}
+
+#[test]
+fn goto_floor_cleared() {
+    let (mut nexstar, script) = nexstar(b"#");
+    nexstar.set_goto_floor(Some(0.0));
+    nexstar.set_goto_floor(None);
+
+    nexstar
+        .goto_azm_alt(AzmAlt {
+            azm: 90.0,
+            alt: -5.0,
+        })
+        .unwrap();
+    assert_eq!(script.written(), b"B4000,FC72");
+}
+
+#[test]
+fn park_below_floor() {
+    let (mut nexstar, script) = nexstar(b"");
+    let mut delay = FakeDelay::default();
+    nexstar.set_goto_floor(Some(-10.0));
+
+    assert!(matches!(
+        nexstar.park(
+            AzmAlt {
+                azm: 0.0,
+                alt: -30.0
+            },
+            &mut delay
+        ),
+        Err(Error::BelowAltitudeLimit { .. })
+    ));
+    // tracking stays on
+    assert!(script.written().is_empty());
+}
+
+#[cfg(feature = "astro")]
+#[test]
+fn goto_ra_dec_checked() {
+    // the local sidereal time is about 100°, as below
+    let (mut nexstar, script) =
+        nexstar(b"\0\0\0\0\0\0\0\0#\0\0\0\x01\x01\0\0\0#\0\0\0\0\0\0\0\0#\0\0\0\x01\x01\0\0\0##");
+
+    assert!(matches!(
+        nexstar.goto_ra_dec_checked(RaDec { ra: 150.0, dec: 0.0 }, 45.0),
+        Err(Error::BelowAltitudeLimit { altitude, limit }) if (39.0..41.0).contains(&altitude) && limit == 45.0
+    ));
+    assert_eq!(script.written(), b"wh");
+
+    nexstar
+        .goto_ra_dec_checked(
+            RaDec {
+                ra: 100.0,
+                dec: 0.0,
+            },
+            45.0,
+        )
+        .unwrap();
+    assert_eq!(&script.written()[..3], b"whR");
+}
+
+#[cfg(feature = "astro")]
+#[test]
+fn goto_floor_ra_dec() {
+    // on the equator at 0° longitude at 2000-01-01 00:00 UTC, the local
+    // sidereal time is about 100°
+    let (mut nexstar, script) = nexstar(b"\0\0\0\0\0\0\0\0#\0\0\0\x01\x01\0\0\0#");
+    nexstar.set_goto_floor(Some(0.0));
+
+    assert!(matches!(
+        nexstar.goto_ra_dec(RaDec { ra: 280.0, dec: 0.0 }),
+        Err(Error::BelowAltitudeLimit { altitude, .. }) if altitude < -80.0
+    ));
+    assert_eq!(script.written(), b"wh");
+}
+
+#[cfg(feature = "astro")]
+#[test]
+fn goto_floor_ra_dec_refracted() {
+    // 0.2° below the horizon, at the same place and time as above
+    let target = RaDec { ra: 9.77, dec: 0.0 };
+    let (mut geometric, _) = nexstar(b"\0\0\0\0\0\0\0\0#\0\0\0\x01\x01\0\0\0#");
+    geometric.set_goto_floor(Some(0.0));
+
+    assert!(matches!(
+        geometric.goto_ra_dec(target),
+        Err(Error::BelowAltitudeLimit { altitude, .. }) if (-0.25..-0.15).contains(&altitude)
+    ));
+
+    // but refraction lifts it by about half a degree
+    let (mut nexstar, script) = nexstar(b"\0\0\0\0\0\0\0\0#\0\0\0\x01\x01\0\0\0##");
+    nexstar.set_goto_floor(Some(0.0));
+    nexstar.set_refraction(Some((10.0, 1010.0)));
+
+    nexstar.goto_ra_dec(target).unwrap();
+    assert_eq!(&script.written()[..3], b"whR");
+}
+
+#[test]
+fn unpark() {
+    let (mut nexstar, script) = nexstar(b"#");
+
+    nexstar.unpark(TrackingMode::EQNorth).unwrap();
+    assert_eq!(script.written(), b"T\x02");
+}
+
+#[test]
+fn goto_with_approach_across_ra_wrap() {
+    let (mut nexstar, script) = nexstar(b"#0##0#");
+    let mut delay = FakeDelay::default();
+
+    let target = RaDec {
+        ra: 359.9,
+        dec: 10.0,
+    };
+    nexstar
+        .goto_with_approach(Target::RaDec(target), 30.0, Approach::Negative, &mut delay)
+        .unwrap();
+
+    let written = script.written();
+    assert_eq!(written.len(), 2 * 18 + 2);
+    let (cmd, ra, dec) = decode_frame(&written[..18]);
+    assert_eq!(cmd, 'r');
+    assert_close(ra, 0.4, 0.001);
+    assert_close(dec, 10.5, 0.001);
+    assert_eq!(written[18], b'L');
+    let (cmd, ra, dec) = decode_frame(&written[19..37]);
+    assert_eq!(cmd, 'r');
+    assert_close(ra, 359.9, 0.001);
+    assert_close(dec, 10.0, 0.001);
+    assert_eq!(written[37], b'L');
+}
+
+#[test]
+fn goto_with_approach_across_azm_wrap() {
+    let (mut nexstar, script) = nexstar(b"#0##0#");
+    let mut delay = FakeDelay::default();
+
+    let target = AzmAlt {
+        azm: 0.2,
+        alt: 89.9,
+    };
+    nexstar
+        .goto_with_approach(Target::AzmAlt(target), 30.0, Approach::Positive, &mut delay)
+        .unwrap();
+
+    let written = script.written();
+    let (cmd, azm, alt) = decode_frame(&written[..18]);
+    assert_eq!(cmd, 'b');
+    assert_close(azm, 359.7, 0.001);
+    assert_close(alt, 89.4, 0.001);
+    let (_, azm, alt) = decode_frame(&written[19..37]);
+    assert_close(azm, 0.2, 0.001);
+    assert_close(alt, 89.9, 0.001);
+}
+
+#[test]
+fn goto_with_approach_clamps_at_pole() {
+    let (mut nexstar, script) = nexstar(b"#0##0#");
+    let mut delay = FakeDelay::default();
+
+    let target = RaDec {
+        ra: 10.0,
+        dec: 89.9,
+    };
+    nexstar
+        .goto_with_approach(Target::RaDec(target), 30.0, Approach::Negative, &mut delay)
+        .unwrap();
+
+    let (_, _, dec) = decode_frame(&script.written()[..18]);
+    assert_close(dec, 90.0, 0.001);
+}
+
+#[test]
+fn positions() {
+    let (mut nexstar, script) = nexstar(b"40000000,10000000#40010000,10000000#40020000,10000000#");
+    let mut delay = FakeDelay::default();
+
+    let samples: Vec<RaDec> = nexstar
+        .positions(&mut delay, 1000)
+        .take(3)
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(samples.len(), 3);
+    assert_close(samples[0].ra, 90.0, 0.0001);
+    assert!(samples[2].ra > samples[1].ra);
+    assert_close(samples[2].dec, 22.5, 0.0001);
+    assert_eq!(delay.delays, [1000, 1000]);
+    assert_eq!(script.written(), b"eee");
+}
+
+#[test]
+fn positions_between_commands() {
+    let (mut nexstar, script) = nexstar(b"20000000,10000000#1#20000000,10000000#");
+    let mut delay = FakeDelay::default();
+
+    let mut positions = nexstar.azm_alt_positions(&mut delay, 500);
+    let first = positions.next().unwrap().unwrap();
+    assert!(positions.nexstar().is_goto_in_progress().unwrap());
+    let second = positions.next().unwrap().unwrap();
+
+    assert_close(first.azm, 45.0, 0.0001);
+    assert_close(second.alt, 22.5, 0.0001);
+    assert_eq!(script.written(), b"zLz");
+}
+
+#[test]
+fn log_positions_reports_errors() {
+    // the first RA/Dec response is garbled and followed by junk
+    let (mut nexstar, script) = nexstar(b"4000zzzz,10000000#junk");
+    let mut delay = FakeDelay::default();
+
+    let mut log = Vec::new();
+    let logged = nexstar
+        .log_positions(&mut delay, 1000, 2, |ms, position| {
+            log.push((ms, position.map_err(|_| ())));
+            script.respond(b"40000000,10000000#20000000,10000000#");
+            true
+        })
+        .unwrap();
+
+    assert_eq!(logged, 2);
+    assert!(matches!(log[0], (0, Err(()))));
+    let (ms, position) = log[1];
+    let (ra_dec, azm_alt) = position.unwrap();
+    assert_eq!(ms, 1000);
+    assert_close(ra_dec.ra, 90.0, 0.0001);
+    assert_close(azm_alt.azm, 45.0, 0.0001);
+    // the junk was drained and the failed sample didn't read Azm/Alt
+    assert_eq!(script.written(), b"eez");
+    assert_eq!(nexstar.link_state(), LinkState::Clean);
+}
+
+#[test]
+fn log_positions_measured_time() {
+    let (mut nexstar, script) = nexstar(b"40000000,10000000#20000000,10000000#");
+    nexstar.set_timeout(manual_clock, 100);
+    set_now(5000);
+    let mut delay = FakeDelay::default();
+
+    // the reads and the sink take 300 ms on top of the interval
+    let mut timestamps = Vec::new();
+    nexstar
+        .log_positions(&mut delay, 1000, 3, |ms, position| {
+            position.unwrap();
+            timestamps.push(ms);
+            set_now(manual_clock() + 1300);
+            script.respond(b"40000000,10000000#20000000,10000000#");
+            true
+        })
+        .unwrap();
+
+    assert_eq!(timestamps, [0, 1300, 2600]);
+    assert_eq!(delay.delays, [1000, 1000]);
+}
+
+/// Delay staging the response of the next sample while waiting
+struct Staging {
+    script: Script,
+    responses: Vec<&'static [u8]>,
+}
+
+impl DelayMs<u32> for Staging {
+    fn delay_ms(&mut self, _: u32) {
+        self.script.respond(self.responses.remove(0));
+    }
+}
+
+#[test]
+fn measure_drift_skips_failed_reads() {
+    // 256 counts of 24 bits are 19.775 arcsec, the RA crosses 0h
+    let (mut nexstar, script) = nexstar(b"FFFF0000,00000000#");
+    let mut delay = Staging {
+        script: script.clone(),
+        responses: vec![b"zzzzzzzz,00000000#", b"00010000,FFFF0000#"],
+    };
+
+    let drift = nexstar.measure_drift(120_000, 60_000, &mut delay).unwrap();
+    assert_close(drift.ra, 19.775, 0.01);
+    assert_close(drift.dec, -9.888, 0.01);
+    assert_eq!((drift.samples, drift.skipped), (2, 1));
+    assert_eq!(script.written(), b"eee");
+
+    // without two good samples the last error is returned
+    let (mut nexstar, script) = common::nexstar(b"00000000,00000000#");
+    let mut delay = Staging {
+        script,
+        responses: vec![b"zzzzzzzz,00000000#"],
+    };
+    assert!(matches!(
+        nexstar.measure_drift(1000, 1000, &mut delay),
+        Err(Error::UnexpectedResponse(Context::Hc(b'e')))
+    ));
+}
+
+#[test]
+fn on_target_across_ra_wrap() {
+    let (mut nexstar, script) = nexstar(b"#");
+    assert!(nexstar.last_target().is_none());
+    assert!(!nexstar.on_target(3600.0).unwrap());
+
+    let target = RaDec {
+        ra: 359.9999,
+        dec: 45.0,
+    };
+    nexstar.precise_goto_ra_dec(target).unwrap();
+    assert!(matches!(
+        nexstar.last_target(),
+        Some(Target::RaDec(RaDec { ra, .. })) if ra == target.ra
+    ));
+
+    // just past 0h, 0.3" away
+    script.respond(b"00000100,20000000#");
+    assert!(nexstar.on_target(1.0).unwrap());
+    script.respond(b"00000100,20000000#");
+    assert!(!nexstar.on_target(0.1).unwrap());
+    assert!(script.written().ends_with(b"ee"));
+}
+
+#[test]
+fn on_target_frame_of_last_goto() {
+    let (mut nexstar, script) = nexstar(b"#");
+    nexstar
+        .goto_azm_alt(AzmAlt {
+            azm: 10.0,
+            alt: 20.0,
+        })
+        .unwrap();
+
+    script.respond(b"071C71C7,0E38E38E#");
+    assert!(nexstar.on_target(1.0).unwrap());
+    assert!(script.written().ends_with(b"z"));
+}
@@ -0,0 +1,230 @@
+//! Property tests of the numeric conversions against f64 reference
+//! implementations.
+#![cfg(feature = "float")]
+
+mod common;
+
+use common::nexstar;
+use nexstar::{DateTime, Location, RaDec};
+use proptest::prelude::*;
+
+/// One count of the 16 bit revolution fraction in degrees
+const COUNT_16: f64 = 360.0 / 65_536.0;
+/// One count of the 24 bit revolution fraction in degrees
+const COUNT_24: f64 = 360.0 / 16_777_216.0;
+/// Rounding error of an f32 angle up to 360 degrees
+const F32_EPSILON: f64 = 360.0 * f32::EPSILON as f64;
+
+/// Angles in -360..360 including the boundaries
+fn angle() -> impl Strategy<Value = f32> {
+    prop_oneof![
+        prop::sample::select(vec![0.0, -0.0, 90.0, -90.0, 180.0, -180.0, 359.999_99]),
+        -360.0f32..360.0,
+    ]
+}
+
+fn latitude() -> impl Strategy<Value = f32> {
+    prop_oneof![
+        prop::sample::select(vec![0.0, -0.0, 90.0, -90.0]),
+        -90.0f32..=90.0,
+    ]
+}
+
+fn longitude() -> impl Strategy<Value = f32> {
+    prop_oneof![
+        prop::sample::select(vec![0.0, -0.0, 180.0, -180.0, 179.999_9]),
+        -180.0f32..=180.0,
+    ]
+}
+
+/// Absolute difference of two angles in degrees (0..180)
+fn angle_between(a: f64, b: f64) -> f64 {
+    let diff = (a - b).rem_euclid(360.0);
+    diff.min(360.0 - diff)
+}
+
+/// Distance between two fractions of a revolution of `bits` bits
+fn count_distance(a: u32, b: u32, bits: u32) -> u32 {
+    let diff = a.wrapping_sub(b) & ((1 << bits) - 1);
+    diff.min((1 << bits) - diff)
+}
+
+fn reference_fraction(degrees: f32, bits: u32) -> u32 {
+    let fraction = (degrees as f64).rem_euclid(360.0) / 360.0 * (1u64 << bits) as f64;
+    fraction.round() as u32 & ((1 << bits) - 1)
+}
+
+fn reference_dms(degrees: f32) -> (u32, u8) {
+    let seconds = (degrees.abs() as f64 * 3600.0).round() as u32;
+    (seconds, degrees.is_sign_negative() as u8)
+}
+
+/// Gets the fractions written by a GOTO to the RA/Dec position.
+fn goto_fractions(position: RaDec, precise: bool) -> (u32, u32) {
+    let (mut driver, script) = nexstar(b"#");
+    if precise {
+        driver.precise_goto_ra_dec(position).unwrap();
+    } else {
+        driver.goto_ra_dec(position).unwrap();
+    }
+
+    let written = String::from_utf8(script.written()).unwrap();
+    let (ra, dec) = written[1..].split_once(',').unwrap();
+    (
+        u32::from_str_radix(ra, 16).unwrap(),
+        u32::from_str_radix(dec, 16).unwrap(),
+    )
+}
+
+/// Gets the DMS bytes written by setting the location.
+fn location_bytes(location: Location) -> Vec<u8> {
+    let (mut driver, script) = nexstar(b"#");
+    driver.set_location(location).unwrap();
+    script.written()[1..].to_vec()
+}
+
+fn dms_seconds(dms: &[u8]) -> u32 {
+    dms[0] as u32 * 3600 + dms[1] as u32 * 60 + dms[2] as u32
+}
+
+proptest! {
+    #[test]
+    fn fraction_16_matches_reference(ra in angle(), dec in angle()) {
+        let (ra_count, dec_count) = goto_fractions(RaDec { ra, dec }, false);
+
+        // f32 rounding may move a value right at a count boundary by one
+        prop_assert!(count_distance(ra_count, reference_fraction(ra, 16), 16) <= 1);
+        prop_assert!(count_distance(dec_count, reference_fraction(dec, 16), 16) <= 1);
+    }
+
+    #[test]
+    fn fraction_24_matches_reference(ra in angle(), dec in angle()) {
+        let (ra_count, dec_count) = goto_fractions(RaDec { ra, dec }, true);
+
+        // only the upper 24 bits are used
+        prop_assert_eq!(ra_count & 0xFF, 0);
+        prop_assert_eq!(dec_count & 0xFF, 0);
+        prop_assert!(count_distance(ra_count >> 8, reference_fraction(ra, 24), 24) <= 1);
+        prop_assert!(count_distance(dec_count >> 8, reference_fraction(dec, 24), 24) <= 1);
+    }
+
+    #[test]
+    fn fraction_16_is_monotonic(a in 0.0f32..359.99, b in 0.0f32..359.99) {
+        let (low, high) = if a <= b { (a, b) } else { (b, a) };
+        let (low, _) = goto_fractions(RaDec { ra: low, dec: 0.0 }, false);
+        let (high, _) = goto_fractions(RaDec { ra: high, dec: 0.0 }, false);
+
+        prop_assert!(low <= high);
+    }
+
+    #[test]
+    fn fraction_24_is_monotonic(a in 0.0f32..359.99, b in 0.0f32..359.99) {
+        let (low, high) = if a <= b { (a, b) } else { (b, a) };
+        let (low, _) = goto_fractions(RaDec { ra: low, dec: 0.0 }, true);
+        let (high, _) = goto_fractions(RaDec { ra: high, dec: 0.0 }, true);
+
+        prop_assert!(low <= high);
+    }
+
+    #[test]
+    fn position_16_roundtrip(ra in angle(), dec in -90.0f32..=90.0) {
+        let (ra_count, dec_count) = goto_fractions(RaDec { ra, dec }, false);
+        let response = format!("{:04X},{:04X}#", ra_count, dec_count);
+        let (mut reader, _) = nexstar(response.as_bytes());
+        let position = reader.ra_dec().unwrap();
+
+        prop_assert!((0.0..360.0).contains(&position.ra));
+        prop_assert!(angle_between(position.ra as f64, ra as f64) <= COUNT_16 + F32_EPSILON);
+        prop_assert!((position.dec as f64 - dec as f64).abs() <= COUNT_16 + F32_EPSILON);
+    }
+
+    #[test]
+    fn position_24_roundtrip(ra in angle(), dec in -90.0f32..=90.0) {
+        let (ra_count, dec_count) = goto_fractions(RaDec { ra, dec }, true);
+        let response = format!("{:08X},{:08X}#", ra_count, dec_count);
+        let (mut reader, _) = nexstar(response.as_bytes());
+        let position = reader.precise_ra_dec().unwrap();
+
+        prop_assert!((0.0..360.0).contains(&position.ra));
+        prop_assert!(angle_between(position.ra as f64, ra as f64) <= COUNT_24 + F32_EPSILON);
+        prop_assert!((position.dec as f64 - dec as f64).abs() <= COUNT_24 + F32_EPSILON);
+    }
+
+    #[test]
+    fn signed_decode_range(ra in 0u32..65_536, dec in 0u32..65_536) {
+        let response = format!("{:04X},{:04X}#", ra, dec);
+        let (mut reader, _) = nexstar(response.as_bytes());
+        let position = reader.ra_dec().unwrap();
+
+        prop_assert!((0.0..360.0).contains(&position.ra));
+        prop_assert!((-180.0..=180.0).contains(&position.dec));
+        let reference = dec as f64 * COUNT_16;
+        let reference = if reference > 180.0 { reference - 360.0 } else { reference };
+        prop_assert!((position.dec as f64 - reference).abs() <= F32_EPSILON);
+    }
+
+    #[test]
+    fn dms_matches_reference(latitude in latitude(), longitude in longitude()) {
+        let bytes = location_bytes(Location { latitude, longitude });
+
+        for (dms, degrees) in [(&bytes[..4], latitude), (&bytes[4..], longitude)] {
+            prop_assert!(dms[1] < 60 && dms[2] < 60, "{:?}", dms);
+            let (seconds, sign) = reference_dms(degrees);
+            prop_assert!((dms_seconds(dms) as i64 - seconds as i64).abs() <= 1);
+            if seconds > 0 {
+                prop_assert_eq!(dms[3], sign);
+            }
+        }
+    }
+
+    #[test]
+    fn dms_is_monotonic(a in 0.0f32..=90.0, b in 0.0f32..=90.0) {
+        let (low, high) = if a <= b { (a, b) } else { (b, a) };
+        let low = location_bytes(Location { latitude: low, longitude: 0.0 });
+        let high = location_bytes(Location { latitude: high, longitude: 0.0 });
+
+        prop_assert!(dms_seconds(&low) <= dms_seconds(&high));
+    }
+
+    #[test]
+    fn location_roundtrip(latitude in latitude(), longitude in longitude()) {
+        let mut response = location_bytes(Location { latitude, longitude });
+        response.push(b'#');
+        let (mut reader, _) = nexstar(&response);
+        let location = reader.location().unwrap();
+
+        // half a second plus the f32 rounding
+        let tolerance = 0.5 / 3600.0 + F32_EPSILON;
+        prop_assert!((location.latitude as f64 - latitude as f64).abs() <= tolerance);
+        prop_assert!((location.longitude as f64 - longitude as f64).abs() <= tolerance);
+    }
+
+    #[test]
+    fn datetime_roundtrip(zone in any::<i8>(), year in 2000u32..2100, dst in any::<bool>()) {
+        let datetime = DateTime {
+            hour: 23,
+            minutes: 59,
+            seconds: 59,
+            zone,
+            daylight_saving: dst,
+            year: (year - 2000) as u8,
+            month: 12,
+            day: 31,
+        };
+        let (mut driver, script) = nexstar(b"#");
+        driver.set_datetime(datetime).unwrap();
+        let written = script.written();
+
+        // negative zones are sent as 256 + zone
+        prop_assert_eq!(written[7] as i32, (zone as i32).rem_euclid(256));
+        prop_assert_eq!(written[6] as u32 + 2000, year);
+
+        let mut response = written[1..].to_vec();
+        response.push(b'#');
+        let (mut reader, _) = nexstar(&response);
+        let read = reader.datetime().unwrap();
+        prop_assert_eq!(read.zone, zone);
+        prop_assert_eq!(read.year as u32 + 2000, year);
+        prop_assert_eq!(read.daylight_saving, dst);
+    }
+}
@@ -0,0 +1,164 @@
+//! Property tests of the sans-IO protocol functions, the set commands encode
+//! the same bytes the get commands parse.
+
+use nexstar::protocol::{
+    encode_command, parse_response, Axes, Counts, ParseError, Parser, Progress, Request, Response,
+    MAX_COMMAND, MAX_RESPONSE,
+};
+use nexstar::{Command, DateTime, Device, Dms, Hemisphere};
+use proptest::prelude::*;
+
+fn dms(max_degrees: u8, hemispheres: [Hemisphere; 2]) -> impl Strategy<Value = Dms> {
+    (
+        0..max_degrees,
+        0u8..60,
+        0u8..60,
+        prop::sample::select(hemispheres.to_vec()),
+    )
+        .prop_map(|(degrees, minutes, seconds, hemisphere)| Dms {
+            degrees,
+            minutes,
+            seconds,
+            hemisphere,
+        })
+}
+
+fn datetime() -> impl Strategy<Value = DateTime> {
+    (
+        (0u8..24, 0u8..60, 0u8..60),
+        (-12i8..=12, any::<bool>()),
+        (0u8..100, 1u8..=12, 1u8..=28),
+    )
+        .prop_map(
+            |((hour, minutes, seconds), (zone, daylight_saving), (year, month, day))| DateTime {
+                hour,
+                minutes,
+                seconds,
+                zone,
+                daylight_saving,
+                year,
+                month,
+                day,
+            },
+        )
+}
+
+fn request() -> impl Strategy<Value = Request> {
+    prop_oneof![
+        (any::<bool>(), any::<bool>()).prop_map(|(azm_alt, precise)| Request::GetPosition {
+            axes: if azm_alt { Axes::AzmAlt } else { Axes::RaDec },
+            precise,
+        }),
+        prop::sample::select(vec![
+            Request::CancelGoto,
+            Request::GetTrackingMode,
+            Request::GetLocation,
+            Request::GetDateTime,
+            Request::GetVersion,
+            Request::GetModel,
+            Request::IsAlignmentComplete,
+            Request::IsGotoInProgress,
+            Request::Echo(b'K'),
+        ]),
+        (0u8..=17).prop_map(|len| Request::pass_through(
+            Device::GPSUnit,
+            Command::GetDeviceVersion,
+            [],
+            len
+        )),
+    ]
+}
+
+/// Encodes the command and gets its arguments after the command byte.
+fn arguments(request: Request) -> Vec<u8> {
+    let mut buf = [0u8; MAX_COMMAND];
+    let len = encode_command(&request, &mut buf).unwrap();
+    buf[1..len].to_vec()
+}
+
+/// Appends the ack to the response data.
+fn acked(mut data: Vec<u8>) -> Vec<u8> {
+    data.push(b'#');
+    data
+}
+
+proptest! {
+    #[test]
+    fn position_roundtrip(first in any::<u32>(), second in any::<u32>(), precise in any::<bool>()) {
+        let goto = Request::Goto {
+            axes: Axes::RaDec,
+            position: Counts { first, second },
+            precise,
+        };
+        let get = Request::GetPosition { axes: Axes::RaDec, precise };
+        let mask = if precise { 0xFF_FFFF } else { 0xFFFF };
+
+        prop_assert_eq!(
+            parse_response(&get, &acked(arguments(goto))),
+            Ok(Response::Position(Counts {
+                first: first & mask,
+                second: second & mask,
+            }))
+        );
+    }
+
+    #[test]
+    fn location_roundtrip(
+        latitude in dms(90, [Hemisphere::North, Hemisphere::South]),
+        longitude in dms(180, [Hemisphere::East, Hemisphere::West]),
+    ) {
+        let set = Request::SetLocation { latitude, longitude };
+        prop_assert_eq!(
+            parse_response(&Request::GetLocation, &acked(arguments(set))),
+            Ok(Response::Location { latitude, longitude })
+        );
+    }
+
+    #[test]
+    fn datetime_roundtrip(datetime in datetime()) {
+        let set = Request::SetDateTime(datetime);
+        prop_assert_eq!(
+            parse_response(&Request::GetDateTime, &acked(arguments(set))),
+            Ok(Response::DateTime(datetime))
+        );
+    }
+
+    #[test]
+    fn parser_agrees(request in request(), input in prop::collection::vec(any::<u8>(), 0..24)) {
+        let mut buffer = [0u8; MAX_RESPONSE + 2];
+        let mut parser = Parser::new(&request);
+        let mut progress = Progress::NeedMore;
+        for byte in &input {
+            progress = parser.push(&mut buffer, *byte);
+            if progress != Progress::NeedMore {
+                break;
+            }
+        }
+
+        match progress {
+            Progress::Done => {
+                // the response stays in place, the parser only framed it
+                let consumed = &input[..parser.received()];
+                prop_assert_eq!(parser.response(&buffer), consumed);
+                prop_assert_ne!(parse_response(&request, consumed), Err(ParseError::Incomplete));
+            }
+            Progress::NeedMore => {
+                prop_assert_eq!(parse_response(&request, &input), Err(ParseError::Incomplete));
+                prop_assert!(input.len() < request.response_len() + 2);
+            }
+        }
+    }
+}
+
+#[test]
+fn oversized_pass_through() {
+    let mut buf = [0u8; MAX_COMMAND];
+    let request = Request::PassThrough {
+        device: Device::AzmRaMotor,
+        command: Command::SetPositiveVariableRate,
+        data: [0; 3],
+        data_len: 3,
+        response_len: 18,
+    };
+    assert_eq!(encode_command(&request, &mut buf), None);
+}
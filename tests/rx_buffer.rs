@@ -0,0 +1,137 @@
+#![cfg(feature = "heapless")]
+
+use embedded_hal::serial::{self, Read};
+use nexstar::rx_buffer::{Overrun, RxBuffer};
+use nexstar::{Error, LinkState, NexStar, Version};
+
+/// Transmitting half discarding the commands
+struct Sink;
+
+impl serial::Write<u8> for Sink {
+    type Error = ();
+
+    fn write(&mut self, _: u8) -> nb::Result<(), ()> {
+        Ok(())
+    }
+
+    fn flush(&mut self) -> nb::Result<(), ()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn bursty_producer_slow_consumer() {
+    let mut buffer = RxBuffer::<16>::new();
+    let (mut producer, mut reader) = buffer.split();
+    let stream: Vec<u8> = (0..=255).collect();
+    let mut sent = stream.iter();
+    let mut received = Vec::new();
+
+    // bursts of up to 12 bytes, the consumer takes 4 bytes in between
+    let overrun = 'outer: loop {
+        for byte in sent.by_ref().take(12) {
+            producer.push(*byte);
+        }
+        for _ in 0..4 {
+            match reader.read() {
+                Ok(byte) => received.push(byte),
+                Err(nb::Error::WouldBlock) => break,
+                Err(nb::Error::Other(e)) => break 'outer e,
+            }
+        }
+    };
+    assert_eq!(overrun, Overrun);
+
+    // no byte was corrupted before the overrun was reported
+    assert!(!received.is_empty());
+    assert_eq!(received[..], stream[..received.len()]);
+    // reported once
+    assert!(reader.read().is_ok());
+}
+
+#[test]
+fn bursts_within_capacity() {
+    let mut buffer = RxBuffer::<16>::new();
+    let (mut producer, reader) = buffer.split();
+    let mut nexstar = NexStar::new(reader, Sink);
+
+    // three responses arrive in one burst before the driver reads
+    for _ in 0..3 {
+        for byte in &[4, 21, b'#'] {
+            producer.push(*byte);
+        }
+    }
+    for _ in 0..3 {
+        assert_eq!(nexstar.version().unwrap(), Version::new(4, 21));
+    }
+}
+
+#[test]
+fn overrun_marks_link_dirty() {
+    let mut buffer = RxBuffer::<4>::new();
+    let (mut producer, reader) = buffer.split();
+    let mut nexstar = NexStar::new(reader, Sink);
+
+    for byte in b"XXXX" {
+        producer.push(*byte);
+    }
+    assert!(matches!(nexstar.version(), Err(Error::Read(Overrun))));
+    assert_eq!(nexstar.link_state(), LinkState::Dirty);
+
+    // the bytes before the overrun are left for the resync to drain
+    let (reader, _) = nexstar.free();
+    assert_eq!(reader.len(), 3);
+}
+
+#[test]
+fn overrun_after_report() {
+    let mut buffer = RxBuffer::<4>::new();
+    let (mut producer, mut reader) = buffer.split();
+
+    for byte in b"abcd" {
+        producer.push(*byte);
+    }
+    assert_eq!(reader.read(), Err(nb::Error::Other(Overrun)));
+    // dropped again right after the report
+    producer.push(b'e');
+    assert_eq!(reader.read(), Err(nb::Error::Other(Overrun)));
+    assert_eq!(reader.read(), Ok(b'a'));
+
+    producer.push(b'f');
+    let rest: Vec<u8> = (0..3).map(|_| reader.read().unwrap()).collect();
+    assert_eq!(rest, b"bcf");
+    assert_eq!(reader.read(), Err(nb::Error::WouldBlock));
+}
+
+#[test]
+fn overruns_from_another_thread() {
+    const BYTES: usize = 100_000;
+    let mut buffer = RxBuffer::<8>::new();
+    let (mut producer, mut reader) = buffer.split();
+
+    let (received, reports) = std::thread::scope(|scope| {
+        let interrupt = scope.spawn(move || {
+            for byte in 0..BYTES {
+                producer.push(byte as u8);
+            }
+        });
+
+        let (mut received, mut reports) = (0, 0);
+        loop {
+            let finished = interrupt.is_finished();
+            match reader.read() {
+                Ok(_) => received += 1,
+                Err(nb::Error::Other(Overrun)) => reports += 1,
+                // a pending overrun is reported before the buffer runs empty
+                Err(nb::Error::WouldBlock) if finished => break,
+                Err(nb::Error::WouldBlock) => {}
+            }
+        }
+        (received, reports)
+    });
+
+    // every drop was reported, at most once each
+    let dropped = BYTES - received;
+    assert!(reports <= dropped);
+    assert_eq!(reports == 0, dropped == 0);
+}
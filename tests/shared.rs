@@ -0,0 +1,194 @@
+#![cfg(feature = "critical-section")]
+
+use std::collections::VecDeque;
+#[cfg(feature = "heapless")]
+use std::sync::mpsc::{self, Sender};
+#[cfg(feature = "heapless")]
+use std::sync::OnceLock;
+use std::sync::{Arc, Mutex};
+use std::thread;
+#[cfg(feature = "heapless")]
+use std::time::Instant;
+
+use embedded_hal::serial;
+#[cfg(feature = "heapless")]
+use nexstar::rx_buffer::RxBuffer;
+use nexstar::shared::SharedNexStar;
+use nexstar::{LinkState, Model, NexStar, Version};
+
+const CALLS: usize = 200;
+
+/// HC answering the version, model and echo commands, shared by both halves
+#[derive(Default)]
+struct Hc {
+    response: VecDeque<u8>,
+    /// A command arrived before the previous response was read.
+    interleaved: bool,
+    /// The next byte is the argument of an echo.
+    echo: bool,
+}
+
+/// Transport whose halves are `Clone`, like a TCP stream
+#[derive(Clone, Default)]
+struct Port {
+    hc: Arc<Mutex<Hc>>,
+}
+
+impl serial::Read<u8> for Port {
+    type Error = ();
+
+    fn read(&mut self) -> nb::Result<u8, ()> {
+        let byte = self.hc.lock().unwrap().response.pop_front();
+        // let the other thread try to get in between
+        thread::yield_now();
+        byte.ok_or(nb::Error::WouldBlock)
+    }
+}
+
+impl serial::Write<u8> for Port {
+    type Error = ();
+
+    fn write(&mut self, word: u8) -> nb::Result<(), ()> {
+        let mut hc = self.hc.lock().unwrap();
+        if !hc.response.is_empty() {
+            hc.interleaved = true;
+        }
+        if hc.echo {
+            hc.echo = false;
+            hc.response.extend(&[word, b'#']);
+            return Ok(());
+        }
+        match word {
+            b'K' => hc.echo = true,
+            b'V' => hc.response.extend(&[4, 21, b'#']),
+            b'm' => hc.response.extend(&[0x0C, b'#']),
+            _ => hc.response.push_back(b'!'),
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> nb::Result<(), ()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn transactions_not_interleaved() {
+    let port = Port::default();
+    let mount = Arc::new(SharedNexStar::new(NexStar::new(port.clone(), port.clone())));
+
+    let logger = {
+        let mount = mount.clone();
+        thread::spawn(move || {
+            for _ in 0..CALLS {
+                let version = mount.lock(|nexstar| nexstar.version()).unwrap();
+                assert_eq!(version, Version::new(4, 21));
+            }
+        })
+    };
+    let commander = {
+        let mount = mount.clone();
+        thread::spawn(move || {
+            for _ in 0..CALLS {
+                let model = mount.lock(|nexstar| nexstar.model()).unwrap();
+                assert_eq!(model, Model::Se6_8);
+            }
+        })
+    };
+    logger.join().unwrap();
+    commander.join().unwrap();
+
+    let hc = port.hc.lock().unwrap();
+    assert!(!hc.interleaved);
+    assert!(hc.response.is_empty());
+}
+
+#[test]
+fn keepalive_beside_commands() {
+    // the port could be cloned, the driver is shared instead
+    let port = Port::default();
+    let mount = Arc::new(SharedNexStar::new(NexStar::new(port.clone(), port.clone())));
+
+    let monitor = {
+        let mount = mount.clone();
+        thread::spawn(move || {
+            for _ in 0..CALLS {
+                mount.lock(|nexstar| nexstar.keepalive()).unwrap();
+            }
+        })
+    };
+    for _ in 0..CALLS {
+        let version = mount.lock(|nexstar| nexstar.version()).unwrap();
+        assert_eq!(version, Version::new(4, 21));
+    }
+    monitor.join().unwrap();
+
+    assert!(!port.hc.lock().unwrap().interleaved);
+    let nexstar = Arc::try_unwrap(mount).ok().unwrap().into_inner();
+    assert_eq!(nexstar.link_state(), LinkState::Clean);
+}
+
+#[test]
+fn try_lock_while_held() {
+    let port = Port::default();
+    let mount = SharedNexStar::new(NexStar::new(port.clone(), port));
+
+    let inner = mount.lock(|_| mount.try_lock(|nexstar| nexstar.version()).is_err());
+    assert!(inner);
+    let version = mount.try_lock(|nexstar| nexstar.version()).unwrap();
+    assert_eq!(version.unwrap(), Version::new(4, 21));
+}
+
+/// Transmitting half passing the commands to the simulated UART interrupt
+#[cfg(feature = "heapless")]
+struct Uart(Sender<u8>);
+
+#[cfg(feature = "heapless")]
+impl serial::Write<u8> for Uart {
+    type Error = ();
+
+    fn write(&mut self, word: u8) -> nb::Result<(), ()> {
+        self.0.send(word).map_err(|_| nb::Error::Other(()))
+    }
+
+    fn flush(&mut self) -> nb::Result<(), ()> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "heapless")]
+fn millis() -> u32 {
+    static START: OnceLock<Instant> = OnceLock::new();
+    START.get_or_init(Instant::now).elapsed().as_millis() as u32
+}
+
+#[cfg(feature = "heapless")]
+#[test]
+fn interrupt_fed_receiver() {
+    let mut buffer = RxBuffer::<16>::new();
+    let (mut producer, reader) = buffer.split();
+    let (uart, commands) = mpsc::channel();
+    let mount = SharedNexStar::new(NexStar::new(reader, Uart(uart)));
+    // fails instead of hanging if the receive interrupt can't get in
+    mount.lock(|nexstar| nexstar.set_timeout(millis, 5000));
+
+    thread::scope(|scope| {
+        // the receive interrupt, which only runs outside critical sections
+        scope.spawn(move || {
+            for command in commands {
+                if command == b'V' {
+                    for byte in [4, 21, b'#'] {
+                        critical_section::with(|_| producer.push(byte));
+                    }
+                }
+            }
+        });
+
+        for _ in 0..CALLS {
+            let version = mount.lock(|nexstar| nexstar.version()).unwrap();
+            assert_eq!(version, Version::new(4, 21));
+        }
+        // closes the channel, which ends the interrupt thread
+        drop(mount);
+    });
+}
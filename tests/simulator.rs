@@ -0,0 +1,1328 @@
+#![cfg(feature = "simulator")]
+
+mod common;
+
+use std::cell::RefCell;
+use std::convert::Infallible;
+
+use common::{assert_close, FakeDelay};
+use embedded_hal::serial;
+use nexstar::aux::AuxBus;
+use nexstar::detect::{detect_baud, detect_protocol, ProtocolKind, PROBE_TIMEOUT_MS};
+use nexstar::pe::PeRecorder;
+use nexstar::queue::{QueueEntry, TargetQueue, Visit};
+use nexstar::simulator::SimulatedHc;
+use nexstar::{
+    Approach, AzmAlt, Backlash, DateTime, Device, Error, FocuserLimits, GpsFix, GpsState,
+    GuideRate, Location, Model, MotorModel, NexStar, RaDec, Target, TrackingMode, TrackingRate,
+    UtcDateTime, Version,
+};
+
+const FIX: GpsFix = GpsFix {
+    latitude: 171_154,
+    longitude: -30_727,
+    utc: UtcDateTime {
+        year: 2026,
+        month: 10,
+        day: 15,
+        hour: 21,
+        minutes: 30,
+        seconds: 5,
+    },
+};
+
+#[test]
+fn connect() {
+    let hc = SimulatedHc::new();
+    hc.set_model(Model::CPC);
+    hc.set_version(Version::new(5, 2));
+    hc.set_device(Device::AltDecMotor, None);
+    let mut nexstar = NexStar::new(&hc, &hc);
+
+    let info = nexstar.connect().unwrap();
+    assert!(matches!(info.model, Model::CPC));
+    assert_eq!(info.hc_version, Version::new(5, 2));
+    assert_eq!(info.azm_ra_version, Some(Version::new(7, 11)));
+    assert_eq!(info.alt_dec_version, None);
+}
+
+#[test]
+fn detect_hc() {
+    let hc = SimulatedHc::new();
+    let (mut rx, mut tx, mut delay) = (&hc, &hc, &hc);
+
+    assert_eq!(
+        detect_protocol(&mut rx, &mut tx, &mut delay).unwrap(),
+        ProtocolKind::Hc
+    );
+    // the port is still in sync
+    assert_eq!(NexStar::new(rx, tx).version().unwrap(), Version::new(4, 21));
+}
+
+#[test]
+fn detect_aux() {
+    let hc = SimulatedHc::new();
+    hc.set_protocol(ProtocolKind::Aux);
+    let (mut rx, mut tx, mut delay) = (&hc, &hc, &hc);
+
+    assert_eq!(
+        detect_protocol(&mut rx, &mut tx, &mut delay).unwrap(),
+        ProtocolKind::Aux
+    );
+    let mut aux = AuxBus::new(rx, tx);
+    assert_eq!(
+        aux.version(Device::AltDecMotor).unwrap(),
+        Version::new(7, 11)
+    );
+}
+
+#[test]
+fn detect_dead_port() {
+    let hc = SimulatedHc::new();
+    hc.set_protocol(ProtocolKind::Neither);
+    let (mut rx, mut tx) = (&hc, &hc);
+    let mut delay = FakeDelay::default();
+
+    assert_eq!(
+        detect_protocol(&mut rx, &mut tx, &mut delay).unwrap(),
+        ProtocolKind::Neither
+    );
+    // both probes waited in full after draining the port
+    assert_eq!(delay.total(), 2 * (20 + PROBE_TIMEOUT_MS));
+}
+
+#[test]
+fn detect_baud_of_aux_port() {
+    let hc = SimulatedHc::new();
+    hc.set_protocol(ProtocolKind::Aux);
+    hc.set_baud(115_200);
+    let mut opened = Vec::new();
+    let mut delay = FakeDelay::default();
+
+    let detected = detect_baud(
+        |baud| {
+            opened.push(baud);
+            Ok::<_, ()>(hc.open(baud))
+        },
+        &[9600, 19_200, 115_200, 230_400],
+        &mut delay,
+    )
+    .unwrap()
+    .unwrap();
+    assert_eq!(
+        (detected.baud, detected.protocol),
+        (115_200, ProtocolKind::Aux)
+    );
+    assert_eq!(opened, [9600, 19_200, 115_200]);
+
+    // the port is left open at the detected rate
+    let mut aux = AuxBus::new(detected.rx, detected.tx);
+    assert_eq!(
+        aux.version(Device::AzmRaMotor).unwrap(),
+        Version::new(7, 11)
+    );
+}
+
+#[test]
+fn detect_baud_none_answers() {
+    let hc = SimulatedHc::new();
+    hc.set_baud(19_200);
+    let mut delay = FakeDelay::default();
+
+    let detected = detect_baud(
+        |baud| Ok::<_, ()>(hc.open(baud)),
+        &[9600, 115_200],
+        &mut delay,
+    );
+    assert!(detected.unwrap().is_none());
+
+    let failed = detect_baud(
+        |_| Err::<(&SimulatedHc, &SimulatedHc), _>("busy"),
+        &[9600],
+        &mut delay,
+    );
+    assert!(matches!(failed, Err("busy")));
+}
+
+#[test]
+fn optional_gps() {
+    let hc = SimulatedHc::new();
+    let mut nexstar = NexStar::new(&hc, &hc);
+
+    assert!(matches!(
+        nexstar.device_version(Device::GPSUnit),
+        Err(Error::UnexpectedResponse(_))
+    ));
+
+    hc.set_device(Device::GPSUnit, Some(Version::new(1, 6)));
+    assert_eq!(
+        nexstar.device_version(Device::GPSUnit).unwrap(),
+        Version::new(1, 6)
+    );
+}
+
+#[test]
+fn goto_moves_the_axes() {
+    let hc = SimulatedHc::new();
+    hc.set_slew_step(10.0);
+    let mut nexstar = NexStar::new(&hc, &hc);
+
+    nexstar
+        .precise_goto_ra_dec(RaDec {
+            ra: 345.0,
+            dec: -25.0,
+        })
+        .unwrap();
+    assert!(hc.is_goto_in_progress());
+
+    // every command moves one step, across the RA wrap instead of the long
+    // way round
+    let position = nexstar.precise_ra_dec().unwrap();
+    assert_close(position.ra, 350.0, 1e-3);
+    assert_close(position.dec, -10.0, 1e-3);
+
+    let mut delay = FakeDelay::default();
+    nexstar.wait_for_goto(&mut delay, 500, 10_000).unwrap();
+    let position = nexstar.precise_ra_dec().unwrap();
+    assert_close(position.ra, 345.0, 1e-3);
+    assert_close(position.dec, -25.0, 1e-3);
+    assert!(!hc.is_goto_in_progress());
+}
+
+#[test]
+fn cancel_goto() {
+    let hc = SimulatedHc::new();
+    let mut nexstar = NexStar::new(&hc, &hc);
+
+    nexstar
+        .goto_azm_alt(AzmAlt {
+            azm: 180.0,
+            alt: 45.0,
+        })
+        .unwrap();
+    nexstar.cancel_goto().unwrap();
+
+    assert!(!nexstar.is_goto_in_progress().unwrap());
+    let (azm, alt) = hc.axes();
+    assert!(azm < 180.0 && alt < 45.0);
+}
+
+#[test]
+fn release_during_goto() {
+    let hc = SimulatedHc::new();
+    let mut nexstar = NexStar::new(&hc, &hc);
+    let mut delay = FakeDelay::default();
+
+    nexstar
+        .goto_azm_alt(AzmAlt {
+            azm: 180.0,
+            alt: 45.0,
+        })
+        .unwrap();
+    let (rx, tx, report) = nexstar.release(&mut delay);
+    assert!(report.is_ok());
+
+    // the GOTO was cancelled before the transports came back
+    let (azm, alt) = hc.axes();
+    assert!(azm < 180.0 && alt < 45.0);
+    let mut nexstar = NexStar::new(rx, tx);
+    assert!(!nexstar.is_goto_in_progress().unwrap());
+    assert_eq!(hc.axes(), (azm, alt));
+}
+
+#[test]
+fn sync() {
+    let hc = SimulatedHc::new();
+    let mut nexstar = NexStar::new(&hc, &hc);
+
+    nexstar
+        .sync_ra_dec(RaDec {
+            ra: 90.0,
+            dec: 45.0,
+        })
+        .unwrap();
+    let position = nexstar.ra_dec().unwrap();
+    assert_close(position.ra, 90.0, 0.01);
+    assert_close(position.dec, 45.0, 0.01);
+}
+
+#[test]
+fn park() {
+    let hc = SimulatedHc::new();
+    hc.set_axes(10.0, 10.0);
+    let mut nexstar = NexStar::new(&hc, &hc);
+    nexstar.set_tracking_mode(TrackingMode::AltAz).unwrap();
+
+    let mut delay = FakeDelay::default();
+    nexstar
+        .park(AzmAlt { azm: 0.0, alt: 0.0 }, &mut delay)
+        .unwrap();
+    assert!(matches!(hc.tracking_mode(), TrackingMode::Off));
+    assert_eq!(hc.axes(), (0.0, 0.0));
+}
+
+#[test]
+fn park_unpark_cycle() {
+    let hc = SimulatedHc::new();
+    hc.set_axes(120.0, 40.0);
+    let mut nexstar = NexStar::new(&hc, &hc);
+    nexstar.set_tracking_mode(TrackingMode::EQNorth).unwrap();
+    let position = AzmAlt {
+        azm: 180.0,
+        alt: 10.0,
+    };
+
+    let mut delay = FakeDelay::default();
+    nexstar.park(position, &mut delay).unwrap();
+    assert!(matches!(hc.tracking_mode(), TrackingMode::Off));
+    assert!(!hc.is_goto_in_progress());
+    let parked = nexstar.azm_alt().unwrap();
+    assert_close(parked.azm, 180.0, 0.01);
+    assert_close(parked.alt, 10.0, 0.01);
+
+    // parking again from the park position doesn't move
+    nexstar.park(position, &mut delay).unwrap();
+
+    nexstar.unpark(TrackingMode::EQNorth).unwrap();
+    assert!(matches!(hc.tracking_mode(), TrackingMode::EQNorth));
+    nexstar
+        .goto_azm_alt(AzmAlt {
+            azm: 190.0,
+            alt: 20.0,
+        })
+        .unwrap();
+    nexstar.wait_for_goto(&mut delay, 100, 10_000).unwrap();
+    assert_close(hc.axes().0, 190.0, 0.01);
+}
+
+#[test]
+fn park_aborted() {
+    let hc = SimulatedHc::new();
+    hc.set_axes(120.0, 40.0);
+    hc.set_slew_step(1.0);
+    let mut nexstar = NexStar::new(&hc, &hc);
+    nexstar.set_abort_hook(Some(|| true));
+
+    let mut delay = FakeDelay::default();
+    assert!(matches!(
+        nexstar.park(AzmAlt { azm: 0.0, alt: 0.0 }, &mut delay),
+        Err(Error::Aborted)
+    ));
+    // the GOTO was cancelled short of the park position
+    assert!(!hc.is_goto_in_progress());
+    assert_ne!(hc.axes(), (0.0, 0.0));
+}
+
+/// Records the axes of the simulated HC after every written byte.
+struct AxesLog<'a> {
+    hc: &'a SimulatedHc,
+    axes: &'a RefCell<Vec<(f32, f32)>>,
+}
+
+impl serial::Write<u8> for AxesLog<'_> {
+    type Error = Infallible;
+
+    fn write(&mut self, word: u8) -> nb::Result<(), Infallible> {
+        let mut hc = self.hc;
+        hc.write(word)?;
+        self.axes.borrow_mut().push(self.hc.axes());
+        Ok(())
+    }
+
+    fn flush(&mut self) -> nb::Result<(), Infallible> {
+        Ok(())
+    }
+}
+
+/// Unwraps an angle into -180..180.
+fn unwrap(degrees: f32) -> f32 {
+    if degrees > 180.0 {
+        degrees - 360.0
+    } else {
+        degrees
+    }
+}
+
+/// Gets the axes from the first visit of the point on, with the first axis
+/// unwrapped.
+fn leg_from(axes: RefCell<Vec<(f32, f32)>>, first: f32, second: f32) -> Vec<(f32, f32)> {
+    let mut axes: Vec<(f32, f32)> = axes
+        .into_inner()
+        .into_iter()
+        .map(|(first, second)| (unwrap(first), second))
+        .collect();
+    axes.dedup();
+    let start = axes
+        .iter()
+        .position(|axes| (axes.0 - first).abs() < 0.001 && (axes.1 - second).abs() < 0.001)
+        .expect("offset point visited");
+    axes.split_off(start)
+}
+
+#[test]
+fn goto_with_approach_across_ra_wrap() {
+    let hc = SimulatedHc::new();
+    hc.set_axes(350.0, 5.0);
+    hc.set_slew_step(0.25);
+    let axes = RefCell::new(Vec::new());
+    let mut nexstar = NexStar::new(
+        &hc,
+        AxesLog {
+            hc: &hc,
+            axes: &axes,
+        },
+    );
+    let mut delay = FakeDelay::default();
+
+    // 23h59m36s
+    let target = RaDec {
+        ra: 359.9,
+        dec: 10.0,
+    };
+    nexstar
+        .goto_with_approach(Target::RaDec(target), 30.0, Approach::Negative, &mut delay)
+        .unwrap();
+
+    // the mount went past the wrap to the offset point and came back with
+    // decreasing RA and Dec
+    let leg = leg_from(axes, 0.4, 10.5);
+    assert!(leg.len() > 2);
+    assert!(leg
+        .windows(2)
+        .all(|step| step[1].0 <= step[0].0 && step[1].1 <= step[0].1));
+    assert_close(hc.axes().0, 359.9, 0.001);
+    assert_close(hc.axes().1, 10.0, 0.001);
+}
+
+#[test]
+fn goto_with_approach_across_azm_wrap() {
+    let hc = SimulatedHc::new();
+    hc.set_axes(10.0, 88.0);
+    hc.set_slew_step(0.25);
+    let axes = RefCell::new(Vec::new());
+    let mut nexstar = NexStar::new(
+        &hc,
+        AxesLog {
+            hc: &hc,
+            axes: &axes,
+        },
+    );
+    let mut delay = FakeDelay::default();
+
+    let target = AzmAlt {
+        azm: 0.2,
+        alt: 85.0,
+    };
+    nexstar
+        .goto_with_approach(Target::AzmAlt(target), 30.0, Approach::Positive, &mut delay)
+        .unwrap();
+
+    // the mount went back past the wrap to the offset point and came back
+    // with increasing Azm and Alt
+    let leg = leg_from(axes, -0.3, 84.5);
+    assert!(leg.len() > 2);
+    assert!(leg
+        .windows(2)
+        .all(|step| step[1].0 >= step[0].0 && step[1].1 >= step[0].1));
+    assert_close(hc.axes().0, 0.2, 0.001);
+    assert_close(hc.axes().1, 85.0, 0.001);
+}
+
+#[test]
+fn location_and_time() {
+    let hc = SimulatedHc::new();
+    let mut nexstar = NexStar::new(&hc, &hc);
+
+    nexstar
+        .set_location(Location {
+            latitude: -33.5,
+            longitude: 151.25,
+        })
+        .unwrap();
+    let location = nexstar.location().unwrap();
+    assert_close(location.latitude, -33.5, 1e-4);
+    assert_close(location.longitude, 151.25, 1e-4);
+    assert_close(hc.location().latitude, -33.5, 1e-4);
+
+    nexstar
+        .set_datetime(DateTime {
+            hour: 21,
+            minutes: 30,
+            seconds: 5,
+            zone: -5,
+            daylight_saving: true,
+            year: 24,
+            month: 6,
+            day: 15,
+        })
+        .unwrap();
+    let datetime = nexstar.datetime().unwrap();
+    assert_eq!(
+        (datetime.hour, datetime.minutes, datetime.seconds),
+        (21, 30, 5)
+    );
+    assert_eq!((datetime.year, datetime.month, datetime.day), (24, 6, 15));
+    assert_eq!(datetime.zone, -5);
+    assert!(datetime.daylight_saving);
+}
+
+#[test]
+fn wait_for_alignment() {
+    let hc = SimulatedHc::new();
+    hc.set_aligned_after(3);
+    let mut nexstar = NexStar::new(&hc, &hc);
+    let mut delay = FakeDelay::default();
+
+    nexstar.wait_for_alignment(&mut delay, 500, 10_000).unwrap();
+    assert_eq!(delay.delays, [500, 500, 500]);
+    assert!(nexstar.is_alignment_complete().unwrap());
+
+    hc.set_aligned_after(5);
+    assert!(matches!(
+        nexstar.wait_for_alignment(&mut delay, 500, 1000),
+        Err(Error::Timeout)
+    ));
+}
+
+#[test]
+fn tracking_and_alignment() {
+    let hc = SimulatedHc::new();
+    hc.set_aligned(false);
+    let mut nexstar = NexStar::new(&hc, &hc);
+
+    assert!(!nexstar.is_alignment_complete().unwrap());
+    hc.set_aligned(true);
+    assert!(nexstar.is_alignment_complete().unwrap());
+
+    nexstar.set_tracking_mode(TrackingMode::EQSouth).unwrap();
+    assert!(matches!(
+        nexstar.tracking_mode().unwrap(),
+        TrackingMode::EQSouth
+    ));
+
+    nexstar.track_rates(100, -100).unwrap();
+    nexstar.stop_tracking_rates().unwrap();
+}
+
+#[test]
+fn track_rates_interleaved() {
+    let hc = SimulatedHc::new();
+    hc.set_axes(100.0, 0.0);
+    let mut nexstar = NexStar::new(&hc, &hc);
+
+    // updates at 1 Hz between other queries, 0.1° per step and second
+    for step in 1..=10 {
+        nexstar.track_rates(360 * step, -360 * step).unwrap();
+        assert!(!nexstar.is_goto_in_progress().unwrap());
+        assert!(matches!(
+            nexstar.tracking_mode().unwrap(),
+            TrackingMode::Off
+        ));
+        hc.advance(1000);
+    }
+    let (first, second) = hc.axes();
+    assert_close(first, 105.5, 0.001);
+    assert_close(second, -5.5, 0.001);
+
+    nexstar.stop_tracking_rates().unwrap();
+    hc.advance(1000);
+    assert_close(hc.axes().0, 105.5, 0.001);
+    assert!(nexstar.is_alignment_complete().unwrap());
+}
+
+#[test]
+fn status_combinations() {
+    let modes = [
+        TrackingMode::Off,
+        TrackingMode::AltAz,
+        TrackingMode::EQNorth,
+        TrackingMode::EQSouth,
+    ];
+
+    for &aligned in &[false, true] {
+        for &goto in &[false, true] {
+            for &mode in &modes {
+                let hc = SimulatedHc::new();
+                hc.set_aligned(aligned);
+                // too slow to arrive during the status queries
+                hc.set_slew_step(0.001);
+                let mut nexstar = NexStar::new(&hc, &hc);
+                nexstar.set_tracking_mode(mode).unwrap();
+                if goto {
+                    nexstar
+                        .goto_azm_alt(AzmAlt {
+                            azm: 90.0,
+                            alt: 45.0,
+                        })
+                        .unwrap();
+                }
+
+                let status = nexstar.status().unwrap();
+                assert_eq!(status.aligned, aligned);
+                assert_eq!(status.goto_in_progress, goto);
+                assert_eq!(status.tracking as u8, mode as u8);
+            }
+        }
+    }
+}
+
+#[test]
+fn on_target_after_goto() {
+    let hc = SimulatedHc::new();
+    hc.set_slew_step(10.0);
+    let mut nexstar = NexStar::new(&hc, &hc);
+
+    nexstar
+        .goto_with_approach(
+            nexstar::Target::RaDec(RaDec { ra: 0.5, dec: 89.5 }),
+            30.0,
+            nexstar::Approach::Positive,
+            &mut FakeDelay::default(),
+        )
+        .unwrap();
+    assert!(nexstar.on_target(1.0).unwrap());
+
+    // 0.75° away across the pole, not 180°
+    hc.set_axes(180.5, 89.75);
+    assert!(!nexstar.on_target(1.0).unwrap());
+    assert!(nexstar.on_target(3600.0).unwrap());
+}
+
+#[test]
+fn gps_fix_after_polls() {
+    let hc = SimulatedHc::new();
+    hc.set_device(Device::GPSUnit, Some(Version::new(1, 6)));
+    hc.set_gps_fix(Some(FIX), 3);
+    let mut nexstar = NexStar::new(&hc, &hc);
+    let mut delay = FakeDelay::default();
+
+    assert!(matches!(nexstar.gps_fix(), Err(Error::NoGpsFix)));
+    assert_eq!(
+        nexstar.wait_for_gps_fix(&mut delay, 1000, 60_000).unwrap(),
+        FIX
+    );
+    assert_eq!(delay.delays, [1000, 1000]);
+}
+
+#[test]
+fn write_status() {
+    let hc = SimulatedHc::new();
+    hc.set_axes(90.0, -45.0);
+    hc.set_aligned(false);
+    let mut nexstar = NexStar::new(&hc, &hc);
+    nexstar.set_tracking_mode(TrackingMode::EQNorth).unwrap();
+
+    let mut out = String::new();
+    nexstar.write_status(&mut out).unwrap();
+    assert_eq!(
+        out,
+        "RA/Dec   06h00m00s -45°00'00\"\n\
+         Tracking EQ North\n\
+         Aligned  no\n\
+         GOTO     idle\n"
+    );
+}
+
+#[test]
+fn gps_states() {
+    let hc = SimulatedHc::new();
+    let mut nexstar = NexStar::new(&hc, &hc);
+    let mut delay = &hc;
+
+    assert_eq!(nexstar.gps_state(&mut delay).unwrap(), GpsState::Absent);
+    assert!(matches!(
+        nexstar.gps_fix(),
+        Err(Error::UnexpectedResponse(_))
+    ));
+
+    hc.set_device(Device::GPSUnit, Some(Version::new(1, 6)));
+    assert_eq!(nexstar.gps_state(&mut delay).unwrap(), GpsState::NoFix);
+    assert!(matches!(nexstar.gps_fix(), Err(Error::NoGpsFix)));
+
+    hc.set_gps_fix(Some(FIX), 0);
+    assert_eq!(nexstar.gps_state(&mut delay).unwrap(), GpsState::Linked);
+    assert_eq!(nexstar.gps_fix().unwrap(), FIX);
+}
+
+#[test]
+fn gps_fix_never_appears() {
+    let hc = SimulatedHc::new();
+    hc.set_device(Device::GPSUnit, Some(Version::new(1, 6)));
+    let mut nexstar = NexStar::new(&hc, &hc);
+    let mut delay = FakeDelay::default();
+
+    assert!(matches!(
+        nexstar.wait_for_gps_fix(&mut delay, 1000, 5000),
+        Err(Error::Timeout)
+    ));
+    assert_eq!(delay.total(), 5000);
+}
+
+#[test]
+fn focuser_calibrate() {
+    let hc = SimulatedHc::new();
+    hc.set_device(Device::Focuser, Some(Version::new(1, 2)));
+    let limits = FocuserLimits {
+        low: 0x0010_0000,
+        high: 0x00FF_0000,
+    };
+    hc.set_focuser_calibration(limits, Some(3));
+    let mut nexstar = NexStar::new(&hc, &hc);
+    let mut delay = FakeDelay::default();
+
+    assert_eq!(
+        nexstar.focuser_limits().unwrap(),
+        FocuserLimits { low: 0, high: 0 }
+    );
+    assert_eq!(
+        nexstar.focuser_calibrate(&mut delay, 10_000).unwrap(),
+        limits
+    );
+    assert_eq!(delay.delays, [500, 500, 500]);
+    assert!(!nexstar.is_focuser_moving().unwrap());
+    assert_eq!(nexstar.focuser_limits().unwrap(), limits);
+}
+
+#[test]
+fn focuser_calibrate_timeout() {
+    let hc = SimulatedHc::new();
+    hc.set_device(Device::Focuser, Some(Version::new(1, 2)));
+    hc.set_focuser_calibration(FocuserLimits { low: 1, high: 2 }, None);
+    let mut nexstar = NexStar::new(&hc, &hc);
+    let mut delay = FakeDelay::default();
+
+    assert!(matches!(
+        nexstar.focuser_calibrate(&mut delay, 5000),
+        Err(Error::Timeout)
+    ));
+    assert_eq!(delay.total(), 5000);
+    assert!(nexstar.is_focuser_moving().unwrap());
+}
+
+#[test]
+fn focuser_calibrate_refuses_while_moving() {
+    let hc = SimulatedHc::new();
+    hc.set_device(Device::Focuser, Some(Version::new(1, 2)));
+    hc.set_focuser_moving(true);
+    let mut nexstar = NexStar::new(&hc, &hc);
+    let mut delay = FakeDelay::default();
+
+    assert!(matches!(
+        nexstar.focuser_calibrate(&mut delay, 5000),
+        Err(Error::Busy)
+    ));
+    assert!(delay.delays.is_empty());
+
+    // without a focuser
+    let hc = SimulatedHc::new();
+    let mut nexstar = NexStar::new(&hc, &hc);
+    assert!(nexstar.focuser_calibrate(&mut delay, 5000).is_err());
+}
+
+#[test]
+fn battery() {
+    let hc = SimulatedHc::new();
+    hc.set_device(Device::Battery, Some(Version::new(1, 0)));
+    hc.set_battery_voltage(13_150);
+    let mut nexstar = NexStar::new(&hc, &hc);
+
+    assert_eq!(nexstar.battery_voltage().unwrap(), 13_150);
+    assert_eq!(nexstar.battery_percent().unwrap(), 55);
+    assert!(!nexstar.is_battery_low(20).unwrap());
+
+    hc.set_battery_voltage(12_900);
+    assert_eq!(nexstar.battery_percent().unwrap(), 20);
+    assert!(nexstar.is_battery_low(20).unwrap());
+
+    hc.set_device(Device::Battery, None);
+    assert!(matches!(
+        nexstar.is_battery_low(20),
+        Err(Error::UnsupportedCommand(_))
+    ));
+}
+
+#[test]
+fn datetime_from_gps() {
+    let hc = SimulatedHc::new();
+    hc.set_device(Device::GPSUnit, Some(Version::new(1, 6)));
+    let mut nexstar = NexStar::new(&hc, &hc);
+
+    assert!(matches!(
+        nexstar.set_datetime_from_gps(-5, true),
+        Err(Error::NoGpsFix)
+    ));
+    assert_eq!(hc.datetime().year, 0);
+
+    hc.set_gps_fix(Some(FIX), 0);
+    let datetime = nexstar.set_datetime_from_gps(-5, true).unwrap();
+    assert_eq!(hc.datetime(), datetime);
+    assert_eq!((datetime.year, datetime.month, datetime.day), (26, 10, 15));
+    assert_eq!(
+        (datetime.hour, datetime.minutes, datetime.seconds),
+        (17, 30, 5)
+    );
+}
+
+#[test]
+fn max_slew_rate_roundtrip() {
+    let hc = SimulatedHc::new();
+    let mut nexstar = NexStar::new(&hc, &hc);
+
+    assert_eq!(nexstar.max_slew_rate(Device::AzmRaMotor).unwrap(), 14_400);
+    nexstar.set_max_slew_rate(Device::AzmRaMotor, 7200).unwrap();
+    nexstar
+        .set_max_slew_rate(Device::AltDecMotor, 3600)
+        .unwrap();
+    assert_eq!(nexstar.max_slew_rate(Device::AzmRaMotor).unwrap(), 7200);
+    assert_eq!(nexstar.max_slew_rate(Device::AltDecMotor).unwrap(), 3600);
+}
+
+#[test]
+fn calibrate_slew_rate() {
+    let hc = SimulatedHc::new();
+    hc.set_rate_scale(Device::AzmRaMotor, 0.9);
+    // the position wraps during the slew
+    hc.set_axes(359.9, 10.0);
+    let mut nexstar = NexStar::new(&hc, &hc);
+    let mut delay = &hc;
+
+    let rate = nexstar
+        .calibrate_slew_rate(Device::AzmRaMotor, 100.0, 10_000, &mut delay)
+        .unwrap();
+    assert_close(rate, 90.0, 0.05);
+    assert_close(hc.axes().0, 0.15, 1e-3);
+
+    let rate = nexstar
+        .calibrate_slew_rate(Device::AltDecMotor, -60.0, 30_000, &mut delay)
+        .unwrap();
+    assert_close(rate, -60.0, 0.05);
+    assert_close(hc.axes().1, 9.5, 1e-3);
+
+    // both motors stopped
+    hc.advance(60_000);
+    assert_close(hc.axes().0, 0.15, 1e-3);
+    assert_close(hc.axes().1, 9.5, 1e-3);
+}
+
+#[test]
+fn set_guiderate() {
+    let hc = SimulatedHc::new();
+    hc.set_axes(10.0, 20.0);
+    let mut nexstar = NexStar::new(&hc, &hc);
+
+    nexstar
+        .set_guiderate(Device::AzmRaMotor, GuideRate(-0.5))
+        .unwrap();
+    nexstar
+        .set_guiderate(Device::AltDecMotor, GuideRate::SIDEREAL)
+        .unwrap();
+    hc.advance(3_600_000);
+    assert_close(hc.axes().0, 10.0 - TrackingRate::SIDEREAL / 2.0, 1e-3);
+    assert_close(hc.axes().1, 20.0 + TrackingRate::SIDEREAL, 1e-3);
+}
+
+#[test]
+fn set_motor_position() {
+    let hc = SimulatedHc::new();
+    hc.set_axes(10.0, 20.0);
+    let mut nexstar = NexStar::new(&hc, &hc);
+
+    for (device, degrees) in [
+        (Device::AzmRaMotor, -120.5),
+        (Device::AltDecMotor, 95.25),
+        (Device::AltDecMotor, 180.0),
+        (Device::AltDecMotor, -180.0),
+    ] {
+        nexstar.set_motor_position(device, degrees).unwrap();
+        let angle = nexstar.motor_angle(device).unwrap();
+        // -180° and 180° are the same position
+        assert_close((angle - degrees + 540.0) % 360.0 - 180.0, 0.0, 1e-4);
+    }
+    assert_close(hc.axes().0, 239.5, 1e-4);
+
+    nexstar
+        .set_motor_position_arcsec(Device::AltDecMotor, -400_000)
+        .unwrap();
+    assert_eq!(
+        nexstar.motor_angle_arcsec(Device::AltDecMotor).unwrap(),
+        -400_000
+    );
+}
+
+#[test]
+fn measure_backlash() {
+    let hc = SimulatedHc::new();
+    hc.set_play(Device::AltDecMotor, 120.0);
+    hc.set_axes(0.0, 10.0);
+    let mut nexstar = NexStar::new(&hc, &hc);
+    let mut delay = &hc;
+
+    let measured = nexstar
+        .measure_backlash(Device::AltDecMotor, 60.0, &mut delay)
+        .unwrap();
+    assert_close(measured.positive, 120.0, 1.0);
+    assert_close(measured.negative, 120.0, 1.0);
+    nexstar
+        .set_backlash(Device::AltDecMotor, measured.setting())
+        .unwrap();
+    assert_eq!(
+        nexstar.backlash(Device::AltDecMotor).unwrap(),
+        Backlash {
+            positive: 12,
+            negative: 12
+        }
+    );
+
+    // the motor stopped
+    let axes = hc.axes();
+    hc.advance(60_000);
+    assert_eq!(hc.axes(), axes);
+
+    // without play the axis moves right away
+    let measured = nexstar
+        .measure_backlash(Device::AzmRaMotor, -100.0, &mut delay)
+        .unwrap();
+    assert_close(measured.positive, 0.0, 1.0);
+    assert_close(measured.negative, 0.0, 1.0);
+}
+
+#[test]
+fn measure_drift() {
+    let hc = SimulatedHc::new();
+    // 30 arcsec/min in RA across 0h and -15 arcsec/min in Dec
+    hc.set_axes(359.99, 45.0);
+    hc.set_drift(0.5, -0.25);
+    let mut nexstar = NexStar::new(&hc, &hc);
+    let mut delay = &hc;
+
+    let drift = nexstar.measure_drift(600_000, 10_000, &mut delay).unwrap();
+    assert_close(drift.ra, 30.0, 0.05);
+    assert_close(drift.dec, -15.0, 0.05);
+    assert_eq!((drift.samples, drift.skipped), (61, 0));
+
+    assert!(matches!(
+        nexstar.measure_drift(999, 1000, &mut delay),
+        Err(Error::TooFewSamples)
+    ));
+}
+
+#[test]
+fn record_pe() {
+    let hc = SimulatedHc::new();
+    // tracking across 0h with a 10 arcsec error of a 480 s worm
+    hc.set_axes(359.9, 0.0);
+    hc.set_drift(TrackingRate::SIDEREAL, 0.0);
+    hc.set_periodic_error(10.0, 480_000);
+    let mut nexstar = NexStar::new(&hc, &hc);
+    let mut delay = &hc;
+
+    let mut residuals = [0.0; 200];
+    let mut recorder = PeRecorder::new(&mut residuals, 480_000, 5000, 2);
+    let stats = nexstar.record_pe(&mut recorder, &mut delay).unwrap();
+    assert_eq!(recorder.residuals().len(), 193);
+    assert_close(stats.peak_to_peak, 20.0, 0.5);
+    assert_close(stats.rms, 10.0 / 2f32.sqrt(), 0.5);
+    // a quarter period in the error is at its peak
+    assert_close(recorder.residuals()[24], 10.0, 0.5);
+
+    let mut short = [0.0; 192];
+    let mut recorder = PeRecorder::new(&mut short, 480_000, 5000, 2);
+    assert!(matches!(
+        nexstar.record_pe(&mut recorder, &mut delay),
+        Err(Error::BufferTooSmall {
+            needed: 193,
+            got: 192
+        })
+    ));
+}
+
+#[test]
+fn motor_model() {
+    let hc = SimulatedHc::new();
+    hc.set_motor_model(Device::AltDecMotor, MotorModel::Gen1);
+    let mut nexstar = NexStar::new(&hc, &hc);
+
+    let azm = nexstar.motor_model(Device::AzmRaMotor).unwrap();
+    let alt = nexstar.motor_model(Device::AltDecMotor).unwrap();
+    assert!(azm.supports_guide_pulses());
+    assert_eq!(alt, MotorModel::Gen1);
+    assert!(!alt.supports_guide_pulses());
+}
+
+#[test]
+fn seek_index_after_polls() {
+    let hc = SimulatedHc::new();
+    hc.set_index_switch(Device::AltDecMotor, Some(3));
+    let mut nexstar = NexStar::new(&hc, &hc);
+
+    nexstar.seek_index(Device::AltDecMotor).unwrap();
+    let polls = (0..10)
+        .take_while(|_| !nexstar.index_found(Device::AltDecMotor).unwrap())
+        .count();
+    assert_eq!(polls, 3);
+
+    nexstar.level_start(Device::AltDecMotor).unwrap();
+    assert!(!nexstar.level_done(Device::AltDecMotor).unwrap());
+}
+
+#[test]
+fn seek_index_without_switch() {
+    let hc = SimulatedHc::new();
+    let mut nexstar = NexStar::new(&hc, &hc);
+
+    assert!(matches!(
+        nexstar.seek_index(Device::AzmRaMotor),
+        Err(Error::UnsupportedCommand(_))
+    ));
+    assert!(matches!(
+        nexstar.index_found(Device::AzmRaMotor),
+        Err(Error::UnsupportedCommand(_))
+    ));
+}
+
+#[test]
+fn find_home() {
+    let hc = SimulatedHc::new();
+    hc.set_index_switch(Device::AzmRaMotor, Some(2));
+    hc.set_index_switch(Device::AltDecMotor, Some(5));
+    let mut nexstar = NexStar::new(&hc, &hc);
+    let mut delay = FakeDelay::default();
+
+    let status = nexstar.find_home(&mut delay, 60_000).unwrap();
+    assert!(status.azm_ra_found && status.alt_dec_found);
+    assert_eq!(delay.total(), 2500);
+}
+
+#[test]
+fn find_home_one_axis_fails() {
+    let hc = SimulatedHc::new();
+    hc.set_index_switch(Device::AzmRaMotor, Some(2));
+    hc.set_index_switch(Device::AltDecMotor, Some(u32::MAX));
+    let mut nexstar = NexStar::new(&hc, &hc);
+    let mut delay = FakeDelay::default();
+
+    match nexstar.find_home(&mut delay, 10_000) {
+        Err(Error::HomeNotFound(status)) => {
+            assert!(status.azm_ra_found);
+            assert!(!status.alt_dec_found);
+        }
+        other => panic!("unexpected result {:?}", other),
+    }
+    assert_eq!(delay.total(), 10_000);
+}
+
+fn survey() -> TargetQueue<4> {
+    let mut queue = TargetQueue::new();
+    let targets = [
+        Target::RaDec(RaDec {
+            ra: 30.0,
+            dec: 20.0,
+        }),
+        Target::AzmAlt(AzmAlt {
+            azm: 200.0,
+            alt: 45.0,
+        }),
+        Target::RaDec(RaDec {
+            ra: 300.0,
+            dec: -10.0,
+        }),
+    ];
+    for target in targets {
+        queue.push(QueueEntry::new(target)).unwrap();
+    }
+    queue
+}
+
+#[test]
+fn run_queue() {
+    let hc = SimulatedHc::new();
+    hc.set_slew_step(10.0);
+    let mut nexstar = NexStar::new(&hc, &hc);
+    let mut delay = FakeDelay::default();
+    let mut queue = survey();
+    queue
+        .push(QueueEntry {
+            target: Target::RaDec(RaDec {
+                ra: 100.0,
+                dec: 40.0,
+            }),
+            approach: Some((Approach::Positive, 60.0)),
+        })
+        .unwrap();
+    assert!(queue
+        .push(QueueEntry::new(Target::RaDec(RaDec { ra: 0.0, dec: 0.0 })))
+        .is_err());
+
+    let mut visited = Vec::new();
+    nexstar
+        .run_queue(&mut queue, &mut delay, |index, _| {
+            // the mount has arrived when the hook runs
+            assert!(!hc.is_goto_in_progress());
+            visited.push((index, hc.axes()));
+            Visit::Continue
+        })
+        .unwrap();
+
+    assert_eq!(visited.len(), 4);
+    assert_eq!(visited[0].0, 0);
+    assert_close(visited[0].1 .0, 30.0, 1e-3);
+    assert_close(visited[1].1 .0, 200.0, 1e-3);
+    assert_close(visited[2].1 .1, -10.0, 1e-3);
+    assert_close(visited[3].1 .0, 100.0, 1e-3);
+    assert!(queue.is_done());
+    assert_eq!(queue.failed(), None);
+
+    // a finished queue has nothing left to do
+    nexstar
+        .run_queue(&mut queue, &mut delay, |_, _| panic!("visited again"))
+        .unwrap();
+}
+
+#[test]
+fn run_queue_failure_resumes() {
+    let hc = SimulatedHc::new();
+    let mut nexstar = NexStar::new(&hc, &hc);
+    let mut delay = FakeDelay::default();
+    let mut queue = TargetQueue::<3>::new();
+    for (azm, alt) in [(10.0, 60.0), (200.0, 30.0), (300.0, 70.0)] {
+        let target = Target::AzmAlt(AzmAlt { azm, alt });
+        queue.push(QueueEntry::new(target)).unwrap();
+    }
+    // the second target is below the floor
+    nexstar.set_goto_floor(Some(50.0));
+
+    let mut visited = Vec::new();
+    assert!(matches!(
+        nexstar.run_queue(&mut queue, &mut delay, |index, _| {
+            visited.push(index);
+            Visit::Continue
+        }),
+        Err(Error::BelowAltitudeLimit { .. })
+    ));
+    assert_eq!(visited, [0]);
+    assert_eq!(queue.failed(), Some(1));
+    assert_eq!(queue.next_index(), 1);
+
+    // running again retries the failed target
+    assert!(nexstar
+        .run_queue(&mut queue, &mut delay, |_, _| Visit::Continue)
+        .is_err());
+    assert_eq!(queue.failed(), Some(1));
+
+    queue.skip();
+    assert_eq!(queue.failed(), None);
+    nexstar
+        .run_queue(&mut queue, &mut delay, |index, _| {
+            visited.push(index);
+            Visit::Continue
+        })
+        .unwrap();
+    assert_eq!(visited, [0, 2]);
+    assert!(queue.is_done());
+}
+
+#[test]
+fn run_queue_abort_from_hook() {
+    let hc = SimulatedHc::new();
+    let mut nexstar = NexStar::new(&hc, &hc);
+    let mut delay = FakeDelay::default();
+    let mut queue = survey();
+
+    let mut visited = Vec::new();
+    assert!(matches!(
+        nexstar.run_queue(&mut queue, &mut delay, |index, entry| {
+            visited.push(index);
+            match entry.target {
+                Target::AzmAlt(_) => Visit::Abort,
+                Target::RaDec(_) => Visit::Continue,
+            }
+        }),
+        Err(Error::Aborted)
+    ));
+    assert_eq!(visited, [0, 1]);
+    // the aborting target was visited, the run continues after it
+    assert_eq!(queue.next_index(), 2);
+    assert_eq!(queue.failed(), None);
+
+    queue.rewind();
+    assert_eq!(queue.next_index(), 0);
+    assert_eq!(queue.len(), 3);
+}
+
+#[test]
+fn positions() {
+    let hc = SimulatedHc::new();
+    hc.set_slew_step(2.0);
+    let mut nexstar = NexStar::new(&hc, &hc);
+    let mut delay = FakeDelay::default();
+    nexstar
+        .goto_ra_dec(RaDec {
+            ra: 20.0,
+            dec: 10.0,
+        })
+        .unwrap();
+
+    let mut samples = Vec::new();
+    let mut positions = nexstar.positions(&mut delay, 1000);
+    for _ in 0..3 {
+        samples.push(positions.next().unwrap().unwrap());
+        // the driver stays usable between the samples
+        assert!(positions.nexstar().is_goto_in_progress().unwrap());
+    }
+
+    // every command moved the mount one step towards the target
+    let ra: Vec<f32> = samples.iter().map(|sample| sample.ra).collect();
+    for (sample, expected) in ra.iter().zip([2.0, 6.0, 10.0]) {
+        assert_close(*sample, expected, 0.001);
+    }
+    assert_close(samples[2].dec, 10.0, 0.01);
+    assert_eq!(delay.delays, [1000, 1000]);
+}
+
+#[test]
+fn log_positions() {
+    let hc = SimulatedHc::new();
+    hc.set_slew_step(1.0);
+    let mut nexstar = NexStar::new(&hc, &hc);
+    let mut delay = FakeDelay::default();
+    nexstar
+        .goto_azm_alt(AzmAlt {
+            azm: 10.0,
+            alt: 5.0,
+        })
+        .unwrap();
+
+    let mut log = Vec::new();
+    let logged = nexstar
+        .log_positions(&mut delay, 250, 4, |ms, position| {
+            let (ra_dec, azm_alt) = position.unwrap();
+            log.push((ms, ra_dec, azm_alt, hc.axes()));
+            true
+        })
+        .unwrap();
+
+    assert_eq!(logged, 4);
+    let timestamps: Vec<u32> = log.iter().map(|sample| sample.0).collect();
+    assert_eq!(timestamps, [0, 250, 500, 750]);
+    assert_eq!(delay.delays, [250, 250, 250]);
+    // both frames were read in step while the mount slewed
+    for (_, _, azm_alt, axes) in &log {
+        assert_close(azm_alt.azm, axes.0, 1e-3);
+        assert_close(azm_alt.alt, axes.1, 1e-3);
+    }
+    assert!(log[3].2.azm > log[0].2.azm);
+
+    // the sink stops the run
+    let logged = nexstar
+        .log_positions(&mut delay, 250, 10, |ms, _| ms < 500)
+        .unwrap();
+    assert_eq!(logged, 3);
+}
+
+fn configured() -> SimulatedHc {
+    let hc = SimulatedHc::new();
+    let mut nexstar = NexStar::new(&hc, &hc);
+    nexstar
+        .set_location(Location {
+            latitude: 47.5,
+            longitude: -8.25,
+        })
+        .unwrap();
+    nexstar
+        .set_datetime(DateTime {
+            hour: 22,
+            minutes: 0,
+            seconds: 0,
+            zone: 1,
+            daylight_saving: true,
+            year: 26,
+            month: 7,
+            day: 1,
+        })
+        .unwrap();
+    nexstar.set_tracking_mode(TrackingMode::EQNorth).unwrap();
+    nexstar.set_autoguide_rate(Device::AzmRaMotor, 75).unwrap();
+    nexstar.set_autoguide_rate(Device::AltDecMotor, 25).unwrap();
+    let backlash = Backlash {
+        positive: 30,
+        negative: 12,
+    };
+    nexstar.set_backlash(Device::AltDecMotor, backlash).unwrap();
+    hc
+}
+
+/// 20:15 UTC on the same day
+const NOW: DateTime = DateTime {
+    hour: 20,
+    minutes: 15,
+    seconds: 0,
+    zone: 0,
+    daylight_saving: false,
+    year: 26,
+    month: 7,
+    day: 1,
+};
+
+#[test]
+fn mount_config_roundtrip() {
+    let hc = configured();
+    let config = NexStar::new(&hc, &hc).snapshot().unwrap();
+
+    let reset = SimulatedHc::new();
+    let mut nexstar = NexStar::new(&reset, &reset);
+    assert!(nexstar.restore(&config, NOW).is_ok());
+
+    let restored = nexstar.snapshot().unwrap();
+    let location = restored.location.unwrap();
+    assert_close(location.latitude, 47.5, 1e-4);
+    assert_close(location.longitude, -8.25, 1e-4);
+    assert_eq!(restored.time_zone, Some((1, true)));
+    assert_eq!(restored.tracking_mode, Some(TrackingMode::EQNorth));
+    assert_eq!(restored.autoguide_rates, [Some(75), Some(25)]);
+    assert_eq!(
+        restored.backlash,
+        [
+            Some(Backlash::default()),
+            Some(Backlash {
+                positive: 30,
+                negative: 12
+            })
+        ]
+    );
+
+    // the same instant in the zone of the snapshot
+    let datetime = reset.datetime();
+    assert_eq!((datetime.hour, datetime.minutes), (22, 15));
+    assert_eq!((datetime.zone, datetime.daylight_saving), (1, true));
+}
+
+#[test]
+fn restore_continues_after_nack() {
+    let hc = configured();
+    let config = NexStar::new(&hc, &hc).snapshot().unwrap();
+
+    // the ALT/DEC motor controller doesn't answer
+    let reset = SimulatedHc::new();
+    reset.set_device(Device::AltDecMotor, None);
+    let mut nexstar = NexStar::new(&reset, &reset);
+    let report = nexstar.restore(&config, NOW);
+
+    assert!(!report.is_ok());
+    assert!(matches!(
+        report.autoguide_rates[1],
+        Err(Error::UnsupportedCommand(_))
+    ));
+    assert!(matches!(
+        report.backlash[1],
+        Err(Error::UnsupportedCommand(_))
+    ));
+    assert!(report.location.is_ok() && report.datetime.is_ok());
+    assert!(report.tracking_mode.is_ok());
+    assert!(report.autoguide_rates[0].is_ok() && report.backlash[0].is_ok());
+
+    // the rest was written, the snapshot skips the missing motor
+    let restored = nexstar.snapshot().unwrap();
+    assert_eq!(reset.tracking_mode(), TrackingMode::EQNorth);
+    assert_eq!(restored.autoguide_rates, [Some(75), None]);
+    assert_eq!(restored.backlash[1], None);
+}
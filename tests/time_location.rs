@@ -0,0 +1,395 @@
+mod common;
+
+#[cfg(feature = "float")]
+use common::assert_close;
+use common::{manual_clock, nexstar, set_now, FakeDelay};
+#[cfg(feature = "float")]
+use nexstar::Location;
+use nexstar::{Context, DateTime, Dms, Error, Hemisphere};
+
+#[cfg(feature = "float")]
+#[test]
+fn location() {
+    let (mut nexstar, script) = nexstar(&[47, 12, 34, 0, 8, 32, 7, 0, b'#']);
+
+    let location = nexstar.location().unwrap();
+    assert_close(location.latitude, 47.2094, 0.0001);
+    assert_close(location.longitude, 8.5353, 0.0001);
+    assert_eq!(script.written(), b"w");
+}
+
+#[cfg(feature = "float")]
+#[test]
+fn location_south_west() {
+    let (mut nexstar, _) = nexstar(&[33, 51, 54, 1, 151, 12, 36, 1, b'#']);
+
+    let location = nexstar.location().unwrap();
+    assert_close(location.latitude, -33.865, 0.0001);
+    assert_close(location.longitude, -151.21, 0.0001);
+}
+
+#[test]
+fn location_dms() {
+    // 0°07'31" is 0.125277... which truncates to 30 seconds when converted
+    // back as f32
+    let (mut nexstar, script) = nexstar(&[0, 7, 31, 1, 151, 12, 36, 0, b'#']);
+
+    let (latitude, longitude) = nexstar.location_dms().unwrap();
+    assert_eq!(
+        latitude,
+        Dms {
+            degrees: 0,
+            minutes: 7,
+            seconds: 31,
+            hemisphere: Hemisphere::South,
+        }
+    );
+    assert_eq!(
+        longitude,
+        Dms {
+            degrees: 151,
+            minutes: 12,
+            seconds: 36,
+            hemisphere: Hemisphere::East,
+        }
+    );
+    assert_eq!(script.written(), b"w");
+}
+
+#[test]
+fn location_dms_with_hash_bytes() {
+    // 35 is the ASCII code of #, the response is read by its length
+    let (mut nexstar, script) = nexstar(&[
+        35, 35, 35, 0, 35, 35, 35, 1, b'#', 0, 0, 0, 0, 0, 0, 0, 0, b'#',
+    ]);
+
+    let (latitude, longitude) = nexstar.location_dms().unwrap();
+    let expected = |hemisphere| Dms {
+        degrees: 35,
+        minutes: 35,
+        seconds: 35,
+        hemisphere,
+    };
+    assert_eq!(latitude, expected(Hemisphere::North));
+    assert_eq!(longitude, expected(Hemisphere::West));
+
+    // the next response starts in sync
+    let (latitude, _) = nexstar.location_dms().unwrap();
+    assert_eq!(latitude.degrees, 0);
+    assert_eq!(script.pending(), 0);
+}
+
+#[test]
+fn location_dms_out_of_range() {
+    let (mut nexstar, _) = nexstar(&[91, 0, 0, 0, 8, 32, 7, 0, b'#']);
+
+    assert!(matches!(
+        nexstar.location_dms(),
+        Err(Error::UnexpectedResponse(Context::Hc(b'w')))
+    ));
+}
+
+#[cfg(feature = "float")]
+#[test]
+fn location_invalid_sign() {
+    let (mut nexstar, _) = nexstar(&[47, 12, 34, 2, 8, 32, 7, 0, b'#']);
+
+    assert!(matches!(
+        nexstar.location(),
+        Err(Error::UnexpectedResponse(Context::Hc(b'w')))
+    ));
+}
+
+#[cfg(feature = "float")]
+#[test]
+fn location_nack() {
+    let (mut nexstar, script) = nexstar(&[47, 12, 34, 0, 8, 32, 7, 0, 0x00, b'#']);
+
+    assert!(matches!(
+        nexstar.location(),
+        Err(Error::UnexpectedResponse(Context::Hc(b'w')))
+    ));
+    assert_eq!(script.pending(), 0);
+}
+
+#[cfg(feature = "float")]
+#[test]
+fn set_location() {
+    let (mut nexstar, script) = nexstar(b"#");
+
+    nexstar
+        .set_location(Location {
+            latitude: 47.209_44,
+            longitude: -8.535_28,
+        })
+        .unwrap();
+    assert_eq!(script.written(), [b'W', 47, 12, 34, 0, 8, 32, 7, 1]);
+}
+
+#[cfg(feature = "float")]
+#[test]
+fn set_location_south_east() {
+    let (mut nexstar, script) = nexstar(b"#");
+
+    nexstar
+        .set_location(Location {
+            latitude: -33.865,
+            longitude: 151.21,
+        })
+        .unwrap();
+    assert_eq!(script.written(), [b'W', 33, 51, 54, 1, 151, 12, 36, 0]);
+}
+
+#[test]
+fn set_location_dms() {
+    let (mut nexstar, script) = nexstar(b"#");
+
+    nexstar
+        .set_location_dms(
+            Dms {
+                degrees: 33,
+                minutes: 51,
+                seconds: 54,
+                hemisphere: Hemisphere::South,
+            },
+            Dms {
+                degrees: 180,
+                minutes: 0,
+                seconds: 0,
+                hemisphere: Hemisphere::West,
+            },
+        )
+        .unwrap();
+    assert_eq!(script.written(), [b'W', 33, 51, 54, 1, 180, 0, 0, 1]);
+}
+
+#[test]
+fn set_location_dms_invalid() {
+    let (mut nexstar, script) = nexstar(b"#");
+    let latitude = Dms {
+        degrees: 47,
+        minutes: 60,
+        seconds: 0,
+        hemisphere: Hemisphere::North,
+    };
+    let longitude = Dms {
+        degrees: 8,
+        minutes: 0,
+        seconds: 0,
+        hemisphere: Hemisphere::East,
+    };
+
+    assert!(matches!(
+        nexstar.set_location_dms(latitude, longitude),
+        Err(Error::InvalidLocation)
+    ));
+    assert!(matches!(
+        nexstar.set_location_dms(longitude, longitude),
+        Err(Error::InvalidLocation)
+    ));
+    assert!(script.written().is_empty());
+}
+
+#[cfg(feature = "float")]
+#[test]
+fn set_location_nack() {
+    let (mut nexstar, _) = nexstar(&[0x00, b'#']);
+
+    let err = nexstar
+        .set_location(Location {
+            latitude: 0.0,
+            longitude: 0.0,
+        })
+        .unwrap_err();
+    assert!(matches!(err, Error::UnexpectedResponse(Context::Hc(b'W'))));
+    assert_eq!(err.to_string(), "unexpected response to 'W'");
+}
+
+#[cfg(feature = "float")]
+#[test]
+fn context_within_sequence() {
+    // a setup sequence whose second step fails
+    let (mut nexstar, script) = nexstar(b"#");
+    let location = Location {
+        latitude: 10.0,
+        longitude: 20.0,
+    };
+
+    nexstar.set_location(location).unwrap();
+    script.respond(&[0x0C, 0x00, b'#']);
+    let err = nexstar.model().unwrap_err();
+    assert!(matches!(err, Error::UnexpectedResponse(Context::Hc(b'm'))));
+    assert_eq!(format!("{:?}", err), "UnexpectedResponse(Hc(109))");
+
+    // and the other way around
+    script.respond(&[0x0C, b'#', 0x00, b'#']);
+    nexstar.model().unwrap();
+    let err = nexstar.set_location(location).unwrap_err();
+    assert!(matches!(err, Error::UnexpectedResponse(Context::Hc(b'W'))));
+    assert_eq!(err.to_string(), "unexpected response to 'W'");
+}
+
+#[test]
+fn datetime() {
+    let (mut nexstar, script) = nexstar(&[22, 30, 15, 10, 14, 26, 0xFE, 1, b'#']);
+
+    let datetime = nexstar.datetime().unwrap();
+    assert_eq!(
+        (datetime.hour, datetime.minutes, datetime.seconds),
+        (22, 30, 15)
+    );
+    assert_eq!((datetime.year, datetime.month, datetime.day), (26, 10, 14));
+    assert_eq!(datetime.zone, -2);
+    assert!(datetime.daylight_saving);
+    assert_eq!(script.written(), b"h");
+}
+
+#[test]
+fn datetime_nack() {
+    let (mut nexstar, _) = nexstar(&[22, 30, 15, 10, 14, 26, 0xFE, 1, 0x00, b'#']);
+
+    assert!(matches!(
+        nexstar.datetime(),
+        Err(Error::UnexpectedResponse(Context::Hc(b'h')))
+    ));
+}
+
+#[test]
+fn set_datetime() {
+    let (mut nexstar, script) = nexstar(b"#");
+
+    nexstar
+        .set_datetime(DateTime {
+            hour: 22,
+            minutes: 30,
+            seconds: 15,
+            zone: -5,
+            daylight_saving: false,
+            year: 26,
+            month: 10,
+            day: 14,
+        })
+        .unwrap();
+    assert_eq!(script.written(), [b'H', 22, 30, 15, 10, 14, 26, 0xFB, 0]);
+}
+
+/// Clock advancing 400 ms on every call
+fn slow_link_clock() -> u32 {
+    thread_local! {
+        static NOW: std::cell::Cell<u32> = const { std::cell::Cell::new(u32::MAX - 100) };
+    }
+    NOW.with(|now| {
+        now.set(now.get().wrapping_add(400));
+        now.get()
+    })
+}
+
+#[test]
+fn set_datetime_compensated() {
+    let (mut nexstar, script) = nexstar(&[0x42, b'#', b'#']);
+
+    // 400 ms round trip of the echo and 900 ms for the command
+    let sent = nexstar
+        .set_datetime_compensated(
+            DateTime {
+                hour: 23,
+                minutes: 59,
+                seconds: 59,
+                zone: -5,
+                daylight_saving: false,
+                year: 26,
+                month: 12,
+                day: 31,
+            },
+            slow_link_clock,
+        )
+        .unwrap();
+    assert_eq!(sent.year, 27);
+    assert_eq!((sent.month, sent.day), (1, 1));
+    assert_eq!((sent.hour, sent.minutes, sent.seconds), (0, 0, 0));
+    assert_eq!(
+        script.written(),
+        [b'K', 0x42, b'H', 0, 0, 0, 1, 1, 27, 0xFB, 0]
+    );
+}
+
+fn new_year() -> DateTime {
+    DateTime {
+        hour: 0,
+        minutes: 0,
+        seconds: 0,
+        zone: 1,
+        daylight_saving: false,
+        year: 27,
+        month: 1,
+        day: 1,
+    }
+}
+
+#[test]
+fn set_datetime_at_boundary() {
+    let (mut nexstar, script) = nexstar(&[0x42, b'#', b'#']);
+    let mut delay = FakeDelay::default();
+    set_now(0);
+
+    // the clock doesn't advance, so the whole wait is spent in the delay
+    let sent = nexstar
+        .set_datetime_at_boundary(new_year(), &mut delay, 250, manual_clock)
+        .unwrap();
+    assert_eq!(sent, new_year());
+    assert_eq!(delay.delays, [250]);
+    assert_eq!(
+        script.written(),
+        [b'K', 0x42, b'H', 0, 0, 0, 1, 1, 27, 1, 0]
+    );
+}
+
+#[test]
+fn set_datetime_at_boundary_less_latency() {
+    let (mut nexstar, _) = nexstar(&[0x42, b'#', b'#']);
+    let mut delay = FakeDelay::default();
+
+    // 400 ms round trip of the echo and 900 ms for the command
+    let sent = nexstar
+        .set_datetime_at_boundary(new_year(), &mut delay, 1500, slow_link_clock)
+        .unwrap();
+    assert_eq!(sent, new_year());
+    assert!((190..=210).contains(&delay.total()), "{:?}", delay.delays);
+}
+
+#[test]
+fn set_datetime_at_boundary_missed() {
+    let (mut nexstar, script) = nexstar(&[0x42, b'#', b'#']);
+    let mut delay = FakeDelay::default();
+
+    // 1300 ms needed but only 100 ms left, the boundary 2 s later is used
+    let sent = nexstar
+        .set_datetime_at_boundary(new_year(), &mut delay, 100, slow_link_clock)
+        .unwrap();
+    assert_eq!(sent, new_year().add_seconds(2));
+    assert_eq!(delay.total(), 800);
+    assert_eq!(
+        script.written(),
+        [b'K', 0x42, b'H', 0, 0, 2, 1, 1, 27, 1, 0]
+    );
+}
+
+#[test]
+fn set_datetime_nack() {
+    let (mut nexstar, _) = nexstar(&[0x00, b'#']);
+
+    let datetime = DateTime {
+        hour: 0,
+        minutes: 0,
+        seconds: 0,
+        zone: 0,
+        daylight_saving: false,
+        year: 0,
+        month: 1,
+        day: 1,
+    };
+    assert!(matches!(
+        nexstar.set_datetime(datetime),
+        Err(Error::UnexpectedResponse(Context::Hc(b'H')))
+    ));
+}
@@ -0,0 +1,319 @@
+#![cfg(feature = "float")]
+
+mod common;
+
+use std::panic::{self, AssertUnwindSafe};
+
+use common::{manual_clock, nexstar, set_now};
+use nexstar::joystick::JoystickMapper;
+use nexstar::{Context, Device, Error, LinkState, TrackingMode, TrackingRate};
+
+fn variable_rate(device: u8, cmd: u8, rate: u16) -> Vec<u8> {
+    let [high, low] = rate.to_be_bytes();
+    vec![b'P', 3, device, cmd, high, low, 0, 0]
+}
+
+#[test]
+fn tracking_mode() {
+    let (mut nexstar, script) = nexstar(&[2, b'#']);
+
+    assert!(matches!(
+        nexstar.tracking_mode().unwrap(),
+        TrackingMode::EQNorth
+    ));
+    assert_eq!(script.written(), b"t");
+}
+
+#[test]
+fn tracking_mode_invalid() {
+    let (mut nexstar, _) = nexstar(&[4, b'#']);
+
+    assert!(matches!(
+        nexstar.tracking_mode(),
+        Err(Error::UnexpectedResponse(Context::Hc(b't')))
+    ));
+}
+
+#[test]
+fn tracking_mode_nack() {
+    let (mut nexstar, _) = nexstar(&[1, 0x00, b'#']);
+
+    assert!(nexstar.tracking_mode().is_err());
+}
+
+#[test]
+fn set_tracking_mode() {
+    let (mut nexstar, script) = nexstar(b"#");
+
+    nexstar.set_tracking_mode(TrackingMode::AltAz).unwrap();
+    assert_eq!(script.written(), b"T\x01");
+}
+
+#[test]
+fn set_tracking_mode_nack() {
+    let (mut nexstar, _) = nexstar(b"\x00#");
+
+    assert!(matches!(
+        nexstar.set_tracking_mode(TrackingMode::Off),
+        Err(Error::UnexpectedResponse(Context::Hc(b'T')))
+    ));
+}
+
+#[test]
+fn named_tracking_rates() {
+    assert_eq!(TrackingRate::Sidereal.arcsec_per_sec(), 15.041_067);
+    assert_eq!(TrackingRate::Lunar.arcsec_per_sec(), 14.685);
+    assert_eq!(TrackingRate::Solar.arcsec_per_sec(), 15.0);
+    assert_eq!(TrackingRate::Custom(-1.5).arcsec_per_sec(), -1.5);
+}
+
+#[test]
+fn set_custom_tracking() {
+    let (mut nexstar, script) = nexstar(b"####");
+
+    nexstar.set_custom_tracking(TrackingRate::Sidereal).unwrap();
+    nexstar.set_custom_tracking(TrackingRate::Lunar).unwrap();
+    nexstar.set_custom_tracking(TrackingRate::Solar).unwrap();
+    nexstar
+        .set_custom_tracking(TrackingRate::Custom(-30.0))
+        .unwrap();
+
+    let mut expected = variable_rate(0x10, 0x06, 60);
+    expected.extend(variable_rate(0x10, 0x06, 59));
+    expected.extend(variable_rate(0x10, 0x06, 60));
+    expected.extend(variable_rate(0x10, 0x07, 120));
+    assert_eq!(script.written(), expected);
+}
+
+#[test]
+fn set_custom_tracking_nack() {
+    let (mut nexstar, _) = nexstar(b"\x00#");
+
+    assert!(matches!(
+        nexstar.set_custom_tracking(TrackingRate::Sidereal),
+        Err(Error::UnexpectedResponse(Context::PassThrough {
+            device: Device::AzmRaMotor,
+            command: 0x06
+        }))
+    ));
+}
+
+#[test]
+fn stop_custom_tracking() {
+    let (mut nexstar, script) = nexstar(b"#");
+
+    nexstar.stop_custom_tracking().unwrap();
+    assert_eq!(script.written(), variable_rate(0x10, 0x06, 0));
+}
+
+#[test]
+fn track_rates() {
+    let (mut nexstar, script) = nexstar(b"##");
+
+    nexstar.track_rates(100, -50).unwrap();
+
+    let mut expected = variable_rate(0x10, 0x06, 400);
+    expected.extend(variable_rate(0x11, 0x07, 200));
+    assert_eq!(script.written(), expected);
+}
+
+#[test]
+fn track_rates_saturate() {
+    let (mut nexstar, script) = nexstar(b"##");
+
+    nexstar.track_rates(i32::MAX, i32::MIN).unwrap();
+
+    let mut expected = variable_rate(0x10, 0x06, u16::MAX);
+    expected.extend(variable_rate(0x11, 0x07, u16::MAX));
+    assert_eq!(script.written(), expected);
+}
+
+#[test]
+fn track_rates_interleaved() {
+    let (mut nexstar, script) = nexstar(b"##");
+
+    for rate in 1..=5 {
+        nexstar.track_rates(rate, -rate).unwrap();
+        script.respond(b"##");
+    }
+    nexstar.stop_tracking_rates().unwrap();
+
+    assert_eq!(script.pending(), 0);
+    assert_eq!(script.written().len(), 6 * 2 * 8);
+}
+
+#[test]
+fn track_rates_nack_on_second_axis() {
+    let (mut nexstar, script) = nexstar(b"#\x00#");
+
+    assert!(matches!(
+        nexstar.track_rates(10, 10),
+        Err(Error::UnexpectedResponse(Context::PassThrough {
+            device: Device::AltDecMotor,
+            ..
+        }))
+    ));
+    script.written();
+
+    script.respond(b"##");
+    nexstar.stop_tracking_rates().unwrap();
+    assert_eq!(script.pending(), 0);
+}
+
+#[test]
+fn slew_guard_stops_when_dropped() {
+    let (mut nexstar, script) = nexstar(b"######");
+
+    {
+        let mut guard = nexstar.begin_slew(100, -50).unwrap();
+        guard.set_rates(25, 0).unwrap();
+    }
+
+    let mut expected = variable_rate(0x10, 0x06, 400);
+    expected.extend(variable_rate(0x11, 0x07, 200));
+    expected.extend(variable_rate(0x10, 0x06, 100));
+    expected.extend(variable_rate(0x11, 0x06, 0));
+    expected.extend(variable_rate(0x10, 0x06, 0));
+    expected.extend(variable_rate(0x11, 0x06, 0));
+    assert_eq!(script.written(), expected);
+    assert_eq!(nexstar.link_state(), LinkState::Clean);
+}
+
+#[test]
+fn slew_guard_stops_on_panic() {
+    let (mut nexstar, script) = nexstar(b"####");
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let _guard = nexstar.begin_slew(10, 10).unwrap();
+        panic!("application bug");
+    }));
+    assert!(result.is_err());
+
+    let written = script.written();
+    assert_eq!(written.len(), 4 * 8);
+    let mut stop = variable_rate(0x10, 0x06, 0);
+    stop.extend(variable_rate(0x11, 0x06, 0));
+    assert_eq!(written[16..], stop[..]);
+}
+
+#[test]
+fn slew_guard_failed_stop() {
+    let (mut nexstar, script) = nexstar(b"##\x00#");
+
+    // finish returns the error and doesn't stop again
+    let guard = nexstar.begin_slew(10, 10).unwrap();
+    assert!(matches!(
+        guard.finish(),
+        Err(Error::UnexpectedResponse(Context::PassThrough {
+            device: Device::AzmRaMotor,
+            ..
+        }))
+    ));
+    assert_eq!(script.written().len(), 3 * 8);
+
+    // dropping only records it
+    script.respond(b"##\x00#");
+    drop(nexstar.begin_slew(10, 10).unwrap());
+    assert_eq!(nexstar.link_state(), LinkState::Dirty);
+}
+
+#[test]
+fn slew_watchdog_refreshed() {
+    let (mut nexstar, script) = nexstar(b"####");
+    set_now(1000);
+    nexstar.set_slew_watchdog(manual_clock, 500);
+
+    nexstar.track_rates(100, 0).unwrap();
+    set_now(1500);
+    nexstar.track_rates(50, 0).unwrap();
+    set_now(1900);
+    nexstar.check_slew_watchdog().unwrap();
+
+    let mut expected = variable_rate(0x10, 0x06, 400);
+    expected.extend(variable_rate(0x11, 0x06, 0));
+    expected.extend(variable_rate(0x10, 0x06, 200));
+    expected.extend(variable_rate(0x11, 0x06, 0));
+    assert_eq!(script.written(), expected);
+}
+
+#[test]
+fn slew_watchdog_fires() {
+    let (mut nexstar, script) = nexstar(b"####");
+    set_now(u32::MAX - 100);
+    nexstar.set_slew_watchdog(manual_clock, 500);
+
+    nexstar.track_rates(-100, 100).unwrap();
+    script.written();
+    // the clock wraps in between
+    set_now(400);
+    assert!(matches!(nexstar.tracking_mode(), Err(Error::WatchdogFired)));
+
+    // only the stop frames were sent, the tracking mode query wasn't
+    let mut expected = variable_rate(0x10, 0x06, 0);
+    expected.extend(variable_rate(0x11, 0x06, 0));
+    assert_eq!(script.written(), expected);
+    assert_eq!(script.pending(), 0);
+    // the watchdog fires once
+    nexstar.check_slew_watchdog().unwrap();
+}
+
+#[test]
+fn slew_watchdog_stopped() {
+    let (mut nexstar, script) = nexstar(b"####");
+    set_now(0);
+    nexstar.set_slew_watchdog(manual_clock, 500);
+
+    nexstar.track_rates(100, 100).unwrap();
+    nexstar.stop_tracking_rates().unwrap();
+    set_now(10_000);
+    nexstar.check_slew_watchdog().unwrap();
+    assert_eq!(script.written().len(), 4 * 8);
+}
+
+#[test]
+fn slew_joystick_suppresses_unchanged_rates() {
+    let (mut nexstar, script) = nexstar(b"####");
+    let mapper = JoystickMapper::new(6400).with_deadzone(0.0).with_step(100);
+
+    assert!(nexstar.slew_joystick(1.0, -0.5, &mapper).unwrap());
+    // rounded to the same rates
+    assert!(!nexstar.slew_joystick(1.0, -0.501, &mapper).unwrap());
+    assert!(!nexstar.slew_joystick(1.5, -0.5, &mapper).unwrap());
+    assert!(nexstar.slew_joystick(0.0, -0.5, &mapper).unwrap());
+
+    let mut expected = variable_rate(0x10, 0x06, 25_600);
+    expected.extend(variable_rate(0x11, 0x07, 6400));
+    expected.extend(variable_rate(0x10, 0x06, 0));
+    expected.extend(variable_rate(0x11, 0x07, 6400));
+    assert_eq!(script.written(), expected);
+}
+
+#[test]
+fn slew_joystick_after_other_rates() {
+    let (mut nexstar, script) = nexstar(b"#\x00#");
+    let mapper = JoystickMapper::new(6400);
+
+    // the second axis failed, its rate is unknown
+    assert!(nexstar.slew_joystick(1.0, 1.0, &mapper).is_err());
+    script.written();
+    script.respond(b"####");
+    assert!(nexstar.slew_joystick(1.0, 1.0, &mapper).unwrap());
+
+    nexstar.stop_tracking_rates().unwrap();
+    assert!(!nexstar.slew_joystick(0.0, 0.0, &mapper).unwrap());
+}
+
+#[test]
+fn slew_joystick_feeds_watchdog() {
+    let (mut nexstar, script) = nexstar(b"####");
+    let mapper = JoystickMapper::new(6400);
+    set_now(0);
+    nexstar.set_slew_watchdog(manual_clock, 1000);
+
+    assert!(nexstar.slew_joystick(1.0, 0.0, &mapper).unwrap());
+    set_now(499);
+    assert!(!nexstar.slew_joystick(1.0, 0.0, &mapper).unwrap());
+    set_now(500);
+    assert!(nexstar.slew_joystick(1.0, 0.0, &mapper).unwrap());
+    assert_eq!(script.written().len(), 4 * 8);
+}
@@ -0,0 +1,246 @@
+#![cfg(feature = "transcript")]
+
+mod common;
+
+use common::{assert_close, transport};
+use nexstar::transcript::{RecordingTransport, ReplayError, ReplayTransport, TranscriptError};
+use nexstar::{
+    Compat, Context, Error, Family, LinkState, Location, Model, NexStar, RaDecArcsec, TrackingMode,
+    Version,
+};
+
+// The fixtures are synthetic, written from the protocol description instead
+// of recorded from a mount. The replays check the driver against that
+// description, not against hardware.
+const SYNTHETIC_CGE: &[u8] = include_bytes!("fixtures/synthetic-cge-connect-datetime.txt");
+const SYNTHETIC_SE_LOCATION: &[u8] = include_bytes!("fixtures/synthetic-se-location.txt");
+const SYNTHETIC_GT_LEGACY: &[u8] = include_bytes!("fixtures/synthetic-gt-legacy.txt");
+const SYNTHETIC_STARSENSE: &[u8] = include_bytes!("fixtures/synthetic-starsense-hc-1.2.txt");
+#[cfg(feature = "synscan")]
+const SYNTHETIC_EQ6R: &[u8] = include_bytes!("fixtures/synthetic-eq6r-connect.txt");
+
+#[test]
+fn record() {
+    let (rx, tx, script) = transport();
+    script.respond(&[4, 21, b'#']);
+    let mut buffer = [0u8; 64];
+    let recording = RecordingTransport::new(rx, tx, &mut buffer);
+    let mut nexstar = NexStar::new(&recording, &recording);
+
+    nexstar.version().unwrap();
+    script.respond(b"#");
+    nexstar.cancel_goto().unwrap();
+
+    assert_eq!(&*recording.transcript(), b"> 56\n< 04 15 23\n> 4D\n< 23");
+    assert!(!recording.is_full());
+}
+
+#[test]
+fn record_into_full_buffer() {
+    let (rx, tx, script) = transport();
+    script.respond(&[4, 21, b'#']);
+    let mut buffer = [0u8; 12];
+    let recording = RecordingTransport::new(rx, tx, &mut buffer);
+    let mut nexstar = NexStar::new(&recording, &recording);
+
+    nexstar.version().unwrap();
+    assert!(recording.is_full());
+    assert_eq!(&*recording.transcript(), b"> 56\n< 04 15");
+    assert!(ReplayTransport::new(&recording.transcript()).is_ok());
+}
+
+#[test]
+fn replay_recording() {
+    let (rx, tx, script) = transport();
+    script.respond(&[0x0C, b'#']);
+    let mut buffer = [0u8; 64];
+    let recording = RecordingTransport::new(rx, tx, &mut buffer);
+    NexStar::new(&recording, &recording).model().unwrap();
+
+    let transcript = recording.transcript();
+    let replay = ReplayTransport::new(&transcript).unwrap();
+    let mut nexstar = NexStar::new(&replay, &replay);
+    assert!(matches!(nexstar.model().unwrap(), Model::Se6_8));
+    assert!(replay.is_finished());
+}
+
+#[test]
+fn replay_synthetic_cge() {
+    let replay = ReplayTransport::new(SYNTHETIC_CGE).unwrap();
+    let mut nexstar = NexStar::new(&replay, &replay);
+
+    let info = nexstar.connect().unwrap();
+    assert!(matches!(info.model, Model::CGE));
+    assert_eq!(info.hc_version, Version::new(4, 15));
+    assert_eq!(info.alt_dec_version, Some(Version::new(5, 7)));
+
+    let datetime = nexstar.datetime().unwrap();
+    assert_eq!(
+        (datetime.hour, datetime.minutes, datetime.seconds),
+        (20, 30, 0)
+    );
+    assert_eq!((datetime.year, datetime.month, datetime.day), (26, 10, 14));
+    assert_eq!(datetime.zone, -5);
+    assert!(datetime.daylight_saving);
+    assert!(replay.is_finished());
+}
+
+#[test]
+fn replay_synthetic_se_location() {
+    let replay = ReplayTransport::new(SYNTHETIC_SE_LOCATION).unwrap();
+    let mut nexstar = NexStar::new(&replay, &replay);
+
+    nexstar
+        .set_location(Location {
+            latitude: -(33.0 + 51.0 / 60.0 + 36.0 / 3600.0),
+            longitude: 151.0 + 12.0 / 60.0 + 40.0 / 3600.0,
+        })
+        .unwrap();
+    let location = nexstar.location().unwrap();
+    assert_close(location.latitude, -33.86, 1e-4);
+    assert_close(location.longitude, 151.2111, 1e-4);
+    assert!(replay.is_finished());
+}
+
+#[test]
+fn replay_synthetic_legacy_gt() {
+    let replay = ReplayTransport::new(SYNTHETIC_GT_LEGACY).unwrap();
+    let mut nexstar = NexStar::new(&replay, &replay);
+
+    let info = nexstar.connect().unwrap();
+    assert_eq!(info.hc_version, Version::new(1, 0));
+    assert!(matches!(info.model, Model::Unknown(0)));
+    assert_eq!(info.azm_ra_version, Some(Version::new(1, 6)));
+    assert_eq!(
+        nexstar.compat(),
+        Compat {
+            family: Family::NexStar,
+            echo: false,
+            model_query: false,
+            wide_positions: true,
+        }
+    );
+
+    let position = nexstar.ra_dec_arcsec().unwrap();
+    assert_eq!((position.ra, position.dec), (324_000, -162_000));
+    let position = nexstar.azm_alt_arcsec().unwrap();
+    assert_eq!((position.azm, position.alt), (648_000, 108_000));
+
+    // refused without sending anything
+    let err = nexstar.model().unwrap_err();
+    assert_eq!(err.to_string(), "firmware 1.0 found, 2.2 required");
+    assert!(matches!(
+        nexstar.keepalive(),
+        Err(Error::UnsupportedFirmware { .. })
+    ));
+    assert!(replay.is_finished());
+}
+
+#[test]
+fn replay_synthetic_starsense() {
+    let replay = ReplayTransport::new(SYNTHETIC_STARSENSE).unwrap();
+    let mut nexstar = NexStar::new(&replay, &replay);
+    nexstar.set_starsense_detection(Some(|| 0));
+
+    let info = nexstar.connect().unwrap();
+    assert_eq!(info.hc_version, Version::new(1, 2));
+    assert_eq!(info.model, Model::CPC);
+    assert_eq!(info.alt_dec_version, Some(Version::new(7, 11)));
+    assert_eq!(nexstar.compat(), Compat::STARSENSE);
+
+    assert!(!nexstar.is_alignment_complete().unwrap());
+    let target = RaDecArcsec {
+        ra: 324_000,
+        dec: -324_000,
+    };
+    let err = nexstar.goto_ra_dec_arcsec(target).unwrap_err();
+    assert!(matches!(err, Error::Aligning(Context::Hc(b'R'))));
+    assert_eq!(err.to_string(), "'R' refused, busy aligning");
+    assert_eq!(nexstar.link_state(), LinkState::Clean);
+
+    assert!(nexstar.is_alignment_complete().unwrap());
+    nexstar.goto_ra_dec_arcsec(target).unwrap();
+    assert!(replay.is_finished());
+}
+
+#[cfg(feature = "synscan")]
+#[test]
+fn replay_synthetic_synscan_eq6r() {
+    let replay = ReplayTransport::new(SYNTHETIC_EQ6R).unwrap();
+    let mut nexstar = NexStar::new(&replay, &replay);
+
+    let info = nexstar.connect().unwrap();
+    assert_eq!(info.hc_version, Version::new(4, 39));
+    assert_eq!(info.model, Model::Eq6);
+    assert!(info.model.capabilities().is_gem);
+    assert_eq!((info.azm_ra_version, info.alt_dec_version), (None, None));
+    assert_eq!(nexstar.compat(), Compat::SYNSCAN);
+    assert_eq!(nexstar.compat().family, Family::SynScan);
+
+    // EQ and PEC both read as the single equatorial mode
+    assert_eq!(nexstar.tracking_mode().unwrap(), TrackingMode::EQNorth);
+    assert_eq!(nexstar.tracking_mode().unwrap(), TrackingMode::EQNorth);
+    nexstar.set_tracking_mode(TrackingMode::EQSouth).unwrap();
+
+    let position = nexstar.ra_dec_arcsec().unwrap();
+    assert_eq!((position.ra, position.dec), (324_000, -324_000));
+
+    nexstar.set_force_refresh(true);
+    assert_eq!(nexstar.version().unwrap(), Version::new(4, 39));
+    assert!(replay.is_finished());
+}
+
+#[test]
+fn replay_mismatch() {
+    let replay = ReplayTransport::new(b"> 56\n< 04 15 23").unwrap();
+    let mut nexstar = NexStar::new(&replay, &replay);
+
+    assert!(matches!(
+        nexstar.model(),
+        Err(Error::Write(ReplayError::Mismatch {
+            offset: 2,
+            expected: 0x56,
+            found: b'm'
+        }))
+    ));
+}
+
+#[test]
+fn replay_unexpected_write() {
+    let replay = ReplayTransport::new(b"> 56\n< 04 15 23").unwrap();
+    let mut nexstar = NexStar::new(&replay, &replay);
+    nexstar.set_force_refresh(true);
+
+    nexstar.version().unwrap();
+    assert!(matches!(
+        nexstar.version(),
+        Err(Error::Write(ReplayError::UnexpectedWrite {
+            found: b'V',
+            ..
+        }))
+    ));
+}
+
+#[test]
+fn replay_missing_response() {
+    let replay = ReplayTransport::new(b"> 56\n< 04 15").unwrap();
+    let mut nexstar = NexStar::new(&replay, &replay);
+
+    assert!(matches!(
+        nexstar.version(),
+        Err(Error::Read(ReplayError::UnexpectedRead { .. }))
+    ));
+}
+
+#[test]
+fn invalid_transcript() {
+    assert_eq!(
+        ReplayTransport::new(b"> 56\n< 04 1G 23").err(),
+        Some(TranscriptError { offset: 10 })
+    );
+    assert_eq!(
+        ReplayTransport::new(b"> 5").err(),
+        Some(TranscriptError { offset: 2 })
+    );
+    assert!(ReplayTransport::new(b"# only a comment").is_ok());
+}